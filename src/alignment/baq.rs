@@ -0,0 +1,354 @@
+//! Base alignment quality (BAQ, Li 2011), i.e. the probability that a read base is aligned to
+//! the wrong reference position. BAQ is computed with a profile pair HMM that aligns the read
+//! to a reference window around its original alignment. Base qualities are capped at their BAQ,
+//! which suppresses false SNPs caused by misalignments near indels in pileup-based calling.
+//!
+//! Qualities are given as integer PHRED scores (not ASCII encoded). The reference window should
+//! cover the aligned part of the read, extended by a few bases (e.g. 10) on both sides.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::baq::Baq;
+//!
+//! let reference = b"ACGTACGATTACAGGCTAGCTAGGCA";
+//! let read = b"ATTACAGGCT";
+//! let qual = [30; 10];
+//! // the read is aligned to reference positions 7 to 16
+//! let positions: Vec<_> = (7..17).map(Some).collect();
+//! let capped = Baq::default().apply(reference, read, &qual, &positions);
+//! assert_eq!(capped, qual);
+//!
+//! // a misaligned read, shifted by one position, gets zero qualities
+//! let positions: Vec<_> = (8..18).map(Some).collect();
+//! let capped = Baq::default().apply(reference, read, &qual, &positions);
+//! assert!(capped.iter().all(|&q| q == 0));
+//! ```
+
+use alignment::{Alignment, AlignmentMode, AlignmentOperation};
+use stats::phred::{prob_to_qual, qual_to_prob};
+
+/// Emission probability of insertions and of unknown bases.
+const EMISSION_UNKNOWN: f64 = 0.25;
+
+/// The most probable alignment of a read base.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum State {
+    /// Aligned to the given reference position.
+    Match(usize),
+    /// Inserted relative to the reference.
+    Insertion,
+}
+
+/// The most probable state of a read base, with its posterior probability.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BaseAlignment {
+    pub state: State,
+    pub prob: f64,
+}
+
+/// Forward or backward values of match, insertion and deletion states.
+#[derive(Clone, Copy, Default)]
+struct Cell {
+    m: f64,
+    i: f64,
+    d: f64,
+}
+
+/// BAQ computation with the given gap probabilities.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Baq {
+    /// Probability to open a gap.
+    pub gap_open: f64,
+    /// Probability to extend a gap.
+    pub gap_extend: f64,
+}
+
+impl Default for Baq {
+    /// Default parameters of samtools (gap open 0.001, gap extension 0.1).
+    fn default() -> Self {
+        Baq::new(0.001, 0.1)
+    }
+}
+
+impl Baq {
+    pub fn new(gap_open: f64, gap_extend: f64) -> Self {
+        assert!(
+            gap_open > 0.0 && gap_open < 0.5 && gap_extend > 0.0 && gap_extend < 1.0,
+            "Expecting gap open probability in (0, 0.5) and gap extension probability in (0, 1)."
+        );
+        Baq {
+            gap_open,
+            gap_extend,
+        }
+    }
+
+    /// Posterior probabilities of all states, by read position (rows, starting with row 1) and
+    /// reference position (columns, starting with column 1).
+    fn posteriors(&self, reference: &[u8], read: &[u8], qual: &[u8]) -> Vec<Vec<Cell>> {
+        let (n, m) = (read.len(), reference.len());
+        let (d, e) = (self.gap_open, self.gap_extend);
+        // probabilities to start and to end the alignment
+        let end = 1.0 / (2 * n + 2) as f64;
+        let (start_m, start_i) = ((1.0 - d) / m as f64, d / m as f64);
+        // transitions from match, insertion and deletion states
+        let mm = (1.0 - 2.0 * d) * (1.0 - end);
+        let (mi, md) = (d * (1.0 - end), d * (1.0 - end));
+        let (im, ii) = ((1.0 - e) * (1.0 - end), e * (1.0 - end));
+        let (dm, dd) = (1.0 - e, e);
+
+        let emission = |i: usize, k: usize| {
+            let (a, b) = (
+                read[i - 1].to_ascii_uppercase(),
+                reference[k - 1].to_ascii_uppercase(),
+            );
+            let err = qual_to_prob(qual[i - 1]);
+            if a == b'N' || b == b'N' {
+                EMISSION_UNKNOWN
+            } else if a == b {
+                1.0 - err
+            } else {
+                err / 3.0
+            }
+        };
+
+        // forward, with each row scaled to sum 1
+        let mut forward = vec![vec![Cell::default(); m + 2]; n + 1];
+        let mut scale = vec![0.0; n + 2];
+        for i in 1..=n {
+            for k in 1..=m {
+                let (prev, diag) = (forward[i - 1][k], forward[i - 1][k - 1]);
+                let left = forward[i][k - 1];
+                let cell = if i == 1 {
+                    Cell {
+                        m: emission(i, k) * start_m,
+                        i: EMISSION_UNKNOWN * start_i,
+                        d: md * left.m + dd * left.d,
+                    }
+                } else {
+                    Cell {
+                        m: emission(i, k) * (mm * diag.m + im * diag.i + dm * diag.d),
+                        i: EMISSION_UNKNOWN * (mi * prev.m + ii * prev.i),
+                        d: md * left.m + dd * left.d,
+                    }
+                };
+                forward[i][k] = cell;
+            }
+            scale[i] = forward[i].iter().map(|c| c.m + c.i + c.d).sum();
+            for cell in &mut forward[i] {
+                cell.m /= scale[i];
+                cell.i /= scale[i];
+                cell.d /= scale[i];
+            }
+        }
+        scale[n + 1] = forward[n].iter().map(|c| (c.m + c.i) * end).sum();
+
+        // backward, scaled consistently with the forward values
+        let mut backward = vec![vec![Cell::default(); m + 2]; n + 1];
+        for cell in &mut backward[n][1..=m] {
+            cell.m = end / scale[n + 1];
+            cell.i = end / scale[n + 1];
+        }
+        for i in (1..n).rev() {
+            for k in (1..=m).rev() {
+                let next_m = if k < m {
+                    emission(i + 1, k + 1) * backward[i + 1][k + 1].m / scale[i + 1]
+                } else {
+                    0.0
+                };
+                let next_i = EMISSION_UNKNOWN * backward[i + 1][k].i / scale[i + 1];
+                let next_d = backward[i][k + 1].d;
+                backward[i][k] = Cell {
+                    m: mm * next_m + mi * next_i + md * next_d,
+                    i: im * next_m + ii * next_i,
+                    d: dm * next_m + dd * next_d,
+                };
+            }
+        }
+
+        for (f, b) in forward.iter_mut().zip(&backward) {
+            for (f, b) in f.iter_mut().zip(b) {
+                f.m *= b.m;
+                f.i *= b.i;
+                f.d *= b.d;
+            }
+        }
+        forward
+    }
+
+    /// Align the read to the reference window and return the most probable state of each read
+    /// base. Reference positions are 0-based positions in the window.
+    pub fn align(&self, reference: &[u8], read: &[u8], qual: &[u8]) -> Vec<BaseAlignment> {
+        assert_eq!(
+            read.len(),
+            qual.len(),
+            "Expecting one quality per read base."
+        );
+        if read.is_empty() || reference.is_empty() {
+            return read
+                .iter()
+                .map(|_| BaseAlignment {
+                    state: State::Insertion,
+                    prob: 1.0,
+                })
+                .collect();
+        }
+        let posteriors = self.posteriors(reference, read, qual);
+        posteriors[1..]
+            .iter()
+            .map(|row| {
+                let mut best = BaseAlignment {
+                    state: State::Insertion,
+                    prob: 0.0,
+                };
+                for (k, cell) in row.iter().enumerate().take(reference.len() + 1).skip(1) {
+                    if cell.m > best.prob {
+                        best = BaseAlignment {
+                            state: State::Match(k - 1),
+                            prob: cell.m,
+                        };
+                    }
+                    if cell.i > best.prob {
+                        best = BaseAlignment {
+                            state: State::Insertion,
+                            prob: cell.i,
+                        };
+                    }
+                }
+                best.prob = best.prob.min(1.0);
+                best
+            })
+            .collect()
+    }
+
+    /// Compute the BAQ of each read base, given the reference positions (0-based in the window)
+    /// of the original alignment, `None` for inserted or clipped bases. The BAQ of a base is
+    /// zero if its most probable alignment differs from the original alignment. Returns the
+    /// qualities capped at the BAQ. Qualities of inserted or clipped bases are left unchanged.
+    pub fn apply(
+        &self,
+        reference: &[u8],
+        read: &[u8],
+        qual: &[u8],
+        positions: &[Option<usize>],
+    ) -> Vec<u8> {
+        assert_eq!(
+            read.len(),
+            positions.len(),
+            "Expecting one reference position per read base."
+        );
+        self.align(reference, read, qual)
+            .iter()
+            .zip(qual)
+            .zip(positions)
+            .map(|((aln, &q), &pos)| match pos {
+                None => q,
+                Some(pos) if aln.state == State::Match(pos) => q.min(prob_to_qual(1.0 - aln.prob)),
+                Some(_) => 0,
+            })
+            .collect()
+    }
+}
+
+/// Reference positions of the read bases in the given alignment of a read (x) against a
+/// reference (y), `None` for inserted or clipped bases.
+pub fn ref_positions(alignment: &Alignment) -> Vec<Option<usize>> {
+    let mut positions = vec![None; alignment.xlen];
+    let (mut x, mut y) = match alignment.mode {
+        AlignmentMode::Custom => (0, 0),
+        _ => (alignment.xstart, alignment.ystart),
+    };
+    for op in &alignment.operations {
+        match *op {
+            AlignmentOperation::Match | AlignmentOperation::Subst => {
+                positions[x] = Some(y);
+                x += 1;
+                y += 1;
+            }
+            AlignmentOperation::Del => y += 1,
+            AlignmentOperation::Ins => x += 1,
+            AlignmentOperation::Xclip(len) => x += len,
+            AlignmentOperation::Yclip(len) => y += len,
+        }
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alignment::pairwise::Aligner;
+
+    #[test]
+    fn test_posteriors_sum_to_one() {
+        let baq = Baq::default();
+        let reference = b"ACGTTGCAAGGCTTAACGTAGC";
+        let read = b"GCAAGGTTAACG";
+        let posteriors = baq.posteriors(reference, read, &[20; 12]);
+        for row in &posteriors[1..] {
+            let sum: f64 = row.iter().map(|c| c.m + c.i).sum();
+            assert_relative_eq!(sum, 1.0, epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_align() {
+        let reference = b"TTTTGCATCGATCGGTTT";
+        let read = b"GCATCGATCGG";
+        let aln = Baq::default().align(reference, read, &[30; 11]);
+        for (i, base) in aln.iter().enumerate() {
+            assert_eq!(base.state, State::Match(i + 4));
+            assert!(base.prob > 0.99);
+        }
+        assert!(Baq::default().align(b"", b"AC", &[30, 30])[0].state == State::Insertion);
+    }
+
+    #[test]
+    fn test_indel() {
+        // the read has a deletion of one A within the homopolymer
+        let reference = b"CCGGTCAGTAAAAAGCTTGCATGC";
+        let read = b"GTCAGTAAAAGCTTG";
+        let qual = [30; 15];
+        let scoring = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+        let mut aligner = Aligner::new(-5, -1, &scoring);
+        let alignment = aligner.semiglobal(read, reference);
+        let positions = ref_positions(&alignment);
+        assert_eq!(positions[0], Some(3));
+        let capped = Baq::default().apply(reference, read, &qual, &positions);
+        assert_eq!(capped[1..6], qual[1..6]);
+        assert_eq!(capped[10..], qual[10..]);
+        // positions within the homopolymer are uncertain
+        assert!(capped[6..10].iter().all(|&q| q < 10));
+
+        // an ungapped alignment that explains the end by mismatches gets low qualities there
+        let ungapped: Vec<_> = (3..18).map(Some).collect();
+        let capped = Baq::default().apply(reference, read, &qual, &ungapped);
+        assert_eq!(capped[1..6], qual[1..6]);
+        assert!(capped[10..].iter().all(|&q| q == 0));
+    }
+
+    #[test]
+    fn test_ref_positions() {
+        let alignment = Alignment {
+            score: 0,
+            xstart: 1,
+            ystart: 2,
+            xend: 5,
+            yend: 7,
+            xlen: 6,
+            ylen: 8,
+            operations: vec![
+                AlignmentOperation::Match,
+                AlignmentOperation::Ins,
+                AlignmentOperation::Subst,
+                AlignmentOperation::Del,
+                AlignmentOperation::Match,
+            ],
+            mode: AlignmentMode::Semiglobal,
+        };
+        assert_eq!(
+            ref_positions(&alignment),
+            [None, Some(2), None, Some(3), Some(5), None]
+        );
+    }
+}
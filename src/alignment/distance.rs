@@ -5,7 +5,7 @@
 
 //! Various subroutines for computing a distance between sequences.
 
-use std::cmp::min;
+use core::cmp::min;
 
 use utils::TextSlice;
 
@@ -0,0 +1,332 @@
+//! Alignment of DNA to protein sequences allowing frameshifts, in the manner of FASTX (Pearson
+//! et al., 1997) and GeneWise.
+//!
+//! Codons of the DNA are translated with the standard genetic code and aligned to the residues
+//! of the protein with a substitution matrix. Besides affine gaps (whole codons missing in the
+//! protein, or residues missing in the DNA), one or two extra bases in the DNA can be skipped at
+//! the cost of a frameshift penalty. This recovers the protein coding region of pseudogenes, or
+//! of error-prone long reads with indels, in one alignment.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::frameshift::{FrameshiftAligner, FrameshiftOperation};
+//! use bio::scores::blosum62;
+//!
+//! let protein = b"MKVLAAGIVG";
+//! // codons for MKVLAAGIVG, with an extra base after the third codon
+//! let dna = b"ATGAAAGTGACTGGCGGCGGGCATTGTGGGC";
+//!
+//! let mut aligner = FrameshiftAligner::new(-11, -1, -15, blosum62);
+//! let alignment = aligner.global(dna, protein);
+//! assert_eq!(alignment.frameshifts, 1);
+//! assert_eq!(alignment.stop_codons, 0);
+//! assert_eq!(alignment.operations[3], FrameshiftOperation::Frameshift(1));
+//! assert_eq!(alignment.score, 46 - 15);
+//! ```
+
+use alignment::pairwise::MatchFunc;
+use seq_analysis::codon::translate_codon;
+
+/// Score of impossible states, low enough to avoid overflows when adding penalties.
+const MIN_SCORE: i32 = i32::MIN / 2;
+
+/// An operation of a DNA to protein alignment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FrameshiftOperation {
+    /// A codon aligned to a residue (match or mismatch).
+    Codon,
+    /// A codon without residue in the protein.
+    CodonInsertion,
+    /// A residue without codon in the DNA.
+    Deletion,
+    /// One or two bases of the DNA skipped by a frameshift.
+    Frameshift(u8),
+}
+
+/// A DNA to protein alignment.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameshiftAlignment {
+    pub score: i32,
+    /// Aligned range of the DNA (0-based, end exclusive).
+    pub dna_start: usize,
+    pub dna_end: usize,
+    /// Aligned range of the protein.
+    pub protein_start: usize,
+    pub protein_end: usize,
+    pub operations: Vec<FrameshiftOperation>,
+    /// Number of frameshifts.
+    pub frameshifts: usize,
+    /// Number of stop codons aligned to residues or inserted.
+    pub stop_codons: usize,
+}
+
+/// Source of the best score of a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Source {
+    Start,
+    Codon,
+    CodonInsertion,
+    Deletion,
+    Frameshift(u8),
+}
+
+/// Aligner of DNA to protein sequences with affine gaps and frameshifts.
+#[derive(Debug)]
+pub struct FrameshiftAligner<F: MatchFunc> {
+    gap_open: i32,
+    gap_extend: i32,
+    frameshift: i32,
+    match_fn: F,
+}
+
+impl<F: MatchFunc> FrameshiftAligner<F> {
+    /// Create a new aligner with the given (negative) gap open, gap extension and frameshift
+    /// penalties, and the given substitution scores of amino acids (e.g. `blosum62`). A gap of
+    /// length `k` (codons or residues) scores `gap_open + k * gap_extend`. Untranslatable codons
+    /// are scored as `X`.
+    pub fn new(gap_open: i32, gap_extend: i32, frameshift: i32, match_fn: F) -> Self {
+        assert!(gap_open <= 0, "Expecting non-positive gap open penalty.");
+        assert!(
+            gap_extend <= 0,
+            "Expecting non-positive gap extend penalty."
+        );
+        assert!(
+            frameshift <= 0,
+            "Expecting non-positive frameshift penalty."
+        );
+        FrameshiftAligner {
+            gap_open,
+            gap_extend,
+            frameshift,
+            match_fn,
+        }
+    }
+
+    /// Align the whole DNA to the whole protein.
+    pub fn global(&mut self, dna: &[u8], protein: &[u8]) -> FrameshiftAlignment {
+        self.align(dna, protein, false)
+    }
+
+    /// Align the best matching regions of the DNA and the protein.
+    pub fn local(&mut self, dna: &[u8], protein: &[u8]) -> FrameshiftAlignment {
+        self.align(dna, protein, true)
+    }
+
+    fn align(&mut self, dna: &[u8], protein: &[u8], local: bool) -> FrameshiftAlignment {
+        let (n, m) = (dna.len(), protein.len());
+        let protein = protein.to_ascii_uppercase();
+        // translation of the codon ending at each position
+        let codons: Vec<u8> = (0..=n)
+            .map(|i| {
+                if i < 3 {
+                    b'X'
+                } else {
+                    translate_codon(&dna[i - 3..i]).unwrap_or(b'X')
+                }
+            })
+            .collect();
+
+        let cols = m + 1;
+        let mut best = vec![MIN_SCORE; (n + 1) * cols];
+        let mut source = vec![Source::Start; (n + 1) * cols];
+        // codon insertions (gap in protein) and deletions (gap in DNA), with whether they
+        // were opened in the cell
+        let mut insertion = vec![MIN_SCORE; (n + 1) * cols];
+        let mut insertion_open = vec![false; (n + 1) * cols];
+        let mut deletion = vec![MIN_SCORE; (n + 1) * cols];
+        let mut deletion_open = vec![false; (n + 1) * cols];
+
+        let mut max = (0, 0, 0);
+        for (i, &codon) in codons.iter().enumerate() {
+            for j in 0..=m {
+                let cell = i * cols + j;
+                if i == 0 && j == 0 || local {
+                    best[cell] = 0;
+                }
+                if i >= 3 {
+                    let from = (i - 3) * cols + j;
+                    let (open, extend) = (
+                        best[from] + self.gap_open + self.gap_extend,
+                        insertion[from] + self.gap_extend,
+                    );
+                    insertion[cell] = open.max(extend);
+                    insertion_open[cell] = open >= extend;
+                    if j >= 1 {
+                        let score = best[from - 1] + self.match_fn.score(codon, protein[j - 1]);
+                        if score > best[cell] {
+                            best[cell] = score;
+                            source[cell] = Source::Codon;
+                        }
+                    }
+                }
+                if j >= 1 {
+                    let from = cell - 1;
+                    let (open, extend) = (
+                        best[from] + self.gap_open + self.gap_extend,
+                        deletion[from] + self.gap_extend,
+                    );
+                    deletion[cell] = open.max(extend);
+                    deletion_open[cell] = open >= extend;
+                }
+                if insertion[cell] > best[cell] {
+                    best[cell] = insertion[cell];
+                    source[cell] = Source::CodonInsertion;
+                }
+                if deletion[cell] > best[cell] {
+                    best[cell] = deletion[cell];
+                    source[cell] = Source::Deletion;
+                }
+                for skip in 1..3 {
+                    if i >= skip {
+                        let score = best[cell - skip * cols] + self.frameshift;
+                        if score > best[cell] {
+                            best[cell] = score;
+                            source[cell] = Source::Frameshift(skip as u8);
+                        }
+                    }
+                }
+                if local && best[cell] > max.0 {
+                    max = (best[cell], i, j);
+                }
+            }
+        }
+
+        let (score, mut i, mut j) = if local {
+            max
+        } else {
+            (best[n * cols + m], n, m)
+        };
+        let (dna_end, protein_end) = (i, j);
+        let mut operations = Vec::new();
+        // state of the traceback: None for the best score of a cell, or the gap being extended
+        let mut gap: Option<Source> = None;
+        loop {
+            let cell = i * cols + j;
+            let state = gap.unwrap_or(source[cell]);
+            match state {
+                Source::Start => break,
+                Source::Codon => {
+                    operations.push(FrameshiftOperation::Codon);
+                    i -= 3;
+                    j -= 1;
+                }
+                Source::CodonInsertion => {
+                    operations.push(FrameshiftOperation::CodonInsertion);
+                    gap = if insertion_open[cell] {
+                        None
+                    } else {
+                        Some(state)
+                    };
+                    i -= 3;
+                }
+                Source::Deletion => {
+                    operations.push(FrameshiftOperation::Deletion);
+                    gap = if deletion_open[cell] {
+                        None
+                    } else {
+                        Some(state)
+                    };
+                    j -= 1;
+                }
+                Source::Frameshift(skip) => {
+                    operations.push(FrameshiftOperation::Frameshift(skip));
+                    i -= skip as usize;
+                }
+            }
+        }
+        operations.reverse();
+
+        // count frameshifts and stop codons
+        let (mut pos, mut frameshifts, mut stop_codons) = (i, 0, 0);
+        for op in &operations {
+            match *op {
+                FrameshiftOperation::Codon | FrameshiftOperation::CodonInsertion => {
+                    pos += 3;
+                    if codons[pos] == b'*' {
+                        stop_codons += 1;
+                    }
+                }
+                FrameshiftOperation::Frameshift(skip) => {
+                    pos += skip as usize;
+                    frameshifts += 1;
+                }
+                FrameshiftOperation::Deletion => (),
+            }
+        }
+
+        FrameshiftAlignment {
+            score,
+            dna_start: i,
+            dna_end,
+            protein_start: j,
+            protein_end,
+            operations,
+            frameshifts,
+            stop_codons,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scores::blosum62;
+
+    use self::FrameshiftOperation::*;
+
+    const PROTEIN: &[u8] = b"MSTNPLKRWEDHQVFAGICY";
+    const CDS: &[u8] = b"ATGAGCACCAACCCCCTGAAGCGCTGGGAGGACCACCAGGTGTTCGCCGGCATCTGCTAC";
+
+    fn aligner() -> FrameshiftAligner<fn(u8, u8) -> i32> {
+        FrameshiftAligner::new(-11, -1, -15, blosum62)
+    }
+
+    #[test]
+    fn test_exact() {
+        let alignment = aligner().global(CDS, PROTEIN);
+        assert_eq!(alignment.operations, vec![Codon; 20]);
+        let expected: i32 = PROTEIN.iter().map(|&aa| blosum62(aa, aa)).sum();
+        assert_eq!(alignment.score, expected);
+        assert_eq!((alignment.dna_end, alignment.protein_end), (60, 20));
+    }
+
+    #[test]
+    fn test_frameshifts_and_gaps() {
+        // one base deleted (codon 5 becomes two bases, shifting the frame by 2), and a stop codon
+        let mut dna = CDS.to_vec();
+        dna.remove(16);
+        dna[29..32].copy_from_slice(b"TAG");
+        let alignment = aligner().global(&dna, PROTEIN);
+        assert_eq!(alignment.frameshifts, 1);
+        assert_eq!(alignment.stop_codons, 1);
+        assert!(alignment.operations.contains(&Frameshift(2)));
+
+        // a missing codon and an extra codon
+        let mut dna = CDS[..9].to_vec();
+        dna.extend_from_slice(&CDS[12..51]);
+        dna.extend_from_slice(b"GGG");
+        dna.extend_from_slice(&CDS[51..]);
+        let alignment = aligner().global(&dna, PROTEIN);
+        assert_eq!(alignment.frameshifts, 0);
+        let count = |op| alignment.operations.iter().filter(|&&o| o == op).count();
+        assert_eq!(
+            (count(Deletion), count(CodonInsertion), count(Codon)),
+            (1, 1, 19)
+        );
+    }
+
+    #[test]
+    fn test_local() {
+        let mut dna = b"CCCCCCCCCCC".to_vec();
+        dna.extend_from_slice(&CDS[6..51]);
+        dna.extend_from_slice(b"GGGGGGGGGG");
+        let alignment = aligner().local(&dna, b"WWWWWWWWWWTNPLKRWEDHQVFAGWWWWW");
+        assert_eq!((alignment.dna_start, alignment.dna_end), (11, 56));
+        assert_eq!((alignment.protein_start, alignment.protein_end), (10, 25));
+        assert_eq!(alignment.operations, vec![Codon; 15]);
+
+        let empty = aligner().local(b"", PROTEIN);
+        assert_eq!((empty.score, empty.operations.len()), (0, 0));
+    }
+}
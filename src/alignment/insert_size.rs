@@ -0,0 +1,266 @@
+//! Robust estimation of the insert size distribution of paired-end libraries, and classification
+//! of read pairs as proper or discordant.
+//!
+//! The distribution is summarized by median and median absolute deviation (MAD, scaled to be a
+//! consistent estimator of the standard deviation under normality), which are insensitive to the
+//! few pairs that span structural variants or chimeric fragments. Mean and standard deviation
+//! are computed after trimming pairs that deviate from the median by more than a multiple of the
+//! MAD (10 by default, as in Picard).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::insert_size::{InsertSizeDistribution, PairClass};
+//!
+//! let sizes = vec![300, 310, 290, 305, 295, 300, 5000];
+//! let dist = InsertSizeDistribution::estimate(sizes).unwrap();
+//! assert_eq!(dist.median, 300.0);
+//! assert_eq!(dist.trimmed, 1);
+//! assert!((dist.mean - 300.0).abs() < 1e-9);
+//! // proper pairs deviate by at most 5 MADs from the median
+//! let (min, max) = dist.bounds(5.0);
+//! assert!(min > 250 && max < 350);
+//! ```
+
+use alignment::sv::{Orientation, ReadAlignment, SignalExtractor};
+
+/// Factor to scale the MAD to a consistent estimator of the standard deviation.
+pub const MAD_SCALE: f64 = 1.4826;
+/// Default number of MADs beyond which insert sizes are trimmed before fitting.
+pub const DEFAULT_TRIM: f64 = 10.0;
+
+/// Median of a sorted, non-empty slice.
+fn median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    }
+}
+
+/// Classification of a read pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PairClass {
+    /// The read is unpaired or its mate is unmapped.
+    Unpaired,
+    /// Expected orientation and insert size.
+    Proper,
+    /// Expected orientation, insert size below the expected range.
+    Short,
+    /// Expected orientation, insert size above the expected range.
+    Long,
+    /// Unexpected orientation of reads on the same contig.
+    Orientation(Orientation),
+    /// Reads on different contigs.
+    Interchromosomal,
+}
+
+impl PairClass {
+    pub fn is_discordant(self) -> bool {
+        !matches!(self, PairClass::Unpaired | PairClass::Proper)
+    }
+}
+
+/// Robust summary of an insert size distribution.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InsertSizeDistribution {
+    pub median: f64,
+    /// Scaled median absolute deviation.
+    pub mad: f64,
+    /// Mean after trimming.
+    pub mean: f64,
+    /// Standard deviation after trimming.
+    pub sd: f64,
+    /// Number of insert sizes used for the fit.
+    pub count: usize,
+    /// Number of trimmed insert sizes.
+    pub trimmed: usize,
+}
+
+impl InsertSizeDistribution {
+    /// Estimate the distribution from the given insert sizes, trimming at `DEFAULT_TRIM` MADs.
+    /// Returns `None` if no insert sizes are given.
+    pub fn estimate<I: IntoIterator<Item = u64>>(sizes: I) -> Option<Self> {
+        Self::estimate_trimmed(sizes, DEFAULT_TRIM)
+    }
+
+    /// Estimate the distribution from the given insert sizes, trimming insert sizes that deviate
+    /// from the median by more than `trim` MADs before computing mean and standard deviation.
+    pub fn estimate_trimmed<I: IntoIterator<Item = u64>>(sizes: I, trim: f64) -> Option<Self> {
+        let mut sizes: Vec<f64> = sizes.into_iter().map(|size| size as f64).collect();
+        if sizes.is_empty() {
+            return None;
+        }
+        sizes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let med = median(&sizes);
+        let mut deviations: Vec<f64> = sizes.iter().map(|size| (size - med).abs()).collect();
+        deviations.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mad = median(&deviations) * MAD_SCALE;
+
+        let kept: Vec<f64> = sizes
+            .iter()
+            .cloned()
+            .filter(|size| (size - med).abs() <= trim * mad)
+            .collect();
+        let count = kept.len();
+        let mean = kept.iter().sum::<f64>() / count as f64;
+        let sd = if count > 1 {
+            (kept.iter().map(|size| (size - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        Some(InsertSizeDistribution {
+            median: med,
+            mad,
+            mean,
+            sd,
+            count,
+            trimmed: sizes.len() - count,
+        })
+    }
+
+    /// Estimate the distribution from read alignments, using each pair with the expected (FR)
+    /// orientation on the same contig once (via the read with positive template length).
+    pub fn from_alignments<'a, I>(reads: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a ReadAlignment>,
+    {
+        Self::estimate(reads.into_iter().filter_map(|read| {
+            let mate = read.mate.as_ref()?;
+            if mate.contig == read.contig
+                && mate.insert_size > 0
+                && read.orientation() == Some(Orientation::FR)
+            {
+                Some(mate.insert_size as u64)
+            } else {
+                None
+            }
+        }))
+    }
+
+    /// Range of insert sizes of proper pairs, i.e. deviating from the median by at most the
+    /// given number of MADs.
+    pub fn bounds(&self, deviations: f64) -> (u64, u64) {
+        let width = deviations * self.mad;
+        (
+            (self.median - width).max(0.0).ceil() as u64,
+            (self.median + width).floor() as u64,
+        )
+    }
+
+    /// Classify the pair of the given read, with proper pairs deviating from the median by at
+    /// most the given number of MADs.
+    pub fn classify(&self, read: &ReadAlignment, deviations: f64) -> PairClass {
+        let (mate, orientation) = match (read.mate.as_ref(), read.orientation()) {
+            (Some(mate), Some(orientation)) => (mate, orientation),
+            _ => return PairClass::Unpaired,
+        };
+        if mate.contig != read.contig {
+            return PairClass::Interchromosomal;
+        }
+        if orientation != Orientation::FR {
+            return PairClass::Orientation(orientation);
+        }
+        let (min, max) = self.bounds(deviations);
+        let size = mate.insert_size.unsigned_abs();
+        if size < min {
+            PairClass::Short
+        } else if size > max {
+            PairClass::Long
+        } else {
+            PairClass::Proper
+        }
+    }
+
+    /// A signal extractor for SV evidence, considering pairs within the given number of MADs
+    /// as concordant.
+    pub fn signal_extractor(&self, min_clip: u32, deviations: f64) -> SignalExtractor {
+        let (min, max) = self.bounds(deviations);
+        SignalExtractor::new(min_clip, min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alignment::sv::{parse_cigar, Mate};
+
+    fn pair(
+        pos: u64,
+        reverse: bool,
+        mate_pos: u64,
+        mate_reverse: bool,
+        tlen: i64,
+    ) -> ReadAlignment {
+        let mut read = ReadAlignment::new("r", "chr1", pos, reverse, parse_cigar("100M").unwrap());
+        read.mate = Some(Mate {
+            contig: "chr1".to_owned(),
+            pos: mate_pos,
+            reverse: mate_reverse,
+            insert_size: tlen,
+        });
+        read
+    }
+
+    #[test]
+    fn test_estimate() {
+        assert!(InsertSizeDistribution::estimate(vec![]).is_none());
+        let dist = InsertSizeDistribution::estimate(vec![100, 200, 300, 400]).unwrap();
+        assert_eq!(dist.median, 250.0);
+        assert_relative_eq!(dist.mad, 100.0 * MAD_SCALE);
+        assert_eq!(dist.count, 4);
+        assert_relative_eq!(dist.sd, 129.0994, epsilon = 1e-4);
+
+        let dist = InsertSizeDistribution::estimate(vec![500; 3]).unwrap();
+        assert_eq!((dist.mad, dist.sd), (0.0, 0.0));
+        assert_eq!(dist.bounds(3.0), (500, 500));
+
+        let dist = InsertSizeDistribution::estimate_trimmed(vec![100, 200, 300, 400], 0.5).unwrap();
+        assert_eq!(dist.count, 2);
+        assert_eq!(dist.mean, 250.0);
+    }
+
+    #[test]
+    fn test_classify() {
+        let reads = vec![
+            pair(1000, false, 1200, true, 300),
+            pair(1200, true, 1000, false, -300),
+            pair(2000, false, 2190, true, 290),
+            pair(3000, false, 3210, true, 310),
+            pair(4000, true, 4200, false, 300),
+        ];
+        let dist = InsertSizeDistribution::from_alignments(&reads).unwrap();
+        assert_eq!(dist.count, 3);
+        assert_eq!(dist.median, 300.0);
+
+        assert_eq!(dist.classify(&reads[1], 3.0), PairClass::Proper);
+        assert_eq!(
+            dist.classify(&reads[4], 3.0),
+            PairClass::Orientation(Orientation::RF)
+        );
+        assert_eq!(
+            dist.classify(&pair(0, false, 5000, true, 5100), 3.0),
+            PairClass::Long
+        );
+        assert_eq!(
+            dist.classify(&pair(0, false, 10, true, 110), 3.0),
+            PairClass::Short
+        );
+        let mut translocation = pair(0, false, 10, true, 0);
+        translocation.mate.as_mut().unwrap().contig = "chr2".to_owned();
+        assert_eq!(
+            dist.classify(&translocation, 3.0),
+            PairClass::Interchromosomal
+        );
+        assert!(PairClass::Interchromosomal.is_discordant());
+        let mut unpaired = reads[0].clone();
+        unpaired.mate = None;
+        assert_eq!(dist.classify(&unpaired, 3.0), PairClass::Unpaired);
+        assert!(!PairClass::Unpaired.is_discordant());
+
+        let extractor = dist.signal_extractor(10, 3.0);
+        assert!(extractor.extract(&reads[0]).is_empty());
+        assert_eq!(extractor.extract(&reads[4]).len(), 1);
+    }
+}
@@ -0,0 +1,362 @@
+//! A small seed-and-extend read mapper on top of the FMD-Index, for aligning short DNA reads
+//! (e.g. from FASTQ files) to a set of reference sequences. Mapping a read has three steps:
+//!
+//! * seeding: supermaximal exact matches (SMEMs) of the read of a minimum length are searched
+//!   on both strands with `FMDIndex::all_smems`, skipping repetitive seeds with too many
+//!   occurrences,
+//! * chaining: the seeds on the same sequence and strand are grouped by their diagonal (the
+//!   reference minus the read position), allowing diagonals to differ by up to the band width
+//!   due to indels, and chains are scored by the number of read bases they cover,
+//! * extension: the read is aligned semiglobally to the reference window of each of the best
+//!   chains with banded alignment (`pairwise::banded`).
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::alignment::mapper::Mapper;
+//! use bio::io::fastq;
+//! use bio_types::strand::ReqStrand;
+//!
+//! let chr1 = b"GATTACAGGCTAGCTAGGATCCGATTACGTAGCATCGATCGATGCTAGCTAGGCTAGCATCGAGCG";
+//! let chr2 = b"TTGACCTAGGCATCGGATGCATTAGCCGATAGGCTTAGACCGTAGCTAGGATCTAGGCAATGCAT";
+//! let mapper = Mapper::new(vec![("chr1", &chr1[..]), ("chr2", &chr2[..])])
+//!     .unwrap()
+//!     .min_seed_len(10);
+//!
+//! let reads = b"@read1\nGGCATCGGATGCATTAGCAGATAGGCTTAG\n+\nIIIIIIIIIIIIIIIIIIIIIIIIIIIIII\n";
+//! for record in fastq::Reader::new(&reads[..]).records() {
+//!     let mappings = mapper.map(record.unwrap().seq());
+//!     let best = &mappings[0];
+//!     assert_eq!((best.contig.as_str(), best.pos), ("chr2", 8));
+//!     assert_eq!(best.strand, ReqStrand::Forward);
+//!     // a mismatch in the middle of the read
+//!     assert_eq!(best.cigar, "18=1X11=");
+//! }
+//! ```
+
+use std::cmp;
+
+use bio_types::strand::ReqStrand;
+
+use alignment::pairwise::banded;
+use alignment::pairwise::MatchParams;
+use alphabets::dna;
+use data_structures::fmindex::{FMDIndex, OwnedFMDIndex};
+use data_structures::suffix_array::RawSuffixArray;
+use error::Error;
+
+/// Length of the k-mers used by the banded alignment to find the band.
+const BAND_KMER_LEN: usize = 11;
+
+/// An alignment of a read to a reference sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    /// Name of the reference sequence.
+    pub contig: String,
+    /// Leftmost aligned position on the forward strand of the reference (0-based).
+    pub pos: u64,
+    /// Strand of the reference the read aligns to. Reads on the reverse strand are aligned as
+    /// reverse complement.
+    pub strand: ReqStrand,
+    /// Extended CIGAR string (`=`, `X`, `I`, `D`) of the alignment.
+    pub cigar: String,
+    pub score: i32,
+}
+
+/// An exact match of the (oriented) read to a reference sequence.
+#[derive(Clone, Copy, Debug)]
+struct Seed {
+    seq: usize,
+    strand: ReqStrand,
+    read_pos: usize,
+    ref_pos: usize,
+    len: usize,
+}
+
+impl Seed {
+    fn diagonal(&self) -> isize {
+        self.ref_pos as isize - self.read_pos as isize
+    }
+}
+
+/// Seeds on one sequence and strand with nearby diagonals.
+#[derive(Clone, Debug)]
+struct Chain {
+    seq: usize,
+    strand: ReqStrand,
+    min_diagonal: isize,
+    max_diagonal: isize,
+    /// Read ranges of the seeds.
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Chain {
+    fn new(seed: &Seed) -> Self {
+        Chain {
+            seq: seed.seq,
+            strand: seed.strand,
+            min_diagonal: seed.diagonal(),
+            max_diagonal: seed.diagonal(),
+            ranges: vec![(seed.read_pos, seed.read_pos + seed.len)],
+        }
+    }
+
+    /// Number of read bases covered by the seeds.
+    fn score(&self) -> usize {
+        let mut ranges = self.ranges.clone();
+        ranges.sort_unstable();
+        let (mut covered, mut end) = (0, 0);
+        for (start, stop) in ranges {
+            if stop > end {
+                covered += stop - cmp::max(start, end);
+                end = stop;
+            }
+        }
+        covered
+    }
+}
+
+/// A read mapper for a set of DNA reference sequences.
+pub struct Mapper {
+    index: OwnedFMDIndex,
+    sa: RawSuffixArray,
+    names: Vec<String>,
+    seqs: Vec<Vec<u8>>,
+    min_seed_len: usize,
+    max_occ: usize,
+    max_chains: usize,
+    band_width: usize,
+    match_score: i32,
+    mismatch_score: i32,
+    gap_open: i32,
+    gap_extend: i32,
+}
+
+impl Mapper {
+    /// Index the given named reference sequences, failing with the first symbol not in the
+    /// DNA alphabet (see `FMDIndex::from_texts`).
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, &'a [u8])>>(
+        references: I,
+    ) -> Result<Self, Error> {
+        let (names, seqs): (Vec<String>, Vec<Vec<u8>>) = references
+            .into_iter()
+            .map(|(name, seq)| (name.to_owned(), seq.to_vec()))
+            .unzip();
+        let (index, sa) = FMDIndex::from_texts(seqs.iter().map(|seq| &seq[..]), 32)?;
+        Ok(Mapper {
+            index,
+            sa,
+            names,
+            seqs,
+            min_seed_len: 19,
+            max_occ: 100,
+            max_chains: 5,
+            band_width: 16,
+            match_score: 1,
+            mismatch_score: -4,
+            gap_open: -6,
+            gap_extend: -1,
+        })
+    }
+
+    /// Minimum length of seeds (default 19).
+    pub fn min_seed_len(mut self, len: usize) -> Self {
+        assert!(len > 0, "Expecting positive seed length.");
+        self.min_seed_len = len;
+        self
+    }
+
+    /// Skip seeds with more occurrences in the references (default 100).
+    pub fn max_occ(mut self, max_occ: usize) -> Self {
+        self.max_occ = max_occ;
+        self
+    }
+
+    /// Maximum number of chains extended per read (default 5).
+    pub fn max_chains(mut self, max_chains: usize) -> Self {
+        self.max_chains = max_chains;
+        self
+    }
+
+    /// Width of the band around the chained diagonals (default 16), which limits the length
+    /// of indels.
+    pub fn band_width(mut self, width: usize) -> Self {
+        self.band_width = width;
+        self
+    }
+
+    /// Alignment scores (default 1, -4, -6 and -1). A gap of length `k` scores
+    /// `gap_open + k * gap_extend`.
+    pub fn scores(
+        mut self,
+        match_score: i32,
+        mismatch_score: i32,
+        gap_open: i32,
+        gap_extend: i32,
+    ) -> Self {
+        self.match_score = match_score;
+        self.mismatch_score = mismatch_score;
+        self.gap_open = gap_open;
+        self.gap_extend = gap_extend;
+        self
+    }
+
+    /// Map the given read, returning its alignments to the best chains with the best first.
+    /// Reads without seeds yield no mappings.
+    pub fn map(&self, read: &[u8]) -> Vec<Mapping> {
+        let mut chains = self.chain(&self.seed(read));
+        chains.sort_by_key(|chain| cmp::Reverse(chain.score()));
+        chains.truncate(self.max_chains);
+
+        let revcomp = dna::revcomp(read);
+        let mut mappings: Vec<Mapping> = chains
+            .iter()
+            .map(|chain| match chain.strand {
+                ReqStrand::Forward => self.extend(chain, read),
+                ReqStrand::Reverse => self.extend(chain, &revcomp),
+            })
+            .collect();
+        mappings.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| (&a.contig, a.pos).cmp(&(&b.contig, b.pos)))
+        });
+        mappings.dedup_by(|a, b| a.contig == b.contig && a.pos == b.pos && a.strand == b.strand);
+        mappings
+    }
+
+    /// Find the seeds of the read, with read positions on the strand of the reference they
+    /// match.
+    fn seed(&self, read: &[u8]) -> Vec<Seed> {
+        let m = read.len();
+        let mut seeds = Vec::new();
+        for smem in self.index.all_smems(read, self.min_seed_len) {
+            if smem.interval.forward().len() > self.max_occ {
+                continue;
+            }
+            let len = smem.end - smem.start;
+            for pos in self.index.sequence_map().occ(&smem.interval, &self.sa) {
+                let read_pos = match pos.strand {
+                    ReqStrand::Forward => smem.start,
+                    ReqStrand::Reverse => m - smem.end,
+                };
+                seeds.push(Seed {
+                    seq: pos.seq,
+                    strand: pos.strand,
+                    read_pos,
+                    ref_pos: pos.offset,
+                    len,
+                });
+            }
+        }
+        seeds
+    }
+
+    /// Group the seeds into chains by sequence, strand and diagonal.
+    fn chain(&self, seeds: &[Seed]) -> Vec<Chain> {
+        let mut seeds = seeds.to_vec();
+        seeds.sort_by_key(|seed| (seed.seq, seed.strand == ReqStrand::Reverse, seed.diagonal()));
+        let mut chains: Vec<Chain> = Vec::new();
+        for seed in &seeds {
+            match chains.last_mut() {
+                Some(chain)
+                    if chain.seq == seed.seq
+                        && chain.strand == seed.strand
+                        && seed.diagonal() - chain.max_diagonal <= self.band_width as isize =>
+                {
+                    chain.max_diagonal = seed.diagonal();
+                    chain.ranges.push((seed.read_pos, seed.read_pos + seed.len));
+                }
+                _ => chains.push(Chain::new(seed)),
+            }
+        }
+        chains
+    }
+
+    /// Align the (oriented) read to the reference window of the given chain.
+    fn extend(&self, chain: &Chain, read: &[u8]) -> Mapping {
+        let reference = &self.seqs[chain.seq];
+        let band = self.band_width as isize;
+        let start = cmp::max(chain.min_diagonal - band, 0) as usize;
+        let end = cmp::min(
+            (chain.max_diagonal + read.len() as isize + band) as usize,
+            reference.len(),
+        );
+        let window = &reference[start..end];
+        let mut aligner = banded::Aligner::with_capacity(
+            read.len(),
+            window.len(),
+            self.gap_open,
+            self.gap_extend,
+            MatchParams::new(self.match_score, self.mismatch_score),
+            cmp::min(BAND_KMER_LEN, self.min_seed_len),
+            self.band_width,
+        );
+        let alignment = aligner.semiglobal(read, window);
+        Mapping {
+            contig: self.names[chain.seq].clone(),
+            pos: (start + alignment.ystart) as u64,
+            strand: chain.strand,
+            cigar: alignment.cigar(false),
+            score: alignment.score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::{SeedableRngExt, Xoshiro256};
+
+    fn random_seq(len: usize, rng: &mut Xoshiro256) -> Vec<u8> {
+        (0..len).map(|_| b"ACGT"[rng.below(4) as usize]).collect()
+    }
+
+    #[test]
+    fn test_map() {
+        let mut rng = Xoshiro256::seed_from_u64(42);
+        let chr1 = random_seq(2000, &mut rng);
+        let chr2 = random_seq(3000, &mut rng);
+        let mapper = Mapper::new(vec![("chr1", &chr1[..]), ("chr2", &chr2[..])]).unwrap();
+
+        // exact read on the forward strand
+        let read = &chr2[1200..1300];
+        let mappings = mapper.map(read);
+        assert_eq!(mappings[0].contig, "chr2");
+        assert_eq!(mappings[0].pos, 1200);
+        assert_eq!(mappings[0].strand, ReqStrand::Forward);
+        assert_eq!(mappings[0].cigar, "100=");
+        assert_eq!(mappings[0].score, 100);
+
+        // read with a deletion of two bases and a mismatch, on the reverse strand (the CIGAR
+        // refers to the forward strand)
+        let mut read = chr1[500..550].to_vec();
+        read.extend_from_slice(&chr1[552..600]);
+        read[70] = if read[70] == b'A' { b'C' } else { b'A' };
+        let mappings = mapper.map(&dna::revcomp(&read));
+        assert_eq!(mappings[0].contig, "chr1");
+        assert_eq!(mappings[0].pos, 500);
+        assert_eq!(mappings[0].strand, ReqStrand::Reverse);
+        assert_eq!(mappings[0].cigar, "50=2D20=1X27=");
+
+        // unrelated read
+        assert!(mapper.map(&random_seq(100, &mut rng)).is_empty());
+    }
+
+    #[test]
+    fn test_chain_score() {
+        let seed = Seed {
+            seq: 0,
+            strand: ReqStrand::Forward,
+            read_pos: 10,
+            ref_pos: 110,
+            len: 20,
+        };
+        let mut chain = Chain::new(&seed);
+        chain.ranges.push((0, 15));
+        chain.ranges.push((40, 50));
+        assert_eq!(chain.score(), 40);
+    }
+}
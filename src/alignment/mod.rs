@@ -5,9 +5,31 @@
 
 //! Various alignment and distance computing algorithms.
 
+#[cfg(feature = "std")]
+pub mod baq;
 pub mod distance;
+#[cfg(feature = "std")]
+pub mod frameshift;
+#[cfg(feature = "std")]
+pub mod insert_size;
+#[cfg(feature = "std")]
+pub mod mapper;
 pub mod pairwise;
+#[cfg(feature = "std")]
+pub mod realign;
 pub mod sparse;
+#[cfg(feature = "std")]
+pub mod sv;
+#[cfg(feature = "std")]
+pub mod translated;
+#[cfg(feature = "std")]
+pub mod wga;
 
 // Re-export the alignment types.
+#[cfg(feature = "std")]
 pub use bio_types::alignment::*;
+
+#[cfg(not(feature = "std"))]
+mod types;
+#[cfg(not(feature = "std"))]
+pub use self::types::*;
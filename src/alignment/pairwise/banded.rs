@@ -81,10 +81,11 @@
 //! ```
 
 use alignment::{Alignment, AlignmentOperation};
-use std::cmp::max;
-use std::cmp::min;
-use std::i32;
-use std::ops::Range;
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::cmp::min;
+use core::i32;
+use core::ops::Range;
 use utils::TextSlice;
 
 use super::*;
@@ -904,6 +905,7 @@ impl<F: MatchFunc> Aligner<F> {
         alignment
     }
 
+    #[cfg(feature = "std")]
     #[allow(dead_code)]
     pub fn visualize(&self, alignment: &Alignment) {
         // First populate the band
@@ -1293,6 +1295,7 @@ impl Band {
         banded_cells
     }
 
+    #[cfg(feature = "std")]
     #[allow(dead_code)]
     fn visualize(&self) {
         let mut view = vec!['.'; self.rows * self.cols];
@@ -1312,6 +1315,7 @@ impl Band {
         }
     }
 
+    #[cfg(feature = "std")]
     #[allow(dead_code)]
     fn stat(&self) {
         let total_cells = self.rows * self.cols;
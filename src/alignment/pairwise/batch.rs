@@ -0,0 +1,272 @@
+//! Score-only alignment of many small sequence pairs at once, e.g. for scoring adapters or
+//! barcodes against reads.
+//!
+//! Instead of computing the DP matrix of one pair after the other, the pairs are processed in
+//! groups of `LANES` problems. Their sequences are stored in structure-of-arrays layout, such
+//! that each cell of the DP matrix holds the scores of all problems of the group side by side.
+//! The recurrences are then evaluated for all problems at once in a fixed-size inner loop
+//! without branches, which the compiler can turn into SIMD instructions (inter-sequence
+//! parallelism). Pairs are grouped by length to keep the padding of shorter problems small.
+//! Scores equal those of the corresponding `Aligner` methods, with affine gaps and constant
+//! match and mismatch scores. Complexity: O(n * m) per pair, using O(m) memory.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::pairwise::batch::BatchAligner;
+//! use bio::alignment::pairwise::Aligner;
+//! use bio::alignment::AlignmentMode;
+//!
+//! let adapters = [&b"AGATCGGAAGAGC"[..], b"CTGTCTCTTATA", b"AGATCGGAAGAGC"];
+//! let reads = [&b"TTGCAGATCGGAAGAGCACAC"[..], b"GGCTGTCTCTTAAATA", b"ACGTACGTACGT"];
+//! let mut aligner = BatchAligner::new(-5, -1, 1, -1).mode(AlignmentMode::Semiglobal);
+//! let scores = aligner.align_batch_scores(&adapters, &reads);
+//! assert_eq!(scores[0], 13);
+//!
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+//! let mut single = Aligner::new(-5, -1, &score);
+//! for i in 0..3 {
+//!     assert_eq!(scores[i], single.semiglobal(adapters[i], reads[i]).score);
+//! }
+//! ```
+
+use alloc::vec::Vec;
+use core::cmp::max;
+
+use alignment::pairwise::MIN_SCORE;
+use alignment::AlignmentMode;
+
+/// Number of alignment problems processed together.
+pub const LANES: usize = 8;
+
+type Lanes<T> = [T; LANES];
+
+/// Aligner computing the scores of many sequence pairs at once. Buffers are reused between
+/// groups and calls.
+#[derive(Debug, Clone)]
+pub struct BatchAligner {
+    gap_open: i32,
+    gap_extend: i32,
+    match_score: i32,
+    mismatch_score: i32,
+    mode: AlignmentMode,
+    x: Vec<Lanes<u8>>,
+    y: Vec<Lanes<u8>>,
+    h: Vec<Lanes<i32>>,
+    f: Vec<Lanes<i32>>,
+}
+
+impl BatchAligner {
+    /// Create a new batch aligner for local alignment with given gap open and gap extend
+    /// penalties and constant match and mismatch scores (see `Scoring::from_scores`).
+    pub fn new(gap_open: i32, gap_extend: i32, match_score: i32, mismatch_score: i32) -> Self {
+        assert!(gap_open <= 0, "gap_open can't be positive");
+        assert!(gap_extend <= 0, "gap_extend can't be positive");
+        BatchAligner {
+            gap_open,
+            gap_extend,
+            match_score,
+            mismatch_score,
+            mode: AlignmentMode::Local,
+            x: Vec::new(),
+            y: Vec::new(),
+            h: Vec::new(),
+            f: Vec::new(),
+        }
+    }
+
+    /// Set the alignment mode (default: local). As for `Aligner`, semiglobal alignment is
+    /// global in the query (x) and local in the target (y). Custom mode is not supported.
+    pub fn mode(mut self, mode: AlignmentMode) -> Self {
+        assert!(
+            mode != AlignmentMode::Custom,
+            "Expecting global, semiglobal or local mode."
+        );
+        self.mode = mode;
+        self
+    }
+
+    /// Compute the alignment score of each query with the target at the same index.
+    pub fn align_batch_scores<Q, T>(&mut self, queries: &[Q], targets: &[T]) -> Vec<i32>
+    where
+        Q: AsRef<[u8]>,
+        T: AsRef<[u8]>,
+    {
+        assert_eq!(
+            queries.len(),
+            targets.len(),
+            "Expecting the same number of queries and targets."
+        );
+        // group problems of similar size
+        let mut order: Vec<usize> = (0..queries.len()).collect();
+        order.sort_by_key(|&i| (queries[i].as_ref().len(), targets[i].as_ref().len()));
+
+        let mut scores = vec![0; queries.len()];
+        for group in order.chunks(LANES) {
+            let mut xs: Lanes<&[u8]> = [&[]; LANES];
+            let mut ys: Lanes<&[u8]> = [&[]; LANES];
+            for (l, &i) in group.iter().enumerate() {
+                xs[l] = queries[i].as_ref();
+                ys[l] = targets[i].as_ref();
+            }
+            let group_scores = self.align_group(&xs, &ys);
+            for (l, &i) in group.iter().enumerate() {
+                scores[i] = group_scores[l];
+            }
+        }
+        scores
+    }
+
+    /// Align up to `LANES` pairs, unused lanes holding empty sequences.
+    fn align_group(&mut self, xs: &Lanes<&[u8]>, ys: &Lanes<&[u8]>) -> Lanes<i32> {
+        let m = xs.iter().map(|x| x.len()).max().unwrap_or(0);
+        let n = ys.iter().map(|y| y.len()).max().unwrap_or(0);
+        let mut len_x = [0i32; LANES];
+        let mut len_y = [0i32; LANES];
+        for l in 0..LANES {
+            len_x[l] = xs[l].len() as i32;
+            len_y[l] = ys[l].len() as i32;
+        }
+        transpose(xs, m, &mut self.x);
+        transpose(ys, n, &mut self.y);
+
+        let (open, extend) = (self.gap_open, self.gap_extend);
+        let (match_score, mismatch_score) = (self.match_score, self.mismatch_score);
+        let local = self.mode == AlignmentMode::Local;
+        let global = self.mode == AlignmentMode::Global;
+
+        // the best score of each lane among the cells that may end its alignment
+        let mut best = [MIN_SCORE; LANES];
+        let update = |best: &mut Lanes<i32>, i: i32, j: i32, h: &Lanes<i32>| {
+            for l in 0..LANES {
+                let row = if local { i <= len_x[l] } else { i == len_x[l] };
+                let col = if global { j == len_y[l] } else { j <= len_y[l] };
+                best[l] = max(best[l], if row && col { h[l] } else { MIN_SCORE });
+            }
+        };
+
+        // first row: x is empty
+        self.h.clear();
+        self.f.clear();
+        for j in 0..=n {
+            let h = if global && j > 0 {
+                open + extend * j as i32
+            } else {
+                0
+            };
+            self.h.push([h; LANES]);
+            self.f.push([MIN_SCORE; LANES]);
+            update(&mut best, 0, j as i32, &self.h[j]);
+        }
+
+        for i in 1..=m {
+            let x = &self.x[i - 1];
+            // H[i - 1][j - 1], starting with the first column
+            let mut diag = self.h[0];
+            let first = if local { 0 } else { open + extend * i as i32 };
+            self.h[0] = [first; LANES];
+            update(&mut best, i as i32, 0, &self.h[0]);
+            let mut e = [MIN_SCORE; LANES];
+            for j in 1..=n {
+                let y = &self.y[j - 1];
+                let left = self.h[j - 1];
+                let up = self.h[j];
+                let f = &mut self.f[j];
+                let mut h = [0; LANES];
+                for l in 0..LANES {
+                    // gap in y (consuming x) and gap in x (consuming y)
+                    f[l] = max(f[l] + extend, up[l] + open + extend);
+                    e[l] = max(e[l] + extend, left[l] + open + extend);
+                    let s = if x[l] == y[l] {
+                        match_score
+                    } else {
+                        mismatch_score
+                    };
+                    h[l] = max(max(diag[l] + s, e[l]), f[l]);
+                    if local {
+                        h[l] = max(h[l], 0);
+                    }
+                }
+                diag = up;
+                self.h[j] = h;
+                update(&mut best, i as i32, j as i32, &h);
+            }
+        }
+        best
+    }
+}
+
+/// Store the given sequences position-wise into `buf`, padding them to length `len`.
+fn transpose(seqs: &Lanes<&[u8]>, len: usize, buf: &mut Vec<Lanes<u8>>) {
+    buf.clear();
+    buf.resize(len, [0; LANES]);
+    for (l, seq) in seqs.iter().enumerate() {
+        for (pos, &a) in seq.iter().enumerate() {
+            buf[pos][l] = a;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alignment::pairwise::Aligner;
+    use utils::{SeedableRngExt, Xoshiro256};
+
+    fn random_seq(len: usize, rng: &mut Xoshiro256) -> Vec<u8> {
+        (0..len).map(|_| b"ACGT"[rng.below(4) as usize]).collect()
+    }
+
+    #[test]
+    fn test_align_batch_scores() {
+        let mut rng = Xoshiro256::seed_from_u64(7);
+        let mut queries = Vec::new();
+        let mut targets = Vec::new();
+        for _ in 0..37 {
+            let len = rng.below(25) as usize;
+            queries.push(random_seq(len, &mut rng));
+            let len = rng.below(40) as usize;
+            let mut target = random_seq(len, &mut rng);
+            // embed a mutated copy of the query in some of the targets
+            if rng.below(2) == 0 && !queries.last().unwrap().is_empty() {
+                let mut query = queries.last().unwrap().clone();
+                let pos = rng.below(query.len() as u64) as usize;
+                query[pos] = b'A';
+                let at = rng.below(target.len() as u64 + 1) as usize;
+                target.splice(at..at, query);
+            }
+            targets.push(target);
+        }
+
+        let score = |a: u8, b: u8| if a == b { 2i32 } else { -3i32 };
+        let mut aligner = Aligner::new(-5, -2, &score);
+        for &mode in [
+            AlignmentMode::Global,
+            AlignmentMode::Semiglobal,
+            AlignmentMode::Local,
+        ]
+        .iter()
+        {
+            let mut batch = BatchAligner::new(-5, -2, 2, -3).mode(mode);
+            let scores = batch.align_batch_scores(&queries, &targets);
+            assert_eq!(scores.len(), queries.len());
+            for ((x, y), &s) in queries.iter().zip(targets.iter()).zip(scores.iter()) {
+                let expected = match mode {
+                    AlignmentMode::Global => aligner.global(x, y).score,
+                    AlignmentMode::Semiglobal => aligner.semiglobal(x, y).score,
+                    _ => aligner.local(x, y).score,
+                };
+                assert_eq!(s, expected, "{:?} {:?}", mode, (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut batch = BatchAligner::new(-5, -1, 1, -1).mode(AlignmentMode::Global);
+        let none: [&[u8]; 0] = [];
+        assert!(batch.align_batch_scores(&none, &none).is_empty());
+        let scores = batch.align_batch_scores(&[&b""[..], b"ACG"], &[&b"AC"[..], b""]);
+        assert_eq!(scores, [-7, -8]);
+    }
+}
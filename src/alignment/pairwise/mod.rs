@@ -97,14 +97,16 @@
 //!    Match, Match, Match, Match, Match, Match, Match, Match, Yclip(4)]);
 //! ```
 
-use std::cmp::max;
-use std::i32;
-use std::iter::repeat;
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::i32;
+use core::iter::repeat;
 
 use alignment::{Alignment, AlignmentMode, AlignmentOperation};
 use utils::TextSlice;
 
 pub mod banded;
+pub mod batch;
 
 /// Value to use as a 'negative infinity' score. Should be close to i32::MIN,
 /// but avoid underflow when used with reasonable scoring parameters or even
@@ -310,6 +312,10 @@ impl<F: MatchFunc> Scoring<F> {
 ///
 /// traceback - see bio::alignment::pairwise::Traceback
 /// scoring - see bio::alignment::pairwise::Scoring
+///
+/// All of the above are reused between alignments. Hence, when aligning many sequences in a
+/// loop (e.g. with one aligner per thread), create the aligner once with `with_capacity` for
+/// the largest expected sequence lengths and no further allocations happen for the DP matrices.
 #[allow(non_snake_case)]
 pub struct Aligner<F: MatchFunc> {
     I: [Vec<i32>; 2],
@@ -1042,6 +1048,26 @@ mod tests {
         assert_eq!(tb.get_s_bits(), TB_YCLIP_SUFFIX);
     }
 
+    #[test]
+    fn test_reuse_buffers() {
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+        let mut aligner = Aligner::with_capacity(20, 30, -5, -1, score);
+        let ptrs =
+            |aligner: &Aligner<_>| (aligner.traceback.matrix.as_ptr(), aligner.S[0].as_ptr());
+        let before = ptrs(&aligner);
+        let pairs: [(&[u8], &[u8]); 3] = [
+            (b"ACCGTGGAT", b"AAAAACCGTTGAT"),
+            (b"GATTACAGATTACAGATTAC", b"CCGATTACAGATTCAGATTACCCCCCCCCC"),
+            (b"A", b"T"),
+        ];
+        for &(x, y) in pairs.iter() {
+            let alignment = aligner.global(x, y);
+            let mut fresh = Aligner::new(-5, -1, score);
+            assert_eq!(alignment, fresh.global(x, y));
+        }
+        assert_eq!(ptrs(&aligner), before);
+    }
+
     #[test]
     fn test_semiglobal() {
         let x = b"ACCGTGGAT";
@@ -0,0 +1,359 @@
+//! Local realignment of reads around candidate indels, in the spirit of the GATK indel
+//! realigner. Reads aligned independently often place indels inconsistently, or explain them by
+//! a cluster of mismatches at their ends. The realigner builds one alternative haplotype per
+//! candidate indel, chooses the haplotype that best explains the reads as a whole, and realigns
+//! each read that fits the chosen haplotype better than the reference.
+//!
+//! Reads are aligned semiglobally (globally in the read, locally in the haplotype) with the
+//! pairwise aligner. Realigned reads are returned as alignments against the reference window,
+//! with the indel expressed as insertion or deletion operations.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::realign::{Indel, Realigner};
+//!
+//! let reference = b"ACGTCCGATGGCATTTTTGCAGTCAACGTGCA";
+//! // reads with a deletion of one T in the homopolymer
+//! let reads: Vec<&[u8]> = vec![b"GATGGCATTTTGCAGTC", b"GGCATTTTGCAGTCAAC"];
+//! let candidates = [Indel::deletion(13, 1), Indel::insertion(13, b"T")];
+//!
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -3i32 };
+//! let mut realigner = Realigner::new(-5, -1, &score);
+//! let realignment = realigner.realign(reference, &candidates, &reads);
+//! assert_eq!(realignment.haplotype, Some(0));
+//! let alignment = &realignment.reads[0].alignment;
+//! assert_eq!(alignment.ystart, 6);
+//! assert_eq!(alignment.cigar(false), "7=1D10=");
+//! ```
+
+use std::collections::HashSet;
+
+use alignment::pairwise::{Aligner, MatchFunc};
+use alignment::{Alignment, AlignmentMode, AlignmentOperation};
+
+/// An indel relative to the reference window: the `deleted` bases starting at `pos` (0-based)
+/// are replaced by the `inserted` bases.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Indel {
+    pub pos: usize,
+    pub deleted: usize,
+    pub inserted: Vec<u8>,
+}
+
+impl Indel {
+    /// Deletion of `len` bases, starting at `pos`.
+    pub fn deletion(pos: usize, len: usize) -> Self {
+        Indel {
+            pos,
+            deleted: len,
+            inserted: Vec::new(),
+        }
+    }
+
+    /// Insertion of the given bases before `pos`.
+    pub fn insertion(pos: usize, bases: &[u8]) -> Self {
+        Indel {
+            pos,
+            deleted: 0,
+            inserted: bases.to_vec(),
+        }
+    }
+
+    /// Indels of an alignment of a read (x) against the reference window (y), e.g. to collect
+    /// candidates from the original alignments.
+    pub fn from_alignment(alignment: &Alignment, x: &[u8]) -> Vec<Indel> {
+        let mut indels: Vec<Indel> = Vec::new();
+        let mut last_gap = false;
+        for (i, j, op) in alignment.path() {
+            match op {
+                AlignmentOperation::Ins => match indels.last_mut() {
+                    Some(indel) if last_gap && indel.pos + indel.deleted == j => {
+                        indel.inserted.push(x[i - 1])
+                    }
+                    _ => indels.push(Indel::insertion(j, &[x[i - 1]])),
+                },
+                AlignmentOperation::Del => match indels.last_mut() {
+                    Some(indel) if last_gap && indel.pos + indel.deleted == j - 1 => {
+                        indel.deleted += 1
+                    }
+                    _ => indels.push(Indel::deletion(j - 1, 1)),
+                },
+                _ => (),
+            }
+            last_gap = op == AlignmentOperation::Ins || op == AlignmentOperation::Del;
+        }
+        indels
+    }
+
+    /// Apply the indel to the reference window.
+    pub fn apply(&self, reference: &[u8]) -> Vec<u8> {
+        assert!(
+            self.pos + self.deleted <= reference.len(),
+            "Indel exceeds the reference window."
+        );
+        let mut seq = reference[..self.pos].to_vec();
+        seq.extend_from_slice(&self.inserted);
+        seq.extend_from_slice(&reference[self.pos + self.deleted..]);
+        seq
+    }
+
+    /// Reference position of the given haplotype position, `None` for inserted bases.
+    fn ref_pos(&self, pos: usize) -> Option<usize> {
+        if pos < self.pos {
+            Some(pos)
+        } else if pos < self.pos + self.inserted.len() {
+            None
+        } else {
+            Some(pos - self.inserted.len() + self.deleted)
+        }
+    }
+}
+
+/// The realignment of a single read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RealignedRead {
+    /// Alignment of the read (x) against the reference window (y).
+    pub alignment: Alignment,
+    /// Whether the read was aligned against the chosen haplotype instead of the reference.
+    pub realigned: bool,
+}
+
+/// The result of realigning a set of reads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Realignment {
+    /// Index of the chosen candidate indel, `None` if no haplotype improved on the reference.
+    pub haplotype: Option<usize>,
+    /// Sum of the alignment scores of all reads against the reference.
+    pub ref_score: i64,
+    /// Sum of the alignment scores of all reads, each against the better of reference and
+    /// chosen haplotype.
+    pub score: i64,
+    /// Realigned reads, in the order given.
+    pub reads: Vec<RealignedRead>,
+}
+
+/// Realigner of reads around candidate indels.
+pub struct Realigner<F: MatchFunc> {
+    aligner: Aligner<F>,
+}
+
+impl<F: MatchFunc> Realigner<F> {
+    /// Create a new realigner with the given gap penalties (negative) and match function.
+    pub fn new(gap_open: i32, gap_extend: i32, match_fn: F) -> Self {
+        Realigner {
+            aligner: Aligner::new(gap_open, gap_extend, match_fn),
+        }
+    }
+
+    /// Realign the given reads, which overlap the candidate indels, against the reference window.
+    /// Duplicate candidates are considered only once.
+    pub fn realign(
+        &mut self,
+        reference: &[u8],
+        candidates: &[Indel],
+        reads: &[&[u8]],
+    ) -> Realignment {
+        let ref_alignments: Vec<_> = reads
+            .iter()
+            .map(|read| self.aligner.semiglobal(read, reference))
+            .collect();
+        let ref_score = ref_alignments.iter().map(|aln| i64::from(aln.score)).sum();
+
+        let mut seen = HashSet::new();
+        let mut best: Option<(usize, i64, Vec<Alignment>)> = None;
+        for (h, indel) in candidates.iter().enumerate() {
+            if !seen.insert(indel) {
+                continue;
+            }
+            let haplotype = indel.apply(reference);
+            let alignments: Vec<_> = reads
+                .iter()
+                .map(|read| self.aligner.semiglobal(read, &haplotype))
+                .collect();
+            let score = alignments
+                .iter()
+                .zip(&ref_alignments)
+                .map(|(hap, reference)| i64::from(hap.score.max(reference.score)))
+                .sum();
+            if best.as_ref().map_or(true, |&(_, best, _)| score > best) {
+                best = Some((h, score, alignments));
+            }
+        }
+
+        match best {
+            Some((h, score, alignments)) if score > ref_score => Realignment {
+                haplotype: Some(h),
+                ref_score,
+                score,
+                reads: alignments
+                    .iter()
+                    .zip(ref_alignments)
+                    .zip(reads)
+                    .map(|((hap, ref_aln), read)| {
+                        if hap.score > ref_aln.score {
+                            RealignedRead {
+                                alignment: project(hap, read, reference, &candidates[h]),
+                                realigned: true,
+                            }
+                        } else {
+                            RealignedRead {
+                                alignment: ref_aln,
+                                realigned: false,
+                            }
+                        }
+                    })
+                    .collect(),
+            },
+            _ => Realignment {
+                haplotype: None,
+                ref_score,
+                score: ref_score,
+                reads: ref_alignments
+                    .into_iter()
+                    .map(|alignment| RealignedRead {
+                        alignment,
+                        realigned: false,
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Project a semiglobal alignment of a read against a haplotype onto the reference window.
+fn project(alignment: &Alignment, read: &[u8], reference: &[u8], indel: &Indel) -> Alignment {
+    let mut operations = Vec::with_capacity(alignment.operations.len());
+    let mut ystart = None;
+    // next reference position to be aligned
+    let mut next = 0;
+    let (mut x, mut y) = (alignment.xstart, alignment.ystart);
+    for &op in &alignment.operations {
+        let consumes_y = op != AlignmentOperation::Ins;
+        let ref_pos = if consumes_y { indel.ref_pos(y) } else { None };
+        if let Some(r) = ref_pos {
+            match ystart {
+                None => ystart = Some(r),
+                Some(_) => operations.extend((next..r).map(|_| AlignmentOperation::Del)),
+            }
+            next = r + 1;
+        }
+        match op {
+            AlignmentOperation::Match | AlignmentOperation::Subst => {
+                operations.push(match ref_pos {
+                    None => AlignmentOperation::Ins,
+                    Some(r) if read[x] == reference[r] => AlignmentOperation::Match,
+                    Some(_) => AlignmentOperation::Subst,
+                });
+                x += 1;
+                y += 1;
+            }
+            AlignmentOperation::Ins => {
+                operations.push(AlignmentOperation::Ins);
+                x += 1;
+            }
+            AlignmentOperation::Del => {
+                if ref_pos.is_some() {
+                    operations.push(AlignmentOperation::Del);
+                }
+                y += 1;
+            }
+            AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => {
+                unreachable!("Clips are filtered in semiglobal mode.")
+            }
+        }
+    }
+    let ystart = ystart.unwrap_or(indel.pos);
+    Alignment {
+        score: alignment.score,
+        xstart: alignment.xstart,
+        ystart,
+        xend: alignment.xend,
+        yend: next.max(ystart),
+        xlen: alignment.xlen,
+        ylen: reference.len(),
+        operations,
+        mode: AlignmentMode::Semiglobal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alignment::AlignmentOperation::*;
+
+    fn score(a: u8, b: u8) -> i32 {
+        if a == b {
+            1
+        } else {
+            -3
+        }
+    }
+
+    #[test]
+    fn test_indel() {
+        let reference = b"ACGTACGT";
+        let del = Indel::deletion(2, 3);
+        assert_eq!(del.apply(reference), b"ACCGT");
+        assert_eq!(del.ref_pos(1), Some(1));
+        assert_eq!(del.ref_pos(2), Some(5));
+        let ins = Indel::insertion(2, b"TT");
+        assert_eq!(ins.apply(reference), b"ACTTGTACGT");
+        assert_eq!(ins.ref_pos(3), None);
+        assert_eq!(ins.ref_pos(4), Some(2));
+    }
+
+    #[test]
+    fn test_from_alignment() {
+        let reference = b"ACGTTGCAGTCCATGAGCTTACGGATCCAGTGCA";
+        // deletion of TC at 9 and insertion of GGG before 24, which is equivalent to the
+        // left-aligned insertion before 22
+        let read = b"ACGTTGCAGCATGAGCTTACGGGGGATCCAGTGCA";
+        let mut aligner = Aligner::new(-5, -1, &score);
+        let alignment = aligner.semiglobal(read, reference);
+        assert_eq!(
+            Indel::from_alignment(&alignment, read),
+            [Indel::deletion(9, 2), Indel::insertion(22, b"GGG")]
+        );
+    }
+
+    #[test]
+    fn test_realign_insertion() {
+        let reference = b"TTGACCGATCAGGCAGGAATCGGCATTAC";
+        // reads carrying an insertion of AC after position 14
+        let hap = Indel::insertion(14, b"AC").apply(reference);
+        let reads: Vec<&[u8]> = vec![&hap[4..24], &hap[8..28], &hap[10..30], &reference[2..20]];
+        let candidates = [
+            Indel::deletion(3, 2),
+            Indel::insertion(14, b"AC"),
+            Indel::insertion(14, b"AC"),
+        ];
+        let mut realigner = Realigner::new(-5, -1, &score);
+        let realignment = realigner.realign(reference, &candidates, &reads);
+        assert_eq!(realignment.haplotype, Some(1));
+        assert!(realignment.score > realignment.ref_score);
+        for read in &realignment.reads[..3] {
+            assert!(read.realigned);
+            let ins = read.alignment.operations.iter().filter(|&&op| op == Ins);
+            assert_eq!(ins.count(), 2);
+        }
+        let first = &realignment.reads[0].alignment;
+        assert_eq!(first.ystart, 4);
+        assert_eq!(first.cigar(false), "10=2I8=");
+        assert_eq!(first.yend, 22);
+        assert!(!realignment.reads[3].realigned);
+        assert_eq!(realignment.reads[3].alignment.ystart, 2);
+    }
+
+    #[test]
+    fn test_realign_without_improvement() {
+        let reference = b"TTGACCGATCAGGCAGGAATCGGCATTAC";
+        let reads: Vec<&[u8]> = vec![&reference[3..20], &reference[5..25]];
+        let mut realigner = Realigner::new(-5, -1, &score);
+        let realignment = realigner.realign(reference, &[Indel::deletion(10, 2)], &reads);
+        assert_eq!(realignment.haplotype, None);
+        assert_eq!(realignment.score, 37);
+        assert!(realignment.reads.iter().all(|read| !read.realigned));
+        assert_eq!(realignment.reads[1].alignment.operations, [Match; 20]);
+    }
+}
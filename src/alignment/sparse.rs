@@ -27,14 +27,25 @@
 //! assert_eq!(match_path, vec![(0,2), (1,3), (2,4), (3,5), (4,6), (5,7), (6,8)]);
 //! assert_eq!(sparse_al.score, 14);
 
+#[cfg(feature = "std")]
 extern crate fxhash;
 
-use self::fxhash::FxHasher;
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+use core::hash::BuildHasherDefault;
 use data_structures::bit_tree::MaxBitTree;
-use std::cmp::{max, min};
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+#[cfg(not(feature = "std"))]
+use rustc_hash::FxHasher;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::hash::BuildHasherDefault;
 
+#[cfg(feature = "std")]
+use self::fxhash::FxHasher;
+
+/// Hash map with the Fx hasher. Without the std feature, the map of `hashbrown` is used.
 pub type HashMapFx<K, V> = HashMap<K, V, BuildHasherDefault<FxHasher>>;
 
 /// Result of a sparse alignment
@@ -0,0 +1,649 @@
+//! Extraction of structural variant (SV) signals from read alignments. Three kinds of evidence
+//! for breakpoints are reported:
+//!
+//! * clipped read ends, i.e. soft or hard clips of a minimum length,
+//! * discordant read pairs, i.e. pairs with unexpected orientation, insert size or mates on
+//!   different contigs,
+//! * split reads, i.e. reads with supplementary alignments (SAM `SA` tag).
+//!
+//! Each evidence record covers the genomic interval where the breakpoint is expected, such that
+//! it can be stored in the interval data structures (e.g. `IntervalTree`) or clustered with
+//! `cluster`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::sv::{cluster, parse_cigar, ReadAlignment, Side, Signal, SignalExtractor};
+//!
+//! let extractor = SignalExtractor::new(10, 200, 600);
+//! let mut evidence = Vec::new();
+//! for (name, pos, cigar) in &[("r1", 1000, "80M20S"), ("r2", 1030, "50M50S")] {
+//!     let read = ReadAlignment::new(name, "chr1", *pos, false, parse_cigar(cigar).unwrap());
+//!     evidence.extend(extractor.extract(&read));
+//! }
+//! assert_eq!(evidence[0].signal, Signal::Clip { side: Side::Right, len: 20 });
+//! assert_eq!(evidence[0].range, 1080..1081);
+//!
+//! // both reads are clipped at position 1080
+//! let clusters = cluster(&evidence, 0);
+//! assert_eq!(clusters.len(), 1);
+//! assert_eq!(clusters[0].evidence, [0, 1]);
+//! ```
+
+use std::cmp;
+use std::ops::Range;
+
+quick_error! {
+    #[derive(Debug, PartialEq)]
+    pub enum SvError {
+        InvalidCigar(cigar: String) {
+            description("invalid CIGAR string")
+            display("invalid CIGAR string '{}'", cigar)
+        }
+        InvalidSupplementary(entry: String) {
+            description("invalid supplementary alignment")
+            display("invalid supplementary alignment '{}'", entry)
+        }
+    }
+}
+
+/// A CIGAR operation with its length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Cigar {
+    Match(u32),
+    Ins(u32),
+    Del(u32),
+    RefSkip(u32),
+    SoftClip(u32),
+    HardClip(u32),
+    Pad(u32),
+    Equal(u32),
+    Diff(u32),
+}
+
+impl Cigar {
+    pub fn len(self) -> u32 {
+        match self {
+            Cigar::Match(l)
+            | Cigar::Ins(l)
+            | Cigar::Del(l)
+            | Cigar::RefSkip(l)
+            | Cigar::SoftClip(l)
+            | Cigar::HardClip(l)
+            | Cigar::Pad(l)
+            | Cigar::Equal(l)
+            | Cigar::Diff(l) => l,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the operation consumes reference positions.
+    pub fn consumes_ref(self) -> bool {
+        matches!(
+            self,
+            Cigar::Match(_) | Cigar::Del(_) | Cigar::RefSkip(_) | Cigar::Equal(_) | Cigar::Diff(_)
+        )
+    }
+
+    fn clip_len(self) -> u32 {
+        match self {
+            Cigar::SoftClip(l) | Cigar::HardClip(l) => l,
+            _ => 0,
+        }
+    }
+}
+
+/// Parse a CIGAR string like `10S80M2D10M`.
+pub fn parse_cigar(cigar: &str) -> Result<Vec<Cigar>, SvError> {
+    let invalid = || SvError::InvalidCigar(cigar.to_owned());
+    let mut ops = Vec::new();
+    let mut len: Option<u32> = None;
+    for c in cigar.chars() {
+        if let Some(d) = c.to_digit(10) {
+            len = Some(
+                len.unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|l| l.checked_add(d))
+                    .ok_or_else(invalid)?,
+            );
+            continue;
+        }
+        let l = len.take().ok_or_else(invalid)?;
+        ops.push(match c {
+            'M' => Cigar::Match(l),
+            'I' => Cigar::Ins(l),
+            'D' => Cigar::Del(l),
+            'N' => Cigar::RefSkip(l),
+            'S' => Cigar::SoftClip(l),
+            'H' => Cigar::HardClip(l),
+            'P' => Cigar::Pad(l),
+            '=' => Cigar::Equal(l),
+            'X' => Cigar::Diff(l),
+            _ => return Err(invalid()),
+        });
+    }
+    if len.is_some() {
+        return Err(invalid());
+    }
+    Ok(ops)
+}
+
+/// Number of reference positions covered by the given CIGAR operations.
+pub fn ref_len(cigar: &[Cigar]) -> u64 {
+    cigar
+        .iter()
+        .filter(|op| op.consumes_ref())
+        .map(|op| u64::from(op.len()))
+        .sum()
+}
+
+/// Clip lengths at the start and end of the given CIGAR operations.
+fn clips(cigar: &[Cigar]) -> (u32, u32) {
+    let leading = cigar.iter().take_while(|op| op.clip_len() > 0);
+    let trailing = cigar.iter().rev().take_while(|op| op.clip_len() > 0);
+    (
+        leading.map(|op| op.clip_len()).sum(),
+        trailing.map(|op| op.clip_len()).sum(),
+    )
+}
+
+/// Alignment position of the mate of a paired read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mate {
+    pub contig: String,
+    /// 0-based leftmost position.
+    pub pos: u64,
+    pub reverse: bool,
+    /// Observed template length (SAM `TLEN`).
+    pub insert_size: i64,
+}
+
+/// A supplementary alignment of a read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Segment {
+    pub contig: String,
+    /// 0-based leftmost position.
+    pub pos: u64,
+    pub reverse: bool,
+    pub cigar: Vec<Cigar>,
+    pub mapq: u8,
+}
+
+impl Segment {
+    /// Parse the value of a SAM `SA` tag, i.e. a list of `contig,pos,strand,CIGAR,mapQ,NM;`
+    /// entries with 1-based positions.
+    pub fn parse_sa_tag(tag: &str) -> Result<Vec<Segment>, SvError> {
+        tag.split(';')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let invalid = || SvError::InvalidSupplementary(entry.to_owned());
+                let fields: Vec<_> = entry.split(',').collect();
+                if fields.len() != 6 {
+                    return Err(invalid());
+                }
+                let pos: u64 = fields[1].parse().map_err(|_| invalid())?;
+                Ok(Segment {
+                    contig: fields[0].to_owned(),
+                    pos: pos.checked_sub(1).ok_or_else(invalid)?,
+                    reverse: match fields[2] {
+                        "+" => false,
+                        "-" => true,
+                        _ => return Err(invalid()),
+                    },
+                    cigar: parse_cigar(fields[3])?,
+                    mapq: fields[4].parse().map_err(|_| invalid())?,
+                })
+            })
+            .collect()
+    }
+
+    /// 0-based exclusive end position.
+    pub fn end(&self) -> u64 {
+        self.pos + ref_len(&self.cigar)
+    }
+}
+
+/// The alignment of a read, with the information relevant for SV signals.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadAlignment {
+    pub name: String,
+    pub contig: String,
+    /// 0-based leftmost position.
+    pub pos: u64,
+    pub reverse: bool,
+    pub cigar: Vec<Cigar>,
+    /// The mate, if the read is paired and the mate is mapped.
+    pub mate: Option<Mate>,
+    /// Supplementary alignments.
+    pub supplementary: Vec<Segment>,
+}
+
+impl ReadAlignment {
+    /// Create an unpaired read alignment without supplementary alignments.
+    pub fn new(name: &str, contig: &str, pos: u64, reverse: bool, cigar: Vec<Cigar>) -> Self {
+        ReadAlignment {
+            name: name.to_owned(),
+            contig: contig.to_owned(),
+            pos,
+            reverse,
+            cigar,
+            mate: None,
+            supplementary: Vec::new(),
+        }
+    }
+
+    /// 0-based exclusive end position.
+    pub fn end(&self) -> u64 {
+        self.pos + ref_len(&self.cigar)
+    }
+
+    /// Orientation of the read pair, `None` if the read is unpaired.
+    pub fn orientation(&self) -> Option<Orientation> {
+        let mate = self.mate.as_ref()?;
+        let leftmost = self.pos <= mate.pos;
+        Some(match (self.reverse, mate.reverse) {
+            (false, false) => Orientation::FF,
+            (true, true) => Orientation::RR,
+            (false, true) if leftmost => Orientation::FR,
+            (true, false) if !leftmost => Orientation::FR,
+            _ => Orientation::RF,
+        })
+    }
+}
+
+/// The end of a read alignment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Orientation of a read pair, by strand of the leftmost and the rightmost read, e.g. `FR` for
+/// the expected orientation of paired-end libraries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Orientation {
+    FR,
+    RF,
+    FF,
+    RR,
+}
+
+/// The kind of SV signal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Signal {
+    /// A clipped read end.
+    Clip { side: Side, len: u32 },
+    /// A discordant read pair, with the insert size if both reads are on the same contig.
+    Discordant {
+        orientation: Orientation,
+        insert_size: Option<u64>,
+        mate_contig: String,
+        mate_pos: u64,
+    },
+    /// A split read, with the breakpoint position in the supplementary alignment.
+    Split {
+        side: Side,
+        partner_contig: String,
+        partner_pos: u64,
+        partner_reverse: bool,
+    },
+}
+
+impl Signal {
+    /// Signals of the same class support the same kind of breakpoint.
+    fn class(&self) -> (u8, u8, &str) {
+        match *self {
+            Signal::Clip { side, .. } => (0, side as u8, ""),
+            Signal::Discordant {
+                orientation,
+                ref mate_contig,
+                ..
+            } => (1, orientation as u8, mate_contig),
+            Signal::Split {
+                side,
+                ref partner_contig,
+                ..
+            } => (2, side as u8, partner_contig),
+        }
+    }
+}
+
+/// Evidence for a breakpoint within the given interval.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Evidence {
+    pub read: String,
+    pub contig: String,
+    pub range: Range<u64>,
+    pub reverse: bool,
+    pub signal: Signal,
+}
+
+/// Extraction of SV signals from read alignments.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalExtractor {
+    /// Minimum length of clips to be reported.
+    pub min_clip: u32,
+    /// Minimum insert size of concordant pairs.
+    pub min_insert_size: u64,
+    /// Maximum insert size of concordant pairs.
+    pub max_insert_size: u64,
+}
+
+impl SignalExtractor {
+    pub fn new(min_clip: u32, min_insert_size: u64, max_insert_size: u64) -> Self {
+        assert!(
+            min_insert_size <= max_insert_size,
+            "Expecting minimum insert size not above maximum insert size."
+        );
+        SignalExtractor {
+            min_clip,
+            min_insert_size,
+            max_insert_size,
+        }
+    }
+
+    /// All SV signals of the given read.
+    pub fn extract(&self, read: &ReadAlignment) -> Vec<Evidence> {
+        let mut evidence = Vec::new();
+        let end = read.end();
+        let mut push = |range: Range<u64>, signal: Signal| {
+            evidence.push(Evidence {
+                read: read.name.clone(),
+                contig: read.contig.clone(),
+                range,
+                reverse: read.reverse,
+                signal,
+            })
+        };
+
+        let (left, right) = clips(&read.cigar);
+        if left >= self.min_clip && left > 0 {
+            push(
+                read.pos..read.pos + 1,
+                Signal::Clip {
+                    side: Side::Left,
+                    len: left,
+                },
+            );
+        }
+        if right >= self.min_clip && right > 0 {
+            push(
+                end..end + 1,
+                Signal::Clip {
+                    side: Side::Right,
+                    len: right,
+                },
+            );
+        }
+
+        if let (Some(mate), Some(orientation)) = (read.mate.as_ref(), read.orientation()) {
+            let insert_size = if mate.contig == read.contig {
+                Some(mate.insert_size.unsigned_abs())
+            } else {
+                None
+            };
+            let concordant = orientation == Orientation::FR
+                && insert_size.is_some_and(|size| {
+                    size >= self.min_insert_size && size <= self.max_insert_size
+                });
+            if !concordant {
+                // the breakpoint is expected downstream of forward and upstream of reverse reads
+                let range = if read.reverse {
+                    read.pos.saturating_sub(self.max_insert_size)..read.pos
+                } else {
+                    end..end + self.max_insert_size
+                };
+                push(
+                    range,
+                    Signal::Discordant {
+                        orientation,
+                        insert_size,
+                        mate_contig: mate.contig.clone(),
+                        mate_pos: mate.pos,
+                    },
+                );
+            }
+        }
+
+        for segment in &read.supplementary {
+            let side = if left > right {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            let (seg_left, seg_right) = clips(&segment.cigar);
+            let partner_pos = if seg_left > seg_right {
+                segment.pos
+            } else {
+                segment.end()
+            };
+            let pos = match side {
+                Side::Left => read.pos,
+                Side::Right => end,
+            };
+            push(
+                pos..pos + 1,
+                Signal::Split {
+                    side,
+                    partner_contig: segment.contig.clone(),
+                    partner_pos,
+                    partner_reverse: segment.reverse,
+                },
+            );
+        }
+        evidence
+    }
+}
+
+/// A cluster of evidence of the same class.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cluster {
+    pub contig: String,
+    /// Union of the intervals of all members.
+    pub range: Range<u64>,
+    /// Indices of the members in the given evidence.
+    pub evidence: Vec<usize>,
+}
+
+/// Cluster evidence of the same class (signal kind, side or orientation, and partner contig)
+/// on the same contig, whose intervals overlap or are at most `max_gap` apart.
+pub fn cluster(evidence: &[Evidence], max_gap: u64) -> Vec<Cluster> {
+    let mut order: Vec<usize> = (0..evidence.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&evidence[a], &evidence[b]);
+        (&a.contig, a.signal.class(), a.range.start, a.range.end).cmp(&(
+            &b.contig,
+            b.signal.class(),
+            b.range.start,
+            b.range.end,
+        ))
+    });
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut last: Option<&Evidence> = None;
+    for i in order {
+        let e = &evidence[i];
+        match clusters.last_mut() {
+            Some(cluster)
+                if last.is_some_and(|last| {
+                    last.contig == e.contig && last.signal.class() == e.signal.class()
+                }) && e.range.start <= cluster.range.end + max_gap =>
+            {
+                cluster.range.end = cmp::max(cluster.range.end, e.range.end);
+                cluster.evidence.push(i);
+            }
+            _ => clusters.push(Cluster {
+                contig: e.contig.clone(),
+                range: e.range.clone(),
+                evidence: vec![i],
+            }),
+        }
+        last = Some(e);
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_structures::interval_tree::IntervalTree;
+
+    fn read(pos: u64, reverse: bool, cigar: &str) -> ReadAlignment {
+        ReadAlignment::new("r", "chr1", pos, reverse, parse_cigar(cigar).unwrap())
+    }
+
+    #[test]
+    fn test_parse_cigar() {
+        let cigar = parse_cigar("5H10S30M2I3D1N4=1X").unwrap();
+        assert_eq!(cigar.len(), 8);
+        assert_eq!(cigar[0], Cigar::HardClip(5));
+        assert_eq!(ref_len(&cigar), 39);
+        assert_eq!(clips(&cigar), (15, 0));
+        assert_eq!(parse_cigar("").unwrap(), []);
+        assert!(parse_cigar("10").is_err());
+        assert!(parse_cigar("M").is_err());
+        assert!(parse_cigar("10Q").is_err());
+    }
+
+    #[test]
+    fn test_parse_sa_tag() {
+        let segments =
+            Segment::parse_sa_tag("chr2,101,-,60S40M,60,0;chr1,5,+,40M60H,20,1;").unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].contig, "chr2");
+        assert_eq!(segments[0].pos, 100);
+        assert!(segments[0].reverse);
+        assert_eq!(segments[0].end(), 140);
+        assert_eq!(segments[1].mapq, 20);
+        assert!(Segment::parse_sa_tag("chr1,0,+,40M,60,0").is_err());
+        assert!(Segment::parse_sa_tag("chr1,5,*,40M,60,0").is_err());
+    }
+
+    #[test]
+    fn test_clips() {
+        let extractor = SignalExtractor::new(10, 200, 600);
+        assert!(extractor.extract(&read(100, false, "5S95M")).is_empty());
+        let evidence = extractor.extract(&read(100, false, "20S70M10S"));
+        assert_eq!(evidence.len(), 2);
+        assert_eq!(evidence[0].range, 100..101);
+        assert_eq!(
+            evidence[1].signal,
+            Signal::Clip {
+                side: Side::Right,
+                len: 10
+            }
+        );
+        assert_eq!(evidence[1].range, 170..171);
+    }
+
+    #[test]
+    fn test_discordant() {
+        let extractor = SignalExtractor::new(10, 200, 600);
+        let mut r = read(100, false, "100M");
+        let mut mate = Mate {
+            contig: "chr1".to_owned(),
+            pos: 300,
+            reverse: true,
+            insert_size: 300,
+        };
+        r.mate = Some(mate.clone());
+        assert!(extractor.extract(&r).is_empty());
+
+        // deletion: insert size too large
+        mate.pos = 2000;
+        mate.insert_size = 2000;
+        r.mate = Some(mate.clone());
+        let evidence = extractor.extract(&r);
+        assert_eq!(evidence.len(), 1);
+        assert_eq!(evidence[0].range, 200..800);
+        match evidence[0].signal {
+            Signal::Discordant {
+                orientation,
+                insert_size,
+                ..
+            } => {
+                assert_eq!(orientation, Orientation::FR);
+                assert_eq!(insert_size, Some(2000));
+            }
+            _ => panic!("Expecting discordant pair."),
+        }
+
+        // the reverse mate, upstream of its position
+        let mut m = read(2000, true, "100M");
+        m.mate = Some(Mate {
+            contig: "chr1".to_owned(),
+            pos: 100,
+            reverse: false,
+            insert_size: -2000,
+        });
+        assert_eq!(extractor.extract(&m)[0].range, 1400..2000);
+
+        // inversion and translocation
+        mate = Mate {
+            contig: "chr1".to_owned(),
+            pos: 300,
+            reverse: false,
+            insert_size: 300,
+        };
+        r.mate = Some(mate.clone());
+        let evidence = extractor.extract(&r);
+        assert!(
+            matches!(evidence[0].signal, Signal::Discordant { orientation, .. } if orientation == Orientation::FF)
+        );
+        mate.contig = "chr2".to_owned();
+        mate.reverse = true;
+        r.mate = Some(mate);
+        let evidence = extractor.extract(&r);
+        assert!(
+            matches!(evidence[0].signal, Signal::Discordant { insert_size, .. } if insert_size.is_none())
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let extractor = SignalExtractor::new(10, 200, 600);
+        let mut r = read(1000, false, "60M40S");
+        r.supplementary = Segment::parse_sa_tag("chr3,5001,+,60H40M,60,0;").unwrap();
+        let evidence = extractor.extract(&r);
+        assert_eq!(evidence.len(), 2);
+        assert_eq!(evidence[1].range, 1060..1061);
+        assert_eq!(
+            evidence[1].signal,
+            Signal::Split {
+                side: Side::Right,
+                partner_contig: "chr3".to_owned(),
+                partner_pos: 5000,
+                partner_reverse: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_cluster() {
+        let extractor = SignalExtractor::new(10, 200, 600);
+        let reads = [
+            read(100, false, "50M50S"),
+            read(400, false, "50S50M"),
+            read(120, false, "30M70S"),
+            read(90, false, "59M41S"),
+            read(1000, false, "50M50S"),
+        ];
+        let evidence: Vec<_> = reads.iter().flat_map(|r| extractor.extract(r)).collect();
+        let clusters = cluster(&evidence, 5);
+        assert_eq!(clusters.len(), 3);
+        // left clips are sorted before right clips
+        assert_eq!(clusters[0].evidence, [1]);
+        assert_eq!(clusters[1].range, 149..151);
+        assert_eq!(clusters[1].evidence, [3, 0, 2]);
+        assert_eq!(clusters[2].range, 1050..1051);
+
+        let tree: IntervalTree<_, _> = evidence
+            .iter()
+            .map(|e| (e.range.clone(), e.read.clone()))
+            .collect();
+        assert_eq!(tree.find(140..160).count(), 3);
+    }
+}
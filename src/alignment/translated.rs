@@ -0,0 +1,563 @@
+//! Translated search of DNA queries against proteins, in the manner of BLASTX.
+//!
+//! Queries are translated in all six reading frames. Exact k-mer seeds of the translations are
+//! looked up in a seed table of the proteins, seeds on nearby diagonals are clustered, and each
+//! cluster is extended by local alignment (BLOSUM62) of the surrounding regions. Hits of the same
+//! protein in different frames of the same strand can be stitched into frameshift-aware chains,
+//! e.g. for genes with sequencing errors or pseudogenes.
+//!
+//! Query coordinates are 0-based, end exclusive nucleotide positions on the forward strand.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::alignment::translated::{ProteinSeedTable, TranslatedSearch};
+//! use bio_types::strand::ReqStrand;
+//!
+//! let protein = b"MKVLAAGIVGLLLAQTSWAHEEPRYCG";
+//! let table = ProteinSeedTable::new(&[("prot1", &protein[..])], 4);
+//! // codons for KVLAAGIVGLLLAQ, preceded by unrelated bases
+//! let query = b"GGTACCAAAGTGCTGGCGGCGGGCATTGTGGGCCTGCTGCTGGCGCAG";
+//!
+//! let hits = TranslatedSearch::new().search(&table, query);
+//! assert_eq!(hits.len(), 1);
+//! let hit = &hits[0];
+//! assert_eq!(table.name(hit.protein), "prot1");
+//! assert_eq!((hit.frame.strand, hit.frame.offset), (ReqStrand::Forward, 0));
+//! assert_eq!((hit.query_start, hit.query_end), (6, 48));
+//! assert_eq!((hit.protein_start, hit.protein_end), (1, 15));
+//! ```
+
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+
+use bio_types::strand::ReqStrand;
+
+use alignment::pairwise::Aligner;
+use alignment::AlignmentOperation;
+use alphabets::dna;
+use scores::blosum62;
+use seq_analysis::codon::translate_codon;
+
+/// A reading frame, given by the strand and the offset (0 to 2) of the first codon on that
+/// strand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Frame {
+    pub strand: ReqStrand,
+    pub offset: usize,
+}
+
+impl fmt::Display for Frame {
+    /// Format like BLAST, i.e. `+1` to `+3` and `-1` to `-3`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = match self.strand {
+            ReqStrand::Forward => '+',
+            ReqStrand::Reverse => '-',
+        };
+        write!(f, "{}{}", sign, self.offset + 1)
+    }
+}
+
+/// Translations of the given DNA sequence in all six reading frames (forward frames first).
+/// Codons with ambiguous bases are translated to `X`, stop codons to `*`.
+pub fn six_frames(seq: &[u8]) -> Vec<(Frame, Vec<u8>)> {
+    let reverse = dna::revcomp(seq);
+    let mut frames = Vec::with_capacity(6);
+    for &(strand, seq) in &[
+        (ReqStrand::Forward, seq),
+        (ReqStrand::Reverse, &reverse[..]),
+    ] {
+        for offset in 0..3 {
+            let translation = seq
+                .get(offset..)
+                .unwrap_or(&[])
+                .chunks_exact(3)
+                .map(|codon| translate_codon(codon).unwrap_or(b'X'))
+                .collect();
+            frames.push((Frame { strand, offset }, translation));
+        }
+    }
+    frames
+}
+
+/// A table of the exact k-mer seeds of a set of proteins.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProteinSeedTable {
+    k: usize,
+    names: Vec<String>,
+    proteins: Vec<Vec<u8>>,
+    seeds: HashMap<Vec<u8>, Vec<(usize, usize)>>,
+}
+
+impl ProteinSeedTable {
+    /// Build the seed table for the given pairs of protein names and sequences. Seeds containing
+    /// `X` or stop codons are skipped.
+    pub fn new(proteins: &[(&str, &[u8])], k: usize) -> Self {
+        assert!(k > 0, "Expecting positive seed length.");
+        let mut table = ProteinSeedTable {
+            k,
+            names: Vec::with_capacity(proteins.len()),
+            proteins: Vec::with_capacity(proteins.len()),
+            seeds: HashMap::new(),
+        };
+        for (i, &(name, protein)) in proteins.iter().enumerate() {
+            let protein = protein.to_ascii_uppercase();
+            for (pos, seed) in protein.windows(k).enumerate() {
+                if is_seed(seed) {
+                    table.seeds.entry(seed.to_vec()).or_default().push((i, pos));
+                }
+            }
+            table.names.push(name.to_owned());
+            table.proteins.push(protein);
+        }
+        table
+    }
+
+    /// Name of the protein with the given index.
+    pub fn name(&self, protein: usize) -> &str {
+        &self.names[protein]
+    }
+
+    /// Sequence of the protein with the given index.
+    pub fn protein(&self, protein: usize) -> &[u8] {
+        &self.proteins[protein]
+    }
+
+    /// Occurrences (protein and position) of the given seed.
+    pub fn lookup(&self, seed: &[u8]) -> &[(usize, usize)] {
+        self.seeds.get(seed).map_or(&[], |hits| hits)
+    }
+}
+
+fn is_seed(seed: &[u8]) -> bool {
+    !seed.iter().any(|&aa| aa == b'X' || aa == b'*')
+}
+
+/// A local alignment of a translated query and a protein.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranslatedHit {
+    pub protein: usize,
+    pub frame: Frame,
+    /// Aligned range of the query (nucleotides, forward strand).
+    pub query_start: usize,
+    pub query_end: usize,
+    /// Aligned range of the protein.
+    pub protein_start: usize,
+    pub protein_end: usize,
+    pub score: i32,
+    /// Alignment operations, the translated query being x and the protein y.
+    pub operations: Vec<AlignmentOperation>,
+}
+
+/// A chain of hits of the same protein on the same strand, possibly in different frames.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StitchedHit {
+    pub protein: usize,
+    pub strand: ReqStrand,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub protein_start: usize,
+    pub protein_end: usize,
+    /// Total score of the hits minus the frameshift penalties.
+    pub score: i32,
+    /// Number of frame changes between adjacent hits.
+    pub frameshifts: usize,
+    /// The hits, in order of protein position.
+    pub hits: Vec<TranslatedHit>,
+}
+
+/// Translated search of DNA queries in a protein seed table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranslatedSearch {
+    gap_open: i32,
+    gap_extend: i32,
+    min_score: i32,
+    band: usize,
+    flank: usize,
+    max_gap: usize,
+    frameshift_penalty: i32,
+}
+
+impl Default for TranslatedSearch {
+    fn default() -> Self {
+        TranslatedSearch {
+            gap_open: -11,
+            gap_extend: -1,
+            min_score: 30,
+            band: 10,
+            flank: 30,
+            max_gap: 30,
+            frameshift_penalty: 15,
+        }
+    }
+}
+
+impl TranslatedSearch {
+    /// Create a new search with BLASTX-like gap penalties (-11, -1), reporting hits with a
+    /// BLOSUM62 score of at least 30.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the gap open and extension penalties (negative) of the local alignments.
+    pub fn gap_penalties(mut self, gap_open: i32, gap_extend: i32) -> Self {
+        assert!(
+            gap_open <= 0 && gap_extend <= 0,
+            "Expecting non-positive gap penalties."
+        );
+        self.gap_open = gap_open;
+        self.gap_extend = gap_extend;
+        self
+    }
+
+    /// Set the minimal alignment score of hits.
+    pub fn min_score(mut self, min_score: i32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Set the maximal distance (amino acids) between the diagonals of seeds that are extended
+    /// together, and the flanks around the seeds included in the local alignment.
+    pub fn band(mut self, band: usize, flank: usize) -> Self {
+        self.band = band;
+        self.flank = flank;
+        self
+    }
+
+    /// Set the maximal distance (nucleotides) between stitched hits, and the penalty per frame
+    /// change.
+    pub fn stitching(mut self, max_gap: usize, frameshift_penalty: i32) -> Self {
+        self.max_gap = max_gap;
+        self.frameshift_penalty = frameshift_penalty;
+        self
+    }
+
+    /// All hits of the query, ordered by protein, query position and frame. Hits that are
+    /// found from several seed clusters are reported once.
+    pub fn search(&self, table: &ProteinSeedTable, query: &[u8]) -> Vec<TranslatedHit> {
+        let k = table.k;
+        let mut aligner = Aligner::new(self.gap_open, self.gap_extend, blosum62);
+        let mut hits = Vec::new();
+        for (frame, translation) in six_frames(query) {
+            // seeds by protein, as (diagonal, translation position)
+            let mut seeds: HashMap<usize, Vec<(isize, usize)>> = HashMap::new();
+            for (pos, seed) in translation.windows(k).enumerate() {
+                for &(protein, protein_pos) in table.lookup(seed) {
+                    seeds
+                        .entry(protein)
+                        .or_default()
+                        .push((pos as isize - protein_pos as isize, pos));
+                }
+            }
+            for (protein, mut seeds) in seeds {
+                seeds.sort_unstable();
+                let sequence = table.protein(protein);
+                for cluster in clusters(&seeds, self.band) {
+                    let (min_diagonal, max_diagonal) = (cluster[0].0, cluster[cluster.len() - 1].0);
+                    let first = cluster.iter().map(|&(_, pos)| pos).min().unwrap();
+                    let last = cluster.iter().map(|&(_, pos)| pos).max().unwrap() + k;
+                    let x_start = first.saturating_sub(self.flank);
+                    let x_end = cmp::min(last + self.flank, translation.len());
+                    let y_start = cmp::max(x_start as isize - max_diagonal, 0) as usize;
+                    let y_end = cmp::min(
+                        cmp::max(x_end as isize - min_diagonal, 0) as usize,
+                        sequence.len(),
+                    );
+                    if y_start >= y_end {
+                        continue;
+                    }
+                    let alignment =
+                        aligner.local(&translation[x_start..x_end], &sequence[y_start..y_end]);
+                    if alignment.score < self.min_score {
+                        continue;
+                    }
+                    let (aa_start, aa_end) = (x_start + alignment.xstart, x_start + alignment.xend);
+                    let (query_start, query_end) = match frame.strand {
+                        ReqStrand::Forward => {
+                            (frame.offset + 3 * aa_start, frame.offset + 3 * aa_end)
+                        }
+                        ReqStrand::Reverse => (
+                            query.len() - frame.offset - 3 * aa_end,
+                            query.len() - frame.offset - 3 * aa_start,
+                        ),
+                    };
+                    hits.push(TranslatedHit {
+                        protein,
+                        frame,
+                        query_start,
+                        query_end,
+                        protein_start: y_start + alignment.ystart,
+                        protein_end: y_start + alignment.yend,
+                        score: alignment.score,
+                        operations: alignment.operations,
+                    });
+                }
+            }
+        }
+        hits.sort_by(|a, b| {
+            (
+                a.protein,
+                a.query_start,
+                a.frame,
+                a.query_end,
+                a.protein_start,
+            )
+                .cmp(&(
+                    b.protein,
+                    b.query_start,
+                    b.frame,
+                    b.query_end,
+                    b.protein_start,
+                ))
+        });
+        hits.dedup_by(|a, b| {
+            (
+                a.protein,
+                a.frame,
+                a.query_start,
+                a.query_end,
+                a.protein_start,
+                a.protein_end,
+            ) == (
+                b.protein,
+                b.frame,
+                b.query_start,
+                b.query_end,
+                b.protein_start,
+                b.protein_end,
+            )
+        });
+        hits
+    }
+
+    /// Stitch hits of the same protein and strand into colinear chains, allowing frame changes
+    /// (penalized) and overlaps between adjacent hits. Chains are extracted greedily, the highest scoring first, and
+    /// ordered by score (decreasing).
+    pub fn stitch(&self, hits: &[TranslatedHit]) -> Vec<StitchedHit> {
+        let mut groups: HashMap<(usize, ReqStrand), Vec<&TranslatedHit>> = HashMap::new();
+        for hit in hits {
+            groups
+                .entry((hit.protein, hit.frame.strand))
+                .or_default()
+                .push(hit);
+        }
+        let mut stitched = Vec::new();
+        for (_, mut group) in groups {
+            group.sort_by_key(|hit| (hit.protein_start, hit.protein_end, hit.query_start));
+            while !group.is_empty() {
+                let chain = self.best_chain(&group);
+                let hits: Vec<_> = chain.iter().map(|&i| group[i].clone()).collect();
+                for &i in chain.iter().rev() {
+                    group.remove(i);
+                }
+                stitched.push(self.stitched(hits));
+            }
+        }
+        stitched.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then((a.protein, a.query_start).cmp(&(b.protein, b.query_start)))
+        });
+        stitched
+    }
+
+    /// Score of appending `next` to a chain ending with `prev`, or `None` if it cannot follow.
+    /// Both hits may overlap (e.g. if an alignment extends beyond a frameshift), in which case
+    /// only the non-overlapping part of `next` is scored.
+    fn link(&self, prev: &TranslatedHit, next: &TranslatedHit) -> Option<i32> {
+        if next.protein_start < prev.protein_start || next.protein_end <= prev.protein_end {
+            return None;
+        }
+        let (prev_start, prev_end, next_start, next_end) = match next.frame.strand {
+            ReqStrand::Forward => (
+                prev.query_start,
+                prev.query_end,
+                next.query_start,
+                next.query_end,
+            ),
+            // coordinates on the reverse strand
+            ReqStrand::Reverse => (
+                usize::MAX - prev.query_end,
+                usize::MAX - prev.query_start,
+                usize::MAX - next.query_end,
+                usize::MAX - next.query_start,
+            ),
+        };
+        if next_start < prev_start
+            || next_end <= prev_end
+            || next_start.saturating_sub(prev_end) > self.max_gap
+        {
+            return None;
+        }
+        let len = next.protein_end - next.protein_start;
+        let overlap = prev.protein_end.saturating_sub(next.protein_start);
+        let score = next.score * (len - overlap) as i32 / len as i32;
+        Some(if next.frame == prev.frame {
+            score
+        } else {
+            score - self.frameshift_penalty
+        })
+    }
+
+    /// Indices of the highest scoring chain of the sorted hits.
+    fn best_chain(&self, hits: &[&TranslatedHit]) -> Vec<usize> {
+        let mut scores = Vec::with_capacity(hits.len());
+        let mut preds = Vec::with_capacity(hits.len());
+        for (i, hit) in hits.iter().enumerate() {
+            let mut best = (hit.score, None);
+            for (j, prev) in hits[..i].iter().enumerate() {
+                if let Some(score) = self.link(prev, hit) {
+                    if scores[j] + score > best.0 {
+                        best = (scores[j] + score, Some(j));
+                    }
+                }
+            }
+            scores.push(best.0);
+            preds.push(best.1);
+        }
+        let mut i = (0..hits.len())
+            .max_by_key(|&i| (scores[i], cmp::Reverse(i)))
+            .unwrap();
+        let mut chain = vec![i];
+        while let Some(j) = preds[i] {
+            chain.push(j);
+            i = j;
+        }
+        chain.reverse();
+        chain
+    }
+
+    fn stitched(&self, hits: Vec<TranslatedHit>) -> StitchedHit {
+        let score = hits[0].score
+            + hits
+                .windows(2)
+                .map(|w| self.link(&w[0], &w[1]).unwrap())
+                .sum::<i32>();
+        StitchedHit {
+            protein: hits[0].protein,
+            strand: hits[0].frame.strand,
+            query_start: hits.iter().map(|hit| hit.query_start).min().unwrap(),
+            query_end: hits.iter().map(|hit| hit.query_end).max().unwrap(),
+            protein_start: hits[0].protein_start,
+            protein_end: hits[hits.len() - 1].protein_end,
+            score,
+            frameshifts: hits.windows(2).filter(|w| w[0].frame != w[1].frame).count(),
+            hits,
+        }
+    }
+}
+
+/// Split the seeds, sorted by diagonal, into clusters of nearby diagonals.
+fn clusters(seeds: &[(isize, usize)], band: usize) -> Vec<&[(isize, usize)]> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    for i in 1..=seeds.len() {
+        if i == seeds.len() || seeds[i].0 - seeds[i - 1].0 > band as isize {
+            clusters.push(&seeds[start..i]);
+            start = i;
+        }
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reverse translation with fixed codons.
+    fn encode(protein: &[u8]) -> Vec<u8> {
+        protein
+            .iter()
+            .flat_map(|&aa| {
+                let codon: &[u8] = match aa {
+                    b'A' => b"GCC",
+                    b'C' => b"TGC",
+                    b'D' => b"GAC",
+                    b'E' => b"GAG",
+                    b'F' => b"TTC",
+                    b'G' => b"GGC",
+                    b'H' => b"CAC",
+                    b'I' => b"ATC",
+                    b'K' => b"AAG",
+                    b'L' => b"CTG",
+                    b'M' => b"ATG",
+                    b'N' => b"AAC",
+                    b'P' => b"CCC",
+                    b'Q' => b"CAG",
+                    b'R' => b"CGC",
+                    b'S' => b"AGC",
+                    b'T' => b"ACC",
+                    b'V' => b"GTG",
+                    b'W' => b"TGG",
+                    _ => b"TAC",
+                };
+                codon.to_vec()
+            })
+            .collect()
+    }
+
+    const PROTEIN: &[u8] = b"MSTNPLKRWEDHQVFAGICYLAKEGTRSWPHNDIVQCMFY";
+
+    #[test]
+    fn test_six_frames() {
+        let frames = six_frames(b"ATGGCCTAAN");
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0].1, b"MA*");
+        assert_eq!(frames[1].1, b"WPX");
+        assert_eq!(frames[3].0.to_string(), "-1");
+        // reverse complement NTTAGGCCAT
+        assert_eq!(frames[3].1, b"XRP");
+        assert_eq!(frames[4].1, b"LGH");
+        assert!(six_frames(b"AC").iter().all(|(_, t)| t.is_empty()));
+    }
+
+    #[test]
+    fn test_search_reverse() {
+        let table = ProteinSeedTable::new(&[("a", b"WWWWWWWW"), ("b", PROTEIN)], 4);
+        assert_eq!(table.lookup(b"MSTN"), [(1, 0)]);
+        assert!(table.lookup(b"AAAA").is_empty());
+        let mut query = b"GG".to_vec();
+        query.extend(dna::revcomp(encode(&PROTEIN[5..35])));
+        query.extend(b"TTT");
+        let hits = TranslatedSearch::new().search(&table, &query);
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.protein, 1);
+        assert_eq!(hit.frame.to_string(), "-1");
+        assert_eq!((hit.query_start, hit.query_end), (2, 92));
+        assert_eq!((hit.protein_start, hit.protein_end), (5, 35));
+        assert!(hit
+            .operations
+            .iter()
+            .all(|&op| op == AlignmentOperation::Match));
+        assert!(TranslatedSearch::new().search(&table, b"ACGT").is_empty());
+    }
+
+    #[test]
+    fn test_stitch() {
+        // a frameshift after codon 20, by a deleted base
+        let mut query = encode(PROTEIN);
+        query.remove(60);
+        let search = TranslatedSearch::new().min_score(20);
+        let hits = search.search(&ProteinSeedTable::new(&[("p", PROTEIN)], 4), &query);
+        let frames: Vec<_> = hits.iter().map(|hit| hit.frame.to_string()).collect();
+        assert!(frames.contains(&"+1".to_owned()) && frames.contains(&"+3".to_owned()));
+
+        let stitched = search.stitch(&hits);
+        let best = &stitched[0];
+        assert_eq!(best.frameshifts, 1);
+        assert_eq!(best.strand, ReqStrand::Forward);
+        assert_eq!((best.query_start, best.query_end), (0, query.len()));
+        assert_eq!((best.protein_start, best.protein_end), (0, PROTEIN.len()));
+        let max_single = hits.iter().map(|hit| hit.score).max().unwrap();
+        assert!(best.score > max_single);
+
+        // frameshifts costing more than the second hit gains
+        let strict = search.stitching(30, 200).stitch(&hits);
+        assert_eq!(strict.len(), 2);
+        assert!(strict.iter().all(|hit| hit.frameshifts == 0));
+    }
+}
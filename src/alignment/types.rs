@@ -0,0 +1,113 @@
+//! Types for representing pairwise sequence alignments without the std feature. They have the
+//! same fields as the types of `bio_types::alignment`, which are re-exported with the std
+//! feature, but `bio-types` needs std.
+
+use alloc::vec::Vec;
+
+pub type TextSlice<'a> = &'a [u8];
+
+/// Alignment operations supported are match, substitution, insertion, deletion
+/// and clipping. The usize value associated with the clipping operations are the
+/// lengths clipped (see `bio_types::alignment::AlignmentOperation`).
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum AlignmentOperation {
+    Match,
+    Subst,
+    Del,
+    Ins,
+    Xclip(usize),
+    Yclip(usize),
+}
+
+/// The modes of alignment: Global, Semi-Global, Local and Custom with explicit clipping
+/// penalties (see `bio_types::alignment::AlignmentMode`).
+///
+/// The default alignment mode is Global.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Default)]
+pub enum AlignmentMode {
+    Local,
+    Semiglobal,
+    #[default]
+    Global,
+    Custom,
+}
+
+/// An alignment between the query or read sequence x and the reference or template sequence y
+/// (see `bio_types::alignment::Alignment`).
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Default)]
+pub struct Alignment {
+    /// Smith-Waterman alignment score
+    pub score: i32,
+
+    /// Start position of alignment in reference
+    pub ystart: usize,
+
+    /// Start position of alignment in query
+    pub xstart: usize,
+
+    /// End position of alignment in reference
+    pub yend: usize,
+
+    /// End position of alignment in query
+    pub xend: usize,
+
+    /// Length of the reference sequence
+    pub ylen: usize,
+
+    /// Length of the query sequence
+    pub xlen: usize,
+
+    /// Vector of alignment operations
+    pub operations: Vec<AlignmentOperation>,
+    pub mode: AlignmentMode,
+}
+
+impl Alignment {
+    /// Returns the optimal path in the alignment matrix
+    pub fn path(&self) -> Vec<(usize, usize, AlignmentOperation)> {
+        let mut path = Vec::new();
+
+        if !self.operations.is_empty() {
+            let (mut x_i, mut y_i) = match self.mode {
+                AlignmentMode::Custom => (self.xlen, self.ylen),
+                _ => (self.xend, self.yend),
+            };
+            for &op in self.operations.iter().rev() {
+                path.push((x_i, y_i, op));
+                match op {
+                    AlignmentOperation::Match | AlignmentOperation::Subst => {
+                        x_i -= 1;
+                        y_i -= 1;
+                    }
+                    AlignmentOperation::Del => y_i -= 1,
+                    AlignmentOperation::Ins => x_i -= 1,
+                    AlignmentOperation::Xclip(len) => x_i -= len,
+                    AlignmentOperation::Yclip(len) => y_i -= len,
+                }
+            }
+        }
+        path.reverse();
+        path
+    }
+
+    /// Filter out Xclip and Yclip operations from the list of operations. Useful
+    /// when invoking the standard modes.
+    pub fn filter_clip_operations(&mut self) {
+        self.operations.retain(|op| {
+            !matches!(
+                op,
+                AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_)
+            )
+        });
+    }
+
+    /// Number of bases in reference sequence that are aligned
+    pub fn y_aln_len(&self) -> usize {
+        self.yend - self.ystart
+    }
+
+    /// Number of bases in query sequence that are aligned
+    pub fn x_aln_len(&self) -> usize {
+        self.xend - self.xstart
+    }
+}
@@ -0,0 +1,489 @@
+//! Whole genome alignment of two sequences in the manner of MUMmer's `nucmer` (Kurtz et al.,
+//! 2004): maximal unique matches (MUMs) are found on both strands with a generalized suffix
+//! array, clustered into colinear chains, and the gaps between the anchors of each chain are
+//! filled by global alignment.
+//!
+//! The resulting alignments are reported like the records of MUMmer's delta format, with
+//! 1-based, inclusive coordinates (query coordinates descending for the reverse strand) and the
+//! positions of indels encoded as distances. Alignments are restricted to the span of their
+//! anchors, i.e. they are not extended beyond the first and last MUM.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::alignment::wga::GenomeAligner;
+//! use bio_types::strand::ReqStrand;
+//!
+//! let reference = b"ACGATCAGCTACGACTAGCATCGATTTACGACTTACAGCATCAGCTAGCATACG";
+//! // two substitutions and a deleted base
+//! let query = b"ACGATCAGCTACGACTAGCAACGATTTACGACTTACGCATCTGCTAGCATACG";
+//!
+//! let aligner = GenomeAligner::new().min_match(6).min_cluster(20);
+//! let alignments = aligner.align(reference, query);
+//! assert_eq!(alignments.len(), 1);
+//! let alignment = &alignments[0];
+//! assert_eq!(alignment.strand, ReqStrand::Forward);
+//! assert_eq!((alignment.ref_start, alignment.ref_end), (1, 54));
+//! assert_eq!((alignment.query_start, alignment.query_end), (1, 53));
+//! assert_eq!(alignment.errors, 3);
+//! assert_eq!(alignment.deltas, [37]);
+//! ```
+
+use std::fmt;
+
+use bio_types::strand::ReqStrand;
+
+use alignment::pairwise::Aligner;
+use alignment::AlignmentOperation;
+use alphabets::dna;
+use data_structures::suffix_array::suffix_array;
+
+/// A maximal unique match of `reference[ref_pos..ref_pos + len]` and the query (strand
+/// `Forward`) or its reverse complement (strand `Reverse`), starting at `query_pos` in the
+/// respective sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Anchor {
+    pub ref_pos: usize,
+    pub query_pos: usize,
+    pub len: usize,
+    pub strand: ReqStrand,
+}
+
+impl Anchor {
+    fn ref_end(&self) -> usize {
+        self.ref_pos + self.len
+    }
+
+    fn query_end(&self) -> usize {
+        self.query_pos + self.len
+    }
+}
+
+/// Normalize a sequence for matching: uppercase, with other bases than `A`, `C`, `G` and `T`
+/// replaced by the given symbol.
+fn normalize(seq: &[u8], other: u8) -> Vec<u8> {
+    seq.iter()
+        .map(|b| match b.to_ascii_uppercase() {
+            b @ b'A' | b @ b'C' | b @ b'G' | b @ b'T' => b,
+            _ => other,
+        })
+        .collect()
+}
+
+/// Maximal unique matches of at least the given length between the reference and the query on
+/// the given strand, ordered by reference position. Bases other than `A`, `C`, `G` and `T` never
+/// match.
+pub fn find_mums(reference: &[u8], query: &[u8], min_len: usize, strand: ReqStrand) -> Vec<Anchor> {
+    assert!(min_len > 0, "Expecting positive minimal match length.");
+    let query = match strand {
+        ReqStrand::Forward => query.to_vec(),
+        ReqStrand::Reverse => dna::revcomp(query),
+    };
+    // the reference and the query are separated by sentinels, and use different symbols for
+    // ambiguous bases
+    let mut text = normalize(reference, b'N');
+    text.push(b'$');
+    text.extend(normalize(&query, b'X'));
+    text.push(b'$');
+    let sa = suffix_array(&text);
+    let lcp = lcp(&text, &sa);
+
+    let offset = reference.len() + 1;
+    let mut mums = Vec::new();
+    for r in 1..text.len() {
+        let len = lcp[r];
+        // the match is unique in both sequences if it is not shared with the neighbours
+        if len < min_len || len <= lcp[r - 1] || len <= lcp[r + 1] {
+            continue;
+        }
+        let (a, b) = (sa[r - 1].min(sa[r]), sa[r - 1].max(sa[r]));
+        if a >= offset || b < offset {
+            continue;
+        }
+        // left maximal
+        if a > 0 && b > offset && text[a - 1] == text[b - 1] {
+            continue;
+        }
+        mums.push(Anchor {
+            ref_pos: a,
+            query_pos: b - offset,
+            len,
+            strand,
+        });
+    }
+    mums.sort();
+    mums
+}
+
+/// Longest common prefixes of suffixes adjacent in the suffix array (Kasai et al., 2001), not
+/// extending over sentinels. Entry `r` refers to suffixes `r - 1` and `r`, the first and last
+/// entry are 0.
+fn lcp(text: &[u8], sa: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut rank = vec![0; n];
+    for (r, &p) in sa.iter().enumerate() {
+        rank[p] = r;
+    }
+    let mut lcp = vec![0; n + 1];
+    let mut l = 0;
+    for p in 0..n {
+        let r = rank[p];
+        if r == 0 {
+            l = 0;
+            continue;
+        }
+        let q = sa[r - 1];
+        while p + l < n && q + l < n && text[p + l] == text[q + l] && text[p + l] != b'$' {
+            l += 1;
+        }
+        lcp[r] = l;
+        l = l.saturating_sub(1);
+    }
+    lcp
+}
+
+/// A gapped alignment of a region of the reference and the query, in the manner of a delta
+/// record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeltaAlignment {
+    /// Aligned range in the reference (1-based, inclusive).
+    pub ref_start: usize,
+    pub ref_end: usize,
+    /// Aligned range in the query (1-based, inclusive). For strand `Reverse`, `query_start` is
+    /// greater than `query_end`.
+    pub query_start: usize,
+    pub query_end: usize,
+    pub strand: ReqStrand,
+    /// Number of mismatches and indels.
+    pub errors: usize,
+    /// Distances between indels, counted in alignment columns from the previous indel
+    /// (inclusive). Positive values denote bases of the reference missing in the query, negative
+    /// values bases of the query missing in the reference.
+    pub deltas: Vec<i64>,
+    /// The anchors the alignment is based on.
+    pub anchors: Vec<Anchor>,
+}
+
+impl DeltaAlignment {
+    /// Identity of the alignment, i.e. the fraction of error-free columns.
+    pub fn identity(&self) -> f64 {
+        let columns =
+            self.ref_end + 1 - self.ref_start + self.deltas.iter().filter(|&&d| d < 0).count();
+        1.0 - self.errors as f64 / columns as f64
+    }
+}
+
+impl fmt::Display for DeltaAlignment {
+    /// Format as in the delta format, with the number of similarity errors set to the number of
+    /// errors and no stop codons.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} {} {} {} {} {} 0",
+            self.ref_start,
+            self.ref_end,
+            self.query_start,
+            self.query_end,
+            self.errors,
+            self.errors
+        )?;
+        for delta in &self.deltas {
+            writeln!(f, "{}", delta)?;
+        }
+        writeln!(f, "0")
+    }
+}
+
+/// Aligner of whole genomes (or large sequences) based on maximal unique matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenomeAligner {
+    min_match: usize,
+    max_gap: usize,
+    min_cluster: usize,
+}
+
+impl Default for GenomeAligner {
+    fn default() -> Self {
+        GenomeAligner {
+            min_match: 20,
+            max_gap: 90,
+            min_cluster: 65,
+        }
+    }
+}
+
+impl GenomeAligner {
+    /// Create a new aligner with the defaults of `nucmer`: MUMs of at least 20 bases, at most 90
+    /// bases between the anchors of a chain, and chains covering at least 65 bases with anchors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimal length of MUMs.
+    pub fn min_match(mut self, min_match: usize) -> Self {
+        assert!(min_match > 0, "Expecting positive minimal match length.");
+        self.min_match = min_match;
+        self
+    }
+
+    /// Set the maximal distance between adjacent anchors of a chain, in both sequences.
+    pub fn max_gap(mut self, max_gap: usize) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Set the minimal total length of the anchors of a chain.
+    pub fn min_cluster(mut self, min_cluster: usize) -> Self {
+        self.min_cluster = min_cluster;
+        self
+    }
+
+    /// MUMs of the reference and the query on both strands.
+    pub fn anchors(&self, reference: &[u8], query: &[u8]) -> Vec<Anchor> {
+        let mut anchors = find_mums(reference, query, self.min_match, ReqStrand::Forward);
+        anchors.extend(find_mums(
+            reference,
+            query,
+            self.min_match,
+            ReqStrand::Reverse,
+        ));
+        anchors
+    }
+
+    /// Cluster the anchors into colinear chains of the same strand. Chains are extracted
+    /// greedily, the one with the highest score (the anchored length minus the shifts between
+    /// the diagonals of adjacent anchors) first. Anchors overlapping their predecessor in either
+    /// sequence are not chained. Chains are ordered by strand and reference position.
+    pub fn chain(&self, anchors: &[Anchor]) -> Vec<Vec<Anchor>> {
+        let mut chains = Vec::new();
+        for &strand in &[ReqStrand::Forward, ReqStrand::Reverse] {
+            let mut anchors: Vec<_> = anchors
+                .iter()
+                .filter(|a| a.strand == strand)
+                .cloned()
+                .collect();
+            anchors.sort();
+            while let Some(chain) = self.best_chain(&anchors) {
+                anchors.retain(|a| !chain.contains(a));
+                chains.push(chain);
+            }
+        }
+        chains.sort_by_key(|chain| (chain[0].strand, chain[0].ref_pos, chain[0].query_pos));
+        chains
+    }
+
+    /// The highest scoring chain of the sorted anchors, if it covers enough bases.
+    fn best_chain(&self, anchors: &[Anchor]) -> Option<Vec<Anchor>> {
+        let mut scores: Vec<i64> = Vec::with_capacity(anchors.len());
+        let mut preds: Vec<Option<usize>> = Vec::with_capacity(anchors.len());
+        for (i, anchor) in anchors.iter().enumerate() {
+            let mut best = (anchor.len as i64, None);
+            for (j, prev) in anchors[..i].iter().enumerate() {
+                if prev.ref_end() > anchor.ref_pos || prev.query_end() > anchor.query_pos {
+                    continue;
+                }
+                let ref_gap = anchor.ref_pos - prev.ref_end();
+                let query_gap = anchor.query_pos - prev.query_end();
+                if ref_gap > self.max_gap || query_gap > self.max_gap {
+                    continue;
+                }
+                let shift = (ref_gap as i64 - query_gap as i64).abs();
+                let score = scores[j] + anchor.len as i64 - shift;
+                if score > best.0 {
+                    best = (score, Some(j));
+                }
+            }
+            scores.push(best.0);
+            preds.push(best.1);
+        }
+
+        let (mut i, _) = scores
+            .iter()
+            .enumerate()
+            .max_by_key(|&(i, &s)| (s, -(i as i64)))?;
+        let mut chain = vec![anchors[i]];
+        while let Some(j) = preds[i] {
+            chain.push(anchors[j]);
+            i = j;
+        }
+        chain.reverse();
+        if chain.iter().map(|a| a.len).sum::<usize>() >= self.min_cluster {
+            Some(chain)
+        } else {
+            None
+        }
+    }
+
+    /// Align the query against the reference, returning one alignment per chain of anchors.
+    pub fn align(&self, reference: &[u8], query: &[u8]) -> Vec<DeltaAlignment> {
+        let reverse_query = dna::revcomp(query);
+        let reference = reference.to_ascii_uppercase();
+        let chains = self.chain(&self.anchors(&reference, query));
+        chains
+            .into_iter()
+            .map(|chain| {
+                let query = match chain[0].strand {
+                    ReqStrand::Forward => query.to_ascii_uppercase(),
+                    ReqStrand::Reverse => reverse_query.to_ascii_uppercase(),
+                };
+                Self::fill(&reference, &query, chain)
+            })
+            .collect()
+    }
+
+    /// Fill the gaps between the anchors of a chain by global alignment.
+    fn fill(reference: &[u8], query: &[u8], chain: Vec<Anchor>) -> DeltaAlignment {
+        let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1i32 } else { -1i32 });
+        // alignment columns as operations, the query being x and the reference y
+        let mut operations = Vec::new();
+        for (i, anchor) in chain.iter().enumerate() {
+            if i > 0 {
+                let prev = &chain[i - 1];
+                let x = &query[prev.query_end()..anchor.query_pos];
+                let y = &reference[prev.ref_end()..anchor.ref_pos];
+                if x.is_empty() || y.is_empty() {
+                    operations.extend(x.iter().map(|_| AlignmentOperation::Ins));
+                    operations.extend(y.iter().map(|_| AlignmentOperation::Del));
+                } else {
+                    operations.extend(aligner.global(x, y).operations);
+                }
+            }
+            operations.extend((0..anchor.len).map(|_| AlignmentOperation::Match));
+        }
+
+        let mut errors = 0;
+        let mut deltas = Vec::new();
+        let mut distance = 0;
+        for op in &operations {
+            distance += 1;
+            match *op {
+                AlignmentOperation::Subst => errors += 1,
+                AlignmentOperation::Del => {
+                    errors += 1;
+                    deltas.push(distance);
+                    distance = 0;
+                }
+                AlignmentOperation::Ins => {
+                    errors += 1;
+                    deltas.push(-distance);
+                    distance = 0;
+                }
+                _ => (),
+            }
+        }
+
+        let (first, last) = (chain[0], chain[chain.len() - 1]);
+        let (query_start, query_end) = match first.strand {
+            ReqStrand::Forward => (first.query_pos + 1, last.query_end()),
+            ReqStrand::Reverse => (
+                query.len() - first.query_pos,
+                query.len() + 1 - last.query_end(),
+            ),
+        };
+        DeltaAlignment {
+            ref_start: first.ref_pos + 1,
+            ref_end: last.ref_end(),
+            query_start,
+            query_end,
+            strand: first.strand,
+            errors,
+            deltas,
+            anchors: chain,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFERENCE: &[u8] =
+        b"TGACTGATCGATCGGCTAGCTAGCTACGATCGACGTACGTAGCTAGCTAGCGGATCCGATCGACTAGG";
+
+    #[test]
+    fn test_find_mums() {
+        let reference = b"ACGTTGCAAGGCTT";
+        let query = b"ACGTTGCTTAAGGC";
+        let mums = find_mums(reference, query, 3, ReqStrand::Forward);
+        let found: Vec<_> = mums
+            .iter()
+            .map(|m| (m.ref_pos, m.query_pos, m.len))
+            .collect();
+        assert_eq!(found, [(0, 0, 7), (7, 9, 5), (10, 5, 4)]);
+
+        // matches occurring twice in the reference are not unique
+        let mums = find_mums(b"ACGTACGTAC", b"ACGTA", 3, ReqStrand::Forward);
+        assert!(mums.iter().all(|m| m.len < 5));
+        // ambiguous bases do not match
+        assert!(find_mums(b"NNNNNN", b"NNNNNN", 3, ReqStrand::Forward).is_empty());
+        assert!(find_mums(b"", b"ACGT", 1, ReqStrand::Forward).is_empty());
+
+        let query = dna::revcomp(&REFERENCE[10..40]);
+        let mums = find_mums(REFERENCE, &query, 10, ReqStrand::Reverse);
+        assert_eq!(
+            mums,
+            [Anchor {
+                ref_pos: 10,
+                query_pos: 0,
+                len: 30,
+                strand: ReqStrand::Reverse
+            }]
+        );
+    }
+
+    #[test]
+    fn test_align_reverse() {
+        let mut query = REFERENCE[5..60].to_vec();
+        query[25] = if query[25] == b'A' { b'C' } else { b'A' };
+        query.insert(40, b'T');
+        let query = dna::revcomp(&query);
+        let aligner = GenomeAligner::new().min_match(10).min_cluster(30);
+        let alignments = aligner.align(REFERENCE, &query);
+        assert_eq!(alignments.len(), 1);
+        let alignment = &alignments[0];
+        assert_eq!(alignment.strand, ReqStrand::Reverse);
+        assert_eq!((alignment.ref_start, alignment.ref_end), (6, 60));
+        assert_eq!((alignment.query_start, alignment.query_end), (56, 1));
+        assert_eq!(alignment.errors, 2);
+        assert_eq!(alignment.deltas.len(), 1);
+        assert!(alignment.deltas[0] < 0);
+        assert_relative_eq!(alignment.identity(), 54.0 / 56.0);
+        let delta = alignment.to_string();
+        assert!(delta.starts_with("6 60 56 1 2 2 0\n"));
+        assert!(delta.ends_with("\n0\n"));
+    }
+
+    #[test]
+    fn test_chain() {
+        // two copies of a segment, in swapped order
+        let mut query = REFERENCE[35..].to_vec();
+        query.extend_from_slice(b"AAAAAAAAAAAAAAAAAAAAAAAAA");
+        query.extend_from_slice(&REFERENCE[..35]);
+        let aligner = GenomeAligner::new().min_match(10).min_cluster(20);
+        let chains = aligner.chain(&aligner.anchors(REFERENCE, &query));
+        assert_eq!(chains.len(), 2);
+        assert_eq!((chains[0][0].ref_pos, chains[0][0].query_pos), (0, 58));
+        assert_eq!((chains[1][0].ref_pos, chains[1][0].query_pos), (35, 0));
+
+        // anchors too far apart are not chained
+        let aligner = aligner.max_gap(0);
+        let anchors = [
+            Anchor {
+                ref_pos: 0,
+                query_pos: 0,
+                len: 15,
+                strand: ReqStrand::Forward,
+            },
+            Anchor {
+                ref_pos: 20,
+                query_pos: 20,
+                len: 15,
+                strand: ReqStrand::Forward,
+            },
+        ];
+        assert!(aligner.chain(&anchors).is_empty());
+        assert_eq!(aligner.max_gap(5).chain(&anchors).len(), 1);
+    }
+}
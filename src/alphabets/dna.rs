@@ -18,6 +18,7 @@
 use std::borrow::Borrow;
 
 use alphabets::Alphabet;
+use bio_types::strand::ReqStrand;
 
 /// The DNA alphabet (uppercase and lowercase).
 pub fn alphabet() -> Alphabet {
@@ -66,3 +67,248 @@ where
         .map(|a| complement(*a.borrow()))
         .collect()
 }
+
+/// Return the bases (uppercase) matched by the given IUPAC DNA symbol (case insensitive), or
+/// `None` if the symbol is not an IUPAC nucleotide code.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna::iupac_bases;
+///
+/// assert_eq!(iupac_bases(b'R'), Some(&b"AG"[..]));
+/// assert_eq!(iupac_bases(b'n'), Some(&b"ACGT"[..]));
+/// assert_eq!(iupac_bases(b'$'), None);
+/// ```
+pub fn iupac_bases(a: u8) -> Option<&'static [u8]> {
+    let bases: &[u8] = match a.to_ascii_uppercase() {
+        b'A' => b"A",
+        b'C' => b"C",
+        b'G' => b"G",
+        b'T' => b"T",
+        b'R' => b"AG",
+        b'Y' => b"CT",
+        b'S' => b"CG",
+        b'W' => b"AT",
+        b'K' => b"GT",
+        b'M' => b"AC",
+        b'B' => b"CGT",
+        b'D' => b"AGT",
+        b'H' => b"ACT",
+        b'V' => b"ACG",
+        b'N' => b"ACGT",
+        _ => return None,
+    };
+    Some(bases)
+}
+
+/// Orientation of a sequence relative to a reference, e.g. of an overlap or a mapping.
+/// This is used instead of plain booleans for denoting whether the reverse complement is meant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Orientation {
+    Forward,
+    Reverse,
+}
+
+impl Orientation {
+    /// Return the opposite orientation.
+    pub fn flip(self) -> Orientation {
+        match self {
+            Orientation::Forward => Orientation::Reverse,
+            Orientation::Reverse => Orientation::Forward,
+        }
+    }
+
+    /// Check if this is the forward orientation.
+    pub fn is_forward(self) -> bool {
+        self == Orientation::Forward
+    }
+
+    /// Check if this is the reverse orientation.
+    pub fn is_reverse(self) -> bool {
+        self == Orientation::Reverse
+    }
+
+    /// Compose two relative orientations, e.g. the orientation of a read relative to a contig
+    /// and that of the contig relative to a reference.
+    pub fn then(self, other: Orientation) -> Orientation {
+        if self == other {
+            Orientation::Forward
+        } else {
+            Orientation::Reverse
+        }
+    }
+
+    /// Return the given sequence in this orientation, i.e. its reverse complement
+    /// if the orientation is reverse.
+    pub fn apply(self, text: &[u8]) -> Vec<u8> {
+        match self {
+            Orientation::Forward => text.to_vec(),
+            Orientation::Reverse => revcomp(text),
+        }
+    }
+}
+
+impl From<ReqStrand> for Orientation {
+    fn from(strand: ReqStrand) -> Self {
+        match strand {
+            ReqStrand::Forward => Orientation::Forward,
+            ReqStrand::Reverse => Orientation::Reverse,
+        }
+    }
+}
+
+impl From<Orientation> for ReqStrand {
+    fn from(orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Forward => ReqStrand::Forward,
+            Orientation::Reverse => ReqStrand::Reverse,
+        }
+    }
+}
+
+/// Return the orientation in which the given sequence is lexicographically smallest when
+/// compared to its reverse complement, without allocating the reverse complement.
+/// Palindromic sequences are considered forward.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna::{canonical_orientation, Orientation};
+///
+/// assert_eq!(canonical_orientation(b"ACGA"), Orientation::Forward);
+/// assert_eq!(canonical_orientation(b"TCGT"), Orientation::Reverse);
+/// ```
+pub fn canonical_orientation(text: &[u8]) -> Orientation {
+    let rc = text.iter().rev().map(|&a| complement(a));
+    for (&a, b) in text.iter().zip(rc) {
+        if a < b {
+            return Orientation::Forward;
+        } else if b < a {
+            return Orientation::Reverse;
+        }
+    }
+    Orientation::Forward
+}
+
+/// Return the canonical form of the given sequence (the lexicographically smaller one of the
+/// sequence and its reverse complement) together with the orientation that was chosen.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna::{canonical, Orientation};
+///
+/// let (seq, orientation) = canonical(b"TTGC");
+/// assert_eq!(seq, b"GCAA");
+/// assert_eq!(orientation, Orientation::Reverse);
+/// ```
+pub fn canonical(text: &[u8]) -> (Vec<u8>, Orientation) {
+    let orientation = canonical_orientation(text);
+    (orientation.apply(text), orientation)
+}
+
+/// Iterator over canonical k-mers, returned by `canonical_kmers`.
+pub struct CanonicalKmers<'a> {
+    text: &'a [u8],
+    k: usize,
+    mask: u64,
+    fwd: u64,
+    rev: u64,
+    valid: usize,
+    pos: usize,
+}
+
+/// Iterate over the 2-bit encoded (A=0, C=1, G=2, T=3) canonical k-mers of the given text,
+/// i.e. the smaller encoding of each k-mer and its reverse complement. K-mers containing other
+/// symbols than `ACGT` (case insensitive) are skipped. `k` must be between 1 and 32.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna::canonical_kmers;
+///
+/// let kmers: Vec<u64> = canonical_kmers(b"ACGNTTG", 2).collect();
+/// // AC, CG, TT (as AA), TG (as CA)
+/// assert_eq!(kmers, vec![0b0001, 0b0110, 0b0000, 0b0100]);
+/// ```
+pub fn canonical_kmers(text: &[u8], k: usize) -> CanonicalKmers<'_> {
+    assert!(k > 0 && k <= 32, "Expecting k between 1 and 32.");
+    CanonicalKmers {
+        text,
+        k,
+        mask: if k == 32 { !0 } else { (1 << (2 * k)) - 1 },
+        fwd: 0,
+        rev: 0,
+        valid: 0,
+        pos: 0,
+    }
+}
+
+impl<'a> Iterator for CanonicalKmers<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while self.pos < self.text.len() {
+            let code = match self.text[self.pos] {
+                b'A' | b'a' => 0,
+                b'C' | b'c' => 1,
+                b'G' | b'g' => 2,
+                b'T' | b't' => 3,
+                _ => 4,
+            };
+            self.pos += 1;
+            if code == 4 {
+                self.valid = 0;
+                continue;
+            }
+            self.fwd = ((self.fwd << 2) | code) & self.mask;
+            self.rev = (self.rev >> 2) | ((3 - code) << (2 * (self.k - 1)));
+            self.valid += 1;
+            if self.valid >= self.k {
+                return Some(self.fwd.min(self.rev));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_kmers() {
+        let text = b"ACGTNACGGTA";
+        let fwd: Vec<u64> = canonical_kmers(text, 3).collect();
+        let rev: Vec<u64> = canonical_kmers(&revcomp(&text[..]), 3).collect();
+        // k-mers spanning the N are skipped
+        assert_eq!(fwd.len(), 6);
+        assert_eq!(fwd, rev.into_iter().rev().collect::<Vec<_>>());
+        assert_eq!(canonical_kmers(b"acg", 3).next(), Some(0b00_01_10));
+        assert_eq!(canonical_kmers(&[b'A'; 40], 32).count(), 9);
+    }
+
+    #[test]
+    fn test_canonical() {
+        for text in [&b"ACGTTGCA"[..], b"GGGA", b"tcca", b"ACGT"].iter() {
+            let (canon, orientation) = canonical(text);
+            let rc = revcomp(*text);
+            assert_eq!(canon, ::std::cmp::min(text.to_vec(), rc.clone()));
+            // both strands share the same canonical form
+            assert_eq!(canonical(&rc).0, canon);
+            assert_eq!(orientation.apply(text), canon);
+        }
+        assert_eq!(canonical_orientation(b"ACGT"), Orientation::Forward);
+    }
+
+    #[test]
+    fn test_orientation() {
+        let fwd = Orientation::Forward;
+        assert_eq!(fwd.flip(), Orientation::Reverse);
+        assert_eq!(fwd.flip().then(Orientation::Reverse), Orientation::Forward);
+        assert_eq!(fwd.then(Orientation::Reverse), Orientation::Reverse);
+        assert_eq!(ReqStrand::from(fwd.flip()), ReqStrand::Reverse);
+        assert_eq!(Orientation::from(ReqStrand::Forward), fwd);
+    }
+}
@@ -0,0 +1,214 @@
+//! FM-Index for mapping bisulfite converted reads in a reduced (three-letter) alphabet.
+//!
+//! Bisulfite conversion turns unmethylated cytosines into thymines, so that reads generally do
+//! not match the reference exactly. Instead, two FM-Indexes are built over in-silico converted
+//! copies of the reference: one with all `C`s converted to `T`s (the original forward strand),
+//! one with all `G`s converted to `A`s (the reverse strand, in forward coordinates). Reads are
+//! converted the same way on the fly before searching, which makes the matching insensitive to
+//! methylation.
+//!
+//! Reads are expected to come from a directional library, i.e. from the original forward or
+//! reverse strand. Hits are reported in original (forward strand, 0-based) coordinates of the
+//! reference sequences, together with the strand the read originates from.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::alphabets::dna::revcomp;
+//! use bio::data_structures::bisulfite_index::{BisulfiteHit, BisulfiteIndex};
+//! use bio::seq_analysis::bisulfite::convert;
+//! use bio_types::strand::ReqStrand;
+//!
+//! let chr1 = b"ACGTTCGAACCATGCA";
+//! let index = BisulfiteIndex::new(&[("chr1", &chr1[..])]);
+//!
+//! // a read of the forward strand with the CpG at 5 methylated
+//! let read = b"TTCGAATTAT";
+//! let hits = index.search(read);
+//! assert_eq!(hits, [BisulfiteHit { contig: 0, pos: 3, strand: ReqStrand::Forward }]);
+//! assert_eq!(index.name(hits[0].contig), "chr1");
+//!
+//! // an unmethylated read of the reverse strand
+//! let read = revcomp(convert(&chr1[6..14], ReqStrand::Reverse));
+//! let hits = index.search(&read);
+//! assert_eq!(hits, [BisulfiteHit { contig: 0, pos: 6, strand: ReqStrand::Reverse }]);
+//! ```
+
+use bio_types::strand::ReqStrand;
+
+use alphabets::dna;
+use data_structures::bwt::{bwt, less, Less, Occ, BWT};
+use data_structures::fmindex::{FMIndex, FMIndexable};
+use data_structures::suffix_array::{suffix_array, RawSuffixArray};
+use seq_analysis::bisulfite::convert;
+
+/// Sampling rate of the occurrence arrays.
+const OCC_SAMPLING: u32 = 32;
+
+/// An exact match of a converted read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BisulfiteHit {
+    /// Index of the reference sequence.
+    pub contig: usize,
+    /// Leftmost position of the match on the forward strand.
+    pub pos: usize,
+    /// Strand the read originates from.
+    pub strand: ReqStrand,
+}
+
+/// FM-Index over one converted strand of the concatenated reference sequences.
+#[derive(Serialize, Deserialize)]
+struct ConvertedIndex {
+    fmindex: FMIndex<BWT, Less, Occ>,
+    sa: RawSuffixArray,
+}
+
+impl ConvertedIndex {
+    fn new(text: &[u8], strand: ReqStrand) -> Self {
+        let text = convert(text, strand);
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, OCC_SAMPLING, &alphabet);
+        ConvertedIndex {
+            fmindex: FMIndex::new(bwt, less, occ),
+            sa,
+        }
+    }
+
+    /// Positions in the concatenated text of the exact occurrences of the given pattern.
+    fn occurrences(&self, pattern: &[u8]) -> Vec<usize> {
+        self.fmindex.backward_search(pattern.iter()).occ(&self.sa)
+    }
+}
+
+/// Bisulfite FM-Index over a set of reference sequences.
+#[derive(Serialize, Deserialize)]
+pub struct BisulfiteIndex {
+    names: Vec<String>,
+    /// Start of each sequence in the concatenated text.
+    offsets: Vec<usize>,
+    forward: ConvertedIndex,
+    reverse: ConvertedIndex,
+}
+
+impl BisulfiteIndex {
+    /// Build the index for the given pairs of sequence names and sequences. Bases other than
+    /// `A`, `C`, `G` and `T` (in any case) are treated as `N`.
+    pub fn new(sequences: &[(&str, &[u8])]) -> Self {
+        let mut text = Vec::new();
+        let mut names = Vec::with_capacity(sequences.len());
+        let mut offsets = Vec::with_capacity(sequences.len());
+        for &(name, seq) in sequences {
+            names.push(name.to_owned());
+            offsets.push(text.len());
+            text.extend(seq.iter().map(|b| match b.to_ascii_uppercase() {
+                b @ b'A' | b @ b'C' | b @ b'G' | b @ b'T' => b,
+                _ => b'N',
+            }));
+            text.push(b'$');
+        }
+        if text.is_empty() {
+            text.push(b'$');
+        }
+        BisulfiteIndex {
+            names,
+            offsets,
+            forward: ConvertedIndex::new(&text, ReqStrand::Forward),
+            reverse: ConvertedIndex::new(&text, ReqStrand::Reverse),
+        }
+    }
+
+    /// Name of the sequence with the given index.
+    pub fn name(&self, contig: usize) -> &str {
+        &self.names[contig]
+    }
+
+    /// All exact matches of the given read after bisulfite conversion, on both strands, ordered
+    /// by sequence, position and strand. The read is given as sequenced, i.e. reads of the
+    /// reverse strand are reverse complementary to the reference.
+    pub fn search(&self, read: &[u8]) -> Vec<BisulfiteHit> {
+        if read.is_empty() {
+            return Vec::new();
+        }
+        let read = read.to_ascii_uppercase();
+        let reverse_read = dna::revcomp(&read);
+        let mut hits: Vec<_> = self
+            .forward
+            .occurrences(&convert(&read, ReqStrand::Forward))
+            .into_iter()
+            .map(|pos| self.hit(pos, ReqStrand::Forward))
+            .chain(
+                self.reverse
+                    .occurrences(&convert(&reverse_read, ReqStrand::Reverse))
+                    .into_iter()
+                    .map(|pos| self.hit(pos, ReqStrand::Reverse)),
+            )
+            .collect();
+        hits.sort();
+        hits
+    }
+
+    fn hit(&self, pos: usize, strand: ReqStrand) -> BisulfiteHit {
+        let contig = self.offsets.partition_point(|&offset| offset <= pos) - 1;
+        BisulfiteHit {
+            contig,
+            pos: pos - self.offsets[contig],
+            strand,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let chr1 = b"ACGTTCGAACCATGCA";
+        let chr2 = b"nnCCGGTTACG";
+        let index = BisulfiteIndex::new(&[("chr1", &chr1[..]), ("chr2", &chr2[..])]);
+        assert_eq!(index.name(1), "chr2");
+
+        // fully methylated and fully unmethylated forward reads
+        for read in [&b"CCGGTTAC"[..], b"TTGGTTAT", b"ttggttat"] {
+            assert_eq!(
+                index.search(read),
+                [BisulfiteHit {
+                    contig: 1,
+                    pos: 2,
+                    strand: ReqStrand::Forward
+                }]
+            );
+        }
+        // reverse strand reads, methylated (the reverse complement) and unmethylated
+        let unmethylated = dna::revcomp(convert(&chr2[3..11], ReqStrand::Reverse));
+        for read in [dna::revcomp(&chr2[3..11]), unmethylated] {
+            assert_eq!(
+                index.search(&read),
+                [BisulfiteHit {
+                    contig: 1,
+                    pos: 3,
+                    strand: ReqStrand::Reverse
+                }]
+            );
+        }
+        // no matches across sequences or on the wrong strand
+        assert!(index.search(b"GCAnnCC").is_empty());
+        assert!(index.search(b"CATGCAT").is_empty());
+        assert!(index.search(b"").is_empty());
+        // multiple hits
+        let hits = index.search(b"TTA");
+        assert!(hits.len() > 1);
+        assert!(hits.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_empty() {
+        let index = BisulfiteIndex::new(&[]);
+        assert!(index.search(b"ACGT").is_empty());
+    }
+}
@@ -24,9 +24,10 @@
 //! assert_eq!(bit.get(3), (4, 3));
 //! assert_eq!(bit.get(4), (4, 3));
 
-use std::cmp::max;
-use std::marker::PhantomData;
-use std::ops::Add;
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::marker::PhantomData;
+use core::ops::{Add, Sub};
 
 /// Fenwick tree prefix operator
 pub trait PrefixOp<T> {
@@ -84,6 +85,26 @@ impl<T: Ord + Default + Copy, Op: PrefixOp<T>> FenwickTree<T, Op> {
             idx += (idx as isize & -(idx as isize)) as usize;
         }
     }
+
+    /// Combine the value at position idx with val using the prefix operation
+    /// (e.g. increment it by val for a prefix-sum tree).
+    pub fn add(&mut self, idx: usize, val: T) {
+        let mut idx = idx + 1;
+        while idx < self.tree.len() {
+            self.tree[idx] = Op::operation(self.tree[idx], val);
+            idx += (idx as isize & -(idx as isize)) as usize;
+        }
+    }
+
+    /// Number of positions in the tree.
+    pub fn len(&self) -> usize {
+        self.tree.len() - 2
+    }
+
+    /// Check whether the tree has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 pub struct MaxOp;
@@ -109,9 +130,47 @@ where
 /// Fenwick tree specialized for prefix-sum
 pub type SumBitTree<T> = FenwickTree<T, SumOp>;
 
+impl<T: Ord + Default + Copy + Add<Output = T> + Sub<Output = T>> FenwickTree<T, SumOp> {
+    /// Sum of the values in the inclusive range [start, end].
+    pub fn range_sum(&self, start: usize, end: usize) -> T {
+        if start == 0 {
+            self.get(end)
+        } else {
+            self.get(end) - self.get(start - 1)
+        }
+    }
+
+    /// Return the smallest position i with get(i) >= target, or None if the total sum is smaller.
+    /// With non-negative values, this answers order statistic queries: if each position holds
+    /// the multiplicity of a key, the result is the key with the given rank.
+    /// Complexity: O(log n).
+    pub fn lower_bound(&self, target: T) -> Option<usize> {
+        if self.tree.len() < 2 {
+            return None;
+        }
+        let n = self.tree.len() - 1;
+        let mut pos = 0;
+        let mut rest = target;
+        let mut step = n.next_power_of_two();
+        while step > 0 {
+            let next = pos + step;
+            if next <= n && self.tree[next] < rest {
+                pos = next;
+                rest = rest - self.tree[next];
+            }
+            step >>= 1;
+        }
+        if pos < self.len() {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod test_bit_tree {
-    use super::MaxBitTree;
+    use super::{MaxBitTree, SumBitTree};
 
     #[test]
     pub fn test_bit_tree() {
@@ -135,4 +194,29 @@ mod test_bit_tree {
         assert_eq!(bit.get(6), (4, 6));
         assert_eq!(bit.get(7), (5, 7));
     }
+
+    #[test]
+    pub fn test_sum_bit_tree() {
+        let mut bit = SumBitTree::new(8);
+        let counts = [3, 0, 1, 4, 0, 0, 2, 1];
+        for (i, &c) in counts.iter().enumerate() {
+            bit.add(i, c);
+        }
+        assert_eq!(bit.len(), 8);
+        assert_eq!(bit.get(3), 8);
+        assert_eq!(bit.get(7), 11);
+        assert_eq!(bit.range_sum(2, 6), 7);
+        assert_eq!(bit.range_sum(0, 0), 3);
+
+        assert_eq!(bit.lower_bound(1), Some(0));
+        assert_eq!(bit.lower_bound(4), Some(2));
+        assert_eq!(bit.lower_bound(5), Some(3));
+        assert_eq!(bit.lower_bound(9), Some(6));
+        assert_eq!(bit.lower_bound(11), Some(7));
+        assert_eq!(bit.lower_bound(12), None);
+
+        bit.add(1, 5);
+        assert_eq!(bit.range_sum(1, 1), 5);
+        assert_eq!(bit.lower_bound(4), Some(1));
+    }
 }
@@ -7,12 +7,14 @@
 //! The implementation is based on the lecture notes
 //! "Algorithmen auf Sequenzen", Kopczynski, Marschall, Martin and Rahmann, 2008 - 2015.
 
+use std::io;
 use std::iter::repeat;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use alphabets::Alphabet;
-use bytecount;
-use data_structures::suffix_array::RawSuffixArray;
-use utils::prescan;
+use utils::{prescan, IndexableText, SizedInt};
+use vec_map::VecMap;
 
 pub type BWT = Vec<u8>;
 pub type BWTSlice = [u8];
@@ -37,13 +39,17 @@ pub type BWTFind = Vec<usize>;
 /// let bwt = bwt(text, &pos);
 /// assert_eq!(bwt, b"ATTATTCAGGACCC$CTTTCAA");
 /// ```
-pub fn bwt(text: &[u8], pos: &RawSuffixArray) -> BWT {
+pub fn bwt<T: IndexableText + ?Sized, I: SizedInt>(text: &T, pos: &[I]) -> BWT {
     assert_eq!(text.len(), pos.len());
     let n = text.len();
     let mut bwt: BWT = repeat(0).take(n).collect();
     for r in 0..n {
-        let p = pos[r];
-        bwt[r] = if p > 0 { text[p - 1] } else { text[n - 1] };
+        let p = pos[r].to_usize();
+        bwt[r] = if p > 0 {
+            text.symbol(p - 1)
+        } else {
+            text.symbol(n - 1)
+        };
     }
 
     bwt
@@ -73,84 +79,598 @@ pub fn invert_bwt(bwt: &BWTSlice) -> Vec<u8> {
     inverse
 }
 
-/// An occurrence array implementation.
+/// Storage of a BWT, e.g. an owned `BWT`, a reference to it, or a slice of a memory mapped
+/// file (see `MappedOcc`).
+pub trait BWTStorage {
+    fn as_bwt(&self) -> &BWTSlice;
+}
+
+impl BWTStorage for BWT {
+    fn as_bwt(&self) -> &BWTSlice {
+        self
+    }
+}
+
+impl BWTStorage for BWTSlice {
+    fn as_bwt(&self) -> &BWTSlice {
+        self
+    }
+}
+
+impl<T: BWTStorage + ?Sized> BWTStorage for &T {
+    fn as_bwt(&self) -> &BWTSlice {
+        (**self).as_bwt()
+    }
+}
+
+impl<T: BWTStorage + ?Sized> BWTStorage for Box<T> {
+    fn as_bwt(&self) -> &BWTSlice {
+        (**self).as_bwt()
+    }
+}
+
+impl<T: BWTStorage + ?Sized> BWTStorage for Rc<T> {
+    fn as_bwt(&self) -> &BWTSlice {
+        (**self).as_bwt()
+    }
+}
+
+impl<T: BWTStorage + ?Sized> BWTStorage for Arc<T> {
+    fn as_bwt(&self) -> &BWTSlice {
+        (**self).as_bwt()
+    }
+}
+
+/// Data structures counting the occurrences of symbols in prefixes of a BWT, as needed by
+/// FM-Index searches: the sampled occ array `Occ`, or a `WaveletTree` of the BWT.
+pub trait OccBackend {
+    /// Get occurrence count of symbol a in BWT[..r+1].
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize;
+}
+
+impl<T: OccBackend + ?Sized> OccBackend for &T {
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        (**self).occ(bwt, r, a)
+    }
+}
+
+impl<T: OccBackend + ?Sized> OccBackend for Box<T> {
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        (**self).occ(bwt, r, a)
+    }
+}
+
+impl<T: OccBackend + ?Sized> OccBackend for Rc<T> {
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        (**self).occ(bwt, r, a)
+    }
+}
+
+impl<T: OccBackend + ?Sized> OccBackend for Arc<T> {
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        (**self).occ(bwt, r, a)
+    }
+}
+
+impl OccBackend for Occ {
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        self.get(bwt, r, a)
+    }
+}
+
+impl<'a> OccBackend for MappedOcc<'a> {
+    fn occ(&self, _bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        self.get(r, a)
+    }
+}
+
+/// Maximal number of rows per superblock of the occ array. Block counts are stored relative to
+/// their superblock, so a superblock must not span more than 2^16 rows.
+const SUPERBLOCK_ROWS: usize = 1 << 16;
+
+/// Number of rows per superblock for the sampling rate k: the largest multiple of k of at most
+/// 2^16 rows, or k itself for larger sampling rates (with all block counts being zero).
+fn superblock_rows(k: u32) -> usize {
+    k as usize * (SUPERBLOCK_ROWS / k as usize).max(1)
+}
+
+/// Number of bits of the codes of the given number of symbols.
+fn code_width(symbols: usize) -> u32 {
+    (usize::BITS - symbols.saturating_sub(1).leading_zeros()).max(1)
+}
+
+/// Number of 64 bit words of n packed codes of the given width.
+fn code_words(n: usize, width: u32) -> usize {
+    n.div_ceil((64 / width) as usize)
+}
+
+/// Count the codes equal to c in the rows [start, end) of the packed codes, where `word` returns
+/// the i-th 64 bit word of codes of the given width.
+fn count_code<F: Fn(usize) -> u64>(word: F, width: u32, start: usize, end: usize, c: u64) -> u64 {
+    let per_word = (64 / width) as usize;
+    // the lowest bit of each code
+    let low = (0..per_word).fold(0u64, |low, i| low | 1 << (i as u32 * width));
+    let pattern = c * low;
+    let mut count = 0;
+    let mut r = start;
+    while r < end {
+        let w = r / per_word;
+        let (from, to) = (r % per_word, (end - w * per_word).min(per_word));
+        let x = word(w) ^ pattern;
+        // the lowest bit of each code equal to c is unset in y
+        let y = (1..width).fold(x, |y, i| y | x >> i);
+        let upper = if to * (width as usize) == 64 {
+            !0
+        } else {
+            (1 << (to as u32 * width)) - 1
+        };
+        let range = upper & !((1u64 << (from as u32 * width)) - 1);
+        count += u64::from((!y & low & range).count_ones());
+        r = (w + 1) * per_word;
+    }
+    count
+}
+
+/// An occurrence array with two-level checkpoints. The BWT is stored as codes of
+/// ceil(log2(A)) bits (A being the alphabet size) packed into 64 bit words, e.g. 3 bits per row
+/// for DNA with `N` and the sentinel `$`. Absolute counts (`u64`) of each symbol are stored for
+/// every superblock of at most 2^16 rows, and counts relative to the superblock (`u16`) for
+/// every k-th row, such that a query adds up the two checkpoints and the matches of the symbol
+/// in the packed codes up to the row, counted with popcounts. For DNA with sampling rate 32,
+/// this needs about 6 bits per row.
 #[derive(Serialize, Deserialize)]
 pub struct Occ {
-    occ: Vec<Vec<usize>>,
+    n: usize,
+    /// Code of each symbol, i.e. its rank in the alphabet.
+    index: VecMap<usize>,
+    width: u32,
+    codes: Vec<u64>,
+    superblocks: Vec<Vec<u64>>,
+    blocks: Vec<Vec<u16>>,
     k: u32,
 }
 
 impl Occ {
     /// Calculate occ array with sampling from BWT of length n.
     /// Time complexity: O(n).
-    /// Space complexity: O(n / k * A) with A being the alphabet size.
-    /// Alphabet size is determined on the fly from the BWT.
-    /// For large texts, it is therefore advisable to transform
-    /// the text before calculating the BWT (see alphabets::rank_transform).
+    /// Space complexity: O(n log A + n / k * A) bits with A being the alphabet size.
+    /// Symbols of the BWT not in the alphabet (e.g. the sentinel `$`) are added to it.
     ///
     /// # Arguments
     ///
     /// * `bwt` - the BWT
-    /// * `k` - the sampling rate: every k-th entry will be stored
+    /// * `k` - the sampling rate: the counts of every k-th row are stored
     pub fn new(bwt: &BWTSlice, k: u32, alphabet: &Alphabet) -> Self {
-        let n = bwt.len();
+        assert!(k > 0, "Expecting positive sampling rate.");
+        let mut alphabet = Alphabet {
+            symbols: alphabet.symbols.clone(),
+        };
+        for &a in bwt {
+            alphabet.insert(a);
+        }
+        let index: VecMap<usize> = alphabet
+            .symbols
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a, i))
+            .collect();
+        let width = code_width(index.len());
+        let per_word = (64 / width) as usize;
+        let mut codes = vec![0u64; code_words(bwt.len(), width)];
+        for (r, &c) in bwt.iter().enumerate() {
+            codes[r / per_word] |= (index[c as usize] as u64) << ((r % per_word) as u32 * width);
+        }
+
+        Self::from_codes(bwt.len(), k, index, width, codes)
+    }
+
+    /// Calculate the checkpoints of the given packed codes, which have to be valid.
+    fn from_codes(n: usize, k: u32, index: VecMap<usize>, width: u32, codes: Vec<u64>) -> Self {
+        let (k_rows, s_rows) = (k as usize, superblock_rows(k));
+        let per_word = (64 / width) as usize;
+        let mask = (1 << width) - 1;
+        let m = index.len();
+        let mut superblocks: Vec<_> = (0..m).map(|_| Vec::with_capacity(n / s_rows + 1)).collect();
+        let mut blocks: Vec<_> = (0..m).map(|_| Vec::with_capacity(n / k_rows + 1)).collect();
+        let mut counts = vec![0u64; m];
+        let mut superblock = vec![0u64; m];
+        for r in 0..=n {
+            if r % s_rows == 0 {
+                superblock.copy_from_slice(&counts);
+                for (superblocks, &count) in superblocks.iter_mut().zip(&counts) {
+                    superblocks.push(count);
+                }
+            }
+            if r % k_rows == 0 {
+                for (s, blocks) in blocks.iter_mut().enumerate() {
+                    blocks.push((counts[s] - superblock[s]) as u16);
+                }
+            }
+            if r < n {
+                let code = (codes[r / per_word] >> ((r % per_word) as u32 * width)) & mask;
+                counts[code as usize] += 1;
+            }
+        }
+
+        Occ {
+            n,
+            index,
+            width,
+            codes,
+            superblocks,
+            blocks,
+            k,
+        }
+    }
+
+    /// Approximate size in bytes of the occ array of a BWT of length n over the given number
+    /// of symbols.
+    pub fn size_estimate(n: usize, k: u32, symbols: usize) -> usize {
+        code_words(n, code_width(symbols)) * 8
+            + symbols * ((n / k as usize + 1) * 2 + (n / superblock_rows(k) + 1) * 8)
+    }
+
+    /// The sampling rate k.
+    pub fn sampling_rate(&self) -> u32 {
+        self.k
+    }
+
+    /// Get occurrence count of symbol a in BWT[..r+1]. The BWT itself is not accessed.
+    /// Complexity: O(k log A / 64), i.e. constant for sampling rates up to 64 / log A.
+    pub fn get(&self, _bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        assert!(r < self.n, "Expecting row within the BWT.");
+        let s = match self.index.get(a as usize) {
+            Some(&s) => s,
+            None => return 0,
+        };
+        let end = r + 1;
+        let block = end / self.k as usize;
+        let start = block * self.k as usize;
+
+        let count = self.superblocks[s][start / superblock_rows(self.k)]
+            + u64::from(self.blocks[s][block])
+            + count_code(|w| self.codes[w], self.width, start, end, s as u64);
+        count as usize
+    }
+
+    /// Write the occ array in a compact binary format (little endian). Only the packed codes
+    /// are written, the checkpoints are recalculated when reading.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.write_codes(&mut writer)
+    }
+
+    /// Write the header and the packed codes.
+    fn write_codes<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.k.to_le_bytes())?;
+        writer.write_all(&(self.n as u64).to_le_bytes())?;
+        writer.write_all(&(self.index.len() as u64).to_le_bytes())?;
+        // the symbols in the order of their codes
+        for (a, _) in &self.index {
+            writer.write_all(&[a as u8])?;
+        }
+        for word in &self.codes {
+            writer.write_all(&word.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read an occ array written with `Occ::write`.
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let (k, n, index) = read_header(&mut reader)?;
+        let width = code_width(index.len());
+        let (per_word, mask) = ((64 / width) as usize, (1 << width) - 1);
+        let mut codes = Vec::with_capacity(code_words(n, width));
+        for w in 0..code_words(n, width) {
+            let word = read_u64(&mut reader)?;
+            let valid = (0..per_word.min(n - w * per_word))
+                .all(|i| ((word >> (i as u32 * width)) & mask) < index.len() as u64);
+            if !valid {
+                return Err(invalid("Invalid symbol code in occ array."));
+            }
+            codes.push(word);
+        }
+        Ok(Self::from_codes(n, k, index, width, codes))
+    }
+
+    /// Write the occ array including its checkpoints (little endian), such that it can be
+    /// accessed without copying with `MappedOcc`, e.g. from a memory mapped file.
+    pub fn write_mapped<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.write_codes(&mut writer)?;
+        for counts in &self.superblocks {
+            for count in counts {
+                writer.write_all(&count.to_le_bytes())?;
+            }
+        }
+        for counts in &self.blocks {
+            for count in counts {
+                writer.write_all(&count.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read the sampling rate, the length and the symbol codes of an occ array.
+fn read_header<R: io::Read>(reader: &mut R) -> io::Result<(u32, usize, VecMap<usize>)> {
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let k = read_u32(reader)?;
+    if k == 0 {
+        return Err(invalid("Invalid sampling rate of occ array."));
+    }
+    let n = read_u64(reader)? as usize;
+    let m = read_u64(reader)? as usize;
+    if m == 0 && n > 0 || m > 256 {
+        return Err(invalid("Invalid number of symbols of occ array."));
+    }
+    let mut index = VecMap::new();
+    for s in 0..m {
+        let mut a = [0];
+        reader.read_exact(&mut a)?;
+        if index.insert(a[0] as usize, s).is_some() {
+            return Err(invalid("Duplicate symbol in occ array."));
+        }
+    }
+    Ok((k, n, index))
+}
+
+/// An occ array written with `Occ::write_mapped`, accessed in place in a byte buffer (e.g. a
+/// memory mapped file) instead of being read into memory.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna;
+/// use bio::data_structures::bwt::{bwt, MappedOcc, Occ};
+/// use bio::data_structures::suffix_array::suffix_array;
+///
+/// let text = b"GCCTTAACATTATTACGCCTA$";
+/// let bwt = bwt(text, &suffix_array(text));
+/// let occ = Occ::new(&bwt, 3, &dna::n_alphabet());
+/// let mut data = Vec::new();
+/// occ.write_mapped(&mut data).unwrap();
+///
+/// let mapped = MappedOcc::new(&data).unwrap();
+/// assert_eq!(mapped.get(10, b'T'), occ.get(&bwt, 10, b'T'));
+/// ```
+pub struct MappedOcc<'a> {
+    n: usize,
+    index: VecMap<usize>,
+    width: u32,
+    codes: &'a [u8],
+    superblocks: Vec<&'a [u8]>,
+    blocks: Vec<&'a [u8]>,
+    k: u32,
+}
+
+impl<'a> MappedOcc<'a> {
+    /// Access the occ array written with `Occ::write_mapped` to the given buffer.
+    pub fn new(mut data: &'a [u8]) -> io::Result<Self> {
+        let occ = Self::parse(&mut data)?;
+        if !data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Trailing data after occ array.",
+            ));
+        }
+        Ok(occ)
+    }
+
+    /// Access the occ array at the start of the buffer, and advance the buffer behind it.
+    pub(crate) fn parse(data: &mut &'a [u8]) -> io::Result<Self> {
+        let (k, n, index) = read_header(data)?;
+        let m = index.len();
+        let width = code_width(m);
+        let codes = split_bytes(data, code_words(n, width) * 8)?;
+        let mut superblocks = Vec::with_capacity(m);
+        for _ in 0..m {
+            superblocks.push(split_bytes(data, (n / superblock_rows(k) + 1) * 8)?);
+        }
+        let mut blocks = Vec::with_capacity(m);
+        for _ in 0..m {
+            blocks.push(split_bytes(data, (n / k as usize + 1) * 2)?);
+        }
+        Ok(MappedOcc {
+            n,
+            index,
+            width,
+            codes,
+            superblocks,
+            blocks,
+            k,
+        })
+    }
+
+    /// The sampling rate k.
+    pub fn sampling_rate(&self) -> u32 {
+        self.k
+    }
+
+    /// Get occurrence count of symbol a in BWT[..r+1], as `Occ::get`.
+    pub fn get(&self, r: usize, a: u8) -> usize {
+        assert!(r < self.n, "Expecting row within the BWT.");
+        let s = match self.index.get(a as usize) {
+            Some(&s) => s,
+            None => return 0,
+        };
+        let end = r + 1;
+        let block = end / self.k as usize;
+        let start = block * self.k as usize;
+
+        let count = le_u64(self.superblocks[s], start / superblock_rows(self.k))
+            + u64::from(u16::from_le_bytes([
+                self.blocks[s][block * 2],
+                self.blocks[s][block * 2 + 1],
+            ]))
+            + count_code(|w| le_u64(self.codes, w), self.width, start, end, s as u64);
+        count as usize
+    }
+}
+
+/// Split the given number of bytes off the start of the buffer.
+pub(crate) fn split_bytes<'a>(data: &mut &'a [u8], len: usize) -> io::Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Unexpected end of data.",
+        ));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+/// The i-th little endian u64 of the buffer.
+pub(crate) fn le_u64(data: &[u8], i: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&data[i * 8..i * 8 + 8]);
+    u64::from_le_bytes(buf)
+}
+
+/// A run-length encoded BWT, storing each run of equal symbols by its symbol and start row.
+/// For highly repetitive texts (e.g. collections of genomes of the same species), the number of
+/// runs r is much smaller than the length n of the BWT. Occurrences are counted with counts of
+/// the symbols sampled every k runs.
+/// Space complexity: O(r + r / k * A) with alphabet size A.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna;
+/// use bio::data_structures::bwt::{bwt, Occ, RunLengthBWT};
+/// use bio::data_structures::suffix_array::suffix_array;
+///
+/// let text = b"ACGTACGTACGTACGT$";
+/// let bwt = bwt(text, &suffix_array(text));
+/// let alphabet = dna::n_alphabet();
+/// let rlbwt = RunLengthBWT::new(&bwt, 2, &alphabet);
+/// assert_eq!(rlbwt.runs(), 5);
+/// assert_eq!(rlbwt.to_bwt(), bwt);
+///
+/// let occ = Occ::new(&bwt, 3, &alphabet);
+/// for r in 0..bwt.len() {
+///     assert_eq!(rlbwt.occ(r, b'C'), occ.get(&bwt, r, b'C'));
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunLengthBWT {
+    /// Symbol of each run.
+    heads: Vec<u8>,
+    /// Start row of each run, followed by the length of the BWT.
+    starts: Vec<usize>,
+    /// Occurrences of each symbol before every k-th run.
+    occ: Vec<Vec<usize>>,
+    k: u32,
+}
+
+impl RunLengthBWT {
+    /// Run-length encode the given BWT, sampling the symbol counts every `k` runs.
+    pub fn new(bwt: &BWTSlice, k: u32, alphabet: &Alphabet) -> Self {
+        assert!(k > 0, "Expecting positive sampling rate.");
+        // count the symbols of the BWT missing from the alphabet as well
         let m = alphabet
             .max_symbol()
-            .expect("Expecting non-empty alphabet.") as usize
+            .expect("Expecting non-empty alphabet.")
+            .max(bwt.iter().cloned().max().unwrap_or(0)) as usize
             + 1;
-        let mut occ = Vec::with_capacity(n / k as usize);
-        let mut curr_occ: Vec<usize> = repeat(0).take(m).collect();
-        for (i, &c) in bwt.iter().enumerate() {
-            curr_occ[c as usize] += 1;
-            if i % k as usize == 0 {
-                occ.push(curr_occ.clone());
+        let mut heads = Vec::new();
+        let mut starts = Vec::new();
+        let mut occ = Vec::new();
+        let mut curr_occ = vec![0; m];
+        for (r, &c) in bwt.iter().enumerate() {
+            if heads.last() != Some(&c) {
+                if heads.len() % k as usize == 0 {
+                    occ.push(curr_occ.clone());
+                }
+                heads.push(c);
+                starts.push(r);
             }
+            curr_occ[c as usize] += 1;
+        }
+        starts.push(bwt.len());
+
+        RunLengthBWT {
+            heads,
+            starts,
+            occ,
+            k,
         }
+    }
+
+    /// Length of the BWT.
+    pub fn len(&self) -> usize {
+        self.starts[self.heads.len()]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of runs.
+    pub fn runs(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// The sampling rate k.
+    pub fn sampling_rate(&self) -> u32 {
+        self.k
+    }
 
-        Occ { occ, k }
+    /// Symbol at row r.
+    /// Complexity: O(log r).
+    pub fn symbol(&self, r: usize) -> u8 {
+        assert!(r < self.len(), "Expecting row within the BWT.");
+        self.heads[self.run(r)]
     }
 
     /// Get occurrence count of symbol a in BWT[..r+1].
-    /// Complexity: O(k).
-    pub fn get(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
-        // NOTE:
-        //
-        // Retrieving byte match counts in this function is critical to the performance of FM Index.
-        //
-        // The below manual count code is roughly equivalent to:
-        // ```
-        // let count = bwt[(i * self.k) + 1..r + 1].iter().filter(|&&c| c == a).count();
-        // self.occ[i][a as usize] + count
-        // ```
-        //
-        // But there are a couple of reasons to do this manually:
-        // 1) As of 2016, versions of rustc/LLVM vectorize this manual loop more reliably
-        //    than the iterator adapter version.
-        // 2) Manually accumulating the byte match count in a single chunk can allows
-        //    us to use a `u32` for that count, which has faster arithmetic on common arches.
-        //    This does necessitate storing `k` as a u32.
-        //
-        // See the conversation in these issues for some of the history here:
-        //
-        // https://github.com/rust-bio/rust-bio/pull/74
-        // https://github.com/rust-bio/rust-bio/pull/76
-
-        // self.k is our sampling rate, so find our last sampled checkpoint
-        let i = r / self.k as usize;
-        let checkpoint = self.occ[i][a as usize];
-
-        // find the portion of the BWT past the checkpoint which we need to count
-        let start = (i * self.k as usize) + 1;
-        let end = r + 1;
+    /// Complexity: O(log r + k).
+    pub fn occ(&self, r: usize, a: u8) -> usize {
+        let i = self.run(r);
+        let checkpoint = i / self.k as usize;
+        // symbols beyond the counted ones do not occur
+        let mut count = self.occ[checkpoint][..]
+            .get(a as usize)
+            .cloned()
+            .unwrap_or(0);
+        for j in checkpoint * self.k as usize..i {
+            if self.heads[j] == a {
+                count += self.starts[j + 1] - self.starts[j];
+            }
+        }
+        if self.heads[i] == a {
+            count += r + 1 - self.starts[i];
+        }
+        count
+    }
 
-        // count all the matching bytes b/t the closest checkpoint and our desired lookup
-        let count = bytecount::count(&bwt[start..end], a);
+    /// Decode the BWT.
+    pub fn to_bwt(&self) -> BWT {
+        let mut bwt = Vec::with_capacity(self.len());
+        for (&c, &end) in self.heads.iter().zip(&self.starts[1..]) {
+            bwt.resize(end, c);
+        }
+        bwt
+    }
 
-        // return the sampled checkpoint for this character + the manual count we just did
-        checkpoint + (count as usize)
+    /// Index of the run containing row r.
+    fn run(&self, r: usize) -> usize {
+        self.starts[..self.heads.len()].partition_point(|&start| start <= r) - 1
     }
 }
 
+pub(crate) fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u64<R: io::Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
 /// Calculate the less array for a given BWT. Complexity O(n).
 pub fn less(bwt: &BWTSlice, alphabet: &Alphabet) -> Less {
     let m = alphabet
@@ -183,7 +703,7 @@ pub fn bwtfind(bwt: &BWTSlice, alphabet: &Alphabet) -> BWTFind {
 
 #[cfg(test)]
 mod tests {
-    use super::{bwt, bwtfind, invert_bwt, Occ};
+    use super::{bwt, bwtfind, invert_bwt, MappedOcc, Occ, RunLengthBWT};
     use alphabets::Alphabet;
     use data_structures::suffix_array::suffix_array;
 
@@ -211,8 +731,140 @@ mod tests {
         let bwt = vec![1u8, 3u8, 3u8, 1u8, 2u8, 0u8];
         let alphabet = Alphabet::new(&[0u8, 1u8, 2u8, 3u8]);
         let occ = Occ::new(&bwt, 3, &alphabet);
-        assert_eq!(occ.occ, [[0, 1, 0, 0], [0, 2, 0, 2]]);
         assert_eq!(occ.get(&bwt, 4, 2u8), 1);
         assert_eq!(occ.get(&bwt, 4, 3u8), 2);
+        assert_eq!(occ.get(&bwt, 4, 4u8), 0);
+    }
+
+    #[test]
+    fn test_occ_checkpoints() {
+        // long enough for several superblocks, with a symbol not in the alphabet
+        let bwt: Vec<u8> = (0..150_000usize)
+            .map(|i| b"ACGT"[(i * 7 + i / 13) % 4])
+            .chain(Some(b'$'))
+            .collect();
+        let alphabet = Alphabet::new(b"ACGTN");
+        for &k in &[1, 64, 100, 1 << 20] {
+            let occ = Occ::new(&bwt, k, &alphabet);
+            let mut counts = [0; 256];
+            for (r, &c) in bwt.iter().enumerate() {
+                counts[c as usize] += 1;
+                if r % 997 == 0 || r + 70 > bwt.len() || r % 65_536 < 70 {
+                    for &a in b"ACGTN$" {
+                        assert_eq!(occ.get(&bwt, r, a), counts[a as usize]);
+                    }
+                }
+            }
+
+            let mut buffer = Vec::new();
+            occ.write(&mut buffer).unwrap();
+            let read = Occ::read(&buffer[..]).unwrap();
+            assert_eq!(read.sampling_rate(), k);
+            for &r in &[0, 65_535, 65_536, 100_000, bwt.len() - 1] {
+                assert_eq!(read.get(&bwt, r, b'G'), occ.get(&bwt, r, b'G'));
+            }
+
+            let mut buffer = Vec::new();
+            occ.write_mapped(&mut buffer).unwrap();
+            let mapped = MappedOcc::new(&buffer).unwrap();
+            assert_eq!(mapped.sampling_rate(), k);
+            for r in (0..bwt.len()).step_by(331).chain(Some(bwt.len() - 1)) {
+                for &a in b"ACGTN$" {
+                    assert_eq!(mapped.get(r, a), occ.get(&bwt, r, a));
+                }
+            }
+            assert!(MappedOcc::new(&buffer[..buffer.len() - 1]).is_err());
+        }
+        assert!(Occ::size_estimate(bwt.len(), 32, 6) < bwt.len() * 2);
+    }
+
+    #[test]
+    fn test_occ_code_widths() {
+        // alphabets of 1, 2, 5 (non-dividing width) and 8 bit codes
+        for &m in &[1usize, 3, 17, 256] {
+            let bwt: Vec<u8> = (0..3000usize)
+                .map(|i| ((i * i + i / 7) % m) as u8)
+                .collect();
+            let alphabet = Alphabet::new(&bwt);
+            for &k in &[1, 7, 32, 300] {
+                let occ = Occ::new(&bwt, k, &alphabet);
+                let mut counts = [0; 256];
+                for (r, &c) in bwt.iter().enumerate() {
+                    counts[c as usize] += 1;
+                    for a in (0..m).step_by(m / 3 + 1) {
+                        assert_eq!(occ.get(&bwt, r, a as u8), counts[a]);
+                    }
+                    assert_eq!(occ.get(&bwt, r, c), counts[c as usize]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_occ_size() {
+        let bwt: Vec<u8> = (0..100_000usize)
+            .map(|i| b"ACGTN"[(i * 7 + i / 13) % 5])
+            .chain(Some(b'$'))
+            .collect();
+        let n = bwt.len();
+        let alphabet = Alphabet::new(b"ACGTN$");
+        for &k in &[1, 16, 32, 64, 128, 1024] {
+            let occ = Occ::new(&bwt, k, &alphabet);
+            let size = occ.codes.len() * 8
+                + occ.superblocks.iter().map(|s| s.len() * 8).sum::<usize>()
+                + occ.blocks.iter().map(|b| b.len() * 2).sum::<usize>();
+            assert!(size <= Occ::size_estimate(n, k, alphabet.len()));
+            // the previous layout stored a usize for each symbol up to the largest one at
+            // every k-th row, and needed the BWT for counting between the checkpoints
+            let old = |m: usize| (n / k as usize + 1) * m * 8;
+            assert!(size < old(b'T' as usize + 1));
+            assert!(size < old(alphabet.len()) + n);
+            if k <= 64 {
+                assert!(size < old(alphabet.len()));
+            }
+        }
+        // 3 bits per row for the codes and 3 bits per row for the counts
+        let occ = Occ::new(&bwt, 32, &alphabet);
+        assert_eq!(occ.width, 3);
+        assert!(Occ::size_estimate(n, 32, alphabet.len()) * 8 < n * 61 / 10);
+    }
+
+    #[test]
+    fn test_run_length_bwt() {
+        let text = b"ACGTTACGTTACGTTACCTTACGTTAGGT$ACGTTACGTTACGTTACGTT$";
+        let bwt = bwt(text, &suffix_array(text));
+        let alphabet = Alphabet::new(b"ACGT$");
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        for &k in &[1, 2, 5, 100] {
+            let rlbwt = RunLengthBWT::new(&bwt, k, &alphabet);
+            assert!(rlbwt.runs() < bwt.len());
+            assert_eq!(rlbwt.len(), bwt.len());
+            assert_eq!(rlbwt.to_bwt(), bwt);
+            for r in 0..bwt.len() {
+                assert_eq!(rlbwt.symbol(r), bwt[r]);
+                for &a in b"ACGT$" {
+                    assert_eq!(rlbwt.occ(r, a), occ.get(&bwt, r, a));
+                }
+            }
+        }
+        let rlbwt = RunLengthBWT::new(&[0u8, 0, 2, 1, 1, 1], 1, &Alphabet::new([0u8, 1, 2]));
+        assert_eq!(rlbwt.occ, [[0, 0, 0], [2, 0, 0], [2, 0, 1]]);
+        assert!(RunLengthBWT::new(b"", 2, &alphabet).is_empty());
+    }
+
+    #[test]
+    fn test_run_length_bwt_absent_symbols() {
+        let bwt = b"AT$GGCAA";
+        // T is not in the alphabet, N and Z not in the BWT
+        let alphabet = Alphabet::new(b"ACG$");
+        let occ = Occ::new(bwt, 2, &alphabet);
+        let rlbwt = RunLengthBWT::new(bwt, 2, &alphabet);
+        for r in 0..bwt.len() {
+            for &a in b"ACGTNZ$" {
+                assert_eq!(rlbwt.occ(r, a), occ.get(bwt, r, a));
+            }
+        }
+        assert_eq!(rlbwt.occ(7, b'T'), 1);
+        assert_eq!(rlbwt.occ(7, b'Z'), 0);
     }
 }
@@ -0,0 +1,433 @@
+//! Space efficient containers for sequences of unsigned integers.
+//!
+//! * `PackedInts`: a vector of integers stored with a fixed number of bits each.
+//! * `EliasFano`: a monotone (non-decreasing) sequence in n * (2 + log(u / n)) bits
+//!   with random access (Elias 1974, Fano 1971).
+//! * `DeltaVarints`: a stream of non-decreasing integers stored as LEB128 encoded gaps,
+//!   intended for sequential access and serialization.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::compressed_ints::{DeltaVarints, EliasFano, PackedInts};
+//!
+//! let mut packed = PackedInts::new(5);
+//! packed.push(3);
+//! packed.push(31);
+//! assert_eq!(packed.get(1), Some(31));
+//!
+//! let positions = [2, 3, 5, 7, 11, 13, 24];
+//! let ef = EliasFano::new(&positions);
+//! assert_eq!(ef.get(4), Some(11));
+//!
+//! let stream: DeltaVarints = positions.iter().cloned().collect();
+//! assert_eq!(stream.iter().collect::<Vec<_>>(), positions);
+//! ```
+
+use bv::{BitVec, BitsMut};
+
+use data_structures::rank_select::RankSelect;
+
+/// Number of bits needed to represent the given value (at least one).
+fn bit_width(v: u64) -> usize {
+    (64 - v.leading_zeros() as usize).max(1)
+}
+
+/// A vector of unsigned integers, each stored with the same fixed number of bits.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedInts {
+    words: Vec<u64>,
+    width: usize,
+    len: usize,
+}
+
+impl PackedInts {
+    /// Create a new, empty instance storing each value with `width` bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - number of bits per value (between 0 and 64)
+    pub fn new(width: usize) -> Self {
+        assert!(width <= 64, "Expecting bit width of at most 64.");
+        PackedInts {
+            words: Vec::new(),
+            width,
+            len: 0,
+        }
+    }
+
+    /// Create a new instance containing `n` zeros.
+    pub fn with_len(width: usize, n: usize) -> Self {
+        let mut packed = PackedInts::new(width);
+        packed.len = n;
+        packed.words = vec![0; (n * width).div_ceil(64)];
+        packed
+    }
+
+    /// Create an instance from the given values, choosing the minimal width that can
+    /// represent the largest value.
+    pub fn from_values(values: &[u64]) -> Self {
+        let width = bit_width(values.iter().cloned().max().unwrap_or(0));
+        let mut packed = PackedInts::with_len(width, values.len());
+        for (i, &v) in values.iter().enumerate() {
+            packed.set(i, v);
+        }
+        packed
+    }
+
+    /// Number of bits used per value.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn mask(&self) -> u64 {
+        if self.width == 64 {
+            !0
+        } else {
+            (1 << self.width) - 1
+        }
+    }
+
+    /// Return value at position `i`, or `None` if out of bounds.
+    pub fn get(&self, i: usize) -> Option<u64> {
+        if i >= self.len {
+            return None;
+        }
+        if self.width == 0 {
+            return Some(0);
+        }
+        let bit = i * self.width;
+        let (w, o) = (bit / 64, bit % 64);
+        let mut v = self.words[w] >> o;
+        if o + self.width > 64 {
+            v |= self.words[w + 1] << (64 - o);
+        }
+        Some(v & self.mask())
+    }
+
+    /// Set value at position `i`. Panics if the value does not fit into the width
+    /// or `i` is out of bounds.
+    pub fn set(&mut self, i: usize, v: u64) {
+        assert!(i < self.len, "Index out of bounds.");
+        assert!(
+            v & !self.mask() == 0,
+            "Value {} does not fit into {} bits.",
+            v,
+            self.width
+        );
+        if self.width == 0 {
+            return;
+        }
+        let bit = i * self.width;
+        let (w, o) = (bit / 64, bit % 64);
+        let mask = self.mask();
+        self.words[w] = (self.words[w] & !(mask << o)) | (v << o);
+        if o + self.width > 64 {
+            let shift = 64 - o;
+            self.words[w + 1] = (self.words[w + 1] & !(mask >> shift)) | (v >> shift);
+        }
+    }
+
+    /// Append a value.
+    pub fn push(&mut self, v: u64) {
+        self.len += 1;
+        let needed = (self.len * self.width).div_ceil(64);
+        if self.words.len() < needed {
+            self.words.push(0);
+        }
+        let i = self.len - 1;
+        self.set(i, v);
+    }
+
+    /// Iterate over the stored values.
+    pub fn iter(&self) -> PackedIntsIter<'_> {
+        PackedIntsIter { packed: self, i: 0 }
+    }
+
+    /// Size of the payload in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.words.len() * 8
+    }
+}
+
+/// Iterator over the values of a `PackedInts` instance.
+pub struct PackedIntsIter<'a> {
+    packed: &'a PackedInts,
+    i: usize,
+}
+
+impl<'a> Iterator for PackedIntsIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let v = self.packed.get(self.i);
+        if v.is_some() {
+            self.i += 1;
+        }
+        v
+    }
+}
+
+/// Elias-Fano representation of a non-decreasing sequence of integers.
+/// Each value is split into `l` low bits, stored in a `PackedInts`, and high bits,
+/// stored in unary in a bit vector that supports select queries.
+#[derive(Serialize, Deserialize)]
+pub struct EliasFano {
+    low: PackedInts,
+    high: RankSelect,
+    low_width: usize,
+    len: usize,
+    universe: u64,
+}
+
+impl EliasFano {
+    /// Create a new instance from a non-decreasing sequence of values below `u64::MAX`, such
+    /// that the universe is representable. Panics if the sequence is not sorted or contains
+    /// `u64::MAX`.
+    pub fn new(values: &[u64]) -> Self {
+        assert!(
+            values.windows(2).all(|w| w[0] <= w[1]),
+            "Expecting non-decreasing values for Elias-Fano encoding."
+        );
+        let n = values.len();
+        let universe = values.last().map_or(0, |&v| {
+            v.checked_add(1)
+                .expect("Expecting values below u64::MAX for Elias-Fano encoding.")
+        });
+        let low_width = if n == 0 || universe <= n as u64 {
+            0
+        } else {
+            63 - (universe / n as u64).leading_zeros() as usize
+        };
+
+        let mut low = PackedInts::with_len(low_width, n);
+        let high_len = n + (universe >> low_width) as usize + 1;
+        let mut high: BitVec<u8> = BitVec::new_fill(false, high_len as u64);
+        for (i, &v) in values.iter().enumerate() {
+            if low_width > 0 {
+                low.set(i, v & ((1 << low_width) - 1));
+            }
+            high.set_bit((v >> low_width) + i as u64, true);
+        }
+
+        EliasFano {
+            low,
+            high: RankSelect::new(high, 1),
+            low_width,
+            len: n,
+            universe,
+        }
+    }
+
+    /// Number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// One plus the largest stored value.
+    pub fn universe(&self) -> u64 {
+        self.universe
+    }
+
+    /// Return the value at position `i`, or `None` if out of bounds.
+    /// Complexity: that of `RankSelect::select_1`.
+    pub fn get(&self, i: usize) -> Option<u64> {
+        if i >= self.len {
+            return None;
+        }
+        let pos = self
+            .high
+            .select_1(i as u64 + 1)
+            .expect("bug: missing high bits");
+        let high = pos - i as u64;
+        Some((high << self.low_width) | self.low.get(i).unwrap())
+    }
+
+    /// Iterate over the stored values.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len).map(move |i| self.get(i).unwrap())
+    }
+}
+
+/// Encode a value as LEB128 varint into the given buffer.
+pub fn encode_varint(mut v: u64, buf: &mut Vec<u8>) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+/// Decode a LEB128 varint from the start of the given buffer.
+/// Returns the value and the number of consumed bytes, or `None` if the buffer ends
+/// before the varint is complete.
+pub fn decode_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let mut v = 0u64;
+    for (i, &b) in buf.iter().enumerate().take(10) {
+        v |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some((v, i + 1));
+        }
+    }
+    None
+}
+
+/// A non-decreasing sequence of integers stored as varint encoded differences
+/// between consecutive values.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaVarints {
+    bytes: Vec<u8>,
+    len: usize,
+    last: u64,
+}
+
+impl DeltaVarints {
+    /// Create a new, empty stream.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Append a value. Panics if the value is smaller than the previously pushed one.
+    pub fn push(&mut self, v: u64) {
+        assert!(
+            v >= self.last,
+            "Expecting non-decreasing values for delta encoding."
+        );
+        encode_varint(v - self.last, &mut self.bytes);
+        self.last = v;
+        self.len += 1;
+    }
+
+    /// Number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether no values are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The encoded representation.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Iterate over the decoded values.
+    pub fn iter(&self) -> DeltaVarintsIter<'_> {
+        DeltaVarintsIter {
+            bytes: &self.bytes,
+            last: 0,
+        }
+    }
+}
+
+impl ::std::iter::FromIterator<u64> for DeltaVarints {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut stream = DeltaVarints::new();
+        for v in iter {
+            stream.push(v);
+        }
+        stream
+    }
+}
+
+/// Iterator over the values of a `DeltaVarints` stream.
+pub struct DeltaVarintsIter<'a> {
+    bytes: &'a [u8],
+    last: u64,
+}
+
+impl<'a> Iterator for DeltaVarintsIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        decode_varint(self.bytes).map(|(delta, consumed)| {
+            self.bytes = &self.bytes[consumed..];
+            self.last += delta;
+            self.last
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_ints() {
+        let values: Vec<u64> = (0..200).map(|i| (i * 37) % 101).collect();
+        let packed = PackedInts::from_values(&values);
+        assert_eq!(packed.width(), 7);
+        assert_eq!(packed.iter().collect::<Vec<_>>(), values);
+        assert_eq!(packed.get(200), None);
+
+        let mut packed = PackedInts::new(64);
+        packed.push(u64::MAX);
+        packed.push(1);
+        assert_eq!(packed.get(0), Some(u64::MAX));
+        assert_eq!(packed.get(1), Some(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_packed_ints_overflow() {
+        let mut packed = PackedInts::new(3);
+        packed.push(8);
+    }
+
+    #[test]
+    fn test_elias_fano() {
+        let values = vec![0, 0, 3, 5, 5, 17, 100, 101, 1000, 1 << 40];
+        let ef = EliasFano::new(&values);
+        assert_eq!(ef.len(), values.len());
+        assert_eq!(ef.iter().collect::<Vec<_>>(), values);
+        assert_eq!(ef.get(values.len()), None);
+
+        let dense: Vec<u64> = (0..100).collect();
+        let ef = EliasFano::new(&dense);
+        assert_eq!(ef.iter().collect::<Vec<_>>(), dense);
+
+        assert!(EliasFano::new(&[]).is_empty());
+
+        let large = [0, 1 << 63, u64::MAX - 1];
+        let ef = EliasFano::new(&large);
+        assert_eq!(ef.universe(), u64::MAX);
+        assert_eq!(ef.iter().collect::<Vec<_>>(), large);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expecting values below u64::MAX")]
+    fn test_elias_fano_max_value() {
+        EliasFano::new(&[1, u64::MAX]);
+    }
+
+    #[test]
+    fn test_varints() {
+        let mut buf = Vec::new();
+        encode_varint(300, &mut buf);
+        assert_eq!(buf, [0b1010_1100, 0b0000_0010]);
+        assert_eq!(decode_varint(&buf), Some((300, 2)));
+        assert_eq!(decode_varint(&buf[..1]), None);
+
+        let values = vec![1, 1, 128, 300, 70000, u64::MAX];
+        let stream: DeltaVarints = values.iter().cloned().collect();
+        assert_eq!(stream.len(), values.len());
+        assert_eq!(stream.iter().collect::<Vec<_>>(), values);
+    }
+}
@@ -0,0 +1,230 @@
+//! Document listing over a text consisting of several documents (e.g. contigs separated by
+//! sentinels): for a suffix array interval, e.g. the occurrences of a pattern found by an
+//! `FMIndex`, report the distinct documents containing them.
+//!
+//! The document array stores the document of each suffix array row. Following Muthukrishnan
+//! (2002), each row is linked to the previous row of the same document, and a range minimum
+//! query over these links finds a row whose document was not yet reported. With the leftmost
+//! minimum, already reported documents need not be checked against the links (Sadakane, 2007),
+//! such that a `SuccinctRMQ` suffices. Listing d documents takes O(d) queries, independent of
+//! the number of occurrences, and needs no suffix array lookups.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alphabets::dna;
+//! use bio::data_structures::bwt::{bwt, less, Occ};
+//! use bio::data_structures::document_array::DocumentArray;
+//! use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+//! use bio::data_structures::suffix_array::suffix_array;
+//!
+//! let text = b"ACGTTA$GGTTAC$CCCA$TTAGA$";
+//! let alphabet = dna::n_alphabet();
+//! let sa = suffix_array(text);
+//! let bwt = bwt(text, &sa);
+//! let fm = FMIndex::new(&bwt, less(&bwt, &alphabet), Occ::new(&bwt, 3, &alphabet));
+//! let docs = DocumentArray::from_text(text, &sa);
+//! assert_eq!(docs.documents(), 4);
+//!
+//! let interval = fm.backward_search(b"TTA".iter());
+//! assert_eq!(docs.list(&interval), [0, 1, 3]);
+//! assert_eq!(docs.list(&fm.backward_search(b"CA".iter())), [2]);
+//! ```
+
+use std::collections::HashSet;
+
+use data_structures::compressed_ints::PackedInts;
+use data_structures::fmindex::{Interval, SequenceMap};
+use data_structures::rmq::SuccinctRMQ;
+use data_structures::suffix_array::SuffixArray;
+
+/// Document array with a range minimum query structure for document listing. The documents
+/// are bit-packed to the width needed for their number.
+#[derive(Serialize, Deserialize)]
+pub struct DocumentArray {
+    docs: PackedInts,
+    prev: SuccinctRMQ,
+    documents: usize,
+}
+
+impl DocumentArray {
+    /// Build the document array from the full suffix array of a text and the sorted start
+    /// positions of its documents, the first one being 0.
+    /// Complexity: O(n log d) for a text of length n with d documents.
+    pub fn new<SA: SuffixArray>(sa: &SA, starts: &[usize]) -> Self {
+        assert_eq!(
+            starts.first(),
+            Some(&0),
+            "Expecting a document starting at 0."
+        );
+        assert!(
+            starts.windows(2).all(|w| w[0] < w[1]),
+            "Expecting sorted document starts."
+        );
+        let n = sa.len();
+        let width = PackedInts::from_values(&[starts.len() as u64 - 1]).width();
+        let mut docs = PackedInts::with_len(width, n);
+        for row in 0..n {
+            let pos = sa.get(row).expect("Expecting full suffix array.");
+            docs.set(
+                row,
+                (starts.partition_point(|&start| start <= pos) - 1) as u64,
+            );
+        }
+
+        // link each row to the previous row of the same document (0 if there is none, else
+        // the row + 1)
+        let mut last = vec![0; starts.len()];
+        let prev: Vec<usize> = docs
+            .iter()
+            .enumerate()
+            .map(|(row, doc)| {
+                let p = last[doc as usize];
+                last[doc as usize] = row + 1;
+                p
+            })
+            .collect();
+
+        DocumentArray {
+            docs,
+            prev: SuccinctRMQ::new(&prev),
+            documents: starts.len(),
+        }
+    }
+
+    /// Build the document array of a text whose documents are each terminated by the sentinel
+    /// `$`, given its full suffix array.
+    pub fn from_text<SA: SuffixArray>(text: &[u8], sa: &SA) -> Self {
+        let mut starts = vec![0];
+        starts.extend(
+            text.iter()
+                .enumerate()
+                .filter(|&(i, &c)| c == b'$' && i + 1 < text.len())
+                .map(|(i, _)| i + 1),
+        );
+        Self::new(sa, &starts)
+    }
+
+    /// Build the document array of an FMD-Index text, with one document per sequence covering
+    /// both strands, given its full suffix array.
+    pub fn from_sequence_map<SA: SuffixArray>(map: &SequenceMap, sa: &SA) -> Self {
+        let mut starts = Vec::with_capacity(map.len());
+        let mut start = 0;
+        for seq in 0..map.len() {
+            starts.push(start);
+            start += 2 * map.seq_len(seq) + 2;
+        }
+        Self::new(sa, &starts)
+    }
+
+    /// Number of suffix array rows.
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    /// Check whether the document array is empty.
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    /// Number of documents.
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// The document of the suffix at the given suffix array row.
+    pub fn document(&self, row: usize) -> usize {
+        self.docs
+            .get(row)
+            .expect("Expecting row within the document array.") as usize
+    }
+
+    /// The distinct documents containing the suffixes of the given interval, in increasing
+    /// order. Complexity: O(d) range minimum queries for d reported documents.
+    pub fn list(&self, interval: &Interval) -> Vec<usize> {
+        let mut reported = Vec::new();
+        let mut seen = HashSet::new();
+        // ranges [l, r) still to be searched, the left one first
+        let mut stack = vec![(interval.lower, interval.upper.min(self.len()))];
+        while let Some((l, r)) = stack.pop() {
+            if l >= r {
+                continue;
+            }
+            let row = self.prev.query(l, r - 1);
+            let doc = self.document(row);
+            // the leftmost minimum is the first row of a new document in [l, r), unless all
+            // documents in the range were reported already
+            if !seen.insert(doc) {
+                continue;
+            }
+            reported.push(doc);
+            stack.push((row + 1, r));
+            stack.push((l, row));
+        }
+        reported.sort_unstable();
+        reported
+    }
+
+    /// Number of distinct documents containing the suffixes of the given interval.
+    pub fn count(&self, interval: &Interval) -> usize {
+        self.list(interval).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alphabets::dna;
+    use data_structures::bwt::{bwt, less, Occ};
+    use data_structures::fmindex::{FMDIndex, FMIndex, FMIndexable};
+    use data_structures::suffix_array::suffix_array;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_list() {
+        let text = b"ACGTTA$GGTTAC$CCCA$TTAGA$ACGT$";
+        let sa = suffix_array(text);
+        let docs = DocumentArray::from_text(text, &sa);
+        assert_eq!(docs.len(), text.len());
+        assert_eq!(docs.documents(), 5);
+        let starts = [0, 7, 14, 19, 25, 30];
+        for (row, &pos) in sa.iter().enumerate() {
+            let doc = docs.document(row);
+            assert!(starts[doc] <= pos && pos < starts[doc + 1]);
+        }
+
+        // compare with decoding all occurrences
+        for lower in 0..text.len() {
+            for upper in lower..=text.len() {
+                let interval = Interval { lower, upper };
+                let expected: BTreeSet<usize> =
+                    (lower..upper).map(|row| docs.document(row)).collect();
+                let expected: Vec<usize> = expected.into_iter().collect();
+                assert_eq!(docs.list(&interval), expected);
+            }
+        }
+
+        let alphabet = dna::n_alphabet();
+        let bwt = bwt(text, &sa);
+        let fm = FMIndex::new(&bwt, less(&bwt, &alphabet), Occ::new(&bwt, 3, &alphabet));
+        let interval = fm.backward_search(b"ACG".iter());
+        assert_eq!(docs.list(&interval), [0, 4]);
+        assert_eq!(docs.count(&interval), 2);
+        assert!(docs.list(&fm.backward_search(b"GGG".iter())).is_empty());
+    }
+
+    #[test]
+    fn test_sequence_map() {
+        let seqs: [&[u8]; 3] = [b"GATTACA", b"CCGGA", b"TTTACG"];
+        let (fmdindex, sa) = FMDIndex::from_texts(seqs.iter().cloned(), 3).unwrap();
+        let docs = DocumentArray::from_sequence_map(fmdindex.sequence_map(), &sa);
+        assert_eq!(docs.documents(), 3);
+        let interval = fmdindex.backward_search(b"TAC".iter());
+        assert_eq!(docs.list(&interval), [0, 2]);
+        // TGTA only occurs on the reverse strand of the first sequence
+        let interval = fmdindex.backward_search(b"TGTA".iter());
+        assert_eq!(docs.list(&interval), [0]);
+        let interval = fmdindex.backward_search(b"CCGG".iter());
+        assert_eq!(docs.list(&interval), [1]);
+    }
+}
@@ -24,20 +24,52 @@
 //! let fm = FMIndex::new(&bwt, &less, &occ);
 //! ```
 //!
+//! If the suffix array is not needed afterwards, an owned index is built in one call:
+//!
+//! ```
+//! use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+//! use bio::alphabets::dna;
+//!
+//! let fm = FMIndex::from_text(b"GCCTTAACATTATTACGCCTA$", 3, &dna::n_alphabet());
+//! assert_eq!(fm.count(b"TTA".iter()), 3);
+//! ```
+//!
+//! ## Build with a sampled suffix array
+//!
+//! `IndexBuilder` builds an FM-Index together with a sampled suffix array, for locating
+//! occurrences without keeping the complete suffix array in memory.
+//!
+//! ```
+//! use bio::data_structures::fmindex::{FMIndexable, IndexBuilder};
+//! use bio::alphabets::dna;
+//!
+//! let index = IndexBuilder::new()
+//!     .occ_sampling_rate(16)
+//!     .sa_sampling_rate(8)
+//!     .alphabet(dna::n_alphabet())
+//!     .threads(2)
+//!     .build(b"GCCTTAACATTATTACGCCTA$")
+//!     .unwrap();
+//! assert_eq!(index.count(b"TTA".iter()), 3);
+//! assert_eq!(index.find(b"TTA"), [3, 12, 9]);
+//! ```
+//!
 //! ## Enclose in struct
 //!
 //! `FMIndex` was designed to not forcibly own the BWT and auxiliary data structures.
 //! It can take a reference (`&`), owned structs or any of the more complex pointer types.
+//! The owning variants are available as `OwnedFMIndex` and `OwnedFMDIndex`, which can be
+//! stored in structs or returned from functions without lifetimes.
 //!
 //! ```
 //! use bio::data_structures::bwt::{BWT, Less, bwt, less, Occ};
-//! use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+//! use bio::data_structures::fmindex::{FMIndex, FMIndexable, OwnedFMIndex};
 //! use bio::data_structures::suffix_array::suffix_array;
 //! use bio::alphabets::dna;
 //! use bio::utils::TextSlice;
 //!
 //! pub struct Example {
-//!     fmindex: FMIndex<BWT, Less, Occ>
+//!     fmindex: OwnedFMIndex
 //! }
 //!
 //! impl Example {
@@ -52,16 +84,60 @@
 //!     }
 //! }
 //! ```
+//!
+//! ## Persist
+//!
+//! Owned FM-Indexes can be written to disk, and loaded instead of being rebuilt.
+//!
+//! ```
+//! use bio::data_structures::bwt::{bwt, less, Occ};
+//! use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+//! use bio::data_structures::suffix_array::suffix_array;
+//! use bio::alphabets::dna;
+//!
+//! let text = b"GCCTTAACATTATTACGCCTA$";
+//! let alphabet = dna::n_alphabet();
+//! let sa = suffix_array(text);
+//! let bwt = bwt(text, &sa);
+//! let less = less(&bwt, &alphabet);
+//! let occ = Occ::new(&bwt, 3, &alphabet);
+//! let fm = FMIndex::new(bwt, less, occ);
+//!
+//! let mut buffer = Vec::new();
+//! fm.write(&mut buffer).unwrap();
+//! let loaded = FMIndex::read(&buffer[..]).unwrap();
+//! let interval = loaded.backward_search(b"TTA".iter());
+//! assert_eq!(interval.occ(&sa), [3, 12, 9]);
+//! ```
 
 use std::borrow::Borrow;
+use std::cmp;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
 use std::iter::DoubleEndedIterator;
+use std::mem;
+use std::path::Path;
+use std::thread;
 
-use alphabets::dna;
-use data_structures::bwt::{Less, Occ, BWT};
-use data_structures::suffix_array::SuffixArray;
+use bio_types::strand::ReqStrand;
+
+use alignment::AlignmentOperation;
+use alphabets::{dna, Alphabet};
+use data_structures::bwt::{
+    bwt, less, read_u64, split_bytes, BWTSlice, BWTStorage, Less, MappedOcc, Occ, OccBackend,
+    RunLengthBWT, BWT,
+};
+use data_structures::suffix_array::{
+    suffix_array, suffix_array_sized, MappedSuffixArray, RawSuffixArray, SampledInverseSuffixArray,
+    SampledSuffixArray, SuffixArray, SuffixArraySample,
+};
+use error::Error;
 use std::mem::swap;
+use utils::{IndexableText, MemoryBudget, SizedInt};
 
-/// A suffix array interval.
+/// A suffix array interval (`lower..upper`).
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Interval {
     pub lower: usize,
@@ -69,10 +145,109 @@ pub struct Interval {
 }
 
 impl Interval {
+    /// Number of suffixes in the interval.
+    pub fn len(&self) -> usize {
+        self.upper.saturating_sub(self.lower)
+    }
+
+    /// Check if the interval is empty, e.g. when the searched pattern does not occur.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn occ<SA: SuffixArray>(&self, sa: &SA) -> Vec<usize> {
+        self.occ_iter(sa).collect()
+    }
+
+    /// Iterate over the text positions of the interval as `occ`, looking them up lazily, e.g.
+    /// for stopping early at intervals with many occurrences.
+    pub fn occ_iter<'a, SA: SuffixArray>(&self, sa: &'a SA) -> impl Iterator<Item = usize> + 'a {
         (self.lower..self.upper)
-            .map(|pos| sa.get(pos).expect("Interval out of range of suffix array"))
-            .collect()
+            .map(move |pos| sa.get(pos).expect("Interval out of range of suffix array"))
+    }
+}
+
+/// A suffix array interval of the occurrences of a pattern with the given number of
+/// mismatches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MismatchInterval {
+    pub interval: Interval,
+    pub mismatches: usize,
+}
+
+/// A suffix array interval of the occurrences of a pattern with the given edit distance, and
+/// an edit script of the pattern against them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditInterval {
+    pub interval: Interval,
+    pub edits: usize,
+    pub operations: Vec<AlignmentOperation>,
+}
+
+/// Observer of the steps of FM-Index searches, e.g. for profiling. All methods do nothing by
+/// default, and `()` observes nothing.
+pub trait SearchObserver {
+    /// An interval was extended by a symbol, yielding an interval of the given size.
+    fn extension(&mut self, _size: usize) {}
+
+    /// A branch of an approximate search was abandoned since it exceeded the allowed number of
+    /// mismatches or edits.
+    fn pruned(&mut self) {}
+
+    /// A match with an interval of the given size was found.
+    fn found(&mut self, _size: usize) {}
+
+    /// A row was mapped to the row of the preceding suffix (LF-mapping) while locating an
+    /// occurrence with a sampled suffix array.
+    fn lf_step(&mut self) {}
+}
+
+impl SearchObserver for () {}
+
+/// A `SearchObserver` counting the steps of searches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SearchStats {
+    pub extensions: usize,
+    /// Extensions yielding empty intervals.
+    pub empty_extensions: usize,
+    /// Total size of the extended intervals.
+    pub extended_size: usize,
+    pub pruned: usize,
+    pub found: usize,
+    /// LF-mapping steps for locating occurrences.
+    pub lf_steps: usize,
+}
+
+impl SearchStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mean size of the extended intervals (`NaN` without extensions).
+    pub fn mean_extended_size(&self) -> f64 {
+        self.extended_size as f64 / self.extensions as f64
+    }
+}
+
+impl SearchObserver for SearchStats {
+    fn extension(&mut self, size: usize) {
+        self.extensions += 1;
+        self.extended_size += size;
+        if size == 0 {
+            self.empty_extensions += 1;
+        }
+    }
+
+    fn pruned(&mut self) {
+        self.pruned += 1;
+    }
+
+    fn found(&mut self, _size: usize) {
+        self.found += 1;
+    }
+
+    fn lf_step(&mut self) {
+        self.lf_steps += 1;
     }
 }
 
@@ -81,10 +256,14 @@ pub trait FMIndexable {
     fn occ(&self, r: usize, a: u8) -> usize;
     /// Also known as
     fn less(&self, a: u8) -> usize;
-    fn bwt(&self) -> &BWT;
+    /// Length of the BWT.
+    fn bwt_len(&self) -> usize;
+    /// Symbol at row r of the BWT.
+    fn bwt_symbol(&self, r: usize) -> u8;
 
     /// Perform backward search, yielding suffix array
     /// interval denoting exact occurrences of the given pattern of length m in the text.
+    /// If the pattern does not occur, the interval is empty.
     /// Complexity: O(m).
     ///
     /// # Arguments
@@ -118,304 +297,3319 @@ pub trait FMIndexable {
         &self,
         pattern: P,
     ) -> Interval {
-        let (mut l, mut r) = (0, self.bwt().len() - 1);
-        for &a in pattern.rev() {
-            let less = self.less(a);
-            l = less + if l > 0 { self.occ(l - 1, a) } else { 0 };
-            r = less + self.occ(r, a) - 1;
-        }
-
-        Interval {
-            lower: l,
-            upper: r + 1,
-        }
-    }
-}
-
-/// The Fast Index in Minute space (FM-Index, Ferragina and Manzini, 2000) for finding suffix array
-/// intervals matching a given pattern.
-#[derive(Serialize, Deserialize)]
-pub struct FMIndex<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> {
-    bwt: DBWT,
-    less: DLess,
-    occ: DOcc,
-}
-
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndexable
-    for FMIndex<DBWT, DLess, DOcc>
-{
-    fn occ(&self, r: usize, a: u8) -> usize {
-        self.occ.borrow().get(self.bwt.borrow(), r, a)
-    }
-    fn less(&self, a: u8) -> usize {
-        self.less.borrow()[a as usize]
+        self.backward_search_observed(pattern, &mut ())
     }
-    /// Provide a reference to the underlying BWT.
-    fn bwt(&self) -> &BWT {
-        self.bwt.borrow()
-    }
-}
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndex<DBWT, DLess, DOcc> {
-    /// Construct a new instance of the FM index.
+    /// Perform backward search for each of the given patterns, sharing the index between the
+    /// given number of threads. The intervals are returned in the order of the patterns.
+    /// Threads are not used when compiling to WebAssembly or with the `wasm` feature.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `bwt` - the BWT
-    /// * `less` - the less array of the BWT
-    /// * `occ` - the occurence array of the BWT
-    pub fn new(bwt: DBWT, less: DLess, occ: DOcc) -> Self {
-        FMIndex { bwt, less, occ }
+    /// ```
+    /// use bio::data_structures::fmindex::{FMIndexable, IndexBuilder};
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let index = IndexBuilder::new().build(text).unwrap();
+    /// let patterns = [&b"TTA"[..], b"GCC", b"GGG"];
+    /// let counts: Vec<_> = index
+    ///     .backward_search_batch(&patterns, 2)
+    ///     .iter()
+    ///     .map(|interval| interval.len())
+    ///     .collect();
+    /// assert_eq!(counts, [3, 2, 0]);
+    /// ```
+    fn backward_search_batch(&self, patterns: &[&[u8]], threads: usize) -> Vec<Interval>
+    where
+        Self: Sync,
+    {
+        assert!(threads > 0, "Expecting at least one thread.");
+        if threads == 1 || cfg!(any(feature = "wasm", target_arch = "wasm32")) {
+            return patterns
+                .iter()
+                .map(|pattern| self.backward_search(pattern.iter()))
+                .collect();
+        }
+        let mut intervals = vec![Interval { lower: 0, upper: 0 }; patterns.len()];
+        let chunk_len = patterns.len().div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            for (intervals, patterns) in intervals
+                .chunks_mut(chunk_len)
+                .zip(patterns.chunks(chunk_len))
+            {
+                scope.spawn(move || {
+                    for (interval, pattern) in intervals.iter_mut().zip(patterns) {
+                        *interval = self.backward_search(pattern.iter());
+                    }
+                });
+            }
+        });
+        intervals
     }
-}
-
-/// A bi-interval on suffix array of the forward and reverse strand of a DNA text.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub struct BiInterval {
-    lower: usize,
-    lower_rev: usize,
-    size: usize,
-    match_size: usize,
-}
 
-impl BiInterval {
-    pub fn forward(&self) -> Interval {
-        Interval {
-            upper: self.lower + self.size,
-            lower: self.lower,
+    /// Perform backward search as `backward_search`, reporting the steps of the search to the
+    /// given observer.
+    fn backward_search_observed<
+        'b,
+        P: Iterator<Item = &'b u8> + DoubleEndedIterator,
+        O: SearchObserver,
+    >(
+        &self,
+        pattern: P,
+        observer: &mut O,
+    ) -> Interval {
+        let mut interval = Interval {
+            lower: 0,
+            upper: self.bwt_len(),
+        };
+        for &a in pattern.rev() {
+            if interval.is_empty() {
+                // the pattern does not occur
+                break;
+            }
+            interval = self.backward_ext(&interval, a);
+            observer.extension(interval.len());
         }
-    }
-    pub fn revcomp(&self) -> Interval {
-        Interval {
-            upper: self.lower_rev + self.size,
-            lower: self.lower_rev,
+        if !interval.is_empty() {
+            observer.found(interval.len());
         }
+
+        interval
     }
 
-    fn swapped(&self) -> BiInterval {
-        BiInterval {
-            lower: self.lower_rev,
-            lower_rev: self.lower,
-            size: self.size,
-            match_size: self.match_size,
+    /// Extend the given non-empty interval of the suffixes starting with a pattern P to the
+    /// interval of the suffixes starting with aP.
+    fn backward_ext(&self, interval: &Interval, a: u8) -> Interval {
+        let less = self.less(a);
+        Interval {
+            lower: less
+                + if interval.lower > 0 {
+                    self.occ(interval.lower - 1, a)
+                } else {
+                    0
+                },
+            upper: less + self.occ(interval.upper - 1, a),
         }
     }
-}
-
-/// The FMD-Index for linear time search of supermaximal exact matches on forward and reverse
-/// strand of DNA texts (Li, 2012).
-#[derive(Serialize, Deserialize)]
-pub struct FMDIndex<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> {
-    fmindex: FMIndex<DBWT, DLess, DOcc>,
-}
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndexable
-    for FMDIndex<DBWT, DLess, DOcc>
-{
-    fn occ(&self, r: usize, a: u8) -> usize {
-        self.fmindex.occ(r, a)
+    /// Perform backward search allowing up to `max_mismatches` substitutions by the given
+    /// symbols (as the backtracking of BWA), yielding the suffix array intervals of all
+    /// matching words with their number of mismatches, sorted by the latter.
+    /// Complexity: O(m * s^k) for s symbols and k mismatches in the worst case.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::alphabets::dna;
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fm = FMIndex::new(&bwt, &less, &occ);
+    ///
+    /// let intervals = fm.backward_search_mismatches(b"TTAG", 1, b"ACGT");
+    /// let positions: Vec<_> = intervals.iter().map(|i| (i.interval.occ(&sa), i.mismatches)).collect();
+    /// // TTAA, TTAC and TTAT
+    /// assert_eq!(positions, [(vec![3], 1), (vec![12], 1), (vec![9], 1)]);
+    /// ```
+    fn backward_search_mismatches(
+        &self,
+        pattern: &[u8],
+        max_mismatches: usize,
+        symbols: &[u8],
+    ) -> Vec<MismatchInterval> {
+        self.backward_search_mismatches_observed(pattern, max_mismatches, symbols, &mut ())
     }
 
-    fn less(&self, a: u8) -> usize {
-        self.fmindex.less(a)
+    /// Perform backward search allowing mismatches as `backward_search_mismatches`, reporting
+    /// the steps of the search to the given observer.
+    fn backward_search_mismatches_observed<O: SearchObserver>(
+        &self,
+        pattern: &[u8],
+        max_mismatches: usize,
+        symbols: &[u8],
+        observer: &mut O,
+    ) -> Vec<MismatchInterval> {
+        let mut intervals = Vec::new();
+        // depth-first search over the prefixes of the reversed pattern
+        let mut stack = vec![(
+            pattern.len(),
+            MismatchInterval {
+                interval: Interval {
+                    lower: 0,
+                    upper: self.bwt_len(),
+                },
+                mismatches: 0,
+            },
+        )];
+        while let Some((i, current)) = stack.pop() {
+            if i == 0 {
+                observer.found(current.interval.len());
+                intervals.push(current);
+                continue;
+            }
+            let a = pattern[i - 1];
+            for &b in symbols {
+                let mismatches = current.mismatches + (a != b) as usize;
+                if mismatches > max_mismatches {
+                    observer.pruned();
+                    continue;
+                }
+                let interval = self.backward_ext(&current.interval, b);
+                observer.extension(interval.len());
+                if !interval.is_empty() {
+                    stack.push((
+                        i - 1,
+                        MismatchInterval {
+                            interval,
+                            mismatches,
+                        },
+                    ));
+                }
+            }
+        }
+        intervals.sort_by_key(|i| (i.mismatches, i.interval.lower));
+        intervals
     }
 
-    /// Provide a reference to the underlying BWT.
-    fn bwt(&self) -> &BWT {
-        self.fmindex.bwt()
+    /// Perform backward search for a pattern containing IUPAC ambiguity codes (e.g. `N` or
+    /// `R`), branching over the bases matched by each ambiguous symbol (see
+    /// `alphabets::dna::iupac_bases`). Other symbols, including `A`, `C`, `G` and `T`, are
+    /// matched literally. Yields the union of the suffix array intervals of all matching words,
+    /// as sorted disjoint intervals, or `None` if more than `max_branches` words of a suffix of
+    /// the pattern occur in the text.
+    /// Complexity: O(m * b) for at most b branches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::alphabets::dna;
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fm = FMIndex::new(&bwt, &less, &occ);
+    ///
+    /// // TTAA, TTAC and TTAT
+    /// let intervals = fm.backward_search_iupac(b"TTAH", 10).unwrap();
+    /// let mut positions: Vec<_> = intervals.iter().flat_map(|i| i.occ(&sa)).collect();
+    /// positions.sort();
+    /// assert_eq!(positions, [3, 9, 12]);
+    /// // the last N alone matches four bases
+    /// assert_eq!(fm.backward_search_iupac(b"NNNN", 3), None);
+    /// ```
+    fn backward_search_iupac(&self, pattern: &[u8], max_branches: usize) -> Option<Vec<Interval>> {
+        self.backward_search_iupac_observed(pattern, max_branches, &mut ())
     }
-}
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> From<FMIndex<DBWT, DLess, DOcc>>
-    for FMDIndex<DBWT, DLess, DOcc>
-{
-    /// Construct a new instance of the FMD index (see Heng Li (2012) Bioinformatics).
-    /// This expects a BWT that was created from a text over the DNA alphabet with N
-    /// (`alphabets::dna::n_alphabet()`) consisting of the
-    /// concatenation with its reverse complement, separated by the sentinel symbol `$`.
-    /// I.e., let T be the original text and R be its reverse complement.
-    /// Then, the expected text is T$R$. Further, multiple concatenated texts are allowed, e.g.
-    /// T1$R1$T2$R2$T3$R3$.
-    ///
-    fn from(fmindex: FMIndex<DBWT, DLess, DOcc>) -> FMDIndex<DBWT, DLess, DOcc> {
-        let mut alphabet = dna::n_alphabet();
-        alphabet.insert(b'$');
-        assert!(
-            alphabet.is_word(fmindex.bwt()),
-            "Expecting BWT over the DNA alphabet (including N) with the sentinel $."
-        );
+    /// Perform backward search with IUPAC ambiguity codes as `backward_search_iupac`, reporting
+    /// the steps of the search to the given observer.
+    fn backward_search_iupac_observed<O: SearchObserver>(
+        &self,
+        pattern: &[u8],
+        max_branches: usize,
+        observer: &mut O,
+    ) -> Option<Vec<Interval>> {
+        let mut intervals = vec![Interval {
+            lower: 0,
+            upper: self.bwt_len(),
+        }];
+        for a in pattern.iter().rev() {
+            let symbols = match dna::iupac_bases(*a) {
+                Some(bases) if bases.len() > 1 => bases,
+                _ => std::slice::from_ref(a),
+            };
+            let mut extended = Vec::with_capacity(intervals.len() * symbols.len());
+            for interval in &intervals {
+                for &b in symbols {
+                    let interval = self.backward_ext(interval, b);
+                    observer.extension(interval.len());
+                    if !interval.is_empty() {
+                        extended.push(interval);
+                    }
+                }
+            }
+            if extended.len() > max_branches {
+                return None;
+            }
+            intervals = extended;
+            if intervals.is_empty() {
+                return Some(intervals);
+            }
+        }
 
-        FMDIndex { fmindex }
+        // intervals of distinct words are disjoint, adjacent ones are joined
+        intervals.sort_by_key(|interval| interval.lower);
+        let mut union: Vec<Interval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match union.last_mut() {
+                Some(last) if last.upper == interval.lower => last.upper = interval.upper,
+                _ => union.push(interval),
+            }
+        }
+        for interval in &union {
+            observer.found(interval.len());
+        }
+        Some(union)
     }
-}
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMDIndex<DBWT, DLess, DOcc> {
-    /// Find supermaximal exact matches of given pattern that overlap position i in the pattern.
-    /// Complexity O(m) with pattern of length m.
+    /// Count the occurrences of the given pattern in the text (0 if it does not occur).
+    /// Complexity: O(m).
     ///
     /// # Example
     ///
     /// ```
-    /// use bio::alphabets::dna;
-    /// use bio::data_structures::fmindex::{FMIndex, FMDIndex};
-    /// use bio::data_structures::suffix_array::suffix_array;
     /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::alphabets::dna;
     ///
-    /// let text = b"ATTC$GAAT$";
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
     /// let alphabet = dna::n_alphabet();
     /// let sa = suffix_array(text);
     /// let bwt = bwt(text, &sa);
     /// let less = less(&bwt, &alphabet);
     /// let occ = Occ::new(&bwt, 3, &alphabet);
     /// let fm = FMIndex::new(&bwt, &less, &occ);
-    /// let fmdindex = FMDIndex::from(fm);
     ///
-    /// let pattern = b"ATT";
-    /// let intervals = fmdindex.smems(pattern, 2);
-    ///
-    /// let forward_positions = intervals[0].forward().occ(&sa);
+    /// assert_eq!(fm.count(b"TTA".iter()), 3);
+    /// assert_eq!(fm.count(b"GGG".iter()), 0);
+    /// ```
+    fn count<'b, P: Iterator<Item = &'b u8> + DoubleEndedIterator>(&self, pattern: P) -> usize {
+        self.backward_search(pattern).len()
+    }
+
+    /// Perform backward search allowing up to `max_edits` substitutions, insertions (pattern
+    /// symbols missing in the text) and deletions (text symbols missing in the pattern) by the
+    /// given symbols, yielding the suffix array intervals of all matching words with their edit
+    /// distance and an edit script of the pattern against them (`Ins` and `Del` as in
+    /// `alignment::pairwise`, with the pattern as x), sorted by edit distance.
+    /// As in BWA, the search is pruned with lower bounds of the edits needed for the remaining
+    /// prefixes of the pattern, and deletions at the end of the pattern are omitted.
     ///
-    /// let revcomp_positions = intervals[0].revcomp().occ(&sa);
+    /// # Example
     ///
-    /// assert_eq!(forward_positions, [0]);
-    /// assert_eq!(revcomp_positions, [6]);
     /// ```
-    pub fn smems(&self, pattern: &[u8], i: usize) -> Vec<BiInterval> {
-        let curr = &mut Vec::new();
-        let prev = &mut Vec::new();
-        let mut matches = Vec::new();
-
-        let mut interval = self.init_interval_with(pattern[i]);
-
+    /// use bio::alignment::AlignmentOperation::*;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::alphabets::dna;
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fm = FMIndex::new(&bwt, &less, &occ);
+    ///
+    /// // ACATTAT with a deleted T
+    /// let hits = fm.backward_search_edits(b"ACATAT", 1, b"ACGT");
+    /// assert_eq!(hits[0].edits, 1);
+    /// assert_eq!(hits[0].interval.occ(&sa), [6]);
+    /// assert_eq!(hits[0].operations.iter().filter(|&&op| op == Del).count(), 1);
+    /// ```
+    fn backward_search_edits(
+        &self,
+        pattern: &[u8],
+        max_edits: usize,
+        symbols: &[u8],
+    ) -> Vec<EditInterval> {
+        self.backward_search_edits_observed(pattern, max_edits, symbols, &mut ())
+    }
+
+    /// Perform backward search allowing edits as `backward_search_edits`, reporting the steps of
+    /// the search to the given observer.
+    fn backward_search_edits_observed<O: SearchObserver>(
+        &self,
+        pattern: &[u8],
+        max_edits: usize,
+        symbols: &[u8],
+        observer: &mut O,
+    ) -> Vec<EditInterval> {
+        let whole = Interval {
+            lower: 0,
+            upper: self.bwt_len(),
+        };
+        // lower bounds of the edits needed for each prefix, from splitting it into substrings
+        // occurring in the text
+        let mut bounds = Vec::with_capacity(pattern.len());
+        let (mut edits, mut start) = (0, 0);
+        for i in 0..pattern.len() {
+            if self.backward_search(pattern[start..=i].iter()).is_empty() {
+                edits += 1;
+                start = i + 1;
+            }
+            bounds.push(edits);
+        }
+        let bound = |i: usize| if i == 0 { 0 } else { bounds[i - 1] };
+
+        let mut hits: HashMap<Interval, (usize, Vec<AlignmentOperation>)> = HashMap::new();
+        // depth-first search over the remaining prefix length, interval, edits and the edit
+        // script (reversed)
+        let mut stack = vec![(pattern.len(), whole, 0, Vec::new())];
+        while let Some((i, interval, edits, operations)) = stack.pop() {
+            if edits + bound(i) > max_edits {
+                observer.pruned();
+                continue;
+            }
+            if i == 0 {
+                observer.found(interval.len());
+                let better = hits.get(&interval).map_or(true, |hit| edits < hit.0);
+                if better {
+                    hits.insert(interval, (edits, operations));
+                }
+                continue;
+            }
+            let last = operations.last().cloned();
+            let a = pattern[i - 1];
+            // insertion, not directly following a deletion (which would be a substitution)
+            if edits < max_edits && last != Some(AlignmentOperation::Del) {
+                let mut operations = operations.clone();
+                operations.push(AlignmentOperation::Ins);
+                stack.push((i - 1, interval, edits + 1, operations));
+            }
+            for &b in symbols {
+                let extended = self.backward_ext(&interval, b);
+                observer.extension(extended.len());
+                if extended.is_empty() {
+                    continue;
+                }
+                let (op, cost) = if a == b {
+                    (AlignmentOperation::Match, 0)
+                } else {
+                    (AlignmentOperation::Subst, 1)
+                };
+                if edits + cost <= max_edits {
+                    let mut operations = operations.clone();
+                    operations.push(op);
+                    stack.push((i - 1, extended, edits + cost, operations));
+                }
+                // deletion, neither at the end of the pattern nor directly following an
+                // insertion
+                if edits < max_edits && last.is_some() && last != Some(AlignmentOperation::Ins) {
+                    let mut operations = operations.clone();
+                    operations.push(AlignmentOperation::Del);
+                    stack.push((i, extended, edits + 1, operations));
+                }
+            }
+        }
+
+        let mut hits: Vec<_> = hits
+            .into_iter()
+            .map(|(interval, (edits, mut operations))| {
+                operations.reverse();
+                EditInterval {
+                    interval,
+                    edits,
+                    operations,
+                }
+            })
+            .collect();
+        hits.sort_by_key(|hit| (hit.edits, hit.interval.lower, hit.interval.upper));
+        hits
+    }
+
+    /// Locate the text positions of the given suffix array interval with a sampled suffix
+    /// array, reconstructing unsampled entries by LF-mapping.
+    /// Complexity: O(k) per position with sampling rate k.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// use bio::alphabets::dna;
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let sampled = sa.sample(&bwt, 4);
+    /// let fm = FMIndex::new(&bwt, &less, &occ);
+    ///
+    /// let sai = fm.backward_search(b"TTA".iter());
+    /// assert_eq!(fm.locate(&sai, &sampled), [3, 12, 9]);
+    /// ```
+    fn locate<SA: SuffixArraySample + ?Sized>(&self, interval: &Interval, sa: &SA) -> Vec<usize> {
+        self.locate_observed(interval, sa, &mut ())
+    }
+
+    /// Locate the text positions of the given suffix array interval as `locate`, reporting
+    /// the LF-mapping steps to the given observer, e.g. for tuning the sampling rate.
+    fn locate_observed<SA: SuffixArraySample + ?Sized, O: SearchObserver>(
+        &self,
+        interval: &Interval,
+        sa: &SA,
+        observer: &mut O,
+    ) -> Vec<usize> {
+        (interval.lower..interval.upper)
+            .map(|r| {
+                let (mut r, mut offset) = (r, 0);
+                loop {
+                    if let Some(pos) = sa.get_sampled(r) {
+                        return pos + offset;
+                    }
+                    let a = self.bwt_symbol(r);
+                    r = self.less(a) + self.occ(r, a) - 1;
+                    offset += 1;
+                    observer.lf_step();
+                }
+            })
+            .collect()
+    }
+
+    /// Reconstruct the substring of the text of the given length starting at the given
+    /// position from the BWT, such that the text need not be kept after building the index.
+    /// The text is traversed backwards with LF-mapping, starting from the next position whose
+    /// row is kept in the given sampled inverse suffix array.
+    /// Complexity: O(len + k) LF-mapping steps for sampling rate k.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// use bio::alphabets::dna;
+    ///
+    /// let text = b"GCCTTAACATTA$TTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let isa = sa.sample_inverse(&bwt, 4);
+    /// let fm = FMIndex::new(&bwt, less(&bwt, &alphabet), Occ::new(&bwt, 3, &alphabet));
+    ///
+    /// assert_eq!(fm.extract(&isa, 3, 5), b"TTAAC");
+    /// assert_eq!(fm.extract(&isa, 10, 6), b"TA$TTA");
+    /// ```
+    fn extract(&self, isa: &SampledInverseSuffixArray, start: usize, len: usize) -> Vec<u8> {
+        let n = self.bwt_len();
+        assert_eq!(
+            isa.len(),
+            n,
+            "Expecting inverse suffix array of the same length."
+        );
+        let end = start + len;
+        assert!(end <= n, "Expecting range within the text.");
+        if len == 0 {
+            return Vec::new();
+        }
+        // the end of the text is the start of the rotation of position 0
+        let s = isa.sampling_rate();
+        let mut pos = cmp::min(end.div_ceil(s) * s, n);
+        let mut r = isa.get_sampled(pos % n).unwrap();
+        let mut text = Vec::with_capacity(len);
+        loop {
+            let a = self.bwt_symbol(r);
+            pos -= 1;
+            if pos < end {
+                text.push(a);
+            }
+            if pos == start {
+                break;
+            }
+            r = if a == b'$' {
+                // LF-mapping does not work at sentinels if the text contains several
+                isa.get_sampled(pos).unwrap()
+            } else {
+                self.less(a) + self.occ(r, a) - 1
+            };
+        }
+        text.reverse();
+        text
+    }
+}
+
+/// An FM-Index owning its BWT, less and occ arrays.
+pub type OwnedFMIndex = FMIndex<BWT, Less, Occ>;
+
+/// An FMD-Index owning its BWT, less and occ arrays.
+pub type OwnedFMDIndex = FMDIndex<BWT, Less, Occ>;
+
+/// An FM-Index owning its run-length encoded BWT (which counts occurrences itself) and less
+/// array.
+pub type RunLengthFMIndex = FMIndex<RunLengthBWT, Less, ()>;
+
+/// The Fast Index in Minute space (FM-Index, Ferragina and Manzini, 2000) for finding suffix array
+/// intervals matching a given pattern. The BWT is either stored plainly (`BWT`) with an occ
+/// array (`Occ`), or run-length encoded (`RunLengthBWT`) without occ array (`()`), e.g. for
+/// highly repetitive texts. Searches work the same on both.
+#[derive(Serialize, Deserialize)]
+pub struct FMIndex<DBWT, DLess, DOcc> {
+    bwt: DBWT,
+    less: DLess,
+    occ: DOcc,
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> FMIndexable
+    for FMIndex<DBWT, DLess, DOcc>
+{
+    fn occ(&self, r: usize, a: u8) -> usize {
+        self.occ.occ(self.bwt.as_bwt(), r, a)
+    }
+    fn less(&self, a: u8) -> usize {
+        // all suffixes are smaller than those starting with symbols beyond the alphabet
+        self.less.borrow()[..]
+            .get(a as usize)
+            .cloned()
+            .unwrap_or_else(|| self.bwt_len())
+    }
+    fn bwt_len(&self) -> usize {
+        self.bwt.as_bwt().len()
+    }
+    fn bwt_symbol(&self, r: usize) -> u8 {
+        self.bwt.as_bwt()[r]
+    }
+}
+
+impl<DBWT: Borrow<RunLengthBWT>, DLess: Borrow<Less>> FMIndexable for FMIndex<DBWT, DLess, ()> {
+    fn occ(&self, r: usize, a: u8) -> usize {
+        self.bwt.borrow().occ(r, a)
+    }
+    fn less(&self, a: u8) -> usize {
+        // all suffixes are smaller than those starting with symbols beyond the alphabet
+        self.less.borrow()[..]
+            .get(a as usize)
+            .cloned()
+            .unwrap_or_else(|| self.bwt_len())
+    }
+    fn bwt_len(&self) -> usize {
+        self.bwt.borrow().len()
+    }
+    fn bwt_symbol(&self, r: usize) -> u8 {
+        self.bwt.borrow().symbol(r)
+    }
+}
+
+impl<DBWT: Borrow<RunLengthBWT>, DLess: Borrow<Less>> FMIndex<DBWT, DLess, ()> {
+    /// Construct a new instance of the FM index on a run-length encoded BWT.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::bwt::{bwt, less, RunLengthBWT};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    ///
+    /// let text = b"GCCTTAACATTA$GCCTTAACATTA$GCCTTAGCATTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let fm = FMIndex::with_run_length_bwt(RunLengthBWT::new(&bwt, 4, &alphabet), less(&bwt, &alphabet));
+    ///
+    /// let sai = fm.backward_search(b"TTA".iter());
+    /// assert_eq!(sai.occ(&sa), [35, 22, 9, 16, 3, 29]);
+    /// ```
+    pub fn with_run_length_bwt(bwt: DBWT, less: DLess) -> Self {
+        FMIndex { bwt, less, occ: () }
+    }
+
+    /// Provide a reference to the underlying BWT.
+    pub fn bwt(&self) -> &RunLengthBWT {
+        self.bwt.borrow()
+    }
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> FMIndex<DBWT, DLess, DOcc> {
+    /// Construct a new instance of the FM index.
+    ///
+    /// # Arguments
+    ///
+    /// * `bwt` - the BWT
+    /// * `less` - the less array of the BWT
+    /// * `occ` - the occurence array of the BWT
+    pub fn new(bwt: DBWT, less: DLess, occ: DOcc) -> Self {
+        FMIndex { bwt, less, occ }
+    }
+
+    /// Provide a reference to the underlying BWT.
+    pub fn bwt(&self) -> &BWTSlice {
+        self.bwt.as_bwt()
+    }
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndex<DBWT, DLess, DOcc> {
+    /// Write the index in a compact binary format, which can be read with `FMIndex::read`.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let bwt = self.bwt.as_bwt();
+        let less = self.less.borrow();
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(bwt.len() as u64).to_le_bytes())?;
+        writer.write_all(bwt)?;
+        writer.write_all(&(less.len() as u64).to_le_bytes())?;
+        for &l in less {
+            writer.write_all(&(l as u64).to_le_bytes())?;
+        }
+        self.occ.borrow().write(writer)
+    }
+
+    /// Write the index to the given file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+}
+
+const MAGIC: &[u8; 8] = b"BIOFMI02";
+
+const MAPPED_MAGIC: &[u8; 8] = b"BIOFMM01";
+
+/// An FM-Index accessing its BWT and occ array in place in a byte buffer, e.g. a memory mapped
+/// file written with `SampledFMIndex::write_mapped`, instead of reading them into memory.
+pub type MappedFMIndex<'a> = FMIndex<&'a BWTSlice, Less, MappedOcc<'a>>;
+
+/// An FMD-Index accessing its BWT and occ array in place in a byte buffer (see
+/// `MappedFMIndex`).
+pub type MappedFMDIndex<'a> = FMDIndex<&'a BWTSlice, Less, MappedOcc<'a>>;
+
+impl<'a> MappedFMIndex<'a> {
+    /// Access the index written with `SampledFMIndex::write_mapped` to the given buffer, e.g.
+    /// a memory mapped file (`&mmap[..]`), together with its sampled suffix array. Only the
+    /// less array is copied, hence this takes constant time, and the pages of the file are
+    /// loaded by the operating system as searches access them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::{FMIndexable, IndexBuilder, MappedFMIndex};
+    ///
+    /// let index = IndexBuilder::new()
+    ///     .sa_sampling_rate(4)
+    ///     .build(b"GCCTTAACATTATTACGCCTA$")
+    ///     .unwrap();
+    /// let mut data = Vec::new();
+    /// index.write_mapped(&mut data).unwrap();
+    ///
+    /// let (fm, sa) = MappedFMIndex::from_bytes(&data).unwrap();
+    /// let interval = fm.backward_search(b"TTA".iter());
+    /// assert_eq!(fm.locate(&interval, &sa), [3, 12, 9]);
+    /// ```
+    pub fn from_bytes(mut data: &'a [u8]) -> io::Result<(Self, MappedSuffixArray<'a>)> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        if split_bytes(&mut data, MAPPED_MAGIC.len())? != MAPPED_MAGIC {
+            return Err(invalid("Not a mapped FM-Index."));
+        }
+        let n = read_u64(&mut data)? as usize;
+        let bwt = split_bytes(&mut data, n)?;
+        let mut less = Vec::new();
+        for _ in 0..read_u64(&mut data)? {
+            less.push(read_u64(&mut data)? as usize);
+        }
+        let occ = MappedOcc::parse(&mut data)?;
+        let sa = MappedSuffixArray::parse(&mut data)?;
+        if !data.is_empty() {
+            return Err(invalid("Trailing data after mapped FM-Index."));
+        }
+        if sa.len() != n || less.len() > 256 {
+            return Err(invalid("Inconsistent mapped FM-Index."));
+        }
+        Ok((FMIndex::new(bwt, less, occ), sa))
+    }
+}
+
+impl<'a> MappedFMDIndex<'a> {
+    /// Access the FMD-Index written with `SampledFMIndex::write_mapped` to the given buffer, as
+    /// `MappedFMIndex::from_bytes`. Instead of scanning the BWT, the occ array is checked to
+    /// count only DNA symbols (including `N`) and the sentinel.
+    pub fn from_bytes(data: &'a [u8]) -> io::Result<(Self, MappedSuffixArray<'a>)> {
+        let (fmindex, sa) = MappedFMIndex::from_bytes(data)?;
+        let mut alphabet = dna::n_alphabet();
+        alphabet.insert(b'$');
+        let n = fmindex.bwt_len();
+        if n > 0
+            && (0..=255u8)
+                .any(|a| !alphabet.symbols.contains(a as usize) && fmindex.occ(n - 1, a) > 0)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Expecting BWT over the DNA alphabet (including N) with the sentinel $.",
+            ));
+        }
+        let fmdindex = FMDIndex {
+            fmindex,
+            seqs: SequenceMap::new(),
+        };
+        Ok((fmdindex, sa))
+    }
+}
+
+impl OwnedFMIndex {
+    /// Build an index of the given text ending with the sentinel `$`, computing its suffix
+    /// array, BWT, less and occ arrays (with sampling rate `k`) over the given alphabet.
+    /// The suffix array is dropped; use `IndexBuilder` for keeping a sampled suffix array to
+    /// locate occurrences.
+    pub fn from_text(text: &[u8], k: u32, alphabet: &Alphabet) -> Self {
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, alphabet);
+        let occ = Occ::new(&bwt, k, alphabet);
+        FMIndex::new(bwt, less, occ)
+    }
+
+    /// Read an index written with `FMIndex::write`.
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not an FM-Index.",
+            ));
+        }
+        let n = read_u64(&mut reader)?;
+        let mut bwt = Vec::new();
+        if (&mut reader).take(n).read_to_end(&mut bwt)? as u64 != n {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let mut less = Vec::new();
+        for _ in 0..read_u64(&mut reader)? {
+            less.push(read_u64(&mut reader)? as usize);
+        }
+        let occ = Occ::read(reader)?;
+        Ok(FMIndex::new(bwt, less, occ))
+    }
+
+    /// Load an index from the given file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(io::BufReader::new(fs::File::open(path)?))
+    }
+
+    /// Append the given text (one or more sequences, each ended by the sentinel `$`) to the
+    /// indexed text, by merging the BWT of the text into the existing one instead of
+    /// reconstructing the index (as in the BWT merging of Holt and McMillan, 2014). Positions
+    /// in a suffix array of the previous text remain valid, but its rows change (see
+    /// `append_with_suffix_array`).
+    /// Complexity: O(m) for constructing the suffix array of the text of length m and O(n + m)
+    /// for the merge and rebuilding the less and occ arrays.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let text_bwt = bwt(text, &suffix_array(text));
+    /// let less = less(&text_bwt, &alphabet);
+    /// let occ = Occ::new(&text_bwt, 3, &alphabet);
+    /// let mut fm = FMIndex::new(text_bwt, less, occ);
+    ///
+    /// fm.append(b"TTAGG$");
+    /// assert_eq!(fm.count(b"TTA".iter()), 4);
+    ///
+    /// // as built from scratch
+    /// let concatenated = b"GCCTTAACATTATTACGCCTA$TTAGG$";
+    /// assert_eq!(fm.bwt(), &bwt(concatenated, &suffix_array(concatenated)));
+    /// ```
+    pub fn append(&mut self, text: &[u8]) {
+        self.merge(text, None);
+    }
+
+    /// Append the given text as `append`, updating the given suffix array of the previous text
+    /// to the one of the concatenated text.
+    pub fn append_with_suffix_array(&mut self, text: &[u8], sa: &mut RawSuffixArray) {
+        assert_eq!(
+            sa.len(),
+            self.bwt.len(),
+            "Expecting suffix array of the indexed text."
+        );
+        self.merge(text, Some(sa));
+    }
+
+    fn merge(&mut self, text: &[u8], sa: Option<&mut RawSuffixArray>) {
+        assert_eq!(
+            text.last(),
+            Some(&b'$'),
+            "Expecting text ended by the sentinel $."
+        );
+        let (n, m) = (self.bwt.len(), text.len());
+        // symbols beyond the largest one have no occurrences in the indexed text
+        let max_symbol = self.less.len() - 2;
+        // number of suffixes of the indexed text that are smaller than each suffix of the
+        // appended text, found by backward search; the sentinels of the appended text are
+        // smaller than the previous ones
+        let mut ranks = vec![0; m];
+        for i in (0..m - 1).rev() {
+            let a = text[i];
+            ranks[i] = if a == b'$' {
+                0
+            } else if a as usize > max_symbol {
+                n
+            } else {
+                let r = ranks[i + 1];
+                self.less(a) + if r > 0 { self.occ(r - 1, a) } else { 0 }
+            };
+        }
+
+        let mut bwt = Vec::with_capacity(n + m);
+        let mut merged_sa = Vec::with_capacity(if sa.is_some() { n + m } else { 0 });
+        let mut r = 0;
+        for p in suffix_array(text) {
+            // suffixes of the previous text with equal prefixes are larger due to the sentinels
+            while r < ranks[p] {
+                bwt.push(self.bwt[r]);
+                if let Some(ref sa) = sa {
+                    merged_sa.push(sa[r]);
+                }
+                r += 1;
+            }
+            // the first suffix is preceded by the last sentinel of the previous text
+            bwt.push(if p > 0 { text[p - 1] } else { b'$' });
+            if sa.is_some() {
+                merged_sa.push(n + p);
+            }
+        }
+        bwt.extend_from_slice(&self.bwt[r..]);
+        if let Some(sa) = sa {
+            merged_sa.extend_from_slice(&sa[r..]);
+            *sa = merged_sa;
+        }
+
+        let mut alphabet = Alphabet::new(&bwt);
+        // keep the symbols that can be queried
+        alphabet.insert(max_symbol as u8);
+        self.less = less(&bwt, &alphabet);
+        self.occ = Occ::new(&bwt, self.occ.sampling_rate(), &alphabet);
+        self.bwt = bwt;
+    }
+}
+
+impl OwnedFMDIndex {
+    /// Build an index of the given text of DNA sequences and their reverse complements, each
+    /// ended by the sentinel `$` (e.g. `ACGA$TCGT$`), with occ sampling rate `k`. Fails with
+    /// the first symbol not in the DNA alphabet (including N). Use `FMDIndex::from_texts` for
+    /// building the text from the sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::{FMDIndex, FMIndexable};
+    ///
+    /// let fmdindex = FMDIndex::from_text(b"ATTGGCA$TGCCAAT$", 3).unwrap();
+    /// assert_eq!(fmdindex.count(b"GCCA".iter()), 1);
+    /// assert!(FMDIndex::from_text(b"ATXG$CXAT$", 3).is_err());
+    /// ```
+    pub fn from_text(text: &[u8], k: u32) -> Result<Self, Error> {
+        FMDIndex::new(FMIndex::from_text(text, k, &dna::n_alphabet()))
+    }
+
+    /// Build the FMD-Index of the given DNA sequences (without sentinels), constructing the
+    /// text T1$R1$T2$R2$... with the reverse complements Ri, its suffix array and BWT, and
+    /// occ with sampling rate `k`. The suffix array is returned for locating occurrences,
+    /// which can be mapped back to the sequences with `sequence_map`. Fails with the first
+    /// symbol not in the DNA alphabet (including N), with its position in its sequence.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate bio;
+    /// # extern crate bio_types;
+    /// use bio::data_structures::fmindex::{FMDIndex, TextPosition};
+    /// use bio_types::strand::ReqStrand;
+    ///
+    /// let seqs = [&b"ATTC"[..], b"GGATTA"];
+    /// let (fmdindex, sa) = FMDIndex::from_texts(seqs.iter().cloned(), 3).unwrap();
+    ///
+    /// // AAT matches the reverse strand of both sequences
+    /// let intervals = fmdindex.smems(b"AAT", 0);
+    /// let mut positions = fmdindex.sequence_map().occ(&intervals[0], &sa);
+    /// positions.sort_by_key(|pos| pos.seq);
+    /// assert_eq!(
+    ///     positions,
+    ///     [
+    ///         TextPosition { seq: 0, offset: 0, strand: ReqStrand::Reverse },
+    ///         TextPosition { seq: 1, offset: 2, strand: ReqStrand::Reverse },
+    ///     ]
+    /// );
+    /// ```
+    pub fn from_texts<'a, I: IntoIterator<Item = &'a [u8]>>(
+        texts: I,
+        k: u32,
+    ) -> Result<(Self, RawSuffixArray), Error> {
+        let mut text = Vec::new();
+        let mut seqs = SequenceMap::new();
+        for seq in texts {
+            seqs.push(seq.len());
+            text.extend_from_slice(&Self::append_text(seq)?);
+        }
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, k, &alphabet);
+        let mut fmdindex = FMDIndex::new(FMIndex::new(bwt, less, occ))?;
+        fmdindex.seqs = seqs;
+        Ok((fmdindex, sa))
+    }
+
+    /// Append the given DNA sequence (without sentinel) and its reverse complement to the
+    /// indexed text without reconstructing the index (see `FMIndex::append`), failing with the
+    /// first symbol not in the DNA alphabet (including N).
+    pub fn append(&mut self, seq: &[u8]) -> Result<(), Error> {
+        let text = Self::append_text(seq)?;
+        self.record_seq(seq);
+        self.fmindex.append(&text);
+        Ok(())
+    }
+
+    /// Append the given DNA sequence as `append`, updating the given suffix array of the
+    /// previous text to the one of the concatenated text.
+    pub fn append_with_suffix_array(
+        &mut self,
+        seq: &[u8],
+        sa: &mut RawSuffixArray,
+    ) -> Result<(), Error> {
+        let text = Self::append_text(seq)?;
+        self.record_seq(seq);
+        self.fmindex.append_with_suffix_array(&text, sa);
+        Ok(())
+    }
+
+    /// Record the start of a sequence appended to an index that knows its sequences.
+    fn record_seq(&mut self, seq: &[u8]) {
+        if !self.seqs.is_empty() {
+            self.seqs.push(seq.len());
+        }
+    }
+
+    /// The text S$R$ for sequence S with reverse complement R.
+    fn append_text(seq: &[u8]) -> Result<Vec<u8>, Error> {
+        let alphabet = dna::n_alphabet();
+        if let Some((pos, &symbol)) = seq
+            .iter()
+            .enumerate()
+            .find(|&(_, &c)| !alphabet.symbols.contains(c as usize))
+        {
+            return Err(Error::InvalidSymbol { symbol, pos });
+        }
+        Ok([seq, b"$", &dna::revcomp(seq), b"$"].concat())
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum IndexBuildError {
+        MissingSentinel {
+            description("text not ended by sentinel")
+            display("expecting text ended by the sentinel $")
+        }
+        InvalidSymbol(symbol: u8) {
+            description("symbol not in alphabet")
+            display("symbol {} of the text is not in the alphabet", *symbol as char)
+        }
+        TooLong(len: usize) {
+            description("text too long for the offset type")
+            display("text of length {} exceeds the offset type", len)
+        }
+        MemoryLimit(required: usize, limit: usize) {
+            description("memory limit exceeded")
+            display(
+                "building the index requires about {} bytes, exceeding the limit of {} bytes",
+                required,
+                limit
+            )
+        }
+    }
+}
+
+/// An FM-Index with a sampled suffix array for locating occurrences, as built by
+/// `IndexBuilder`. The entries of the sampled suffix array are stored with the offset type `I`
+/// (see `IndexBuilder::build_sized`). The BWT, the less array and the occ array do not depend
+/// on it: the BWT is stored with one byte per row, the occ array as packed symbol codes with
+/// 16 bit block counts, and only O(A) entries of the less array and O(A n / 2^16) superblock
+/// counts of the occ array are stored with full width.
+#[derive(Serialize, Deserialize)]
+pub struct SampledFMIndex<I = usize> {
+    fmindex: OwnedFMIndex,
+    sa: SampledSuffixArray<I>,
+}
+
+impl<I: SizedInt> FMIndexable for SampledFMIndex<I> {
+    fn occ(&self, r: usize, a: u8) -> usize {
+        self.fmindex.occ(r, a)
+    }
+
+    fn less(&self, a: u8) -> usize {
+        self.fmindex.less(a)
+    }
+
+    fn bwt_len(&self) -> usize {
+        self.fmindex.bwt_len()
+    }
+
+    fn bwt_symbol(&self, r: usize) -> u8 {
+        self.fmindex.bwt_symbol(r)
+    }
+}
+
+impl<I: SizedInt> SampledFMIndex<I> {
+    /// Provide a reference to the underlying BWT.
+    pub fn bwt(&self) -> &BWT {
+        &self.fmindex.bwt
+    }
+
+    /// Write the index such that it can be accessed in place with `MappedFMIndex::from_bytes`
+    /// or `MappedFMDIndex::from_bytes`, e.g. from a memory mapped file. Unlike
+    /// `FMIndex::write`, this includes the checkpoints of the occ array and the sampled suffix
+    /// array.
+    pub fn write_mapped<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let bwt = self.bwt();
+        writer.write_all(MAPPED_MAGIC)?;
+        writer.write_all(&(bwt.len() as u64).to_le_bytes())?;
+        writer.write_all(bwt)?;
+        writer.write_all(&(self.fmindex.less.len() as u64).to_le_bytes())?;
+        for &l in &self.fmindex.less {
+            writer.write_all(&(l as u64).to_le_bytes())?;
+        }
+        self.fmindex.occ.write_mapped(&mut writer)?;
+        self.sa.write_mapped(writer)
+    }
+
+    /// Write the index to the given file as `write_mapped`.
+    pub fn save_mapped<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        self.write_mapped(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Combine an FM-Index with the sampled suffix array of the same text.
+    pub fn new(fmindex: OwnedFMIndex, sa: SampledSuffixArray<I>) -> Result<Self, Error> {
+        if fmindex.bwt().len() != sa.len() {
+            return Err(Error::IndexMismatch(format!(
+                "BWT of length {}, but suffix array of length {}",
+                fmindex.bwt().len(),
+                sa.len()
+            )));
+        }
+        Ok(SampledFMIndex { fmindex, sa })
+    }
+
+    pub fn fmindex(&self) -> &OwnedFMIndex {
+        &self.fmindex
+    }
+
+    pub fn suffix_array(&self) -> &SampledSuffixArray<I> {
+        &self.sa
+    }
+
+    /// Text positions of the occurrences of the given pattern (in suffix array order).
+    pub fn find(&self, pattern: &[u8]) -> Vec<usize> {
+        self.find_observed(pattern, &mut ())
+    }
+
+    /// Text positions of the occurrences of the given pattern as `find`, reporting the steps
+    /// of searching and locating to the given observer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::{IndexBuilder, SearchStats};
+    ///
+    /// let index = IndexBuilder::new()
+    ///     .sa_sampling_rate(8)
+    ///     .build(b"GCCTTAACATTATTACGCCTA$")
+    ///     .unwrap();
+    /// let mut stats = SearchStats::new();
+    /// assert_eq!(index.find_observed(b"TTA", &mut stats).len(), 3);
+    /// assert_eq!((stats.extensions, stats.found), (3, 1));
+    /// assert!(stats.lf_steps > 0);
+    /// ```
+    pub fn find_observed<O: SearchObserver>(&self, pattern: &[u8], observer: &mut O) -> Vec<usize> {
+        let interval = self.backward_search_observed(pattern.iter(), observer);
+        self.locate_observed(&interval, &self.sa, observer)
+    }
+
+    /// Merge this index with the index of another sequence collection into the index of the
+    /// text of this index followed by the other text (Holt and McMillan, 2014), e.g. for
+    /// building indexes of chunks of a collection in parallel or on several machines. The
+    /// texts are not needed: the other text is traversed backwards with its index, while
+    /// ranking its suffixes among the suffixes of this text. The sampling rates of this index
+    /// are kept.
+    /// Complexity: O(n + m) for texts of length n and m, and O(m) additional memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::IndexBuilder;
+    ///
+    /// let builder = IndexBuilder::new().sa_sampling_rate(4);
+    /// let chunks = [&b"GCCTTAACATTA$"[..], b"TTACGCCTA$ACGT$"];
+    /// let indexes: Vec<_> = chunks.iter().map(|chunk| builder.build(chunk).unwrap()).collect();
+    /// let merged = indexes[0].merge(&indexes[1]);
+    ///
+    /// let mut positions = merged.find(b"TTA");
+    /// positions.sort();
+    /// assert_eq!(positions, [3, 9, 13]);
+    /// ```
+    pub fn merge(&self, other: &SampledFMIndex<I>) -> SampledFMIndex<I> {
+        let (n, m) = (self.bwt().len(), other.bwt().len());
+        assert!(
+            I::fits(n + m),
+            "Expecting merged text length within the offset type."
+        );
+        let (bwt1, bwt2) = (self.bwt(), other.bwt());
+        // symbols beyond the largest one have no occurrences in this text
+        let max_symbol = self.fmindex.less.len() - 2;
+
+        // the rows of the suffixes starting with a sentinel are ordered by descending position
+        let mut sentinel_positions: Vec<usize> = (0..m)
+            .filter(|&r| bwt2[r] == b'$')
+            .map(|r| {
+                let pos = other.locate(
+                    &Interval {
+                        lower: r,
+                        upper: r + 1,
+                    },
+                    &other.sa,
+                )[0];
+                (pos + m - 1) % m
+            })
+            .collect();
+        sentinel_positions.sort_unstable_by(|a, b| b.cmp(a));
+
+        // traverse the other text backwards, starting with the row of its last sentinel,
+        // recording the position of each row and the number of suffixes of this text that are
+        // smaller (none for suffixes starting with a sentinel of the other text)
+        let (mut positions, mut ranks) = (vec![0; m], vec![0; m]);
+        let (mut row, mut rank) = (0, 0);
+        positions[0] = m - 1;
+        for pos in (0..m - 1).rev() {
+            let a = bwt2[row];
+            if a == b'$' {
+                row = sentinel_positions
+                    .binary_search_by(|p| pos.cmp(p))
+                    .expect("Expecting sentinel in the text.");
+                rank = 0;
+            } else {
+                row = other.less(a) + other.occ(row, a) - 1;
+                rank = if a as usize > max_symbol {
+                    n
+                } else {
+                    self.less(a) + if rank > 0 { self.occ(rank - 1, a) } else { 0 }
+                };
+            }
+            positions[row] = pos;
+            ranks[row] = rank;
+        }
+
+        // interleave the rows, where suffixes of the other text with equal prefixes come first
+        // due to their smaller sentinels
+        let s = self.sa.sampling_rate();
+        let mut bwt = Vec::with_capacity(n + m);
+        let mut sample = Vec::with_capacity((n + m) / s + 1);
+        let mut sentinels = HashMap::new();
+        let mut add_row = |bwt: &mut BWT, a: u8, pos: &dyn Fn() -> usize| {
+            let r = bwt.len();
+            if r % s == 0 {
+                sample.push(I::from_usize(pos()));
+            } else if a == b'$' {
+                sentinels.insert(r, pos());
+            }
+            bwt.push(a);
+        };
+        let locate = |r: usize| {
+            self.locate(
+                &Interval {
+                    lower: r,
+                    upper: r + 1,
+                },
+                &self.sa,
+            )[0]
+        };
+        let mut r1 = 0;
+        for r2 in 0..m {
+            while r1 < ranks[r2] {
+                add_row(&mut bwt, bwt1[r1], &|| locate(r1));
+                r1 += 1;
+            }
+            add_row(&mut bwt, bwt2[r2], &|| n + positions[r2]);
+        }
+        for (r1, &a) in bwt1.iter().enumerate().skip(r1) {
+            add_row(&mut bwt, a, &|| locate(r1));
+        }
+
+        let mut alphabet = Alphabet::new(&bwt);
+        // keep the symbols that can be queried in both indexes
+        alphabet.insert(max_symbol.max(other.fmindex.less.len() - 2) as u8);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, self.fmindex.occ.sampling_rate(), &alphabet);
+        SampledFMIndex {
+            fmindex: FMIndex::new(bwt, less, occ),
+            sa: SampledSuffixArray::from_parts(sample, sentinels, s, n + m),
+        }
+    }
+}
+
+/// Builder of FM-Indexes with sampled suffix arrays.
+pub struct IndexBuilder {
+    occ_sampling_rate: u32,
+    sa_sampling_rate: usize,
+    alphabet: Option<Alphabet>,
+    threads: usize,
+    memory_budget: MemoryBudget,
+}
+
+impl Default for IndexBuilder {
+    fn default() -> Self {
+        IndexBuilder {
+            occ_sampling_rate: 32,
+            sa_sampling_rate: 32,
+            alphabet: None,
+            threads: 1,
+            memory_budget: MemoryBudget::Unlimited,
+        }
+    }
+}
+
+impl IndexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a block checkpoint of the occ array every k rows (default 32, see `Occ::new`).
+    /// Rates above 64 save memory, but slow down searches.
+    pub fn occ_sampling_rate(mut self, k: u32) -> Self {
+        assert!(k > 0, "Expecting positive sampling rate.");
+        self.occ_sampling_rate = k;
+        self
+    }
+
+    /// Keep every k-th entry of the suffix array (default 32). Larger rates save memory, but
+    /// slow down locating occurrences.
+    pub fn sa_sampling_rate(mut self, k: usize) -> Self {
+        assert!(k > 0, "Expecting positive sampling rate.");
+        self.sa_sampling_rate = k;
+        self
+    }
+
+    /// Alphabet of the texts besides the sentinel `$` (default: the symbols of each text).
+    /// Texts with other symbols are rejected.
+    pub fn alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = Some(alphabet);
+        self
+    }
+
+    /// Number of threads used for computing the BWT from the suffix array (default 1). Only
+    /// this step is parallelized: the suffix array, its sampling, the occ array and the merging
+    /// of indexes built within a memory budget are computed by the calling thread. Ignored when
+    /// compiling to WebAssembly or with the `wasm` feature.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Expecting at least one thread.");
+        self.threads = threads;
+        self
+    }
+
+    /// Limit the memory used while building to about the given number of bytes (default: no
+    /// limit). This is a shorthand for `memory_budget(MemoryBudget::Bytes(bytes))`, and
+    /// replaces any previously set budget.
+    pub fn memory_limit(self, bytes: usize) -> Self {
+        self.memory_budget(MemoryBudget::Bytes(bytes))
+    }
+
+    /// Build within the given memory budget (default: unlimited). If building the index of the
+    /// whole text would exceed the budget, the sequences of the text (separated by sentinels)
+    /// are indexed in groups fitting into the budget, and the indexes are merged (see
+    /// `SampledFMIndex::merge`). This is slower, and texts with single sequences too large for
+    /// the budget are rejected with `IndexBuildError::MemoryLimit`. Replaces any limit set by
+    /// `memory_limit`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::{FMIndexable, IndexBuilder};
+    /// use bio::utils::MemoryBudget;
+    ///
+    /// let text = b"GCCTTAACATTA$TTACGCCTA$ACGTACGTTA$";
+    /// let index = IndexBuilder::new()
+    ///     .memory_budget(MemoryBudget::Bytes(1500))
+    ///     .build(text)
+    ///     .unwrap();
+    /// let mut positions = index.find(b"TTA");
+    /// positions.sort();
+    /// assert_eq!(positions, [3, 9, 13, 30]);
+    /// ```
+    pub fn memory_budget(mut self, budget: MemoryBudget) -> Self {
+        self.memory_budget = budget;
+        self
+    }
+
+    /// Build the index of the given text, which has to be ended by the sentinel `$` (and may
+    /// contain further sentinels separating multiple sequences).
+    pub fn build<T: IndexableText + Sync + ?Sized>(
+        &self,
+        text: &T,
+    ) -> Result<SampledFMIndex, IndexBuildError> {
+        self.build_sized(text)
+    }
+
+    /// Build the index of the given text as `build`, constructing the suffix array and storing
+    /// its sample with the given offset type, e.g. `u32` for texts shorter than 4 GB, which
+    /// halves their memory on 64 bit machines. Texts too long for the offset type are rejected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::{FMIndexable, IndexBuilder, SampledFMIndex};
+    ///
+    /// let index: SampledFMIndex<u32> = IndexBuilder::new()
+    ///     .build_sized(b"GCCTTAACATTATTACGCCTA$")
+    ///     .unwrap();
+    /// let mut positions = index.find(b"TTA");
+    /// positions.sort();
+    /// assert_eq!(positions, [3, 9, 12]);
+    /// ```
+    pub fn build_sized<I: SizedInt, T: IndexableText + Sync + ?Sized>(
+        &self,
+        text: &T,
+    ) -> Result<SampledFMIndex<I>, IndexBuildError> {
+        let n = text.len();
+        if n == 0 || text.symbol(n - 1) != b'$' {
+            return Err(IndexBuildError::MissingSentinel);
+        }
+        if !I::fits(n) {
+            return Err(IndexBuildError::TooLong(n));
+        }
+        let mut alphabet = match self.alphabet {
+            Some(ref alphabet) => {
+                if let Some(symbol) = text
+                    .symbols(0..n)
+                    .find(|&c| c != b'$' && !alphabet.symbols.contains(c as usize))
+                {
+                    return Err(IndexBuildError::InvalidSymbol(symbol));
+                }
+                Alphabet {
+                    symbols: alphabet.symbols.clone(),
+                }
+            }
+            None => Alphabet::new(text.symbols(0..n)),
+        };
+        alphabet.insert(b'$');
+        let required = self.memory_estimate::<I>(text.len(), &alphabet);
+        if let Some(budget) = self.memory_budget.limit() {
+            if required > budget {
+                return self.build_chunked(text, alphabet, budget);
+            }
+        }
+
+        let sa = suffix_array_sized::<I, _>(text);
+        let bwt = self.bwt(text, &sa);
+        let sampled = sa.sample_sized(&bwt, self.sa_sampling_rate);
+        drop(sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, self.occ_sampling_rate, &alphabet);
+        Ok(SampledFMIndex {
+            fmindex: FMIndex::new(bwt, less, occ),
+            sa: sampled,
+        })
+    }
+
+    /// Build the index of the given text by merging the indexes of groups of its sequences,
+    /// such that the estimated peak memory stays within the budget: the merged index of the
+    /// preceding groups, its successor and the construction of the index of the current group.
+    fn build_chunked<I: SizedInt, T: IndexableText + Sync + ?Sized>(
+        &self,
+        text: &T,
+        alphabet: Alphabet,
+        budget: usize,
+    ) -> Result<SampledFMIndex<I>, IndexBuildError> {
+        let n = text.len();
+        let index_size = 2 * self.index_size::<I>(n, &alphabet);
+        let builder = IndexBuilder {
+            occ_sampling_rate: self.occ_sampling_rate,
+            sa_sampling_rate: self.sa_sampling_rate,
+            alphabet: Some(Alphabet {
+                symbols: alphabet.symbols.clone(),
+            }),
+            threads: self.threads,
+            memory_budget: MemoryBudget::Unlimited,
+        };
+        let mut index: Option<SampledFMIndex<I>> = None;
+        let add_chunk = |index: Option<SampledFMIndex<I>>, start: usize, end: usize| {
+            let chunk: Vec<u8> = text.symbols(start..end).collect();
+            let chunk_index = builder.build_sized(&chunk)?;
+            Ok(Some(match index {
+                Some(index) => index.merge(&chunk_index),
+                None => chunk_index,
+            }))
+        };
+
+        // group the sequences greedily, where [start, end) are the sequences of the group
+        let (mut start, mut end) = (0, 0);
+        for pos in 0..n {
+            if text.symbol(pos) != b'$' {
+                continue;
+            }
+            let mut required = index_size + self.memory_estimate::<I>(pos + 1 - start, &alphabet);
+            if required > budget && end > start {
+                index = add_chunk(index, start, end)?;
+                start = end;
+                required = index_size + self.memory_estimate::<I>(pos + 1 - start, &alphabet);
+            }
+            if required > budget {
+                return Err(IndexBuildError::MemoryLimit(required, budget));
+            }
+            end = pos + 1;
+        }
+        Ok(add_chunk(index, start, end)?.expect("Expecting non-empty text."))
+    }
+
+    /// Estimated size in bytes of the index of a text of the given length.
+    fn index_size<I>(&self, len: usize, alphabet: &Alphabet) -> usize {
+        let word = mem::size_of::<I>();
+        len + Occ::size_estimate(len, self.occ_sampling_rate, alphabet.len())
+            + (len / self.sa_sampling_rate + 1) * word
+    }
+
+    /// Estimated peak memory in bytes for building the index of a text of the given length:
+    /// the text, its suffix array and the working space of its construction, the BWT and the
+    /// occ array.
+    fn memory_estimate<I>(&self, len: usize, alphabet: &Alphabet) -> usize {
+        let word = mem::size_of::<I>();
+        len * (2 * word + 2) + Occ::size_estimate(len, self.occ_sampling_rate, alphabet.len())
+    }
+
+    /// Compute the BWT with the given number of threads.
+    fn bwt<T: IndexableText + Sync + ?Sized, I: SizedInt>(&self, text: &T, sa: &[I]) -> BWT {
+        if self.threads == 1 || cfg!(any(feature = "wasm", target_arch = "wasm32")) {
+            return bwt(text, sa);
+        }
+        let n = text.len();
+        let mut bwt = vec![0; n];
+        let chunk_len = n.div_ceil(self.threads).max(1);
+        thread::scope(|scope| {
+            for (bwt, sa) in bwt.chunks_mut(chunk_len).zip(sa.chunks(chunk_len)) {
+                scope.spawn(move || {
+                    for (c, &p) in bwt.iter_mut().zip(sa) {
+                        let p = p.to_usize();
+                        *c = text.symbol(if p > 0 { p - 1 } else { n - 1 });
+                    }
+                });
+            }
+        });
+        bwt
+    }
+}
+
+/// A bi-interval on suffix array of the forward and reverse strand of a DNA text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BiInterval {
+    lower: usize,
+    lower_rev: usize,
+    size: usize,
+    match_size: usize,
+}
+
+impl BiInterval {
+    pub fn forward(&self) -> Interval {
+        Interval {
+            upper: self.lower + self.size,
+            lower: self.lower,
+        }
+    }
+    pub fn revcomp(&self) -> Interval {
+        Interval {
+            upper: self.lower_rev + self.size,
+            lower: self.lower_rev,
+        }
+    }
+
+    /// Start of the interval in the suffix array (see `forward`).
+    pub fn lower(&self) -> usize {
+        self.lower
+    }
+
+    /// Start of the interval of the reverse complement in the suffix array (see `revcomp`).
+    pub fn lower_rev(&self) -> usize {
+        self.lower_rev
+    }
+
+    /// Number of occurrences of the matched pattern, i.e. the size of both intervals.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Length of the matched pattern.
+    pub fn match_size(&self) -> usize {
+        self.match_size
+    }
+
+    /// Iterate over the text positions of the occurrences of the matched pattern on the
+    /// forward strand of the text, looking them up lazily in the suffix array.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMDIndex, FMIndex};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    ///
+    /// let text = b"ATTCATTC$GAATGAAT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+    ///
+    /// let interval = fmdindex.smems(b"ATTC", 0)[0];
+    /// assert_eq!((interval.size(), interval.match_size()), (2, 4));
+    /// let first = interval.occ_iter(&sa).min().unwrap();
+    /// assert_eq!(first, 0);
+    /// ```
+    pub fn occ_iter<'a, SA: SuffixArray>(&self, sa: &'a SA) -> impl Iterator<Item = usize> + 'a {
+        self.forward().occ_iter(sa)
+    }
+
+    fn swapped(&self) -> BiInterval {
+        BiInterval {
+            lower: self.lower_rev,
+            lower_rev: self.lower,
+            size: self.size,
+            match_size: self.match_size,
+        }
+    }
+}
+
+/// A supermaximal exact match of the range `start..end` of a pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Smem {
+    pub start: usize,
+    pub end: usize,
+    pub interval: BiInterval,
+}
+
+/// A maximal exact match of the range `start..end` of a pattern, with the interval of all
+/// occurrences of the matched sequence (of which at least one cannot be extended).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Mem {
+    pub start: usize,
+    pub end: usize,
+    pub interval: BiInterval,
+}
+
+/// The FMD-Index for linear time search of supermaximal exact matches on forward and reverse
+/// strand of DNA texts (Li, 2012).
+#[derive(Serialize, Deserialize)]
+pub struct FMDIndex<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> {
+    fmindex: FMIndex<DBWT, DLess, DOcc>,
+    /// The sequences of the text, if built by `FMDIndex::from_texts`.
+    #[serde(default)]
+    seqs: SequenceMap,
+}
+
+/// A position in a sequence of an FMD-Index text: the index of the sequence, the offset on its
+/// forward strand and the strand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextPosition {
+    pub seq: usize,
+    pub offset: usize,
+    pub strand: ReqStrand,
+}
+
+/// The layout of an FMD-Index text T1$R1$T2$R2$... of sequences Ti with reverse complements
+/// Ri, for mapping positions in the text back to the sequences.
+///
+/// # Example
+///
+/// ```
+/// # extern crate bio;
+/// # extern crate bio_types;
+/// use bio::data_structures::fmindex::{SequenceMap, TextPosition};
+/// use bio_types::strand::ReqStrand;
+///
+/// // text ATTC$GAAT$GGATTA$TAATCC$
+/// let mut map = SequenceMap::new();
+/// map.push(4);
+/// map.push(6);
+/// assert_eq!(map.text_len(), 24);
+/// assert_eq!(
+///     map.position(18),
+///     Some(TextPosition { seq: 1, offset: 4, strand: ReqStrand::Reverse })
+/// );
+/// assert_eq!(map.position(4), None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceMap {
+    starts: Vec<usize>,
+    text_len: usize,
+}
+
+impl SequenceMap {
+    pub fn new() -> Self {
+        SequenceMap::default()
+    }
+
+    /// Add a sequence of the given length (without sentinel) to the end of the text.
+    pub fn push(&mut self, len: usize) {
+        self.starts.push(self.text_len);
+        self.text_len += 2 * len + 2;
+    }
+
+    /// Number of sequences.
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Length of the text, including reverse complements and sentinels.
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    /// Length of the given sequence.
+    pub fn seq_len(&self, seq: usize) -> usize {
+        let end = if seq + 1 < self.starts.len() {
+            self.starts[seq + 1]
+        } else {
+            self.text_len
+        };
+        (end - self.starts[seq] - 2) / 2
+    }
+
+    /// Map a position of the text to its sequence, offset and strand. Returns `None` for
+    /// sentinels and positions beyond the text.
+    pub fn position(&self, pos: usize) -> Option<TextPosition> {
+        self.match_position(pos, 1)
+    }
+
+    /// Map the occurrences of the given bi-interval, as found by `FMDIndex::smems`, to the
+    /// sequences. Occurrences on the reverse strand are given by the start of the matched
+    /// range on the forward strand.
+    pub fn occ<SA: SuffixArray>(&self, interval: &BiInterval, sa: &SA) -> Vec<TextPosition> {
+        interval
+            .occ_iter(sa)
+            .filter_map(|pos| self.match_position(pos, interval.match_size))
+            .collect()
+    }
+
+    /// Position of a match of the given length starting at the given text position.
+    fn match_position(&self, pos: usize, len: usize) -> Option<TextPosition> {
+        if pos >= self.text_len {
+            return None;
+        }
+        let seq = self.starts.partition_point(|&start| start <= pos) - 1;
+        let m = self.seq_len(seq);
+        let offset = pos - self.starts[seq];
+        if offset + len <= m {
+            Some(TextPosition {
+                seq,
+                offset,
+                strand: ReqStrand::Forward,
+            })
+        } else if offset > m && offset + len <= 2 * m + 1 {
+            Some(TextPosition {
+                seq,
+                offset: 2 * m + 1 - offset - len,
+                strand: ReqStrand::Reverse,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> FMIndexable
+    for FMDIndex<DBWT, DLess, DOcc>
+{
+    fn occ(&self, r: usize, a: u8) -> usize {
+        self.fmindex.occ(r, a)
+    }
+
+    fn less(&self, a: u8) -> usize {
+        self.fmindex.less(a)
+    }
+
+    fn bwt_len(&self) -> usize {
+        self.fmindex.bwt_len()
+    }
+
+    fn bwt_symbol(&self, r: usize) -> u8 {
+        self.fmindex.bwt_symbol(r)
+    }
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> From<FMIndex<DBWT, DLess, DOcc>>
+    for FMDIndex<DBWT, DLess, DOcc>
+{
+    /// Construct a new instance of the FMD index (see Heng Li (2012) Bioinformatics).
+    /// This expects a BWT that was created from a text over the DNA alphabet with N
+    /// (`alphabets::dna::n_alphabet()`) consisting of the
+    /// concatenation with its reverse complement, separated by the sentinel symbol `$`.
+    /// I.e., let T be the original text and R be its reverse complement.
+    /// Then, the expected text is T$R$. Further, multiple concatenated texts are allowed, e.g.
+    /// T1$R1$T2$R2$T3$R3$.
+    ///
+    fn from(fmindex: FMIndex<DBWT, DLess, DOcc>) -> FMDIndex<DBWT, DLess, DOcc> {
+        FMDIndex::new(fmindex)
+            .expect("Expecting BWT over the DNA alphabet (including N) with the sentinel $.")
+    }
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> FMDIndex<DBWT, DLess, DOcc> {
+    /// Provide a reference to the underlying BWT.
+    pub fn bwt(&self) -> &BWTSlice {
+        self.fmindex.bwt()
+    }
+
+    /// Construct a new instance of the FMD index, as `FMDIndex::from`, but returning an error
+    /// with the first invalid symbol of the BWT instead of panicking.
+    pub fn new(fmindex: FMIndex<DBWT, DLess, DOcc>) -> Result<Self, Error> {
+        let mut alphabet = dna::n_alphabet();
+        alphabet.insert(b'$');
+        if let Some((pos, &symbol)) = fmindex
+            .bwt()
+            .iter()
+            .enumerate()
+            .find(|&(_, &c)| !alphabet.symbols.contains(c as usize))
+        {
+            return Err(Error::InvalidSymbol { symbol, pos });
+        }
+
+        Ok(FMDIndex {
+            fmindex,
+            seqs: SequenceMap::new(),
+        })
+    }
+
+    /// The sequences of the text, if the index was built by `FMDIndex::from_texts`, or empty
+    /// otherwise.
+    pub fn sequence_map(&self) -> &SequenceMap {
+        &self.seqs
+    }
+
+    /// Find supermaximal exact matches of given pattern that overlap position i in the pattern.
+    /// Complexity O(m) with pattern of length m.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::fmindex::{FMIndex, FMDIndex};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    ///
+    /// let text = b"ATTC$GAAT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fm = FMIndex::new(&bwt, &less, &occ);
+    /// let fmdindex = FMDIndex::from(fm);
+    ///
+    /// let pattern = b"ATT";
+    /// let intervals = fmdindex.smems(pattern, 2);
+    ///
+    /// let forward_positions = intervals[0].forward().occ(&sa);
+    ///
+    /// let revcomp_positions = intervals[0].revcomp().occ(&sa);
+    ///
+    /// assert_eq!(forward_positions, [0]);
+    /// assert_eq!(revcomp_positions, [6]);
+    /// ```
+    pub fn smems(&self, pattern: &[u8], i: usize) -> Vec<BiInterval> {
+        self.smems_observed(pattern, i, &mut ())
+    }
+
+    /// Find supermaximal exact matches as `smems`, reporting the steps of the search to the
+    /// given observer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::fmindex::{FMIndex, FMDIndex, SearchStats};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    ///
+    /// let text = b"ATTC$GAAT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+    ///
+    /// let mut stats = SearchStats::new();
+    /// let intervals = fmdindex.smems_observed(b"ATT", 2, &mut stats);
+    /// assert_eq!(stats.found, intervals.len());
+    /// assert!(stats.extensions > 0);
+    /// ```
+    pub fn smems_observed<O: SearchObserver>(
+        &self,
+        pattern: &[u8],
+        i: usize,
+        observer: &mut O,
+    ) -> Vec<BiInterval> {
+        let mut matches = Vec::new();
+        self.smems_into(
+            pattern,
+            i,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut matches,
+            observer,
+        );
+        matches.into_iter().map(|(_, interval)| interval).collect()
+    }
+
+    /// Find all supermaximal exact matches of at least `min_len` bases in the given pattern,
+    /// by searching the SMEMs overlapping the first position, and continuing after the
+    /// end of the rightmost one (as BWA-MEM). The matches are sorted by their start.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::fmindex::{FMIndex, FMDIndex};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    ///
+    /// let text = b"ACGTCGATTTAG$CTAAATCGACGT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+    ///
+    /// // a read with a sequencing error (T -> C) after the first five bases
+    /// let smems = fmdindex.all_smems(b"TCGATCTAGCC", 4);
+    /// let ranges: Vec<_> = smems.iter().map(|smem| (smem.start, smem.end)).collect();
+    /// assert_eq!(ranges, [(0, 5)]);
+    /// assert_eq!(smems[0].interval.forward().occ(&sa), [3]);
+    /// ```
+    pub fn all_smems(&self, pattern: &[u8], min_len: usize) -> Vec<Smem> {
+        let mut smems = Vec::new();
+        let mut i = 0;
+        while i < pattern.len() {
+            let mut next = i + 1;
+            for (start, interval) in self.smems_with_starts(pattern, i) {
+                if interval.size == 0 {
+                    continue;
+                }
+                let end = start + interval.match_size;
+                next = next.max(end);
+                if interval.match_size >= min_len {
+                    smems.push(Smem {
+                        start,
+                        end,
+                        interval,
+                    });
+                }
+            }
+            i = next;
+        }
+        smems.sort_by_key(|smem| (smem.start, smem.end));
+        smems
+    }
+
+    /// The k longest SMEMs of the pattern, found as with `all_smems`, sorted by decreasing
+    /// length (and by start among equally long ones). Matches ordered after the current k-th
+    /// one are discarded as they are found, instead of collecting all of them, which saves work
+    /// for repetitive patterns with many short matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::fmindex::{FMDIndex, FMIndex};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    ///
+    /// let text = b"ACGTCGATTTAG$CTAAATCGACGT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+    ///
+    /// let matches = fmdindex.longest_matches(b"TCGATCTAGCC", 2);
+    /// let ranges: Vec<_> = matches.iter().map(|smem| (smem.start, smem.end)).collect();
+    /// assert_eq!(ranges, [(0, 5), (3, 6)]);
+    /// ```
+    pub fn longest_matches(&self, pattern: &[u8], k: usize) -> Vec<Smem> {
+        let mut matches: Vec<Smem> = Vec::with_capacity(k + 1);
+        if k == 0 {
+            return matches;
+        }
+        let mut i = 0;
+        while i < pattern.len() {
+            let mut next = i + 1;
+            for (start, interval) in self.smems_with_starts(pattern, i) {
+                if interval.size == 0 {
+                    continue;
+                }
+                let end = start + interval.match_size;
+                next = next.max(end);
+                // matches are ordered by decreasing length, then by start
+                let key = (cmp::Reverse(interval.match_size), start);
+                let key_of = |smem: &Smem| (cmp::Reverse(smem.interval.match_size), smem.start);
+                if matches.len() == k && key >= key_of(&matches[k - 1]) {
+                    continue;
+                }
+                let pos = matches.partition_point(|smem| key_of(smem) <= key);
+                matches.insert(
+                    pos,
+                    Smem {
+                        start,
+                        end,
+                        interval,
+                    },
+                );
+                matches.truncate(k);
+            }
+            i = next;
+        }
+        matches
+    }
+
+    /// Find all maximal exact matches (MEMs) of at least `min_len` bases in the given pattern,
+    /// i.e. ranges of the pattern with an occurrence in the text that can be extended neither
+    /// to the left nor to the right. Unlike SMEMs, these include matches contained in longer
+    /// ones, e.g. shorter matches at other loci. Matches with more than `max_occ` occurrences
+    /// are skipped, to avoid a blowup in repeats. The matches are sorted by their start.
+    /// Complexity: O(m * l) extensions for a pattern of length m with matches of length l.
+    ///
+    /// For each start, the pattern is extended forward as long as it occurs. Whether a range
+    /// has a maximal occurrence follows from the sizes of the intervals of the range extended
+    /// by one base to the left and to the right.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::fmindex::FMDIndex;
+    ///
+    /// let seqs = [&b"ACGTCGATTTAGCA"[..], b"GGCGATTTAGGTT"];
+    /// let (fmdindex, _) = FMDIndex::from_texts(seqs.iter().cloned(), 3).unwrap();
+    ///
+    /// let mems = fmdindex.mems(b"ACGTCGATTTAGGT", 5, 10);
+    /// let ranges: Vec<_> = mems.iter().map(|mem| (mem.start, mem.end)).collect();
+    /// // CGATTTAGG is contained in the SMEM ACGTCGATTTAG, but matches another sequence
+    /// assert_eq!(ranges, [(0, 12), (4, 14)]);
+    /// ```
+    pub fn mems(&self, pattern: &[u8], min_len: usize, max_occ: usize) -> Vec<Mem> {
+        let m = pattern.len();
+        let mut mems = Vec::new();
+        // interval sizes of the ranges starting at the previous and current start, by end
+        let (mut prev, mut curr) = (vec![0; m + 1], vec![0; m + 1]);
+        let mut prev_end = 0;
+        let mut intervals = Vec::new();
+        for start in 0..m {
+            intervals.clear();
+            let mut interval = self.init_interval_with(pattern[start]);
+            while interval.size > 0 {
+                let end = start + interval.match_size;
+                curr[end] = interval.size;
+                intervals.push(interval);
+                if end == m {
+                    break;
+                }
+                interval = self.forward_ext(&interval, pattern[end]);
+            }
+            let curr_end = start + intervals.len();
+            let size = |sizes: &[usize], last: usize, end: usize| {
+                if end <= last {
+                    sizes[end]
+                } else {
+                    0
+                }
+            };
+
+            for interval in &intervals {
+                let end = start + interval.match_size;
+                if interval.match_size < min_len || interval.size > max_occ {
+                    continue;
+                }
+                // occurrences neither extendable to the left nor to the right, by
+                // inclusion-exclusion
+                let maximal = (interval.size + size(&prev, prev_end, end + 1))
+                    - size(&curr, curr_end, end + 1)
+                    - size(&prev, prev_end, end);
+                if maximal > 0 {
+                    mems.push(Mem {
+                        start,
+                        end,
+                        interval: *interval,
+                    });
+                }
+            }
+            swap(&mut prev, &mut curr);
+            prev_end = curr_end;
+        }
+        mems
+    }
+
+    /// Searcher of SMEMs reusing its buffers, which avoids allocations when searching many
+    /// patterns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::fmindex::{FMIndex, FMDIndex};
+    /// use bio::data_structures::suffix_array::suffix_array;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    ///
+    /// let text = b"ATTC$GAAT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+    ///
+    /// let mut searcher = fmdindex.smem_searcher();
+    /// for read in &[&b"ATT"[..], b"GAAT"] {
+    ///     for interval in searcher.smems_iter(read, 1) {
+    ///         assert!(!interval.forward().occ(&sa).is_empty());
+    ///     }
+    /// }
+    /// ```
+    pub fn smem_searcher(&self) -> SmemSearcher<'_, DBWT, DLess, DOcc> {
+        SmemSearcher {
+            fmdindex: self,
+            curr: Vec::new(),
+            prev: Vec::new(),
+            matches: Vec::new(),
+        }
+    }
+
+    /// SMEMs overlapping position i in the pattern, with their start in the pattern.
+    fn smems_with_starts(&self, pattern: &[u8], i: usize) -> Vec<(usize, BiInterval)> {
+        let mut matches = Vec::new();
+        self.smems_into(
+            pattern,
+            i,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut matches,
+            &mut (),
+        );
+        matches
+    }
+
+    /// Write the SMEMs overlapping position i in the pattern, with their start in the pattern,
+    /// into `matches`, using the given buffers for intervals and reporting to the observer.
+    fn smems_into<O: SearchObserver>(
+        &self,
+        pattern: &[u8],
+        i: usize,
+        curr: &mut Vec<BiInterval>,
+        prev: &mut Vec<BiInterval>,
+        matches: &mut Vec<(usize, BiInterval)>,
+        observer: &mut O,
+    ) {
+        curr.clear();
+        prev.clear();
+        matches.clear();
+
+        let mut interval = self.init_interval_with(pattern[i]);
+
         for &a in pattern[i + 1..].iter() {
             // forward extend interval
             let forward_interval = self.forward_ext(&interval, a);
+            observer.extension(forward_interval.size);
+
+            // if size changed, add last interval to list
+            if interval.size != forward_interval.size {
+                curr.push(interval);
+            }
+            // if new interval size is zero, stop, as no further forward extension is possible
+            if forward_interval.size == 0 {
+                break;
+            }
+            interval = forward_interval;
+        }
+        // add the last non-zero interval
+        curr.push(interval);
+        // reverse intervals such that longest comes first
+        curr.reverse();
+
+        swap(curr, prev);
+        let mut j = pattern.len() as isize;
+
+        for k in (-1..i as isize).rev() {
+            let a = if k == -1 { b'$' } else { pattern[k as usize] };
+            curr.clear();
+            // size of the last confirmed interval
+            let mut last_size = -1;
+
+            for interval in prev.iter() {
+                // backward extend interval
+                let forward_interval = self.backward_ext(interval, a);
+                observer.extension(forward_interval.size);
+
+                if (forward_interval.size == 0 || k == -1) &&
+                        // interval could not be extended further
+                        // if no interval has been extended this iteration,
+                        // interval is maximal and can be added to the matches
+                        curr.is_empty() && k < j
+                {
+                    j = k;
+                    observer.found(interval.size);
+                    matches.push(((k + 1) as usize, *interval));
+                }
+                // add _interval to curr (will be further extended next iteration)
+                if forward_interval.size != 0 && forward_interval.size as isize != last_size {
+                    last_size = forward_interval.size as isize;
+                    curr.push(forward_interval);
+                }
+            }
+            if curr.is_empty() {
+                break;
+            }
+            swap(curr, prev);
+        }
+    }
+
+    /// Initialize interval with given start character.
+    pub fn init_interval_with(&self, a: u8) -> BiInterval {
+        let comp_a = dna::complement(a);
+        let lower = self.fmindex.less(a);
+
+        BiInterval {
+            lower,
+            lower_rev: self.fmindex.less(comp_a),
+            size: self.fmindex.less(a + 1) - lower,
+            match_size: 1,
+        }
+    }
+
+    /// Initialize interval for empty pattern. The interval points at the whole suffix array.
+    pub fn init_interval(&self) -> BiInterval {
+        BiInterval {
+            lower: 0,
+            lower_rev: 0,
+            size: self.fmindex.bwt.as_bwt().len(),
+            match_size: 0,
+        }
+    }
+
+    /// Backward extension of given interval with given character.
+    pub fn backward_ext(&self, interval: &BiInterval, a: u8) -> BiInterval {
+        let mut s = 0;
+        let mut o = 0;
+        let mut l = interval.lower_rev;
+        // Interval [l(c(aP)), u(c(aP))] is a subinterval of [l(c(P)), u(c(P))] for each a,
+        // starting with the lexicographically smallest ($),
+        // then c(T) = A, c(G) = C, c(C) = G, N, c(A) = T, ...
+        // Hence, we calculate lower revcomp bounds by iterating over
+        // symbols and updating from previous one.
+        for &b in b"$TGCNAtgcna".iter() {
+            l += s;
+            o = if interval.lower == 0 {
+                0
+            } else {
+                self.fmindex.occ(interval.lower - 1, b)
+            };
+            // calculate size
+            s = self.fmindex.occ(interval.lower + interval.size - 1, b) - o;
+            if b == a {
+                break;
+            }
+        }
+        // calculate lower bound
+        let k = self.fmindex.less(a) + o;
+
+        BiInterval {
+            lower: k,
+            lower_rev: l,
+            size: s,
+            match_size: interval.match_size + 1,
+        }
+    }
+
+    pub fn forward_ext(&self, interval: &BiInterval, a: u8) -> BiInterval {
+        let comp_a = dna::complement(a);
+
+        self.backward_ext(&interval.swapped(), comp_a).swapped()
+    }
+}
+
+/// Searcher of SMEMs in an FMD-Index, reusing its buffers across searches (see
+/// `FMDIndex::smem_searcher`).
+pub struct SmemSearcher<'a, DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> {
+    fmdindex: &'a FMDIndex<DBWT, DLess, DOcc>,
+    curr: Vec<BiInterval>,
+    prev: Vec<BiInterval>,
+    matches: Vec<(usize, BiInterval)>,
+}
+
+impl<'a, DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend>
+    SmemSearcher<'a, DBWT, DLess, DOcc>
+{
+    /// Iterate over the supermaximal exact matches of the given pattern that overlap position
+    /// i, in the order of `FMDIndex::smems`. Allocates only while the buffers grow.
+    pub fn smems_iter(
+        &mut self,
+        pattern: &[u8],
+        i: usize,
+    ) -> impl Iterator<Item = BiInterval> + '_ {
+        self.fmdindex.smems_into(
+            pattern,
+            i,
+            &mut self.curr,
+            &mut self.prev,
+            &mut self.matches,
+            &mut (),
+        );
+        self.matches.drain(..).map(|(_, interval)| interval)
+    }
+}
+
+/// A pair of suffix array intervals of a bidirectional FM-Index: the interval of the suffixes of
+/// the text starting with a pattern P, and the interval of the suffixes of the reversed text
+/// starting with the reverse of P.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BidirectionalInterval {
+    lower: usize,
+    lower_rev: usize,
+    size: usize,
+    match_size: usize,
+}
+
+impl BidirectionalInterval {
+    /// The interval in the suffix array of the text.
+    pub fn forward(&self) -> Interval {
+        Interval {
+            lower: self.lower,
+            upper: self.lower + self.size,
+        }
+    }
+
+    /// The interval in the suffix array of the reversed text.
+    pub fn reverse(&self) -> Interval {
+        Interval {
+            lower: self.lower_rev,
+            upper: self.lower_rev + self.size,
+        }
+    }
+
+    /// Number of occurrences of the pattern.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Length of the pattern.
+    pub fn match_size(&self) -> usize {
+        self.match_size
+    }
+}
+
+/// A bidirectional FM-Index over an arbitrary alphabet (Lam et al., 2009), consisting of the
+/// FM-Indexes of a text and of the reversed text, such that patterns can be extended in both
+/// directions (e.g. for protein or RNA texts, where the FMD-Index cannot be used).
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::protein;
+/// use bio::data_structures::fmindex::BidirectionalFMIndex;
+/// use bio::data_structures::suffix_array::suffix_array;
+///
+/// let text = b"MKVLAAGIVGMKVW$";
+/// let index = BidirectionalFMIndex::from_text(text, &protein::alphabet(), 4);
+///
+/// // search KV, then extend to the left by M and to the right by L
+/// let interval = index.search(b"KV");
+/// assert_eq!(interval.len(), 2);
+/// let interval = index.backward_ext(&interval, b'M');
+/// let interval = index.forward_ext(&interval, b'L');
+/// assert_eq!(interval.match_size(), 4);
+/// assert_eq!(interval.forward().occ(&suffix_array(text)), [0]);
+/// ```
+#[derive(Serialize, Deserialize)]
+pub struct BidirectionalFMIndex<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend> {
+    forward: FMIndex<DBWT, DLess, DOcc>,
+    reverse: FMIndex<DBWT, DLess, DOcc>,
+    /// The symbols occurring in the text, in ascending order.
+    symbols: Vec<u8>,
+}
+
+impl BidirectionalFMIndex<BWT, Less, Occ> {
+    /// Build the index of the given text, ended by the sentinel `$` (and possibly containing
+    /// further sentinels separating multiple sequences), keeping every k-th occ array entry.
+    pub fn from_text(text: &[u8], alphabet: &Alphabet, k: u32) -> Self {
+        let (seq, sentinel) = text.split_at(text.len().saturating_sub(1));
+        assert_eq!(sentinel, b"$", "Expecting text ended by the sentinel $.");
+        let mut reversed = seq.to_vec();
+        reversed.reverse();
+        reversed.push(b'$');
+
+        let mut alphabet = Alphabet {
+            symbols: alphabet.symbols.clone(),
+        };
+        alphabet.insert(b'$');
+        let index = |text: &[u8]| {
+            let bwt = bwt(text, &suffix_array(text));
+            let less = less(&bwt, &alphabet);
+            let occ = Occ::new(&bwt, k, &alphabet);
+            FMIndex::new(bwt, less, occ)
+        };
+        BidirectionalFMIndex::new(index(text), index(&reversed))
+            .expect("Expecting matching indexes of a text and its reverse.")
+    }
+}
+
+impl<DBWT: BWTStorage, DLess: Borrow<Less>, DOcc: OccBackend>
+    BidirectionalFMIndex<DBWT, DLess, DOcc>
+{
+    /// Combine the FM-Indexes of a text and of its reverse. If the text is T$, the reversed text
+    /// is expected to be R$ with R the reverse of T. Further, multiple concatenated texts are
+    /// allowed, e.g. for T1$T2$ the reversed text is R2$R1$.
+    pub fn new(
+        forward: FMIndex<DBWT, DLess, DOcc>,
+        reverse: FMIndex<DBWT, DLess, DOcc>,
+    ) -> Result<Self, Error> {
+        let symbols = Alphabet::new(forward.bwt().iter());
+        if forward.bwt().len() != reverse.bwt().len()
+            || symbols.symbols != Alphabet::new(reverse.bwt().iter()).symbols
+        {
+            return Err(Error::IndexMismatch(
+                "the reversed text differs in length or symbols from the text".to_owned(),
+            ));
+        }
+        let symbols = symbols.symbols.iter().map(|a| a as u8).collect();
+        Ok(BidirectionalFMIndex {
+            forward,
+            reverse,
+            symbols,
+        })
+    }
+
+    /// The FM-Index of the text.
+    pub fn forward(&self) -> &FMIndex<DBWT, DLess, DOcc> {
+        &self.forward
+    }
+
+    /// The FM-Index of the reversed text.
+    pub fn reverse(&self) -> &FMIndex<DBWT, DLess, DOcc> {
+        &self.reverse
+    }
+
+    /// Interval of the empty pattern, i.e. of all suffixes.
+    pub fn init_interval(&self) -> BidirectionalInterval {
+        BidirectionalInterval {
+            lower: 0,
+            lower_rev: 0,
+            size: self.forward.bwt().len(),
+            match_size: 0,
+        }
+    }
+
+    /// Interval of the given pattern, which must not contain the sentinel.
+    pub fn search(&self, pattern: &[u8]) -> BidirectionalInterval {
+        let mut interval = self.init_interval();
+        for &a in pattern.iter().rev() {
+            if interval.is_empty() {
+                break;
+            }
+            interval = self.backward_ext(&interval, a);
+        }
+        interval
+    }
+
+    /// Extend the interval of a pattern P to the interval of aP.
+    pub fn backward_ext(&self, interval: &BidirectionalInterval, a: u8) -> BidirectionalInterval {
+        let (lower, lower_rev, size) = self.extend(
+            &self.forward,
+            interval.lower,
+            interval.lower_rev,
+            interval.size,
+            a,
+        );
+        BidirectionalInterval {
+            lower,
+            lower_rev,
+            size,
+            match_size: interval.match_size + 1,
+        }
+    }
+
+    /// Extend the interval of a pattern P to the interval of Pa.
+    pub fn forward_ext(&self, interval: &BidirectionalInterval, a: u8) -> BidirectionalInterval {
+        let (lower_rev, lower, size) = self.extend(
+            &self.reverse,
+            interval.lower_rev,
+            interval.lower,
+            interval.size,
+            a,
+        );
+        BidirectionalInterval {
+            lower,
+            lower_rev,
+            size,
+            match_size: interval.match_size + 1,
+        }
+    }
+
+    /// Backward extension in the given index, where the lower bound of the interval in the
+    /// other index is shifted by the occurrences of smaller symbols in the BWT interval.
+    fn extend(
+        &self,
+        fmindex: &FMIndex<DBWT, DLess, DOcc>,
+        lower: usize,
+        lower_other: usize,
+        size: usize,
+        a: u8,
+    ) -> (usize, usize, usize) {
+        assert!(
+            a != b'$',
+            "Expecting extension by symbols other than the sentinel."
+        );
+        if self.symbols.binary_search(&a).is_err() {
+            return (0, 0, 0);
+        }
+        // occurrences of a symbol before the given row
+        let occ = |r: usize, b: u8| if r == 0 { 0 } else { fmindex.occ(r - 1, b) };
+        let upper = lower + size;
+        let smaller: usize = self
+            .symbols
+            .iter()
+            .take_while(|&&b| b < a)
+            .map(|&b| occ(upper, b) - occ(lower, b))
+            .sum();
+        (
+            fmindex.less(a) + occ(lower, a),
+            lower_other + smaller,
+            occ(upper, a) - occ(lower, a),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alphabets::dna;
+    use data_structures::bwt::{bwt, less, Occ};
+    use data_structures::packed_seq::PackedDnaSeq;
+    use data_structures::suffix_array::suffix_array;
+    use utils::{SeedableRngExt, Xoshiro256};
+
+    #[test]
+    fn test_fmindex() {
+        let text = b"GCCTTAACATTATTACGCCTA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        let pattern = b"TTA";
+        let sai = fm.backward_search(pattern.iter());
+
+        let positions = sai.occ(&sa);
+
+        assert_eq!(positions, [3, 12, 9]);
+    }
+
+    #[test]
+    fn test_from_text() {
+        let text = b"GCCTTAACATTATTACGCCTA$";
+        let alphabet = dna::n_alphabet();
+        let fm = FMIndex::from_text(text, 3, &alphabet);
+        let sa = suffix_array(text);
+        assert_eq!(fm.bwt(), &bwt(text, &sa)[..]);
+        assert_eq!(fm.backward_search(b"TTA".iter()).occ(&sa), [3, 12, 9]);
+
+        let fmdindex = FMDIndex::from_text(b"ACGTTA$TAACGT$", 3).unwrap();
+        let interval = fmdindex.smems(b"AACG", 0)[0];
+        assert_eq!(interval.match_size, 4);
+        assert_eq!(interval.size, 1);
+        assert!(FMDIndex::from_text(b"ACGUA$TUCGT$", 3).is_err());
+    }
+
+    #[test]
+    fn test_count() {
+        let text = b"GCCTTAACATTATTACGCCTA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        for pattern in [&b"TTA"[..], b"A", b"GCC", b"GCCTTAACATTATTACGCCTA", b""] {
+            let expected = (0..text.len())
+                .filter(|&i| text[i..].starts_with(pattern))
+                .count();
+            assert_eq!(fm.count(pattern.iter()), expected);
+        }
+        for pattern in [
+            &b"CCCC"[..],
+            b"N",
+            b"AN",
+            b"GCCTTAACATTATTACGCCTAA",
+            b"!",
+            b"A!",
+        ] {
+            assert_eq!(fm.count(pattern.iter()), 0);
+            assert!(fm.backward_search(pattern.iter()).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_index_builder() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACATTATTACGCCNNACGT";
+        let text = [&orig_text[..], b"$", &dna::revcomp(orig_text), b"$"].concat();
+        let sa = suffix_array(&text);
+        for &threads in &[1, 3, 100] {
+            let index = IndexBuilder::new()
+                .occ_sampling_rate(5)
+                .sa_sampling_rate(7)
+                .threads(threads)
+                .build(&text)
+                .unwrap();
+            assert_eq!(index.bwt(), &bwt(&text, &sa));
+            assert_eq!(index.suffix_array().sampling_rate(), 7);
+            for pattern in [&b"TTA"[..], b"GCC", b"NNA", b"CCCC"] {
+                let interval = index.backward_search(pattern.iter());
+                assert_eq!(index.find(pattern), interval.occ(&sa));
+            }
+        }
+
+        // from a packed text
+        let packed = PackedDnaSeq::new(&text);
+        let index = IndexBuilder::new().threads(2).build(&packed).unwrap();
+        assert_eq!(index.bwt(), &bwt(&text, &sa));
+        let expected = IndexBuilder::new().build(&text).unwrap();
+        assert_eq!(index.find(b"TTACG"), expected.find(b"TTACG"));
+    }
+
+    #[test]
+    fn test_index_builder_sized() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACATTATTACGCCNNACGT";
+        let text = [&orig_text[..], b"$", &dna::revcomp(orig_text), b"$"].concat();
+        let builder = IndexBuilder::new().sa_sampling_rate(3).threads(2);
+        let expected = builder.build(&text).unwrap();
+        let index: SampledFMIndex<u32> = builder.build_sized(&text).unwrap();
+        assert_eq!(index.bwt(), expected.bwt());
+        for pattern in [&b"TTA"[..], b"GCC", b"NNA", b"A$"] {
+            assert_eq!(index.find(pattern), expected.find(pattern));
+        }
+
+        // merging and building within a memory budget keep the offset type
+        let (first, second) = text.split_at(orig_text.len() + 1);
+        let merged: SampledFMIndex<u32> = builder
+            .build_sized(first)
+            .unwrap()
+            .merge(&builder.build_sized(second).unwrap());
+        assert_eq!(merged.bwt(), expected.bwt());
+        let texts = [&text[..]; 6].concat();
+        let alphabet = Alphabet::new(&texts);
+        let budget = 2 * builder.index_size::<u32>(texts.len(), &alphabet)
+            + builder.memory_estimate::<u32>(first.len(), &alphabet);
+        assert!(budget < builder.memory_estimate::<u32>(texts.len(), &alphabet));
+        let chunked: SampledFMIndex<u32> =
+            builder.memory_limit(budget).build_sized(&texts).unwrap();
+        let mut positions = chunked.find(b"ATTA");
+        positions.sort_unstable();
+        let mut unchunked = IndexBuilder::new().build(&texts).unwrap().find(b"ATTA");
+        unchunked.sort_unstable();
+        assert_eq!(positions, unchunked);
+
+        let mut data = Vec::new();
+        index.write_mapped(&mut data).unwrap();
+        let (fm, sa) = MappedFMIndex::from_bytes(&data).unwrap();
+        let interval = fm.backward_search(b"TTA".iter());
+        assert_eq!(fm.locate(&interval, &sa), expected.find(b"TTA"));
+    }
+
+    #[test]
+    fn test_index_builder_absent_symbols() {
+        // the default alphabet only contains the symbols of the text
+        let index = IndexBuilder::new().build(&b"ACGA$"[..]).unwrap();
+        let alphabet = dna::n_alphabet();
+        let expected = FMIndex::new(
+            index.bwt(),
+            less(index.bwt(), &alphabet),
+            Occ::new(index.bwt(), 3, &alphabet),
+        );
+        for &pattern in &[&b"T"[..], b"N", b"AT", b"TA", b"GT$", b"Z"] {
+            assert!(index.find(pattern).is_empty());
+            assert_eq!(index.count(pattern.iter()), 0);
+            assert_eq!(expected.count(pattern.iter()), 0);
+        }
+        assert_eq!(index.find(b"GA"), [2]);
+    }
+
+    #[test]
+    fn test_index_builder_memory_budget() {
+        let seqs: Vec<Vec<u8>> = (0..20)
+            .map(|i| {
+                (0..50 + i)
+                    .map(|j| b"ACGT"[(i * 7 + j * j + j / 3) % 4])
+                    .chain(Some(b'$'))
+                    .collect()
+            })
+            .collect();
+        let text = seqs.concat();
+        let builder = IndexBuilder::new().sa_sampling_rate(3);
+        let alphabet = Alphabet::new(b"ACGT$");
+        let required = builder.memory_estimate::<usize>(text.len(), &alphabet);
+        let minimum = 2 * builder.index_size::<usize>(text.len(), &alphabet)
+            + builder.memory_estimate::<usize>(seqs[19].len(), &alphabet);
+        let expected = builder.build(&text).unwrap();
+        for &budget in &[minimum, minimum + 500, required - 1] {
+            let index = IndexBuilder::new()
+                .sa_sampling_rate(3)
+                .memory_budget(MemoryBudget::Bytes(budget))
+                .build(&text)
+                .unwrap();
+            assert_eq!(index.bwt().len(), text.len());
+            for &pattern in &[&b"ACG"[..], b"TT", b"GATTA", b"T$A", b"CAGTC"] {
+                let mut positions = index.find(pattern);
+                positions.sort_unstable();
+                let mut expected = expected.find(pattern);
+                expected.sort_unstable();
+                assert_eq!(positions, expected);
+            }
+        }
+        match builder
+            .memory_budget(MemoryBudget::Bytes(minimum - 1))
+            .build(&text)
+        {
+            Err(IndexBuildError::MemoryLimit(_, limit)) => assert_eq!(limit, minimum - 1),
+            _ => panic!("Expecting memory budget to be exceeded."),
+        }
+    }
+
+    #[test]
+    fn test_index_builder_errors() {
+        let builder = IndexBuilder::new().alphabet(dna::alphabet());
+        assert_eq!(
+            builder.build(b"ACGT").err(),
+            Some(IndexBuildError::MissingSentinel)
+        );
+        assert_eq!(
+            builder.build(b"ACNGT$").err(),
+            Some(IndexBuildError::InvalidSymbol(b'N'))
+        );
+        assert!(builder.build(b"ACGT$ACGT$").is_ok());
+        let builder = builder.memory_limit(2000);
+        assert!(builder.build(&[b'A'; 10]).is_err());
+        assert!(builder.build(b"ACGT$").is_ok());
+        match builder.build(&[&[b'A'; 100][..], b"$"].concat()) {
+            Err(IndexBuildError::MemoryLimit(_, 2000)) => (),
+            _ => panic!("Expecting memory limit to be exceeded."),
+        }
+        // the last setting wins
+        let builder = builder.memory_budget(MemoryBudget::Unlimited);
+        assert!(builder.build(&[&[b'A'; 100][..], b"$"].concat()).is_ok());
+        let builder = builder.memory_limit(2000);
+        assert!(builder.build(&[&[b'A'; 100][..], b"$"].concat()).is_err());
+    }
+
+    #[test]
+    fn test_backward_search_iupac() {
+        let text = b"ACGTTGCAACGGTGCAATCGTTGCA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        let matches = |pattern: &[u8]| -> Vec<usize> {
+            (0..text.len() - pattern.len())
+                .filter(|&p| {
+                    text[p..p + pattern.len()]
+                        .iter()
+                        .zip(pattern)
+                        .all(|(&a, &b)| {
+                            dna::iupac_bases(b).map_or(a == b, |bases| bases.contains(&a))
+                        })
+                })
+                .collect()
+        };
+        for pattern in &[
+            &b"CGTTGC"[..],
+            b"NGTTGC",
+            b"GCAAY",
+            b"GYNNTGCA",
+            b"NNN",
+            b"KKK",
+        ] {
+            let intervals = fm.backward_search_iupac(pattern, 100).unwrap();
+            let mut found: Vec<_> = intervals.iter().flat_map(|i| i.occ(&sa)).collect();
+            found.sort();
+            assert_eq!(found, matches(pattern));
+            assert!(intervals.windows(2).all(|w| w[0].upper < w[1].lower));
+        }
+        assert_eq!(
+            fm.backward_search_iupac(b"CGTTGC", 1).unwrap(),
+            [fm.backward_search(b"CGTTGC".iter())]
+        );
+        // the suffixes starting with a word of length 3 are only interrupted by CA$
+        let intervals = fm.backward_search_iupac(b"NNN", 100).unwrap();
+        assert_eq!(intervals.len(), 2);
+        assert!(fm.backward_search_iupac(b"NNN", 8).is_none());
+        assert_eq!(fm.backward_search_iupac(b"GGGN", 10), Some(vec![]));
+
+        let mut stats = SearchStats::new();
+        fm.backward_search_iupac_observed(b"GCAAY", 10, &mut stats);
+        assert_eq!(stats.found, 1);
+    }
+
+    #[test]
+    fn test_backward_search_mismatches() {
+        let text = b"ACGTTGCAACGGTGCAATCGTTGCA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        let pattern = b"CGTTGC";
+        for k in 0..3 {
+            let intervals = fm.backward_search_mismatches(pattern, k, b"ACGT");
+            let mut found: Vec<_> = intervals
+                .iter()
+                .flat_map(|i| {
+                    i.interval
+                        .occ(&sa)
+                        .into_iter()
+                        .map(move |p| (p, i.mismatches))
+                })
+                .collect();
+            found.sort();
+            let mut expected: Vec<_> = (0..text.len() - pattern.len())
+                .map(|p| {
+                    let mismatches = text[p..p + pattern.len()]
+                        .iter()
+                        .zip(pattern)
+                        .filter(|&(a, b)| a != b)
+                        .count();
+                    (p, mismatches)
+                })
+                .filter(|&(_, mismatches)| mismatches <= k)
+                .collect();
+            expected.sort();
+            assert_eq!(found, expected);
+            assert!(intervals
+                .windows(2)
+                .all(|w| w[0].mismatches <= w[1].mismatches));
+        }
+        let exact = fm.backward_search_mismatches(pattern, 0, b"ACGT");
+        assert_eq!(exact[0].interval, fm.backward_search(pattern.iter()));
+        assert!(fm
+            .backward_search_mismatches(b"AAAAA", 1, b"ACGT")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_bidirectional_fmindex() {
+        use alphabets::protein;
+
+        let text = b"MKVLAAGIVGMKVWAAG$KVLAQQMKV$";
+        let reversed = b"VKMQQALVK$GAAWVKMGVIGAALVKM$";
+        let index = BidirectionalFMIndex::from_text(text, &protein::alphabet(), 3);
+        let (sa, sa_rev) = (suffix_array(text), suffix_array(reversed));
+        let naive = |text: &[u8], pattern: &[u8]| {
+            let mut positions: Vec<_> = (0..text.len() - pattern.len() + 1)
+                .filter(|&i| &text[i..i + pattern.len()] == pattern)
+                .collect();
+            positions.sort();
+            positions
+        };
+        let sorted = |mut positions: Vec<usize>| {
+            positions.sort();
+            positions
+        };
+
+        for pattern in [
+            &b"KV"[..],
+            b"MKV",
+            b"AAG",
+            b"VLA",
+            b"GMKVW",
+            b"Q",
+            b"WQ",
+            b"KVM",
+        ] {
+            let mut rev_pattern = pattern.to_vec();
+            rev_pattern.reverse();
+            // only forward extensions
+            let mut interval = index.init_interval();
+            for &a in pattern {
+                interval = index.forward_ext(&interval, a);
+            }
+            let expected = index.search(pattern);
+            // empty intervals are not further extended by search
+            if !expected.is_empty() {
+                assert_eq!(interval, expected);
+            }
+            assert_eq!(interval.len(), expected.len());
+            assert_eq!(interval.match_size(), pattern.len());
+            assert_eq!(sorted(interval.forward().occ(&sa)), naive(text, pattern));
+            assert_eq!(
+                sorted(interval.reverse().occ(&sa_rev)),
+                naive(reversed, &rev_pattern)
+            );
+            // starting in the middle
+            let mid = pattern.len() / 2;
+            let mut interval = index.search(&pattern[mid..mid + 1]);
+            for &a in &pattern[mid + 1..] {
+                interval = index.forward_ext(&interval, a);
+            }
+            for &a in pattern[..mid].iter().rev() {
+                interval = index.backward_ext(&interval, a);
+            }
+            if !expected.is_empty() {
+                assert_eq!(interval, expected);
+            }
+            assert_eq!(interval.len(), expected.len());
+        }
+        assert!(index.search(b"B").is_empty());
+        assert_eq!(index.init_interval().len(), text.len());
+
+        let mut alphabet = protein::alphabet();
+        alphabet.insert(b'$');
+        let fmindex = |text: &[u8]| {
+            let bwt = bwt(text, &suffix_array(text));
+            let less = less(&bwt, &alphabet);
+            let occ = Occ::new(&bwt, 3, &alphabet);
+            FMIndex::new(bwt, less, occ)
+        };
+        assert!(BidirectionalFMIndex::new(fmindex(b"MKV$"), fmindex(b"KVM$")).is_ok());
+        assert!(BidirectionalFMIndex::new(fmindex(b"MKV$"), fmindex(b"KVMM$")).is_err());
+        assert!(BidirectionalFMIndex::new(fmindex(b"MKV$"), fmindex(b"KVW$")).is_err());
+    }
+
+    #[test]
+    fn test_append() {
+        let texts = [
+            &b"GCCTTAACATTATTACGCCTA$"[..],
+            b"TTAGG$",
+            b"AACA$GCCT$",
+            b"$",
+            b"ACNNGTTAZ$",
+            b"GCCTTAACATTATTACGCCTA$",
+        ];
+        let alphabet = dna::n_alphabet();
+        let bwt0 = bwt(texts[0], &suffix_array(texts[0]));
+        let less0 = less(&bwt0, &alphabet);
+        let occ0 = Occ::new(&bwt0, 3, &alphabet);
+        let mut fm = FMIndex::new(bwt0, less0, occ0);
+        let mut sa = suffix_array(texts[0]);
+        let mut text = texts[0].to_vec();
+        for &appended in &texts[1..] {
+            fm.append_with_suffix_array(appended, &mut sa);
+            text.extend_from_slice(appended);
+            assert_eq!(sa, suffix_array(&text));
+            assert_eq!(fm.bwt(), &bwt(&text, &sa));
+            for &pattern in &[&b"TTA"[..], b"GCC", b"NNG", b"AZ", b"t"] {
+                let expected = (0..text.len())
+                    .filter(|&i| text[i..].starts_with(pattern))
+                    .count();
+                assert_eq!(fm.count(pattern.iter()), expected);
+            }
+        }
+        assert_eq!(fm.occ.sampling_rate(), 3);
+    }
+
+    #[test]
+    fn test_fmdindex_append() {
+        let seqs = [
+            &b"GCCTTAACATTATTACGCCTAACGTGCAATTGACC"[..],
+            b"ACGGTNNAGCCATGACATTA",
+        ];
+        let text = |seqs: &[&[u8]]| {
+            seqs.iter()
+                .flat_map(|seq| [*seq, b"$", &dna::revcomp(*seq), b"$"].concat())
+                .collect::<Vec<_>>()
+        };
+        let alphabet = dna::n_alphabet();
+        let index = |text: &[u8]| {
+            let bwt = bwt(text, &suffix_array(text));
+            let less = less(&bwt, &alphabet);
+            let occ = Occ::new(&bwt, 3, &alphabet);
+            FMDIndex::from(FMIndex::new(bwt, less, occ))
+        };
+        let mut fmdindex = index(&text(&seqs[..1]));
+        let mut sa = suffix_array(&text(&seqs[..1]));
+        fmdindex.append_with_suffix_array(seqs[1], &mut sa).unwrap();
+        let expected = index(&text(&seqs));
+        assert_eq!(fmdindex.bwt(), expected.bwt());
+        assert_eq!(sa, suffix_array(&text(&seqs)));
+        let read = b"TGACATTAGCCTTAAC";
+        for i in 0..read.len() {
+            assert_eq!(fmdindex.smems(read, i), expected.smems(read, i));
+        }
+
+        match fmdindex.append(b"ACGU") {
+            Err(Error::InvalidSymbol {
+                symbol: b'U',
+                pos: 3,
+            }) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_fmdindex_from_texts() {
+        let seqs = [&b"GCCTTAACATTA"[..], b"ACGGTNNAGCC", b"TTACG"];
+        let (mut fmdindex, mut sa) = FMDIndex::from_texts(seqs[..2].iter().cloned(), 3).unwrap();
+        fmdindex.append_with_suffix_array(seqs[2], &mut sa).unwrap();
+        assert_eq!(fmdindex.sequence_map().len(), 3);
 
-            // if size changed, add last interval to list
-            if interval.size != forward_interval.size {
-                curr.push(interval);
+        let text: Vec<u8> = seqs
+            .iter()
+            .flat_map(|seq| [*seq, b"$", &dna::revcomp(*seq), b"$"].concat())
+            .collect();
+        let (expected, expected_sa) = FMDIndex::from_texts(seqs.iter().cloned(), 3).unwrap();
+        assert_eq!(fmdindex.bwt(), expected.bwt());
+        assert_eq!(sa, expected_sa);
+        assert_eq!(sa, suffix_array(&text));
+
+        let map = fmdindex.sequence_map();
+        assert_eq!(map.text_len(), text.len());
+        for (pos, &c) in text.iter().enumerate() {
+            match map.position(pos) {
+                Some(TextPosition {
+                    seq,
+                    offset,
+                    strand: ReqStrand::Forward,
+                }) => assert_eq!(seqs[seq][offset], c),
+                Some(TextPosition {
+                    seq,
+                    offset,
+                    strand: ReqStrand::Reverse,
+                }) => assert_eq!(dna::complement(seqs[seq][offset]), c),
+                None => assert_eq!(c, b'$'),
             }
-            // if new interval size is zero, stop, as no further forward extension is possible
-            if forward_interval.size == 0 {
-                break;
+        }
+        assert_eq!(
+            map.position(26),
+            Some(TextPosition {
+                seq: 1,
+                offset: 0,
+                strand: ReqStrand::Forward,
+            })
+        );
+        assert_eq!(map.position(text.len()), None);
+
+        let read = b"TGACATTAGCCTTAAC";
+        for i in 0..read.len() {
+            for interval in fmdindex.smems(read, i) {
+                let positions = map.occ(&interval, &sa);
+                assert_eq!(positions.len(), interval.forward().len());
+                for pos in positions {
+                    let m = interval.match_size();
+                    let matched = &seqs[pos.seq][pos.offset..pos.offset + m];
+                    let matched = match pos.strand {
+                        ReqStrand::Forward => matched.to_vec(),
+                        ReqStrand::Reverse => dna::revcomp(matched),
+                    };
+                    assert!(read.windows(m).any(|w| w == &matched[..]));
+                }
             }
-            interval = forward_interval;
         }
-        // add the last non-zero interval
-        curr.push(interval);
-        // reverse intervals such that longest comes first
-        curr.reverse();
 
-        swap(curr, prev);
-        let mut j = pattern.len() as isize;
+        let alphabet = dna::n_alphabet();
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+        assert!(fmdindex.sequence_map().is_empty());
+        assert_eq!(fmdindex.sequence_map().position(0), None);
 
-        for k in (-1..i as isize).rev() {
-            let a = if k == -1 { b'$' } else { pattern[k as usize] };
-            curr.clear();
-            // size of the last confirmed interval
-            let mut last_size = -1;
+        match FMDIndex::from_texts(vec![&b"ACGT"[..], b"ACGU"], 3) {
+            Err(Error::InvalidSymbol {
+                symbol: b'U',
+                pos: 3,
+            }) => (),
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(_) => panic!("Expecting invalid symbol."),
+        }
+    }
 
-            for interval in prev.iter() {
-                // backward extend interval
-                let forward_interval = self.backward_ext(interval, a);
+    #[test]
+    fn test_run_length_fmindex() {
+        let text = b"ACGTTACGTTACGTTACCTTACGTTAGGT$ACGTTACGTTACGTTACGTT$ACGTTACGTTACGGTACGTT$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+        let rlbwt = RunLengthBWT::new(&bwt, 4, &alphabet);
+        let rlfm = FMIndex::with_run_length_bwt(&rlbwt, &less);
+        assert_eq!(rlfm.bwt().to_bwt(), bwt);
+        assert_eq!(rlfm.bwt_len(), fm.bwt_len());
 
-                if (forward_interval.size == 0 || k == -1) &&
-                        // interval could not be extended further
-                        // if no interval has been extended this iteration,
-                        // interval is maximal and can be added to the matches
-                        curr.is_empty() && k < j
-                {
-                    j = k;
-                    matches.push(*interval);
-                }
-                // add _interval to curr (will be further extended next iteration)
-                if forward_interval.size != 0 && forward_interval.size as isize != last_size {
-                    last_size = forward_interval.size as isize;
-                    curr.push(forward_interval);
-                }
-            }
-            if curr.is_empty() {
-                break;
-            }
-            swap(curr, prev);
+        for &pattern in &[&b"TTAC"[..], b"CGGT", b"GTT$", b"AAAA", b""] {
+            assert_eq!(
+                rlfm.backward_search(pattern.iter()),
+                fm.backward_search(pattern.iter())
+            );
         }
+        assert_eq!(
+            rlfm.backward_search_mismatches(b"TACGG", 1, b"ACGT"),
+            fm.backward_search_mismatches(b"TACGG", 1, b"ACGT")
+        );
+        let sampled = sa.sample(&bwt, 5);
+        let interval = rlfm.backward_search(b"CGTT".iter());
+        assert_eq!(
+            rlfm.locate(&interval, &sampled),
+            fm.locate(&interval, &sampled)
+        );
 
-        matches
+        let owned: RunLengthFMIndex = FMIndex::with_run_length_bwt(rlbwt.clone(), less.clone());
+        assert_eq!(
+            owned.backward_search(b"ACGT".iter()),
+            fm.backward_search(b"ACGT".iter())
+        );
     }
 
-    /// Initialize interval with given start character.
-    pub fn init_interval_with(&self, a: u8) -> BiInterval {
-        let comp_a = dna::complement(a);
-        let lower = self.fmindex.less(a);
+    #[test]
+    fn test_merge() {
+        let chunks = [
+            &b"GCCTTAACATTATTACGCCTA$AACA$"[..],
+            b"TTAGG$",
+            b"ACNNGTTAZ$GCCTTAACATTATTACGCCTA$",
+            b"$AC$",
+        ];
+        let builder = IndexBuilder::new().occ_sampling_rate(3).sa_sampling_rate(5);
+        let mut merged = builder.build(chunks[0]).unwrap();
+        let mut text = chunks[0].to_vec();
+        for &chunk in &chunks[1..] {
+            merged = merged.merge(&builder.build(chunk).unwrap());
+            text.extend_from_slice(chunk);
+            let sa = suffix_array(&text);
+            assert_eq!(merged.bwt(), &bwt(&text, &sa));
+            assert_eq!(merged.suffix_array(), &sa.sample(&bwt(&text, &sa), 5));
+            for &pattern in &[&b"TTA"[..], b"GCC", b"NNG", b"GTTA", b"A"] {
+                let mut positions = merged.find(pattern);
+                positions.sort();
+                let expected: Vec<_> = (0..text.len())
+                    .filter(|&i| text[i..].starts_with(pattern))
+                    .collect();
+                assert_eq!(positions, expected);
+            }
+        }
+    }
 
-        BiInterval {
-            lower,
-            lower_rev: self.fmindex.less(comp_a),
-            size: self.fmindex.less(a + 1) - lower,
-            match_size: 1,
+    #[test]
+    fn test_merge_absent_symbols() {
+        let builder = IndexBuilder::new();
+        let merged = builder
+            .build(&b"ACGA$"[..])
+            .unwrap()
+            .merge(&builder.build(&b"CAGA$"[..]).unwrap());
+        for &pattern in &[&b"T"[..], b"N", b"AT", b"TG"] {
+            assert!(merged.find(pattern).is_empty());
+            assert_eq!(merged.count(pattern.iter()), 0);
         }
+        let mut positions = merged.find(b"GA");
+        positions.sort_unstable();
+        assert_eq!(positions, [2, 7]);
     }
 
-    /// Initialize interval for empty pattern. The interval points at the whole suffix array.
-    pub fn init_interval(&self) -> BiInterval {
-        BiInterval {
-            lower: 0,
-            lower_rev: 0,
-            size: self.fmindex.bwt.borrow().len(),
-            match_size: 0,
+    #[test]
+    fn test_backward_search_batch() {
+        let text = b"ACGTTGCAACGGTGCAATCGTTGCA$GATTACAGATTACA$";
+        let index = IndexBuilder::new().build(text).unwrap();
+        let patterns: Vec<&[u8]> = (0..text.len() - 3)
+            .map(|i| &text[i..i + 3])
+            .chain(vec![&b""[..], b"CCCC", b"GATTACA"])
+            .collect();
+        let expected: Vec<_> = patterns
+            .iter()
+            .map(|pattern| index.backward_search(pattern.iter()))
+            .collect();
+        for &threads in &[1, 2, 3, 64] {
+            assert_eq!(index.backward_search_batch(&patterns, threads), expected);
         }
+        assert!(index.backward_search_batch(&[], 4).is_empty());
     }
 
-    /// Backward extension of given interval with given character.
-    pub fn backward_ext(&self, interval: &BiInterval, a: u8) -> BiInterval {
-        let mut s = 0;
-        let mut o = 0;
-        let mut l = interval.lower_rev;
-        // Interval [l(c(aP)), u(c(aP))] is a subinterval of [l(c(P)), u(c(P))] for each a,
-        // starting with the lexicographically smallest ($),
-        // then c(T) = A, c(G) = C, c(C) = G, N, c(A) = T, ...
-        // Hence, we calculate lower revcomp bounds by iterating over
-        // symbols and updating from previous one.
-        for &b in b"$TGCNAtgcna".iter() {
-            l += s;
-            o = if interval.lower == 0 {
-                0
-            } else {
-                self.fmindex.occ(interval.lower - 1, b)
-            };
-            // calculate size
-            s = self.fmindex.occ(interval.lower + interval.size - 1, b) - o;
-            if b == a {
-                break;
+    #[test]
+    fn test_search_observer() {
+        let text = b"ACGTTGCAACGGTGCAATCGTTGCATTTACGATCGGA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        let mut stats = SearchStats::new();
+        let hits = fm.backward_search_mismatches_observed(b"TTGC", 1, b"ACGT", &mut stats);
+        assert_eq!(hits, fm.backward_search_mismatches(b"TTGC", 1, b"ACGT"));
+        assert_eq!(stats.found, hits.len());
+        assert!(stats.pruned > 0 && stats.empty_extensions > 0);
+        assert!(stats.mean_extended_size() > 0.0);
+
+        let mut stats = SearchStats::new();
+        let hits = fm.backward_search_edits_observed(b"CGATCCGA", 1, b"ACGT", &mut stats);
+        assert_eq!(hits, fm.backward_search_edits(b"CGATCCGA", 1, b"ACGT"));
+        assert!(stats.found >= hits.len());
+        assert!(stats.pruned > 0 && stats.extensions > stats.empty_extensions);
+        assert!(SearchStats::new().mean_extended_size().is_nan());
+
+        let mut stats = SearchStats::new();
+        let interval = fm.backward_search_observed(b"TGCA".iter(), &mut stats);
+        assert_eq!(interval, fm.backward_search(b"TGCA".iter()));
+        assert_eq!((stats.extensions, stats.found), (4, 1));
+        let sampled = sa.sample(&bwt, 4);
+        let positions = fm.locate_observed(&interval, &sampled, &mut stats);
+        assert_eq!(positions, interval.occ(&sa));
+        assert!(stats.lf_steps > 0 && stats.lf_steps <= 3 * positions.len());
+
+        let mut stats = SearchStats::new();
+        assert!(fm
+            .backward_search_observed(b"GGGG".iter(), &mut stats)
+            .is_empty());
+        assert_eq!(stats.found, 0);
+        assert_eq!(stats.empty_extensions, 1);
+    }
+
+    #[test]
+    fn test_backward_search_edits() {
+        let text = b"ACGTTGCAACGGTGCAATCGTTGCATTTACGATCGGA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        // exact and mismatch searches agree
+        let pattern = b"CGTTGC";
+        let exact = fm.backward_search_edits(pattern, 0, b"ACGT");
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].interval, fm.backward_search(pattern.iter()));
+        assert_eq!(exact[0].operations, vec![AlignmentOperation::Match; 6]);
+
+        // each hit spells a text word at the given edit distance, according to its script
+        for pattern in [&b"CATTACG"[..], b"TGCAACCGTG", b"GGTGCAAATCG"] {
+            let hits = fm.backward_search_edits(pattern, 2, b"ACGT");
+            assert!(!hits.is_empty());
+            for hit in &hits {
+                let ops = &hit.operations;
+                let cost = ops
+                    .iter()
+                    .filter(|&&op| op != AlignmentOperation::Match)
+                    .count();
+                assert_eq!(cost, hit.edits);
+                let consumed = ops
+                    .iter()
+                    .filter(|&&op| op != AlignmentOperation::Del)
+                    .count();
+                assert_eq!(consumed, pattern.len());
+                let word_len = ops
+                    .iter()
+                    .filter(|&&op| op != AlignmentOperation::Ins)
+                    .count();
+                for pos in hit.interval.occ(&sa) {
+                    let word = &text[pos..pos + word_len];
+                    let (mut x, mut y) = (0, 0);
+                    for &op in ops {
+                        match op {
+                            AlignmentOperation::Match => assert_eq!(pattern[x], word[y]),
+                            AlignmentOperation::Subst => assert_ne!(pattern[x], word[y]),
+                            _ => (),
+                        }
+                        if op != AlignmentOperation::Del {
+                            x += 1;
+                        }
+                        if op != AlignmentOperation::Ins {
+                            y += 1;
+                        }
+                    }
+                }
             }
         }
-        // calculate lower bound
-        let k = self.fmindex.less(a) + o;
+        // TGCAACGGTG with a substitution
+        let hits = fm.backward_search_edits(b"TGCAACCGTG", 2, b"ACGT");
+        assert!(hits
+            .iter()
+            .any(|hit| hit.edits == 1 && hit.interval.occ(&sa) == [4]));
+        assert!(fm.backward_search_edits(b"AAAAAAA", 2, b"ACGT").is_empty());
+    }
 
-        BiInterval {
-            lower: k,
-            lower_rev: l,
-            size: s,
-            match_size: interval.match_size + 1,
+    #[test]
+    fn test_fallible_constructors() {
+        let text = b"ACGTX$";
+        let alphabet = Alphabet::new(text);
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        match FMDIndex::new(FMIndex::new(&bwt, &less, &occ)) {
+            Err(Error::InvalidSymbol { symbol: b'X', pos }) => assert_eq!(bwt[pos], b'X'),
+            _ => panic!("Expecting invalid symbol."),
         }
-    }
 
-    pub fn forward_ext(&self, interval: &BiInterval, a: u8) -> BiInterval {
-        let comp_a = dna::complement(a);
+        let index = IndexBuilder::new().build(b"ACGT$").unwrap();
+        let other = IndexBuilder::new().build(b"ACGTA$").unwrap();
+        let fmindex = FMIndex::new(
+            index.bwt().clone(),
+            less.clone(),
+            Occ::new(index.bwt(), 3, &alphabet),
+        );
+        assert!(SampledFMIndex::new(fmindex, other.suffix_array().clone()).is_err());
+    }
 
-        self.backward_ext(&interval.swapped(), comp_a).swapped()
+    fn owned_fmdindex(text: &[u8]) -> OwnedFMDIndex {
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        FMDIndex::from(FMIndex::new(bwt, less, occ))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use alphabets::dna;
-    use data_structures::bwt::{bwt, less, Occ};
-    use data_structures::suffix_array::suffix_array;
+    #[test]
+    fn test_owned() {
+        let orig_text = b"GCCTTAACATTATTACGCCTA";
+        let text = [&orig_text[..], b"$", &dna::revcomp(orig_text), b"$"].concat();
+        let fmdindex = owned_fmdindex(&text);
+        let interval = fmdindex.backward_search(b"TTA".iter());
+        assert_eq!(interval.upper - interval.lower, 4);
+    }
 
     #[test]
-    fn test_fmindex() {
+    fn test_save_load() {
         let text = b"GCCTTAACATTATTACGCCTA$";
         let alphabet = dna::n_alphabet();
         let sa = suffix_array(text);
@@ -424,12 +3618,113 @@ mod tests {
         let occ = Occ::new(&bwt, 3, &alphabet);
         let fm = FMIndex::new(&bwt, &less, &occ);
 
-        let pattern = b"TTA";
-        let sai = fm.backward_search(pattern.iter());
+        let path = std::env::temp_dir().join("rust-bio-test-fmindex.bin");
+        fm.save(&path).unwrap();
+        let loaded = FMIndex::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.bwt(), &bwt);
+        for pattern in [&b"TTA"[..], b"GCC", b"A", b"CCCC"] {
+            assert_eq!(
+                loaded.backward_search(pattern.iter()).occ(&sa),
+                fm.backward_search(pattern.iter()).occ(&sa)
+            );
+        }
 
-        let positions = sai.occ(&sa);
+        let mut buffer = Vec::new();
+        fm.write(&mut buffer).unwrap();
+        assert!(FMIndex::read(&buffer[..buffer.len() - 1]).is_err());
+        buffer[0] = b'X';
+        assert!(FMIndex::read(&buffer[..]).is_err());
+    }
 
-        assert_eq!(positions, [3, 12, 9]);
+    #[test]
+    fn test_mapped() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACATTATTACGCC";
+        let text = [&orig_text[..], b"$", &dna::revcomp(orig_text), b"$"].concat();
+        let sa = suffix_array(&text);
+        let index = IndexBuilder::new()
+            .sa_sampling_rate(5)
+            .build(&text)
+            .unwrap();
+        let mut data = Vec::new();
+        index.write_mapped(&mut data).unwrap();
+
+        let (fm, mapped_sa) = MappedFMIndex::from_bytes(&data).unwrap();
+        assert_eq!(fm.bwt(), &bwt(&text, &sa)[..]);
+        for pattern in [&b"TTA"[..], b"GCC", b"A", b"CCCC", b"TAACATTATT"] {
+            let interval = fm.backward_search(pattern.iter());
+            assert_eq!(interval, index.backward_search(pattern.iter()));
+            assert_eq!(fm.locate(&interval, &mapped_sa), interval.occ(&sa));
+        }
+
+        let (fmdindex, _) = MappedFMDIndex::from_bytes(&data).unwrap();
+        let expected = owned_fmdindex(&text);
+        assert_eq!(
+            fmdindex.smems(b"ATTATTACGCCTAAGG", 3),
+            expected.smems(b"ATTATTACGCCTAAGG", 3)
+        );
+
+        assert!(MappedFMIndex::from_bytes(&data[..data.len() - 1]).is_err());
+        let mut invalid = data.clone();
+        invalid[0] = b'X';
+        assert!(MappedFMIndex::from_bytes(&invalid).is_err());
+
+        let mut data = Vec::new();
+        IndexBuilder::new()
+            .build(b"ACGTX$")
+            .unwrap()
+            .write_mapped(&mut data)
+            .unwrap();
+        assert!(MappedFMIndex::from_bytes(&data).is_ok());
+        assert!(MappedFMDIndex::from_bytes(&data).is_err());
+    }
+
+    #[test]
+    fn test_locate() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACATTATTACGCC";
+        let text = [&orig_text[..], b"$", &dna::revcomp(orig_text), b"$"].concat();
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fm = FMIndex::new(&bwt, &less, &occ);
+
+        for &rate in &[1, 2, 5, 32, 100] {
+            let sampled = sa.sample(&bwt, rate);
+            let all = Interval {
+                lower: 0,
+                upper: sa.len(),
+            };
+            assert_eq!(fm.locate(&all, &sampled), sa);
+            for pattern in [&b"TTA"[..], b"GCC", b"CCCC"] {
+                let interval = fm.backward_search(pattern.iter());
+                assert_eq!(fm.locate(&interval, &sampled), interval.occ(&sa));
+            }
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        let text = b"GCCTTAACATTA$TTACGCCTAACATTATTACGCC$ACGT$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let fm = FMIndex::new(&bwt, less(&bwt, &alphabet), Occ::new(&bwt, 3, &alphabet));
+        let rlbwt = RunLengthBWT::new(&bwt, 4, &alphabet);
+        let rlfm = FMIndex::with_run_length_bwt(&rlbwt, less(&bwt, &alphabet));
+
+        for &rate in &[1, 3, 8, 100] {
+            let isa = sa.sample_inverse(&bwt, rate);
+            assert_eq!(fm.extract(&isa, 0, text.len()), &text[..]);
+            for start in 0..=text.len() {
+                for len in 0..=(text.len() - start).min(12) {
+                    let expected = &text[start..start + len];
+                    assert_eq!(fm.extract(&isa, start, len), expected);
+                    assert_eq!(rlfm.extract(&isa, start, len), expected);
+                }
+            }
+        }
     }
 
     #[test]
@@ -454,6 +3749,11 @@ mod tests {
             let revcomp = intervals[0].revcomp();
             assert_eq!(forward.occ(&sa), [5, 16]);
             assert_eq!(revcomp.occ(&sa), [3, 14]);
+            assert_eq!(intervals[0].lower(), forward.lower);
+            assert_eq!(intervals[0].lower_rev(), revcomp.lower);
+            assert_eq!(intervals[0].size(), 2);
+            assert!(intervals[0].occ_iter(&sa).eq(vec![5, 16]));
+            assert_eq!(forward.occ_iter(&sa).next(), Some(5));
         }
         {
             let pattern = b"CTTAA";
@@ -464,6 +3764,170 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_all_smems() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACGTGCAATTGACCAGT";
+        let revcomp_text = dna::revcomp(orig_text);
+        let text = [&orig_text[..], b"$", &revcomp_text[..], b"$"].concat();
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+
+        // two pieces of the text separated by a foreign base
+        let read = b"CATTATTACGCNTGCAATTGAC";
+        let smems = fmdindex.all_smems(read, 8);
+        let ranges: Vec<_> = smems.iter().map(|smem| (smem.start, smem.end)).collect();
+        assert_eq!(ranges, [(0, 11), (12, 22)]);
+        assert_eq!(smems[0].interval.forward().occ(&sa), [7]);
+        assert_eq!(smems[1].interval.forward().occ(&sa), [24]);
+        for smem in &smems {
+            // the matches are as reported by smems
+            assert!(fmdindex.smems(read, smem.start).contains(&smem.interval));
+        }
+        assert!(fmdindex.all_smems(read, 12).is_empty());
+        assert!(fmdindex.all_smems(b"", 1).is_empty());
+    }
+
+    #[test]
+    fn test_mems() {
+        let mut rng = Xoshiro256::seed_from_u64(3);
+        let mut random_seq =
+            |len: usize| -> Vec<u8> { (0..len).map(|_| b"ACGT"[rng.below(4) as usize]).collect() };
+        let seqs = [random_seq(60), random_seq(45)];
+        let text: Vec<u8> = seqs
+            .iter()
+            .flat_map(|seq| [&seq[..], b"$", &dna::revcomp(seq), b"$"].concat())
+            .collect();
+        let (fmdindex, sa) = FMDIndex::from_texts(seqs.iter().map(|seq| &seq[..]), 3).unwrap();
+
+        // a read built from pieces of both sequences, with a mismatch
+        let mut read = [&seqs[0][10..30], &seqs[1][5..25], &seqs[0][28..40]].concat();
+        read[8] = if read[8] == b'A' { b'C' } else { b'A' };
+        for &(min_len, max_occ) in &[(1, 1000), (4, 1000), (4, 3), (10, 2)] {
+            // naive MEMs: extend each pair of pattern and text positions with maximal left end
+            let mut expected = Vec::new();
+            for start in 0..read.len() {
+                for p in 0..text.len() {
+                    if start > 0 && p > 0 && read[start - 1] == text[p - 1] {
+                        continue;
+                    }
+                    let len = read[start..]
+                        .iter()
+                        .zip(&text[p..])
+                        .take_while(|&(a, b)| a == b)
+                        .count();
+                    let occ = (0..text.len())
+                        .filter(|&q| text[q..].starts_with(&read[start..start + len]))
+                        .count();
+                    if len >= min_len && occ <= max_occ {
+                        expected.push((start, start + len));
+                    }
+                }
+            }
+            expected.sort_unstable();
+            expected.dedup();
+
+            let mems = fmdindex.mems(&read, min_len, max_occ);
+            let ranges: Vec<_> = mems.iter().map(|mem| (mem.start, mem.end)).collect();
+            assert_eq!(ranges, expected);
+            for mem in &mems {
+                for pos in mem.interval.forward().occ(&sa) {
+                    assert_eq!(
+                        &text[pos..pos + mem.end - mem.start],
+                        &read[mem.start..mem.end]
+                    );
+                }
+            }
+        }
+
+        // SMEMs are MEMs
+        let mems = fmdindex.mems(&read, 8, usize::MAX);
+        for smem in fmdindex.all_smems(&read, 8) {
+            assert!(mems
+                .iter()
+                .any(|mem| (mem.start, mem.end) == (smem.start, smem.end)));
+        }
+        assert!(fmdindex.mems(b"", 1, 10).is_empty());
+    }
+
+    #[test]
+    fn test_longest_matches() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACGTGCAATTGACCAGT";
+        let revcomp_text = dna::revcomp(orig_text);
+        let text = [&orig_text[..], b"$", &revcomp_text[..], b"$"].concat();
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+
+        for read in &[
+            &b"CATTATTACGCNTGCAATTGAC"[..],
+            b"TTAGGACGTGGGCCTAACNACGTG",
+            b"A",
+        ] {
+            let mut expected = fmdindex.all_smems(read, 1);
+            expected.sort_by_key(|smem| (cmp::Reverse(smem.end - smem.start), smem.start));
+            for k in 0..expected.len() + 2 {
+                let matches = fmdindex.longest_matches(read, k);
+                assert_eq!(matches.len(), k.min(expected.len()));
+                for (smem, expected) in matches.iter().zip(&expected) {
+                    assert_eq!((smem.start, smem.end), (expected.start, expected.end));
+                    assert_eq!(smem.interval, expected.interval);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_longest_matches_ties() {
+        let orig_text = b"AAGTGCGTTCTCGAGCTCGCACGAATCC";
+        let text = [&orig_text[..], b"$", &dna::revcomp(orig_text), b"$"].concat();
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+
+        // the SMEMs (14, 17) and (16, 19) are equally long, the latter being found first
+        let matches: Vec<_> = fmdindex
+            .longest_matches(b"AAGTGCGTTCTCGAGCAATGG", 2)
+            .iter()
+            .map(|smem| (smem.start, smem.end))
+            .collect();
+        assert_eq!(matches, [(0, 16), (14, 17)]);
+    }
+
+    #[test]
+    fn test_smems_iter() {
+        let orig_text = b"GCCTTAACATTATTACGCCTAACGTGCAATTGACCAGT";
+        let revcomp_text = dna::revcomp(orig_text);
+        let text = [&orig_text[..], b"$", &revcomp_text[..], b"$"].concat();
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+
+        let mut searcher = fmdindex.smem_searcher();
+        for read in &[&b"CATTATTACGCNTGCAATTGAC"[..], b"TTAGGCGTAA", b"A"] {
+            for i in 0..read.len() {
+                let smems: Vec<_> = searcher.smems_iter(read, i).collect();
+                assert_eq!(smems, fmdindex.smems(read, i));
+            }
+        }
+        // partially consumed iterators do not affect the next search
+        assert!(searcher.smems_iter(b"CATTATTACGC", 3).next().is_some());
+        let smems: Vec<_> = searcher.smems_iter(b"GCAATT", 0).collect();
+        assert_eq!(smems, fmdindex.smems(b"GCAATT", 0));
+    }
+
     #[test]
     fn test_init_interval() {
         let text = b"ACGT$TGCA$";
@@ -554,9 +4018,7 @@ mod tests {
         let read_pos = 0;
 
         for i in 0..read.len() {
-            println!("i {}", i);
             let intervals = fmdindex.smems(read, i);
-            println!("{:?}", intervals);
             let matches = intervals
                 .iter()
                 .flat_map(|interval| interval.forward().occ(&sa))
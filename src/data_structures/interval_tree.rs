@@ -48,8 +48,8 @@ impl<N: Ord + Clone, D> Default for IntervalTree<N, D> {
 /// wraps the fields `interval` and `data` in an `Entry`.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Entry<'a, N: Ord + Clone + 'a, D: 'a> {
-    data: &'a D,
-    interval: &'a Interval<N>,
+    pub(crate) data: &'a D,
+    pub(crate) interval: &'a Interval<N>,
 }
 
 impl<'a, N: Ord + Clone + 'a, D: 'a> Entry<'a, N, D> {
@@ -366,7 +366,7 @@ fn swap_interval_data<N: Ord + Clone, D>(node_1: &mut Node<N, D>, node_2: &mut N
     mem::swap(&mut node_1.interval, &mut node_2.interval);
 }
 
-fn intersect<N: Ord + Clone>(range_1: &Interval<N>, range_2: &Interval<N>) -> bool {
+pub(crate) fn intersect<N: Ord + Clone>(range_1: &Interval<N>, range_2: &Interval<N>) -> bool {
     range_1.start < range_1.end
         && range_2.start < range_2.end
         && range_1.end > range_2.start
@@ -5,14 +5,44 @@
 
 //! Various useful data structures.
 
+#[cfg(feature = "std")]
 pub mod annot_map;
+#[cfg(feature = "std")]
+pub mod bisulfite_index;
 pub mod bit_tree;
+#[cfg(feature = "std")]
 pub mod bitenc;
+#[cfg(feature = "std")]
 pub mod bwt;
+#[cfg(feature = "std")]
+pub mod compressed_ints;
+#[cfg(feature = "std")]
+pub mod document_array;
+#[cfg(feature = "std")]
 pub mod fmindex;
+#[cfg(feature = "std")]
 pub mod interpolation_table;
+#[cfg(feature = "std")]
 pub mod interval_tree;
+#[cfg(feature = "std")]
+pub mod packed_seq;
+#[cfg(feature = "std")]
+pub mod persistent_interval_tree;
+#[cfg(feature = "std")]
 pub mod qgram_index;
+#[cfg(feature = "std")]
 pub mod rank_select;
+#[cfg(feature = "std")]
+pub mod rmq;
+#[cfg(feature = "std")]
+pub mod segment_tree;
+#[cfg(feature = "std")]
 pub mod smallints;
+#[cfg(feature = "std")]
 pub mod suffix_array;
+#[cfg(feature = "std")]
+pub mod taxonomy;
+#[cfg(feature = "std")]
+pub mod union_find;
+#[cfg(feature = "std")]
+pub mod wavelet_tree;
@@ -0,0 +1,114 @@
+//! A DNA sequence packed with two bits per base, such that indexes of large genomes can be
+//! built without keeping the sequence as bytes in memory. Symbols other than `ACGT` (e.g. `N`,
+//! lowercase bases or the sentinel `$`) are kept separately, which is efficient as long as they
+//! are rare.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::packed_seq::PackedDnaSeq;
+//! use bio::data_structures::suffix_array::suffix_array;
+//!
+//! let seq = PackedDnaSeq::new(b"GCCTTAACATTATTACGCCTA$");
+//! assert_eq!(seq.get(21), Some(b'$'));
+//! assert_eq!(suffix_array(&seq), suffix_array(b"GCCTTAACATTATTACGCCTA$"));
+//! ```
+
+use std::collections::BTreeMap;
+
+use data_structures::bitenc::BitEnc;
+use utils::IndexableText;
+
+/// A DNA sequence with two bits per base.
+#[derive(Serialize, Deserialize)]
+pub struct PackedDnaSeq {
+    bases: BitEnc,
+    /// Symbols other than `ACGT` by position.
+    others: BTreeMap<usize, u8>,
+}
+
+impl Default for PackedDnaSeq {
+    fn default() -> Self {
+        PackedDnaSeq {
+            bases: BitEnc::new(2),
+            others: BTreeMap::new(),
+        }
+    }
+}
+
+impl PackedDnaSeq {
+    /// Pack the given sequence.
+    pub fn new(seq: &[u8]) -> Self {
+        let mut packed = PackedDnaSeq {
+            bases: BitEnc::with_capacity(2, seq.len()),
+            others: BTreeMap::new(),
+        };
+        for &a in seq {
+            packed.push(a);
+        }
+        packed
+    }
+
+    /// Append a symbol.
+    pub fn push(&mut self, a: u8) {
+        let code = match a {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' => 3,
+            _ => {
+                self.others.insert(self.bases.len(), a);
+                0
+            }
+        };
+        self.bases.push(code);
+    }
+
+    /// The symbol at position `i`.
+    pub fn get(&self, i: usize) -> Option<u8> {
+        self.bases.get(i).map(|code| match self.others.get(&i) {
+            Some(&a) => a,
+            None => b"ACGT"[code as usize],
+        })
+    }
+
+    /// Iterate over the symbols.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len()).map(move |i| self.symbol(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.bases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty()
+    }
+}
+
+impl IndexableText for PackedDnaSeq {
+    fn len(&self) -> usize {
+        self.bases.len()
+    }
+
+    fn symbol(&self, i: usize) -> u8 {
+        self.get(i)
+            .expect("Expecting position within the sequence.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_dna_seq() {
+        let seq = b"ACGTNNacgtRACGTACGTACGTACGTACGTACGTACG$";
+        let packed = PackedDnaSeq::new(seq);
+        assert_eq!(packed.len(), seq.len());
+        assert_eq!(packed.iter().collect::<Vec<_>>(), seq.to_vec());
+        assert_eq!(packed.symbols(4..8).collect::<Vec<_>>(), b"NNac");
+        assert_eq!(packed.get(seq.len()), None);
+        assert!(PackedDnaSeq::default().is_empty());
+    }
+}
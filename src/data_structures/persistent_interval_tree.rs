@@ -0,0 +1,375 @@
+//! An immutable, structurally shared interval tree for concurrent readers.
+//!
+//! Inserting into a `PersistentIntervalTree` does not modify the tree but returns a new version
+//! that shares all untouched nodes with the previous one (path copying in an augmented AVL tree).
+//! Old versions stay valid and can be queried from other threads while new versions are built.
+//! A `Snapshot` cell allows a writer to publish new versions that readers pick up with `load()`.
+//! Readers only hold a lock while cloning an `Arc`, never while querying.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::persistent_interval_tree::{PersistentIntervalTree, Snapshot};
+//! use std::sync::Arc;
+//! use std::thread;
+//!
+//! let v1 = PersistentIntervalTree::new().insert(11..20, "gene_1");
+//! let published = Arc::new(Snapshot::new(v1));
+//!
+//! let reader = {
+//!     let published = published.clone();
+//!     thread::spawn(move || published.load().find(15..25).count())
+//! };
+//!
+//! // the writer publishes an updated version, existing readers are not affected
+//! let v2 = published.load().insert(22..30, "gene_2");
+//! published.store(v2);
+//!
+//! assert!(reader.join().unwrap() >= 1);
+//! assert_eq!(published.load().find(15..25).count(), 2);
+//! ```
+
+use std::cmp;
+use std::iter::FromIterator;
+use std::sync::{Arc, RwLock};
+
+use data_structures::interval_tree::{intersect, Entry};
+use utils::Interval;
+
+#[derive(Debug)]
+struct Node<N: Ord + Clone, D> {
+    interval: Interval<N>,
+    value: Arc<D>,
+    max: N,
+    height: i64,
+    len: usize,
+    left: Option<Arc<Node<N, D>>>,
+    right: Option<Arc<Node<N, D>>>,
+}
+
+type Link<N, D> = Option<Arc<Node<N, D>>>;
+
+fn height<N: Ord + Clone, D>(link: &Link<N, D>) -> i64 {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+fn size<N: Ord + Clone, D>(link: &Link<N, D>) -> usize {
+    link.as_ref().map_or(0, |n| n.len)
+}
+
+/// Create a node with metadata derived from its children.
+fn node<N: Ord + Clone, D>(
+    interval: Interval<N>,
+    value: Arc<D>,
+    left: Link<N, D>,
+    right: Link<N, D>,
+) -> Arc<Node<N, D>> {
+    let mut max = interval.end.clone();
+    for child in left.iter().chain(right.iter()) {
+        if max < child.max {
+            max = child.max.clone();
+        }
+    }
+    Arc::new(Node {
+        height: 1 + cmp::max(height(&left), height(&right)),
+        len: 1 + size(&left) + size(&right),
+        interval,
+        value,
+        max,
+        left,
+        right,
+    })
+}
+
+/// Create a node like `node`, rotating if the AVL balance is violated by at most two.
+fn balance<N: Ord + Clone, D>(
+    interval: Interval<N>,
+    value: Arc<D>,
+    left: Link<N, D>,
+    right: Link<N, D>,
+) -> Arc<Node<N, D>> {
+    let (hl, hr) = (height(&left), height(&right));
+    if hl > hr + 1 {
+        let l = left.expect("bug: taller subtree must exist");
+        if height(&l.left) >= height(&l.right) {
+            let r = node(interval, value, l.right.clone(), right);
+            node(l.interval.clone(), l.value.clone(), l.left.clone(), Some(r))
+        } else {
+            let lr = l.right.as_ref().expect("bug: taller subtree must exist");
+            let new_left = node(
+                l.interval.clone(),
+                l.value.clone(),
+                l.left.clone(),
+                lr.left.clone(),
+            );
+            let new_right = node(interval, value, lr.right.clone(), right);
+            node(
+                lr.interval.clone(),
+                lr.value.clone(),
+                Some(new_left),
+                Some(new_right),
+            )
+        }
+    } else if hr > hl + 1 {
+        let r = right.expect("bug: taller subtree must exist");
+        if height(&r.right) >= height(&r.left) {
+            let l = node(interval, value, left, r.left.clone());
+            node(
+                r.interval.clone(),
+                r.value.clone(),
+                Some(l),
+                r.right.clone(),
+            )
+        } else {
+            let rl = r.left.as_ref().expect("bug: taller subtree must exist");
+            let new_left = node(interval, value, left, rl.left.clone());
+            let new_right = node(
+                r.interval.clone(),
+                r.value.clone(),
+                rl.right.clone(),
+                r.right.clone(),
+            );
+            node(
+                rl.interval.clone(),
+                rl.value.clone(),
+                Some(new_left),
+                Some(new_right),
+            )
+        }
+    } else {
+        node(interval, value, left, right)
+    }
+}
+
+fn insert<N: Ord + Clone, D>(
+    link: &Link<N, D>,
+    interval: Interval<N>,
+    value: Arc<D>,
+) -> Arc<Node<N, D>> {
+    match *link {
+        None => node(interval, value, None, None),
+        Some(ref n) => {
+            if interval.start <= n.interval.start {
+                let left = insert(&n.left, interval, value);
+                balance(
+                    n.interval.clone(),
+                    n.value.clone(),
+                    Some(left),
+                    n.right.clone(),
+                )
+            } else {
+                let right = insert(&n.right, interval, value);
+                balance(
+                    n.interval.clone(),
+                    n.value.clone(),
+                    n.left.clone(),
+                    Some(right),
+                )
+            }
+        }
+    }
+}
+
+/// An immutable interval tree. Cloning is O(1) and insertion returns a new version in
+/// O(log n) time and space.
+#[derive(Debug)]
+pub struct PersistentIntervalTree<N: Ord + Clone, D> {
+    root: Link<N, D>,
+}
+
+impl<N: Ord + Clone, D> Clone for PersistentIntervalTree<N, D> {
+    fn clone(&self) -> Self {
+        PersistentIntervalTree {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<N: Ord + Clone, D> Default for PersistentIntervalTree<N, D> {
+    fn default() -> Self {
+        PersistentIntervalTree { root: None }
+    }
+}
+
+impl<N: Ord + Clone, D> PersistentIntervalTree<N, D> {
+    /// Creates a new empty tree.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Number of stored intervals.
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Check whether the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Return a new version of the tree that additionally contains the given interval
+    /// associated with `data`. The current version is left untouched.
+    pub fn insert<I: Into<Interval<N>>>(&self, interval: I, data: D) -> Self {
+        PersistentIntervalTree {
+            root: Some(insert(&self.root, interval.into(), Arc::new(data))),
+        }
+    }
+
+    /// Find all intervals overlapping the given one.
+    pub fn find<I: Into<Interval<N>>>(
+        &self,
+        interval: I,
+    ) -> PersistentIntervalTreeIterator<'_, N, D> {
+        PersistentIntervalTreeIterator {
+            nodes: self.root.iter().map(|n| &**n).collect(),
+            interval: interval.into(),
+        }
+    }
+}
+
+impl<N: Ord + Clone, D, R: Into<Interval<N>>> FromIterator<(R, D)>
+    for PersistentIntervalTree<N, D>
+{
+    fn from_iter<I: IntoIterator<Item = (R, D)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentIntervalTree::new(), |tree, (r, d)| {
+                tree.insert(r, d)
+            })
+    }
+}
+
+/// Iterator over the entries overlapping a query, returned by `PersistentIntervalTree::find`.
+pub struct PersistentIntervalTreeIterator<'a, N: Ord + Clone + 'a, D: 'a> {
+    nodes: Vec<&'a Node<N, D>>,
+    interval: Interval<N>,
+}
+
+impl<'a, N: Ord + Clone + 'a, D: 'a> Iterator for PersistentIntervalTreeIterator<'a, N, D> {
+    type Item = Entry<'a, N, D>;
+
+    fn next(&mut self) -> Option<Entry<'a, N, D>> {
+        while let Some(candidate) = self.nodes.pop() {
+            // stop traversal if the query interval is beyond the current node and all children
+            if self.interval.start < candidate.max {
+                if let Some(ref left) = candidate.left {
+                    self.nodes.push(left);
+                }
+                if self.interval.end > candidate.interval.start {
+                    if let Some(ref right) = candidate.right {
+                        self.nodes.push(right);
+                    }
+                    if intersect(&self.interval, &candidate.interval) {
+                        return Some(Entry {
+                            data: &candidate.value,
+                            interval: &candidate.interval,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// A cell holding the currently published version of an immutable value, e.g. a
+/// `PersistentIntervalTree`. Readers obtain the current version with `load()`, a writer
+/// replaces it with `store()`. Versions that are still in use by readers stay alive.
+#[derive(Debug, Default)]
+pub struct Snapshot<T> {
+    current: RwLock<Arc<T>>,
+}
+
+impl<T> Snapshot<T> {
+    /// Create a new cell publishing the given version.
+    pub fn new(value: T) -> Self {
+        Snapshot {
+            current: RwLock::new(Arc::new(value)),
+        }
+    }
+
+    /// Return the currently published version.
+    pub fn load(&self) -> Arc<T> {
+        self.current
+            .read()
+            .expect("bug: writer panicked while publishing")
+            .clone()
+    }
+
+    /// Publish a new version.
+    pub fn store(&self, value: T) {
+        let value = Arc::new(value);
+        *self
+            .current
+            .write()
+            .expect("bug: writer panicked while publishing") = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn validate<N: Ord + Clone, D>(link: &Link<N, D>) -> i64 {
+        match *link {
+            None => 0,
+            Some(ref n) => {
+                let (hl, hr) = (validate(&n.left), validate(&n.right));
+                assert!((hl - hr).abs() <= 1);
+                assert_eq!(n.height, 1 + cmp::max(hl, hr));
+                n.height
+            }
+        }
+    }
+
+    #[test]
+    fn test_versions() {
+        let mut versions = vec![PersistentIntervalTree::new()];
+        for i in 0..100i64 {
+            let start = (i * 37) % 101;
+            let next = versions.last().unwrap().insert(start..start + 5, i);
+            versions.push(next);
+        }
+        for (i, version) in versions.iter().enumerate() {
+            assert_eq!(version.len(), i);
+            validate(&version.root);
+        }
+
+        let tree = versions.last().unwrap();
+        let mut found: Vec<i64> = tree.find(10..12).map(|e| *e.data()).collect();
+        found.sort();
+        let mut expected: Vec<i64> = (0..100)
+            .filter(|i| {
+                let start = (i * 37) % 101;
+                start < 12 && start + 5 > 10
+            })
+            .collect();
+        expected.sort();
+        assert_eq!(found, expected);
+        // old versions do not see later insertions
+        assert_eq!(versions[1].find(0..200).count(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_threads() {
+        let tree: PersistentIntervalTree<u32, u32> =
+            (0..50).map(|i| (i * 10..i * 10 + 10, i)).collect();
+        let published = Arc::new(Snapshot::new(tree));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let published = published.clone();
+                thread::spawn(move || {
+                    let snapshot = published.load();
+                    snapshot.find(95..105).count()
+                })
+            })
+            .collect();
+        let updated = published.load().insert(100..101, 1000);
+        published.store(updated);
+        for reader in readers {
+            let count = reader.join().unwrap();
+            assert!(count == 2 || count == 3);
+        }
+        assert_eq!(published.load().find(95..105).count(), 3);
+    }
+}
@@ -29,11 +29,11 @@
 
 use std;
 use std::cmp;
-use std::collections;
 use std::collections::hash_map::Entry;
 
 use alphabets::{Alphabet, RankTransform};
 use utils;
+use utils::KmerHashMap;
 
 /// A classical, flexible, q-gram index implementation.
 #[derive(Serialize, Deserialize)]
@@ -109,7 +109,7 @@ impl QGramIndex {
     /// Complexity O(m + k) for pattern of length m and k being the number of matching q-grams.
     pub fn matches(&self, pattern: &[u8], min_count: usize) -> Vec<Match> {
         let q = self.q as usize;
-        let mut diagonals = collections::HashMap::new();
+        let mut diagonals = KmerHashMap::default();
         for (i, qgram) in self.ranks.qgrams(self.q, pattern).enumerate() {
             for &p in self.qgram_matches(qgram) {
                 let diagonal = p - i;
@@ -146,7 +146,7 @@ impl QGramIndex {
     /// Complexity O(m + k) for pattern of length m and k being the number of matching q-grams.
     pub fn exact_matches(&self, pattern: &[u8]) -> Vec<ExactMatch> {
         let q = self.q as usize;
-        let mut diagonals = collections::HashMap::new();
+        let mut diagonals = KmerHashMap::default();
         let mut matches = Vec::new();
 
         for (i, qgram) in self.ranks.qgrams(self.q, pattern).enumerate() {
@@ -100,7 +100,7 @@ impl RankSelect {
             let mask = ((2u16 << j) - 1) as u8;
             rank += (self.bits.get_block(b as usize) & mask).count_ones() as u64;
             // add the popcounts of blocks in between
-            for block in s * 32 / 8..b {
+            for block in s * self.s as u64 / 8..b {
                 let b = self.bits.get_block(block as usize);
                 rank += b.count_ones() as u64;
             }
@@ -161,12 +161,10 @@ impl RankSelect {
         is_match: F,
         count_all: C,
     ) -> Option<u64> {
-        let mut superblock = match superblocks.binary_search(&j) {
-            Ok(i) | Err(i) => i, // superblock with same rank exists
-        };
-        if superblock > 0 {
-            superblock -= 1;
-        }
+        // first superblock with a rank of at least j, the bit is located in the one before
+        let superblock = superblocks
+            .partition_point(|&rank| rank < j)
+            .saturating_sub(1);
         let mut rank = superblocks[superblock];
 
         let first_block = superblock * self.s / 8;
@@ -180,7 +178,7 @@ impl RankSelect {
                 for i in 0..max_bit {
                     rank += is_match(b & bit) as u64;
                     if rank == j {
-                        return Some((first_block + block) as u64 * 8 + i);
+                        return Some(block as u64 * 8 + i);
                     }
                     bit <<= 1;
                 }
@@ -284,4 +282,35 @@ mod tests {
         assert_eq!(rs.rank_0(0), Some(1));
         assert_eq!(rs.rank_1(0), Some(0));
     }
+
+    #[test]
+    fn test_select_beyond_first_superblock() {
+        let mut bits: BitVec<u8> = BitVec::new_fill(false, 256);
+        for i in (0..128).chain(200..210) {
+            bits.set_bit(i, true);
+        }
+        let rs = RankSelect::new(bits, 1);
+        for j in 1..=128 {
+            assert_eq!(rs.select_1(j), Some(j - 1));
+        }
+        assert_eq!(rs.select_1(129), Some(200));
+        assert_eq!(rs.select_1(138), Some(209));
+        assert_eq!(rs.select_1(139), None);
+        assert_eq!(rs.select_0(1), Some(128));
+    }
+
+    #[test]
+    fn test_rank_larger_superblocks() {
+        let mut bits: BitVec<u8> = BitVec::new_fill(false, 512);
+        for i in (0..512).step_by(3) {
+            bits.set_bit(i, true);
+        }
+        let rs = RankSelect::new(bits, 4);
+        for i in 0..512 {
+            assert_eq!(rs.rank_1(i), Some(i / 3 + 1));
+        }
+        for j in 1..=171 {
+            assert_eq!(rs.select_1(j), Some((j - 1) * 3));
+        }
+    }
 }
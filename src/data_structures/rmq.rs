@@ -0,0 +1,334 @@
+//! Range minimum queries (RMQ) over slices of totally ordered values.
+//! A query for the inclusive range `[i, j]` returns the position of the leftmost minimum
+//! in that range.
+//!
+//! * `SparseTableRMQ`: O(n log n) words of preprocessing, O(1) query time.
+//! * `SuccinctRMQ`: 2n + o(n) bits, answering queries without access to the original values.
+//!   The array is encoded as balanced parentheses of its left-to-right minima tree
+//!   (Ferrada and Navarro, 2016), and a query is reduced to finding the rightmost minimum
+//!   excess between the corresponding opening parentheses.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::rmq::{SparseTableRMQ, SuccinctRMQ};
+//!
+//! let values = [5, 2, 7, 2, 9, 1, 4];
+//! let sparse = SparseTableRMQ::new(&values[..]);
+//! let succinct = SuccinctRMQ::new(&values);
+//! assert_eq!(sparse.query(0, 4), 1);
+//! assert_eq!(succinct.query(0, 4), 1);
+//! assert_eq!(succinct.query(2, 6), 5);
+//! ```
+
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::marker::PhantomData;
+
+use bv::{BitVec, Bits, BitsMut};
+
+use data_structures::rank_select::RankSelect;
+
+/// Floor of the binary logarithm (with log(0) defined as 0).
+fn log2(n: usize) -> usize {
+    if n == 0 {
+        0
+    } else {
+        (63 - (n as u64).leading_zeros()) as usize
+    }
+}
+
+/// Sparse table for constant time range minimum queries (Bender and Farach-Colton, 2000).
+/// Like `FMIndex`, it can take a reference or an owned container of the values.
+#[derive(Serialize, Deserialize)]
+pub struct SparseTableRMQ<T: Ord, D: Borrow<[T]>> {
+    values: D,
+    table: Vec<Vec<usize>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Ord, D: Borrow<[T]>> SparseTableRMQ<T, D> {
+    /// Build the sparse table. Complexity: O(n log n).
+    pub fn new(values: D) -> Self {
+        let n = values.borrow().len();
+        let mut table: Vec<Vec<usize>> = vec![(0..n).collect()];
+        {
+            let v = values.borrow();
+            for k in 1..=log2(n) {
+                let half = 1 << (k - 1);
+                let level = {
+                    let prev = &table[k - 1];
+                    (0..n + 1 - (1 << k))
+                        .map(|i| {
+                            let (a, b) = (prev[i], prev[i + half]);
+                            if v[b] < v[a] {
+                                b
+                            } else {
+                                a
+                            }
+                        })
+                        .collect()
+                };
+                table.push(level);
+            }
+        }
+
+        SparseTableRMQ {
+            values,
+            table,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Number of values.
+    pub fn len(&self) -> usize {
+        self.values.borrow().len()
+    }
+
+    /// Check whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Position of the leftmost minimum in the inclusive range `[i, j]`. Complexity: O(1).
+    pub fn query(&self, i: usize, j: usize) -> usize {
+        assert!(i <= j && j < self.len(), "Invalid query range.");
+        let k = log2(j - i + 1);
+        let (a, b) = (self.table[k][i], self.table[k][j + 1 - (1 << k)]);
+        if self.values.borrow()[b] < self.values.borrow()[a] {
+            b
+        } else {
+            a
+        }
+    }
+}
+
+/// Minimum excess of each superblock, together with the superblock index for rightmost tie
+/// breaking.
+type SuperblockMinima = SparseTableRMQ<(isize, Reverse<usize>), Vec<(isize, Reverse<usize>)>>;
+
+/// Succinct range minimum query structure using 2n + o(n) bits.
+///
+/// The parentheses are divided into blocks of log² n bits, which are grouped into superblocks
+/// of log n blocks. A sparse table over the superblocks takes O(n / log³ n · log n) words, i.e.
+/// O(n / log n) bits, and the minimum excess of each block is stored relative to the start of
+/// its superblock in 32 bits, i.e. O(n / log n) bits as well.
+#[derive(Serialize, Deserialize)]
+pub struct SuccinctRMQ {
+    parens: RankSelect,
+    block_size: usize,
+    superblock_len: usize,
+    block_min: Vec<i32>,
+    superblock_min: SuperblockMinima,
+    n: usize,
+}
+
+impl SuccinctRMQ {
+    /// Build the structure for the given values. The values are not needed afterwards.
+    /// Complexity: O(n).
+    pub fn new<T: Ord>(values: &[T]) -> Self {
+        let n = values.len();
+        let len = 2 * n as u64;
+        // Write an opening parenthesis (1) for each pushed and a closing one (0) for each
+        // popped element of the stack of left-to-right minima.
+        let mut bits: BitVec<u8> = BitVec::new_fill(false, len);
+        let mut stack: Vec<&T> = Vec::new();
+        let mut p = 0;
+        for v in values {
+            while stack.last().is_some_and(|&top| top > v) {
+                stack.pop();
+                p += 1;
+            }
+            stack.push(v);
+            bits.set_bit(p, true);
+            p += 1;
+        }
+
+        let lg = log2(2 * n).max(1);
+        let block_size = (lg * lg).max(64);
+        let superblock_len = lg;
+        let mut block_min = Vec::new();
+        let mut superblock_min = Vec::new();
+        let mut excess = 0isize;
+        let mut superblock_excess = 0isize;
+        for (b, start) in (0..len).step_by(block_size).enumerate() {
+            if b % superblock_len == 0 {
+                superblock_excess = excess;
+                superblock_min.push((excess, Reverse(b / superblock_len)));
+            }
+            let mut min = excess;
+            for q in start..(start + block_size as u64).min(len) {
+                min = min.min(excess);
+                excess += if bits.get_bit(q) { 1 } else { -1 };
+            }
+            block_min.push((min - superblock_excess) as i32);
+            let last = superblock_min.last_mut().unwrap();
+            last.0 = last.0.min(min);
+        }
+
+        let k = (lg * lg / 32).max(1);
+        SuccinctRMQ {
+            parens: RankSelect::new(bits, k),
+            block_size,
+            superblock_len,
+            block_min,
+            superblock_min: SparseTableRMQ::new(superblock_min),
+            n,
+        }
+    }
+
+    /// Number of values.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// Check whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Excess (opening minus closing parentheses) before position `p`.
+    fn excess(&self, p: u64) -> isize {
+        if p == 0 {
+            0
+        } else {
+            2 * self.parens.rank_1(p - 1).unwrap() as isize - p as isize
+        }
+    }
+
+    /// Rightmost block of minimum excess in the blocks `[a, b]` by scanning their minima.
+    fn scan_blocks(&self, a: usize, b: usize, best: &mut (isize, usize)) {
+        for block in a..=b {
+            let superblock_start = (block / self.superblock_len * self.superblock_len) as u64;
+            let min = self.excess(superblock_start * self.block_size as u64)
+                + self.block_min[block] as isize;
+            if min <= best.0 {
+                *best = (min, block);
+            }
+        }
+    }
+
+    /// Rightmost block of minimum excess in the blocks `[a, b]`.
+    fn min_block(&self, a: usize, b: usize) -> usize {
+        let l = self.superblock_len;
+        let (sa, sb) = (a / l, b / l);
+        let mut best = (isize::MAX, a);
+        if sb <= sa + 1 {
+            self.scan_blocks(a, b, &mut best);
+        } else {
+            self.scan_blocks(a, (sa + 1) * l - 1, &mut best);
+            let s = self.superblock_min.query(sa + 1, sb - 1);
+            self.scan_blocks(s * l, (s + 1) * l - 1, &mut best);
+            self.scan_blocks(sb * l, b, &mut best);
+        }
+        best.1
+    }
+
+    /// Rightmost position of minimum excess in `[x, y]` by scanning.
+    fn scan(&self, x: u64, y: u64, best: &mut (isize, u64)) {
+        let mut e = self.excess(x);
+        for p in x..=y {
+            if e <= best.0 {
+                *best = (e, p);
+            }
+            e += if self.parens.get(p) { 1 } else { -1 };
+        }
+    }
+
+    /// Position of the leftmost minimum in the inclusive range `[i, j]`.
+    /// Complexity: O(log² n) for scanning plus the select and rank queries.
+    pub fn query(&self, i: usize, j: usize) -> usize {
+        assert!(i <= j && j < self.n, "Invalid query range.");
+        let x = self.parens.select_1(i as u64 + 1).unwrap();
+        let y = self.parens.select_1(j as u64 + 1).unwrap();
+        let bs = self.block_size as u64;
+        let (bx, by) = (x / bs, y / bs);
+
+        let mut best = (isize::MAX, x);
+        if by <= bx + 1 {
+            self.scan(x, y, &mut best);
+        } else {
+            self.scan(x, (bx + 1) * bs - 1, &mut best);
+            let b = self.min_block(bx as usize + 1, by as usize - 1) as u64;
+            self.scan(b * bs, (b + 1) * bs - 1, &mut best);
+            self.scan(by * bs, y, &mut best);
+        }
+        // the number of opening parentheses before the minimum is the answer
+        let (e, p) = best;
+        ((e + p as isize) / 2) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(values: &[u32], i: usize, j: usize) -> usize {
+        let mut m = i;
+        for k in i..=j {
+            if values[k] < values[m] {
+                m = k;
+            }
+        }
+        m
+    }
+
+    fn pseudo_random(n: usize, modulo: u32) -> Vec<u32> {
+        let mut x = 12345u32;
+        (0..n)
+            .map(|_| {
+                x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (x >> 16) % modulo
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sparse_table() {
+        let values = pseudo_random(300, 50);
+        let rmq = SparseTableRMQ::new(&values[..]);
+        for i in 0..values.len() {
+            for j in i..values.len() {
+                assert_eq!(rmq.query(i, j), naive(&values, i, j));
+            }
+        }
+    }
+
+    #[test]
+    fn test_succinct() {
+        for &modulo in &[3, 1000] {
+            let values = pseudo_random(700, modulo);
+            let rmq = SuccinctRMQ::new(&values);
+            for i in (0..values.len()).step_by(7) {
+                for j in i..values.len() {
+                    assert_eq!(rmq.query(i, j), naive(&values, i, j));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_succinct_superblocks() {
+        let values = pseudo_random(20000, 5000);
+        let rmq = SuccinctRMQ::new(&values);
+        let sparse = SparseTableRMQ::new(&values[..]);
+        assert!(rmq.superblock_min.len() > 10);
+        let bounds = pseudo_random(400, values.len() as u32);
+        for pair in bounds.chunks(2) {
+            let (i, j) = (pair[0].min(pair[1]) as usize, pair[0].max(pair[1]) as usize);
+            assert_eq!(rmq.query(i, j), sparse.query(i, j));
+            assert_eq!(rmq.query(0, j), sparse.query(0, j));
+        }
+    }
+
+    #[test]
+    fn test_monotone() {
+        let increasing: Vec<u32> = (0..200).collect();
+        let decreasing: Vec<u32> = (0..200).rev().collect();
+        let inc = SuccinctRMQ::new(&increasing);
+        let dec = SuccinctRMQ::new(&decreasing);
+        assert_eq!(inc.query(17, 199), 17);
+        assert_eq!(dec.query(17, 150), 150);
+        assert_eq!(inc.query(5, 5), 5);
+    }
+}
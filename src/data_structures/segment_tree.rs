@@ -0,0 +1,195 @@
+//! Segment tree supporting point updates and range queries for an associative operation
+//! with identity (e.g. sum, minimum or maximum). Both operations take O(log n) time.
+//! In contrast to the Fenwick tree (see `data_structures::bit_tree`), arbitrary ranges
+//! can be queried also for non-invertible operations like minimum and maximum.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::segment_tree::{MaxSegmentTree, SumSegmentTree};
+//!
+//! let mut tree = SumSegmentTree::from_values(&[1, 2, 3, 4, 5]);
+//! assert_eq!(tree.query(1..4), 9);
+//! tree.set(2, 10);
+//! assert_eq!(tree.query(1..4), 16);
+//!
+//! let tree = MaxSegmentTree::from_values(&[3, 7, 1, 5]);
+//! assert_eq!(tree.query(2..4), 5);
+//! ```
+
+use std::marker::PhantomData;
+use std::ops::{Add, Range};
+
+use num_traits::{Bounded, Zero};
+
+/// An associative operation with an identity element.
+pub trait SegmentOp<T> {
+    /// The identity element, i.e. `operation(identity(), t) == t`.
+    fn identity() -> T;
+    fn operation(t1: T, t2: T) -> T;
+}
+
+/// Segment tree over values of type `T` combined with the operation `Op`.
+#[derive(Clone, Debug)]
+pub struct SegmentTree<T: Copy, Op: SegmentOp<T>> {
+    tree: Vec<T>,
+    n: usize,
+    phantom: PhantomData<Op>,
+}
+
+impl<T: Copy, Op: SegmentOp<T>> SegmentTree<T, Op> {
+    /// Create a new segment tree with `n` positions, each set to the identity.
+    pub fn new(n: usize) -> Self {
+        SegmentTree {
+            tree: vec![Op::identity(); 2 * n],
+            n,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Create a segment tree from the given values. Complexity: O(n).
+    pub fn from_values(values: &[T]) -> Self {
+        let n = values.len();
+        let mut tree = vec![Op::identity(); 2 * n];
+        tree[n..].copy_from_slice(values);
+        for i in (1..n).rev() {
+            tree[i] = Op::operation(tree[2 * i], tree[2 * i + 1]);
+        }
+
+        SegmentTree {
+            tree,
+            n,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Number of positions.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    /// Check whether the tree has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Return the value at position `idx`.
+    pub fn get(&self, idx: usize) -> T {
+        self.tree[self.n + idx]
+    }
+
+    /// Set position `idx` to the given value.
+    pub fn set(&mut self, idx: usize, val: T) {
+        assert!(idx < self.n, "Index out of bounds.");
+        let mut i = self.n + idx;
+        self.tree[i] = val;
+        while i > 1 {
+            i /= 2;
+            self.tree[i] = Op::operation(self.tree[2 * i], self.tree[2 * i + 1]);
+        }
+    }
+
+    /// Combine the values in the given range. An empty range yields the identity.
+    pub fn query(&self, range: Range<usize>) -> T {
+        assert!(range.end <= self.n, "Range out of bounds.");
+        // results from the left and right side are kept separate, such that
+        // the operation does not need to be commutative
+        let mut left = Op::identity();
+        let mut right = Op::identity();
+        let (mut l, mut r) = (range.start + self.n, range.end + self.n);
+        while l < r {
+            if l % 2 == 1 {
+                left = Op::operation(left, self.tree[l]);
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                right = Op::operation(self.tree[r], right);
+            }
+            l /= 2;
+            r /= 2;
+        }
+
+        Op::operation(left, right)
+    }
+}
+
+pub struct SumOp;
+impl<T: Copy + Zero + Add<Output = T>> SegmentOp<T> for SumOp {
+    fn identity() -> T {
+        T::zero()
+    }
+
+    fn operation(t1: T, t2: T) -> T {
+        t1 + t2
+    }
+}
+
+pub struct MinOp;
+impl<T: Copy + Ord + Bounded> SegmentOp<T> for MinOp {
+    fn identity() -> T {
+        T::max_value()
+    }
+
+    fn operation(t1: T, t2: T) -> T {
+        t1.min(t2)
+    }
+}
+
+pub struct MaxOp;
+impl<T: Copy + Ord + Bounded> SegmentOp<T> for MaxOp {
+    fn identity() -> T {
+        T::min_value()
+    }
+
+    fn operation(t1: T, t2: T) -> T {
+        t1.max(t2)
+    }
+}
+
+/// Segment tree specialized for range sums
+pub type SumSegmentTree<T> = SegmentTree<T, SumOp>;
+/// Segment tree specialized for range minima
+pub type MinSegmentTree<T> = SegmentTree<T, MinOp>;
+/// Segment tree specialized for range maxima
+pub type MaxSegmentTree<T> = SegmentTree<T, MaxOp>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_queries() {
+        let values: Vec<i64> = vec![5, -3, 8, 0, 2, 9, -7, 4, 1];
+        let sum = SumSegmentTree::from_values(&values);
+        let min = MinSegmentTree::from_values(&values);
+        let max = MaxSegmentTree::from_values(&values);
+        for i in 0..values.len() {
+            for j in i..=values.len() {
+                let range = &values[i..j];
+                assert_eq!(sum.query(i..j), range.iter().sum::<i64>());
+                assert_eq!(
+                    min.query(i..j),
+                    range.iter().cloned().min().unwrap_or(i64::MAX)
+                );
+                assert_eq!(
+                    max.query(i..j),
+                    range.iter().cloned().max().unwrap_or(i64::MIN)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_point_update() {
+        let mut tree = MinSegmentTree::new(6);
+        assert_eq!(tree.query(0..6), u32::MAX);
+        tree.set(4, 7);
+        tree.set(1, 9);
+        assert_eq!(tree.query(0..6), 7);
+        assert_eq!(tree.query(0..4), 9);
+        tree.set(4, 10);
+        assert_eq!(tree.query(0..6), 9);
+        assert_eq!(tree.get(4), 10);
+    }
+}
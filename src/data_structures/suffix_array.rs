@@ -9,7 +9,9 @@
 
 use std;
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::io;
 use std::iter;
 use std::ops::Deref;
 
@@ -20,10 +22,15 @@ use bv::{BitVec, Bits, BitsMut};
 use vec_map::VecMap;
 
 use alphabets::{Alphabet, RankTransform};
+use data_structures::bwt::{le_u64, read_u64, split_bytes, BWTSlice};
+use data_structures::compressed_ints::PackedInts;
 use data_structures::smallints::SmallInts;
+use utils::{IndexableText, SizedInt};
 
 pub type LCPArray = SmallInts<i8, isize>;
-pub type RawSuffixArray = Vec<usize>;
+/// A suffix array with entries of the given offset type, e.g. `RawSuffixArray<u32>` for texts
+/// shorter than 4 GB (see `suffix_array_sized`).
+pub type RawSuffixArray<I = usize> = Vec<I>;
 
 /// A trait exposing general functionality of suffix arrays.
 pub trait SuffixArray {
@@ -31,69 +38,454 @@ pub trait SuffixArray {
     fn len(&self) -> usize;
     fn is_empty(&self) -> bool;
 
-    // /// Sample the suffix array with the given sample rate.
-    // ///
-    // /// # Arguments
-    // ///
-    // /// * `bwt` - the corresponding BWT
-    // /// * `less` - the corresponding less array
-    // /// * `occ` - the corresponding occ table
-    // /// * `sampling_rate` - if sampling rate is k, every k-th entry will be kept
-    // ///
-    // /// # Example
-    // ///
-    // /// ```
-    // /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
-    // /// use bio::data_structures::bwt::{bwt, less, Occ};
-    // /// use bio::alphabets::dna;
-    // ///
-    // /// let text = b"ACGCGAT$";
-    // /// let alphabet = dna::n_alphabet();
-    // /// let sa = suffix_array(text);
-    // /// let bwt = bwt(text, &sa);
-    // /// let less = less(&bwt, &alphabet);
-    // /// let occ = Occ::new(&bwt, 3, &alphabet);
-    // /// let sampled = sa.sample(&bwt, &less, &occ, 1);
-    // ///
-    // /// for i in 0..sa.len() {
-    // ///    assert_eq!(sa.get(i), sampled.get(i));
-    // /// }
-    // /// ```
-    // fn sample<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc>
-    //     (&self, bwt: DBWT, less: DLess, occ: DOcc, sampling_rate: usize) ->
-    //     SampledSuffixArray<DBWT, DLess, DOcc> {
-    //
-    //     let mut sample = Vec::with_capacity((self.len() as f32 / sampling_rate as f32).ceil() as usize);
-    //     for i in 0..self.len() {
-    //         if (i % sampling_rate) == 0 {
-    //             sample.push(self.get(i).unwrap());
-    //         }
-    //     }
-    //
-    //     SampledSuffixArray {
-    //         bwt: bwt,
-    //         less: less,
-    //         occ: occ,
-    //         sample: sample,
-    //         s: sampling_rate,
-    //     }
-    // }
+    /// Sample the suffix array with the given sampling rate, for locating occurrences with
+    /// `FMIndexable::locate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bwt` - the corresponding BWT
+    /// * `sampling_rate` - if sampling rate is k, every k-th entry will be kept
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// use bio::data_structures::bwt::bwt;
+    ///
+    /// let text = b"ACGCGAT$";
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let sampled = sa.sample(&bwt, 4);
+    ///
+    /// assert_eq!(sampled.len(), sa.len());
+    /// assert_eq!(sampled.sampling_rate(), 4);
+    /// ```
+    fn sample(&self, bwt: &BWTSlice, sampling_rate: usize) -> SampledSuffixArray {
+        sample(self, bwt, sampling_rate)
+    }
+
+    /// Sample the suffix array as `sample`, storing the kept entries with the given offset
+    /// type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// use bio::data_structures::bwt::bwt;
+    ///
+    /// let text = b"ACGCGAT$";
+    /// let sa = suffix_array(text);
+    /// let sampled = sa.sample_sized::<u32>(&bwt(text, &sa), 4);
+    /// assert_eq!(sampled.get_sampled(4), sa.get(4));
+    /// ```
+    fn sample_sized<I: SizedInt>(
+        &self,
+        bwt: &BWTSlice,
+        sampling_rate: usize,
+    ) -> SampledSuffixArray<I>
+    where
+        Self: Sized,
+    {
+        sample(self, bwt, sampling_rate)
+    }
+
+    /// Sample the inverse suffix array with the given sampling rate, for extracting text with
+    /// `FMIndexable::extract`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bwt` - the corresponding BWT
+    /// * `sampling_rate` - if sampling rate is k, the rows of every k-th text position will
+    ///   be kept
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// use bio::data_structures::bwt::bwt;
+    ///
+    /// let text = b"ACGCGAT$";
+    /// let sa = suffix_array(text);
+    /// let isa = sa.sample_inverse(&bwt(text, &sa), 4);
+    /// assert_eq!(sa.get(isa.get_sampled(4).unwrap()), Some(4));
+    /// assert_eq!(isa.get_sampled(5), None);
+    /// ```
+    fn sample_inverse(&self, bwt: &BWTSlice, sampling_rate: usize) -> SampledInverseSuffixArray {
+        assert!(sampling_rate > 0, "Expecting positive sampling rate.");
+        assert_eq!(bwt.len(), self.len(), "Expecting BWT of the same length.");
+        let n = self.len();
+        // the sentinel positions, i.e. the positions before the suffixes of rows with the
+        // sentinel in the BWT
+        let mut sentinels: HashMap<usize, usize> = bwt
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c == b'$')
+            .map(|(r, _)| {
+                let pos = self.get(r).unwrap();
+                ((if pos == 0 { n } else { pos }) - 1, 0)
+            })
+            .filter(|&(pos, _)| pos % sampling_rate != 0)
+            .collect();
+        let width = PackedInts::from_values(&[n as u64]).width();
+        let mut sample = PackedInts::with_len(width, n.div_ceil(sampling_rate));
+        for r in 0..n {
+            let pos = self.get(r).unwrap();
+            if pos % sampling_rate == 0 {
+                sample.set(pos / sampling_rate, r as u64);
+            } else if let Some(row) = sentinels.get_mut(&pos) {
+                *row = r;
+            }
+        }
+        SampledInverseSuffixArray {
+            sample,
+            sentinels,
+            s: sampling_rate,
+            n,
+        }
+    }
+}
+
+fn sample<I: SizedInt, SA: SuffixArray + ?Sized>(
+    sa: &SA,
+    bwt: &BWTSlice,
+    sampling_rate: usize,
+) -> SampledSuffixArray<I> {
+    assert!(sampling_rate > 0, "Expecting positive sampling rate.");
+    assert_eq!(bwt.len(), sa.len(), "Expecting BWT of the same length.");
+    assert!(
+        I::fits(sa.len()),
+        "Expecting suffix array length within the offset type."
+    );
+    let sample = (0..sa.len())
+        .step_by(sampling_rate)
+        .map(|r| I::from_usize(sa.get(r).unwrap()))
+        .collect();
+    // LF-mapping does not work at sentinels if the text contains several, hence the
+    // positions of these rows are kept as well
+    let sentinels = bwt
+        .iter()
+        .enumerate()
+        .filter(|&(r, &c)| c == b'$' && r % sampling_rate != 0)
+        .map(|(r, _)| (r, sa.get(r).unwrap()))
+        .collect();
+    SampledSuffixArray {
+        sample,
+        sentinels,
+        s: sampling_rate,
+        n: sa.len(),
+    }
+}
+
+/// A sampled suffix array, keeping every k-th entry (and the entries of rows with the sentinel
+/// `$` in the BWT). The other entries are reconstructed with the corresponding FM-Index. The
+/// kept entries are stored with the offset type `I`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledSuffixArray<I = usize> {
+    sample: Vec<I>,
+    sentinels: HashMap<usize, usize>,
+    s: usize, // Rate of sampling
+    n: usize,
+}
+
+impl<I: SizedInt> SampledSuffixArray<I> {
+    /// Sampled suffix array of length n from the entries of every s-th row and the entries of
+    /// the other rows with the sentinel in the BWT.
+    pub(crate) fn from_parts(
+        sample: Vec<I>,
+        sentinels: HashMap<usize, usize>,
+        s: usize,
+        n: usize,
+    ) -> Self {
+        SampledSuffixArray {
+            sample,
+            sentinels,
+            s,
+            n,
+        }
+    }
+
+    pub fn sampling_rate(&self) -> usize {
+        self.s
+    }
+
+    /// Number of entries of the complete suffix array.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Get the entry of the given row if it was kept.
+    pub fn get_sampled(&self, index: usize) -> Option<usize> {
+        if index % self.s == 0 {
+            self.sample[..].get(index / self.s).map(|&p| p.to_usize())
+        } else {
+            self.sentinels.get(&index).cloned()
+        }
+    }
+
+    /// Copy the sampled suffix array with the kept entries bit-packed to the width needed for
+    /// the text length, e.g. 29 instead of 32 or 64 bits for a text of 500 million bases.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::bwt::bwt;
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    ///
+    /// let text = b"ACGCGAT$";
+    /// let sa = suffix_array(text);
+    /// let packed = sa.sample(&bwt(text, &sa), 2).pack();
+    /// assert_eq!(packed.get_sampled(4), sa.get(4));
+    /// assert_eq!(packed.get_sampled(5), None);
+    /// ```
+    pub fn pack(&self) -> PackedSampledSuffixArray {
+        let width = PackedInts::from_values(&[self.n as u64]).width();
+        let mut sample = PackedInts::with_len(width, self.sample.len());
+        for (i, &p) in self.sample.iter().enumerate() {
+            sample.set(i, p.to_usize() as u64);
+        }
+        PackedSampledSuffixArray {
+            sample,
+            sentinels: self.sentinels.clone(),
+            s: self.s,
+            n: self.n,
+        }
+    }
+
+    /// Write the sampled suffix array (little endian) such that it can be accessed without
+    /// copying with `MappedSuffixArray`, e.g. from a memory mapped file. Entries are written
+    /// with 32 bits if the suffix array is shorter than 2^32, otherwise with 64 bits.
+    pub fn write_mapped<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let width: u8 = if self.n <= u32::MAX as usize { 4 } else { 8 };
+        writer.write_all(&(self.s as u64).to_le_bytes())?;
+        writer.write_all(&(self.n as u64).to_le_bytes())?;
+        writer.write_all(&[width])?;
+        writer.write_all(&(self.sample.len() as u64).to_le_bytes())?;
+        for &p in &self.sample {
+            writer.write_all(&(p.to_usize() as u64).to_le_bytes()[..width as usize])?;
+        }
+        let mut sentinels: Vec<_> = self.sentinels.iter().collect();
+        sentinels.sort_unstable();
+        writer.write_all(&(sentinels.len() as u64).to_le_bytes())?;
+        for (&r, &p) in sentinels {
+            writer.write_all(&(r as u64).to_le_bytes())?;
+            writer.write_all(&(p as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// A sampled suffix array with bit-packed entries, created with `SampledSuffixArray::pack`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackedSampledSuffixArray {
+    sample: PackedInts,
+    sentinels: HashMap<usize, usize>,
+    s: usize, // Rate of sampling
+    n: usize,
+}
+
+impl PackedSampledSuffixArray {
+    pub fn sampling_rate(&self) -> usize {
+        self.s
+    }
+
+    /// Number of entries of the complete suffix array.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Get the entry of the given row if it was kept.
+    pub fn get_sampled(&self, index: usize) -> Option<usize> {
+        if index % self.s == 0 {
+            self.sample.get(index / self.s).map(|p| p as usize)
+        } else {
+            self.sentinels.get(&index).cloned()
+        }
+    }
 }
 
-// /// A sampled suffix array.
-// pub struct SampledSuffixArray<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc> {
-//     bwt: DBWT,
-//     less: DLess,
-//     occ: DOcc,
-//     sample: Vec<usize>,
-//     s: usize, // Rate of sampling
-// }
+/// A sampled inverse suffix array, keeping the rows of every k-th text position (and of the
+/// positions of sentinels `$`). The other rows are reached with the corresponding FM-Index.
+/// The kept rows are bit-packed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledInverseSuffixArray {
+    sample: PackedInts,
+    sentinels: HashMap<usize, usize>,
+    s: usize, // Rate of sampling
+    n: usize,
+}
+
+impl SampledInverseSuffixArray {
+    pub fn sampling_rate(&self) -> usize {
+        self.s
+    }
 
-impl SuffixArray for RawSuffixArray {
+    /// Length of the text.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Get the row of the suffix starting at the given text position if it was kept.
+    pub fn get_sampled(&self, pos: usize) -> Option<usize> {
+        if pos % self.s == 0 {
+            self.sample.get(pos / self.s).map(|r| r as usize)
+        } else {
+            self.sentinels.get(&pos).cloned()
+        }
+    }
+}
+
+/// Suffix arrays keeping only some of their entries, for locating occurrences with
+/// `FMIndexable::locate`.
+pub trait SuffixArraySample {
+    /// Get the entry of the given row if it was kept.
+    fn get_sampled(&self, index: usize) -> Option<usize>;
+}
+
+impl<I: SizedInt> SuffixArraySample for SampledSuffixArray<I> {
+    fn get_sampled(&self, index: usize) -> Option<usize> {
+        SampledSuffixArray::get_sampled(self, index)
+    }
+}
+
+impl SuffixArraySample for PackedSampledSuffixArray {
+    fn get_sampled(&self, index: usize) -> Option<usize> {
+        PackedSampledSuffixArray::get_sampled(self, index)
+    }
+}
+
+/// A sampled suffix array written with `SampledSuffixArray::write_mapped`, accessed in place in
+/// a byte buffer (e.g. a memory mapped file) instead of being read into memory.
+///
+/// # Example
+///
+/// ```
+/// use bio::data_structures::bwt::bwt;
+/// use bio::data_structures::suffix_array::{suffix_array, MappedSuffixArray, SuffixArray};
+///
+/// let text = b"ACGCGAT$";
+/// let sa = suffix_array(text);
+/// let sampled = sa.sample(&bwt(text, &sa), 4);
+/// let mut data = Vec::new();
+/// sampled.write_mapped(&mut data).unwrap();
+///
+/// let mapped = MappedSuffixArray::new(&data).unwrap();
+/// assert_eq!(mapped.get_sampled(4), sa.get(4));
+/// assert_eq!(mapped.get_sampled(5), None);
+/// ```
+pub struct MappedSuffixArray<'a> {
+    s: usize,
+    n: usize,
+    width: usize,
+    sample: &'a [u8],
+    /// Pairs of rows with the sentinel in the BWT and their entries, sorted by row.
+    sentinels: &'a [u8],
+}
+
+impl<'a> MappedSuffixArray<'a> {
+    /// Access the sampled suffix array written with `SampledSuffixArray::write_mapped` to the
+    /// given buffer.
+    pub fn new(mut data: &'a [u8]) -> io::Result<Self> {
+        let sa = Self::parse(&mut data)?;
+        if !data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Trailing data after suffix array.",
+            ));
+        }
+        Ok(sa)
+    }
+
+    /// Access the sampled suffix array at the start of the buffer, and advance the buffer
+    /// behind it.
+    pub(crate) fn parse(data: &mut &'a [u8]) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let s = read_u64(data)? as usize;
+        let n = read_u64(data)? as usize;
+        let width = split_bytes(data, 1)?[0] as usize;
+        if s == 0 || (width != 4 && width != 8) {
+            return Err(invalid("Invalid sampling rate or width of suffix array."));
+        }
+        let len = read_u64(data)? as usize;
+        if len != n.div_ceil(s) {
+            return Err(invalid("Invalid number of suffix array entries."));
+        }
+        let sample = split_bytes(data, len * width)?;
+        let sentinel_count = read_u64(data)? as usize;
+        let sentinels = split_bytes(data, sentinel_count * 16)?;
+        Ok(MappedSuffixArray {
+            s,
+            n,
+            width,
+            sample,
+            sentinels,
+        })
+    }
+
+    pub fn sampling_rate(&self) -> usize {
+        self.s
+    }
+
+    /// Number of entries of the complete suffix array.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Get the entry of the given row if it was kept.
+    pub fn get_sampled(&self, index: usize) -> Option<usize> {
+        if index % self.s == 0 {
+            let i = index / self.s;
+            if i * self.width >= self.sample.len() {
+                return None;
+            }
+            let mut buf = [0; 8];
+            buf[..self.width].copy_from_slice(&self.sample[i * self.width..(i + 1) * self.width]);
+            Some(u64::from_le_bytes(buf) as usize)
+        } else {
+            let row = |i| le_u64(self.sentinels, 2 * i) as usize;
+            // binary search for the row
+            let (mut lower, mut upper) = (0, self.sentinels.len() / 16);
+            while lower < upper {
+                let mid = lower + (upper - lower) / 2;
+                match row(mid).cmp(&index) {
+                    cmp::Ordering::Less => lower = mid + 1,
+                    cmp::Ordering::Greater => upper = mid,
+                    cmp::Ordering::Equal => {
+                        return Some(le_u64(self.sentinels, 2 * mid + 1) as usize)
+                    }
+                }
+            }
+            None
+        }
+    }
+}
+
+impl<'a> SuffixArraySample for MappedSuffixArray<'a> {
+    fn get_sampled(&self, index: usize) -> Option<usize> {
+        MappedSuffixArray::get_sampled(self, index)
+    }
+}
+
+impl<I: SizedInt> SuffixArray for RawSuffixArray<I> {
     fn get(&self, index: usize) -> Option<usize> {
         // Explicitly written out because Vec::get(index) generates a recursion warning
         if index < self.len() {
-            Some(self[index])
+            Some(self[index].to_usize())
         } else {
             None
         }
@@ -106,59 +498,8 @@ impl SuffixArray for RawSuffixArray {
     fn is_empty(&self) -> bool {
         Vec::is_empty(self)
     }
-
-    // fn sample<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc>
-    //     (&self, bwt: DBWT, less: DLess, occ: DOcc, sampling_rate: usize) ->
-    //     SampledSuffixArray<DBWT, DLess, DOcc> {
-    //     // Provide a specialized, faster implementation using iterators.
-    //
-    //     let sample = self.iter().cloned().step(sampling_rate).collect();
-    //
-    //     SampledSuffixArray {
-    //         bwt: bwt,
-    //         less: less,
-    //         occ: occ,
-    //         sample: sample,
-    //         s: sampling_rate,
-    //     }
-    // }
 }
 
-// impl<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc> SuffixArray for SampledSuffixArray<DBWT, DLess, DOcc> {
-//     fn get(&self, index: usize) -> Option<usize> {
-//         if index < self.len() {
-//             let mut pos = index;
-//             let mut offset = 0;
-//             loop {
-//                 if pos % self.s == 0 {
-//                     return Some(self.sample[pos / self.s] + offset);
-//                 }
-//
-//                 let c = self.bwt[pos];
-//                 pos = self.less[c as usize] + self.occ.get(&self.bwt, pos - 1, c);
-//                 offset += 1;
-//             }
-//         } else {
-//             None
-//         }
-//     }
-//
-//     fn len(&self) -> usize {
-//         self.bwt.len()
-//     }
-
-//     fn is_empty(&self) -> bool {
-//         self.bwt.is_empty()
-//     }
-// }
-//
-//
-// impl<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc> SampledSuffixArray<DBWT, DLess, DOcc> {
-//     pub fn sampling_rate(&self) -> usize {
-//         self.s
-//     }
-// }
-
 /// Construct suffix array for given text of length n.
 /// Complexity: O(n).
 /// This is an implementation of the induced sorting as presented by
@@ -189,7 +530,8 @@ impl SuffixArray for RawSuffixArray {
 ///
 /// # Arguments
 ///
-/// * `text` - the text, ended by sentinel symbol (being lexicographically smallest). The text may
+/// * `text` - the text (e.g. a byte slice or a `PackedDnaSeq`, see `utils::IndexableText`), ended
+///   by sentinel symbol (being lexicographically smallest). The text may
 ///   also contain multiple sentinel symbols, used to concatenate multiple sequences without mixing
 ///   their suffixes together.
 ///
@@ -204,23 +546,40 @@ impl SuffixArray for RawSuffixArray {
 ///     2, 16, 0, 19, 4, 13, 10, 3, 12, 9
 /// ]);
 /// ```
-pub fn suffix_array(text: &[u8]) -> RawSuffixArray {
+pub fn suffix_array<T: IndexableText + ?Sized>(text: &T) -> RawSuffixArray {
+    suffix_array_sized(text)
+}
+
+/// Construct the suffix array of the given text as `suffix_array`, with entries (and
+/// intermediate arrays during construction) of the given offset type, e.g. `u32` for texts
+/// shorter than 4 GB, which needs half the memory of `usize` entries on 64 bit machines.
+///
+/// # Example
+///
+/// ```
+/// use bio::data_structures::suffix_array::{suffix_array, suffix_array_sized};
+/// let text = b"GCCTTAACATTATTACGCCTA$";
+/// let pos = suffix_array_sized::<u32, _>(text);
+/// assert!(pos.iter().map(|&p| p as usize).eq(suffix_array(text)));
+/// ```
+pub fn suffix_array_sized<I: SizedInt, T: IndexableText + ?Sized>(text: &T) -> RawSuffixArray<I> {
     let n = text.len();
-    let alphabet = Alphabet::new(text);
+    assert!(I::fits(n), "Expecting text length within the offset type.");
+    let alphabet = Alphabet::new(text.symbols(0..n));
     let sentinel_count = sentinel_count(text);
-    let mut sais = SAIS::new(n);
+    let mut sais = SAIS::<I>::new(n);
 
     match alphabet.len() + sentinel_count {
         a if a <= std::u8::MAX as usize => {
-            sais.construct(&transform_text::<u8>(text, &alphabet, sentinel_count))
+            sais.construct(&transform_text::<u8, T>(text, &alphabet, sentinel_count))
         }
         a if a <= std::u16::MAX as usize => {
-            sais.construct(&transform_text::<u16>(text, &alphabet, sentinel_count))
+            sais.construct(&transform_text::<u16, T>(text, &alphabet, sentinel_count))
         }
         a if a <= std::u32::MAX as usize => {
-            sais.construct(&transform_text::<u32>(text, &alphabet, sentinel_count))
+            sais.construct(&transform_text::<u32, T>(text, &alphabet, sentinel_count))
         }
-        _ => sais.construct(&transform_text::<u64>(text, &alphabet, sentinel_count)),
+        _ => sais.construct(&transform_text::<u64, T>(text, &alphabet, sentinel_count)),
     }
 
     sais.pos
@@ -259,7 +618,10 @@ pub fn suffix_array(text: &[u8]) -> RawSuffixArray {
 ///     ]
 /// )
 /// ```
-pub fn lcp<SA: Deref<Target = RawSuffixArray>>(text: &[u8], pos: SA) -> LCPArray {
+pub fn lcp<T: IndexableText + ?Sized, SA: Deref<Target = RawSuffixArray>>(
+    text: &T,
+    pos: SA,
+) -> LCPArray {
     assert_eq!(text.len(), pos.len());
     let n = text.len();
 
@@ -275,7 +637,7 @@ pub fn lcp<SA: Deref<Target = RawSuffixArray>>(text: &[u8], pos: SA) -> LCPArray
         // since the sentinel has rank 0 and is excluded above,
         // we will never have a negative index below
         let pred = pos[r - 1];
-        while pred + l < n && p + l < n && text[p + l] == text[pred + l] {
+        while pred + l < n && p + l < n && text.symbol(p + l) == text.symbol(pred + l) {
             l += 1;
         }
         lcp.set(r, l as isize);
@@ -332,26 +694,26 @@ pub fn shortest_unique_substrings<SA: SuffixArray>(pos: &SA, lcp: &LCPArray) ->
 }
 
 /// Return last character of the text (expected to be the sentinel).
-fn sentinel(text: &[u8]) -> u8 {
-    text[text.len() - 1]
+fn sentinel<T: IndexableText + ?Sized>(text: &T) -> u8 {
+    text.symbol(text.len() - 1)
 }
 
 /// Count the sentinels occurring in the text given that the last character is the sentinel.
-fn sentinel_count(text: &[u8]) -> usize {
+fn sentinel_count<T: IndexableText + ?Sized>(text: &T) -> usize {
     let sentinel = sentinel(text);
     assert!(
-        text.iter().all(|&a| a >= sentinel),
+        text.symbols(0..text.len()).all(|a| a >= sentinel),
         "Expecting extra sentinel symbol being lexicographically smallest at the end of the \
          text."
     );
 
-    text.iter()
-        .fold(0, |count, &a| count + (a == sentinel) as usize)
+    text.symbols(0..text.len())
+        .fold(0, |count, a| count + (a == sentinel) as usize)
 }
 
 /// Transform the given text into integers for usage in `SAIS`.
-fn transform_text<T: Integer + Unsigned + NumCast + Copy + Debug>(
-    text: &[u8],
+fn transform_text<T: Integer + Unsigned + NumCast + Copy + Debug, S: IndexableText + ?Sized>(
+    text: &S,
     alphabet: &Alphabet,
     sentinel_count: usize,
 ) -> Vec<T> {
@@ -361,7 +723,7 @@ fn transform_text<T: Integer + Unsigned + NumCast + Copy + Debug>(
 
     let mut transformed: Vec<T> = Vec::with_capacity(text.len());
     let mut s = sentinel_count;
-    for &a in text.iter() {
+    for a in text.symbols(0..text.len()) {
         if a == sentinel {
             s -= 1;
             transformed.push(cast(s).unwrap());
@@ -375,22 +737,22 @@ fn transform_text<T: Integer + Unsigned + NumCast + Copy + Debug>(
 }
 
 /// SAIS implementation (see function `suffix_array` for description).
-struct SAIS {
-    pos: Vec<usize>,
-    lms_pos: Vec<usize>,
-    reduced_text_pos: Vec<usize>,
+struct SAIS<I> {
+    pos: Vec<I>,
+    lms_pos: Vec<I>,
+    reduced_text_pos: Vec<I>,
     bucket_sizes: VecMap<usize>,
     bucket_start: Vec<usize>,
     bucket_end: Vec<usize>,
 }
 
-impl SAIS {
+impl<I: SizedInt> SAIS<I> {
     /// Create a new instance.
     fn new(n: usize) -> Self {
         SAIS {
             pos: Vec::with_capacity(n),
             lms_pos: Vec::with_capacity(n),
-            reduced_text_pos: vec![0; n],
+            reduced_text_pos: vec![I::default(); n],
             bucket_sizes: VecMap::new(),
             bucket_start: Vec::with_capacity(n),
             bucket_end: Vec::with_capacity(n),
@@ -467,15 +829,16 @@ impl SAIS {
             // sort LMS suffixes by recursively building SA on reduced text
             let mut reduced_text: Vec<S> = vec![cast(0).unwrap(); lms_substring_count];
             let mut label = 0;
-            reduced_text[self.reduced_text_pos[self.pos[0]]] = cast(label).unwrap();
+            reduced_text[self.reduced_text_pos[self.pos[0].to_usize()].to_usize()] =
+                cast(label).unwrap();
             let mut prev = None;
-            for &p in &self.pos {
+            for p in self.pos.iter().map(|&p| p.to_usize()) {
                 if pos_types.is_lms_pos(p) {
                     // choose same label if substrings are equal
                     if prev.is_some() && !self.lms_substring_eq(text, pos_types, prev.unwrap(), p) {
                         label += 1;
                     }
-                    reduced_text[self.reduced_text_pos[p]] = cast(label).unwrap();
+                    reduced_text[self.reduced_text_pos[p].to_usize()] = cast(label).unwrap();
                     prev = Some(p);
                 }
             }
@@ -490,14 +853,14 @@ impl SAIS {
                 // obtain sorted lms suffixes
                 self.lms_pos.clear();
                 for &p in &self.pos {
-                    self.lms_pos.push(lms_pos[p]);
+                    self.lms_pos.push(lms_pos[p.to_usize()]);
                 }
             } else {
                 // otherwise, lms_pos is updated with the sorted suffixes from pos
                 // obtain sorted lms suffixes
                 self.lms_pos.clear();
                 for &p in &self.pos {
-                    if pos_types.is_lms_pos(p) {
+                    if pos_types.is_lms_pos(p.to_usize()) {
                         self.lms_pos.push(p);
                     }
                 }
@@ -525,8 +888,8 @@ impl SAIS {
         let mut i = 0;
         for r in 0..n {
             if pos_types.is_lms_pos(r) {
-                self.lms_pos.push(r);
-                self.reduced_text_pos[r] = i;
+                self.lms_pos.push(I::from_usize(r));
+                self.reduced_text_pos[r] = I::from_usize(i);
                 i += 1;
             }
         }
@@ -561,12 +924,12 @@ impl SAIS {
 
         // init all positions as unknown (n-1 is max position)
         for _ in text.iter() {
-            self.pos.push(n);
+            self.pos.push(I::from_usize(n));
         }
 
         // insert LMS positions to the end of their buckets
         for &p in self.lms_pos.iter().rev() {
-            let c: usize = cast(text[p]).unwrap();
+            let c: usize = cast(text[p.to_usize()]).unwrap();
             self.pos[self.bucket_end[c]] = p;
             // subtract without overflow: last -1 will cause overflow, but it does not matter
             self.bucket_end[c] = self.bucket_end[c].wrapping_sub(1);
@@ -577,7 +940,7 @@ impl SAIS {
 
         // insert L-positions into buckets
         for r in 0..n {
-            let p = self.pos[r];
+            let p = self.pos[r].to_usize();
             // ignore undefined positions and the zero since it has no predecessor
             if p == n || p == 0 {
                 continue;
@@ -585,21 +948,21 @@ impl SAIS {
             let pred = p - 1;
             if pos_types.is_l_pos(pred) {
                 let c: usize = cast(text[pred]).unwrap();
-                self.pos[self.bucket_start[c]] = pred;
+                self.pos[self.bucket_start[c]] = I::from_usize(pred);
                 self.bucket_start[c] += 1;
             }
         }
 
         // insert S-positions into buckets
         for r in (0..n).rev() {
-            let p = self.pos[r];
+            let p = self.pos[r].to_usize();
             if p == 0 {
                 continue;
             }
             let pred = p - 1;
             if pos_types.is_s_pos(pred) {
                 let c: usize = cast(text[pred]).unwrap();
-                self.pos[self.bucket_end[c]] = pred;
+                self.pos[self.bucket_end[c]] = I::from_usize(pred);
                 // subtract without overflow: last -1 will cause overflow, but it won't be used
                 self.bucket_end[c] = self.bucket_end[c].wrapping_sub(1);
             }
@@ -666,6 +1029,7 @@ mod tests {
     use super::{transform_text, PosTypes, SAIS};
     use alphabets::Alphabet;
     use bv::{BitVec, BitsPush};
+    use data_structures::bwt::bwt;
     //use data_structures::bwt::{bwt, less, Occ};
     use std::str;
 
@@ -693,7 +1057,7 @@ mod tests {
         let text: Vec<u8> = transform_text(orig_text, &alphabet, 1);
         let n = text.len();
 
-        let mut sais = SAIS::new(n);
+        let mut sais = SAIS::<usize>::new(n);
         sais.init_bucket_start(&text);
         assert_eq!(sais.bucket_start, vec![0, 1, 7, 13, 15]);
         sais.init_bucket_end(&text);
@@ -707,7 +1071,7 @@ mod tests {
         let text: Vec<u8> = transform_text(orig_text, &alphabet, 1);
         let n = text.len();
 
-        let mut sais = SAIS::new(n);
+        let mut sais = SAIS::<usize>::new(n);
         let pos_types = PosTypes::new(&text);
         sais.lms_pos = vec![21, 5, 14, 8, 11, 17, 1];
         sais.calc_pos(&text, &pos_types);
@@ -724,7 +1088,7 @@ mod tests {
         let text: Vec<u8> = transform_text(orig_text, &alphabet, 1);
         let n = text.len();
 
-        let mut sais = SAIS::new(n);
+        let mut sais = SAIS::<usize>::new(n);
         let pos_types = PosTypes::new(&text);
         sais.calc_lms_pos(&text, &pos_types);
     }
@@ -749,6 +1113,26 @@ mod tests {
         suffix_array(reads);
     }
 
+    #[test]
+    fn test_suffix_array_sized() {
+        let text = b"GTAGGCCTAATTATAATCAGCGGACATTTCGTATTGCTCGG$AATCAGCGGACATTTCGTATTGCTCGGGCTGCC$";
+        let sa = suffix_array(text);
+        let sa32 = suffix_array_sized::<u32, _>(text);
+        let sa64 = suffix_array_sized::<u64, _>(&text[..]);
+        for (r, &pos) in sa.iter().enumerate() {
+            assert_eq!(sa32.get(r), Some(pos));
+            assert_eq!(sa64.get(r), Some(pos));
+        }
+
+        let bwt = ::data_structures::bwt::bwt(text, &sa32);
+        assert_eq!(bwt, ::data_structures::bwt::bwt(text, &sa));
+        let sampled = sa.sample(&bwt, 3);
+        let sampled32 = sa32.sample_sized::<u32>(&bwt, 3);
+        for r in 0..sa.len() {
+            assert_eq!(sampled32.get_sampled(r), sampled.get_sampled(r));
+        }
+    }
+
     fn str_from_pos(sa: &Vec<usize>, text: &[u8], index: usize) -> String {
         String::from(
             str::from_utf8(&text[sa[index]..])
@@ -800,6 +1184,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_packed_samples() {
+        let text = b"GTAGGCCTAATTATAATCAGCGGACATTTCGTATTGCTCGGG$CCTAATTATAATCAGCGGA$";
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let sampled = sa.sample(&bwt, 3);
+        let packed = sampled.pack();
+        assert_eq!(packed.len(), sa.len());
+        assert_eq!(packed.sampling_rate(), 3);
+        for row in 0..sa.len() {
+            assert_eq!(packed.get_sampled(row), sampled.get_sampled(row));
+        }
+
+        let isa = sa.sample_inverse(&bwt, 4);
+        for (row, &pos) in sa.iter().enumerate() {
+            if pos % 4 == 0 || text[pos] == b'$' {
+                assert_eq!(isa.get_sampled(pos), Some(row));
+            }
+        }
+    }
+
     // #[test]
     // fn test_sampled_matches() {
     //     let test_cases =             [(&b"A$C$G$T$"[..], "simple"),
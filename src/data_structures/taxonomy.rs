@@ -0,0 +1,204 @@
+//! A taxonomy tree given by parent links between taxon ids, with lowest common ancestor
+//! (LCA) queries, ranks, names and lineages. The NCBI taxonomy can be read with
+//! `io::taxonomy`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::taxonomy::Taxonomy;
+//!
+//! let mut taxonomy = Taxonomy::new(1);
+//! taxonomy.add_node(2, 1);
+//! taxonomy.add_node(3, 2);
+//! taxonomy.add_node(4, 2);
+//! taxonomy.add_node(5, 1);
+//! assert_eq!(taxonomy.lca(3, 4), 2);
+//! assert_eq!(taxonomy.lca(3, 5), 1);
+//! assert_eq!(taxonomy.ancestors(3).collect::<Vec<_>>(), vec![3, 2, 1]);
+//!
+//! taxonomy.set_rank(2, "genus");
+//! taxonomy.set_name(2, "Escherichia");
+//! assert_eq!(taxonomy.ancestor_at_rank(3, "genus"), Some(2));
+//! assert_eq!(taxonomy.name(2), Some("Escherichia"));
+//! assert_eq!(taxonomy.lineage(3), vec![1, 2, 3]);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+/// Type of taxon ids.
+pub type TaxId = u32;
+
+/// A rooted taxonomy tree.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Taxonomy {
+    root: TaxId,
+    parents: HashMap<TaxId, TaxId>,
+    ranks: HashMap<TaxId, String>,
+    names: HashMap<TaxId, String>,
+}
+
+impl Taxonomy {
+    /// Create a new taxonomy consisting only of the given root.
+    pub fn new(root: TaxId) -> Self {
+        let mut parents = HashMap::new();
+        parents.insert(root, root);
+        Taxonomy {
+            root,
+            parents,
+            ranks: HashMap::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// The root taxon.
+    pub fn root(&self) -> TaxId {
+        self.root
+    }
+
+    /// Number of taxa.
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Check whether the taxonomy only consists of the root.
+    pub fn is_empty(&self) -> bool {
+        self.parents.len() <= 1
+    }
+
+    /// Add (or move) taxon `taxid` below `parent`. The parent does not need to be known yet,
+    /// which allows adding the nodes of a taxonomy in arbitrary order.
+    pub fn add_node(&mut self, taxid: TaxId, parent: TaxId) {
+        assert!(taxid != self.root, "The root cannot be added as a child.");
+        self.parents.insert(taxid, parent);
+    }
+
+    /// Check whether the taxon is known.
+    pub fn contains(&self, taxid: TaxId) -> bool {
+        self.parents.contains_key(&taxid)
+    }
+
+    /// Return the parent of the given taxon, or `None` for the root and unknown taxa.
+    pub fn parent(&self, taxid: TaxId) -> Option<TaxId> {
+        if taxid == self.root {
+            None
+        } else {
+            self.parents.get(&taxid).cloned()
+        }
+    }
+
+    /// Set the rank (e.g. "species" or "genus") of the given taxon.
+    pub fn set_rank(&mut self, taxid: TaxId, rank: &str) {
+        self.ranks.insert(taxid, rank.to_owned());
+    }
+
+    /// Return the rank of the given taxon, if known.
+    pub fn rank(&self, taxid: TaxId) -> Option<&str> {
+        self.ranks.get(&taxid).map(|rank| rank.as_str())
+    }
+
+    /// Set the (scientific) name of the given taxon.
+    pub fn set_name(&mut self, taxid: TaxId, name: &str) {
+        self.names.insert(taxid, name.to_owned());
+    }
+
+    /// Return the name of the given taxon, if known.
+    pub fn name(&self, taxid: TaxId) -> Option<&str> {
+        self.names.get(&taxid).map(|name| name.as_str())
+    }
+
+    /// Iterate over all taxa except the root, paired with their parents, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (TaxId, TaxId)> + '_ {
+        let root = self.root;
+        self.parents
+            .iter()
+            .filter(move |&(&taxid, _)| taxid != root)
+            .map(|(&taxid, &parent)| (taxid, parent))
+    }
+
+    /// Iterate over the given taxon and its ancestors, up to the root.
+    /// The iteration stops early at taxa without known parent.
+    pub fn ancestors(&self, taxid: TaxId) -> Ancestors<'_> {
+        Ancestors {
+            taxonomy: self,
+            next: Some(taxid),
+        }
+    }
+
+    /// Return the path from the root (or the topmost known ancestor) to the given taxon.
+    pub fn lineage(&self, taxid: TaxId) -> Vec<TaxId> {
+        let mut lineage: Vec<TaxId> = self.ancestors(taxid).collect();
+        lineage.reverse();
+        lineage
+    }
+
+    /// Return the closest ancestor (including the taxon itself) with the given rank.
+    pub fn ancestor_at_rank(&self, taxid: TaxId, rank: &str) -> Option<TaxId> {
+        self.ancestors(taxid).find(|&t| self.rank(t) == Some(rank))
+    }
+
+    /// Check whether `ancestor` is `taxid` or one of its ancestors.
+    pub fn is_ancestor(&self, ancestor: TaxId, taxid: TaxId) -> bool {
+        self.ancestors(taxid).any(|t| t == ancestor)
+    }
+
+    /// Lowest common ancestor of the given taxa. If the taxa are not connected, e.g. because
+    /// of unknown taxa, the root is returned.
+    pub fn lca(&self, a: TaxId, b: TaxId) -> TaxId {
+        if a == b {
+            return a;
+        }
+        let ancestors_a: HashSet<TaxId> = self.ancestors(a).collect();
+        self.ancestors(b)
+            .find(|t| ancestors_a.contains(t))
+            .unwrap_or(self.root)
+    }
+}
+
+/// Iterator over the ancestors of a taxon, returned by `Taxonomy::ancestors`.
+pub struct Ancestors<'a> {
+    taxonomy: &'a Taxonomy,
+    next: Option<TaxId>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = TaxId;
+
+    fn next(&mut self) -> Option<TaxId> {
+        let current = self.next?;
+        self.next = self.taxonomy.parent(current);
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lca() {
+        let mut taxonomy = Taxonomy::new(1);
+        // children before parents
+        taxonomy.add_node(10, 5);
+        taxonomy.add_node(11, 5);
+        taxonomy.add_node(5, 2);
+        taxonomy.add_node(2, 1);
+        taxonomy.add_node(3, 1);
+        assert_eq!(taxonomy.len(), 6);
+        assert_eq!(taxonomy.lca(10, 11), 5);
+        assert_eq!(taxonomy.lca(10, 5), 5);
+        assert_eq!(taxonomy.lca(10, 3), 1);
+        assert_eq!(taxonomy.lca(10, 1), 1);
+        assert_eq!(taxonomy.lca(10, 99), 1);
+        assert!(taxonomy.is_ancestor(2, 11));
+        assert!(!taxonomy.is_ancestor(3, 11));
+        assert_eq!(taxonomy.parent(1), None);
+        assert_eq!(taxonomy.parent(99), None);
+        assert_eq!(taxonomy.lineage(11), vec![1, 2, 5, 11]);
+        assert_eq!(taxonomy.ancestor_at_rank(11, "genus"), None);
+        taxonomy.set_rank(5, "genus");
+        taxonomy.set_rank(11, "species");
+        assert_eq!(taxonomy.ancestor_at_rank(11, "genus"), Some(5));
+        assert_eq!(taxonomy.ancestor_at_rank(11, "species"), Some(11));
+        assert_eq!(taxonomy.rank(5), Some("genus"));
+    }
+}
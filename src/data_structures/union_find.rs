@@ -0,0 +1,165 @@
+//! Disjoint-set (union-find) data structure with union by size and path compression
+//! (Tarjan, 1975). Operations run in amortized O(α(n)) time, with α being the inverse
+//! Ackermann function. This is useful for clustering seeds or overlaps and for finding the
+//! connected components of string or assembly graphs.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::union_find::UnionFind;
+//!
+//! let mut uf = UnionFind::new(5);
+//! uf.union(0, 1);
+//! uf.union(3, 4);
+//! uf.union(1, 4);
+//! assert!(uf.same_set(0, 3));
+//! assert_eq!(uf.set_size(0), 4);
+//! assert_eq!(uf.num_sets(), 2);
+//! assert_eq!(uf.components(), vec![vec![0, 1, 3, 4], vec![2]]);
+//! ```
+
+/// A disjoint-set forest over the elements `0..n`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    num_sets: usize,
+}
+
+impl UnionFind {
+    /// Create a new instance with `n` singleton sets.
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            num_sets: n,
+        }
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Check whether there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Number of disjoint sets.
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    /// Add a new singleton set and return its element.
+    pub fn push(&mut self) -> usize {
+        let x = self.parent.len();
+        self.parent.push(x);
+        self.size.push(1);
+        self.num_sets += 1;
+        x
+    }
+
+    /// Return the representative of the set containing `x`, compressing the path on the way.
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    /// Return the representative of the set containing `x` without modifying the structure.
+    pub fn find_immutable(&self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        root
+    }
+
+    /// Merge the sets containing `x` and `y`.
+    /// Returns `false` if they already were in the same set.
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let (mut rx, mut ry) = (self.find(x), self.find(y));
+        if rx == ry {
+            return false;
+        }
+        if self.size[rx] < self.size[ry] {
+            ::std::mem::swap(&mut rx, &mut ry);
+        }
+        self.parent[ry] = rx;
+        self.size[rx] += self.size[ry];
+        self.num_sets -= 1;
+        true
+    }
+
+    /// Check whether `x` and `y` belong to the same set.
+    pub fn same_set(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    /// Size of the set containing `x`.
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// Return all sets, each sorted ascendingly and ordered by their smallest element.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut index: Vec<Option<usize>> = vec![None; self.len()];
+        let mut components: Vec<Vec<usize>> = Vec::with_capacity(self.num_sets);
+        for x in 0..self.len() {
+            let root = self.find(x);
+            match index[root] {
+                Some(i) => components[i].push(x),
+                None => {
+                    index[root] = Some(components.len());
+                    components.push(vec![x]);
+                }
+            }
+        }
+        components
+    }
+
+    /// Iterate over the elements paired with the representative of their set.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.len()).map(move |x| (x, self.find_immutable(x)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        let mut uf = UnionFind::new(10);
+        assert_eq!(uf.num_sets(), 10);
+        for &(x, y) in &[(0, 2), (2, 4), (4, 6), (1, 3), (8, 9)] {
+            assert!(uf.union(x, y));
+        }
+        assert!(!uf.union(0, 6));
+        assert_eq!(uf.num_sets(), 5);
+        assert_eq!(uf.set_size(6), 4);
+        assert_eq!(uf.set_size(5), 1);
+        assert!(uf.same_set(0, 6));
+        assert!(!uf.same_set(0, 1));
+        assert_eq!(
+            uf.components(),
+            vec![vec![0, 2, 4, 6], vec![1, 3], vec![5], vec![7], vec![8, 9]]
+        );
+
+        let z = uf.push();
+        assert_eq!(z, 10);
+        uf.union(z, 7);
+        assert_eq!(uf.set_size(7), 2);
+        assert!(uf.iter().all(|(x, r)| uf.find_immutable(x) == r));
+    }
+}
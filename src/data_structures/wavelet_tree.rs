@@ -0,0 +1,194 @@
+//! A wavelet tree over a text, answering rank queries (occurrences of a symbol in a prefix) in
+//! O(log σ) time for alphabet size σ, independent of the text length. The tree is stored level
+//! by level (as a wavelet matrix, Claude, Navarro and Ordóñez, 2015), with one rank/select
+//! bitvector per level, and needs about n log σ bits plus the rank samples.
+//!
+//! As an occ backend of the FM-Index, it replaces the occ array, whose size grows with σ and
+//! whose queries scan up to k symbols of the BWT.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alphabets::dna;
+//! use bio::data_structures::bwt::{bwt, less};
+//! use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+//! use bio::data_structures::suffix_array::suffix_array;
+//! use bio::data_structures::wavelet_tree::WaveletTree;
+//!
+//! let text = b"GCCTTAACATTATTACGCCTA$";
+//! let alphabet = dna::n_alphabet();
+//! let sa = suffix_array(text);
+//! let bwt = bwt(text, &sa);
+//! let wt = WaveletTree::new(&bwt, &alphabet);
+//! assert_eq!(wt.rank(b'T', 10), 4);
+//!
+//! let fm = FMIndex::new(&bwt, less(&bwt, &alphabet), wt);
+//! let sai = fm.backward_search(b"TTA".iter());
+//! assert_eq!(sai.occ(&sa), [3, 12, 9]);
+//! ```
+
+use bv::{BitVec, BitsMut};
+use vec_map::VecMap;
+
+use alphabets::{Alphabet, RankTransform};
+use data_structures::bwt::{BWTSlice, OccBackend};
+use data_structures::rank_select::RankSelect;
+
+/// A wavelet tree in level-wise layout.
+#[derive(Serialize, Deserialize)]
+pub struct WaveletTree {
+    n: usize,
+    /// Codes (alphabet ranks) of the symbols.
+    codes: VecMap<u8>,
+    /// Bitvector of each level, from the most significant bit of the codes.
+    levels: Vec<RankSelect>,
+    /// Number of zeros in each level.
+    zeros: Vec<usize>,
+}
+
+impl WaveletTree {
+    /// Build the wavelet tree of the given text over the given alphabet. Symbols of the text
+    /// not in the alphabet (e.g. the sentinel `$`) are added to it.
+    /// Complexity: O(n log σ).
+    pub fn new(text: &BWTSlice, alphabet: &Alphabet) -> Self {
+        let mut alphabet = Alphabet {
+            symbols: alphabet.symbols.clone(),
+        };
+        for &a in text {
+            alphabet.insert(a);
+        }
+        let codes = RankTransform::new(&alphabet).ranks;
+        let width = (usize::BITS - alphabet.len().saturating_sub(1).leading_zeros()) as usize;
+        let n = text.len();
+        let mut seq: Vec<u8> = text.iter().map(|&a| codes[a as usize]).collect();
+
+        let mut levels = Vec::with_capacity(width);
+        let mut zeros = Vec::with_capacity(width);
+        for level in 0..width {
+            let shift = width - 1 - level;
+            let mut bits: BitVec<u8> = BitVec::new_fill(false, n as u64);
+            for (i, &c) in seq.iter().enumerate() {
+                if (c >> shift) & 1 == 1 {
+                    bits.set_bit(i as u64, true);
+                }
+            }
+            // stable partition into the codes with bit 0 followed by those with bit 1
+            let (mut next, ones): (Vec<u8>, Vec<u8>) =
+                seq.iter().partition(|&&c| (c >> shift) & 1 == 0);
+            zeros.push(next.len());
+            next.extend(ones);
+            seq = next;
+            levels.push(RankSelect::new(bits, 1));
+        }
+
+        WaveletTree {
+            n,
+            codes,
+            levels,
+            zeros,
+        }
+    }
+
+    /// Length of the text.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Number of occurrences of symbol a in text[..r+1]. Symbols not in the alphabet do not
+    /// occur.
+    /// Complexity: O(log σ).
+    pub fn rank(&self, a: u8, r: usize) -> usize {
+        assert!(r < self.n, "Expecting position within the text.");
+        let c = match self.codes.get(a as usize) {
+            Some(&c) => c,
+            None => return 0,
+        };
+        let width = self.levels.len();
+        let (mut start, mut end) = (0, r + 1);
+        for (level, (bits, &zeros)) in self.levels.iter().zip(&self.zeros).enumerate() {
+            if (c >> (width - 1 - level)) & 1 == 0 {
+                start = start - ones_before(bits, start);
+                end = end - ones_before(bits, end);
+            } else {
+                start = zeros + ones_before(bits, start);
+                end = zeros + ones_before(bits, end);
+            }
+        }
+        end - start
+    }
+}
+
+/// Number of ones in bits[..i].
+fn ones_before(bits: &RankSelect, i: usize) -> usize {
+    if i == 0 {
+        0
+    } else {
+        bits.rank_1(i as u64 - 1)
+            .expect("Expecting position within the bitvector.") as usize
+    }
+}
+
+impl OccBackend for WaveletTree {
+    fn occ(&self, _bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        self.rank(a, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alphabets::dna;
+    use data_structures::bwt::{bwt, less, Occ};
+    use data_structures::fmindex::{FMDIndex, FMIndex, FMIndexable};
+    use data_structures::suffix_array::suffix_array;
+
+    #[test]
+    fn test_rank() {
+        for alphabet in &[
+            Alphabet::new(b"A"),
+            Alphabet::new(b"AC"),
+            Alphabet::new(b"ACG"),
+            Alphabet::new(b"ACGTN$"),
+            Alphabet::new((0..=255u8).collect::<Vec<_>>()),
+        ] {
+            let symbols: Vec<u8> = alphabet.symbols.iter().map(|a| a as u8).collect();
+            let text: Vec<u8> = (0..300)
+                .map(|i| symbols[(i * 7 + i / 5) % symbols.len()])
+                .collect();
+            let wt = WaveletTree::new(&text, alphabet);
+            let occ = Occ::new(&text, 4, alphabet);
+            for r in 0..text.len() {
+                for &a in &symbols {
+                    assert_eq!(wt.rank(a, r), occ.get(&text, r, a));
+                }
+            }
+            assert_eq!(wt.rank(b'Z', 10), 0);
+        }
+        assert!(WaveletTree::new(b"", &Alphabet::new(b"AC")).is_empty());
+    }
+
+    #[test]
+    fn test_fmdindex() {
+        let text = b"ACGGATGCTGGATCGGATCGCGCTAGCTA$TAGCTAGCGCGATCCGATCCAGCATCCGT$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let wt = WaveletTree::new(&bwt, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+        let wt_fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &wt));
+        let read = b"GGATCGCGCTATCC";
+        for i in 0..read.len() {
+            assert_eq!(wt_fmdindex.smems(read, i), fmdindex.smems(read, i));
+        }
+        assert_eq!(
+            FMIndex::new(&bwt, &less, wt).backward_search(b"GATC".iter()),
+            FMIndex::new(&bwt, &less, &occ).backward_search(b"GATC".iter())
+        );
+    }
+}
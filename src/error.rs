@@ -0,0 +1,218 @@
+//! A crate-wide error type, into which the errors of the individual modules convert, so that
+//! applications can handle failures uniformly.
+//!
+//! Readers returning `io::Result` wrap parse errors (with their line) into `io::Error`s, which are
+//! unwrapped again when converted into `Error`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::error::{Error, Result};
+//! use bio::io::fastq;
+//!
+//! fn total_len(data: &[u8]) -> Result<usize> {
+//!     let mut reader = fastq::Reader::new(data);
+//!     let mut len = 0;
+//!     while let Some(record) = reader.read_ref()? {
+//!         len += record.seq().len();
+//!     }
+//!     Ok(len)
+//! }
+//!
+//! assert_eq!(total_len(b"@id\nACGT\n+\nIIII\n").unwrap(), 4);
+//! match total_len(b"@id\nACGT\n+\nIIII\nACGT\n") {
+//!     Err(Error::Parse { line, .. }) => assert_eq!(line, 5),
+//!     _ => panic!("Expecting parse error."),
+//! }
+//! ```
+
+use std::io;
+use std::result;
+
+use csv;
+
+use alignment::sv::SvError;
+use data_structures::fmindex::IndexBuildError;
+use genome::coord::CoordError;
+use genome::region::RegionError;
+use io::agp::AgpError;
+use io::sort::UnsortedError;
+use pattern_matching::pssm::PSSMError;
+use seq_analysis::rna::structure::StructureError;
+use seq_analysis::scaffold::ScaffoldError;
+use stats::hmm::HMMError;
+use stats::probs::ProbError;
+use utils::IntervalError;
+
+pub type Result<T> = result::Result<T, Error>;
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum Error {
+        Io(err: io::Error) {
+            description("I/O error")
+            display("{}", err)
+            cause(err)
+        }
+        Parse { format: &'static str, line: usize, msg: String } {
+            description("parse error")
+            display("invalid {} in line {}: {}", format, line, msg)
+        }
+        InvalidSymbol { symbol: u8, pos: usize } {
+            description("symbol not in alphabet")
+            display("symbol {} at position {} is not in the alphabet", *symbol as char, pos)
+        }
+        TooLong { len: usize, max: usize } {
+            description("input too long")
+            display("input of length {} exceeds the maximum length {}", len, max)
+        }
+        IndexMismatch(msg: String) {
+            description("index data structures do not match")
+            display("index data structures do not match: {}", msg)
+        }
+        Csv(err: csv::Error) {
+            from()
+            description("CSV error")
+            display("{}", err)
+            cause(err)
+        }
+        Agp(err: AgpError) {
+            from()
+            description("AGP error")
+            display("{}", err)
+        }
+        Scaffold(err: ScaffoldError) {
+            from()
+            description("scaffold error")
+            display("{}", err)
+        }
+        IndexBuild(err: IndexBuildError) {
+            from()
+            description("index build error")
+            display("{}", err)
+        }
+        Sv(err: SvError) {
+            from()
+            description("structural variant error")
+            display("{}", err)
+        }
+        Prob(err: ProbError) {
+            from()
+            description("probability error")
+            display("{}", err)
+        }
+        HMM(err: HMMError) {
+            from()
+            description("HMM error")
+            display("{}", err)
+        }
+        Structure(err: StructureError) {
+            from()
+            description("RNA structure error")
+            display("{}", err)
+        }
+        PSSM(err: PSSMError) {
+            from()
+            description("PSSM error")
+            display("{}", err)
+        }
+        Interval(err: IntervalError) {
+            from()
+            description("interval error")
+            display("{}", err)
+        }
+        Unsorted(err: UnsortedError) {
+            from()
+            description("unsorted records")
+            display("{}", err)
+        }
+        Region(err: RegionError) {
+            from()
+            description("region error")
+            display("{}", err)
+        }
+        Coord(err: CoordError) {
+            from()
+            description("coordinate error")
+            display("{}", err)
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    /// Unwrap errors of this crate wrapped into an `io::Error`.
+    fn from(err: io::Error) -> Self {
+        if err.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            *err.into_inner().unwrap().downcast::<Error>().unwrap()
+        } else {
+            Error::Io(err)
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    /// Wrap into an `io::Error` of kind `InvalidData`, unless it is an I/O error.
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_roundtrip() {
+        let err = Error::Parse {
+            format: "FASTA",
+            line: 3,
+            msg: "expected > at record start".to_owned(),
+        };
+        let io_err: io::Error = err.into();
+        assert_eq!(io_err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            io_err.to_string(),
+            "invalid FASTA in line 3: expected > at record start"
+        );
+        match Error::from(io_err) {
+            Error::Parse { line: 3, .. } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+
+        let eof = io::Error::from(io::ErrorKind::UnexpectedEof);
+        match Error::from(eof) {
+            Error::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_from_module_errors() {
+        let err: Error = SvError::InvalidCigar("3Q".to_owned()).into();
+        assert_eq!(err.to_string(), "invalid CIGAR string '3Q'");
+        let err: Error = IndexBuildError::MissingSentinel.into();
+        match err {
+            Error::IndexBuild(IndexBuildError::MissingSentinel) => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        let err: Error = UnsortedError::Contig(7, "chr2".to_owned()).into();
+        match err {
+            Error::Unsorted(UnsortedError::Contig(7, ref contig)) if contig == "chr2" => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        let err: Error = RegionError::InvalidRange(20, 10).into();
+        assert_eq!(err.to_string(), "invalid range 20-10 in region string");
+        match err {
+            Error::Region(RegionError::InvalidRange(20, 10)) => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+        let err: Error = CoordError::InvalidPosition(0).into();
+        match err {
+            Error::Coord(CoordError::InvalidPosition(0)) => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Chromosome name aliasing.
+//!
+//! Names are resolved to canonical names either by an explicit alias table (e.g. RefSeq
+//! accessions) or by comparing their normalized forms, which ignore a `chr` prefix, the case and
+//! the difference between `M` and `MT` for the mitochondrial genome.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::alias::ChromAliases;
+//!
+//! let aliases = ChromAliases::grch38();
+//! assert_eq!(aliases.resolve("chr1"), Some("1"));
+//! assert_eq!(aliases.resolve("NC_000001.11"), Some("1"));
+//! assert_eq!(aliases.resolve("chrM"), Some("MT"));
+//! assert!(aliases.same("chrX", "NC_000023.11"));
+//! assert_eq!(aliases.resolve("chr1_KI270706v1_random"), None);
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::io::prelude::*;
+
+/// GRCh38 chromosomes (Ensembl names) and their RefSeq accessions.
+const GRCH38: [(&str, &str); 25] = [
+    ("1", "NC_000001.11"),
+    ("2", "NC_000002.12"),
+    ("3", "NC_000003.12"),
+    ("4", "NC_000004.12"),
+    ("5", "NC_000005.10"),
+    ("6", "NC_000006.12"),
+    ("7", "NC_000007.14"),
+    ("8", "NC_000008.11"),
+    ("9", "NC_000009.12"),
+    ("10", "NC_000010.11"),
+    ("11", "NC_000011.10"),
+    ("12", "NC_000012.12"),
+    ("13", "NC_000013.11"),
+    ("14", "NC_000014.9"),
+    ("15", "NC_000015.10"),
+    ("16", "NC_000016.10"),
+    ("17", "NC_000017.11"),
+    ("18", "NC_000018.10"),
+    ("19", "NC_000019.10"),
+    ("20", "NC_000020.11"),
+    ("21", "NC_000021.9"),
+    ("22", "NC_000022.11"),
+    ("X", "NC_000023.11"),
+    ("Y", "NC_000024.10"),
+    ("MT", "NC_012920.1"),
+];
+
+/// Normalized form of a chromosome name: without `chr` prefix, upper case, and `MT` for `M`.
+pub fn normalize(name: &str) -> String {
+    let has_prefix = name.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("chr"));
+    let stripped = if has_prefix && name.len() > 3 {
+        &name[3..]
+    } else {
+        name
+    };
+    let normalized = stripped.to_ascii_uppercase();
+    if normalized == "M" {
+        "MT".to_owned()
+    } else {
+        normalized
+    }
+}
+
+/// A table resolving chromosome names to canonical names.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChromAliases {
+    /// Canonical names by normalized alias.
+    canonical: HashMap<String, String>,
+}
+
+impl ChromAliases {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Aliases of the primary GRCh38 chromosomes, with Ensembl names as canonical names.
+    pub fn grch38() -> Self {
+        let mut aliases = ChromAliases::new();
+        for &(name, refseq) in GRCH38.iter() {
+            aliases.add(name, refseq);
+        }
+        aliases
+    }
+
+    /// Read a tab separated alias file, where the first column contains the canonical name and
+    /// all further columns contain aliases (e.g. the `chromAlias.txt` files provided by UCSC).
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut aliases = ChromAliases::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t').map(str::trim);
+            let name = fields.next().unwrap();
+            aliases.add_name(name);
+            for alias in fields.filter(|alias| !alias.is_empty()) {
+                aliases.add(name, alias);
+            }
+        }
+        Ok(aliases)
+    }
+
+    /// Register a canonical name.
+    pub fn add_name(&mut self, name: &str) {
+        self.canonical.insert(normalize(name), name.to_owned());
+    }
+
+    /// Register a canonical name and an alias of it.
+    pub fn add(&mut self, name: &str, alias: &str) {
+        self.add_name(name);
+        self.canonical.insert(normalize(alias), name.to_owned());
+    }
+
+    /// Number of known names and aliases.
+    pub fn len(&self) -> usize {
+        self.canonical.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.canonical.is_empty()
+    }
+
+    /// Resolve the given name to its canonical name, if known.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        self.canonical.get(&normalize(name)).map(String::as_str)
+    }
+
+    /// Whether both names refer to the same chromosome, either via the alias table or because
+    /// their normalized forms are equal.
+    pub fn same(&self, a: &str, b: &str) -> bool {
+        match (self.resolve(a), self.resolve(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => normalize(a) == normalize(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("chr1"), "1");
+        assert_eq!(normalize("Chr2L"), "2L");
+        assert_eq!(normalize("chrM"), "MT");
+        assert_eq!(normalize("M"), "MT");
+        assert_eq!(normalize("chr"), "CHR");
+        assert_eq!(normalize("scaffold_1"), "SCAFFOLD_1");
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let file = b"# ucsc\tassembly\tgenbank\trefseq
+chr1\t1\tCM000663.2\tNC_000001.11
+chrM\tMT\tJ01415.2\tNC_012920.1
+chrUn_KI270302v1\tHSCHRUN_RANDOM_CTG1\tKI270302.1\t
+";
+        let aliases = ChromAliases::from_reader(&file[..]).unwrap();
+        assert_eq!(aliases.resolve("1"), Some("chr1"));
+        assert_eq!(aliases.resolve("CM000663.2"), Some("chr1"));
+        assert_eq!(aliases.resolve("MT"), Some("chrM"));
+        assert_eq!(aliases.resolve("KI270302.1"), Some("chrUn_KI270302v1"));
+        assert_eq!(aliases.resolve("chr2"), None);
+        assert!(aliases.same("chr2", "2"));
+        assert!(!aliases.same("chr1", "NC_012920.1"));
+        assert_eq!(ChromAliases::grch38().len(), 50);
+    }
+}
@@ -0,0 +1,160 @@
+//! Canonical sequence checksums, e.g. for comparing references against sequence dictionaries or
+//! looking them up in external services.
+//!
+//! Before hashing, sequences are canonicalized by converting them to upper case and removing
+//! whitespace and gaps (`-` and `.`). The following checksums are provided:
+//!
+//! * MD5 (hexadecimal), as used in the `M5` tag of SAM headers and in refget,
+//! * `sha512t24u`, i.e. the url-safe base64 encoding of the first 24 bytes of the SHA-512 digest,
+//!   as used in refget and for GA4GH identifiers (`SQ.` prefix),
+//! * SEGUID (Babnigg and Giometti, 2006), i.e. the base64 encoding of the SHA-1 digest without
+//!   padding.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::checksum::{md5, Checksummer};
+//!
+//! assert_eq!(md5(b"acgt"), "f1f8f4bf413b16ad135722aa4591043e");
+//!
+//! // chunks of a large sequence, e.g. lines of a FASTA file
+//! let mut checksummer = Checksummer::new();
+//! checksummer.update(b"AC-GT\n");
+//! checksummer.update(b"NNACGT\n");
+//! let checksums = checksummer.finish();
+//! assert_eq!(checksums.len, 10);
+//! assert_eq!(checksums.refget_id(), "SQ.Yb1RIcSYZuLT4uJ6GrN-HikkLFxQxDyN");
+//! ```
+
+use utils::{to_hex, Md5, Sha1, Sha512};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Base64 encoding without padding.
+fn base64(bytes: &[u8], alphabet: &[u8; 64]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).cloned().unwrap_or(0),
+            chunk.get(2).cloned().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..=chunk.len() {
+            encoded.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Canonical form of a sequence: upper case, without whitespace and gaps.
+pub fn canonicalize(seq: &[u8]) -> Vec<u8> {
+    seq.iter()
+        .filter(|&&b| !(b.is_ascii_whitespace() || b == b'-' || b == b'.'))
+        .map(|b| b.to_ascii_uppercase())
+        .collect()
+}
+
+/// Checksums of a canonicalized sequence.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checksums {
+    /// Length of the canonicalized sequence.
+    pub len: u64,
+    pub md5: String,
+    pub sha512t24u: String,
+    pub seguid: String,
+}
+
+impl Checksums {
+    /// The refget sequence identifier, i.e. `SQ.` followed by the `sha512t24u` checksum.
+    pub fn refget_id(&self) -> String {
+        format!("SQ.{}", self.sha512t24u)
+    }
+}
+
+/// Streaming computation of checksums, for sequences given in chunks.
+#[derive(Clone, Debug, Default)]
+pub struct Checksummer {
+    len: u64,
+    md5: Md5,
+    sha512: Sha512,
+    sha1: Sha1,
+}
+
+impl Checksummer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add the next chunk of the sequence, which is canonicalized before hashing.
+    pub fn update(&mut self, chunk: &[u8]) {
+        let chunk = canonicalize(chunk);
+        self.len += chunk.len() as u64;
+        self.md5.update(&chunk);
+        self.sha512.update(&chunk);
+        self.sha1.update(&chunk);
+    }
+
+    pub fn finish(self) -> Checksums {
+        Checksums {
+            len: self.len,
+            md5: to_hex(&self.md5.finalize()),
+            sha512t24u: base64(&self.sha512.finalize()[..24], BASE64_URL),
+            seguid: base64(&self.sha1.finalize(), BASE64),
+        }
+    }
+}
+
+/// All checksums of the given sequence.
+pub fn checksums(seq: &[u8]) -> Checksums {
+    let mut checksummer = Checksummer::new();
+    checksummer.update(seq);
+    checksummer.finish()
+}
+
+/// MD5 checksum of the canonicalized sequence.
+pub fn md5(seq: &[u8]) -> String {
+    to_hex(&Md5::digest(&canonicalize(seq)))
+}
+
+/// `sha512t24u` checksum of the canonicalized sequence.
+pub fn sha512t24u(seq: &[u8]) -> String {
+    base64(&Sha512::digest(&canonicalize(seq))[..24], BASE64_URL)
+}
+
+/// SEGUID checksum of the canonicalized sequence.
+pub fn seguid(seq: &[u8]) -> String {
+    base64(&Sha1::digest(&canonicalize(seq)), BASE64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b"", BASE64), "");
+        assert_eq!(base64(b"f", BASE64), "Zg");
+        assert_eq!(base64(b"fo", BASE64), "Zm8");
+        assert_eq!(base64(b"foo", BASE64), "Zm9v");
+        assert_eq!(base64(b"foob", BASE64), "Zm9vYg");
+        assert_eq!(base64(&[0xfb, 0xff], BASE64), "+/8");
+        assert_eq!(base64(&[0xfb, 0xff], BASE64_URL), "-_8");
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(md5(b"ACGT"), "f1f8f4bf413b16ad135722aa4591043e");
+        // refget specification example
+        assert_eq!(sha512t24u(b"ACGT"), "aKF498dAxcJAqme6QYQ7EZ07-fiw8Kw2");
+        assert_eq!(seguid(b"ACGT"), "IQiZThf2zKn/I1KtqStlEdsHYDQ");
+        assert_eq!(canonicalize(b"ac-g.t \n"), b"ACGT");
+
+        let all = checksums(b"acgtnn\nACGT");
+        assert_eq!(all.len, 10);
+        assert_eq!(all.md5, "1c4ab0b3062cfd287a4c8b1d77ac5bd8");
+        assert_eq!(all.sha512t24u, "Yb1RIcSYZuLT4uJ6GrN-HikkLFxQxDyN");
+        assert_eq!(all.seguid, "1rHoWOsvn9vj9bTiN3MUwd3Grv4");
+    }
+}
@@ -0,0 +1,412 @@
+//! Stranded positions and regions on named sequences, tagged with their coordinate system.
+//! The coordinate system (`ZeroBased` with half-open regions as in BED, or `OneBased` with
+//! closed regions as in GFF, VCF and SAM) is part of the type, so coordinates of different
+//! systems cannot be mixed up without an explicit `convert`. Arithmetic is checked, and
+//! shifting and flanking follow the strand, i.e. upstream of a region on the reverse strand
+//! lies at higher coordinates.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::genome::coord::{CoordRegion, OneBased, ZeroBased};
+//! use bio_types::strand::ReqStrand;
+//!
+//! # fn main() {
+//! let gene = CoordRegion::<OneBased>::new("chr1", 1001, 2000, Some(ReqStrand::Reverse)).unwrap();
+//! assert_eq!(gene.len(), 1000);
+//! assert_eq!(gene.five_prime().unwrap().pos(), 2000);
+//!
+//! let promoter = gene.upstream(500);
+//! assert_eq!((promoter.start(), promoter.end()), (2001, 2500));
+//!
+//! let bed: CoordRegion<ZeroBased> = promoter.convert();
+//! assert_eq!((bed.start(), bed.end()), (2000, 2500));
+//! # }
+//! ```
+
+use std::cmp;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use bio_types::strand::ReqStrand;
+
+use genome::region;
+
+/// A coordinate system, given by the coordinate of the first position of a sequence. Region
+/// ends are the same in both systems: exclusive for 0-based, inclusive for 1-based coordinates.
+pub trait CoordSystem: Copy + Debug + Default + Eq + Hash + Ord {
+    /// Coordinate of the first position of a sequence.
+    const FIRST: u64;
+}
+
+/// 0-based coordinates with half-open regions, e.g. as in BED files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ZeroBased;
+
+impl CoordSystem for ZeroBased {
+    const FIRST: u64 = 0;
+}
+
+/// 1-based coordinates with closed regions, e.g. as in GFF, VCF and SAM files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OneBased;
+
+impl CoordSystem for OneBased {
+    const FIRST: u64 = 1;
+}
+
+quick_error! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum CoordError {
+        InvalidPosition(pos: u64) {
+            description("invalid position")
+            display("invalid position {} in the coordinate system", pos)
+        }
+        InvalidRange(start: u64, end: u64) {
+            description("invalid range")
+            display("invalid range {}-{} in the coordinate system", start, end)
+        }
+    }
+}
+
+/// Move a 0-based coordinate by delta positions downstream on the given strand.
+fn move_downstream(pos: u64, delta: i64, strand: Option<ReqStrand>) -> Option<u64> {
+    let delta = match strand {
+        Some(ReqStrand::Reverse) => delta.checked_neg()?,
+        _ => delta,
+    };
+    if delta >= 0 {
+        pos.checked_add(delta as u64)
+    } else {
+        pos.checked_sub(delta.unsigned_abs())
+    }
+}
+
+/// A position on the given contig and strand (`None` if unknown) in the coordinate system `C`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position<C: CoordSystem> {
+    contig: String,
+    /// 0-based position.
+    pos: u64,
+    strand: Option<ReqStrand>,
+    coord: PhantomData<C>,
+}
+
+impl<C: CoordSystem> Position<C> {
+    /// Position `pos` (in the coordinate system `C`) on the given contig and strand.
+    pub fn new(contig: &str, pos: u64, strand: Option<ReqStrand>) -> Result<Self, CoordError> {
+        if pos < C::FIRST {
+            return Err(CoordError::InvalidPosition(pos));
+        }
+        Ok(Self::from_zero_based(
+            contig.to_owned(),
+            pos - C::FIRST,
+            strand,
+        ))
+    }
+
+    fn from_zero_based(contig: String, pos: u64, strand: Option<ReqStrand>) -> Self {
+        Position {
+            contig,
+            pos,
+            strand,
+            coord: PhantomData,
+        }
+    }
+
+    pub fn contig(&self) -> &str {
+        &self.contig
+    }
+
+    /// The position in the coordinate system `C`.
+    pub fn pos(&self) -> u64 {
+        self.pos + C::FIRST
+    }
+
+    pub fn strand(&self) -> Option<ReqStrand> {
+        self.strand
+    }
+
+    /// The position delta coordinates further, or `None` on overflow.
+    pub fn checked_add(&self, delta: u64) -> Option<Self> {
+        let pos = self.pos.checked_add(delta)?;
+        Some(Self::from_zero_based(self.contig.clone(), pos, self.strand))
+    }
+
+    /// The position delta coordinates before, or `None` if before the start of the contig.
+    pub fn checked_sub(&self, delta: u64) -> Option<Self> {
+        let pos = self.pos.checked_sub(delta)?;
+        Some(Self::from_zero_based(self.contig.clone(), pos, self.strand))
+    }
+
+    /// The position delta positions downstream (upstream if negative) on its strand, i.e.
+    /// towards lower coordinates on the reverse strand. Unknown strands are treated as forward.
+    /// Returns `None` if the position would leave the contig start or overflow.
+    pub fn shift(&self, delta: i64) -> Option<Self> {
+        let pos = move_downstream(self.pos, delta, self.strand)?;
+        Some(Self::from_zero_based(self.contig.clone(), pos, self.strand))
+    }
+
+    /// The same position in the coordinate system `D`.
+    pub fn convert<D: CoordSystem>(&self) -> Position<D> {
+        Position::from_zero_based(self.contig.clone(), self.pos, self.strand)
+    }
+}
+
+/// A region on the given contig and strand (`None` if unknown) in the coordinate system `C`.
+/// Unlike `region::Region`, which is always 0-based and unstranded, the strand determines the
+/// direction of `shift`, `upstream` and `downstream`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CoordRegion<C: CoordSystem> {
+    contig: String,
+    /// 0-based start.
+    start: u64,
+    /// 0-based exclusive end.
+    end: u64,
+    strand: Option<ReqStrand>,
+    coord: PhantomData<C>,
+}
+
+impl<C: CoordSystem> CoordRegion<C> {
+    /// Region from `start` to `end` (in the coordinate system `C`) on the given contig and
+    /// strand. Empty regions have end `start - 1` in 1-based coordinates.
+    pub fn new(
+        contig: &str,
+        start: u64,
+        end: u64,
+        strand: Option<ReqStrand>,
+    ) -> Result<Self, CoordError> {
+        if start < C::FIRST || start - C::FIRST > end {
+            return Err(CoordError::InvalidRange(start, end));
+        }
+        Ok(Self::from_zero_based(
+            contig.to_owned(),
+            start - C::FIRST,
+            end,
+            strand,
+        ))
+    }
+
+    fn from_zero_based(contig: String, start: u64, end: u64, strand: Option<ReqStrand>) -> Self {
+        CoordRegion {
+            contig,
+            start,
+            end,
+            strand,
+            coord: PhantomData,
+        }
+    }
+
+    pub fn contig(&self) -> &str {
+        &self.contig
+    }
+
+    /// The start in the coordinate system `C`.
+    pub fn start(&self) -> u64 {
+        self.start + C::FIRST
+    }
+
+    /// The end in the coordinate system `C` (exclusive if 0-based, inclusive if 1-based).
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn strand(&self) -> Option<ReqStrand> {
+        self.strand
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Check whether the region contains the given position, regardless of strands.
+    pub fn contains(&self, pos: &Position<C>) -> bool {
+        self.contig == pos.contig && self.start <= pos.pos && pos.pos < self.end
+    }
+
+    /// The first position of the region on its strand, i.e. the last coordinate on the reverse
+    /// strand, or `None` if the region is empty.
+    pub fn five_prime(&self) -> Option<Position<C>> {
+        if self.is_empty() {
+            return None;
+        }
+        let pos = match self.strand {
+            Some(ReqStrand::Reverse) => self.end - 1,
+            _ => self.start,
+        };
+        Some(Position::from_zero_based(
+            self.contig.clone(),
+            pos,
+            self.strand,
+        ))
+    }
+
+    /// The last position of the region on its strand, or `None` if the region is empty.
+    pub fn three_prime(&self) -> Option<Position<C>> {
+        if self.is_empty() {
+            return None;
+        }
+        let pos = match self.strand {
+            Some(ReqStrand::Reverse) => self.start,
+            _ => self.end - 1,
+        };
+        Some(Position::from_zero_based(
+            self.contig.clone(),
+            pos,
+            self.strand,
+        ))
+    }
+
+    /// The region moved delta positions downstream (upstream if negative) on its strand, or
+    /// `None` if it would leave the contig start or overflow.
+    pub fn shift(&self, delta: i64) -> Option<Self> {
+        let start = move_downstream(self.start, delta, self.strand)?;
+        let end = start.checked_add(self.len())?;
+        Some(Self::from_zero_based(
+            self.contig.clone(),
+            start,
+            end,
+            self.strand,
+        ))
+    }
+
+    /// The flank of len positions upstream of the region on its strand, clipped at the contig
+    /// start.
+    pub fn upstream(&self, len: u64) -> Self {
+        let (start, end) = match self.strand {
+            Some(ReqStrand::Reverse) => (self.end, self.end.saturating_add(len)),
+            _ => (self.start.saturating_sub(len), self.start),
+        };
+        Self::from_zero_based(self.contig.clone(), start, end, self.strand)
+    }
+
+    /// The flank of len positions downstream of the region on its strand, clipped at the
+    /// contig start.
+    pub fn downstream(&self, len: u64) -> Self {
+        let (start, end) = match self.strand {
+            Some(ReqStrand::Reverse) => (self.start.saturating_sub(len), self.start),
+            _ => (self.end, self.end.saturating_add(len)),
+        };
+        Self::from_zero_based(self.contig.clone(), start, end, self.strand)
+    }
+
+    /// The region extended by the given numbers of positions upstream and downstream on its
+    /// strand, clipped at the contig start.
+    pub fn expand(&self, upstream: u64, downstream: u64) -> Self {
+        let (before, after) = match self.strand {
+            Some(ReqStrand::Reverse) => (downstream, upstream),
+            _ => (upstream, downstream),
+        };
+        Self::from_zero_based(
+            self.contig.clone(),
+            self.start.saturating_sub(before),
+            self.end.saturating_add(after),
+            self.strand,
+        )
+    }
+
+    /// Number of positions shared with the other region, regardless of strands.
+    pub fn overlap(&self, other: &Self) -> u64 {
+        if self.contig != other.contig {
+            return 0;
+        }
+        cmp::min(self.end, other.end).saturating_sub(cmp::max(self.start, other.start))
+    }
+
+    /// The same region in the coordinate system `D`.
+    pub fn convert<D: CoordSystem>(&self) -> CoordRegion<D> {
+        CoordRegion::from_zero_based(self.contig.clone(), self.start, self.end, self.strand)
+    }
+}
+
+impl<C: CoordSystem> From<region::Region> for CoordRegion<C> {
+    /// The unstranded region.
+    fn from(region: region::Region) -> Self {
+        Self::from_zero_based(region.contig, region.start, region.end, None)
+    }
+}
+
+impl<C: CoordSystem> From<CoordRegion<C>> for region::Region {
+    /// The 0-based region without strand.
+    fn from(region: CoordRegion<C>) -> Self {
+        region::Region {
+            contig: region.contig,
+            start: region.start,
+            end: region.end,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        assert_eq!(
+            Position::<OneBased>::new("chr1", 0, None),
+            Err(CoordError::InvalidPosition(0))
+        );
+        let pos = Position::<OneBased>::new("chr1", 10, Some(ReqStrand::Reverse)).unwrap();
+        assert_eq!(pos.convert::<ZeroBased>().pos(), 9);
+        assert_eq!(pos.shift(3).unwrap().pos(), 7);
+        assert_eq!(pos.shift(-3).unwrap().pos(), 13);
+        assert_eq!(pos.shift(10), None);
+        assert_eq!(pos.checked_add(5).unwrap().pos(), 15);
+        assert_eq!(pos.checked_sub(9).unwrap().pos(), 1);
+        assert_eq!(pos.checked_sub(10), None);
+        assert_eq!(pos.checked_add(u64::MAX), None);
+
+        let pos = Position::<ZeroBased>::new("chr1", 0, None).unwrap();
+        assert_eq!(pos.shift(2).unwrap().pos(), 2);
+        assert_eq!(pos.shift(-1), None);
+    }
+
+    #[test]
+    fn test_region() {
+        assert_eq!(
+            CoordRegion::<ZeroBased>::new("chr1", 10, 9, None),
+            Err(CoordError::InvalidRange(10, 9))
+        );
+        let empty = CoordRegion::<OneBased>::new("chr1", 10, 9, None).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.five_prime(), None);
+
+        let fwd =
+            CoordRegion::<ZeroBased>::new("chr1", 100, 200, Some(ReqStrand::Forward)).unwrap();
+        assert_eq!(fwd.five_prime().unwrap().pos(), 100);
+        assert_eq!(fwd.three_prime().unwrap().pos(), 199);
+        assert_eq!(fwd.upstream(150).start(), 0);
+        assert_eq!(fwd.upstream(150).end(), 100);
+        assert_eq!(fwd.downstream(10).start(), 200);
+        assert_eq!(fwd.shift(-100).unwrap().start(), 0);
+        assert_eq!(fwd.shift(-101), None);
+        assert_eq!(fwd.expand(10, 20).len(), 130);
+        assert!(fwd.contains(&fwd.three_prime().unwrap()));
+        assert!(!fwd.contains(&fwd.downstream(1).five_prime().unwrap()));
+
+        let rev = CoordRegion::<OneBased>::new("chr1", 101, 200, Some(ReqStrand::Reverse)).unwrap();
+        assert_eq!(rev.five_prime().unwrap().pos(), 200);
+        assert_eq!(rev.three_prime().unwrap().pos(), 101);
+        assert_eq!(
+            (rev.downstream(10).start(), rev.downstream(10).end()),
+            (91, 100)
+        );
+        assert_eq!(rev.shift(100).unwrap().start(), 1);
+        assert_eq!(rev.shift(101), None);
+        let expanded = rev.expand(10, 20);
+        assert_eq!((expanded.start(), expanded.end()), (81, 210));
+        assert_eq!(rev.overlap(&fwd.convert()), 100);
+
+        let plain: region::Region = rev.clone().into();
+        assert_eq!(plain, region::Region::new("chr1", 100, 200));
+        assert_eq!(CoordRegion::<OneBased>::from(plain).strand(), None);
+    }
+}
@@ -0,0 +1,338 @@
+//! Sequence dictionaries, i.e. the names, lengths and checksums of the sequences of a reference
+//! genome, as read from Picard/GATK `.dict` files (SAM headers), UCSC `chrom.sizes` files or
+//! FASTA indices.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::alias::ChromAliases;
+//! use bio::genome::dict::SequenceDictionary;
+//!
+//! let dict = b"@HD\tVN:1.6\n\
+//! @SQ\tSN:chr1\tLN:248956422\tM5:6aef897c3d6ff0c78aff06ac189178dd\tAN:1,NC_000001.11\n\
+//! @SQ\tSN:chr2\tLN:242193529\tM5:f98db672eb0993dcfdabafe2a882905c\n";
+//! let dict = SequenceDictionary::from_dict(&dict[..]).unwrap();
+//! assert_eq!(dict.len(), 2);
+//! assert_eq!(dict.get("chr2").unwrap().len, 242_193_529);
+//! assert_eq!(dict.get("NC_000001.11").unwrap().name, "chr1");
+//!
+//! // resolve names of another naming scheme
+//! let aliases = ChromAliases::grch38();
+//! assert_eq!(dict.get_with_aliases("NC_000002.12", &aliases).unwrap().name, "chr2");
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use genome::alias::{normalize, ChromAliases};
+use genome::checksum::Checksums;
+use io::fasta;
+
+/// A sequence of a reference genome.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceRecord {
+    pub name: String,
+    pub len: u64,
+    /// MD5 checksum of the sequence (lower case hexadecimal).
+    pub md5: Option<String>,
+    /// Alternative names.
+    pub aliases: Vec<String>,
+}
+
+impl SequenceRecord {
+    /// Create a record without checksum and aliases.
+    pub fn new(name: &str, len: u64) -> Self {
+        SequenceRecord {
+            name: name.to_owned(),
+            len,
+            md5: None,
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Whether the given checksums of a sequence agree with the length and (if known) the MD5
+    /// checksum of this record.
+    pub fn matches(&self, checksums: &Checksums) -> bool {
+        self.len == checksums.len && self.md5.as_ref().map_or(true, |md5| *md5 == checksums.md5)
+    }
+}
+
+/// An ordered collection of the sequences of a reference genome.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceDictionary {
+    records: Vec<SequenceRecord>,
+    /// Record index by name and alias.
+    index: HashMap<String, usize>,
+}
+
+fn invalid_data(msg: &str, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", msg, line.trim_end()),
+    )
+}
+
+impl SequenceDictionary {
+    /// Create an empty dictionary.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Read a `.dict` file, i.e. the `@SQ` lines of a SAM header. The `SN` and `LN` tags are
+    /// required, the `M5` and `AN` (alternative names) tags are used if present.
+    pub fn from_dict<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut dict = SequenceDictionary::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.starts_with("@SQ\t") {
+                continue;
+            }
+            let (mut name, mut len, mut md5, mut aliases) = (None, None, None, Vec::new());
+            for field in line.split('\t').skip(1) {
+                if field.len() < 3 || field.as_bytes()[2] != b':' {
+                    return Err(invalid_data("Invalid SAM header tag", &line));
+                }
+                let value = &field[3..];
+                match &field[..2] {
+                    "SN" => name = Some(value.to_owned()),
+                    "LN" => {
+                        len = Some(
+                            value
+                                .parse()
+                                .map_err(|_| invalid_data("Invalid sequence length", &line))?,
+                        )
+                    }
+                    "M5" => md5 = Some(value.to_ascii_lowercase()),
+                    "AN" => aliases = value.split(',').map(str::to_owned).collect(),
+                    _ => (),
+                }
+            }
+            match (name, len) {
+                (Some(name), Some(len)) => dict.push(SequenceRecord {
+                    name,
+                    len,
+                    md5,
+                    aliases,
+                })?,
+                _ => return Err(invalid_data("Expecting SN and LN tags", &line)),
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Read a `chrom.sizes` file, i.e. tab separated names and lengths.
+    pub fn from_chrom_sizes<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut dict = SequenceDictionary::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (name, len) = match (fields.next(), fields.next()) {
+                (Some(name), Some(len)) => (name, len),
+                _ => return Err(invalid_data("Expecting name and length", &line)),
+            };
+            let len = len
+                .parse()
+                .map_err(|_| invalid_data("Invalid sequence length", &line))?;
+            dict.push(SequenceRecord::new(name, len))?;
+        }
+        Ok(dict)
+    }
+
+    /// Read a `.dict` file from the given path.
+    pub fn from_dict_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_dict(io::BufReader::new(fs::File::open(path)?))
+    }
+
+    /// Read a `chrom.sizes` file from the given path.
+    pub fn from_chrom_sizes_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_chrom_sizes(io::BufReader::new(fs::File::open(path)?))
+    }
+
+    /// Create a dictionary of the sequences of a FASTA index.
+    pub fn from_fasta_index(index: &fasta::Index) -> Self {
+        let mut dict = SequenceDictionary::new();
+        for seq in index.sequences() {
+            dict.push(SequenceRecord::new(&seq.name, seq.len))
+                .expect("bug: duplicate sequence in FASTA index");
+        }
+        dict
+    }
+
+    /// Append a record. Returns an error if its name or one of its aliases is already used.
+    pub fn push(&mut self, record: SequenceRecord) -> io::Result<()> {
+        let i = self.records.len();
+        let names = Some(&record.name).into_iter().chain(&record.aliases);
+        if let Some(name) = names.clone().find(|&name| self.index.contains_key(name)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Duplicate sequence name: {}", name),
+            ));
+        }
+        for name in names {
+            self.index.insert(name.clone(), i);
+        }
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Number of sequences.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Total length of all sequences.
+    pub fn total_len(&self) -> u64 {
+        self.records.iter().map(|r| r.len).sum()
+    }
+
+    /// Iterate over the records in order.
+    pub fn iter(&self) -> impl Iterator<Item = &SequenceRecord> {
+        self.records.iter()
+    }
+
+    /// Get the record with the given name or alias.
+    pub fn get(&self, name: &str) -> Option<&SequenceRecord> {
+        self.index.get(name).map(|&i| &self.records[i])
+    }
+
+    /// Get the record of the given name, resolving names of other naming schemes via the given
+    /// aliases or by comparing the normalized names (e.g. `1` and `chr1`).
+    pub fn get_with_aliases(&self, name: &str, aliases: &ChromAliases) -> Option<&SequenceRecord> {
+        if let Some(record) = self.get(name) {
+            return Some(record);
+        }
+        self.records.iter().find(|record| {
+            Some(&record.name)
+                .into_iter()
+                .chain(&record.aliases)
+                .any(|n| aliases.same(n, name))
+        })
+    }
+
+    /// Check that the given dictionary contains the same sequences with the same lengths
+    /// (and checksums, if known in both), allowing for differently named sequences.
+    pub fn is_compatible(&self, other: &SequenceDictionary, aliases: &ChromAliases) -> bool {
+        self.len() == other.len()
+            && self.records.iter().all(|record| {
+                other
+                    .get_with_aliases(&record.name, aliases)
+                    .is_some_and(|o| {
+                        o.len == record.len
+                            && (record.md5.is_none() || o.md5.is_none() || o.md5 == record.md5)
+                    })
+            })
+    }
+
+    /// Names of all sequences whose normalized names are equal, which would be ambiguous if
+    /// names are compared irrespective of the naming scheme.
+    pub fn ambiguous_names(&self) -> Vec<&str> {
+        let mut counts = HashMap::new();
+        for record in &self.records {
+            *counts.entry(normalize(&record.name)).or_insert(0) += 1;
+        }
+        self.records
+            .iter()
+            .filter(|record| counts[&normalize(&record.name)] > 1)
+            .map(|record| record.name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genome::checksum;
+
+    const DICT: &[u8] = b"@HD\tVN:1.6\tSO:unsorted
+@SQ\tSN:chr1\tLN:248956422\tM5:6AEF897C3D6FF0C78AFF06AC189178DD\tUR:file:/ref.fa
+@SQ\tSN:chrM\tLN:16569\tAN:MT
+";
+
+    #[test]
+    fn test_from_dict() {
+        let dict = SequenceDictionary::from_dict(DICT).unwrap();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.total_len(), 248_956_422 + 16_569);
+        let chr1 = dict.get("chr1").unwrap();
+        assert_eq!(
+            chr1.md5.as_ref().unwrap(),
+            "6aef897c3d6ff0c78aff06ac189178dd"
+        );
+        assert_eq!(dict.get("MT").unwrap().name, "chrM");
+        assert_eq!(dict.get("1"), None);
+        assert_eq!(
+            dict.get_with_aliases("1", &ChromAliases::new())
+                .unwrap()
+                .name,
+            "chr1"
+        );
+        assert_eq!(
+            dict.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+            ["chr1", "chrM"]
+        );
+
+        assert!(SequenceDictionary::from_dict(&b"@SQ\tSN:chr1\n"[..]).is_err());
+        assert!(SequenceDictionary::from_dict(&b"@SQ\tSN:chr1\tLN:x\n"[..]).is_err());
+        assert!(
+            SequenceDictionary::from_dict(&b"@SQ\tSN:chr1\tLN:1\n@SQ\tSN:chr1\tLN:2\n"[..])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let dict = SequenceDictionary::from_dict(
+            &b"@SQ\tSN:a\tLN:4\tM5:f1f8f4bf413b16ad135722aa4591043e\n@SQ\tSN:b\tLN:4\n"[..],
+        )
+        .unwrap();
+        let acgt = checksum::checksums(b"ACGT");
+        assert!(dict.get("a").unwrap().matches(&acgt));
+        assert!(!dict
+            .get("a")
+            .unwrap()
+            .matches(&checksum::checksums(b"ACGA")));
+        assert!(dict
+            .get("b")
+            .unwrap()
+            .matches(&checksum::checksums(b"ACGA")));
+        assert!(!dict.get("b").unwrap().matches(&checksum::checksums(b"ACG")));
+    }
+
+    #[test]
+    fn test_from_chrom_sizes() {
+        let sizes = b"1\t248956422\nMT\t16569\n\n";
+        let dict = SequenceDictionary::from_chrom_sizes(&sizes[..]).unwrap();
+        assert_eq!(dict.get("MT").unwrap().len, 16_569);
+        assert!(SequenceDictionary::from_chrom_sizes(&b"chr1\n"[..]).is_err());
+
+        let ucsc = SequenceDictionary::from_dict(DICT).unwrap();
+        let aliases = ChromAliases::grch38();
+        assert!(ucsc.is_compatible(&dict, &aliases));
+        assert!(dict.is_compatible(&ucsc, &aliases));
+        let other =
+            SequenceDictionary::from_chrom_sizes(&b"1\t248956422\nMT\t16570\n"[..]).unwrap();
+        assert!(!ucsc.is_compatible(&other, &aliases));
+    }
+
+    #[test]
+    fn test_from_fasta_index() {
+        let fai = b"chr1\t16\t6\t15\t16\nchr2\t8\t29\t8\t9\n";
+        let index = fasta::Index::new(&fai[..]).unwrap();
+        let dict = SequenceDictionary::from_fasta_index(&index);
+        assert_eq!(dict.get("chr2").unwrap().len, 8);
+        assert!(dict.ambiguous_names().is_empty());
+        let mut dict = dict;
+        dict.push(SequenceRecord::new("2", 8)).unwrap();
+        assert_eq!(dict.ambiguous_names(), ["chr2", "2"]);
+    }
+}
@@ -0,0 +1,268 @@
+//! Enrichment tests for the overlap of two sets of genomic regions, e.g. whether peaks overlap
+//! promoters more than expected by chance.
+//!
+//! Overlaps are measured in base pairs, after merging overlapping regions within each set. Two
+//! tests are provided:
+//!
+//! * `fisher_test`: Fisher's exact test on the 2x2 table of base pairs inside or outside of
+//!   either set, within a genome (universe) of given size. It assumes independence of base
+//!   pairs, and hence tends to be overly significant for large regions.
+//! * `permutation_test`: the query regions are repeatedly placed at random positions with a
+//!   `SegmentSampler` (honoring exclusion regions), and the observed overlap is compared to the
+//!   overlaps of the permutations.
+//!
+//! Both tests are one-sided, testing for enrichment, and report the fold enrichment of the
+//! observed over the expected overlap as effect size.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::enrichment::{fisher_test, permutation_test};
+//! use bio::genome::region::Region;
+//! use bio::genome::sample::SegmentSampler;
+//! use bio::utils::Xoshiro256;
+//!
+//! let promoters: Vec<_> = (0..20).map(|i| Region::new("chr1", i * 5000, i * 5000 + 500)).collect();
+//! let peaks: Vec<_> = (0..20).map(|i| Region::new("chr1", i * 5000 + 100, i * 5000 + 300)).collect();
+//!
+//! let result = fisher_test(&peaks, &promoters, 100_000);
+//! assert_eq!(result.observed, 4000);
+//! assert!((result.fold_enrichment - 10.0).abs() < 1e-9);
+//! assert!(result.p_value < 1e-100);
+//!
+//! let sampler = SegmentSampler::from_lengths(&[("chr1", 100_000)], &[]);
+//! let mut rng = Xoshiro256::new(42);
+//! let result = permutation_test(&peaks, &promoters, &sampler, 99, &mut rng);
+//! assert_eq!(result.p_value, 0.01);
+//! ```
+
+use std::cmp;
+
+use statrs::function::factorial::ln_binomial;
+
+use genome::region::{merge, Region};
+use genome::sample::SegmentSampler;
+use utils::SeedableRngExt;
+
+/// Result of an enrichment test.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Enrichment {
+    /// Observed overlap (base pairs).
+    pub observed: u64,
+    /// Expected overlap (base pairs) under the null hypothesis.
+    pub expected: f64,
+    /// Ratio of observed and expected overlap.
+    pub fold_enrichment: f64,
+    /// Odds ratio of the 2x2 table (Fisher's exact test only).
+    pub odds_ratio: Option<f64>,
+    /// One-sided p-value for enrichment.
+    pub p_value: f64,
+}
+
+/// Number of base pairs covered by both sets of regions.
+pub fn overlap_bp(a: &[Region], b: &[Region]) -> u64 {
+    let (a, b) = (merge(a), merge(b));
+    let (mut i, mut j) = (0, 0);
+    let mut overlap = 0;
+    while i < a.len() && j < b.len() {
+        let (x, y) = (&a[i], &b[j]);
+        overlap += x.overlap(y);
+        // advance the region that ends first
+        if (&x.contig, x.end) < (&y.contig, y.end) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    overlap
+}
+
+/// Number of base pairs covered by the regions.
+fn covered_bp(regions: &[Region]) -> u64 {
+    merge(regions).iter().map(|region| region.len()).sum()
+}
+
+/// Upper tail `P(X >= k)` of the hypergeometric distribution of the number of successes when
+/// drawing `n` of `total` items, `successes` of which are successes.
+fn hypergeometric_upper_tail(k: u64, total: u64, successes: u64, n: u64) -> f64 {
+    let min = (n + successes).saturating_sub(total);
+    let max = cmp::min(n, successes);
+    if k <= min {
+        return 1.0;
+    }
+    if k > max {
+        return 0.0;
+    }
+    let ln_pmf = |x: u64| {
+        ln_binomial(successes, x) + ln_binomial(total - successes, n - x) - ln_binomial(total, n)
+    };
+    // ratio pmf(x + 1) / pmf(x)
+    let ratio = |x: u64| {
+        let x = x as f64;
+        (successes as f64 - x) * (n as f64 - x)
+            / ((x + 1.0) * ((total - successes - n) as f64 + x + 1.0))
+    };
+    let mode = ((n as f64 + 1.0) * (successes as f64 + 1.0) / (total as f64 + 2.0)).floor() as u64;
+
+    // sum the tail that does not contain the mode, starting from its largest term
+    let sum_from = |start: u64, upwards: bool| {
+        let mut term = ln_pmf(start).exp();
+        let mut sum = 0.0;
+        let mut x = start;
+        loop {
+            sum += term;
+            if term <= sum * 1e-17 || (upwards && x == max) || (!upwards && x == min) {
+                return sum;
+            }
+            if upwards {
+                term *= ratio(x);
+                x += 1;
+            } else {
+                x -= 1;
+                term /= ratio(x);
+            }
+        }
+    };
+    if k > mode {
+        sum_from(k, true).min(1.0)
+    } else {
+        (1.0 - sum_from(k - 1, false)).max(0.0)
+    }
+}
+
+/// Fisher's exact test for enrichment of the overlap of the query and the reference regions,
+/// within a genome of the given size.
+pub fn fisher_test(query: &[Region], reference: &[Region], genome_len: u64) -> Enrichment {
+    let observed = overlap_bp(query, reference);
+    let (q, r) = (covered_bp(query), covered_bp(reference));
+    assert!(
+        q <= genome_len && r <= genome_len,
+        "Expecting regions to fit into the genome."
+    );
+    let expected = q as f64 * r as f64 / genome_len as f64;
+    // 2x2 table: query and reference, query only, reference only, neither
+    let (a, b, c) = (observed, q - observed, r - observed);
+    let d = genome_len - q - r + observed;
+    Enrichment {
+        observed,
+        expected,
+        fold_enrichment: observed as f64 / expected,
+        odds_ratio: Some((a as f64 * d as f64) / (b as f64 * c as f64)),
+        p_value: hypergeometric_upper_tail(observed, genome_len, r, q),
+    }
+}
+
+/// Permutation test for enrichment of the overlap of the query and the reference regions. In
+/// each of the given number of permutations, all query regions are placed randomly with the
+/// given sampler. Query regions that do not fit anywhere are dropped.
+pub fn permutation_test<R: SeedableRngExt>(
+    query: &[Region],
+    reference: &[Region],
+    sampler: &SegmentSampler,
+    permutations: usize,
+    rng: &mut R,
+) -> Enrichment {
+    assert!(permutations > 0, "Expecting at least one permutation.");
+    let observed = overlap_bp(query, reference);
+    let mut sum = 0;
+    let mut exceeding = 0;
+    for _ in 0..permutations {
+        let permuted: Vec<_> = sampler
+            .sample_matched(query, rng)
+            .into_iter()
+            .flatten()
+            .collect();
+        let overlap = overlap_bp(&permuted, reference);
+        sum += overlap;
+        if overlap >= observed {
+            exceeding += 1;
+        }
+    }
+    let expected = sum as f64 / permutations as f64;
+    Enrichment {
+        observed,
+        expected,
+        fold_enrichment: observed as f64 / expected,
+        odds_ratio: None,
+        p_value: (exceeding + 1) as f64 / (permutations + 1) as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stats::combinatorics::combinations;
+    use utils::Xoshiro256;
+
+    #[test]
+    fn test_overlap_bp() {
+        let a = [
+            Region::new("chr1", 0, 100),
+            Region::new("chr1", 50, 150),
+            Region::new("chr2", 0, 10),
+        ];
+        let b = [
+            Region::new("chr1", 90, 200),
+            Region::new("chr2", 5, 8),
+            Region::new("chr3", 0, 10),
+        ];
+        assert_eq!(overlap_bp(&a, &b), 60 + 3);
+        assert_eq!(overlap_bp(&b, &a), 63);
+        assert_eq!(overlap_bp(&a, &[]), 0);
+    }
+
+    #[test]
+    fn test_hypergeometric() {
+        // exact values from explicit sums
+        let pmf = |k: u64| combinations(10, k) * combinations(40, 12 - k) / combinations(50, 12);
+        for k in 0..=11 {
+            let expected: f64 = (k..=10).map(pmf).sum();
+            assert_relative_eq!(
+                hypergeometric_upper_tail(k, 50, 10, 12),
+                expected,
+                max_relative = 1e-9
+            );
+        }
+        assert_eq!(hypergeometric_upper_tail(0, 50, 10, 12), 1.0);
+        assert_eq!(hypergeometric_upper_tail(11, 50, 10, 12), 0.0);
+        // large universe
+        let p = hypergeometric_upper_tail(1_000_000, 3_000_000_000, 10_000_000, 100_000_000);
+        assert!(p < 1e-100);
+        let p = hypergeometric_upper_tail(333_333, 3_000_000_000, 10_000_000, 100_000_000);
+        assert!(p > 0.4 && p < 0.6);
+    }
+
+    #[test]
+    fn test_fisher() {
+        let query = [Region::new("chr1", 0, 10)];
+        let reference = [Region::new("chr1", 5, 15)];
+        let result = fisher_test(&query, &reference, 100);
+        assert_eq!(result.observed, 5);
+        assert_eq!(result.expected, 1.0);
+        assert_eq!(result.odds_ratio, Some(5.0 * 85.0 / 25.0));
+        assert_relative_eq!(
+            result.p_value,
+            hypergeometric_upper_tail(5, 100, 10, 10),
+            max_relative = 1e-12
+        );
+        assert!(result.p_value < 0.01);
+    }
+
+    #[test]
+    fn test_permutation() {
+        let sampler = SegmentSampler::from_lengths(&[("chr1", 100_000)], &[]);
+        let reference: Vec<_> = (0..100)
+            .map(|i| Region::new("chr1", i * 1000, i * 1000 + 500))
+            .collect();
+        let mut rng = Xoshiro256::new(1);
+        // the reference covers half of the genome, and half of the query overlaps it
+        let query: Vec<_> = (0..10)
+            .map(|i| Region::new("chr1", i * 10_000 + 450, i * 10_000 + 550))
+            .collect();
+        let result = permutation_test(&query, &reference, &sampler, 200, &mut rng);
+        assert_eq!(result.observed, 500);
+        assert!(result.p_value > 0.1);
+        assert!((result.fold_enrichment - 1.0).abs() < 0.1);
+        assert_eq!(result.odds_ratio, None);
+    }
+}
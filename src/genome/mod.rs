@@ -0,0 +1,15 @@
+//! Reference genome metadata: chromosome naming schemes, sequence dictionaries and checksums,
+//! as well as genomic positions and regions, their random sampling and genome-wide windows.
+//!
+//! Different sources name the same chromosome differently, e.g. `chr1` (UCSC), `1` (Ensembl)
+//! or `NC_000001.11` (RefSeq). The types in this module allow to resolve such names to a
+//! single naming scheme before comparing coordinates of different sources.
+
+pub mod alias;
+pub mod checksum;
+pub mod coord;
+pub mod dict;
+pub mod enrichment;
+pub mod region;
+pub mod sample;
+pub mod window;
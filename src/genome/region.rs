@@ -0,0 +1,355 @@
+//! Genomic regions, i.e. half-open intervals on named sequences with 0-based coordinates.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::region::{merge, Region};
+//!
+//! let regions = vec![
+//!     Region::new("chr2", 10, 20),
+//!     Region::new("chr1", 5, 15),
+//!     Region::new("chr1", 0, 10),
+//! ];
+//! let merged = merge(&regions);
+//! assert_eq!(merged, [Region::new("chr1", 0, 15), Region::new("chr2", 10, 20)]);
+//! assert_eq!(merged[0].overlap(&regions[1]), 10);
+//! ```
+//!
+//! Regions can be parsed from and formatted as samtools-style region strings, which are 1-based
+//! and inclusive (see `Region::parse`):
+//!
+//! ```
+//! use bio::genome::region::Region;
+//!
+//! let region: Region = "chr1:1,001-2,000".parse().unwrap();
+//! assert_eq!(region, Region::new("chr1", 1000, 2000));
+//! assert_eq!(region.to_string(), "chr1:1001-2000");
+//! ```
+
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use io::bed;
+use io::sort::{GenomicRecord, Spill};
+
+/// A region `start..end` (0-based, end exclusive) on the given contig.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Region {
+    pub contig: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Region {
+    pub fn new(contig: &str, start: u64, end: u64) -> Self {
+        assert!(start <= end, "Expecting region start not after end.");
+        Region {
+            contig: contig.to_owned(),
+            start,
+            end,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Number of positions shared with the other region.
+    pub fn overlap(&self, other: &Region) -> u64 {
+        if self.contig != other.contig {
+            return 0;
+        }
+        cmp::min(self.end, other.end).saturating_sub(cmp::max(self.start, other.start))
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum RegionError {
+        InvalidFormat(region: String) {
+            description("invalid region string")
+            display("invalid region string '{}'", region)
+        }
+        InvalidPosition(pos: String) {
+            description("invalid position in region string")
+            display("invalid position '{}' in region string", pos)
+        }
+        InvalidRange(start: u64, end: u64) {
+            description("invalid range in region string")
+            display("invalid range {}-{} in region string", start, end)
+        }
+        UnknownContig(contig: String) {
+            description("unknown contig")
+            display("unknown length of contig '{}'", contig)
+        }
+    }
+}
+
+/// Parse a 1-based position, allowing commas as thousands separators.
+fn parse_pos(pos: &str) -> Result<u64, RegionError> {
+    let digits: String = pos.chars().filter(|&c| c != ',').collect();
+    match digits.parse::<u64>() {
+        Ok(pos) if pos > 0 => Ok(pos),
+        _ => Err(RegionError::InvalidPosition(pos.to_owned())),
+    }
+}
+
+impl Region {
+    /// Parse a samtools-style region string with 1-based, inclusive coordinates, given the
+    /// lengths of the contigs. The forms are
+    ///
+    /// * `chr1`: the whole contig,
+    /// * `chr1:1000`, `chr1:1000-`: from position 1000 to the end of the contig,
+    /// * `chr1:1,000-2,000`: positions 1000 to 2000, where commas are ignored,
+    /// * `{chr1:A}:1-10`: braces enclose contig names containing colons.
+    ///
+    /// Ends beyond the contig are clipped to its length. Contigs of unknown length (`None`)
+    /// are only allowed with both start and end given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::genome::region::{Region, RegionError};
+    ///
+    /// let len = |contig: &str| if contig == "chr1" { Some(5000) } else { None };
+    /// assert_eq!(Region::parse("chr1", len), Ok(Region::new("chr1", 0, 5000)));
+    /// assert_eq!(Region::parse("chr1:4,001-", len), Ok(Region::new("chr1", 4000, 5000)));
+    /// assert_eq!(Region::parse("chr2:5-10", len), Ok(Region::new("chr2", 4, 10)));
+    /// assert_eq!(
+    ///     Region::parse("chr2:5", len),
+    ///     Err(RegionError::UnknownContig("chr2".to_owned()))
+    /// );
+    /// ```
+    pub fn parse<F: Fn(&str) -> Option<u64>>(
+        region: &str,
+        seq_len: F,
+    ) -> Result<Self, RegionError> {
+        let invalid = || RegionError::InvalidFormat(region.to_owned());
+        let (contig, range) = if region.starts_with('{') {
+            let close = region.find('}').ok_or_else(invalid)?;
+            let rest = &region[close + 1..];
+            if !rest.is_empty() && !rest.starts_with(':') {
+                return Err(invalid());
+            }
+            (&region[1..close], rest.get(1..))
+        } else {
+            match region.rfind(':') {
+                Some(colon) => (&region[..colon], Some(&region[colon + 1..])),
+                None => (region, None),
+            }
+        };
+        if contig.is_empty() {
+            return Err(invalid());
+        }
+
+        let (start, end) = match range {
+            None | Some("") => (1, None),
+            Some(range) => match range.find('-') {
+                Some(dash) if dash + 1 == range.len() => (parse_pos(&range[..dash])?, None),
+                Some(dash) => (
+                    parse_pos(&range[..dash])?,
+                    Some(parse_pos(&range[dash + 1..])?),
+                ),
+                None => (parse_pos(range)?, None),
+            },
+        };
+        let len = seq_len(contig);
+        let end = match (end, len) {
+            (Some(end), Some(len)) => cmp::min(end, len),
+            (Some(end), None) => end,
+            (None, Some(len)) => len,
+            (None, None) => return Err(RegionError::UnknownContig(contig.to_owned())),
+        };
+        // empty regions are given by an end just before the start
+        if end + 1 < start || len.is_some_and(|len| start > len + 1) {
+            return Err(RegionError::InvalidRange(start, end));
+        }
+        Ok(Region::new(contig, start - 1, end))
+    }
+}
+
+/// Parse a region string with start and end (see `Region::parse`).
+impl FromStr for Region {
+    type Err = RegionError;
+
+    fn from_str(region: &str) -> Result<Self, RegionError> {
+        Region::parse(region, |_| None)
+    }
+}
+
+/// Format as samtools-style region string with 1-based, inclusive coordinates, enclosing
+/// contig names with colons in braces.
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.contig.contains(':') {
+            write!(f, "{{{}}}:{}-{}", self.contig, self.start + 1, self.end)
+        } else {
+            write!(f, "{}:{}-{}", self.contig, self.start + 1, self.end)
+        }
+    }
+}
+
+impl From<&bed::Record> for Region {
+    fn from(record: &bed::Record) -> Self {
+        Region::new(record.chrom(), record.start(), record.end())
+    }
+}
+
+impl GenomicRecord for Region {
+    fn contig(&self) -> &str {
+        &self.contig
+    }
+
+    fn pos(&self) -> u64 {
+        self.start
+    }
+}
+
+impl Spill for Region {
+    fn spill<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.contig.spill(writer)?;
+        self.start.spill(writer)?;
+        self.end.spill(writer)
+    }
+
+    fn unspill<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Region {
+            contig: String::unspill(reader)?,
+            start: u64::unspill(reader)?,
+            end: u64::unspill(reader)?,
+        })
+    }
+}
+
+/// Sort the given regions and merge overlapping or adjacent ones.
+pub fn merge(regions: &[Region]) -> Vec<Region> {
+    let mut sorted = regions.to_vec();
+    sorted.sort();
+    let mut merged: Vec<Region> = Vec::with_capacity(sorted.len());
+    for region in sorted {
+        match merged.last_mut() {
+            Some(last) if last.contig == region.contig && region.start <= last.end => {
+                last.end = cmp::max(last.end, region.end);
+            }
+            _ => merged.push(region),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region() {
+        let a = Region::new("chr1", 10, 20);
+        assert_eq!(a.len(), 10);
+        assert!(Region::new("chr1", 5, 5).is_empty());
+        assert_eq!(a.overlap(&Region::new("chr1", 15, 30)), 5);
+        assert_eq!(a.overlap(&Region::new("chr1", 20, 30)), 0);
+        assert_eq!(a.overlap(&Region::new("chr2", 10, 20)), 0);
+
+        let mut record = bed::Record::new();
+        record.set_chrom("chrX");
+        record.set_start(3);
+        record.set_end(7);
+        assert_eq!(Region::from(&record), Region::new("chrX", 3, 7));
+    }
+
+    #[test]
+    fn test_parse() {
+        let len = |contig: &str| match contig {
+            "chr1" => Some(1000),
+            "HLA-A*01:01" => Some(50),
+            _ => None,
+        };
+        let parse = |region| Region::parse(region, len);
+        assert_eq!(parse("chr1"), Ok(Region::new("chr1", 0, 1000)));
+        assert_eq!(parse("chr1:"), Ok(Region::new("chr1", 0, 1000)));
+        assert_eq!(parse("chr1:100"), Ok(Region::new("chr1", 99, 1000)));
+        assert_eq!(parse("chr1:100-"), Ok(Region::new("chr1", 99, 1000)));
+        assert_eq!(parse("chr1:100-200"), Ok(Region::new("chr1", 99, 200)));
+        assert_eq!(parse("chr1:100-5,000"), Ok(Region::new("chr1", 99, 1000)));
+        assert_eq!(parse("chr1:100-99"), Ok(Region::new("chr1", 99, 99)));
+        assert_eq!(
+            parse("{HLA-A*01:01}"),
+            Ok(Region::new("HLA-A*01:01", 0, 50))
+        );
+        assert_eq!(
+            parse("{HLA-A*01:01}:5-10"),
+            Ok(Region::new("HLA-A*01:01", 4, 10))
+        );
+        assert_eq!(
+            parse("chrX:1,000,000-2,000,000"),
+            Ok(Region::new("chrX", 999_999, 2_000_000))
+        );
+
+        assert_eq!(
+            parse("chr1:100-98"),
+            Err(RegionError::InvalidRange(100, 98))
+        );
+        assert_eq!(
+            parse("chr1:2000-3000"),
+            Err(RegionError::InvalidRange(2000, 1000))
+        );
+        assert_eq!(
+            parse("chr1:0-10"),
+            Err(RegionError::InvalidPosition("0".to_owned()))
+        );
+        assert_eq!(
+            parse("chr1:a-10"),
+            Err(RegionError::InvalidPosition("a".to_owned()))
+        );
+        assert_eq!(
+            parse("chr1:-10"),
+            Err(RegionError::InvalidPosition("".to_owned()))
+        );
+        assert_eq!(
+            parse(":1-10"),
+            Err(RegionError::InvalidFormat(":1-10".to_owned()))
+        );
+        assert_eq!(
+            parse("{chr1:1-10"),
+            Err(RegionError::InvalidFormat("{chr1:1-10".to_owned()))
+        );
+        assert_eq!(
+            "chr2".parse::<Region>(),
+            Err(RegionError::UnknownContig("chr2".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        for region in &[
+            Region::new("chr1", 0, 10),
+            Region::new("chr1", 5, 5),
+            Region::new("HLA-A*01:01", 3, 7),
+        ] {
+            assert_eq!(&region.to_string().parse::<Region>().unwrap(), region);
+        }
+        assert_eq!(Region::new("chr1", 0, 10).to_string(), "chr1:1-10");
+        assert_eq!(Region::new("a:b", 0, 10).to_string(), "{a:b}:1-10");
+    }
+
+    #[test]
+    fn test_merge() {
+        let regions = vec![
+            Region::new("chr1", 20, 30),
+            Region::new("chr1", 0, 10),
+            Region::new("chr1", 10, 12),
+            Region::new("chr1", 25, 28),
+        ];
+        assert_eq!(
+            merge(&regions),
+            [Region::new("chr1", 0, 12), Region::new("chr1", 20, 30)]
+        );
+        assert!(merge(&[]).is_empty());
+    }
+}
@@ -0,0 +1,263 @@
+//! Sampling of random genomic segments, e.g. to build null models for enrichment analyses.
+//!
+//! Segments are placed uniformly at random among all positions where they fit into the genome
+//! without overlapping any exclusion region (e.g. assembly gaps or blacklisted regions).
+//! Background sets can be matched to a foreground set by length, and additionally by GC
+//! content, using the reference sequence from any `FetchSequence` backend. Results are
+//! reproducible for equally seeded generators (see `utils::SeedableRngExt`).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::region::Region;
+//! use bio::genome::sample::SegmentSampler;
+//! use bio::utils::Xoshiro256;
+//!
+//! let lengths = [("chr1", 1000), ("chr2", 500)];
+//! let exclude = [Region::new("chr1", 100, 900)];
+//! let sampler = SegmentSampler::from_lengths(&lengths, &exclude);
+//! let mut rng = Xoshiro256::new(42);
+//!
+//! let foreground = [Region::new("chr1", 10, 60), Region::new("chr2", 0, 200)];
+//! let background = sampler.sample_matched(&foreground, &mut rng);
+//! for (fg, bg) in foreground.iter().zip(&background) {
+//!     let bg = bg.as_ref().unwrap();
+//!     assert_eq!(bg.len(), fg.len());
+//!     assert_eq!(bg.overlap(&exclude[0]), 0);
+//! }
+//! ```
+
+use std::io;
+
+use genome::dict::SequenceDictionary;
+use genome::region::{merge, Region};
+use io::fetch::FetchSequence;
+use utils::SeedableRngExt;
+
+/// GC content of the A, C, G and T bases of a sequence, `None` without such bases.
+fn gc_content(seq: &[u8]) -> Option<f64> {
+    let (mut gc, mut acgt) = (0, 0);
+    for &b in seq {
+        match b.to_ascii_uppercase() {
+            b'G' | b'C' => {
+                gc += 1;
+                acgt += 1;
+            }
+            b'A' | b'T' => acgt += 1,
+            _ => (),
+        }
+    }
+    if acgt > 0 {
+        Some(gc as f64 / acgt as f64)
+    } else {
+        None
+    }
+}
+
+/// Sampler of random segments within the allowed parts of a genome.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentSampler {
+    /// Maximal regions not overlapping any exclusion region.
+    allowed: Vec<Region>,
+}
+
+impl SegmentSampler {
+    /// Create a sampler for the sequences of the given dictionary.
+    pub fn new(dict: &SequenceDictionary, exclude: &[Region]) -> Self {
+        let lengths: Vec<_> = dict
+            .iter()
+            .map(|record| (record.name.as_str(), record.len))
+            .collect();
+        Self::from_lengths(&lengths, exclude)
+    }
+
+    /// Create a sampler for the given sequence names and lengths.
+    pub fn from_lengths(lengths: &[(&str, u64)], exclude: &[Region]) -> Self {
+        let exclude = merge(exclude);
+        let mut allowed = Vec::new();
+        for &(name, len) in lengths {
+            let mut start = 0;
+            for region in exclude.iter().filter(|region| region.contig == name) {
+                if region.start > start {
+                    allowed.push(Region::new(name, start, region.start.min(len)));
+                }
+                start = start.max(region.end);
+                if start >= len {
+                    break;
+                }
+            }
+            if start < len {
+                allowed.push(Region::new(name, start, len));
+            }
+        }
+        allowed.retain(|region| !region.is_empty());
+        SegmentSampler { allowed }
+    }
+
+    /// The regions where segments may be placed.
+    pub fn allowed(&self) -> &[Region] {
+        &self.allowed
+    }
+
+    /// Number of positions at which a segment of the given length may start.
+    pub fn placements(&self, len: u64) -> u64 {
+        self.allowed
+            .iter()
+            .map(|region| (region.len() + 1).saturating_sub(len))
+            .sum()
+    }
+
+    /// Sample a segment of the given length, `None` if it does not fit anywhere.
+    pub fn sample<R: SeedableRngExt>(&self, len: u64, rng: &mut R) -> Option<Region> {
+        let total = self.placements(len);
+        if total == 0 {
+            return None;
+        }
+        let mut k = rng.below(total);
+        for region in &self.allowed {
+            let n = (region.len() + 1).saturating_sub(len);
+            if k < n {
+                let start = region.start + k;
+                return Some(Region::new(&region.contig, start, start + len));
+            }
+            k -= n;
+        }
+        unreachable!()
+    }
+
+    /// Sample one segment of the same length for each foreground region.
+    pub fn sample_matched<R: SeedableRngExt>(
+        &self,
+        foreground: &[Region],
+        rng: &mut R,
+    ) -> Vec<Option<Region>> {
+        foreground
+            .iter()
+            .map(|region| self.sample(region.len(), rng))
+            .collect()
+    }
+
+    /// Sample one segment of the same length and similar GC content for each foreground region.
+    /// Candidates are drawn until their GC content deviates by at most `tolerance` from the
+    /// foreground, for at most `max_tries` candidates, after which the closest candidate is
+    /// used. Regions without GC content (e.g. only `N`s) are matched by length only.
+    pub fn sample_gc_matched<F: FetchSequence, R: SeedableRngExt>(
+        &self,
+        foreground: &[Region],
+        reference: &mut F,
+        tolerance: f64,
+        max_tries: usize,
+        rng: &mut R,
+    ) -> io::Result<Vec<Option<Region>>> {
+        let mut background = Vec::with_capacity(foreground.len());
+        for region in foreground {
+            let target = gc_content(&reference.fetch(&region.contig, region.start, region.end)?);
+            let mut best: Option<(f64, Region)> = None;
+            for _ in 0..max_tries.max(1) {
+                let candidate = match self.sample(region.len(), rng) {
+                    Some(candidate) => candidate,
+                    None => break,
+                };
+                let target = match target {
+                    Some(target) => target,
+                    None => {
+                        best = Some((0.0, candidate));
+                        break;
+                    }
+                };
+                let seq = reference.fetch(&candidate.contig, candidate.start, candidate.end)?;
+                let deviation = match gc_content(&seq) {
+                    Some(gc) => (gc - target).abs(),
+                    None => continue,
+                };
+                if best.as_ref().map_or(true, |&(d, _)| deviation < d) {
+                    best = Some((deviation, candidate));
+                }
+                if deviation <= tolerance {
+                    break;
+                }
+            }
+            background.push(best.map(|(_, region)| region));
+        }
+        Ok(background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use utils::Xoshiro256;
+
+    #[test]
+    fn test_allowed() {
+        let exclude = [
+            Region::new("a", 0, 10),
+            Region::new("a", 50, 60),
+            Region::new("a", 55, 120),
+            Region::new("c", 0, 5),
+        ];
+        let sampler = SegmentSampler::from_lengths(&[("a", 100), ("b", 20)], &exclude);
+        assert_eq!(
+            sampler.allowed(),
+            [Region::new("a", 10, 50), Region::new("b", 0, 20)]
+        );
+        assert_eq!(sampler.placements(20), 21 + 1);
+        assert_eq!(sampler.placements(21), 20);
+        assert_eq!(sampler.placements(41), 0);
+    }
+
+    #[test]
+    fn test_sample() {
+        let sampler =
+            SegmentSampler::from_lengths(&[("a", 100), ("b", 20)], &[Region::new("a", 0, 10)]);
+        let mut rng = Xoshiro256::new(1);
+        let mut starts = HashMap::new();
+        for _ in 0..10_000 {
+            let region = sampler.sample(20, &mut rng).unwrap();
+            assert_eq!(region.len(), 20);
+            assert!(region.contig == "b" || region.start >= 10);
+            assert!(region.end <= if region.contig == "a" { 100 } else { 20 });
+            *starts.entry((region.contig, region.start)).or_insert(0) += 1;
+        }
+        // 71 placements on a, 1 on b, all equally likely
+        assert_eq!(starts.len(), 72);
+        assert!(starts.values().all(|&c| c > 80 && c < 200));
+        assert!(sampler.sample(91, &mut rng).is_none());
+
+        let foreground = [Region::new("x", 0, 5), Region::new("x", 0, 95)];
+        let background = sampler.sample_matched(&foreground, &mut rng);
+        assert_eq!(background[0].as_ref().unwrap().len(), 5);
+        assert!(background[1].is_none());
+    }
+
+    #[test]
+    fn test_sample_gc_matched() {
+        let mut reference = HashMap::new();
+        reference.insert("gc".to_owned(), b"GCGCGCGCGCGCGCGCGCGC".to_vec());
+        reference.insert("at".to_owned(), b"ATATATATATATATATATAT".to_vec());
+        reference.insert("n".to_owned(), b"NNNNNNNNNN".to_vec());
+        let sampler =
+            SegmentSampler::from_lengths(&[("gc", 20), ("at", 20)], &[Region::new("gc", 0, 10)]);
+        let foreground = [
+            Region::new("gc", 0, 5),
+            Region::new("at", 0, 5),
+            Region::new("n", 0, 5),
+        ];
+        let mut rng = Xoshiro256::new(3);
+        for _ in 0..20 {
+            let background = sampler
+                .sample_gc_matched(&foreground, &mut reference, 0.1, 100, &mut rng)
+                .unwrap();
+            let contigs: Vec<_> = background
+                .iter()
+                .map(|region| region.as_ref().unwrap().contig.as_str())
+                .collect();
+            assert_eq!(contigs[..2], ["gc", "at"]);
+            assert!(background[0].as_ref().unwrap().start >= 10);
+        }
+        assert!(sampler
+            .sample_gc_matched(&[Region::new("x", 0, 5)], &mut reference, 0.1, 10, &mut rng)
+            .is_err());
+    }
+}
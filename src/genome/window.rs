@@ -0,0 +1,224 @@
+//! Genome-wide tiling with fixed-size windows, e.g. for computing coverage, GC content or
+//! population genetic statistics per window. Windows of a given size start every `step`
+//! positions of each sequence (overlapping if the step is smaller than the size), and the
+//! `LastWindow` policy decides what happens at the end of a sequence, where a window would
+//! be shorter than the others.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::genome::dict::{SequenceDictionary, SequenceRecord};
+//! use bio::genome::region::Region;
+//! use bio::genome::window::{LastWindow, Tiler};
+//!
+//! let mut dict = SequenceDictionary::new();
+//! dict.push(SequenceRecord::new("chr1", 250)).unwrap();
+//! dict.push(SequenceRecord::new("chr2", 80)).unwrap();
+//!
+//! let tiler = Tiler::new(100).step(50).last_window(LastWindow::Drop);
+//! let windows: Vec<Region> = tiler.tile(&dict).collect();
+//! assert_eq!(
+//!     windows,
+//!     [
+//!         Region::new("chr1", 0, 100),
+//!         Region::new("chr1", 50, 150),
+//!         Region::new("chr1", 100, 200),
+//!         Region::new("chr1", 150, 250),
+//!     ]
+//! );
+//! ```
+
+use genome::dict::SequenceDictionary;
+use genome::region::Region;
+
+/// What to do at the end of a sequence, where a window would be shorter than the window size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LastWindow {
+    /// Keep the shorter windows, ending at the sequence end.
+    #[default]
+    Truncate,
+    /// Skip the shorter windows, such that all windows have the same size.
+    Drop,
+    /// Replace the shorter windows by one full-size window ending at the sequence end, which
+    /// overlaps the previous window. Sequences shorter than the window size get one window
+    /// covering them.
+    Shift,
+    /// Extend the last full-size window to the sequence end. Sequences shorter than the window
+    /// size get one window covering them.
+    Extend,
+}
+
+/// Generator of windows of a given size and step over the sequences of a genome.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tiler {
+    size: u64,
+    step: u64,
+    last: LastWindow,
+}
+
+impl Tiler {
+    /// Tile with adjacent windows of the given size, truncating the last window of each
+    /// sequence.
+    pub fn new(size: u64) -> Self {
+        assert!(size > 0, "Expecting positive window size.");
+        Tiler {
+            size,
+            step: size,
+            last: LastWindow::default(),
+        }
+    }
+
+    /// Distance between the starts of consecutive windows (default: the window size).
+    pub fn step(mut self, step: u64) -> Self {
+        assert!(step > 0, "Expecting positive window step.");
+        self.step = step;
+        self
+    }
+
+    /// Policy for the windows at the end of a sequence (default: `LastWindow::Truncate`).
+    pub fn last_window(mut self, last: LastWindow) -> Self {
+        self.last = last;
+        self
+    }
+
+    /// The windows of the sequence with the given name and length.
+    pub fn windows(&self, contig: &str, len: u64) -> Windows {
+        Windows {
+            contig: contig.to_owned(),
+            len,
+            tiler: *self,
+            start: 0,
+            done: false,
+        }
+    }
+
+    /// The windows of all sequences of the dictionary, in the order of the dictionary.
+    pub fn tile<'a>(&self, dict: &'a SequenceDictionary) -> impl Iterator<Item = Region> + 'a {
+        let tiler = *self;
+        dict.iter()
+            .flat_map(move |record| tiler.windows(&record.name, record.len))
+    }
+}
+
+/// Iterator over the windows of a sequence.
+#[derive(Clone, Debug)]
+pub struct Windows {
+    contig: String,
+    len: u64,
+    tiler: Tiler,
+    start: u64,
+    done: bool,
+}
+
+impl Iterator for Windows {
+    type Item = Region;
+
+    fn next(&mut self) -> Option<Region> {
+        if self.done || self.start >= self.len {
+            return None;
+        }
+        let Tiler { size, step, last } = self.tiler;
+        let start = self.start;
+        let end = start.saturating_add(size);
+        self.start = start.saturating_add(step);
+
+        if end <= self.len {
+            let next_is_shorter =
+                self.start < self.len && self.start.saturating_add(size) > self.len;
+            if last == LastWindow::Extend && next_is_shorter {
+                self.done = true;
+                return Some(Region::new(&self.contig, start, self.len));
+            }
+            return Some(Region::new(&self.contig, start, end));
+        }
+
+        match last {
+            LastWindow::Truncate => Some(Region::new(&self.contig, start, self.len)),
+            LastWindow::Drop => {
+                self.done = true;
+                None
+            }
+            LastWindow::Shift => {
+                self.done = true;
+                let shifted = self.len.saturating_sub(size);
+                // the shifted window equals the previous one if that ended at the sequence end
+                if start > 0 && shifted == start - step {
+                    None
+                } else {
+                    Some(Region::new(&self.contig, shifted, self.len))
+                }
+            }
+            LastWindow::Extend => {
+                self.done = true;
+                Some(Region::new(&self.contig, start, self.len))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genome::dict::SequenceRecord;
+
+    fn ranges(tiler: Tiler, len: u64) -> Vec<(u64, u64)> {
+        tiler
+            .windows("chr1", len)
+            .map(|window| (window.start, window.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_adjacent() {
+        let tiler = Tiler::new(10);
+        assert_eq!(ranges(tiler, 25), [(0, 10), (10, 20), (20, 25)]);
+        assert_eq!(ranges(tiler, 20), [(0, 10), (10, 20)]);
+        assert_eq!(ranges(tiler, 0), []);
+        let tiler = tiler.last_window(LastWindow::Drop);
+        assert_eq!(ranges(tiler, 25), [(0, 10), (10, 20)]);
+        assert_eq!(ranges(tiler, 5), []);
+        let tiler = tiler.last_window(LastWindow::Shift);
+        assert_eq!(ranges(tiler, 25), [(0, 10), (10, 20), (15, 25)]);
+        assert_eq!(ranges(tiler, 20), [(0, 10), (10, 20)]);
+        assert_eq!(ranges(tiler, 5), [(0, 5)]);
+        let tiler = tiler.last_window(LastWindow::Extend);
+        assert_eq!(ranges(tiler, 25), [(0, 10), (10, 25)]);
+        assert_eq!(ranges(tiler, 20), [(0, 10), (10, 20)]);
+        assert_eq!(ranges(tiler, 5), [(0, 5)]);
+    }
+
+    #[test]
+    fn test_step() {
+        let tiler = Tiler::new(10).step(4);
+        assert_eq!(
+            ranges(tiler, 22),
+            [(0, 10), (4, 14), (8, 18), (12, 22), (16, 22), (20, 22)]
+        );
+        let tiler = tiler.last_window(LastWindow::Shift);
+        assert_eq!(ranges(tiler, 20), [(0, 10), (4, 14), (8, 18), (10, 20)]);
+        assert_eq!(ranges(tiler, 22), [(0, 10), (4, 14), (8, 18), (12, 22)]);
+        let tiler = tiler.last_window(LastWindow::Extend);
+        assert_eq!(ranges(tiler, 20), [(0, 10), (4, 14), (8, 20)]);
+
+        // gaps between windows
+        let tiler = Tiler::new(5).step(10).last_window(LastWindow::Drop);
+        assert_eq!(ranges(tiler, 33), [(0, 5), (10, 15), (20, 25)]);
+    }
+
+    #[test]
+    fn test_tile() {
+        let mut dict = SequenceDictionary::new();
+        dict.push(SequenceRecord::new("chr1", 15)).unwrap();
+        dict.push(SequenceRecord::new("chr2", 0)).unwrap();
+        dict.push(SequenceRecord::new("chr3", 7)).unwrap();
+        let windows: Vec<_> = Tiler::new(10).tile(&dict).collect();
+        assert_eq!(
+            windows,
+            [
+                Region::new("chr1", 0, 10),
+                Region::new("chr1", 10, 15),
+                Region::new("chr3", 0, 7),
+            ]
+        );
+    }
+}
@@ -0,0 +1,582 @@
+//! AGP (v2.0 and v2.1) format reading and writing. AGP files describe how objects (e.g.
+//! scaffolds or chromosomes) are composed of components (e.g. contigs) and gaps, see
+//! <https://www.ncbi.nlm.nih.gov/assembly/agp/AGP_Specification/>.
+//!
+//! Coordinates are 1-based and inclusive, as in the file. Records can be validated against the
+//! lengths of the components and objects, and converted to and from the layouts of
+//! `seq_analysis::scaffold`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::agp;
+//! use bio::seq_analysis::scaffold;
+//! use std::collections::HashMap;
+//!
+//! let file = b"##agp-version\t2.1\n\
+//! scaf1\t1\t4\t1\tW\tscaf1_1\t1\t4\t+\n\
+//! scaf1\t5\t10\t2\tN\t6\tscaffold\tyes\tpaired-ends\n\
+//! scaf1\t11\t19\t3\tW\tctg9\t3\t11\t+\n";
+//! let records: Vec<_> = agp::Reader::new(&file[..])
+//!     .records()
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(records.len(), 3);
+//!
+//! let mut lengths = HashMap::new();
+//! lengths.insert("scaf1_1".to_owned(), 4);
+//! lengths.insert("ctg9".to_owned(), 12);
+//! agp::validate(&records, &lengths).unwrap();
+//!
+//! // layouts from splitting scaffolds at gaps can be written as AGP
+//! let (_, layout) = scaffold::split("scaf2", b"ACGTNNNNNNGGCC", 5);
+//! let mut writer = agp::Writer::new(Vec::new());
+//! for record in agp::from_layout(&layout) {
+//!     writer.write(&record).unwrap();
+//! }
+//! let written = String::from_utf8(writer.into_inner()).unwrap();
+//! assert!(written.starts_with("##agp-version\t2.1\nscaf2\t1\t4\t1\tW\tscaf2_1\t1\t4\t+\n"));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use seq_analysis::scaffold::{Part, PartKind};
+
+quick_error! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum AgpError {
+        UnknownComponent(object: String, id: String) {
+            description("unknown component")
+            display("component {} of object {} has unknown length", id, object)
+        }
+        ComponentOutOfBounds(object: String, id: String, end: u64, len: u64) {
+            description("component range exceeds component length")
+            display("component {} of object {} ends at {}, but has length {}", id, object, end, len)
+        }
+        LengthMismatch(object: String, part_number: u64) {
+            description("part length does not match its object range")
+            display("length of part {} of object {} does not match its object range", part_number, object)
+        }
+        NotContiguous(object: String, part_number: u64) {
+            description("parts are not contiguous")
+            display("part {} of object {} does not continue the previous part", part_number, object)
+        }
+        Unsupported(object: String, part_number: u64) {
+            description("part cannot be converted to a scaffold layout")
+            display("part {} of object {} is not a complete forward component", part_number, object)
+        }
+    }
+}
+
+/// Orientation of a component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Orientation {
+    Forward,
+    Reverse,
+    /// Unknown orientation (`?`, or `0` in AGP v2.0).
+    Unknown,
+    /// Irrelevant orientation (`na`), e.g. for components that are whole objects.
+    Na,
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Orientation::Forward => "+",
+            Orientation::Reverse => "-",
+            Orientation::Unknown => "?",
+            Orientation::Na => "na",
+        })
+    }
+}
+
+/// A component or gap of an object.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Component {
+    Sequence {
+        /// The component type, e.g. `W` for WGS contigs or `F` for finished sequences.
+        component_type: char,
+        id: String,
+        start: u64,
+        end: u64,
+        orientation: Orientation,
+    },
+    Gap {
+        /// `N` for gaps of specified, `U` for gaps of unknown length.
+        component_type: char,
+        len: u64,
+        /// The gap type, e.g. `scaffold`, `contig` or `centromere`.
+        gap_type: String,
+        linkage: bool,
+        /// The linkage evidence, e.g. `paired-ends` (empty for `na`).
+        evidence: Vec<String>,
+    },
+}
+
+/// An AGP record, i.e. a line describing one part of an object.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub object: String,
+    pub object_start: u64,
+    pub object_end: u64,
+    pub part_number: u64,
+    pub component: Component,
+}
+
+impl Record {
+    /// Length of the part in the object.
+    pub fn len(&self) -> u64 {
+        self.object_end + 1 - self.object_start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_gap(&self) -> bool {
+        match self.component {
+            Component::Gap { .. } => true,
+            Component::Sequence { .. } => false,
+        }
+    }
+}
+
+fn invalid_data(msg: &str, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", msg, line.trim_end()),
+    )
+}
+
+fn parse_number(field: &str, line: &str) -> io::Result<u64> {
+    field
+        .parse()
+        .map_err(|_| invalid_data("Invalid number in AGP record", line))
+}
+
+/// Parse a line of an AGP file.
+pub fn parse_record(line: &str) -> io::Result<Record> {
+    let fields: Vec<_> = line.trim_end_matches(['\n', '\r']).split('\t').collect();
+    if fields.len() < 9 {
+        return Err(invalid_data("Expecting nine fields in AGP record", line));
+    }
+    let mut component_type = fields[4].chars();
+    let component_type = match (component_type.next(), component_type.next()) {
+        (Some(c), None) => c,
+        _ => return Err(invalid_data("Invalid component type", line)),
+    };
+    let component = match component_type {
+        'N' | 'U' => Component::Gap {
+            component_type,
+            len: parse_number(fields[5], line)?,
+            gap_type: fields[6].to_owned(),
+            linkage: match fields[7] {
+                "yes" => true,
+                "no" => false,
+                _ => return Err(invalid_data("Invalid linkage", line)),
+            },
+            evidence: match fields[8] {
+                "na" | "" => Vec::new(),
+                evidence => evidence.split(';').map(str::to_owned).collect(),
+            },
+        },
+        _ => Component::Sequence {
+            component_type,
+            id: fields[5].to_owned(),
+            start: parse_number(fields[6], line)?,
+            end: parse_number(fields[7], line)?,
+            orientation: match fields[8] {
+                "+" => Orientation::Forward,
+                "-" => Orientation::Reverse,
+                "?" | "0" => Orientation::Unknown,
+                "na" => Orientation::Na,
+                _ => return Err(invalid_data("Invalid orientation", line)),
+            },
+        },
+    };
+    let record = Record {
+        object: fields[0].to_owned(),
+        object_start: parse_number(fields[1], line)?,
+        object_end: parse_number(fields[2], line)?,
+        part_number: parse_number(fields[3], line)?,
+        component,
+    };
+    if record.object_start == 0 || record.object_end < record.object_start {
+        return Err(invalid_data("Invalid object range", line));
+    }
+    Ok(record)
+}
+
+/// An AGP reader.
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    inner: io::BufReader<R>,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::File::open(path).map(Reader::new)
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given reader.
+    pub fn new(reader: R) -> Self {
+        Reader {
+            inner: io::BufReader::new(reader),
+        }
+    }
+
+    /// Iterate over all records, skipping comment and empty lines.
+    pub fn records(self) -> impl Iterator<Item = io::Result<Record>> {
+        self.inner.lines().filter_map(|line| match line {
+            Ok(ref line) if line.starts_with('#') || line.trim().is_empty() => None,
+            Ok(line) => Some(parse_record(&line)),
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
+
+/// An AGP writer. The version header (AGP v2.1) is written before the first record.
+#[derive(Debug)]
+pub struct Writer<W: io::Write> {
+    inner: W,
+    header: bool,
+}
+
+impl Writer<fs::File> {
+    /// Write to a given file path.
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::File::create(path).map(Writer::new)
+    }
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Write to a given writer.
+    pub fn new(writer: W) -> Self {
+        Writer {
+            inner: writer,
+            header: false,
+        }
+    }
+
+    /// Write a record.
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        if !self.header {
+            self.inner.write_all(b"##agp-version\t2.1\n")?;
+            self.header = true;
+        }
+        write!(
+            self.inner,
+            "{}\t{}\t{}\t{}\t",
+            record.object, record.object_start, record.object_end, record.part_number
+        )?;
+        match record.component {
+            Component::Sequence {
+                component_type,
+                ref id,
+                start,
+                end,
+                orientation,
+            } => writeln!(
+                self.inner,
+                "{}\t{}\t{}\t{}\t{}",
+                component_type, id, start, end, orientation
+            ),
+            Component::Gap {
+                component_type,
+                len,
+                ref gap_type,
+                linkage,
+                ref evidence,
+            } => writeln!(
+                self.inner,
+                "{}\t{}\t{}\t{}\t{}",
+                component_type,
+                len,
+                gap_type,
+                if linkage { "yes" } else { "no" },
+                if evidence.is_empty() {
+                    "na".to_owned()
+                } else {
+                    evidence.join(";")
+                }
+            ),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Validate records against the given component lengths: the parts of each object have to be
+/// numbered consecutively from 1 and be contiguous from position 1, part lengths have to match
+/// their object ranges, and components have to lie within their sequences.
+pub fn validate(
+    records: &[Record],
+    component_lengths: &HashMap<String, u64>,
+) -> Result<(), AgpError> {
+    let mut last: HashMap<&str, (u64, u64)> = HashMap::new();
+    for record in records {
+        let object = &record.object;
+        let (part_number, end) = last.get(object.as_str()).cloned().unwrap_or((0, 0));
+        if record.part_number != part_number + 1 || record.object_start != end + 1 {
+            return Err(AgpError::NotContiguous(object.clone(), record.part_number));
+        }
+        let len = match record.component {
+            Component::Sequence {
+                ref id, start, end, ..
+            } => {
+                let component_len = *component_lengths
+                    .get(id)
+                    .ok_or_else(|| AgpError::UnknownComponent(object.clone(), id.clone()))?;
+                if end > component_len {
+                    return Err(AgpError::ComponentOutOfBounds(
+                        object.clone(),
+                        id.clone(),
+                        end,
+                        component_len,
+                    ));
+                }
+                (end + 1).saturating_sub(start.max(1))
+            }
+            Component::Gap { len, .. } => len,
+        };
+        if len != record.len() {
+            return Err(AgpError::LengthMismatch(object.clone(), record.part_number));
+        }
+        last.insert(object, (record.part_number, record.object_end));
+    }
+    Ok(())
+}
+
+/// Lengths of the objects described by the given records.
+pub fn object_lengths(records: &[Record]) -> HashMap<String, u64> {
+    let mut lengths = HashMap::new();
+    for record in records {
+        let len = lengths.entry(record.object.clone()).or_insert(0);
+        *len = (*len).max(record.object_end);
+    }
+    lengths
+}
+
+/// AGP records of scaffold layouts. Contigs become WGS components (`W`) in forward orientation,
+/// gaps become scaffold gaps (`N`) with linkage of unspecified evidence.
+pub fn from_layout(layout: &[Part]) -> Vec<Record> {
+    let mut part_numbers: HashMap<&str, u64> = HashMap::new();
+    layout
+        .iter()
+        .map(|part| {
+            let part_number = part_numbers.entry(&part.scaffold).or_insert(0);
+            *part_number += 1;
+            Record {
+                object: part.scaffold.clone(),
+                object_start: part.start as u64 + 1,
+                object_end: part.end as u64,
+                part_number: *part_number,
+                component: match part.kind {
+                    PartKind::Contig(ref id) => Component::Sequence {
+                        component_type: 'W',
+                        id: id.clone(),
+                        start: 1,
+                        end: part.len() as u64,
+                        orientation: Orientation::Forward,
+                    },
+                    PartKind::Gap => Component::Gap {
+                        component_type: 'N',
+                        len: part.len() as u64,
+                        gap_type: "scaffold".to_owned(),
+                        linkage: true,
+                        evidence: vec!["unspecified".to_owned()],
+                    },
+                },
+            }
+        })
+        .collect()
+}
+
+/// Scaffold layouts of the given records. Fails for components that are reverse oriented or
+/// only partially used, which scaffold layouts cannot express.
+pub fn to_layout(
+    records: &[Record],
+    component_lengths: &HashMap<String, u64>,
+) -> Result<Vec<Part>, AgpError> {
+    records
+        .iter()
+        .map(|record| {
+            let kind = match record.component {
+                Component::Gap { .. } => PartKind::Gap,
+                Component::Sequence {
+                    ref id,
+                    start,
+                    end,
+                    orientation,
+                    ..
+                } => {
+                    let complete = start == 1 && component_lengths.get(id) == Some(&end);
+                    if !complete || orientation == Orientation::Reverse {
+                        return Err(AgpError::Unsupported(
+                            record.object.clone(),
+                            record.part_number,
+                        ));
+                    }
+                    PartKind::Contig(id.clone())
+                }
+            };
+            Ok(Part {
+                scaffold: record.object.clone(),
+                start: record.object_start as usize - 1,
+                end: record.object_end as usize,
+                kind,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seq_analysis::scaffold::{join, split};
+
+    const AGP: &[u8] = b"##agp-version\t2.0
+# ORGANISM: Homo sapiens
+chr1\t1\t10\t1\tF\tAC1.1\t1\t10\t+
+chr1\t11\t60\t2\tN\t50\tcentromere\tno\tna
+
+chr1\t61\t70\t3\tW\tctg2\t6\t15\t-
+chr2\t1\t5\t1\tW\tctg3\t1\t5\t0
+chr2\t6\t105\t2\tU\t100\tcontig\tyes\tpaired-ends;map
+";
+
+    fn lengths() -> HashMap<String, u64> {
+        let mut lengths = HashMap::new();
+        lengths.insert("AC1.1".to_owned(), 10);
+        lengths.insert("ctg2".to_owned(), 20);
+        lengths.insert("ctg3".to_owned(), 5);
+        lengths
+    }
+
+    fn records() -> Vec<Record> {
+        Reader::new(AGP)
+            .records()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let records = records();
+        assert_eq!(records.len(), 5);
+        assert_eq!(
+            records[2].component,
+            Component::Sequence {
+                component_type: 'W',
+                id: "ctg2".to_owned(),
+                start: 6,
+                end: 15,
+                orientation: Orientation::Reverse,
+            }
+        );
+        assert_eq!(
+            records[4].component,
+            Component::Gap {
+                component_type: 'U',
+                len: 100,
+                gap_type: "contig".to_owned(),
+                linkage: true,
+                evidence: vec!["paired-ends".to_owned(), "map".to_owned()],
+            }
+        );
+        assert!(records[1].is_gap());
+        assert_eq!(records[1].len(), 50);
+        let lengths = object_lengths(&records);
+        assert_eq!((lengths["chr1"], lengths["chr2"]), (70, 105));
+
+        assert!(parse_record("chr1\t1\t10\t1\tW\tctg\t1\t10").is_err());
+        assert!(parse_record("chr1\t1\t10\t1\tW\tctg\t1\t10\tx").is_err());
+        assert!(parse_record("chr1\t0\t10\t1\tW\tctg\t1\t10\t+").is_err());
+        assert!(parse_record("chr1\t1\t10\t1\tN\t10\tcontig\tmaybe\tna").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let records = records();
+        let mut writer = Writer::new(Vec::new());
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        let written = writer.into_inner();
+        assert!(written.starts_with(b"##agp-version\t2.1\n"));
+        assert!(String::from_utf8_lossy(&written).contains("chr2\t1\t5\t1\tW\tctg3\t1\t5\t?\n"));
+        let reread: Vec<_> = Reader::new(&written[..])
+            .records()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(reread, records);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut records = records();
+        assert_eq!(validate(&records, &lengths()), Ok(()));
+
+        let mut short = lengths();
+        short.insert("ctg2".to_owned(), 12);
+        assert_eq!(
+            validate(&records, &short),
+            Err(AgpError::ComponentOutOfBounds(
+                "chr1".to_owned(),
+                "ctg2".to_owned(),
+                15,
+                12
+            ))
+        );
+        short.remove("ctg2");
+        assert!(validate(&records, &short).is_err());
+
+        records[1].object_end = 59;
+        assert_eq!(
+            validate(&records, &lengths()),
+            Err(AgpError::LengthMismatch("chr1".to_owned(), 2))
+        );
+        records.remove(1);
+        assert_eq!(
+            validate(&records, &lengths()),
+            Err(AgpError::NotContiguous("chr1".to_owned(), 3))
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let scaffold = b"NNACGTNNNNACGTAAN";
+        let (contigs, layout) = split("s", scaffold, 2);
+        let records = from_layout(&layout);
+        let lengths: HashMap<_, _> = contigs
+            .iter()
+            .map(|(name, seq)| (name.clone(), seq.len() as u64))
+            .collect();
+        assert_eq!(validate(&records, &lengths), Ok(()));
+        assert_eq!(records[3].object_start, 11);
+        assert_eq!(records[3].part_number, 4);
+        let restored = to_layout(&records, &lengths).unwrap();
+        assert_eq!(restored, layout);
+        assert_eq!(join(&restored, &contigs).unwrap(), &scaffold[..]);
+
+        assert_eq!(
+            to_layout(&self::records(), &self::lengths()),
+            Err(AgpError::Unsupported("chr1".to_owned(), 3))
+        );
+    }
+}
@@ -33,6 +33,8 @@ use bio_types::annot;
 use bio_types::annot::loc::Loc;
 use bio_types::strand;
 
+use io::sort::{GenomicRecord, Spill};
+
 /// A BED reader.
 #[derive(Debug)]
 pub struct Reader<R: io::Read> {
@@ -231,6 +233,34 @@ impl Record {
     }
 }
 
+impl GenomicRecord for Record {
+    fn contig(&self) -> &str {
+        &self.chrom
+    }
+
+    fn pos(&self) -> u64 {
+        self.start
+    }
+}
+
+impl Spill for Record {
+    fn spill<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.chrom.spill(writer)?;
+        self.start.spill(writer)?;
+        self.end.spill(writer)?;
+        self.aux.spill(writer)
+    }
+
+    fn unspill<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        Ok(Record {
+            chrom: String::unspill(reader)?,
+            start: u64::unspill(reader)?,
+            end: u64::unspill(reader)?,
+            aux: Vec::unspill(reader)?,
+        })
+    }
+}
+
 impl<'a> From<&'a Record> for annot::contig::Contig<String, strand::Strand> {
     /// Returns a `Contig` annotation for the BED record.
     ///
@@ -0,0 +1,279 @@
+//! A thread-safe LRU cache for subsequences fetched from an indexed FASTA file, avoiding
+//! repeated disk I/O when the same reference windows are requested over and over again (e.g.
+//! by pileups or pair HMMs over overlapping regions).
+//!
+//! Sequences are cached in blocks of fixed size. Requests are answered from the cached blocks,
+//! reading only missing blocks from the underlying reader. When the cache is full, the least
+//! recently used block is evicted.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io;
+//! use bio::io::{cache::CachedIndexedReader, fasta};
+//!
+//! let fasta = b">chr1\nACGTACGTAC\nGTACGT\n";
+//! let fai = b"chr1\t16\t6\t10\t11\n";
+//! let reader = fasta::IndexedReader::new(io::Cursor::new(&fasta[..]), &fai[..]).unwrap();
+//! let cached = CachedIndexedReader::with_capacity(reader, 4, 16);
+//!
+//! assert_eq!(cached.fetch("chr1", 2, 12).unwrap(), b"GTACGTACGT");
+//! assert_eq!(cached.fetch("chr1", 4, 8).unwrap(), b"ACGT");
+//! let stats = cached.stats();
+//! assert_eq!((stats.hits, stats.misses), (1, 3));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use io::fasta;
+
+/// Default block size (bases).
+pub const DEFAULT_BLOCK_SIZE: u64 = 64 * 1024;
+/// Default maximum number of cached blocks.
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// A least recently used cache.
+#[derive(Debug)]
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    /// Keys by time of last use.
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Expecting positive cache capacity.");
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        let tick = self.tick + 1;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.1);
+        self.order.insert(tick, key.clone());
+        entry.1 = tick;
+        self.tick = tick;
+        Some(entry.0.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        if let Some((_, last_use)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&last_use);
+        } else if self.entries.len() > self.capacity {
+            let (&oldest, _) = self.order.iter().next().unwrap();
+            let evicted = self.order.remove(&oldest).unwrap();
+            self.entries.remove(&evicted);
+        }
+        self.order.insert(self.tick, key);
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Cached blocks by record index and block number.
+type BlockCache = Lru<(usize, u64), Arc<Vec<u8>>>;
+
+/// Cache usage statistics (counted in blocks).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Number of currently cached blocks.
+    pub blocks: usize,
+}
+
+/// An indexed FASTA reader with an LRU block cache, that can be shared between threads.
+#[derive(Debug)]
+pub struct CachedIndexedReader<R: io::Read + io::Seek> {
+    reader: Mutex<fasta::IndexedReader<R>>,
+    /// Record index and length by sequence name.
+    sequences: HashMap<String, (usize, u64)>,
+    block_size: u64,
+    cache: Mutex<BlockCache>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<R: io::Read + io::Seek> CachedIndexedReader<R> {
+    /// Wrap the given reader, with default block size and capacity.
+    pub fn new(reader: fasta::IndexedReader<R>) -> Self {
+        Self::with_capacity(reader, DEFAULT_BLOCK_SIZE, DEFAULT_CAPACITY)
+    }
+
+    /// Wrap the given reader, caching up to `capacity` blocks of `block_size` bases.
+    pub fn with_capacity(
+        reader: fasta::IndexedReader<R>,
+        block_size: u64,
+        capacity: usize,
+    ) -> Self {
+        assert!(block_size > 0, "Expecting positive block size.");
+        let sequences = reader
+            .index
+            .sequences()
+            .into_iter()
+            .enumerate()
+            .map(|(rid, seq)| (seq.name, (rid, seq.len)))
+            .collect();
+        CachedIndexedReader {
+            reader: Mutex::new(reader),
+            sequences,
+            block_size,
+            cache: Mutex::new(Lru::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get a block, reading it if it is not cached.
+    fn block(&self, rid: usize, len: u64, block: u64) -> io::Result<Arc<Vec<u8>>> {
+        if let Some(seq) = self.cache.lock().unwrap().get(&(rid, block)) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(seq);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let start = block * self.block_size;
+        let stop = (start + self.block_size).min(len);
+        let mut seq = Vec::with_capacity((stop - start) as usize);
+        {
+            let mut reader = self.reader.lock().unwrap();
+            reader.fetch_by_rid(rid, start, stop)?;
+            reader.read(&mut seq)?;
+        }
+        let seq = Arc::new(seq);
+        self.cache
+            .lock()
+            .unwrap()
+            .insert((rid, block), Arc::clone(&seq));
+        Ok(seq)
+    }
+
+    /// Fetch the interval `start..stop` (0-based, stop exclusive) of the given sequence.
+    pub fn fetch(&self, seq_name: &str, start: u64, stop: u64) -> io::Result<Vec<u8>> {
+        let &(rid, len) = self
+            .sequences
+            .get(seq_name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Unknown sequence name."))?;
+        if stop > len || start > stop {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "FASTA read interval was out of bounds",
+            ));
+        }
+        let mut seq = Vec::with_capacity((stop - start) as usize);
+        if start == stop {
+            return Ok(seq);
+        }
+        for block in start / self.block_size..=(stop - 1) / self.block_size {
+            let block_start = block * self.block_size;
+            let data = self.block(rid, len, block)?;
+            let from = start.saturating_sub(block_start) as usize;
+            let to = ((stop - block_start) as usize).min(data.len());
+            seq.extend_from_slice(&data[from..to]);
+        }
+        Ok(seq)
+    }
+
+    /// Fetch the whole sequence with the given name.
+    pub fn fetch_all(&self, seq_name: &str) -> io::Result<Vec<u8>> {
+        let len = self.seq_len(seq_name).unwrap_or(0);
+        self.fetch(seq_name, 0, len)
+    }
+
+    /// Length of the sequence with the given name.
+    pub fn seq_len(&self, seq_name: &str) -> Option<u64> {
+        self.sequences.get(seq_name).map(|&(_, len)| len)
+    }
+
+    /// Usage statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            blocks: self.cache.lock().unwrap().len(),
+        }
+    }
+
+    /// Return the wrapped reader.
+    pub fn into_inner(self) -> fasta::IndexedReader<R> {
+        self.reader.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const FASTA: &[u8] = b">a\nACGTACGTAC\nGTTTGGGCCC\nAAA\n>b\nTTTT\n";
+    const FAI: &[u8] = b"a\t23\t3\t10\t11\nb\t4\t32\t4\t5\n";
+
+    fn reader() -> fasta::IndexedReader<io::Cursor<&'static [u8]>> {
+        fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap()
+    }
+
+    #[test]
+    fn test_lru() {
+        let mut lru = Lru::new(2);
+        lru.insert(1, "a");
+        lru.insert(2, "b");
+        assert_eq!(lru.get(&1), Some("a"));
+        lru.insert(3, "c");
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.get(&1), Some("a"));
+        lru.insert(3, "d");
+        assert_eq!(lru.get(&3), Some("d"));
+        assert_eq!(lru.len(), 2);
+    }
+
+    #[test]
+    fn test_fetch() {
+        let mut plain = reader();
+        let cached = CachedIndexedReader::with_capacity(reader(), 3, 2);
+        for &(name, len) in &[("a", 23), ("b", 4)] {
+            for start in 0..=len {
+                for stop in start..=len {
+                    let mut expected = Vec::new();
+                    plain.fetch(name, start, stop).unwrap();
+                    plain.read(&mut expected).unwrap();
+                    assert_eq!(cached.fetch(name, start, stop).unwrap(), expected);
+                }
+            }
+        }
+        assert_eq!(cached.stats().blocks, 2);
+        assert_eq!(cached.fetch_all("b").unwrap(), b"TTTT");
+        assert!(cached.fetch("a", 0, 24).is_err());
+        assert!(cached.fetch("c", 0, 1).is_err());
+    }
+
+    #[test]
+    fn test_concurrent() {
+        let cached = CachedIndexedReader::new(reader());
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        assert_eq!(cached.fetch("a", 8, 14).unwrap(), b"ACGTTT");
+                    }
+                });
+            }
+        });
+        let stats = cached.stats();
+        assert_eq!(stats.hits + stats.misses, 40);
+        assert_eq!(stats.blocks, 1);
+        assert_eq!(cached.into_inner().index.sequences().len(), 2);
+    }
+}
@@ -20,9 +20,11 @@ use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::path::Path;
+use std::str;
 
 use csv;
 
+use error::Error;
 use utils::{Text, TextSlice};
 
 /// Maximum size of temporary buffer used for reading indexed FASTA files.
@@ -38,6 +40,8 @@ pub trait FastaRead {
 pub struct Reader<R: io::Read> {
     reader: io::BufReader<R>,
     line: String,
+    line_number: usize,
+    buffer: Vec<u8>,
 }
 
 impl Reader<fs::File> {
@@ -65,6 +69,8 @@ impl<R: io::Read> Reader<R> {
         Reader {
             reader: io::BufReader::new(reader),
             line: String::new(),
+            line_number: 0,
+            buffer: Vec::new(),
         }
     }
 
@@ -94,6 +100,72 @@ impl<R: io::Read> Reader<R> {
             error_has_occured: false,
         }
     }
+
+    /// Read the next record into an internal buffer, and return it as a record borrowing from
+    /// that buffer (`None` at the end of the file). The buffer is reused for every record,
+    /// avoiding the allocations of `read`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use bio::io::fasta::Reader;
+    /// let mut reader = Reader::new(&b">id1 desc\nACGT\nAC\n>id2\nGG\n"[..]);
+    /// let mut lengths = Vec::new();
+    /// while let Some(record) = reader.read_ref().unwrap() {
+    ///     lengths.push((record.id().to_owned(), record.seq().len()));
+    /// }
+    /// assert_eq!(lengths, [("id1".to_owned(), 6), ("id2".to_owned(), 2)]);
+    /// ```
+    pub fn read_ref(&mut self) -> io::Result<Option<RecordRef<'_>>> {
+        self.buffer.clear();
+        if self.line.is_empty() {
+            self.next_line()?;
+            if self.line.is_empty() {
+                return Ok(None);
+            }
+        }
+        if !self.line.starts_with('>') {
+            return Err(self.parse_error("expected > at record start"));
+        }
+        self.buffer
+            .extend_from_slice(self.line[1..].trim_end().as_bytes());
+        let header_len = self.buffer.len();
+        loop {
+            self.next_line()?;
+            if self.line.is_empty() || self.line.starts_with('>') {
+                break;
+            }
+            self.buffer
+                .extend_from_slice(self.line.trim_end().as_bytes());
+        }
+
+        let (header, seq) = self.buffer.split_at(header_len);
+        // valid UTF-8, since copied from a string
+        let mut header = str::from_utf8(header).unwrap().splitn(2, ' ');
+        Ok(Some(RecordRef {
+            id: header.next().unwrap_or_default(),
+            desc: header.next(),
+            seq,
+        }))
+    }
+
+    /// Replace the current line with the next one.
+    fn next_line(&mut self) -> io::Result<()> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line)? > 0 {
+            self.line_number += 1;
+        }
+        Ok(())
+    }
+
+    /// Error for invalid content in the current line.
+    fn parse_error(&self, msg: &str) -> io::Error {
+        Error::Parse {
+            format: "FASTA",
+            line: self.line_number,
+            msg: msg.to_owned(),
+        }
+        .into()
+    }
 }
 
 impl<R> FastaRead for Reader<R>
@@ -123,17 +195,14 @@ where
     fn read(&mut self, record: &mut Record) -> io::Result<()> {
         record.clear();
         if self.line.is_empty() {
-            try!(self.reader.read_line(&mut self.line));
+            try!(self.next_line());
             if self.line.is_empty() {
                 return Ok(());
             }
         }
 
         if !self.line.starts_with('>') {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Expected > at record start.",
-            ));
+            return Err(self.parse_error("expected > at record start"));
         }
         record.id = self.line[1..]
             .trim_right()
@@ -147,8 +216,7 @@ where
             .nth(1)
             .map(|s| s.to_owned());
         loop {
-            self.line.clear();
-            try!(self.reader.read_line(&mut self.line));
+            try!(self.next_line());
             if self.line.is_empty() || self.line.starts_with('>') {
                 break;
             }
@@ -669,6 +737,36 @@ impl Record {
     }
 }
 
+/// A Fasta record borrowing from the buffer of a reader (see `Reader::read_ref`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordRef<'a> {
+    id: &'a str,
+    desc: Option<&'a str>,
+    seq: &'a [u8],
+}
+
+impl<'a> RecordRef<'a> {
+    /// Return the id of the record.
+    pub fn id(&self) -> &'a str {
+        self.id
+    }
+
+    /// Return descriptions if present.
+    pub fn desc(&self) -> Option<&'a str> {
+        self.desc
+    }
+
+    /// Return the sequence of the record.
+    pub fn seq(&self) -> TextSlice<'a> {
+        self.seq
+    }
+
+    /// Copy into an owned record.
+    pub fn to_record(&self) -> Record {
+        Record::with_attrs(self.id, self.desc, self.seq)
+    }
+}
+
 /// An iterator over the records of a Fasta file.
 pub struct Records<R: io::Read> {
     reader: Reader<R>,
@@ -793,6 +891,30 @@ ATTGTTGTTTTA
         }
     }
 
+    #[test]
+    fn test_read_ref() {
+        for &file in &[FASTA_FILE, FASTA_FILE_CRLF] {
+            let mut reader = Reader::new(file);
+            let mut owned = Reader::new(file).records();
+            let mut count = 0;
+            while let Some(record) = reader.read_ref().unwrap() {
+                let expected = owned.next().unwrap().unwrap();
+                assert_eq!(record.id(), expected.id());
+                assert_eq!(record.desc(), expected.desc());
+                assert_eq!(record.seq(), expected.seq());
+                assert_eq!(record.to_record().seq(), expected.seq());
+                count += 1;
+            }
+            assert_eq!(count, 2);
+            assert!(owned.next().is_none());
+        }
+        let mut reader = Reader::new(&b"id\nACGT\n"[..]);
+        match Error::from(reader.read_ref().unwrap_err()) {
+            Error::Parse { line: 1, .. } => (),
+            err => panic!("Unexpected error {:?}", err),
+        }
+    }
+
     #[test]
     fn test_faread_trait() {
         let path = "genome.fa.gz";
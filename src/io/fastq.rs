@@ -20,6 +20,8 @@ use std::io;
 use std::io::prelude::*;
 use std::path::Path;
 
+use error::Error;
+use stats::phred::{self, QualityEncoding};
 use utils::TextSlice;
 
 /// Trait for FASTQ readers.
@@ -32,6 +34,8 @@ pub trait FastqRead {
 pub struct Reader<R: io::Read> {
     reader: io::BufReader<R>,
     sep_line: String,
+    buffer: String,
+    line_number: usize,
 }
 
 impl Reader<fs::File> {
@@ -47,6 +51,8 @@ impl<R: io::Read> Reader<R> {
         Reader {
             reader: io::BufReader::new(reader),
             sep_line: String::new(),
+            buffer: String::new(),
+            line_number: 0,
         }
     }
 
@@ -54,6 +60,50 @@ impl<R: io::Read> Reader<R> {
     pub fn records(self) -> Records<R> {
         Records { reader: self }
     }
+
+    /// Read the next record into an internal buffer, and return it as a record borrowing from
+    /// that buffer (`None` at the end of the file). The buffer is reused for every record,
+    /// avoiding the allocations of `read`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::io::fastq;
+    ///
+    /// let mut reader = fastq::Reader::new(&b"@id1 desc\nACGT\n+\nIIII\n@id2\nGG\n+\nJJ\n"[..]);
+    /// let mut bases = 0;
+    /// while let Some(record) = reader.read_ref().unwrap() {
+    ///     assert_eq!(record.seq().len(), record.qual().len());
+    ///     bases += record.seq().len();
+    /// }
+    /// assert_eq!(bases, 6);
+    /// ```
+    pub fn read_ref(&mut self) -> io::Result<Option<RecordRef<'_>>> {
+        self.buffer.clear();
+        if read_line(&mut self.reader, &mut self.buffer, &mut self.line_number)? == 0 {
+            return Ok(None);
+        }
+        let header_line = self.line_number;
+        if !self.buffer.starts_with('@') {
+            return Err(parse_error(header_line, "expected @ at record start"));
+        }
+        let header_end = self.buffer.len();
+        read_line(&mut self.reader, &mut self.buffer, &mut self.line_number)?;
+        let seq_end = self.buffer.len();
+        read_line(&mut self.reader, &mut self.buffer, &mut self.line_number)?;
+        self.buffer.truncate(seq_end);
+        if read_line(&mut self.reader, &mut self.buffer, &mut self.line_number)? == 0 {
+            return Err(parse_error(header_line, INCOMPLETE_RECORD));
+        }
+
+        let mut header = self.buffer[1..header_end].trim_end().splitn(2, ' ');
+        Ok(Some(RecordRef {
+            id: header.next().unwrap_or_default(),
+            desc: header.next(),
+            seq: self.buffer[header_end..seq_end].trim_end().as_bytes(),
+            qual: self.buffer[seq_end..].trim_end().as_bytes(),
+        }))
+    }
 }
 
 impl<R> FastqRead for Reader<R>
@@ -67,14 +117,16 @@ where
         record.clear();
 
         let mut header = String::new();
-        try!(self.reader.read_line(&mut header));
+        try!(read_line(
+            &mut self.reader,
+            &mut header,
+            &mut self.line_number
+        ));
 
         if !header.is_empty() {
+            let header_line = self.line_number;
             if !header.starts_with('@') {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Expected @ at record start.",
-                ));
+                return Err(parse_error(header_line, "expected @ at record start"));
             }
             record.id = header[1..]
                 .trim_right()
@@ -87,16 +139,23 @@ where
                 .splitn(2, ' ')
                 .nth(1)
                 .map(|s| s.to_owned());
-            try!(self.reader.read_line(&mut record.seq));
-            try!(self.reader.read_line(&mut self.sep_line));
-            try!(self.reader.read_line(&mut record.qual));
+            try!(read_line(
+                &mut self.reader,
+                &mut record.seq,
+                &mut self.line_number
+            ));
+            try!(read_line(
+                &mut self.reader,
+                &mut self.sep_line,
+                &mut self.line_number
+            ));
+            try!(read_line(
+                &mut self.reader,
+                &mut record.qual,
+                &mut self.line_number
+            ));
             if record.qual.is_empty() {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Incomplete record. Each FastQ record has to consist \
-                     of 4 lines: header, sequence, separator and \
-                     qualities.",
-                ));
+                return Err(parse_error(header_line, INCOMPLETE_RECORD));
             }
         }
 
@@ -104,6 +163,32 @@ where
     }
 }
 
+const INCOMPLETE_RECORD: &str = "incomplete record, each FastQ record has to consist of 4 \
+                                 lines: header, sequence, separator and qualities";
+
+/// Append the next line to the given buffer, counting lines.
+fn read_line<B: io::BufRead>(
+    reader: &mut B,
+    buffer: &mut String,
+    line_number: &mut usize,
+) -> io::Result<usize> {
+    let len = reader.read_line(buffer)?;
+    if len > 0 {
+        *line_number += 1;
+    }
+    Ok(len)
+}
+
+/// Error for invalid content of the record starting in the given line.
+fn parse_error(line: usize, msg: &str) -> io::Error {
+    Error::Parse {
+        format: "FastQ",
+        line,
+        msg: msg.to_owned(),
+    }
+    .into()
+}
+
 /// A FastQ record.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Record {
@@ -184,6 +269,36 @@ impl Record {
         self.qual.trim_right().as_bytes()
     }
 
+    /// Expected number of errors of the record, i.e. the sum of the error probabilities of
+    /// its bases, assuming Sanger encoded qualities.
+    pub fn expected_errors(&self) -> f64 {
+        phred::expected_errors(self.qual(), QualityEncoding::Sanger)
+    }
+
+    /// Check if the expected number of errors does not exceed `max_ee`
+    /// (as `-fastq_maxee` in USEARCH).
+    pub fn passes_max_ee(&self, max_ee: f64) -> bool {
+        self.expected_errors() <= max_ee
+    }
+
+    /// Check if the expected number of errors per base does not exceed `max_ee_rate`
+    /// (as `-fastq_maxee_rate` in USEARCH). Empty records pass.
+    pub fn passes_max_ee_rate(&self, max_ee_rate: f64) -> bool {
+        phred::mean_error_prob(self.qual(), QualityEncoding::Sanger) <= max_ee_rate
+    }
+
+    /// Return a copy of the record truncated to the longest prefix whose expected number
+    /// of errors does not exceed `max_ee` (as `-fastq_truncee` in USEARCH).
+    pub fn trim_max_ee(&self, max_ee: f64) -> Record {
+        let len = phred::max_ee_prefix_len(self.qual(), max_ee, QualityEncoding::Sanger);
+        Record::with_attrs(
+            self.id(),
+            self.desc(),
+            &self.seq()[..len],
+            &self.qual()[..len],
+        )
+    }
+
     /// Clear the record.
     fn clear(&mut self) {
         self.id.clear();
@@ -206,6 +321,42 @@ impl fmt::Display for Record {
     }
 }
 
+/// A FastQ record borrowing from the buffer of a reader (see `Reader::read_ref`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordRef<'a> {
+    id: &'a str,
+    desc: Option<&'a str>,
+    seq: &'a [u8],
+    qual: &'a [u8],
+}
+
+impl<'a> RecordRef<'a> {
+    /// Return the id of the record.
+    pub fn id(&self) -> &'a str {
+        self.id
+    }
+
+    /// Return descriptions if present.
+    pub fn desc(&self) -> Option<&'a str> {
+        self.desc
+    }
+
+    /// Return the sequence of the record.
+    pub fn seq(&self) -> TextSlice<'a> {
+        self.seq
+    }
+
+    /// Return the base qualities of the record.
+    pub fn qual(&self) -> &'a [u8] {
+        self.qual
+    }
+
+    /// Copy into an owned record.
+    pub fn to_record(&self) -> Record {
+        Record::with_attrs(self.id, self.desc, self.seq, self.qual)
+    }
+}
+
 /// An iterator over the records of a FastQ file.
 #[derive(Debug)]
 pub struct Records<R: io::Read> {
@@ -306,6 +457,29 @@ IIIIIIJJJJJJ
         }
     }
 
+    #[test]
+    fn test_read_ref() {
+        let file = [FASTQ_FILE, b"@id2\r\nAC\r\n+id2\r\nII\r\n"].concat();
+        let mut reader = Reader::new(&file[..]);
+        {
+            let record = reader.read_ref().unwrap().unwrap();
+            assert_eq!(record.id(), "id");
+            assert_eq!(record.desc(), Some("desc"));
+            assert_eq!(record.seq(), b"ACCGTAGGCTGA");
+            assert_eq!(record.qual(), b"IIIIIIJJJJJJ");
+        }
+        let record = reader.read_ref().unwrap().unwrap().to_record();
+        assert_eq!(record.check(), Ok(()));
+        assert_eq!((record.id(), record.desc()), ("id2", None));
+        assert_eq!((record.seq(), record.qual()), (&b"AC"[..], &b"II"[..]));
+        assert_eq!(reader.read_ref().unwrap(), None);
+
+        let mut reader = Reader::new(&b"@id\nACGT\n+\n"[..]);
+        assert!(reader.read_ref().is_err());
+        let mut reader = Reader::new(&b"id\nACGT\n+\nIIII\n"[..]);
+        assert!(reader.read_ref().is_err());
+    }
+
     #[test]
     fn test_fqread_trait() {
         let path = "reads.fq.gz";
@@ -325,6 +499,22 @@ IIIIIIJJJJJJ
         assert_eq!(record.qual(), b"IIIIIIJJJJJJ");
     }
 
+    #[test]
+    fn test_max_ee() {
+        let record = Record::with_attrs("id", None, b"ACGTACGT", b"IIII++II");
+        assert_relative_eq!(record.expected_errors(), 0.2006, epsilon = 1e-9);
+        assert!(record.passes_max_ee(0.5));
+        assert!(!record.passes_max_ee(0.1));
+        assert!(record.passes_max_ee_rate(0.03));
+        assert!(!record.passes_max_ee_rate(0.02));
+
+        let trimmed = record.trim_max_ee(0.15);
+        assert_eq!(trimmed.id(), "id");
+        assert_eq!(trimmed.seq(), b"ACGTA");
+        assert_eq!(trimmed.qual(), b"IIII+");
+        assert_eq!(trimmed.check(), Ok(()));
+    }
+
     #[test]
     fn test_record_with_attrs() {
         let record = Record::with_attrs("id_str", Some("desc"), b"ATGCGGG", b"QQQQQQQ");
@@ -0,0 +1,177 @@
+//! A common interface for fetching reference subsequences, such that algorithms can be written
+//! independently of the backend that stores the reference.
+//!
+//! `FetchSequence` is implemented by indexed FASTA readers (`fasta::IndexedReader`), their
+//! cached variant (`cache::CachedIndexedReader`) and in-memory sequences
+//! (`HashMap<String, Vec<u8>>`).
+//! Note that the trait methods have to be called with the trait in scope, and, for readers that
+//! have inherent methods of the same name, with the fully qualified syntax, e.g.
+//! `FetchSequence::fetch(&mut reader, "chr1", 0, 10)`.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::io;
+//! use bio::io::fasta;
+//! use bio::io::fetch::FetchSequence;
+//!
+//! /// GC content of a region, for any reference backend.
+//! fn gc<F: FetchSequence>(reference: &mut F, name: &str, start: u64, end: u64) -> io::Result<f64> {
+//!     let seq = reference.fetch(name, start, end)?;
+//!     let gc = seq.iter().filter(|&&b| b == b'G' || b == b'C').count();
+//!     Ok(gc as f64 / seq.len() as f64)
+//! }
+//!
+//! let mut in_memory = HashMap::new();
+//! in_memory.insert("chr1".to_owned(), b"ACGTGGCCAT".to_vec());
+//! let fasta = b">chr1\nACGTGGCCAT\n";
+//! let mut indexed =
+//!     fasta::IndexedReader::new(io::Cursor::new(&fasta[..]), &b"chr1\t10\t6\t10\t11\n"[..])
+//!         .unwrap();
+//!
+//! assert_eq!(gc(&mut in_memory, "chr1", 2, 8).unwrap(), gc(&mut indexed, "chr1", 2, 8).unwrap());
+//! assert_eq!(in_memory.seq_len("chr1"), Some(10));
+//! assert_eq!(in_memory.fetch_region("chr1:3-8").unwrap(), b"GTGGCC");
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+
+use genome::region::Region;
+use io::cache::CachedIndexedReader;
+use io::fasta;
+
+/// A source of reference sequences that allows to fetch subsequences.
+pub trait FetchSequence {
+    /// Fetch the interval `start..end` (0-based, end exclusive) of the sequence with the given
+    /// name. Returns an error for unknown sequences and intervals out of bounds.
+    fn fetch(&mut self, name: &str, start: u64, end: u64) -> io::Result<Vec<u8>>;
+
+    /// Length of the sequence with the given name, `None` if it is unknown.
+    fn seq_len(&self, name: &str) -> Option<u64>;
+
+    /// Fetch the whole sequence with the given name.
+    fn fetch_all(&mut self, name: &str) -> io::Result<Vec<u8>> {
+        let len = self
+            .seq_len(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Unknown sequence name."))?;
+        self.fetch(name, 0, len)
+    }
+
+    /// Fetch the region given by a samtools-style region string with 1-based, inclusive
+    /// coordinates, e.g. `chr1:1,001-2,000` or `chr1` (see `Region::parse`).
+    fn fetch_region(&mut self, region: &str) -> io::Result<Vec<u8>> {
+        let region = Region::parse(region, |name| self.seq_len(name))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.fetch(&region.contig, region.start, region.end)
+    }
+}
+
+impl<R: io::Read + io::Seek> FetchSequence for fasta::IndexedReader<R> {
+    fn fetch(&mut self, name: &str, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        let mut seq = Vec::new();
+        fasta::IndexedReader::fetch(self, name, start, end)?;
+        self.read(&mut seq)?;
+        Ok(seq)
+    }
+
+    fn seq_len(&self, name: &str) -> Option<u64> {
+        self.index
+            .sequences()
+            .into_iter()
+            .find(|seq| seq.name == name)
+            .map(|seq| seq.len)
+    }
+}
+
+impl<R: io::Read + io::Seek> FetchSequence for CachedIndexedReader<R> {
+    fn fetch(&mut self, name: &str, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        CachedIndexedReader::fetch(self, name, start, end)
+    }
+
+    fn seq_len(&self, name: &str) -> Option<u64> {
+        CachedIndexedReader::seq_len(self, name)
+    }
+}
+
+/// Shared references to cached readers can be used from multiple threads.
+impl<R: io::Read + io::Seek> FetchSequence for &CachedIndexedReader<R> {
+    fn fetch(&mut self, name: &str, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        CachedIndexedReader::fetch(self, name, start, end)
+    }
+
+    fn seq_len(&self, name: &str) -> Option<u64> {
+        CachedIndexedReader::seq_len(self, name)
+    }
+}
+
+impl FetchSequence for HashMap<String, Vec<u8>> {
+    fn fetch(&mut self, name: &str, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        let seq = self
+            .get(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Unknown sequence name."))?;
+        if start > end || end > seq.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Read interval was out of bounds",
+            ));
+        }
+        Ok(seq[start as usize..end as usize].to_vec())
+    }
+
+    fn seq_len(&self, name: &str) -> Option<u64> {
+        self.get(name).map(|seq| seq.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FASTA: &[u8] = b">a\nACGTACGTAC\nGTTTGGGCCC\nAAA\n>b\nTTTT\n";
+    const FAI: &[u8] = b"a\t23\t3\t10\t11\nb\t4\t32\t4\t5\n";
+
+    /// Check that the given backend agrees with the in-memory sequences.
+    fn check<F: FetchSequence>(mut reference: F) {
+        let mut expected = HashMap::new();
+        expected.insert("a".to_owned(), b"ACGTACGTACGTTTGGGCCCAAA".to_vec());
+        expected.insert("b".to_owned(), b"TTTT".to_vec());
+        for name in &["a", "b"] {
+            let len = expected.seq_len(name).unwrap();
+            assert_eq!(reference.seq_len(name), Some(len));
+            for start in 0..=len {
+                for end in start..=len {
+                    assert_eq!(
+                        reference.fetch(name, start, end).unwrap(),
+                        expected.fetch(name, start, end).unwrap()
+                    );
+                }
+            }
+            assert_eq!(reference.fetch_all(name).unwrap(), expected[*name]);
+            assert!(reference.fetch(name, 0, len + 1).is_err());
+        }
+        assert_eq!(reference.seq_len("c"), None);
+        assert!(reference.fetch("c", 0, 0).is_err());
+        assert!(reference.fetch_all("c").is_err());
+        assert_eq!(reference.fetch_region("a:11-14").unwrap(), b"GTTT");
+        assert_eq!(reference.fetch_region("a:20").unwrap(), b"CAAA");
+        assert_eq!(reference.fetch_region("b").unwrap(), b"TTTT");
+        assert!(reference.fetch_region("c").is_err());
+        assert!(reference.fetch_region("a:0-3").is_err());
+    }
+
+    #[test]
+    fn test_backends() {
+        let reader = || fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap();
+        check(reader());
+        check(CachedIndexedReader::with_capacity(reader(), 4, 2));
+        let cached = CachedIndexedReader::new(reader());
+        check(&cached);
+        let mut memory = HashMap::new();
+        for name in &["a", "b"] {
+            memory.insert(name.to_string(), cached.fetch_all(name).unwrap());
+        }
+        check(memory);
+    }
+}
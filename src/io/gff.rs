@@ -29,6 +29,8 @@ use csv;
 
 use bio_types::strand::Strand;
 
+use io::sort::GenomicRecord;
+
 /// `GffType`
 ///
 /// We have three format in the GFF family.
@@ -351,6 +353,16 @@ impl Record {
     }
 }
 
+impl GenomicRecord for Record {
+    fn contig(&self) -> &str {
+        &self.seqname
+    }
+
+    fn pos(&self) -> u64 {
+        self.start
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,562 @@
+//! Merging of overlapping paired-end reads into single fragments, as done by FLASH or PEAR.
+//!
+//! If the insert of a read pair is shorter than the sum of the read lengths, the end of the
+//! first read overlaps the reverse complement of the second read. A `PairMerger` finds this
+//! overlap with a banded alignment of the read ends (such that indels are tolerated), and
+//! resolves the overlapping bases by their qualities: agreeing calls get a combined, higher
+//! quality, while disagreeing calls are resolved in favour of the higher quality with a
+//! correspondingly lowered quality (see `stats::phred`). Pairs whose overlap is too short or
+//! contains too many differences stay unmerged. `MergeStats` collects merge rates as well as
+//! overlap and fragment length distributions.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alphabets::dna;
+//! use bio::io::fastq;
+//! use bio::io::merge_pairs::{MergeStats, PairMerger};
+//!
+//! // a 30 bp fragment, sequenced with 20 bp reads from both ends
+//! let fragment = b"ACGTTGCAAGGCTTACCGATGGATCCTAGT";
+//! let r1 = fastq::Record::with_attrs("pair", None, &fragment[..20], &[b'5'; 20]);
+//! let r2 = fastq::Record::with_attrs("pair", None, &dna::revcomp(&fragment[10..]), &[b'5'; 20]);
+//!
+//! let merger = PairMerger::new().min_overlap(8);
+//! let mut stats = MergeStats::new();
+//! let result = merger.merge(&r1, &r2);
+//! stats.add(&result);
+//!
+//! let merged = result.unwrap();
+//! assert_eq!(merged.overlap, 10);
+//! assert_eq!(merged.record.seq(), &fragment[..]);
+//! // both reads agree on the overlapping bases, hence these get a higher quality
+//! assert!(merged.record.qual()[15] > b'5');
+//! assert_eq!(stats.merge_rate(), 1.0);
+//! ```
+
+use std::cmp;
+use std::io;
+
+use alignment::pairwise::{banded, Scoring};
+use alignment::AlignmentOperation;
+use alphabets::dna;
+use io::fastq;
+use stats::phred::{
+    combine_agreeing, combine_disagreeing, expected_errors, prob_to_qual, qual_to_prob,
+    QualityEncoding,
+};
+use stats::Prob;
+
+/// Default minimum number of overlapping bases (as in PEAR).
+pub const DEFAULT_MIN_OVERLAP: usize = 10;
+/// Default maximum fraction of differences in the overlap (as in FLASH).
+pub const DEFAULT_MAX_MISMATCH_DENSITY: f64 = 0.25;
+/// Default width of the band around the diagonals of k-mer matches between the reads.
+pub const DEFAULT_BAND_WIDTH: usize = 5;
+
+/// Length of the k-mers used by the banded alignment to find the band.
+const BAND_KMER_LEN: usize = 8;
+const MATCH_SCORE: i32 = 1;
+const MISMATCH_SCORE: i32 = -2;
+const GAP_OPEN: i32 = -4;
+const GAP_EXTEND: i32 = -2;
+
+/// Reason for leaving a read pair unmerged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MergeFailure {
+    /// The reads do not overlap by at least the minimum overlap.
+    NoOverlap,
+    /// The overlap contains too many mismatching or gapped bases.
+    MismatchDensity,
+}
+
+/// A read pair merged into a single fragment.
+#[derive(Clone, Debug)]
+pub struct MergedPair {
+    /// The merged read, named after the first read.
+    pub record: fastq::Record,
+    /// Number of columns of the alignment of the overlapping read ends.
+    pub overlap: usize,
+    /// Number of mismatches and gaps in the overlap.
+    pub mismatches: usize,
+}
+
+/// Merger of overlapping read pairs, configured with builder methods.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PairMerger {
+    min_overlap: usize,
+    max_mismatch_density: f64,
+    band_width: usize,
+    staggered: bool,
+    encoding: QualityEncoding,
+}
+
+impl Default for PairMerger {
+    fn default() -> Self {
+        PairMerger {
+            min_overlap: DEFAULT_MIN_OVERLAP,
+            max_mismatch_density: DEFAULT_MAX_MISMATCH_DENSITY,
+            band_width: DEFAULT_BAND_WIDTH,
+            staggered: false,
+            encoding: QualityEncoding::default(),
+        }
+    }
+}
+
+impl PairMerger {
+    /// Create a merger with the default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Minimum number of overlapping bases (default: `DEFAULT_MIN_OVERLAP`).
+    pub fn min_overlap(mut self, min_overlap: usize) -> Self {
+        assert!(min_overlap > 0, "Expecting positive minimum overlap.");
+        self.min_overlap = min_overlap;
+        self
+    }
+
+    /// Maximum fraction of mismatching or gapped bases in the overlap
+    /// (default: `DEFAULT_MAX_MISMATCH_DENSITY`).
+    pub fn max_mismatch_density(mut self, density: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&density),
+            "Expecting mismatch density between 0 and 1."
+        );
+        self.max_mismatch_density = density;
+        self
+    }
+
+    /// Width of the alignment band, i.e. roughly the number of indels tolerated in the
+    /// overlap (default: `DEFAULT_BAND_WIDTH`).
+    pub fn band_width(mut self, band_width: usize) -> Self {
+        self.band_width = band_width;
+        self
+    }
+
+    /// Also merge pairs whose insert is shorter than the reads, such that each read extends
+    /// beyond the start of its mate (default: false). The overhangs, usually adapter
+    /// sequence, are removed from the merged read.
+    pub fn staggered(mut self, staggered: bool) -> Self {
+        self.staggered = staggered;
+        self
+    }
+
+    /// Encoding of the quality scores of the reads (default: Sanger).
+    pub fn encoding(mut self, encoding: QualityEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Merge the first read with the second read of a pair, which is sequenced from the other
+    /// end of the fragment.
+    pub fn merge(
+        &self,
+        r1: &fastq::Record,
+        r2: &fastq::Record,
+    ) -> Result<MergedPair, MergeFailure> {
+        let (seq1, qual1) = (r1.seq(), r1.qual());
+        let seq2 = dna::revcomp(r2.seq());
+        let qual2: Vec<u8> = r2.qual().iter().rev().cloned().collect();
+        if seq1.len() < self.min_overlap || seq2.len() < self.min_overlap {
+            return Err(MergeFailure::NoOverlap);
+        }
+
+        // the first read may start before the second one, and the second one may end after
+        // the first one, but not vice versa unless staggered overlaps are allowed
+        let mut scoring = Scoring::from_scores(GAP_OPEN, GAP_EXTEND, MATCH_SCORE, MISMATCH_SCORE);
+        scoring.xclip_prefix = 0;
+        scoring.yclip_suffix = 0;
+        if self.staggered {
+            scoring.xclip_suffix = 0;
+            scoring.yclip_prefix = 0;
+        }
+        let mut aligner = banded::Aligner::with_capacity_and_scoring(
+            seq1.len(),
+            seq2.len(),
+            scoring,
+            cmp::min(BAND_KMER_LEN, self.min_overlap),
+            self.band_width,
+        );
+        let alignment = aligner.custom(seq1, &seq2);
+
+        let is_column = |op: &&AlignmentOperation| {
+            !matches!(
+                op,
+                AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_)
+            )
+        };
+        let overlap = alignment.operations.iter().filter(is_column).count();
+        // with staggered overlaps, the alignment must still span from the start of one read
+        // to the end of one read
+        let dovetail = (alignment.xstart == 0 || alignment.ystart == 0)
+            && (alignment.xend == seq1.len() || alignment.yend == seq2.len());
+        if overlap < self.min_overlap || !dovetail {
+            return Err(MergeFailure::NoOverlap);
+        }
+        let mismatches = alignment
+            .operations
+            .iter()
+            .filter(is_column)
+            .filter(|&&op| op != AlignmentOperation::Match)
+            .count();
+        if mismatches as f64 > self.max_mismatch_density * overlap as f64 {
+            return Err(MergeFailure::MismatchDensity);
+        }
+
+        // gaps are resolved in favour of the read with fewer expected errors in the overlap
+        let first_wins_gaps =
+            expected_errors(&qual1[alignment.xstart..alignment.xend], self.encoding)
+                <= expected_errors(&qual2[alignment.ystart..alignment.yend], self.encoding);
+
+        let len = alignment.xstart + overlap + (seq2.len() - alignment.yend);
+        let mut seq = Vec::with_capacity(len);
+        let mut qual = Vec::with_capacity(len);
+        seq.extend_from_slice(&seq1[..alignment.xstart]);
+        qual.extend_from_slice(&qual1[..alignment.xstart]);
+        let (mut i, mut j) = (alignment.xstart, alignment.ystart);
+        for op in &alignment.operations {
+            match *op {
+                AlignmentOperation::Match | AlignmentOperation::Subst => {
+                    let (base, q) = self.resolve((seq1[i], qual1[i]), (seq2[j], qual2[j]));
+                    seq.push(base);
+                    qual.push(q);
+                    i += 1;
+                    j += 1;
+                }
+                AlignmentOperation::Ins => {
+                    if first_wins_gaps {
+                        seq.push(seq1[i]);
+                        qual.push(qual1[i]);
+                    }
+                    i += 1;
+                }
+                AlignmentOperation::Del => {
+                    if !first_wins_gaps {
+                        seq.push(seq2[j]);
+                        qual.push(qual2[j]);
+                    }
+                    j += 1;
+                }
+                AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => (),
+            }
+        }
+        seq.extend_from_slice(&seq2[alignment.yend..]);
+        qual.extend_from_slice(&qual2[alignment.yend..]);
+
+        Ok(MergedPair {
+            record: fastq::Record::with_attrs(r1.id(), r1.desc(), &seq, &qual),
+            overlap,
+            mismatches,
+        })
+    }
+
+    /// Merge the read pairs of two FASTQ files (or any other paired record sources), writing
+    /// merged reads to `merged` and, if given, unmerged pairs to the two `unmerged` writers.
+    /// Returns an error if the sources have different numbers of records.
+    pub fn merge_all<I1, I2, W, U>(
+        &self,
+        reads1: I1,
+        reads2: I2,
+        merged: &mut fastq::Writer<W>,
+        mut unmerged: Option<(&mut fastq::Writer<U>, &mut fastq::Writer<U>)>,
+    ) -> io::Result<MergeStats>
+    where
+        I1: IntoIterator<Item = io::Result<fastq::Record>>,
+        I2: IntoIterator<Item = io::Result<fastq::Record>>,
+        W: io::Write,
+        U: io::Write,
+    {
+        let mut stats = MergeStats::new();
+        let mut reads2 = reads2.into_iter();
+        for r1 in reads1 {
+            let r1 = r1?;
+            let r2 = match reads2.next() {
+                Some(r2) => r2?,
+                None => return Err(unpaired(r1.id())),
+            };
+            let result = self.merge(&r1, &r2);
+            stats.add(&result);
+            match result {
+                Ok(pair) => merged.write_record(&pair.record)?,
+                Err(_) => {
+                    if let Some((ref mut w1, ref mut w2)) = unmerged {
+                        w1.write_record(&r1)?;
+                        w2.write_record(&r2)?;
+                    }
+                }
+            }
+        }
+        match reads2.next() {
+            Some(r2) => Err(unpaired(r2?.id())),
+            None => Ok(stats),
+        }
+    }
+
+    /// Consensus of two calls of the same base, given as base and ASCII encoded quality.
+    fn resolve(&self, (b1, c1): (u8, u8), (b2, c2): (u8, u8)) -> (u8, u8) {
+        let p1 = Prob(qual_to_prob(self.encoding.decode(c1)));
+        let p2 = Prob(qual_to_prob(self.encoding.decode(c2)));
+        let encode = |p: Prob| self.encoding.encode(prob_to_qual(*p));
+        if b1.eq_ignore_ascii_case(&b2) {
+            (b1, encode(combine_agreeing(p1, p2)))
+        } else if b2.eq_ignore_ascii_case(&b'N') {
+            (b1, c1)
+        } else if b1.eq_ignore_ascii_case(&b'N') {
+            (b2, c2)
+        } else if *p2 < *p1 {
+            (b2, encode(combine_disagreeing(p2, p1)))
+        } else {
+            // ties are resolved in favour of the first read
+            (b1, encode(combine_disagreeing(p1, p2)))
+        }
+    }
+}
+
+fn unpaired(id: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Read {} has no mate in the other file.", id),
+    )
+}
+
+/// Statistics of merged read pairs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeStats {
+    /// Number of read pairs.
+    pub pairs: u64,
+    /// Number of merged read pairs.
+    pub merged: u64,
+    /// Number of pairs left unmerged because of an insufficient overlap.
+    pub no_overlap: u64,
+    /// Number of pairs left unmerged because of too many differences in the overlap.
+    pub mismatch_density: u64,
+    /// Number of merged pairs by overlap length.
+    pub overlap_lengths: Vec<u64>,
+    /// Number of merged pairs by merged read length.
+    pub merged_lengths: Vec<u64>,
+}
+
+impl MergeStats {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the result of merging a pair.
+    pub fn add(&mut self, result: &Result<MergedPair, MergeFailure>) {
+        self.pairs += 1;
+        match *result {
+            Ok(ref pair) => {
+                self.merged += 1;
+                increment(&mut self.overlap_lengths, pair.overlap);
+                increment(&mut self.merged_lengths, pair.record.seq().len());
+            }
+            Err(MergeFailure::NoOverlap) => self.no_overlap += 1,
+            Err(MergeFailure::MismatchDensity) => self.mismatch_density += 1,
+        }
+    }
+
+    /// Add the counts of other statistics, e.g. of another lane.
+    pub fn merge(&mut self, other: &MergeStats) {
+        self.pairs += other.pairs;
+        self.merged += other.merged;
+        self.no_overlap += other.no_overlap;
+        self.mismatch_density += other.mismatch_density;
+        add_counts(&mut self.overlap_lengths, &other.overlap_lengths);
+        add_counts(&mut self.merged_lengths, &other.merged_lengths);
+    }
+
+    /// Fraction of merged pairs (0 if there are no pairs).
+    pub fn merge_rate(&self) -> f64 {
+        if self.pairs == 0 {
+            0.0
+        } else {
+            self.merged as f64 / self.pairs as f64
+        }
+    }
+
+    /// Mean length of the merged reads (0 if no pairs were merged).
+    pub fn mean_merged_len(&self) -> f64 {
+        if self.merged == 0 {
+            0.0
+        } else {
+            let total: u64 = self
+                .merged_lengths
+                .iter()
+                .enumerate()
+                .map(|(len, &count)| len as u64 * count)
+                .sum();
+            total as f64 / self.merged as f64
+        }
+    }
+}
+
+fn increment(counts: &mut Vec<u64>, i: usize) {
+    if counts.len() <= i {
+        counts.resize(i + 1, 0);
+    }
+    counts[i] += 1;
+}
+
+fn add_counts(counts: &mut Vec<u64>, other: &[u64]) {
+    if counts.len() < other.len() {
+        counts.resize(other.len(), 0);
+    }
+    for (count, &c) in counts.iter_mut().zip(other) {
+        *count += c;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::{SeedableRngExt, Xoshiro256};
+
+    fn random_seq(len: usize, rng: &mut Xoshiro256) -> Vec<u8> {
+        (0..len).map(|_| b"ACGT"[rng.below(4) as usize]).collect()
+    }
+
+    fn pair(fragment: &[u8], read_len: usize) -> (fastq::Record, fastq::Record) {
+        let read_len = cmp::min(read_len, fragment.len());
+        let qual = vec![b'5'; read_len];
+        let r2 = dna::revcomp(&fragment[fragment.len() - read_len..]);
+        (
+            fastq::Record::with_attrs("r", None, &fragment[..read_len], &qual),
+            fastq::Record::with_attrs("r", None, &r2, &qual),
+        )
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut rng = Xoshiro256::seed_from_u64(42);
+        let fragment = random_seq(250, &mut rng);
+        let merger = PairMerger::new();
+        for &read_len in &[130, 150, 200, 250] {
+            let (r1, r2) = pair(&fragment, read_len);
+            let merged = merger.merge(&r1, &r2).unwrap();
+            assert_eq!(merged.record.seq(), &fragment[..]);
+            assert_eq!(merged.overlap, 2 * read_len - fragment.len());
+            assert_eq!(merged.mismatches, 0);
+            assert_eq!(merged.record.qual().len(), fragment.len());
+        }
+        // no overlap
+        let (r1, r2) = pair(&fragment, 120);
+        assert_eq!(merger.merge(&r1, &r2).err(), Some(MergeFailure::NoOverlap));
+        // overlap below the minimum
+        let (r1, r2) = pair(&fragment, 129);
+        assert_eq!(merger.merge(&r1, &r2).err(), Some(MergeFailure::NoOverlap));
+        // unrelated reads
+        let r1 = fastq::Record::with_attrs("r", None, &fragment[..100], &[b'5'; 100]);
+        let r2 = random_seq(100, &mut rng);
+        let r2 = fastq::Record::with_attrs("r", None, &r2, &[b'5'; 100]);
+        assert!(merger.merge(&r1, &r2).is_err());
+    }
+
+    #[test]
+    fn test_quality_resolution() {
+        let mut rng = Xoshiro256::seed_from_u64(7);
+        let fragment = random_seq(150, &mut rng);
+        let (r1, r2) = pair(&fragment, 100);
+        // sequencing errors at fragment position 70 in both reads, with different qualities
+        let mut seq1 = r1.seq().to_vec();
+        let mut qual1 = r1.qual().to_vec();
+        seq1[70] = dna::complement(seq1[70]);
+        qual1[70] = b'#';
+        let mut seq2 = r2.seq().to_vec();
+        let mut qual2 = r2.qual().to_vec();
+        // position 80 of the fragment is position 150 - 1 - 80 of the second read
+        seq2[69] = dna::complement(seq2[69]);
+        qual2[69] = b'#';
+        let r1 = fastq::Record::with_attrs("r", None, &seq1, &qual1);
+        let r2 = fastq::Record::with_attrs("r", None, &seq2, &qual2);
+
+        let merged = PairMerger::new().merge(&r1, &r2).unwrap();
+        assert_eq!(merged.mismatches, 2);
+        assert_eq!(merged.record.seq(), &fragment[..]);
+        let qual = merged.record.qual();
+        // disagreeing bases get a low quality, agreeing ones a boosted quality
+        assert!(qual[70] < b'5' && qual[80] < b'5');
+        assert!(qual[75] > b'5');
+        assert_eq!(qual[10], b'5');
+        assert_eq!(qual[140], b'5');
+    }
+
+    #[test]
+    fn test_indel() {
+        let mut rng = Xoshiro256::seed_from_u64(3);
+        let fragment = random_seq(150, &mut rng);
+        let (r1, r2) = pair(&fragment, 100);
+        // the second read has an extra base in the overlap, but a lower quality
+        let mut seq2 = r2.seq().to_vec();
+        seq2.insert(75, b'A');
+        let r2 = fastq::Record::with_attrs("r", None, &seq2, &vec![b'+'; seq2.len()]);
+        let merged = PairMerger::new().merge(&r1, &r2).unwrap();
+        assert_eq!(merged.record.seq(), &fragment[..]);
+        assert!(merged.mismatches >= 1);
+    }
+
+    #[test]
+    fn test_staggered() {
+        let mut rng = Xoshiro256::seed_from_u64(11);
+        let fragment = random_seq(80, &mut rng);
+        let adapter1 = b"AGATCGGAAGAGCACACGTC";
+        let adapter2 = b"AGATCGGAAGAGCGTCGTGT";
+        let mut seq1 = fragment.clone();
+        seq1.extend_from_slice(adapter1);
+        let mut seq2 = dna::revcomp(&fragment);
+        seq2.extend_from_slice(adapter2);
+        let r1 = fastq::Record::with_attrs("r", None, &seq1, &vec![b'5'; seq1.len()]);
+        let r2 = fastq::Record::with_attrs("r", None, &seq2, &vec![b'5'; seq2.len()]);
+
+        assert!(PairMerger::new().merge(&r1, &r2).is_err());
+        let merged = PairMerger::new().staggered(true).merge(&r1, &r2).unwrap();
+        assert_eq!(merged.record.seq(), &fragment[..]);
+        assert_eq!(merged.overlap, fragment.len());
+    }
+
+    #[test]
+    fn test_merge_all() {
+        let mut rng = Xoshiro256::seed_from_u64(5);
+        let mut reads1 = Vec::new();
+        let mut reads2 = Vec::new();
+        for &len in &[150, 180, 300] {
+            let fragment = random_seq(len, &mut rng);
+            let (r1, r2) = pair(&fragment, 100);
+            reads1.push(r1);
+            reads2.push(r2);
+        }
+        let mut merged = fastq::Writer::new(Vec::new());
+        let mut unmerged1 = fastq::Writer::new(Vec::new());
+        let mut unmerged2 = fastq::Writer::new(Vec::new());
+        let stats = PairMerger::new()
+            .merge_all(
+                reads1.iter().cloned().map(Ok),
+                reads2.iter().cloned().map(Ok),
+                &mut merged,
+                Some((&mut unmerged1, &mut unmerged2)),
+            )
+            .unwrap();
+        assert_eq!(stats.pairs, 3);
+        assert_eq!(stats.merged, 2);
+        assert_eq!(stats.no_overlap, 1);
+        assert_eq!(stats.overlap_lengths[50], 1);
+        assert_eq!(stats.overlap_lengths[20], 1);
+        assert!((stats.mean_merged_len() - 165.0).abs() < 1e-9);
+
+        let mut total = stats.clone();
+        total.merge(&stats);
+        assert_eq!(total.pairs, 6);
+        assert_eq!(total.merged_lengths[180], 2);
+
+        reads2.pop();
+        assert!(PairMerger::new()
+            .merge_all(
+                reads1.into_iter().map(Ok),
+                reads2.into_iter().map(Ok),
+                &mut merged,
+                None::<(&mut fastq::Writer<Vec<u8>>, _)>
+            )
+            .is_err());
+    }
+}
@@ -1,6 +1,16 @@
 //! Readers and writers for common bioinformatics file formats.
 
+pub mod agp;
 pub mod bed;
+pub mod cache;
 pub mod fasta;
 pub mod fastq;
+pub mod fetch;
 pub mod gff;
+pub mod merge_pairs;
+pub mod normalize;
+pub mod pipeline;
+pub mod qc;
+pub mod sample;
+pub mod sort;
+pub mod taxonomy;
@@ -0,0 +1,327 @@
+//! On-the-fly normalization of sequences, e.g. while reading FASTA or FASTQ files.
+//!
+//! A `Normalizer` combines any of the following steps, applied to each symbol in this order:
+//! removing whitespace, removing gaps (`-` and `.`), converting to upper case, converting `U` to
+//! `T`, and replacing symbols that are not contained in an alphabet (e.g. the IUPAC DNA
+//! alphabet) by a given symbol (e.g. `N`). The normalizer keeps statistics of all changes.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::{fasta, normalize::Normalizer};
+//!
+//! let file = b">a\nacgu-NNXacgt\n>b\nACGT\n";
+//! let records = fasta::Reader::new(&file[..]).records();
+//! let mut normalized = Normalizer::dna().wrap(records);
+//! let a = normalized.next().unwrap().unwrap();
+//! assert_eq!(a.seq(), b"ACGTNNNACGT");
+//! assert_eq!(normalized.next().unwrap().unwrap().seq(), b"ACGT");
+//!
+//! let stats = normalized.stats();
+//! assert_eq!(stats.sequences, 2);
+//! assert_eq!(stats.uppercased, 8);
+//! assert_eq!(stats.u_to_t, 1);
+//! assert_eq!(stats.gaps_removed, 1);
+//! assert_eq!(stats.invalid_replaced, 1);
+//! ```
+
+use std::fmt;
+
+use alphabets::{dna, Alphabet};
+use io::{fasta, fastq};
+
+/// Counts of the changes made by a `Normalizer`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Number of normalized sequences.
+    pub sequences: u64,
+    /// Number of symbols before normalization.
+    pub symbols_in: u64,
+    /// Number of symbols after normalization.
+    pub symbols_out: u64,
+    pub whitespace_removed: u64,
+    pub gaps_removed: u64,
+    pub uppercased: u64,
+    pub u_to_t: u64,
+    pub invalid_replaced: u64,
+}
+
+impl Stats {
+    /// Whether any symbol was changed or removed.
+    pub fn changed(&self) -> bool {
+        self.whitespace_removed
+            + self.gaps_removed
+            + self.uppercased
+            + self.u_to_t
+            + self.invalid_replaced
+            > 0
+    }
+}
+
+/// A configurable sequence normalizer. By default, no step is enabled.
+#[derive(Clone, Default)]
+pub struct Normalizer {
+    strip_whitespace: bool,
+    strip_gaps: bool,
+    uppercase: bool,
+    u_to_t: bool,
+    /// Valid symbols and replacement of invalid symbols.
+    replace_invalid: Option<(Vec<bool>, u8)>,
+    stats: Stats,
+}
+
+impl fmt::Debug for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Normalizer")
+            .field("strip_whitespace", &self.strip_whitespace)
+            .field("strip_gaps", &self.strip_gaps)
+            .field("uppercase", &self.uppercase)
+            .field("u_to_t", &self.u_to_t)
+            .field(
+                "replacement",
+                &self.replace_invalid.as_ref().map(|&(_, r)| r as char),
+            )
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl Normalizer {
+    /// Create a normalizer that leaves sequences unchanged.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create a normalizer for DNA, enabling all steps and replacing symbols that are not
+    /// contained in the IUPAC DNA alphabet by `N`.
+    pub fn dna() -> Self {
+        Normalizer::new()
+            .strip_whitespace()
+            .strip_gaps()
+            .uppercase()
+            .u_to_t()
+            .replace_invalid(&dna::iupac_alphabet(), b'N')
+    }
+
+    /// Remove whitespace, e.g. line breaks within sequences.
+    pub fn strip_whitespace(mut self) -> Self {
+        self.strip_whitespace = true;
+        self
+    }
+
+    /// Remove gap symbols (`-` and `.`).
+    pub fn strip_gaps(mut self) -> Self {
+        self.strip_gaps = true;
+        self
+    }
+
+    /// Convert to upper case.
+    pub fn uppercase(mut self) -> Self {
+        self.uppercase = true;
+        self
+    }
+
+    /// Convert `U` to `T` (RNA to DNA), preserving the case.
+    pub fn u_to_t(mut self) -> Self {
+        self.u_to_t = true;
+        self
+    }
+
+    /// Replace all symbols that are not contained in the given alphabet by `replacement`.
+    pub fn replace_invalid(mut self, alphabet: &Alphabet, replacement: u8) -> Self {
+        let valid = (0..256).map(|a| alphabet.symbols.contains(a)).collect();
+        self.replace_invalid = Some((valid, replacement));
+        self
+    }
+
+    /// Statistics of all sequences normalized so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Normalize a sequence, calling `keep` with the position of each symbol that is kept.
+    fn process<F: FnMut(usize)>(&mut self, seq: &[u8], mut keep: F) -> Vec<u8> {
+        let stats = &mut self.stats;
+        stats.sequences += 1;
+        stats.symbols_in += seq.len() as u64;
+        let mut normalized = Vec::with_capacity(seq.len());
+        for (i, &a) in seq.iter().enumerate() {
+            if self.strip_whitespace && a.is_ascii_whitespace() {
+                stats.whitespace_removed += 1;
+                continue;
+            }
+            if self.strip_gaps && (a == b'-' || a == b'.') {
+                stats.gaps_removed += 1;
+                continue;
+            }
+            let mut a = a;
+            if self.uppercase && a.is_ascii_lowercase() {
+                a.make_ascii_uppercase();
+                stats.uppercased += 1;
+            }
+            if self.u_to_t && (a == b'U' || a == b'u') {
+                a = if a == b'U' { b'T' } else { b't' };
+                stats.u_to_t += 1;
+            }
+            if let Some((ref valid, replacement)) = self.replace_invalid {
+                if !valid[a as usize] {
+                    a = replacement;
+                    stats.invalid_replaced += 1;
+                }
+            }
+            normalized.push(a);
+            keep(i);
+        }
+        stats.symbols_out += normalized.len() as u64;
+        normalized
+    }
+
+    /// Normalize the given sequence.
+    pub fn normalize(&mut self, seq: &[u8]) -> Vec<u8> {
+        self.process(seq, |_| ())
+    }
+
+    /// Wrap an iterator over sequences or records, normalizing each item.
+    pub fn wrap<I>(self, iter: I) -> Normalized<I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Normalize,
+    {
+        Normalized {
+            inner: iter.into_iter(),
+            normalizer: self,
+        }
+    }
+}
+
+/// Items that can be normalized, i.e. sequences and sequence records.
+pub trait Normalize {
+    fn normalize(self, normalizer: &mut Normalizer) -> Self;
+}
+
+impl Normalize for Vec<u8> {
+    fn normalize(self, normalizer: &mut Normalizer) -> Self {
+        normalizer.normalize(&self)
+    }
+}
+
+impl Normalize for fasta::Record {
+    fn normalize(self, normalizer: &mut Normalizer) -> Self {
+        let seq = normalizer.normalize(self.seq());
+        fasta::Record::with_attrs(self.id(), self.desc(), &seq)
+    }
+}
+
+impl Normalize for fastq::Record {
+    /// Normalize the sequence, removing the qualities of removed symbols.
+    fn normalize(self, normalizer: &mut Normalizer) -> Self {
+        let mut qual = Vec::with_capacity(self.qual().len());
+        let seq = normalizer.process(self.seq(), |i| qual.push(self.qual()[i]));
+        fastq::Record::with_attrs(self.id(), self.desc(), &seq, &qual)
+    }
+}
+
+/// Results (e.g. of readers) are normalized if they are ok.
+impl<T: Normalize, E> Normalize for Result<T, E> {
+    fn normalize(self, normalizer: &mut Normalizer) -> Self {
+        self.map(|item| item.normalize(normalizer))
+    }
+}
+
+/// An iterator normalizing the items of the wrapped iterator.
+#[derive(Debug)]
+pub struct Normalized<I> {
+    inner: I,
+    normalizer: Normalizer,
+}
+
+impl<I> Normalized<I> {
+    /// Statistics of all items normalized so far.
+    pub fn stats(&self) -> &Stats {
+        self.normalizer.stats()
+    }
+
+    /// Return the normalizer, e.g. to obtain the final statistics.
+    pub fn into_normalizer(self) -> Normalizer {
+        self.normalizer
+    }
+}
+
+impl<I> Iterator for Normalized<I>
+where
+    I: Iterator,
+    I::Item: Normalize,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.inner.next()?;
+        Some(item.normalize(&mut self.normalizer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps() {
+        let seq = b"ac gu\n-.Xu";
+        assert_eq!(Normalizer::new().normalize(seq), seq.to_vec());
+        assert_eq!(
+            Normalizer::new().strip_whitespace().normalize(seq),
+            b"acgu-.Xu"
+        );
+        assert_eq!(Normalizer::new().strip_gaps().normalize(seq), b"ac gu\nXu");
+        assert_eq!(Normalizer::new().uppercase().normalize(seq), b"AC GU\n-.XU");
+        assert_eq!(Normalizer::new().u_to_t().normalize(seq), b"ac gt\n-.Xt");
+        let mut normalizer = Normalizer::new().replace_invalid(&dna::alphabet(), b'N');
+        assert_eq!(normalizer.normalize(seq), b"acNgNNNNNN");
+        assert_eq!(normalizer.stats().invalid_replaced, 7);
+
+        let mut normalizer = Normalizer::dna();
+        assert_eq!(normalizer.normalize(seq), b"ACGTNT");
+        assert_eq!(normalizer.normalize(b"ACGT"), b"ACGT");
+        let stats = normalizer.stats();
+        assert_eq!(
+            *stats,
+            Stats {
+                sequences: 2,
+                symbols_in: 14,
+                symbols_out: 10,
+                whitespace_removed: 2,
+                gaps_removed: 2,
+                uppercased: 5,
+                u_to_t: 2,
+                invalid_replaced: 1,
+            }
+        );
+        assert!(stats.changed());
+        assert!(!Normalizer::new()
+            .wrap(vec![b"acgt".to_vec()])
+            .stats()
+            .changed());
+    }
+
+    #[test]
+    fn test_fastq() {
+        let file = b"@r1\nac-gt\n+\nAB#CD\n@r2\nACG\n+\nIII\n";
+        let records = fastq::Reader::new(&file[..]).records();
+        let mut normalized = Normalizer::dna().wrap(records);
+        let r1 = normalized.next().unwrap().unwrap();
+        assert_eq!(r1.seq(), b"ACGT");
+        assert_eq!(r1.qual(), b"ABCD");
+        assert_eq!(r1.id(), "r1");
+        let r2 = normalized.next().unwrap().unwrap();
+        assert_eq!(r2.seq(), b"ACG");
+        assert!(normalized.next().is_none());
+        assert_eq!(normalized.into_normalizer().stats().symbols_out, 7);
+    }
+
+    #[test]
+    fn test_wrap_sequences() {
+        let seqs = vec![b"acgt".to_vec(), b"NNuu".to_vec()];
+        let normalized: Vec<_> = Normalizer::new().uppercase().wrap(seqs).collect();
+        assert_eq!(normalized, [b"ACGT".to_vec(), b"NNUU".to_vec()]);
+    }
+}
@@ -0,0 +1,527 @@
+//! Composable processing of record streams, e.g. reading, filtering, trimming and writing reads.
+//!
+//! Processing steps implement `RecordTransform`, which turns a record into a new record or
+//! drops it. Any closure returning an `Option` is a transform; `Trim`, `Mask` and `Dedup`
+//! provide common read processing steps. Transforms are chained with `RecordTransform::then`
+//! and applied to iterators (e.g. readers) via the `Pipeline` extension trait:
+//!
+//! * `transform`: apply a transform to each record,
+//! * `try_transform`: apply a transform to each `Ok` record of a reader, passing on errors,
+//! * `par_transform`: apply a transform with several threads, processing batches of records,
+//!   such that at most one batch is buffered (backpressure) and the order is kept,
+//! * `write_to`: write all records with a writer implementing `WriteRecord`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fastq;
+//! use bio::io::pipeline::{Dedup, Mask, Pipeline, RecordTransform, Trim};
+//!
+//! let input = b"@r1\nACGTACGT\n+\nIIII5I##\n@r2\nACGTACGT\n+\nIIIIIIII\n@r3\nGG\n+\nII\n";
+//! let mut output = Vec::new();
+//! {
+//!     let mut writer = fastq::Writer::new(&mut output);
+//!     let written = fastq::Reader::new(&input[..])
+//!         .records()
+//!         .try_transform(Trim::new(10).min_len(4).then(Mask::new(30)))
+//!         .write_to(&mut writer)
+//!         .unwrap();
+//!     assert_eq!(written, 2);
+//! }
+//! assert_eq!(
+//!     output,
+//!     &b"@r1\nACGTNC\n+\nIIII5I\n@r2\nACGTACGT\n+\nIIIIIIII\n"[..]
+//! );
+//!
+//! let records: Vec<_> = fastq::Reader::new(&input[..])
+//!     .records()
+//!     .map(|record| record.unwrap())
+//!     .transform(Dedup::new())
+//!     .collect();
+//! assert_eq!(records.len(), 2);
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::thread;
+
+use io::{fasta, fastq};
+
+/// A step of record processing, turning a record into a new record or dropping it.
+pub trait RecordTransform<T> {
+    type Output;
+
+    /// Transform the given record, or drop it by returning `None`.
+    fn apply(&mut self, record: T) -> Option<Self::Output>;
+
+    /// Apply the given transform to the output of this transform.
+    fn then<B: RecordTransform<Self::Output>>(self, next: B) -> Chain<Self, B>
+    where
+        Self: Sized,
+    {
+        Chain { first: self, next }
+    }
+}
+
+impl<T, U, F: FnMut(T) -> Option<U>> RecordTransform<T> for F {
+    type Output = U;
+
+    fn apply(&mut self, record: T) -> Option<U> {
+        self(record)
+    }
+}
+
+/// Two transforms applied one after the other.
+#[derive(Clone, Copy, Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<T, A: RecordTransform<T>, B: RecordTransform<A::Output>> RecordTransform<T> for Chain<A, B> {
+    type Output = B::Output;
+
+    fn apply(&mut self, record: T) -> Option<B::Output> {
+        let record = self.first.apply(record)?;
+        self.next.apply(record)
+    }
+}
+
+/// Trim bases of Phred quality (Sanger encoding) below a threshold from the 3' end of FASTQ
+/// records, dropping records shorter than a minimum length after trimming.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Trim {
+    min_qual: u8,
+    min_len: usize,
+}
+
+impl Trim {
+    /// Trim bases below the given quality, keeping all records.
+    pub fn new(min_qual: u8) -> Self {
+        Trim {
+            min_qual,
+            min_len: 0,
+        }
+    }
+
+    /// Drop records shorter than `min_len` after trimming.
+    pub fn min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len;
+        self
+    }
+}
+
+impl RecordTransform<fastq::Record> for Trim {
+    type Output = fastq::Record;
+
+    fn apply(&mut self, record: fastq::Record) -> Option<fastq::Record> {
+        let len = record
+            .qual()
+            .iter()
+            .rposition(|&q| q.saturating_sub(33) >= self.min_qual)
+            .map_or(0, |i| i + 1);
+        if len < self.min_len {
+            return None;
+        }
+        if len == record.seq().len() {
+            return Some(record);
+        }
+        Some(fastq::Record::with_attrs(
+            record.id(),
+            record.desc(),
+            &record.seq()[..len],
+            &record.qual()[..len],
+        ))
+    }
+}
+
+/// Replace bases of Phred quality (Sanger encoding) below a threshold in FASTQ records by `N`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mask {
+    min_qual: u8,
+    symbol: u8,
+}
+
+impl Mask {
+    /// Mask bases below the given quality.
+    pub fn new(min_qual: u8) -> Self {
+        Mask {
+            min_qual,
+            symbol: b'N',
+        }
+    }
+
+    /// Mask with the given symbol instead of `N`.
+    pub fn symbol(mut self, symbol: u8) -> Self {
+        self.symbol = symbol;
+        self
+    }
+}
+
+impl RecordTransform<fastq::Record> for Mask {
+    type Output = fastq::Record;
+
+    fn apply(&mut self, record: fastq::Record) -> Option<fastq::Record> {
+        if record
+            .qual()
+            .iter()
+            .all(|&q| q.saturating_sub(33) >= self.min_qual)
+        {
+            return Some(record);
+        }
+        let seq: Vec<u8> = record
+            .seq()
+            .iter()
+            .zip(record.qual())
+            .map(|(&base, &q)| {
+                if q.saturating_sub(33) >= self.min_qual {
+                    base
+                } else {
+                    self.symbol
+                }
+            })
+            .collect();
+        Some(fastq::Record::with_attrs(
+            record.id(),
+            record.desc(),
+            &seq,
+            record.qual(),
+        ))
+    }
+}
+
+/// Drop records whose sequence occurred in a previous record. All distinct sequences are kept
+/// in memory.
+#[derive(Clone, Debug, Default)]
+pub struct Dedup {
+    seen: HashSet<Vec<u8>>,
+}
+
+impl Dedup {
+    pub fn new() -> Self {
+        Dedup::default()
+    }
+
+    /// Number of distinct sequences seen so far.
+    pub fn distinct(&self) -> usize {
+        self.seen.len()
+    }
+
+    fn is_new(&mut self, seq: &[u8]) -> bool {
+        !self.seen.contains(seq) && self.seen.insert(seq.to_vec())
+    }
+}
+
+impl RecordTransform<fasta::Record> for Dedup {
+    type Output = fasta::Record;
+
+    fn apply(&mut self, record: fasta::Record) -> Option<fasta::Record> {
+        if self.is_new(record.seq()) {
+            Some(record)
+        } else {
+            None
+        }
+    }
+}
+
+impl RecordTransform<fastq::Record> for Dedup {
+    type Output = fastq::Record;
+
+    fn apply(&mut self, record: fastq::Record) -> Option<fastq::Record> {
+        if self.is_new(record.seq()) {
+            Some(record)
+        } else {
+            None
+        }
+    }
+}
+
+/// Writers of records.
+pub trait WriteRecord<T> {
+    fn write_record(&mut self, record: &T) -> io::Result<()>;
+}
+
+impl<W: io::Write> WriteRecord<fasta::Record> for fasta::Writer<W> {
+    fn write_record(&mut self, record: &fasta::Record) -> io::Result<()> {
+        fasta::Writer::write_record(self, record)
+    }
+}
+
+impl<W: io::Write> WriteRecord<fastq::Record> for fastq::Writer<W> {
+    fn write_record(&mut self, record: &fastq::Record) -> io::Result<()> {
+        fastq::Writer::write_record(self, record)
+    }
+}
+
+/// Iterator applying a transform to the records of the wrapped iterator.
+#[derive(Clone, Debug)]
+pub struct Transformed<I, F> {
+    inner: I,
+    transform: F,
+}
+
+impl<I: Iterator, F: RecordTransform<I::Item>> Iterator for Transformed<I, F> {
+    type Item = F::Output;
+
+    fn next(&mut self) -> Option<F::Output> {
+        for record in self.inner.by_ref() {
+            if let Some(record) = self.transform.apply(record) {
+                return Some(record);
+            }
+        }
+        None
+    }
+}
+
+/// Iterator applying a transform to the `Ok` records of the wrapped iterator.
+#[derive(Clone, Debug)]
+pub struct TryTransformed<I, F> {
+    inner: I,
+    transform: F,
+}
+
+impl<T, E, I, F> Iterator for TryTransformed<I, F>
+where
+    I: Iterator<Item = Result<T, E>>,
+    F: RecordTransform<T>,
+{
+    type Item = Result<F::Output, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for record in &mut self.inner {
+            match record {
+                Ok(record) => {
+                    if let Some(record) = self.transform.apply(record) {
+                        return Some(Ok(record));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
+/// Iterator applying a transform to batches of records of the wrapped iterator with several
+/// threads, keeping the order of the records.
+#[derive(Debug)]
+pub struct ParTransformed<I: Iterator, F: RecordTransform<I::Item>> {
+    inner: I,
+    workers: Vec<F>,
+    batch_size: usize,
+    buffer: VecDeque<F::Output>,
+}
+
+impl<I, F> Iterator for ParTransformed<I, F>
+where
+    I: Iterator,
+    I::Item: Send,
+    F: RecordTransform<I::Item> + Send,
+    F::Output: Send,
+{
+    type Item = F::Output;
+
+    fn next(&mut self) -> Option<F::Output> {
+        while self.buffer.is_empty() {
+            let batch: Vec<_> = (&mut self.inner).take(self.batch_size).collect();
+            if batch.is_empty() {
+                return None;
+            }
+            let chunk_size = batch.len().div_ceil(self.workers.len());
+            let mut chunks = Vec::with_capacity(self.workers.len());
+            let mut batch = batch.into_iter();
+            loop {
+                let chunk: Vec<_> = (&mut batch).take(chunk_size).collect();
+                if chunk.is_empty() {
+                    break;
+                }
+                chunks.push(chunk);
+            }
+            let results: Vec<Vec<F::Output>> = thread::scope(|scope| {
+                let handles: Vec<_> = self
+                    .workers
+                    .iter_mut()
+                    .zip(chunks)
+                    .map(|(worker, chunk)| {
+                        scope.spawn(move || {
+                            chunk
+                                .into_iter()
+                                .filter_map(|record| worker.apply(record))
+                                .collect()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("Expecting worker not to panic."))
+                    .collect()
+            });
+            self.buffer.extend(results.into_iter().flatten());
+        }
+        self.buffer.pop_front()
+    }
+}
+
+/// Record processing with transforms, for iterators over records (e.g. readers).
+pub trait Pipeline: Iterator + Sized {
+    /// Apply the given transform to each record.
+    fn transform<F: RecordTransform<Self::Item>>(self, transform: F) -> Transformed<Self, F> {
+        Transformed {
+            inner: self,
+            transform,
+        }
+    }
+
+    /// Apply the given transform to each `Ok` record, e.g. of a reader, passing on errors.
+    fn try_transform<T, E, F>(self, transform: F) -> TryTransformed<Self, F>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+        F: RecordTransform<T>,
+    {
+        TryTransformed {
+            inner: self,
+            transform,
+        }
+    }
+
+    /// Apply the given transform to each record with the given number of threads. Records are
+    /// read in batches of `batch_size`, which are split among the threads, and returned in
+    /// their original order. Each thread uses its own clone of the transform, such that
+    /// stateful transforms (e.g. `Dedup`) only see a part of the records; these should be
+    /// applied with `transform`.
+    fn par_transform<F>(
+        self,
+        threads: usize,
+        batch_size: usize,
+        transform: F,
+    ) -> ParTransformed<Self, F>
+    where
+        F: RecordTransform<Self::Item> + Clone,
+    {
+        assert!(
+            threads > 0 && batch_size > 0,
+            "Expecting positive number of threads and batch size."
+        );
+        ParTransformed {
+            inner: self,
+            workers: vec![transform; threads],
+            batch_size,
+            buffer: VecDeque::new(),
+        }
+    }
+
+    /// Write all records with the given writer, returning the number of written records.
+    fn write_to<T, E, W>(self, writer: &mut W) -> io::Result<usize>
+    where
+        Self: Iterator<Item = Result<T, E>>,
+        E: Into<io::Error>,
+        W: WriteRecord<T>,
+    {
+        let mut count = 0;
+        for record in self {
+            writer.write_record(&record.map_err(Into::into)?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl<I: Iterator> Pipeline for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reads() -> Vec<fastq::Record> {
+        (0..100)
+            .map(|i| {
+                let seq = format!("ACGT{:08b}", i).replace('0', "A").replace('1', "C");
+                let qual: String = (0..seq.len())
+                    .map(|j| if j < 4 + i % 8 { 'I' } else { '#' })
+                    .collect();
+                fastq::Record::with_attrs(&format!("r{}", i), None, seq.as_bytes(), qual.as_bytes())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trim_mask() {
+        let record = fastq::Record::with_attrs("r", None, b"ACGTACGT", b"II#I5I##");
+        let trimmed = Trim::new(10).apply(record.clone()).unwrap();
+        assert_eq!(
+            (trimmed.seq(), trimmed.qual()),
+            (&b"ACGTAC"[..], &b"II#I5I"[..])
+        );
+        assert!(Trim::new(10).min_len(7).apply(record.clone()).is_none());
+        assert!(Trim::new(50)
+            .apply(record.clone())
+            .unwrap()
+            .seq()
+            .is_empty());
+        let masked = Mask::new(30).symbol(b'n').apply(record).unwrap();
+        assert_eq!(masked.seq(), b"ACnTnCnn");
+        assert_eq!(masked.qual(), b"II#I5I##");
+    }
+
+    #[test]
+    fn test_dedup() {
+        let records = vec![
+            fasta::Record::with_attrs("a", None, b"ACGT"),
+            fasta::Record::with_attrs("b", None, b"ACGA"),
+            fasta::Record::with_attrs("c", None, b"ACGT"),
+        ];
+        let mut dedup = Dedup::new();
+        let ids: Vec<_> = records
+            .into_iter()
+            .filter_map(|record| dedup.apply(record))
+            .map(|record| record.id().to_owned())
+            .collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(dedup.distinct(), 2);
+    }
+
+    #[test]
+    fn test_chain() {
+        let lengths = Trim::new(10).then(|record: fastq::Record| {
+            if record.seq().len() > 6 {
+                Some(record.seq().len())
+            } else {
+                None
+            }
+        });
+        let lengths: Vec<_> = reads().into_iter().transform(lengths).collect();
+        assert_eq!(lengths.len(), 61);
+        assert!(lengths.iter().all(|&len| len > 6 && len < 12));
+    }
+
+    #[test]
+    fn test_par_transform() {
+        let transform = Trim::new(10).min_len(6).then(Mask::new(10));
+        let summary = |record: fastq::Record| (record.id().to_owned(), record.seq().to_vec());
+        let expected: Vec<_> = reads()
+            .into_iter()
+            .transform(transform)
+            .map(summary)
+            .collect();
+        assert_eq!(expected.len(), 74);
+        for &(threads, batch_size) in &[(1, 1), (3, 10), (4, 7), (8, 1000)] {
+            let records: Vec<_> = reads()
+                .into_iter()
+                .par_transform(threads, batch_size, transform)
+                .map(summary)
+                .collect();
+            assert_eq!(records, expected);
+        }
+    }
+
+    #[test]
+    fn test_try_transform_errors() {
+        let input = b"@r1\nACGT\n+\nIIII\nr2\nACGT\n+\nIIII\n";
+        let mut writer = fastq::Writer::new(Vec::new());
+        let result = fastq::Reader::new(&input[..])
+            .records()
+            .try_transform(Trim::new(10))
+            .write_to(&mut writer);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,501 @@
+//! Quality control (QC) metrics of sequencing read files, and their aggregation across files.
+//!
+//! `QcMetrics` collects, per file, the length distribution, per-position and overall quality
+//! distributions, the GC content distribution, the sequence duplication level and the adapter
+//! content (i.e. the fraction of reads containing an adapter up to each position, as in FastQC).
+//! Metrics of several files (e.g. lanes or samples) can be merged, and summarized in a
+//! `QcReport` with per-file and combined summaries. All summaries implement `Serialize`, such
+//! that they can be written as JSON or similar for QC dashboards.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fastq;
+//! use bio::io::qc::{QcAggregator, QcMetrics};
+//!
+//! let lane1 = b"@r1\nACGTAGATCGGAAGAGCA\n+\nIIIIIIIIIIIIIIIIII\n@r2\nGGGGCCCC\n+\nIIII####\n";
+//! let lane2 = b"@r3\nGGGGCCCC\n+\nIIIIIIII\n";
+//!
+//! let mut aggregator = QcAggregator::new();
+//! for (name, file) in &[("lane1", &lane1[..]), ("lane2", &lane2[..])] {
+//!     let mut metrics = QcMetrics::new();
+//!     for record in fastq::Reader::new(*file).records() {
+//!         metrics.add_fastq(&record.unwrap());
+//!     }
+//!     aggregator.add(name, metrics);
+//! }
+//! let report = aggregator.report();
+//! assert_eq!(report.files.len(), 2);
+//! assert_eq!(report.combined.reads, 3);
+//! // r2 and r3 are duplicates
+//! assert!((report.combined.duplication_rate - 1.0 / 3.0).abs() < 1e-9);
+//! // r1 contains the Illumina universal adapter at position 4
+//! assert_eq!(report.files[0].adapter_content[0].fractions[4], 0.5);
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use io::{fasta, fastq};
+use stats::phred::{QualityEncoding, MAX_QUAL};
+
+/// Default maximum number of distinct sequences tracked for duplication levels.
+pub const DEFAULT_MAX_TRACKED: usize = 100_000;
+
+/// Adapters searched by default: Illumina universal and Nextera transposase adapters.
+pub const DEFAULT_ADAPTERS: [(&str, &[u8]); 2] = [
+    ("Illumina Universal Adapter", b"AGATCGGAAGAG"),
+    ("Nextera Transposase Sequence", b"CTGTCTCTTATA"),
+];
+
+/// Increment the counter at the given index, growing the counters if necessary.
+fn increment(counts: &mut Vec<u64>, i: usize, by: u64) {
+    if counts.len() <= i {
+        counts.resize(i + 1, 0);
+    }
+    counts[i] += by;
+}
+
+/// Add the counters of `other` to `counts`.
+fn add_counts(counts: &mut Vec<u64>, other: &[u64]) {
+    for (i, &c) in other.iter().enumerate() {
+        increment(counts, i, c);
+    }
+}
+
+/// Counts of the start positions of an adapter in reads.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdapterCounts {
+    pub name: String,
+    pub seq: Vec<u8>,
+    /// Number of reads with the first occurrence of the adapter at each position.
+    pub starts: Vec<u64>,
+}
+
+/// QC metrics of a set of reads.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QcMetrics {
+    encoding: QualityEncoding,
+    max_tracked: usize,
+    pub reads: u64,
+    pub bases: u64,
+    /// Number of reads by length.
+    pub lengths: Vec<u64>,
+    /// Sum of qualities and number of qualities by read position.
+    pub position_quality: Vec<(u64, u64)>,
+    /// Number of bases by quality score.
+    pub qualities: Vec<u64>,
+    /// Number of reads by GC content in percent.
+    pub gc: Vec<u64>,
+    /// Number of `N` bases by read position.
+    pub n_bases: Vec<u64>,
+    /// Number of occurrences by sequence hash, for up to `max_tracked` distinct sequences.
+    duplicates: HashMap<u64, u64>,
+    pub adapters: Vec<AdapterCounts>,
+}
+
+impl Default for QcMetrics {
+    fn default() -> Self {
+        QcMetrics::new()
+    }
+}
+
+impl QcMetrics {
+    /// Create empty metrics for Sanger encoded qualities, searching the default adapters.
+    pub fn new() -> Self {
+        QcMetrics {
+            encoding: QualityEncoding::Sanger,
+            max_tracked: DEFAULT_MAX_TRACKED,
+            reads: 0,
+            bases: 0,
+            lengths: Vec::new(),
+            position_quality: Vec::new(),
+            qualities: vec![0; MAX_QUAL as usize + 1],
+            gc: vec![0; 101],
+            n_bases: Vec::new(),
+            duplicates: HashMap::new(),
+            adapters: Vec::new(),
+        }
+        .adapters(&DEFAULT_ADAPTERS)
+    }
+
+    /// Use the given encoding of quality scores.
+    pub fn encoding(mut self, encoding: QualityEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Search the given adapters (name and sequence) instead of the default ones.
+    pub fn adapters(mut self, adapters: &[(&str, &[u8])]) -> Self {
+        assert!(
+            adapters.iter().all(|&(_, seq)| !seq.is_empty()),
+            "Expecting non-empty adapter sequences."
+        );
+        self.adapters = adapters
+            .iter()
+            .map(|&(name, seq)| AdapterCounts {
+                name: name.to_owned(),
+                seq: seq.to_ascii_uppercase(),
+                starts: Vec::new(),
+            })
+            .collect();
+        self
+    }
+
+    /// Track up to the given number of distinct sequences for duplication levels.
+    pub fn max_tracked(mut self, max_tracked: usize) -> Self {
+        self.max_tracked = max_tracked;
+        self
+    }
+
+    /// Add a read with optional ASCII encoded qualities.
+    pub fn add(&mut self, seq: &[u8], qual: Option<&[u8]>) {
+        self.reads += 1;
+        self.bases += seq.len() as u64;
+        increment(&mut self.lengths, seq.len(), 1);
+
+        if let Some(qual) = qual {
+            if self.position_quality.len() < qual.len() {
+                self.position_quality.resize(qual.len(), (0, 0));
+            }
+            for (i, &c) in qual.iter().enumerate() {
+                let q = self.encoding.decode(c).min(MAX_QUAL);
+                self.position_quality[i].0 += u64::from(q);
+                self.position_quality[i].1 += 1;
+                self.qualities[q as usize] += 1;
+            }
+        }
+
+        let mut gc = 0;
+        let mut acgt = 0;
+        for (i, &b) in seq.iter().enumerate() {
+            match b.to_ascii_uppercase() {
+                b'G' | b'C' => {
+                    gc += 1;
+                    acgt += 1;
+                }
+                b'A' | b'T' => acgt += 1,
+                b'N' => increment(&mut self.n_bases, i, 1),
+                _ => (),
+            }
+        }
+        if acgt > 0 {
+            self.gc[(100.0 * gc as f64 / acgt as f64).round() as usize] += 1;
+        }
+
+        let seq = seq.to_ascii_uppercase();
+        let mut hasher = DefaultHasher::new();
+        seq.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.duplicates.len() < self.max_tracked || self.duplicates.contains_key(&hash) {
+            *self.duplicates.entry(hash).or_insert(0) += 1;
+        }
+
+        for adapter in &mut self.adapters {
+            if let Some(start) = seq
+                .windows(adapter.seq.len())
+                .position(|w| w == &adapter.seq[..])
+            {
+                increment(&mut adapter.starts, start, 1);
+            }
+        }
+    }
+
+    pub fn add_fastq(&mut self, record: &fastq::Record) {
+        self.add(record.seq(), Some(record.qual()));
+    }
+
+    pub fn add_fasta(&mut self, record: &fasta::Record) {
+        self.add(record.seq(), None);
+    }
+
+    /// Merge the metrics of another set of reads. Adapters are matched by name.
+    pub fn merge(&mut self, other: &QcMetrics) {
+        self.reads += other.reads;
+        self.bases += other.bases;
+        add_counts(&mut self.lengths, &other.lengths);
+        if self.position_quality.len() < other.position_quality.len() {
+            self.position_quality
+                .resize(other.position_quality.len(), (0, 0));
+        }
+        for (i, &(sum, count)) in other.position_quality.iter().enumerate() {
+            self.position_quality[i].0 += sum;
+            self.position_quality[i].1 += count;
+        }
+        add_counts(&mut self.qualities, &other.qualities);
+        add_counts(&mut self.gc, &other.gc);
+        add_counts(&mut self.n_bases, &other.n_bases);
+        for (&hash, &count) in &other.duplicates {
+            if self.duplicates.len() < self.max_tracked || self.duplicates.contains_key(&hash) {
+                *self.duplicates.entry(hash).or_insert(0) += count;
+            }
+        }
+        for adapter in &other.adapters {
+            match self.adapters.iter_mut().find(|a| a.name == adapter.name) {
+                Some(a) => add_counts(&mut a.starts, &adapter.starts),
+                None => self.adapters.push(adapter.clone()),
+            }
+        }
+    }
+
+    /// Mean quality by read position.
+    pub fn quality_profile(&self) -> Vec<f64> {
+        self.position_quality
+            .iter()
+            .map(|&(sum, count)| sum as f64 / count as f64)
+            .collect()
+    }
+
+    /// Mean quality of all bases, `None` without qualities.
+    pub fn mean_quality(&self) -> Option<f64> {
+        let count: u64 = self.qualities.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let sum: u64 = self
+            .qualities
+            .iter()
+            .enumerate()
+            .map(|(q, &c)| q as u64 * c)
+            .sum();
+        Some(sum as f64 / count as f64)
+    }
+
+    /// Mean GC content of reads, in percent.
+    pub fn mean_gc(&self) -> Option<f64> {
+        let count: u64 = self.gc.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let sum: u64 = self.gc.iter().enumerate().map(|(p, &c)| p as u64 * c).sum();
+        Some(sum as f64 / count as f64)
+    }
+
+    /// Fraction of tracked reads that are duplicates of another read.
+    pub fn duplication_rate(&self) -> f64 {
+        let tracked: u64 = self.duplicates.values().sum();
+        if tracked == 0 {
+            0.0
+        } else {
+            1.0 - self.duplicates.len() as f64 / tracked as f64
+        }
+    }
+
+    /// Number of distinct sequences by number of occurrences.
+    pub fn duplication_levels(&self) -> Vec<u64> {
+        let mut levels = Vec::new();
+        for &count in self.duplicates.values() {
+            increment(&mut levels, count as usize, 1);
+        }
+        levels
+    }
+
+    /// Fraction of reads containing each adapter up to each position.
+    pub fn adapter_content(&self) -> Vec<AdapterContent> {
+        let len = self.lengths.len().saturating_sub(1);
+        self.adapters
+            .iter()
+            .map(|adapter| {
+                let mut cumulative = 0;
+                let fractions = (0..len)
+                    .map(|i| {
+                        cumulative += adapter.starts.get(i).cloned().unwrap_or(0);
+                        cumulative as f64 / self.reads as f64
+                    })
+                    .collect();
+                AdapterContent {
+                    name: adapter.name.clone(),
+                    fractions,
+                }
+            })
+            .collect()
+    }
+
+    /// Summary of the metrics, e.g. for reports.
+    pub fn summary(&self, name: &str) -> QcSummary {
+        QcSummary {
+            name: name.to_owned(),
+            reads: self.reads,
+            bases: self.bases,
+            min_len: self.lengths.iter().position(|&c| c > 0).unwrap_or(0),
+            max_len: self.lengths.len().saturating_sub(1),
+            mean_len: if self.reads > 0 {
+                self.bases as f64 / self.reads as f64
+            } else {
+                0.0
+            },
+            mean_quality: self.mean_quality(),
+            quality_profile: self.quality_profile(),
+            mean_gc: self.mean_gc(),
+            gc_histogram: self.gc.clone(),
+            n_fraction: self.n_bases.iter().sum::<u64>() as f64 / self.bases.max(1) as f64,
+            duplication_rate: self.duplication_rate(),
+            duplication_levels: self.duplication_levels(),
+            adapter_content: self.adapter_content(),
+        }
+    }
+}
+
+/// Fraction of reads containing an adapter up to each position.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdapterContent {
+    pub name: String,
+    pub fractions: Vec<f64>,
+}
+
+/// Summary of the QC metrics of a file or a set of files.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QcSummary {
+    pub name: String,
+    pub reads: u64,
+    pub bases: u64,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub mean_len: f64,
+    pub mean_quality: Option<f64>,
+    /// Mean quality by read position.
+    pub quality_profile: Vec<f64>,
+    /// Mean GC content in percent.
+    pub mean_gc: Option<f64>,
+    /// Number of reads by GC content in percent.
+    pub gc_histogram: Vec<u64>,
+    /// Fraction of `N` bases.
+    pub n_fraction: f64,
+    pub duplication_rate: f64,
+    /// Number of distinct sequences by number of occurrences.
+    pub duplication_levels: Vec<u64>,
+    pub adapter_content: Vec<AdapterContent>,
+}
+
+/// A QC report with summaries of all files and their combination.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QcReport {
+    pub files: Vec<QcSummary>,
+    pub combined: QcSummary,
+}
+
+/// Aggregation of the QC metrics of multiple files.
+#[derive(Clone, Debug, Default)]
+pub struct QcAggregator {
+    files: Vec<(String, QcMetrics)>,
+}
+
+impl QcAggregator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add the metrics of the file with the given name.
+    pub fn add(&mut self, name: &str, metrics: QcMetrics) {
+        self.files.push((name.to_owned(), metrics));
+    }
+
+    /// The merged metrics of all files.
+    pub fn combined(&self) -> QcMetrics {
+        let mut combined = match self.files.first() {
+            Some((_, metrics)) => QcMetrics {
+                reads: 0,
+                bases: 0,
+                lengths: Vec::new(),
+                position_quality: Vec::new(),
+                qualities: Vec::new(),
+                gc: Vec::new(),
+                n_bases: Vec::new(),
+                duplicates: HashMap::new(),
+                adapters: Vec::new(),
+                ..metrics.clone()
+            },
+            None => QcMetrics::new(),
+        };
+        for (_, metrics) in &self.files {
+            combined.merge(metrics);
+        }
+        combined
+    }
+
+    pub fn report(&self) -> QcReport {
+        QcReport {
+            files: self
+                .files
+                .iter()
+                .map(|(name, metrics)| metrics.summary(name))
+                .collect(),
+            combined: self.combined().summary("combined"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let mut metrics = QcMetrics::new().max_tracked(2);
+        metrics.add(b"ACGN", Some(b"I5+!"));
+        metrics.add(b"acgtacgt", Some(b"IIIIIIII"));
+        metrics.add(b"TTTT", None);
+        metrics.add(b"ACGN", None);
+        assert_eq!(metrics.reads, 4);
+        assert_eq!(metrics.bases, 20);
+        assert_eq!(metrics.lengths[4], 3);
+        let profile = metrics.quality_profile();
+        assert_eq!(profile.len(), 8);
+        assert_eq!(profile[0], 40.0);
+        assert_eq!(profile[1], 30.0);
+        assert_eq!(profile[3], 20.0);
+        assert_eq!(metrics.mean_quality(), Some(390.0 / 12.0));
+        assert_eq!(metrics.gc[67], 2);
+        assert_eq!(metrics.gc[50], 1);
+        assert_eq!(metrics.n_bases[3], 2);
+        // TTTT is not tracked
+        assert_relative_eq!(metrics.duplication_rate(), 1.0 / 3.0);
+        assert_eq!(metrics.duplication_levels(), [0, 1, 1]);
+        assert!(QcMetrics::new().mean_quality().is_none());
+    }
+
+    #[test]
+    fn test_adapters() {
+        let mut metrics = QcMetrics::new().adapters(&[("a", b"acgt")]);
+        metrics.add(b"TTACGTACGT", None);
+        metrics.add(b"ACGTTT", None);
+        metrics.add(b"TTTT", None);
+        metrics.add(b"TTTTTTTTAC", None);
+        let content = metrics.adapter_content();
+        assert_eq!(content[0].name, "a");
+        assert_eq!(content[0].fractions.len(), 10);
+        assert_eq!(content[0].fractions[0], 0.25);
+        assert_eq!(content[0].fractions[2], 0.5);
+        assert_eq!(content[0].fractions[9], 0.5);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = QcMetrics::new();
+        a.add(b"ACGT", Some(b"IIII"));
+        let mut b = QcMetrics::new().adapters(&[("x", b"GG")]);
+        b.add(b"ACGTGG", Some(b"++++++"));
+        b.add(b"ACGT", Some(b"++++"));
+
+        let mut aggregator = QcAggregator::new();
+        aggregator.add("a", a.clone());
+        aggregator.add("b", b.clone());
+        let combined = aggregator.combined();
+        a.merge(&b);
+        assert_eq!(combined, a);
+        assert_eq!(combined.reads, 3);
+        assert_eq!(
+            combined.quality_profile(),
+            [20.0, 20.0, 20.0, 20.0, 10.0, 10.0]
+        );
+        assert_relative_eq!(combined.duplication_rate(), 1.0 / 3.0);
+        assert_eq!(combined.adapters.len(), 3);
+
+        let report = aggregator.report();
+        assert_eq!(report.files[1].name, "b");
+        assert_eq!(report.files[1].max_len, 6);
+        assert_eq!(report.combined.min_len, 4);
+        assert_relative_eq!(report.combined.mean_gc.unwrap(), 167.0 / 3.0);
+    }
+}
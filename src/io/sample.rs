@@ -0,0 +1,186 @@
+//! Reproducible subsampling of records, e.g. of FASTQ, FASTA or alignment record iterators.
+//!
+//! Three strategies are provided via the `Sample` extension trait on iterators:
+//!
+//! * `downsample`: keep each record independently with the given probability,
+//! * `downsample_exact`: keep exactly `k` of `n` records (selection sampling, Knuth's Algorithm
+//!   S), given the number of records `n`, e.g. from a previous pass or an index,
+//! * `reservoir`: keep `k` records of an iterator of unknown length, uniformly at random
+//!   (Algorithm R), returned in their original order.
+//!
+//! All strategies are deterministic for a given seed. Since the random decisions only depend on
+//! the position of a record, sampling both files of paired-end reads with the same seed keeps
+//! the mates in sync.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fastq;
+//! use bio::io::sample::Sample;
+//!
+//! let mut file = Vec::new();
+//! for i in 0..100 {
+//!     file.extend(format!("@read{}\nACGT\n+\nIIII\n", i).bytes());
+//! }
+//!
+//! let records = fastq::Reader::new(&file[..]).records();
+//! let sampled: Vec<_> = records.downsample_exact(100, 10, 42).collect();
+//! assert_eq!(sampled.len(), 10);
+//!
+//! let records = fastq::Reader::new(&file[..]).records();
+//! let reservoir = records.reservoir(5, 42);
+//! assert_eq!(reservoir.len(), 5);
+//! ```
+
+use utils::Xoshiro256;
+
+/// Iterator keeping each record with a fixed probability.
+#[derive(Clone, Debug)]
+pub struct Downsample<I> {
+    inner: I,
+    fraction: f64,
+    rng: Xoshiro256,
+}
+
+impl<I: Iterator> Iterator for Downsample<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            let item = self.inner.next()?;
+            if self.rng.next_f64() < self.fraction {
+                return Some(item);
+            }
+        }
+    }
+}
+
+/// Iterator keeping exactly `k` of `n` records.
+#[derive(Clone, Debug)]
+pub struct DownsampleExact<I> {
+    inner: I,
+    /// Number of records not yet seen.
+    remaining: u64,
+    /// Number of records still to be selected.
+    select: u64,
+    rng: Xoshiro256,
+}
+
+impl<I: Iterator> Iterator for DownsampleExact<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        while self.select > 0 && self.remaining > 0 {
+            let item = self.inner.next()?;
+            let keep = self.rng.below(self.remaining) < self.select;
+            self.remaining -= 1;
+            if keep {
+                self.select -= 1;
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+/// Subsampling of iterators.
+pub trait Sample: Iterator + Sized {
+    /// Keep each record with probability `fraction`.
+    fn downsample(self, fraction: f64, seed: u64) -> Downsample<Self> {
+        assert!(
+            (0.0..=1.0).contains(&fraction),
+            "Expecting fraction between 0 and 1."
+        );
+        Downsample {
+            inner: self,
+            fraction,
+            rng: Xoshiro256::new(seed),
+        }
+    }
+
+    /// Keep exactly `k` of the `n` records of this iterator. If the iterator has less than `n`
+    /// records, fewer records may be returned; records beyond the first `n` are never returned.
+    fn downsample_exact(self, n: u64, k: u64, seed: u64) -> DownsampleExact<Self> {
+        assert!(k <= n, "Expecting to select at most all records.");
+        DownsampleExact {
+            inner: self,
+            remaining: n,
+            select: k,
+            rng: Xoshiro256::new(seed),
+        }
+    }
+
+    /// Select `k` records uniformly at random, returned in their original order. If the
+    /// iterator has at most `k` records, all are returned.
+    fn reservoir(self, k: usize, seed: u64) -> Vec<Self::Item> {
+        let mut rng = Xoshiro256::new(seed);
+        let mut reservoir: Vec<(usize, Self::Item)> = Vec::with_capacity(k);
+        for (i, item) in self.enumerate() {
+            if i < k {
+                reservoir.push((i, item));
+            } else {
+                let j = rng.below(i as u64 + 1) as usize;
+                if j < k {
+                    reservoir[j] = (i, item);
+                }
+            }
+        }
+        reservoir.sort_by_key(|&(i, _)| i);
+        reservoir.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+impl<I: Iterator> Sample for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downsample() {
+        let sampled: Vec<_> = (0..10_000).downsample(0.1, 1).collect();
+        assert!(sampled.len() > 900 && sampled.len() < 1100);
+        assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+        let again: Vec<_> = (0..10_000).downsample(0.1, 1).collect();
+        assert_eq!(sampled, again);
+        assert_ne!(sampled, (0..10_000).downsample(0.1, 2).collect::<Vec<_>>());
+        assert_eq!((0..100).downsample(0.0, 1).count(), 0);
+        assert_eq!((0..100).downsample(1.0, 1).count(), 100);
+    }
+
+    #[test]
+    fn test_downsample_exact() {
+        for k in 0..=20 {
+            let sampled: Vec<_> = (0..20).downsample_exact(20, k, 3).collect();
+            assert_eq!(sampled.len() as u64, k);
+        }
+        // paired files stay in sync
+        let r1: Vec<_> = (0..50).downsample_exact(50, 7, 5).collect();
+        let r2: Vec<_> = (100..150).downsample_exact(50, 7, 5).collect();
+        assert_eq!(r1.iter().map(|i| i + 100).collect::<Vec<_>>(), r2);
+        // each record is selected with probability k / n
+        let mut counts = [0; 10];
+        for seed in 0..2000 {
+            for i in (0..10).downsample_exact(10, 3, seed) {
+                counts[i] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&c| c > 500 && c < 700));
+    }
+
+    #[test]
+    fn test_reservoir() {
+        assert_eq!((0..3).reservoir(5, 1), [0, 1, 2]);
+        let sampled = (0..1000).reservoir(10, 1);
+        assert_eq!(sampled.len(), 10);
+        assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sampled, (0..1000).reservoir(10, 1));
+        let mut counts = [0; 10];
+        for seed in 0..2000 {
+            for i in (0..10).reservoir(3, seed) {
+                counts[i] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&c| c > 500 && c < 700));
+    }
+}
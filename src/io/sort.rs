@@ -0,0 +1,640 @@
+//! External merge sort of record streams too large for memory, e.g. for coordinate-sorting
+//! BED records. Records are collected into runs of fixed length, each run is sorted and
+//! written to a temporary file, and the runs are merged with a k-way merge while reading them
+//! back. If all records fit into a single run, no files are written.
+//!
+//! Records are written to the temporary files via the `Spill` trait, a compact binary
+//! encoding implemented for BED records and for basic types. Streams of records located on
+//! contigs (`GenomicRecord`) can be checked for coordinate order or sorted into it with the
+//! `CoordinateSort` extension trait.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::bed;
+//! use bio::io::sort::ExternalSorter;
+//!
+//! let example = b"chr2\t10\t20\ta\nchr1\t30\t40\tb\nchr1\t5\t8\tc\nchr2\t1\t3\td\n";
+//! let mut reader = bed::Reader::new(&example[..]);
+//! let mut sorter = ExternalSorter::new(|a: &bed::Record, b: &bed::Record| {
+//!     (a.chrom(), a.start()).cmp(&(b.chrom(), b.start()))
+//! })
+//! .run_len(2);
+//! for record in reader.records() {
+//!     sorter.push(record.unwrap()).unwrap();
+//! }
+//! let sorted: Vec<_> = sorter
+//!     .sort()
+//!     .unwrap()
+//!     .map(|record| record.unwrap().start())
+//!     .collect();
+//! assert_eq!(sorted, [5, 30, 1, 10]);
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::vec;
+
+/// Default number of records per run.
+pub const DEFAULT_RUN_LEN: usize = 1_000_000;
+
+/// Counter for unique names of temporary files within this process.
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Records that can be written to and read back from temporary files.
+pub trait Spill: Sized {
+    /// Write the record.
+    fn spill<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+
+    /// Read a record written with `spill`.
+    fn unspill<R: Read>(reader: &mut R) -> io::Result<Self>;
+}
+
+impl Spill for u64 {
+    fn spill<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+
+    fn unspill<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut buf = [0; 8];
+        reader.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+impl Spill for i64 {
+    fn spill<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+
+    fn unspill<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut buf = [0; 8];
+        reader.read_exact(&mut buf)?;
+        Ok(i64::from_le_bytes(buf))
+    }
+}
+
+impl Spill for String {
+    fn spill<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).spill(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn unspill<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = u64::unspill(reader)? as usize;
+        let mut buf = vec![0; len];
+        reader.read_exact(&mut buf)?;
+        String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<T: Spill> Spill for Vec<T> {
+    fn spill<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        (self.len() as u64).spill(writer)?;
+        for item in self {
+            item.spill(writer)?;
+        }
+        Ok(())
+    }
+
+    fn unspill<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let len = u64::unspill(reader)? as usize;
+        (0..len).map(|_| T::unspill(reader)).collect()
+    }
+}
+
+impl<A: Spill, B: Spill> Spill for (A, B) {
+    fn spill<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.spill(writer)?;
+        self.1.spill(writer)
+    }
+
+    fn unspill<R: Read>(reader: &mut R) -> io::Result<Self> {
+        Ok((A::unspill(reader)?, B::unspill(reader)?))
+    }
+}
+
+/// A temporary file holding a sorted run, removed when dropped.
+#[derive(Debug)]
+struct Run {
+    path: PathBuf,
+    len: usize,
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        // the file may not have been created if writing failed
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sorter of records by a comparator, spilling sorted runs to temporary files.
+#[derive(Debug)]
+pub struct ExternalSorter<T, F> {
+    compare: F,
+    run_len: usize,
+    dir: PathBuf,
+    buffer: Vec<T>,
+    runs: Vec<Run>,
+}
+
+impl<T: Spill, F: FnMut(&T, &T) -> Ordering> ExternalSorter<T, F> {
+    /// Create a new sorter with the given comparator. Runs have `DEFAULT_RUN_LEN` records and
+    /// are written to the temporary directory of the system.
+    pub fn new(compare: F) -> Self {
+        ExternalSorter {
+            compare,
+            run_len: DEFAULT_RUN_LEN,
+            dir: env::temp_dir(),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Number of records kept in memory before a run is written (default `DEFAULT_RUN_LEN`).
+    pub fn run_len(mut self, run_len: usize) -> Self {
+        assert!(run_len > 0, "Expecting positive run length.");
+        self.run_len = run_len;
+        self
+    }
+
+    /// Directory for the temporary files (default: the temporary directory of the system).
+    pub fn temp_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.dir = dir.as_ref().to_owned();
+        self
+    }
+
+    /// Add a record, writing a run if the buffer is full.
+    pub fn push(&mut self, record: T) -> io::Result<()> {
+        self.buffer.push(record);
+        if self.buffer.len() >= self.run_len {
+            self.write_run()?;
+        }
+        Ok(())
+    }
+
+    /// Number of runs written so far.
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Sort the added records. Equal records keep the order in which they were added.
+    pub fn sort(mut self) -> io::Result<Sorted<T, F>> {
+        if self.runs.is_empty() {
+            let compare = &mut self.compare;
+            self.buffer.sort_by(|a, b| compare(a, b));
+            return Ok(Sorted {
+                inner: SortedInner::Memory(self.buffer.into_iter()),
+            });
+        }
+        if !self.buffer.is_empty() {
+            self.write_run()?;
+        }
+
+        let mut merge = Merge {
+            compare: self.compare,
+            readers: Vec::with_capacity(self.runs.len()),
+            remaining: Vec::with_capacity(self.runs.len()),
+            heads: Vec::with_capacity(self.runs.len()),
+            heap: Vec::with_capacity(self.runs.len()),
+            runs: Vec::new(),
+        };
+        for run in &self.runs {
+            merge
+                .readers
+                .push(io::BufReader::new(fs::File::open(&run.path)?));
+            merge.remaining.push(run.len);
+            merge.heads.push(None);
+        }
+        merge.runs = self.runs;
+        for i in 0..merge.readers.len() {
+            merge.advance(i)?;
+            if merge.heads[i].is_some() {
+                merge.heap.push(i);
+                merge.sift_up(merge.heap.len() - 1);
+            }
+        }
+        Ok(Sorted {
+            inner: SortedInner::Merge(merge),
+        })
+    }
+
+    /// Sort the buffer and write it as a run.
+    fn write_run(&mut self) -> io::Result<()> {
+        let compare = &mut self.compare;
+        self.buffer.sort_by(|a, b| compare(a, b));
+        let path = self.dir.join(format!(
+            "rust-bio-sort-{}-{}.run",
+            process::id(),
+            RUN_ID.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        let run = Run {
+            path,
+            len: self.buffer.len(),
+        };
+        let mut writer = io::BufWriter::new(fs::File::create(&run.path)?);
+        for record in self.buffer.drain(..) {
+            record.spill(&mut writer)?;
+        }
+        writer.flush()?;
+        self.runs.push(run);
+        Ok(())
+    }
+}
+
+/// K-way merge of sorted runs, with a binary heap of the runs ordered by their next records.
+#[derive(Debug)]
+struct Merge<T, F> {
+    compare: F,
+    readers: Vec<io::BufReader<fs::File>>,
+    /// Number of records left in each run.
+    remaining: Vec<usize>,
+    /// Next record of each run.
+    heads: Vec<Option<T>>,
+    heap: Vec<usize>,
+    runs: Vec<Run>,
+}
+
+impl<T: Spill, F: FnMut(&T, &T) -> Ordering> Merge<T, F> {
+    /// Read the next record of run i.
+    fn advance(&mut self, i: usize) -> io::Result<()> {
+        self.heads[i] = if self.remaining[i] > 0 {
+            self.remaining[i] -= 1;
+            Some(T::unspill(&mut self.readers[i])?)
+        } else {
+            None
+        };
+        Ok(())
+    }
+
+    /// Check whether the head of run i precedes the head of run j, where earlier runs go first
+    /// among equal records.
+    fn less(&mut self, i: usize, j: usize) -> bool {
+        let (a, b) = (
+            self.heads[i].as_ref().expect("Expecting record."),
+            self.heads[j].as_ref().expect("Expecting record."),
+        );
+        (self.compare)(a, b).then(i.cmp(&j)) == Ordering::Less
+    }
+
+    fn sift_up(&mut self, mut k: usize) {
+        while k > 0 {
+            let parent = (k - 1) / 2;
+            if !self.less(self.heap[k], self.heap[parent]) {
+                break;
+            }
+            self.heap.swap(k, parent);
+            k = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut k: usize) {
+        loop {
+            let mut min = k;
+            for child in &[2 * k + 1, 2 * k + 2] {
+                if *child < self.heap.len() && self.less(self.heap[*child], self.heap[min]) {
+                    min = *child;
+                }
+            }
+            if min == k {
+                break;
+            }
+            self.heap.swap(k, min);
+            k = min;
+        }
+    }
+
+    fn next(&mut self) -> Option<io::Result<T>> {
+        let i = *self.heap.first()?;
+        let record = self.heads[i].take().expect("Expecting record.");
+        if let Err(e) = self.advance(i) {
+            self.heap.clear();
+            return Some(Err(e));
+        }
+        if self.heads[i].is_none() {
+            let last = self.heap.pop().expect("Expecting non-empty heap.");
+            if !self.heap.is_empty() {
+                self.heap[0] = last;
+            }
+        }
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+        Some(Ok(record))
+    }
+}
+
+#[derive(Debug)]
+enum SortedInner<T, F> {
+    Memory(vec::IntoIter<T>),
+    Merge(Merge<T, F>),
+}
+
+/// Iterator over the sorted records. The temporary files are removed when it is dropped.
+#[derive(Debug)]
+pub struct Sorted<T, F> {
+    inner: SortedInner<T, F>,
+}
+
+impl<T: Spill, F: FnMut(&T, &T) -> Ordering> Iterator for Sorted<T, F> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<io::Result<T>> {
+        match self.inner {
+            SortedInner::Memory(ref mut records) => records.next().map(Ok),
+            SortedInner::Merge(ref mut merge) => merge.next(),
+        }
+    }
+}
+
+/// Records located on a contig, e.g. BED or GFF records.
+pub trait GenomicRecord {
+    /// Name of the contig.
+    fn contig(&self) -> &str;
+
+    /// Start position on the contig.
+    fn pos(&self) -> u64;
+}
+
+quick_error! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum UnsortedError {
+        Position(record: usize, contig: String, previous: u64, pos: u64) {
+            description("record not sorted by position")
+            display(
+                "record {} at {}:{} precedes the previous record at {}:{}",
+                record, contig, pos, contig, previous
+            )
+        }
+        Contig(record: usize, contig: String) {
+            description("records of a contig not consecutive")
+            display(
+                "record {} is on contig {}, which was followed by other contigs before",
+                record, contig
+            )
+        }
+    }
+}
+
+/// Iterator checking that records are coordinate-sorted.
+#[derive(Debug)]
+pub struct CheckSorted<I> {
+    inner: I,
+    /// Index of the next record.
+    record: usize,
+    /// Contig and position of the previous record.
+    last: Option<(String, u64)>,
+    /// Contigs before the one of the previous record.
+    done: HashSet<String>,
+    failed: bool,
+}
+
+impl<I: Iterator> Iterator for CheckSorted<I>
+where
+    I::Item: GenomicRecord,
+{
+    type Item = Result<I::Item, UnsortedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let item = self.inner.next()?;
+        let record = self.record;
+        self.record += 1;
+        let error = match self.last {
+            Some((ref contig, previous)) if contig == item.contig() => {
+                if item.pos() < previous {
+                    Some(UnsortedError::Position(
+                        record,
+                        contig.clone(),
+                        previous,
+                        item.pos(),
+                    ))
+                } else {
+                    None
+                }
+            }
+            _ if self.done.contains(item.contig()) => {
+                Some(UnsortedError::Contig(record, item.contig().to_owned()))
+            }
+            _ => None,
+        };
+        if let Some(error) = error {
+            self.failed = true;
+            return Some(Err(error));
+        }
+        match self.last {
+            Some((ref mut contig, ref mut pos)) if contig == item.contig() => *pos = item.pos(),
+            _ => {
+                if let Some((contig, _)) = self.last.take() {
+                    self.done.insert(contig);
+                }
+                self.last = Some((item.contig().to_owned(), item.pos()));
+            }
+        }
+        Some(Ok(item))
+    }
+}
+
+/// Comparator of records tagged with the rank of their contig.
+type RankedCompare<T> = fn(&(u64, T), &(u64, T)) -> Ordering;
+
+fn compare_ranked<T: GenomicRecord>(a: &(u64, T), b: &(u64, T)) -> Ordering {
+    (a.0, a.1.pos()).cmp(&(b.0, b.1.pos()))
+}
+
+/// Iterator over coordinate-sorted records.
+#[derive(Debug)]
+pub struct CoordinateSorted<T> {
+    inner: Sorted<(u64, T), RankedCompare<T>>,
+}
+
+impl<T: Spill + GenomicRecord> Iterator for CoordinateSorted<T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<io::Result<T>> {
+        self.inner
+            .next()
+            .map(|result| result.map(|(_, record)| record))
+    }
+}
+
+/// Coordinate order of record streams: records are grouped by contig and sorted by position
+/// within each contig. Contigs may occur in any order, e.g. that of a reference. Most
+/// region-based algorithms assume this order and misbehave silently on unsorted input.
+///
+/// # Example
+///
+/// ```
+/// use bio::genome::region::Region;
+/// use bio::io::sort::{CoordinateSort, UnsortedError};
+///
+/// let regions = vec![
+///     Region::new("chr2", 10, 20),
+///     Region::new("chr1", 5, 15),
+///     Region::new("chr2", 0, 10),
+/// ];
+/// let checked: Result<Vec<_>, _> = regions.clone().into_iter().check_sorted().collect();
+/// assert_eq!(checked, Err(UnsortedError::Contig(2, "chr2".to_owned())));
+///
+/// let sorted: Vec<_> = regions
+///     .into_iter()
+///     .sort_by_coordinate(1000)
+///     .unwrap()
+///     .map(|region| region.unwrap().start)
+///     .collect();
+/// assert_eq!(sorted, [0, 10, 5]);
+/// ```
+pub trait CoordinateSort: Iterator + Sized {
+    /// Check that the records are coordinate-sorted, yielding an error for the first record
+    /// out of order and stopping there.
+    fn check_sorted(self) -> CheckSorted<Self>
+    where
+        Self::Item: GenomicRecord,
+    {
+        CheckSorted {
+            inner: self,
+            record: 0,
+            last: None,
+            done: HashSet::new(),
+            failed: false,
+        }
+    }
+
+    /// Sort the records into coordinate order with an `ExternalSorter` keeping the given
+    /// number of records in memory. Contigs are ordered by their first occurrence, and
+    /// records with equal positions keep their order.
+    fn sort_by_coordinate(self, run_len: usize) -> io::Result<CoordinateSorted<Self::Item>>
+    where
+        Self::Item: GenomicRecord + Spill,
+    {
+        let compare: RankedCompare<Self::Item> = compare_ranked;
+        let mut sorter = ExternalSorter::new(compare).run_len(run_len);
+        let mut ranks: HashMap<String, u64> = HashMap::new();
+        for record in self {
+            let rank = match ranks.get(record.contig()) {
+                Some(&rank) => rank,
+                None => {
+                    let rank = ranks.len() as u64;
+                    ranks.insert(record.contig().to_owned(), rank);
+                    rank
+                }
+            };
+            sorter.push((rank, record))?;
+        }
+        Ok(CoordinateSorted {
+            inner: sorter.sort()?,
+        })
+    }
+}
+
+impl<I: Iterator> CoordinateSort for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use genome::region::Region;
+    use io::bed;
+
+    #[test]
+    fn test_external_sort() {
+        let records: Vec<(u64, u64)> = (0..1000).map(|i| ((i * 7919) % 13, i)).collect();
+        for &run_len in &[1, 7, 100, 1000, 5000] {
+            let mut sorter = ExternalSorter::new(|a: &(u64, u64), b: &(u64, u64)| a.0.cmp(&b.0))
+                .run_len(run_len);
+            for &record in &records {
+                sorter.push(record).unwrap();
+            }
+            assert_eq!(sorter.runs(), 1000 / run_len);
+            let sorted: Vec<_> = sorter.sort().unwrap().map(|r| r.unwrap()).collect();
+            let mut expected = records.clone();
+            // stable, as the sort of the sorter
+            expected.sort_by_key(|r| r.0);
+            assert_eq!(sorted, expected);
+        }
+    }
+
+    #[test]
+    fn test_temp_files_removed() {
+        let dir = env::temp_dir().join(format!("rust-bio-test-sort-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut sorter = ExternalSorter::new(|a: &String, b: &String| b.cmp(a))
+            .run_len(2)
+            .temp_dir(&dir);
+        for word in &["b", "d", "a", "c", "e"] {
+            sorter.push(word.to_string()).unwrap();
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        let mut sorted = sorter.sort().unwrap();
+        assert_eq!(sorted.next().unwrap().unwrap(), "e");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        drop(sorted);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_sorted() {
+        let regions = vec![
+            Region::new("chr1", 0, 10),
+            Region::new("chr1", 5, 10),
+            Region::new("chr1", 5, 7),
+            Region::new("chrX", 1, 2),
+            Region::new("chr2", 1, 2),
+            Region::new("chr2", 0, 2),
+        ];
+        let mut checked = regions.clone().into_iter().check_sorted();
+        assert_eq!(checked.by_ref().filter(|r| r.is_ok()).count(), 5);
+        assert!(checked.next().is_none());
+        let checked: Result<Vec<_>, _> = regions.into_iter().check_sorted().collect();
+        assert_eq!(
+            checked,
+            Err(UnsortedError::Position(5, "chr2".to_owned(), 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_sort_by_coordinate() {
+        let regions: Vec<Region> = (0..100u64)
+            .map(|i| Region::new(["chr3", "chr1", "chr2"][i as usize % 3], (i * 37) % 11, 20))
+            .collect();
+        for &run_len in &[1, 8, 1000] {
+            let sorted: Vec<Region> = regions
+                .clone()
+                .into_iter()
+                .sort_by_coordinate(run_len)
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            assert_eq!(sorted.len(), regions.len());
+            assert_eq!(sorted[0].contig, "chr3");
+            assert_eq!(sorted[99].contig, "chr2");
+            assert!(sorted.iter().cloned().check_sorted().all(|r| r.is_ok()));
+        }
+    }
+
+    #[test]
+    fn test_sort_bed() {
+        let example = b"chr1\t5\t5000\tname1\t0.5\nchr1\t3\t10\tname3\t1\nchr0\t8\t9\tname2\t0\n";
+        let mut reader = bed::Reader::new(&example[..]);
+        let mut sorter =
+            ExternalSorter::new(|a: &bed::Record, b: &bed::Record| a.start().cmp(&b.start()))
+                .run_len(1);
+        for record in reader.records() {
+            sorter.push(record.unwrap()).unwrap();
+        }
+        let sorted: Vec<_> = sorter.sort().unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(sorted[0].chrom(), "chr1");
+        assert_eq!(sorted[0].end(), 10);
+        assert_eq!(sorted[1].score(), Some("0.5"));
+        assert_eq!(sorted[2].name(), Some("name2"));
+    }
+}
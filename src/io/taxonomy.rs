@@ -0,0 +1,243 @@
+//! Reading of the NCBI taxonomy dump (`nodes.dmp` and `names.dmp` from
+//! <ftp://ftp.ncbi.nih.gov/pub/taxonomy/taxdump.tar.gz>) into a `Taxonomy`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::taxonomy;
+//!
+//! let nodes = b"1\t|\t1\t|\tno rank\t|\n\
+//! 2\t|\t131567\t|\tsuperkingdom\t|\n\
+//! 131567\t|\t1\t|\tno rank\t|\n\
+//! 562\t|\t561\t|\tspecies\t|\n\
+//! 561\t|\t2\t|\tgenus\t|\n";
+//! let names = b"562\t|\tEscherichia coli\t|\t\t|\tscientific name\t|\n\
+//! 562\t|\tE. coli\t|\t\t|\tcommon name\t|\n";
+//!
+//! let tax = taxonomy::Reader::new(&nodes[..], &names[..]).read().unwrap();
+//! assert_eq!(tax.root(), 1);
+//! assert_eq!(tax.lineage(562), vec![1, 131567, 2, 561, 562]);
+//! assert_eq!(tax.rank(561), Some("genus"));
+//! assert_eq!(tax.name(562), Some("Escherichia coli"));
+//! ```
+
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use data_structures::taxonomy::{TaxId, Taxonomy};
+
+/// A record of `nodes.dmp`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Node {
+    pub taxid: TaxId,
+    pub parent: TaxId,
+    pub rank: String,
+}
+
+/// A record of `names.dmp`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Name {
+    pub taxid: TaxId,
+    pub name: String,
+    pub unique_name: Option<String>,
+    /// The name class, e.g. "scientific name" or "synonym".
+    pub class: String,
+}
+
+fn invalid_data(msg: &str, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", msg, line.trim_end()),
+    )
+}
+
+/// Split a line of a dump file into its fields, which are separated by `\t|\t`.
+fn fields(line: &str) -> Vec<&str> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let line = line.trim_end_matches("\t|");
+    line.split("\t|\t").collect()
+}
+
+fn parse_taxid(field: &str, line: &str) -> io::Result<TaxId> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| invalid_data("Invalid taxon id", line))
+}
+
+/// Parse a line of `nodes.dmp`.
+pub fn parse_node(line: &str) -> io::Result<Node> {
+    let fields = fields(line);
+    if fields.len() < 3 {
+        return Err(invalid_data(
+            "Expecting at least three fields in node",
+            line,
+        ));
+    }
+    Ok(Node {
+        taxid: parse_taxid(fields[0], line)?,
+        parent: parse_taxid(fields[1], line)?,
+        rank: fields[2].to_owned(),
+    })
+}
+
+/// Parse a line of `names.dmp`.
+pub fn parse_name(line: &str) -> io::Result<Name> {
+    let fields = fields(line);
+    if fields.len() < 4 {
+        return Err(invalid_data("Expecting four fields in name", line));
+    }
+    Ok(Name {
+        taxid: parse_taxid(fields[0], line)?,
+        name: fields[1].to_owned(),
+        unique_name: if fields[2].is_empty() {
+            None
+        } else {
+            Some(fields[2].to_owned())
+        },
+        class: fields[3].to_owned(),
+    })
+}
+
+/// Iterate over the records of a dump file, parsing each non-empty line with `parse`.
+fn records<R: BufRead, T, F>(reader: R, parse: F) -> impl Iterator<Item = io::Result<T>>
+where
+    F: Fn(&str) -> io::Result<T>,
+{
+    reader.lines().filter_map(move |line| match line {
+        Ok(ref line) if line.trim().is_empty() => None,
+        Ok(line) => Some(parse(&line)),
+        Err(e) => Some(Err(e)),
+    })
+}
+
+/// Iterate over the records of `nodes.dmp`.
+pub fn nodes<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<Node>> {
+    records(reader, parse_node)
+}
+
+/// Iterate over the records of `names.dmp`.
+pub fn names<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<Name>> {
+    records(reader, parse_name)
+}
+
+/// A reader for the NCBI taxonomy dump.
+#[derive(Debug)]
+pub struct Reader<N: io::Read, M: io::Read> {
+    nodes: io::BufReader<N>,
+    names: io::BufReader<M>,
+}
+
+impl Reader<fs::File, fs::File> {
+    /// Read `nodes.dmp` and `names.dmp` from the given directory, e.g. an extracted
+    /// `taxdump.tar.gz`.
+    pub fn from_dir<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        Ok(Reader::new(
+            fs::File::open(path.join("nodes.dmp"))?,
+            fs::File::open(path.join("names.dmp"))?,
+        ))
+    }
+}
+
+impl<N: io::Read, M: io::Read> Reader<N, M> {
+    /// Read from the given readers of `nodes.dmp` and `names.dmp`.
+    pub fn new(nodes: N, names: M) -> Self {
+        Reader {
+            nodes: io::BufReader::new(nodes),
+            names: io::BufReader::new(names),
+        }
+    }
+
+    /// Read the taxonomy. The root is the node that is its own parent. Only scientific names
+    /// are stored.
+    pub fn read(self) -> io::Result<Taxonomy> {
+        let mut root = None;
+        let mut children = Vec::new();
+        let mut ranks = Vec::new();
+        for node in nodes(self.nodes) {
+            let node = node?;
+            if node.taxid == node.parent {
+                if root.is_some() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Multiple root nodes in taxonomy.",
+                    ));
+                }
+                root = Some(node.taxid);
+            } else {
+                children.push((node.taxid, node.parent));
+            }
+            ranks.push((node.taxid, node.rank));
+        }
+        let root = root.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "No root node in taxonomy.")
+        })?;
+
+        let mut taxonomy = Taxonomy::new(root);
+        for (taxid, parent) in children {
+            taxonomy.add_node(taxid, parent);
+        }
+        for (taxid, rank) in ranks {
+            taxonomy.set_rank(taxid, &rank);
+        }
+        for name in names(self.names) {
+            let name = name?;
+            if name.class == "scientific name" {
+                taxonomy.set_name(name.taxid, &name.name);
+            }
+        }
+
+        Ok(taxonomy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODES: &[u8] =
+        b"1\t|\t1\t|\tno rank\t|\t\t|\t8\t|\t0\t|\t1\t|\t0\t|\t0\t|\t0\t|\t0\t|\t0\t|\t\t|
+2\t|\t131567\t|\tsuperkingdom\t|\t\t|\t0\t|\t0\t|\t11\t|\t0\t|\t0\t|\t0\t|\t0\t|\t0\t|\t\t|
+131567\t|\t1\t|\tno rank\t|\t\t|\t8\t|\t1\t|\t1\t|\t1\t|\t0\t|\t1\t|\t1\t|\t0\t|\t\t|
+1224\t|\t2\t|\tphylum\t|\t\t|\t0\t|\t1\t|\t11\t|\t1\t|\t0\t|\t1\t|\t0\t|\t0\t|\t\t|
+";
+
+    const NAMES: &[u8] = b"1\t|\tall\t|\t\t|\tsynonym\t|
+1\t|\troot\t|\t\t|\tscientific name\t|
+2\t|\tBacteria\t|\tBacteria <bacteria>\t|\tscientific name\t|
+1224\t|\tProteobacteria\t|\t\t|\tscientific name\t|
+";
+
+    #[test]
+    fn test_parse() {
+        let name =
+            parse_name("2\t|\tBacteria\t|\tBacteria <bacteria>\t|\tscientific name\t|\n").unwrap();
+        assert_eq!(name.taxid, 2);
+        assert_eq!(name.name, "Bacteria");
+        assert_eq!(name.unique_name, Some("Bacteria <bacteria>".to_owned()));
+        assert_eq!(name.class, "scientific name");
+        assert!(parse_node("x\t|\t1\t|\tgenus\t|").is_err());
+        assert!(parse_node("1\t|\t1").is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        let taxonomy = Reader::new(NODES, NAMES).read().unwrap();
+        assert_eq!(taxonomy.len(), 4);
+        assert_eq!(taxonomy.root(), 1);
+        assert_eq!(taxonomy.name(1), Some("root"));
+        assert_eq!(taxonomy.name(2), Some("Bacteria"));
+        assert_eq!(taxonomy.rank(1224), Some("phylum"));
+        assert_eq!(taxonomy.lineage(1224), vec![1, 131567, 2, 1224]);
+        assert_eq!(taxonomy.ancestor_at_rank(1224, "superkingdom"), Some(2));
+    }
+
+    #[test]
+    fn test_missing_root() {
+        let nodes = b"2\t|\t1\t|\tsuperkingdom\t|\n";
+        assert!(Reader::new(&nodes[..], &b""[..]).read().is_err());
+    }
+}
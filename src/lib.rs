@@ -85,44 +85,95 @@
 //! ```
 //!
 //! Documentation and further examples for each module can be found in the module descriptions below.
+//!
+//! # Features
+//!
+//! The default `std` feature enables all modules. Without it, the crate is compiled with
+//! `no_std` and `alloc`, e.g. for WebAssembly or embedded targets, and provides the alignment
+//! kernels (`alignment::pairwise` including `banded`, `alignment::sparse` and
+//! `alignment::distance`), the pattern matchers (`pattern_matching`, except `bom` and `pssm`)
+//! and the k-mer hashers of `utils`. The alignment types are then defined by this crate with
+//! the same fields, instead of being re-exported from `bio-types`, which needs std.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg_attr(not(feature = "std"), macro_use)]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
 
+#[cfg(feature = "std")]
 extern crate bio_types;
 
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate approx;
+#[cfg(feature = "std")]
 extern crate bit_set;
+#[cfg(feature = "std")]
 extern crate bytecount;
+#[cfg(feature = "std")]
 extern crate csv;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate custom_derive;
+#[cfg(feature = "std")]
 extern crate itertools;
+#[cfg(feature = "std")]
 extern crate itertools_num;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "std")]
 extern crate multimap;
+#[cfg(feature = "std")]
 extern crate ndarray;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate newtype_derive;
+#[cfg(feature = "std")]
 extern crate num_integer;
 extern crate num_traits;
+#[cfg(feature = "std")]
 extern crate ordered_float;
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate quick_error;
+#[cfg(feature = "std")]
 extern crate regex;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "std")]
 extern crate bv;
+#[cfg(feature = "std")]
 extern crate fnv;
+#[cfg(not(feature = "std"))]
+extern crate hashbrown;
+#[cfg(not(feature = "std"))]
+extern crate rustc_hash;
+#[cfg(feature = "std")]
 extern crate statrs;
+#[cfg(feature = "std")]
 extern crate vec_map;
 
 pub mod alignment;
+#[cfg(feature = "std")]
 pub mod alphabets;
 pub mod data_structures;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod genome;
+#[cfg(feature = "std")]
 pub mod io;
 pub mod pattern_matching;
+#[cfg(feature = "std")]
 pub mod scores;
+#[cfg(feature = "std")]
 pub mod seq_analysis;
+#[cfg(feature = "std")]
 pub mod stats;
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -38,6 +38,8 @@
 //! assert_eq!(occ, [8, 25]);
 //! ```
 
+use alloc::vec::Vec;
+
 use utils::TextSlice;
 
 /// Algorithm of Horspool.
@@ -22,8 +22,9 @@
 //! assert_eq!(occ, [4, 15]);
 //! ```
 
-use std::borrow::Borrow;
-use std::iter::{repeat, Enumerate};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::iter::{repeat, Enumerate};
 
 use utils::TextSlice;
 
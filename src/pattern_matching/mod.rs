@@ -18,10 +18,12 @@
 //! Another fast pattern matching algorithm is available in the twoway crate: https://crates.io/crates/twoway
 
 pub mod bndm;
+#[cfg(feature = "std")]
 pub mod bom;
 pub mod horspool;
 pub mod kmp;
 pub mod myers;
+#[cfg(feature = "std")]
 pub mod pssm;
 pub mod shift_and;
 pub mod ukkonen;
@@ -45,7 +45,7 @@ use super::*;
 /// specifying additional ambiguities (`builder.ambig(b'A', b"MRWVHDN")`, etc...).
 #[derive(Default, Clone, Eq, PartialEq)]
 pub struct MyersBuilder {
-    ambigs: HashMap<u8, Vec<u8>>,
+    ambigs: BTreeMap<u8, Vec<u8>>,
     wildcards: Vec<u8>,
 }
 
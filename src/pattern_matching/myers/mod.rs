@@ -182,14 +182,15 @@
 //!
 //! For more examples see the documentation of [`MyersBuilder`](struct.MyersBuilder.html).
 
-use std::borrow::Borrow;
-use std::cmp::min;
-use std::collections::HashMap;
-use std::iter;
-use std::mem::size_of;
-use std::ops::Range;
-use std::ops::*;
-use std::u64;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::min;
+use core::iter;
+use core::mem::size_of;
+use core::ops::Range;
+use core::ops::*;
+use core::u64;
 
 use num_traits::{Bounded, FromPrimitive, One, PrimInt, ToPrimitive, WrappingAdd, Zero};
 
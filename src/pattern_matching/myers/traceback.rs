@@ -226,6 +226,7 @@ where
     }
 
     // Useful for debugging
+    #[cfg(feature = "std")]
     #[allow(dead_code)]
     fn print_tb_matrix(&self, pos: usize) {
         let states = self.states[..pos + 1]
@@ -18,8 +18,8 @@
 //! assert_eq!(occ, 8);
 //! ```
 
-use std::borrow::Borrow;
-use std::iter::Enumerate;
+use core::borrow::Borrow;
+use core::iter::Enumerate;
 
 /// `ShiftAnd` algorithm.
 pub struct ShiftAnd {
@@ -24,10 +24,11 @@
 //! assert_eq!(occ, [(13, 1), (14, 1)]);
 //! ```
 
-use std::borrow::Borrow;
-use std::cmp::min;
-use std::iter;
-use std::iter::repeat;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::min;
+use core::iter;
+use core::iter::repeat;
 
 use utils::TextSlice;
 
@@ -0,0 +1,223 @@
+//! Estimation of the average nucleotide identity (ANI) between genomes from MinHash sketches
+//! of their canonical k-mers (Mash, Ondov et al., 2016).
+//!
+//! A sketch keeps the `size` smallest hash values of the k-mers of a genome. The Jaccard index
+//! `j` of two genomes is estimated from the fraction of shared hashes among the smallest `size`
+//! hashes of the union of both sketches. The Mash distance `D = -1/k * ln(2j / (1 + j))`
+//! approximates the per base mutation rate, such that `1 - D` estimates the ANI.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::ani::{ani_matrix, MinHashSketch};
+//!
+//! let genome_a = b"ACGTTGCATGCATCGATCGACTAGCTAGCATCGACTACGACTAGCTTAG";
+//! let mut genome_b = genome_a.to_vec();
+//! genome_b[25] = b'A'; // one substitution
+//!
+//! let sketches: Vec<MinHashSketch> = [&genome_a[..], &genome_b[..]]
+//!     .iter()
+//!     .map(|genome| MinHashSketch::new(genome, 11, 1000))
+//!     .collect();
+//! let matrix = ani_matrix(&sketches);
+//! assert_eq!(matrix[[0, 0]], 1.0);
+//! assert!(matrix[[0, 1]] > 0.95 && matrix[[0, 1]] < 1.0);
+//! assert_eq!(matrix[[0, 1]], matrix[[1, 0]]);
+//! ```
+
+use std::collections::BTreeSet;
+
+use ndarray::Array2;
+
+use alphabets::dna::canonical_kmers;
+use utils::{KmerHasher, ShortKeyHasher};
+
+/// A bottom-s MinHash sketch of the canonical k-mers of a sequence.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MinHashSketch {
+    k: usize,
+    size: usize,
+    hashes: Vec<u64>,
+}
+
+impl MinHashSketch {
+    /// Sketch the given sequence, keeping the `size` smallest k-mer hashes.
+    pub fn new(seq: &[u8], k: usize, size: usize) -> Self {
+        Self::from_seqs(&[seq], k, size)
+    }
+
+    /// Sketch a genome consisting of multiple sequences (e.g. contigs or chromosomes).
+    pub fn from_seqs<S: AsRef<[u8]>>(seqs: &[S], k: usize, size: usize) -> Self {
+        Self::from_seqs_with_hasher(seqs, k, size, &ShortKeyHasher::default())
+    }
+
+    /// Sketch a genome consisting of multiple sequences with the given k-mer hash function.
+    /// Only sketches built with the same hash function can be compared.
+    pub fn from_seqs_with_hasher<S: AsRef<[u8]>, H: KmerHasher>(
+        seqs: &[S],
+        k: usize,
+        size: usize,
+        hasher: &H,
+    ) -> Self {
+        assert!(size > 0, "Expecting positive sketch size.");
+        let mut hashes = BTreeSet::new();
+        for seq in seqs {
+            for kmer in canonical_kmers(seq.as_ref(), k) {
+                let h = hasher.hash_kmer(kmer, k);
+                if hashes.len() < size {
+                    hashes.insert(h);
+                } else if h < *hashes.iter().next_back().unwrap() && hashes.insert(h) {
+                    let max = *hashes.iter().next_back().unwrap();
+                    hashes.remove(&max);
+                }
+            }
+        }
+        MinHashSketch {
+            k,
+            size,
+            hashes: hashes.into_iter().collect(),
+        }
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The sorted hashes of the sketch.
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Estimate the Jaccard index of the k-mer sets of both sketched sequences.
+    pub fn jaccard(&self, other: &MinHashSketch) -> f64 {
+        assert_eq!(self.k, other.k, "Sketches must use the same k.");
+        let size = self.size.min(other.size);
+        let (mut i, mut j) = (0, 0);
+        let (mut union, mut shared) = (0, 0);
+        // merge the sorted hashes until the bottom `size` hashes of the union are seen
+        while union < size && i < self.hashes.len() && j < other.hashes.len() {
+            let (a, b) = (self.hashes[i], other.hashes[j]);
+            if a == b {
+                shared += 1;
+            }
+            if a <= b {
+                i += 1;
+            }
+            if b <= a {
+                j += 1;
+            }
+            union += 1;
+        }
+        union += (self.hashes.len() - i + other.hashes.len() - j).min(size - union);
+        if union == 0 {
+            0.0
+        } else {
+            shared as f64 / union as f64
+        }
+    }
+
+    /// Mash distance, an estimate of the mutation rate between both sequences.
+    /// Sketches without shared hashes have distance 1.
+    pub fn distance(&self, other: &MinHashSketch) -> f64 {
+        let j = self.jaccard(other);
+        if j == 0.0 {
+            1.0
+        } else {
+            (-(2.0 * j / (1.0 + j)).ln() / self.k as f64).min(1.0)
+        }
+    }
+
+    /// Estimated average nucleotide identity of both sequences.
+    pub fn ani(&self, other: &MinHashSketch) -> f64 {
+        1.0 - self.distance(other)
+    }
+}
+
+/// All-vs-all ANI matrix of the given sketches.
+pub fn ani_matrix(sketches: &[MinHashSketch]) -> Array2<f64> {
+    let n = sketches.len();
+    let mut matrix = Array2::from_elem((n, n), 1.0);
+    for i in 0..n {
+        for j in i + 1..n {
+            let ani = sketches[i].ani(&sketches[j]);
+            matrix[[i, j]] = ani;
+            matrix[[j, i]] = ani;
+        }
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alphabets::dna;
+
+    /// A pseudo random genome from a linear congruential generator.
+    fn genome(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                b"ACGT"[(state >> 62) as usize]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sketch() {
+        let g = genome(5000, 1);
+        let sketch = MinHashSketch::new(&g, 15, 100);
+        assert_eq!(sketch.hashes().len(), 100);
+        assert!(sketch.hashes().windows(2).all(|w| w[0] < w[1]));
+        // strand independent
+        assert_eq!(MinHashSketch::new(&dna::revcomp(&g), 15, 100), sketch);
+        // contigs
+        let split = MinHashSketch::from_seqs(&[&g[..2500], &g[2500 - 14..]], 15, 100);
+        assert_eq!(split, sketch);
+    }
+
+    #[test]
+    fn test_ani() {
+        let a = genome(20000, 1);
+        let mut b = a.clone();
+        // mutate every 100th base
+        for i in (50..b.len()).step_by(100) {
+            b[i] = if b[i] == b'A' { b'C' } else { b'A' };
+        }
+        let c = genome(20000, 2);
+        let sketches: Vec<MinHashSketch> = [&a, &b, &c]
+            .iter()
+            .map(|g| MinHashSketch::new(g, 21, 2000))
+            .collect();
+        assert_relative_eq!(sketches[0].jaccard(&sketches[0]), 1.0);
+        let matrix = ani_matrix(&sketches);
+        assert!((matrix[[0, 1]] - 0.99).abs() < 0.003);
+        assert!(matrix[[0, 2]] < 0.8);
+        assert_eq!(matrix[[2, 2]], 1.0);
+    }
+
+    #[test]
+    fn test_hashers() {
+        use utils::{InvertibleKmerHasher, NtHasher};
+
+        fn ani<H: KmerHasher>(a: &[u8], b: &[u8], hasher: &H) -> f64 {
+            let sketch = |g: &[u8]| MinHashSketch::from_seqs_with_hasher(&[g], 21, 2000, hasher);
+            sketch(a).ani(&sketch(b))
+        }
+
+        let a = genome(20000, 1);
+        let mut b = a.clone();
+        for i in (50..b.len()).step_by(100) {
+            b[i] = if b[i] == b'A' { b'C' } else { b'A' };
+        }
+        assert!((ani(&a, &b, &InvertibleKmerHasher) - 0.99).abs() < 0.003);
+        assert!((ani(&a, &b, &NtHasher) - 0.99).abs() < 0.003);
+        let default = MinHashSketch::new(&a, 21, 100);
+        let explicit =
+            MinHashSketch::from_seqs_with_hasher(&[&a], 21, 100, &ShortKeyHasher::default());
+        assert_eq!(default, explicit);
+    }
+}
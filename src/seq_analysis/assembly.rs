@@ -0,0 +1,281 @@
+//! Contiguity statistics of genome assemblies, such as N50, L50, NG50 and auN.
+//!
+//! `AssemblyMetrics` collects the lengths, base composition and gaps (runs of `N`) of the
+//! sequences of an assembly, e.g. from the records of a FASTA reader, and summarizes them in an
+//! `AssemblySummary`. N50 is the length of the shortest sequence among the longest sequences
+//! covering half of the assembly, and L50 the number of these sequences. NG50 and LG50 are
+//! defined likewise, relative to an (estimated) genome size instead of the assembly size. auN
+//! is the area under the Nx curve, i.e. the length-weighted mean sequence length.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fasta;
+//! use bio::seq_analysis::assembly::AssemblyMetrics;
+//!
+//! let fasta = b">ctg1\nACGTACGTACNNNNNACGT\n>ctg2\nGGCCGGCC\n>ctg3\nATATA\n";
+//! let mut metrics = AssemblyMetrics::new();
+//! metrics.add_fasta(fasta::Reader::new(&fasta[..])).unwrap();
+//!
+//! let summary = metrics.summary(Some(50));
+//! assert_eq!(summary.sequences, 3);
+//! assert_eq!(summary.total_len, 32);
+//! assert_eq!((summary.n50, summary.l50), (19, 1));
+//! assert_eq!((summary.ng50, summary.lg50), (Some(8), Some(2)));
+//! assert_eq!(summary.gaps, 1);
+//! assert_eq!(summary.gap_len, 5);
+//! ```
+
+use std::io;
+
+use io::fasta;
+use seq_analysis::scaffold::find_gaps;
+
+/// Summary statistics of an assembly.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssemblySummary {
+    pub sequences: usize,
+    pub total_len: u64,
+    pub min_len: u64,
+    pub max_len: u64,
+    pub mean_len: f64,
+    pub n50: u64,
+    pub l50: usize,
+    pub n90: u64,
+    pub l90: usize,
+    /// NG50 and LG50, given a genome size (`None` if the assembly covers less than half of it).
+    pub ng50: Option<u64>,
+    pub lg50: Option<usize>,
+    pub au_n: f64,
+    /// GC content of the `A`, `C`, `G` and `T` bases.
+    pub gc_content: f64,
+    /// Number of `N` bases.
+    pub n_bases: u64,
+    /// Number and total length of gaps, i.e. runs of `N` of at least the minimal gap length.
+    pub gaps: usize,
+    pub gap_len: u64,
+    pub max_gap_len: u64,
+}
+
+/// A bin of the sequence length histogram, for lengths `lower..upper`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LengthBin {
+    pub lower: u64,
+    /// Exclusive upper bound, `None` for the last bin.
+    pub upper: Option<u64>,
+    pub count: usize,
+    pub total_len: u64,
+}
+
+/// Metrics of the sequences of an assembly.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssemblyMetrics {
+    min_gap: usize,
+    lengths: Vec<u64>,
+    gc: u64,
+    acgt: u64,
+    n_bases: u64,
+    gaps: Vec<u64>,
+}
+
+impl Default for AssemblyMetrics {
+    fn default() -> Self {
+        AssemblyMetrics {
+            min_gap: 1,
+            lengths: Vec::new(),
+            gc: 0,
+            acgt: 0,
+            n_bases: 0,
+            gaps: Vec::new(),
+        }
+    }
+}
+
+impl AssemblyMetrics {
+    /// Create new metrics, counting every run of `N` as a gap.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimal length of runs of `N` counted as gaps.
+    pub fn min_gap(mut self, min_gap: usize) -> Self {
+        assert!(min_gap > 0, "Expecting positive minimal gap length.");
+        self.min_gap = min_gap;
+        self
+    }
+
+    /// Add a sequence.
+    pub fn add(&mut self, seq: &[u8]) {
+        self.lengths.push(seq.len() as u64);
+        for &b in seq {
+            match b.to_ascii_uppercase() {
+                b'G' | b'C' => {
+                    self.gc += 1;
+                    self.acgt += 1;
+                }
+                b'A' | b'T' => self.acgt += 1,
+                b'N' => self.n_bases += 1,
+                _ => (),
+            }
+        }
+        self.gaps.extend(
+            find_gaps(seq, self.min_gap)
+                .into_iter()
+                .map(|gap| gap.len() as u64),
+        );
+    }
+
+    /// Add the sequence of a FASTA record.
+    pub fn add_record(&mut self, record: &fasta::Record) {
+        self.add(record.seq());
+    }
+
+    /// Add all records of a FASTA reader.
+    pub fn add_fasta<R: io::Read>(&mut self, reader: fasta::Reader<R>) -> io::Result<()> {
+        for record in reader.records() {
+            self.add_record(&record?);
+        }
+        Ok(())
+    }
+
+    /// Sequence lengths, in decreasing order.
+    fn sorted_lengths(&self) -> Vec<u64> {
+        let mut lengths = self.lengths.clone();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths
+    }
+
+    /// Nx (length) and Lx (count) of the sequences relative to the given total length, for `x`
+    /// in `(0, 100]`. `None` if the sequences cover less than `x` percent of the total.
+    fn nx_of(lengths: &[u64], x: f64, total: u64) -> Option<(u64, usize)> {
+        assert!(
+            x > 0.0 && x <= 100.0,
+            "Expecting percentage between 0 and 100."
+        );
+        let target = total as f64 * x / 100.0;
+        let mut sum = 0;
+        for (i, &len) in lengths.iter().enumerate() {
+            sum += len;
+            if sum as f64 >= target {
+                return Some((len, i + 1));
+            }
+        }
+        None
+    }
+
+    /// Nx and Lx of the assembly, e.g. N50 and L50 for `x = 50`. `None` without sequences.
+    pub fn nx(&self, x: f64) -> Option<(u64, usize)> {
+        let total = self.lengths.iter().sum();
+        Self::nx_of(&self.sorted_lengths(), x, total).filter(|_| total > 0)
+    }
+
+    /// NGx and LGx of the assembly for the given genome size, e.g. NG50 and LG50 for `x = 50`.
+    pub fn ngx(&self, x: f64, genome_size: u64) -> Option<(u64, usize)> {
+        Self::nx_of(&self.sorted_lengths(), x, genome_size)
+    }
+
+    /// Histogram of the sequence lengths for the given increasing bin boundaries. The first bin
+    /// starts at 0 and the last one is open-ended.
+    pub fn histogram(&self, boundaries: &[u64]) -> Vec<LengthBin> {
+        assert!(
+            boundaries.windows(2).all(|w| w[0] < w[1]),
+            "Expecting increasing bin boundaries."
+        );
+        let mut bins: Vec<_> = (0..=boundaries.len())
+            .map(|i| LengthBin {
+                lower: if i == 0 { 0 } else { boundaries[i - 1] },
+                upper: boundaries.get(i).cloned(),
+                count: 0,
+                total_len: 0,
+            })
+            .collect();
+        for &len in &self.lengths {
+            let bin = &mut bins[boundaries.partition_point(|&b| b <= len)];
+            bin.count += 1;
+            bin.total_len += len;
+        }
+        bins
+    }
+
+    /// Summary statistics, with NG50 and LG50 for the given genome size.
+    pub fn summary(&self, genome_size: Option<u64>) -> AssemblySummary {
+        let lengths = self.sorted_lengths();
+        let total_len: u64 = lengths.iter().sum();
+        let (n50, l50) = Self::nx_of(&lengths, 50.0, total_len).unwrap_or((0, 0));
+        let (n90, l90) = Self::nx_of(&lengths, 90.0, total_len).unwrap_or((0, 0));
+        let ng = genome_size.and_then(|size| Self::nx_of(&lengths, 50.0, size));
+        let squares: f64 = lengths.iter().map(|&len| (len as f64).powi(2)).sum();
+        let ratio = |a: f64, b: f64| if b > 0.0 { a / b } else { 0.0 };
+        AssemblySummary {
+            sequences: lengths.len(),
+            total_len,
+            min_len: lengths.last().cloned().unwrap_or(0),
+            max_len: lengths.first().cloned().unwrap_or(0),
+            mean_len: ratio(total_len as f64, lengths.len() as f64),
+            n50,
+            l50,
+            n90,
+            l90,
+            ng50: ng.map(|(n, _)| n),
+            lg50: ng.map(|(_, l)| l),
+            au_n: ratio(squares, total_len as f64),
+            gc_content: ratio(self.gc as f64, self.acgt as f64),
+            n_bases: self.n_bases,
+            gaps: self.gaps.len(),
+            gap_len: self.gaps.iter().sum(),
+            max_gap_len: self.gaps.iter().cloned().max().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nx() {
+        let mut metrics = AssemblyMetrics::new();
+        for &len in &[80, 70, 50, 40, 30, 20, 10] {
+            metrics.add(&vec![b'A'; len]);
+        }
+        // total 300
+        assert_eq!(metrics.nx(50.0), Some((70, 2)));
+        assert_eq!(metrics.nx(90.0), Some((30, 5)));
+        assert_eq!(metrics.nx(100.0), Some((10, 7)));
+        assert_eq!(metrics.ngx(50.0, 1000), None);
+        assert_eq!(metrics.ngx(50.0, 400), Some((50, 3)));
+        let summary = metrics.summary(None);
+        assert_eq!((summary.n90, summary.l90), (30, 5));
+        assert_eq!((summary.min_len, summary.max_len), (10, 80));
+        assert_relative_eq!(summary.mean_len, 300.0 / 7.0);
+        // (6400 + 4900 + 2500 + 1600 + 900 + 400 + 100) / 300
+        assert_relative_eq!(summary.au_n, 16800.0 / 300.0);
+        assert_eq!(summary.ng50, None);
+        assert_eq!(summary.gc_content, 0.0);
+        assert_eq!(AssemblyMetrics::new().nx(50.0), None);
+    }
+
+    #[test]
+    fn test_gaps_and_histogram() {
+        let mut metrics = AssemblyMetrics::new().min_gap(3);
+        metrics.add(b"ACGTNNNNNNGCnGCnnnAT");
+        metrics.add(b"GC");
+        let summary = metrics.summary(Some(10));
+        assert_eq!(summary.n_bases, 10);
+        assert_eq!(
+            (summary.gaps, summary.gap_len, summary.max_gap_len),
+            (2, 9, 6)
+        );
+        assert_relative_eq!(summary.gc_content, 8.0 / 12.0);
+        assert_eq!((summary.ng50, summary.lg50), (Some(20), Some(1)));
+
+        let histogram = metrics.histogram(&[10, 100]);
+        let counts: Vec<_> = histogram.iter().map(|b| (b.count, b.total_len)).collect();
+        assert_eq!(counts, [(1, 2), (1, 20), (0, 0)]);
+        assert_eq!(histogram[2].upper, None);
+        assert_eq!(histogram[1].lower, 10);
+
+        let empty = AssemblyMetrics::new().summary(Some(100));
+        assert_eq!((empty.sequences, empty.n50, empty.ng50), (0, 0, None));
+    }
+}
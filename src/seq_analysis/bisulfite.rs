@@ -0,0 +1,283 @@
+//! Bisulfite sequencing utilities: in-silico bisulfite conversion, cytosine methylation
+//! contexts and methylation calling from aligned reads.
+//!
+//! Bisulfite treatment converts unmethylated cytosines to uracil (sequenced as thymine), while
+//! methylated cytosines are protected. Reads of the forward strand hence show `C→T` changes,
+//! reads of the reverse strand `G→A` changes when aligned to the forward strand of the
+//! reference. Cytosines are classified by their context on their own strand into `CG`, `CHG`
+//! and `CHH` (with `H` being `A`, `C` or `T`).
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::alignment::pairwise::Aligner;
+//! use bio::seq_analysis::bisulfite::{context, convert, Context, MethylationCounter};
+//! use bio_types::strand::ReqStrand;
+//!
+//! let reference = b"ATCGATTCAGCTACCA";
+//! assert_eq!(convert(reference, ReqStrand::Forward), b"ATTGATTTAGTTATTA");
+//! assert_eq!(context(reference, 2), Some((ReqStrand::Forward, Context::Cg)));
+//! assert_eq!(context(reference, 3), Some((ReqStrand::Reverse, Context::Cg)));
+//! assert_eq!(context(reference, 7), Some((ReqStrand::Forward, Context::Chg)));
+//!
+//! // the CpG is methylated, all other cytosines are not
+//! let read = b"ATCGATTTAGTTATTA";
+//! let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1i32 } else { -1i32 });
+//! let alignment = aligner.semiglobal(read, reference);
+//!
+//! let mut counter = MethylationCounter::new(reference);
+//! counter.add(read, &alignment, ReqStrand::Forward);
+//! let calls = counter.calls();
+//! assert_eq!(calls.len(), 4);
+//! assert_eq!((calls[0].pos, calls[0].methylated, calls[0].unmethylated), (2, 1, 0));
+//! assert!(calls[1..].iter().all(|call| call.level() == 0.0));
+//! ```
+
+use std::collections::BTreeMap;
+
+use bio_types::strand::ReqStrand;
+
+use alignment::baq::ref_positions;
+use alignment::Alignment;
+
+/// Sequence context of a cytosine on its own strand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Context {
+    Cg,
+    Chg,
+    Chh,
+}
+
+/// In-silico bisulfite conversion of all cytosines of the given strand, i.e. `C→T` for the
+/// forward and `G→A` for the reverse strand. The case of the bases is preserved.
+pub fn convert(seq: &[u8], strand: ReqStrand) -> Vec<u8> {
+    let (from, to) = match strand {
+        ReqStrand::Forward => (b'C', b'T'),
+        ReqStrand::Reverse => (b'G', b'A'),
+    };
+    seq.iter()
+        .map(|&b| {
+            if b == from {
+                to
+            } else if b == from.to_ascii_lowercase() {
+                to.to_ascii_lowercase()
+            } else {
+                b
+            }
+        })
+        .collect()
+}
+
+/// Strand and context of a cytosine at the given position, i.e. of a `C` on the forward or a
+/// `G` on the reverse strand. `None` for other bases, or if the context is unknown because of
+/// ambiguous bases or the sequence end.
+pub fn context(seq: &[u8], pos: usize) -> Option<(ReqStrand, Context)> {
+    let base = |i: usize| seq.get(i).map(u8::to_ascii_uppercase);
+    // the two following bases on the strand of the cytosine, complemented for the reverse strand
+    let (strand, next, next2) = match base(pos)? {
+        b'C' => (ReqStrand::Forward, base(pos + 1), base(pos + 2)),
+        b'G' => (
+            ReqStrand::Reverse,
+            pos.checked_sub(1).and_then(base).map(complement),
+            pos.checked_sub(2).and_then(base).map(complement),
+        ),
+        _ => return None,
+    };
+    let is_h = |b: u8| b == b'A' || b == b'C' || b == b'T';
+    let context = match (next?, next2) {
+        (b'G', _) => Context::Cg,
+        (h, Some(b'G')) if is_h(h) => Context::Chg,
+        (h, Some(h2)) if is_h(h) && is_h(h2) => Context::Chh,
+        _ => return None,
+    };
+    Some((strand, context))
+}
+
+fn complement(base: u8) -> u8 {
+    match base {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        _ => b'N',
+    }
+}
+
+/// A cytosine of a reference sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cytosine {
+    pub pos: usize,
+    pub strand: ReqStrand,
+    pub context: Context,
+}
+
+/// All cytosines of both strands of the given sequence with known context, ordered by position.
+pub fn cytosines(seq: &[u8]) -> Vec<Cytosine> {
+    (0..seq.len())
+        .filter_map(|pos| {
+            context(seq, pos).map(|(strand, context)| Cytosine {
+                pos,
+                strand,
+                context,
+            })
+        })
+        .collect()
+}
+
+/// Methylation evidence at a reference cytosine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MethylationCall {
+    pub pos: usize,
+    pub strand: ReqStrand,
+    pub context: Context,
+    /// Number of reads with an unconverted cytosine.
+    pub methylated: u32,
+    /// Number of reads with a converted cytosine.
+    pub unmethylated: u32,
+}
+
+impl MethylationCall {
+    /// Fraction of methylated observations.
+    pub fn level(&self) -> f64 {
+        f64::from(self.methylated) / f64::from(self.methylated + self.unmethylated)
+    }
+}
+
+/// Counter of methylated and unmethylated observations of the cytosines of a reference.
+#[derive(Clone, Debug)]
+pub struct MethylationCounter<'a> {
+    reference: &'a [u8],
+    counts: BTreeMap<usize, (u32, u32)>,
+}
+
+impl<'a> MethylationCounter<'a> {
+    pub fn new(reference: &'a [u8]) -> Self {
+        MethylationCounter {
+            reference,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Count the cytosines of the given strand covered by a read, given its alignment (read as
+    /// x, reference as y). Bases other than the expected converted or unconverted ones (e.g.
+    /// sequencing errors or SNVs) are ignored.
+    pub fn add(&mut self, read: &[u8], alignment: &Alignment, strand: ReqStrand) {
+        for (&base, pos) in read.iter().zip(ref_positions(alignment)) {
+            let pos = match pos {
+                Some(pos) => pos,
+                None => continue,
+            };
+            match context(self.reference, pos) {
+                Some((s, _)) if s == strand => (),
+                _ => continue,
+            }
+            let (methylated, unmethylated) = match strand {
+                ReqStrand::Forward => (b'C', b'T'),
+                ReqStrand::Reverse => (b'G', b'A'),
+            };
+            let counts = self.counts.entry(pos).or_insert((0, 0));
+            match base.to_ascii_uppercase() {
+                b if b == methylated => counts.0 += 1,
+                b if b == unmethylated => counts.1 += 1,
+                _ => (),
+            }
+        }
+    }
+
+    /// Methylation calls of all covered cytosines, ordered by position.
+    pub fn calls(&self) -> Vec<MethylationCall> {
+        self.counts
+            .iter()
+            .filter(|&(_, &(m, u))| m + u > 0)
+            .map(|(&pos, &(methylated, unmethylated))| {
+                let (strand, context) = context(self.reference, pos).unwrap();
+                MethylationCall {
+                    pos,
+                    strand,
+                    context,
+                    methylated,
+                    unmethylated,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alignment::{AlignmentMode, AlignmentOperation};
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(convert(b"ACGTacgtN", ReqStrand::Forward), b"ATGTatgtN");
+        assert_eq!(convert(b"ACGTacgtN", ReqStrand::Reverse), b"ACATacatN");
+    }
+
+    #[test]
+    fn test_context() {
+        let seq = b"CGCAGCTTCCNG";
+        let contexts: Vec<_> = (0..seq.len()).map(|i| context(seq, i)).collect();
+        assert_eq!(
+            contexts,
+            [
+                Some((ReqStrand::Forward, Context::Cg)),
+                Some((ReqStrand::Reverse, Context::Cg)),
+                Some((ReqStrand::Forward, Context::Chg)),
+                None,
+                // CTG on the reverse strand
+                Some((ReqStrand::Reverse, Context::Chg)),
+                Some((ReqStrand::Forward, Context::Chh)),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ]
+        );
+        assert_eq!(context(b"GC", 0), None);
+        assert_eq!(context(b"aag", 2), Some((ReqStrand::Reverse, Context::Chh)));
+        assert_eq!(cytosines(b"CGCAGCTTCCNG").len(), 5);
+    }
+
+    #[test]
+    fn test_counter() {
+        let reference = b"ACGTTGCCA";
+        // reverse strand read with the CpG (G at 2) methylated, G at 5 unmethylated
+        let alignment = Alignment {
+            score: 0,
+            xstart: 0,
+            ystart: 0,
+            xend: 9,
+            yend: 9,
+            xlen: 9,
+            ylen: 9,
+            operations: vec![AlignmentOperation::Match; 9],
+            mode: AlignmentMode::Global,
+        };
+        let mut counter = MethylationCounter::new(reference);
+        counter.add(b"ACGTTACCA", &alignment, ReqStrand::Reverse);
+        counter.add(b"ACGTTACCA", &alignment, ReqStrand::Reverse);
+        counter.add(b"ACATTACCA", &alignment, ReqStrand::Reverse);
+        // forward strand read with an SNV at a cytosine, and one of unknown context
+        counter.add(b"ATGTTGGTA", &alignment, ReqStrand::Forward);
+        let calls = counter.calls();
+        let summary: Vec<_> = calls
+            .iter()
+            .map(|c| (c.pos, c.strand, c.methylated, c.unmethylated))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, ReqStrand::Forward, 0, 1),
+                (2, ReqStrand::Reverse, 2, 1),
+                (5, ReqStrand::Reverse, 0, 3),
+            ]
+        );
+        assert_eq!(calls[1].context, Context::Cg);
+        assert_relative_eq!(calls[1].level(), 2.0 / 3.0);
+    }
+}
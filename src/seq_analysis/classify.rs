@@ -0,0 +1,287 @@
+//! Taxonomic classification of DNA reads by exact k-mer matches, following Kraken
+//! (Wood and Salzberg, 2014).
+//!
+//! A database maps each canonical k-mer of the reference sequences to the lowest common
+//! ancestor (LCA) of all taxa containing it. A read is classified by looking up its k-mers and
+//! choosing the taxon with the highest scoring root-to-leaf path in the taxonomy, where the
+//! score of a path is the number of k-mers mapped to taxa on it. Ties are resolved by the LCA.
+//! Optionally, a confidence threshold moves the classification up the taxonomy until the
+//! fraction of k-mers supporting the clade is large enough.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::taxonomy::Taxonomy;
+//! use bio::seq_analysis::classify::Database;
+//!
+//! // 1 is the root, 2 a genus with the two species 3 and 4
+//! let mut taxonomy = Taxonomy::new(1);
+//! taxonomy.add_node(2, 1);
+//! taxonomy.add_node(3, 2);
+//! taxonomy.add_node(4, 2);
+//!
+//! let mut db = Database::new(5, taxonomy);
+//! db.add_reference(b"ACGTTGCATGCA", 3);
+//! db.add_reference(b"ACGTTGGGCCTA", 4);
+//!
+//! assert_eq!(db.classify(b"TTGCATGC").taxon, Some(3));
+//! // shared k-mers are assigned to the genus
+//! assert_eq!(db.classify(b"ACGTTG").taxon, Some(2));
+//! assert_eq!(db.classify(b"CCCCCCCC").taxon, None);
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+
+use alphabets::dna::canonical_kmers;
+use data_structures::taxonomy::{TaxId, Taxonomy};
+use utils::KmerHashMap;
+
+/// Result of classifying a read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Classification {
+    /// The assigned taxon, or `None` if the read is unclassified.
+    pub taxon: Option<TaxId>,
+    /// Number of k-mers of the read consisting only of `ACGT`.
+    pub kmers: usize,
+    /// Number of k-mers found in the database, per taxon.
+    pub hits: HashMap<TaxId, usize>,
+}
+
+impl Classification {
+    /// Check whether the read was classified.
+    pub fn is_classified(&self) -> bool {
+        self.taxon.is_some()
+    }
+}
+
+/// A k-mer to taxon database together with its taxonomy.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Database {
+    k: usize,
+    taxonomy: Taxonomy,
+    kmers: KmerHashMap<u64, TaxId>,
+}
+
+const MAGIC: &[u8; 8] = b"BIOKMDB1";
+
+fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: io::Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+impl Database {
+    /// Create a new empty database for k-mers of length `k` (at most 32).
+    pub fn new(k: usize, taxonomy: Taxonomy) -> Self {
+        assert!(k > 0 && k <= 32, "Expecting k between 1 and 32.");
+        Database {
+            k,
+            taxonomy,
+            kmers: KmerHashMap::default(),
+        }
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The taxonomy.
+    pub fn taxonomy(&self) -> &Taxonomy {
+        &self.taxonomy
+    }
+
+    /// Number of distinct canonical k-mers.
+    pub fn len(&self) -> usize {
+        self.kmers.len()
+    }
+
+    /// Check whether the database contains no k-mers.
+    pub fn is_empty(&self) -> bool {
+        self.kmers.is_empty()
+    }
+
+    /// Add the k-mers of a reference sequence belonging to the given taxon. K-mers that are
+    /// already present are reassigned to the LCA of their current taxon and `taxon`.
+    pub fn add_reference(&mut self, seq: &[u8], taxon: TaxId) {
+        let taxonomy = &self.taxonomy;
+        for kmer in canonical_kmers(seq, self.k) {
+            self.kmers
+                .entry(kmer)
+                .and_modify(|t| *t = taxonomy.lca(*t, taxon))
+                .or_insert(taxon);
+        }
+    }
+
+    /// Classify a read without confidence threshold.
+    pub fn classify(&self, seq: &[u8]) -> Classification {
+        self.classify_with_confidence(seq, 0.0)
+    }
+
+    /// Classify a read. The assigned taxon is moved up the taxonomy until the fraction of
+    /// k-mers hitting its clade is at least `confidence` (as in Kraken 2). If even the root
+    /// does not reach the threshold, the read is unclassified.
+    pub fn classify_with_confidence(&self, seq: &[u8], confidence: f64) -> Classification {
+        let mut kmers = 0;
+        let mut hits: HashMap<TaxId, usize> = HashMap::new();
+        for kmer in canonical_kmers(seq, self.k) {
+            kmers += 1;
+            if let Some(&taxon) = self.kmers.get(&kmer) {
+                *hits.entry(taxon).or_insert(0) += 1;
+            }
+        }
+
+        let mut taxon = self.best_taxon(&hits);
+        while let Some(t) = taxon {
+            let clade_hits: usize = hits
+                .iter()
+                .filter(|&(&hit, _)| self.taxonomy.is_ancestor(t, hit))
+                .map(|(_, &count)| count)
+                .sum();
+            if clade_hits as f64 >= confidence * kmers as f64 {
+                break;
+            }
+            taxon = self.taxonomy.parent(t);
+        }
+
+        Classification { taxon, kmers, hits }
+    }
+
+    /// Taxon with the highest scoring root-to-leaf path, resolving ties by their LCA.
+    fn best_taxon(&self, hits: &HashMap<TaxId, usize>) -> Option<TaxId> {
+        let mut best: Option<(usize, TaxId)> = None;
+        for &taxon in hits.keys() {
+            let score: usize = self
+                .taxonomy
+                .ancestors(taxon)
+                .filter_map(|t| hits.get(&t))
+                .sum();
+            best = match best {
+                Some((s, t)) if s > score => Some((s, t)),
+                Some((s, t)) if s == score => Some((s, self.taxonomy.lca(t, taxon))),
+                _ => Some((score, taxon)),
+            };
+        }
+        best.map(|(_, t)| t)
+    }
+
+    /// Write the database in a compact binary format.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.k as u32).to_le_bytes())?;
+        writer.write_all(&self.taxonomy.root().to_le_bytes())?;
+        writer.write_all(&(self.taxonomy.len() as u64 - 1).to_le_bytes())?;
+        for (taxon, parent) in self.taxonomy.iter() {
+            writer.write_all(&taxon.to_le_bytes())?;
+            writer.write_all(&parent.to_le_bytes())?;
+        }
+        writer.write_all(&(self.kmers.len() as u64).to_le_bytes())?;
+        for (kmer, taxon) in &self.kmers {
+            writer.write_all(&kmer.to_le_bytes())?;
+            writer.write_all(&taxon.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Read a database written with `Database::write`.
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a k-mer database.",
+            ));
+        }
+        let k = read_u32(&mut reader)? as usize;
+        if k == 0 || k > 32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid k-mer length in database.",
+            ));
+        }
+        let mut taxonomy = Taxonomy::new(read_u32(&mut reader)?);
+        for _ in 0..read_u64(&mut reader)? {
+            let taxon = read_u32(&mut reader)?;
+            let parent = read_u32(&mut reader)?;
+            taxonomy.add_node(taxon, parent);
+        }
+        let mut db = Database::new(k, taxonomy);
+        for _ in 0..read_u64(&mut reader)? {
+            let kmer = read_u64(&mut reader)?;
+            let taxon = read_u32(&mut reader)?;
+            db.kmers.insert(kmer, taxon);
+        }
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alphabets::dna;
+
+    fn taxonomy() -> Taxonomy {
+        let mut taxonomy = Taxonomy::new(1);
+        for &(taxon, parent) in &[(10, 1), (11, 10), (12, 10), (20, 1), (21, 20)] {
+            taxonomy.add_node(taxon, parent);
+        }
+        taxonomy
+    }
+
+    #[test]
+    fn test_classify() {
+        let mut db = Database::new(8, taxonomy());
+        let species_11 = b"ATGCGTACGTTAGCCGATCGATCGGCTAAGCT";
+        let species_12 = b"ATGCGTACGTTAGCCGTTTTCCCCAAAAGGGG";
+        let species_21 = b"GGCCTTAAGGCCTTAACCGGTTAACCGGTTAA";
+        db.add_reference(species_11, 11);
+        db.add_reference(species_12, 12);
+        db.add_reference(species_21, 21);
+
+        let read = dna::revcomp(&species_11[10..30]);
+        let classification = db.classify(&read);
+        assert_eq!(classification.taxon, Some(11));
+        assert_eq!(classification.kmers, 13);
+        assert_eq!(classification.hits.get(&11), Some(&13));
+        assert!(classification.is_classified());
+
+        assert_eq!(db.classify(&species_12[..14]).taxon, Some(10));
+        // half of the read is random
+        let mut read = species_21[..16].to_vec();
+        read.extend_from_slice(b"ACACACACACACACAC");
+        assert_eq!(db.classify(&read).taxon, Some(21));
+        assert_eq!(db.classify_with_confidence(&read, 0.5).taxon, None);
+        assert_eq!(db.classify_with_confidence(&read, 0.3).taxon, Some(21));
+    }
+
+    #[test]
+    fn test_tie() {
+        let mut db = Database::new(4, taxonomy());
+        db.add_reference(b"AAAACCCC", 11);
+        db.add_reference(b"GAGAGTGT", 21);
+        assert_eq!(db.classify(b"AAAANGAGA").taxon, Some(1));
+    }
+
+    #[test]
+    fn test_read_write() {
+        let mut db = Database::new(6, taxonomy());
+        db.add_reference(b"ACGTAGCTAGCTAGGATC", 11);
+        db.add_reference(b"ACGTAGCTTTTTAGGATC", 12);
+        let mut buf = Vec::new();
+        db.write(&mut buf).unwrap();
+        let loaded = Database::read(&buf[..]).unwrap();
+        assert_eq!(loaded.k(), 6);
+        assert_eq!(loaded.len(), db.len());
+        assert_eq!(loaded.taxonomy(), db.taxonomy());
+        assert_eq!(loaded.kmers, db.kmers);
+        assert!(Database::read(&b"garbage!"[..]).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+//! Codon usage tables, relative synonymous codon usage (RSCU) and the Codon Adaptation Index
+//! (CAI; Sharp and Li, 1987), based on the standard genetic code.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::codon::CodonUsage;
+//!
+//! // a reference set of highly expressed genes
+//! let reference = CodonUsage::from_cds(vec![&b"ATGCTGCTGCTGAAAGGCTAA"[..], b"ATGCTGAAGGGCTGA"]);
+//! assert_eq!(reference.count(b"CTG"), 4);
+//! // Leu has six codons, of which only CTG is used
+//! assert_eq!(reference.rscu(b"CTG"), Some(6.0));
+//!
+//! let cai = reference.cai(b"ATGCTGAAAGGCTAA").unwrap();
+//! assert!(cai > 0.6 && cai <= 1.0);
+//! ```
+
+/// Amino acids (one letter code, `*` for stop) of the standard genetic code, indexed by
+/// codons encoded with A=0, C=1, G=2, T=3 as `16 * first + 4 * second + third`.
+const STANDARD_CODE: &[u8; 64] =
+    b"KNKNTTTTRSRSIIMIQHQHPPPPRRRRLLLLEDEDAAAAGGGGVVVV*Y*YSSSS*CWCLFLF";
+
+fn base_rank(base: u8) -> Option<usize> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' | b'U' | b'u' => Some(3),
+        _ => None,
+    }
+}
+
+/// Index (0 to 63) of the given codon, or `None` if it contains other symbols than
+/// `ACGTU` (case insensitive).
+pub fn codon_index(codon: &[u8]) -> Option<usize> {
+    assert_eq!(codon.len(), 3, "Expecting codon of length 3.");
+    Some(16 * base_rank(codon[0])? + 4 * base_rank(codon[1])? + base_rank(codon[2])?)
+}
+
+/// Return the codon with the given index as DNA.
+pub fn index_codon(index: usize) -> [u8; 3] {
+    const BASES: &[u8; 4] = b"ACGT";
+    [BASES[index / 16], BASES[(index / 4) % 4], BASES[index % 4]]
+}
+
+/// Translate a codon with the standard genetic code. Stop codons yield `*`.
+pub fn translate_codon(codon: &[u8]) -> Option<u8> {
+    codon_index(codon).map(|i| STANDARD_CODE[i])
+}
+
+/// Indices of all codons encoding the same amino acid as the codon with the given index.
+fn synonymous(index: usize) -> impl Iterator<Item = usize> {
+    let aa = STANDARD_CODE[index];
+    (0..64).filter(move |&i| STANDARD_CODE[i] == aa)
+}
+
+/// Codon counts of a set of coding sequences.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodonUsage {
+    counts: Vec<u64>,
+}
+
+impl Default for CodonUsage {
+    fn default() -> Self {
+        CodonUsage {
+            counts: vec![0; 64],
+        }
+    }
+}
+
+impl CodonUsage {
+    /// Create a new empty table.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Count the codons of the given coding sequences.
+    pub fn from_cds<S: AsRef<[u8]>, I: IntoIterator<Item = S>>(cds: I) -> Self {
+        let mut usage = CodonUsage::new();
+        for seq in cds {
+            usage.add_cds(seq.as_ref());
+        }
+        usage
+    }
+
+    /// Count the codons of a coding sequence, read in frame from its start. Codons with
+    /// ambiguous bases and trailing incomplete codons are ignored.
+    pub fn add_cds(&mut self, cds: &[u8]) {
+        for codon in cds.chunks_exact(3) {
+            if let Some(i) = codon_index(codon) {
+                self.counts[i] += 1;
+            }
+        }
+    }
+
+    /// Number of occurrences of the given codon.
+    pub fn count(&self, codon: &[u8]) -> u64 {
+        codon_index(codon).map_or(0, |i| self.counts[i])
+    }
+
+    /// Total number of counted codons.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Frequency of the given codon among all counted codons.
+    pub fn frequency(&self, codon: &[u8]) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            0.0
+        } else {
+            self.count(codon) as f64 / total as f64
+        }
+    }
+
+    /// Relative synonymous codon usage, i.e. the count of the codon divided by the mean count
+    /// of all codons of the same amino acid. Returns `None` for ambiguous codons and amino
+    /// acids that were not observed.
+    pub fn rscu(&self, codon: &[u8]) -> Option<f64> {
+        let i = codon_index(codon)?;
+        let (n, sum) = synonymous(i).fold((0, 0), |(n, sum), j| (n + 1, sum + self.counts[j]));
+        if sum == 0 {
+            None
+        } else {
+            Some(self.counts[i] as f64 * f64::from(n) / sum as f64)
+        }
+    }
+
+    /// Relative adaptiveness of the codon, i.e. its count divided by the count of the most
+    /// frequent synonymous codon. Codons that were not observed are assigned a count of 0.5.
+    /// Returns `None` for ambiguous codons and amino acids that were not observed.
+    pub fn relative_adaptiveness(&self, codon: &[u8]) -> Option<f64> {
+        let i = codon_index(codon)?;
+        let max = synonymous(i).map(|j| self.counts[j]).max().unwrap_or(0);
+        if max == 0 {
+            None
+        } else {
+            Some((self.counts[i] as f64).max(0.5) / max as f64)
+        }
+    }
+
+    /// Codon Adaptation Index of a gene with respect to this table (as reference of highly
+    /// expressed genes), i.e. the geometric mean of the relative adaptiveness of its codons.
+    /// Stop codons, codons of amino acids with a single codon (Met and Trp), ambiguous codons
+    /// and codons of amino acids not observed in the reference are skipped.
+    /// Returns `None` if no codon remains.
+    pub fn cai(&self, gene: &[u8]) -> Option<f64> {
+        let (mut n, mut log_sum) = (0, 0.0);
+        for codon in gene.chunks_exact(3) {
+            let i = match codon_index(codon) {
+                Some(i) => i,
+                None => continue,
+            };
+            if STANDARD_CODE[i] == b'*' || synonymous(i).count() == 1 {
+                continue;
+            }
+            if let Some(w) = self.relative_adaptiveness(codon) {
+                n += 1;
+                log_sum += w.ln();
+            }
+        }
+        if n == 0 {
+            None
+        } else {
+            Some((log_sum / f64::from(n)).exp())
+        }
+    }
+
+    /// Iterate over all 64 codons and their counts.
+    pub fn iter(&self) -> impl Iterator<Item = ([u8; 3], u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (index_codon(i), count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genetic_code() {
+        assert_eq!(translate_codon(b"ATG"), Some(b'M'));
+        assert_eq!(translate_codon(b"TGG"), Some(b'W'));
+        assert_eq!(translate_codon(b"uaa"), Some(b'*'));
+        assert_eq!(translate_codon(b"GCN"), None);
+        assert_eq!(STANDARD_CODE.iter().filter(|&&aa| aa == b'*').count(), 3);
+        assert_eq!(synonymous(codon_index(b"CTG").unwrap()).count(), 6);
+        for i in 0..64 {
+            assert_eq!(codon_index(&index_codon(i)), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_usage() {
+        let usage = CodonUsage::from_cds(vec![&b"GCTGCTGCCGCNAAAAAGAA"[..]]);
+        assert_eq!(usage.total(), 5);
+        assert_eq!(usage.count(b"GCT"), 2);
+        assert_relative_eq!(usage.frequency(b"AAA"), 0.2);
+        // Ala has four codons: 2 GCT, 1 GCC
+        assert_relative_eq!(usage.rscu(b"GCT").unwrap(), 8.0 / 3.0);
+        assert_relative_eq!(usage.rscu(b"GCA").unwrap(), 0.0);
+        assert_eq!(usage.rscu(b"TGG"), None);
+        assert_relative_eq!(usage.relative_adaptiveness(b"GCC").unwrap(), 0.5);
+        assert_relative_eq!(usage.relative_adaptiveness(b"GCA").unwrap(), 0.25);
+        assert_eq!(usage.iter().map(|(_, c)| c).sum::<u64>(), 5);
+    }
+
+    #[test]
+    fn test_cai() {
+        let reference = CodonUsage::from_cds(vec![&b"GCTGCTAAAAAAAAG"[..]]);
+        // optimal codons only
+        assert_relative_eq!(reference.cai(b"ATGGCTAAATAA").unwrap(), 1.0);
+        // w(GCC) = 0.25, w(AAG) = 0.5
+        assert_relative_eq!(
+            reference.cai(b"GCCAAG").unwrap(),
+            (0.25f64 * 0.5).sqrt(),
+            epsilon = 1e-12
+        );
+        assert_eq!(reference.cai(b"ATGTGGTAA"), None);
+    }
+}
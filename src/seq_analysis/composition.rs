@@ -0,0 +1,276 @@
+//! Fast kernels for base composition and complementing of DNA sequences.
+//!
+//! Counting bases and complementing sequences are small operations that dominate many
+//! preprocessing loops. On x86_64 CPUs supporting AVX2 (detected at runtime), the kernels process
+//! 32 bases at a time; otherwise, and for the remaining bases, scalar implementations are used.
+//! Both give identical results.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::composition::{count_bases, gc_windows, revcomp};
+//!
+//! let seq = b"ACGTNNacgtGGCC";
+//! let counts = count_bases(seq);
+//! assert_eq!((counts.a, counts.c, counts.g, counts.t, counts.n), (2, 4, 4, 2, 2));
+//! assert_eq!(counts.gc_content(), 8.0 / 12.0);
+//!
+//! assert_eq!(gc_windows(seq, 4, 4), [0.5, 0.5, 0.75]);
+//! assert_eq!(revcomp(seq), b"GGCCacgtNNACGT");
+//! ```
+
+use alphabets::dna;
+
+/// Counts of the bases of a sequence (case insensitive).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BaseCounts {
+    pub a: u64,
+    pub c: u64,
+    pub g: u64,
+    pub t: u64,
+    pub n: u64,
+    /// Symbols other than `A`, `C`, `G`, `T` and `N`.
+    pub other: u64,
+}
+
+impl BaseCounts {
+    /// Total number of symbols.
+    pub fn len(&self) -> u64 {
+        self.a + self.c + self.g + self.t + self.n + self.other
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// GC content among the `A`, `C`, `G` and `T` bases (`NaN` if there are none).
+    pub fn gc_content(&self) -> f64 {
+        (self.c + self.g) as f64 / (self.a + self.c + self.g + self.t) as f64
+    }
+
+    fn add(&mut self, other: &BaseCounts) {
+        self.a += other.a;
+        self.c += other.c;
+        self.g += other.g;
+        self.t += other.t;
+        self.n += other.n;
+        self.other += other.other;
+    }
+}
+
+/// Count the bases of the given sequence.
+pub fn count_bases(seq: &[u8]) -> BaseCounts {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // safe since the CPU supports AVX2
+            return unsafe { avx2::count_bases(seq) };
+        }
+    }
+    scalar::count_bases(seq)
+}
+
+/// GC content (among the `A`, `C`, `G` and `T` bases) of the windows of the given length,
+/// starting every `step` bases. Windows without these bases have GC content `NaN`.
+pub fn gc_windows(seq: &[u8], window: usize, step: usize) -> Vec<f64> {
+    assert!(
+        window > 0 && step > 0,
+        "Expecting positive window length and step."
+    );
+    (0..(seq.len() + 1).saturating_sub(window))
+        .step_by(step)
+        .map(|start| count_bases(&seq[start..start + window]).gc_content())
+        .collect()
+}
+
+/// Complement the given sequence in place, as `alphabets::dna::complement` (IUPAC alphabet
+/// supported, case preserved).
+pub fn complement(seq: &mut [u8]) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // safe since the CPU supports AVX2
+            unsafe { avx2::complement(seq) };
+            return;
+        }
+    }
+    scalar::complement(seq)
+}
+
+/// Reverse complement of the given sequence.
+pub fn revcomp(seq: &[u8]) -> Vec<u8> {
+    let mut revcomp = seq.to_vec();
+    revcomp.reverse();
+    complement(&mut revcomp);
+    revcomp
+}
+
+mod scalar {
+    use super::*;
+
+    pub fn count_bases(seq: &[u8]) -> BaseCounts {
+        let mut counts = [0u64; 256];
+        for &b in seq {
+            counts[b as usize] += 1;
+        }
+        let count = |b: u8| counts[b as usize] + counts[b.to_ascii_lowercase() as usize];
+        let mut result = BaseCounts {
+            a: count(b'A'),
+            c: count(b'C'),
+            g: count(b'G'),
+            t: count(b'T'),
+            n: count(b'N'),
+            other: 0,
+        };
+        result.other = seq.len() as u64 - result.len();
+        result
+    }
+
+    pub fn complement(seq: &mut [u8]) {
+        for b in seq {
+            *b = dna::complement(*b);
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use super::*;
+    use std::arch::x86_64::*;
+
+    /// Number of 32 byte blocks after which the 8 bit counters are flushed.
+    const FLUSH: usize = 255;
+
+    /// Sum of the 8 bit counters in the vector.
+    #[target_feature(enable = "avx2")]
+    unsafe fn sum(counts: __m256i) -> u64 {
+        let sums = _mm256_sad_epu8(counts, _mm256_setzero_si256());
+        (_mm256_extract_epi64(sums, 0)
+            + _mm256_extract_epi64(sums, 1)
+            + _mm256_extract_epi64(sums, 2)
+            + _mm256_extract_epi64(sums, 3)) as u64
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn count_bases(seq: &[u8]) -> BaseCounts {
+        let mut result = BaseCounts::default();
+        let chunks = seq.chunks_exact(32);
+        let remainder = chunks.remainder();
+        let (lower, a, c, g, t, n) = (
+            _mm256_set1_epi8(0x20),
+            _mm256_set1_epi8(b'a' as i8),
+            _mm256_set1_epi8(b'c' as i8),
+            _mm256_set1_epi8(b'g' as i8),
+            _mm256_set1_epi8(b't' as i8),
+            _mm256_set1_epi8(b'n' as i8),
+        );
+        let blocks: Vec<_> = chunks.collect();
+        for batch in blocks.chunks(FLUSH) {
+            let mut counts = [_mm256_setzero_si256(); 5];
+            for block in batch {
+                // setting bit 5 turns uppercase letters into lowercase ones, and does not turn
+                // other symbols into letters
+                let v =
+                    _mm256_or_si256(_mm256_loadu_si256(block.as_ptr() as *const __m256i), lower);
+                // matches are -1, hence subtracted
+                for (count, &base) in counts.iter_mut().zip(&[a, c, g, t, n]) {
+                    *count = _mm256_sub_epi8(*count, _mm256_cmpeq_epi8(v, base));
+                }
+            }
+            result.a += sum(counts[0]);
+            result.c += sum(counts[1]);
+            result.g += sum(counts[2]);
+            result.t += sum(counts[3]);
+            result.n += sum(counts[4]);
+        }
+        result.other = (seq.len() - remainder.len()) as u64 - result.len();
+        result.add(&scalar::count_bases(remainder));
+        result
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn complement(seq: &mut [u8]) {
+        let (lower, a, c, g, t, n) = (
+            _mm256_set1_epi8(0x20),
+            _mm256_set1_epi8(b'a' as i8),
+            _mm256_set1_epi8(b'c' as i8),
+            _mm256_set1_epi8(b'g' as i8),
+            _mm256_set1_epi8(b't' as i8),
+            _mm256_set1_epi8(b'n' as i8),
+        );
+        // A (0x41) and T (0x54) differ in the bits 0x15, C (0x43) and G (0x47) in 0x04
+        let (at_bits, cg_bits) = (_mm256_set1_epi8(0x15), _mm256_set1_epi8(0x04));
+        let mut chunks = seq.chunks_exact_mut(32);
+        for block in &mut chunks {
+            let ptr = block.as_mut_ptr() as *mut __m256i;
+            let v = _mm256_loadu_si256(ptr);
+            let folded = _mm256_or_si256(v, lower);
+            let at = _mm256_or_si256(_mm256_cmpeq_epi8(folded, a), _mm256_cmpeq_epi8(folded, t));
+            let cg = _mm256_or_si256(_mm256_cmpeq_epi8(folded, c), _mm256_cmpeq_epi8(folded, g));
+            let known = _mm256_or_si256(_mm256_or_si256(at, cg), _mm256_cmpeq_epi8(folded, n));
+            if _mm256_movemask_epi8(known) != -1 {
+                // other IUPAC symbols
+                scalar::complement(block);
+                continue;
+            }
+            let flip =
+                _mm256_or_si256(_mm256_and_si256(at, at_bits), _mm256_and_si256(cg, cg_bits));
+            _mm256_storeu_si256(ptr, _mm256_xor_si256(v, flip));
+        }
+        scalar::complement(chunks.into_remainder());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::Xoshiro256;
+
+    fn random_seq(len: usize, alphabet: &[u8], seed: u64) -> Vec<u8> {
+        let mut rng = Xoshiro256::new(seed);
+        (0..len)
+            .map(|_| alphabet[rng.below(alphabet.len() as u64) as usize])
+            .collect()
+    }
+
+    #[test]
+    fn test_count_bases() {
+        for &len in &[0, 1, 31, 32, 33, 1000, 255 * 32 + 17, 20000] {
+            let seq = random_seq(len, b"ACGTNacgtnRY-\n\xff", len as u64);
+            let counts = count_bases(&seq);
+            assert_eq!(counts, scalar::count_bases(&seq));
+            assert_eq!(counts.len(), len as u64);
+            let a = seq.iter().filter(|&&b| b == b'A' || b == b'a').count();
+            assert_eq!(counts.a, a as u64);
+        }
+        assert!(count_bases(b"").is_empty());
+        assert_eq!(count_bases(b"AAAT").gc_content(), 0.0);
+        assert!(count_bases(b"NN").gc_content().is_nan());
+    }
+
+    #[test]
+    fn test_complement() {
+        for &len in &[0, 5, 32, 100, 1000] {
+            let seq = random_seq(len, b"ACGTNacgtn", len as u64);
+            assert_eq!(revcomp(&seq), dna::revcomp(&seq));
+            // IUPAC symbols in some blocks
+            let seq = random_seq(
+                len,
+                b"ACGTACGTACGTACGTACGTACGTACGTACGTRYSWKMBDHVN",
+                len as u64,
+            );
+            assert_eq!(revcomp(&seq), dna::revcomp(&seq));
+        }
+        let mut seq = b"ACGTacgtNn".to_vec();
+        complement(&mut seq);
+        assert_eq!(seq, b"TGCAtgcaNn");
+    }
+
+    #[test]
+    fn test_gc_windows() {
+        assert_eq!(gc_windows(b"GGCCAATTGCNN", 4, 2), [1.0, 0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(gc_windows(b"GGCCAATTGCNNA", 4, 3), [1.0, 0.25, 0.5, 0.5]);
+        assert!(gc_windows(b"ACG", 4, 1).is_empty());
+        assert!(gc_windows(b"NNNN", 4, 1)[0].is_nan());
+    }
+}
@@ -0,0 +1,292 @@
+//! Denoising of amplicon reads into amplicon sequence variants (ASVs) with the abundance skew
+//! model of UNOISE (Edgar, 2016).
+//!
+//! Reads are dereplicated into unique sequences, which are processed in order of decreasing
+//! abundance. A unique sequence with abundance `a` is considered an error of an already accepted,
+//! more abundant ASV with abundance `A` at edit distance `d`, if the skew `a / A` is at most
+//! `1 / 2^(alpha * d + 1)`. Otherwise, if it occurs at least `min_abundance` times, it becomes a
+//! new ASV. Rare unique sequences that cannot be explained as errors are discarded.
+//! The input reads are expected to be quality filtered, e.g. with `fastq::Record::passes_max_ee`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::denoise::Denoiser;
+//!
+//! let mut reads = vec![b"ACGTACGTAC".to_vec(); 100];
+//! reads.extend(vec![b"ACGTACCTAC".to_vec(); 2]); // sequencing error of the first variant
+//! reads.extend(vec![b"TTGTACGAAC".to_vec(); 40]); // a second variant
+//!
+//! let asvs = Denoiser::default().denoise(&reads);
+//! assert_eq!(asvs.len(), 2);
+//! assert_eq!(asvs[0].seq, b"ACGTACGTAC");
+//! assert_eq!(asvs[0].abundance, 102);
+//! assert_eq!(asvs[1].abundance, 40);
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+
+use alignment::distance::levenshtein;
+
+/// An amplicon sequence variant.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Asv {
+    /// The (centroid) sequence of the variant.
+    pub seq: Vec<u8>,
+    /// Number of reads assigned to the variant, including reads considered errors of it.
+    pub abundance: usize,
+    /// Number of reads exactly matching the variant.
+    pub exact_abundance: usize,
+}
+
+/// Denoiser following the UNOISE abundance skew model.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Denoiser {
+    alpha: f64,
+    min_abundance: usize,
+}
+
+impl Default for Denoiser {
+    /// The defaults of UNOISE3: `alpha = 2` and `min_abundance = 8`.
+    fn default() -> Self {
+        Denoiser::new(2.0, 8)
+    }
+}
+
+/// A dereplicated sequence with its abundance and the ASV it was assigned to.
+struct Unique<'a> {
+    seq: &'a [u8],
+    abundance: usize,
+    asv: Option<usize>,
+}
+
+impl Denoiser {
+    /// Create a new denoiser.
+    ///
+    /// # Arguments
+    ///
+    /// * `alpha` - the steepness of the abundance skew threshold with growing distance
+    /// * `min_abundance` - minimum number of reads of a unique sequence to become an ASV
+    pub fn new(alpha: f64, min_abundance: usize) -> Self {
+        assert!(alpha > 0.0, "Expecting positive alpha.");
+        Denoiser {
+            alpha,
+            min_abundance: min_abundance.max(1),
+        }
+    }
+
+    /// Maximum abundance skew allowed for an error at edit distance `d`.
+    pub fn max_skew(&self, d: u32) -> f64 {
+        1.0 / 2f64.powf(self.alpha * f64::from(d) + 1.0)
+    }
+
+    /// Largest distance at which a sequence with the given abundance can be an error of
+    /// an ASV with abundance `parent`.
+    fn max_dist(&self, abundance: usize, parent: usize) -> f64 {
+        ((parent as f64 / abundance as f64).log2() - 1.0) / self.alpha
+    }
+
+    /// Dereplicate the reads and assign each unique sequence to an ASV.
+    fn cluster<'a, S: AsRef<[u8]>>(&self, reads: &'a [S]) -> (Vec<Asv>, Vec<Unique<'a>>) {
+        let mut counts: HashMap<&[u8], usize> = HashMap::new();
+        for read in reads {
+            *counts.entry(read.as_ref()).or_insert(0) += 1;
+        }
+        let mut uniques: Vec<Unique> = counts
+            .into_iter()
+            .map(|(seq, abundance)| Unique {
+                seq,
+                abundance,
+                asv: None,
+            })
+            .collect();
+        // break ties by sequence for deterministic results
+        uniques.sort_by(|a, b| b.abundance.cmp(&a.abundance).then(a.seq.cmp(b.seq)));
+
+        let mut asvs: Vec<Asv> = Vec::new();
+        for unique in &mut uniques {
+            // among all ASVs explaining the sequence as an error, choose the closest one and
+            // break ties by abundance (ASVs are sorted by decreasing exact abundance)
+            let mut parent = None;
+            let mut best_dist = f64::INFINITY;
+            for (i, asv) in asvs.iter().enumerate() {
+                let max_dist = self
+                    .max_dist(unique.abundance, asv.exact_abundance)
+                    .min(best_dist);
+                let len_diff = (asv.seq.len() as isize - unique.seq.len() as isize).abs();
+                if max_dist < 1.0 || len_diff as f64 > max_dist {
+                    continue;
+                }
+                let d = f64::from(levenshtein(unique.seq, &asv.seq));
+                if d <= max_dist && d < best_dist {
+                    parent = Some(i);
+                    best_dist = d;
+                }
+            }
+            match parent {
+                Some(i) => {
+                    asvs[i].abundance += unique.abundance;
+                    unique.asv = Some(i);
+                }
+                None if unique.abundance >= self.min_abundance => {
+                    unique.asv = Some(asvs.len());
+                    asvs.push(Asv {
+                        seq: unique.seq.to_vec(),
+                        abundance: unique.abundance,
+                        exact_abundance: unique.abundance,
+                    });
+                }
+                None => (),
+            }
+        }
+
+        (asvs, uniques)
+    }
+
+    /// Denoise the given reads into ASVs, sorted by decreasing exact abundance.
+    pub fn denoise<S: AsRef<[u8]>>(&self, reads: &[S]) -> Vec<Asv> {
+        self.cluster(reads).0
+    }
+
+    /// Denoise the pooled reads of multiple samples and count the reads of each sample per ASV.
+    /// Each sample is given as pair of name and reads.
+    pub fn asv_table<N: AsRef<str>, S: AsRef<[u8]>>(&self, samples: &[(N, Vec<S>)]) -> AsvTable {
+        let pooled: Vec<&[u8]> = samples
+            .iter()
+            .flat_map(|(_, reads)| reads.iter().map(|read| read.as_ref()))
+            .collect();
+        let (asvs, uniques) = self.cluster(&pooled);
+        let assignment: HashMap<&[u8], Option<usize>> =
+            uniques.iter().map(|u| (u.seq, u.asv)).collect();
+
+        let mut counts = vec![vec![0; samples.len()]; asvs.len()];
+        let mut unassigned = vec![0; samples.len()];
+        for (j, (_, reads)) in samples.iter().enumerate() {
+            for read in reads {
+                match assignment[read.as_ref()] {
+                    Some(i) => counts[i][j] += 1,
+                    None => unassigned[j] += 1,
+                }
+            }
+        }
+
+        AsvTable {
+            samples: samples
+                .iter()
+                .map(|(name, _)| name.as_ref().to_owned())
+                .collect(),
+            asvs,
+            counts,
+            unassigned,
+        }
+    }
+}
+
+/// Read counts of ASVs across samples.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AsvTable {
+    samples: Vec<String>,
+    asvs: Vec<Asv>,
+    counts: Vec<Vec<usize>>,
+    unassigned: Vec<usize>,
+}
+
+impl AsvTable {
+    /// The sample names.
+    pub fn samples(&self) -> &[String] {
+        &self.samples
+    }
+
+    /// The ASVs, sorted by decreasing exact abundance over all samples.
+    pub fn asvs(&self) -> &[Asv] {
+        &self.asvs
+    }
+
+    /// Number of reads of sample `sample` assigned to ASV `asv`.
+    pub fn count(&self, asv: usize, sample: usize) -> usize {
+        self.counts[asv][sample]
+    }
+
+    /// Number of reads of sample `sample` that were not assigned to any ASV.
+    pub fn unassigned(&self, sample: usize) -> usize {
+        self.unassigned[sample]
+    }
+
+    /// Write the table as tab separated values, with one row per ASV named `ASV1`, `ASV2`, ...
+    /// and one column per sample.
+    pub fn write_tsv<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "#ASV")?;
+        for sample in &self.samples {
+            write!(writer, "\t{}", sample)?;
+        }
+        writeln!(writer)?;
+        for (i, counts) in self.counts.iter().enumerate() {
+            write!(writer, "ASV{}", i + 1)?;
+            for count in counts {
+                write!(writer, "\t{}", count)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reads(seq: &[u8], n: usize) -> Vec<Vec<u8>> {
+        vec![seq.to_vec(); n]
+    }
+
+    #[test]
+    fn test_skew() {
+        let denoiser = Denoiser::default();
+        assert_relative_eq!(denoiser.max_skew(1), 0.125);
+        assert_relative_eq!(denoiser.max_skew(2), 1.0 / 32.0);
+    }
+
+    #[test]
+    fn test_denoise() {
+        let mut input = reads(b"GATTACAGATTACA", 1000);
+        // one substitution, skew 0.05 <= 0.125
+        input.extend(reads(b"GATTACAGCTTACA", 50));
+        // two substitutions, skew 0.2 > 1/32: a true variant
+        input.extend(reads(b"GATTACAGCTTCCA", 200));
+        // one deletion from the second variant
+        input.extend(reads(b"GATTACAGCTTCA", 10));
+        // rare and unexplained
+        input.extend(reads(b"CCCCCCCCCCCCCC", 3));
+
+        let asvs = Denoiser::default().denoise(&input);
+        assert_eq!(asvs.len(), 2);
+        assert_eq!(asvs[0].seq, b"GATTACAGATTACA");
+        assert_eq!(asvs[0].abundance, 1050);
+        assert_eq!(asvs[0].exact_abundance, 1000);
+        assert_eq!(asvs[1].seq, b"GATTACAGCTTCCA");
+        assert_eq!(asvs[1].abundance, 210);
+    }
+
+    #[test]
+    fn test_asv_table() {
+        let mut s1 = reads(b"ACGTACGT", 30);
+        s1.extend(reads(b"ACGTACGA", 1));
+        s1.extend(reads(b"GGGGCCCC", 2));
+        let mut s2 = reads(b"TTTTAAAA", 20);
+        s2.extend(reads(b"ACGTACGT", 5));
+        let table = Denoiser::default().asv_table(&[("s1", s1), ("s2", s2)]);
+
+        assert_eq!(table.samples(), &["s1".to_owned(), "s2".to_owned()]);
+        assert_eq!(table.asvs().len(), 2);
+        assert_eq!(table.count(0, 0), 31);
+        assert_eq!(table.count(0, 1), 5);
+        assert_eq!(table.count(1, 0), 0);
+        assert_eq!(table.count(1, 1), 20);
+        assert_eq!(table.unassigned(0), 2);
+
+        let mut tsv = Vec::new();
+        table.write_tsv(&mut tsv).unwrap();
+        assert_eq!(tsv, b"#ASV\ts1\ts2\nASV1\t31\t5\nASV2\t0\t20\n".to_vec());
+    }
+}
@@ -0,0 +1,289 @@
+//! Dot plot data for comparing two DNA sequences.
+//!
+//! Matches are reported either as exact k-mer matches, or as windows of a given ungapped identity
+//! along the diagonals. Matches of the second sequence's reverse complement are reported with
+//! strand `Reverse`, such that inversions show up as anti-diagonal lines. Consecutive k-mer
+//! matches along a (anti-)diagonal can be merged into segments, which are easier to draw than
+//! individual dots for long sequences.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::seq_analysis::dotplot::{kmer_matches, segments, Dot};
+//! use bio_types::strand::ReqStrand;
+//!
+//! let x = b"AAGATTACATT";
+//! let y = b"GATTACAGGTGTAATC";
+//! let dots = kmer_matches(x, y, 5);
+//! assert_eq!(dots[0], Dot { x: 2, y: 0, strand: ReqStrand::Forward });
+//!
+//! let segments = segments(&dots, 5);
+//! let lines: Vec<_> = segments
+//!     .iter()
+//!     .map(|s| (s.x_start, s.y_start, s.x_end, s.y_end, s.strand))
+//!     .collect();
+//! assert_eq!(
+//!     lines,
+//!     [(2, 0, 9, 7, ReqStrand::Forward), (2, 16, 9, 9, ReqStrand::Reverse)]
+//! );
+//! ```
+
+use std::collections::HashMap;
+
+use bio_types::strand::ReqStrand;
+
+use alphabets::dna;
+
+/// A match of `x[x..x + k]` and `y[y..y + k]` (strand `Forward`), or of `x[x..x + k]` and the
+/// reverse complement of `y[y..y + k]` (strand `Reverse`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Dot {
+    pub x: usize,
+    pub y: usize,
+    pub strand: ReqStrand,
+}
+
+/// A window match with its ungapped identity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowDot {
+    pub x: usize,
+    pub y: usize,
+    pub strand: ReqStrand,
+    pub identity: f64,
+}
+
+/// A line of consecutive matches from `(x_start, y_start)` to `(x_end, y_end)`. Forward lines
+/// ascend along the diagonal, reverse lines descend (i.e. `y_start > y_end`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DotSegment {
+    pub x_start: usize,
+    pub y_start: usize,
+    pub x_end: usize,
+    pub y_end: usize,
+    pub strand: ReqStrand,
+}
+
+impl DotSegment {
+    /// Length of the segment along the first sequence.
+    pub fn len(&self) -> usize {
+        self.x_end - self.x_start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x_start == self.x_end
+    }
+}
+
+fn is_acgt(kmer: &[u8]) -> bool {
+    kmer.iter().all(|b| matches!(b, b'A' | b'C' | b'G' | b'T'))
+}
+
+/// All exact matches of k-mers of `x` with k-mers of `y` or of its reverse complement, ordered
+/// by position in `x`, position in `y` and strand. Matching ignores case, k-mers containing
+/// other bases than `A`, `C`, `G` and `T` are skipped. Palindromic k-mers are reported on both
+/// strands.
+pub fn kmer_matches(x: &[u8], y: &[u8], k: usize) -> Vec<Dot> {
+    assert!(k > 0, "Expecting positive k.");
+    let x = x.to_ascii_uppercase();
+    let y = y.to_ascii_uppercase();
+    let mut kmers: HashMap<&[u8], Vec<usize>> = HashMap::new();
+    for (i, kmer) in y.windows(k).enumerate() {
+        if is_acgt(kmer) {
+            kmers.entry(kmer).or_default().push(i);
+        }
+    }
+
+    let mut dots = Vec::new();
+    for (i, kmer) in x.windows(k).enumerate() {
+        if !is_acgt(kmer) {
+            continue;
+        }
+        let revcomp = dna::revcomp(kmer);
+        for (kmer, strand) in [
+            (kmer, ReqStrand::Forward),
+            (&revcomp[..], ReqStrand::Reverse),
+        ] {
+            if let Some(positions) = kmers.get(kmer) {
+                dots.extend(positions.iter().map(|&j| Dot { x: i, y: j, strand }));
+            }
+        }
+    }
+    dots.sort();
+    dots
+}
+
+fn identity(x: &[u8], y: &[u8]) -> f64 {
+    let matches = x
+        .iter()
+        .zip(y)
+        .filter(|&(a, b)| a.eq_ignore_ascii_case(b))
+        .count();
+    matches as f64 / x.len() as f64
+}
+
+/// Windows of the given length, starting every `step` positions in both sequences, whose
+/// ungapped identity (the fraction of equal bases, ignoring case) is at least `min_identity`.
+/// For strand `Reverse`, windows of `x` are compared to the reverse complement of windows of
+/// `y`. Windows are ordered by position in `x`, position in `y` and strand.
+pub fn window_matches(
+    x: &[u8],
+    y: &[u8],
+    window: usize,
+    step: usize,
+    min_identity: f64,
+) -> Vec<WindowDot> {
+    assert!(
+        window > 0 && step > 0,
+        "Expecting positive window length and step."
+    );
+    let starts = |len: usize| (0..(len + 1).saturating_sub(window)).step_by(step);
+    let y_windows: Vec<_> = starts(y.len())
+        .map(|j| (j, &y[j..j + window], dna::revcomp(&y[j..j + window])))
+        .collect();
+
+    let mut dots = Vec::new();
+    for i in starts(x.len()) {
+        let x_window = &x[i..i + window];
+        for (j, forward, reverse) in &y_windows {
+            for (other, strand) in [
+                (*forward, ReqStrand::Forward),
+                (&reverse[..], ReqStrand::Reverse),
+            ] {
+                let identity = identity(x_window, other);
+                if identity >= min_identity {
+                    dots.push(WindowDot {
+                        x: i,
+                        y: *j,
+                        strand,
+                        identity,
+                    });
+                }
+            }
+        }
+    }
+    dots
+}
+
+/// Merge k-mer matches that are consecutive along the same diagonal (strand `Forward`) or
+/// anti-diagonal (strand `Reverse`) into segments, ordered by strand and start.
+pub fn segments(dots: &[Dot], k: usize) -> Vec<DotSegment> {
+    let mut dots = dots.to_vec();
+    // group matches by strand and (anti-)diagonal, in increasing order of x
+    dots.sort_by_key(|dot| match dot.strand {
+        ReqStrand::Forward => (dot.strand, dot.x as isize - dot.y as isize, dot.x),
+        ReqStrand::Reverse => (dot.strand, (dot.x + dot.y) as isize, dot.x),
+    });
+    dots.dedup();
+
+    let mut segments: Vec<DotSegment> = Vec::new();
+    for dot in dots {
+        let (y_start, y_end) = match dot.strand {
+            ReqStrand::Forward => (dot.y, dot.y + k),
+            ReqStrand::Reverse => (dot.y + k, dot.y),
+        };
+        if let Some(last) = segments.last_mut() {
+            let consecutive = last.strand == dot.strand
+                && last.x_end == dot.x + k - 1
+                && match dot.strand {
+                    ReqStrand::Forward => last.y_end + 1 == y_end,
+                    ReqStrand::Reverse => last.y_end == y_end + 1,
+                };
+            if consecutive {
+                last.x_end += 1;
+                last.y_end = y_end;
+                continue;
+            }
+        }
+        segments.push(DotSegment {
+            x_start: dot.x,
+            y_start,
+            x_end: dot.x + k,
+            y_end,
+            strand: dot.strand,
+        });
+    }
+    segments.sort_by_key(|s| (s.strand, s.x_start, s.y_start));
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmer_matches() {
+        let dots: Vec<_> = kmer_matches(b"ACGTA", b"acgtNACG", 3)
+            .iter()
+            .map(|dot| (dot.x, dot.y, dot.strand))
+            .collect();
+        assert_eq!(
+            dots,
+            [
+                (0, 0, ReqStrand::Forward),
+                (0, 1, ReqStrand::Reverse),
+                (0, 5, ReqStrand::Forward),
+                (1, 0, ReqStrand::Reverse),
+                (1, 1, ReqStrand::Forward),
+                (1, 5, ReqStrand::Reverse),
+            ]
+        );
+        assert!(kmer_matches(b"AC", b"ACGT", 3).is_empty());
+        assert!(kmer_matches(b"NNN", b"NNN", 3).is_empty());
+    }
+
+    #[test]
+    fn test_segments() {
+        // an inverted copy of 10 bases between unrelated sequence
+        let x = b"GATTACAGGCCCTTAAGTCA";
+        let mut y = b"GGGGGGGG".to_vec();
+        y.extend(dna::revcomp(&x[5..15]));
+        y.extend(b"GGGGGGGG");
+        let inverted = segments(&kmer_matches(x, &y, 8), 8);
+        let reverse: Vec<_> = inverted
+            .iter()
+            .filter(|s| s.strand == ReqStrand::Reverse)
+            .collect();
+        assert_eq!(reverse.len(), 1);
+        assert_eq!((reverse[0].x_start, reverse[0].y_start), (5, 18));
+        assert_eq!((reverse[0].x_end, reverse[0].y_end), (15, 8));
+        assert_eq!(reverse[0].len(), 10);
+
+        // repeated k-mers in y yield separate segments, the palindrome ACGT matches both strands
+        let repeated = segments(&kmer_matches(b"ACGTT", b"ACGTTACGTT", 4), 4);
+        let lengths: Vec<_> = repeated.iter().map(|s| (s.len(), s.strand)).collect();
+        assert_eq!(
+            lengths,
+            [
+                (5, ReqStrand::Forward),
+                (5, ReqStrand::Forward),
+                (4, ReqStrand::Reverse),
+                (4, ReqStrand::Reverse),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_window_matches() {
+        let x = b"GATTACAGCCTTAGGA";
+        let mut y = x[..8].to_vec();
+        y[3] = b'A';
+        y.extend(dna::revcomp(&x[8..]));
+        let dots = window_matches(x, &y, 8, 8, 0.8);
+        assert_eq!(dots.len(), 2);
+        assert_eq!(
+            (dots[0].x, dots[0].y, dots[0].strand),
+            (0, 0, ReqStrand::Forward)
+        );
+        assert_relative_eq!(dots[0].identity, 7.0 / 8.0);
+        assert_eq!(
+            (dots[1].x, dots[1].y, dots[1].strand),
+            (8, 8, ReqStrand::Reverse)
+        );
+        assert_relative_eq!(dots[1].identity, 1.0);
+
+        assert_eq!(window_matches(x, &y, 8, 4, 0.0).len(), 2 * 9);
+        assert!(window_matches(b"ACG", b"ACGT", 4, 1, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+//! Heuristic detection of transmembrane (TM) helices and signal peptides in protein sequences
+//! by hydrophobicity windows, meant for a quick triage of candidates.
+//!
+//! TM helices are predicted as in Kyte and Doolittle (1982): segments whose windowed
+//! hydropathy (window of 19 residues) exceeds 1.6. Signal peptides are predicted following the
+//! three region model of von Heijne (1983): a positively charged n-region, a hydrophobic h-region
+//! starting early in the sequence, and a cleavage site obeying the (-3, -1) rule.
+//! Note that the h-region of a signal peptide is usually also reported as TM helix candidate.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::membrane::{signal_peptide, tm_helices};
+//!
+//! // E. coli OmpA precursor (start)
+//! let seq = b"MKKTAIAIAVALAGFATVAQAAPKDNTWYTGAKLGWSQYHDTGFINNNGPTHENQLGAGAFGGYQVNPYVGFEMGYDWLGRMPYKGSVENGAYKAQGVQLTAKLGYPITDDLDIYTRLGGMVWRADTKSNVYGKNHDTGVSPVFAGGVEYAITPEIATRLEYQWTNNIGDAHTIGTRPDNGMLSLGVSYRFG";
+//! let sp = signal_peptide(seq).unwrap();
+//! assert_eq!(sp.cleavage_site, 21);
+//!
+//! let helices = tm_helices(seq, 19, 1.6).unwrap();
+//! assert!(helices.iter().any(|h| h.range.start < sp.cleavage_site));
+//! ```
+
+use std::ops::Range;
+
+use seq_analysis::protein::{profile, regions_above, Scale};
+
+/// A candidate segment with its maximum windowed score.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub range: Range<usize>,
+    pub score: f64,
+}
+
+/// Predict TM helix candidates as segments covering all windows of the given size with a mean
+/// Kyte-Doolittle hydropathy above `threshold` (Kyte and Doolittle suggest 19 and 1.6).
+/// Overlapping candidates are merged. Returns `None` if the sequence contains non-standard
+/// residues.
+pub fn tm_helices(seq: &[u8], window: usize, threshold: f64) -> Option<Vec<Segment>> {
+    let hydropathy = profile(seq, Scale::KyteDoolittle, window)?;
+    let half = window / 2;
+    let mut segments: Vec<Segment> = Vec::new();
+    for centers in regions_above(&hydropathy, threshold, 1) {
+        let score = hydropathy[centers.clone()]
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let range = centers.start.saturating_sub(half)..(centers.end + half).min(seq.len());
+        match segments.last_mut() {
+            Some(last) if last.range.end >= range.start => {
+                last.range.end = range.end;
+                last.score = last.score.max(score);
+            }
+            _ => segments.push(Segment { range, score }),
+        }
+    }
+    Some(segments)
+}
+
+/// A predicted signal peptide.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalPeptide {
+    /// The hydrophobic core (h-region).
+    pub h_region: Range<usize>,
+    /// Position of the first residue of the mature protein, i.e. the signal peptide is
+    /// `seq[..cleavage_site]`.
+    pub cleavage_site: usize,
+}
+
+const H_WINDOW: usize = 7;
+const H_THRESHOLD: f64 = 1.6;
+const MAX_H_START: usize = 20;
+const MAX_CLEAVAGE_SITE: usize = 40;
+
+const MAX_C_REGION: usize = 10;
+
+/// Score of a residue at position -1 of a cleavage site (0 if not allowed).
+fn score_minus_one(aa: u8) -> usize {
+    match aa.to_ascii_uppercase() {
+        b'A' => 3,
+        b'G' | b'S' => 2,
+        b'C' | b'T' => 1,
+        _ => 0,
+    }
+}
+
+/// Score of a residue at position -3 of a cleavage site (0 if not allowed).
+fn score_minus_three(aa: u8) -> usize {
+    match aa.to_ascii_uppercase() {
+        b'A' | b'V' => 2,
+        b'G' | b'S' | b'C' | b'T' | b'I' | b'L' => 1,
+        _ => 0,
+    }
+}
+
+/// Predict a signal peptide at the N-terminus of the given protein. The h-region is the first
+/// stretch of windows of 7 residues with a mean hydropathy of at least 1.6, starting within the
+/// first 20 residues. The n-region before it has to contain more `KR` than `DE`. The cleavage
+/// site is chosen within 10 residues after the h-region and the first 40 residues, requiring a
+/// small residue (`AGSCT`) at -1 and a small or aliphatic residue at -3. Among the candidates,
+/// the best one according to the preferences for A at -1 and A or V at -3 is chosen.
+/// Returns `None` if no signal peptide is found or the sequence contains non-standard residues.
+pub fn signal_peptide(seq: &[u8]) -> Option<SignalPeptide> {
+    let region = &seq[..seq.len().min(MAX_CLEAVAGE_SITE + H_WINDOW)];
+    let hydropathy: Vec<f64> = region
+        .windows(H_WINDOW)
+        .map(|w| {
+            w.iter()
+                .map(|&aa| Scale::KyteDoolittle.value(aa))
+                .sum::<Option<f64>>()
+                .map(|sum| sum / H_WINDOW as f64)
+        })
+        .collect::<Option<_>>()?;
+
+    let h_start = hydropathy.iter().position(|&h| h >= H_THRESHOLD)?;
+    if h_start > MAX_H_START {
+        return None;
+    }
+    let h_windows = hydropathy[h_start..]
+        .iter()
+        .take_while(|&&h| h >= H_THRESHOLD)
+        .count();
+    let h_end = h_start + h_windows - 1 + H_WINDOW;
+
+    let n_charge: isize = seq[..h_start]
+        .iter()
+        .map(|aa| match aa.to_ascii_uppercase() {
+            b'K' | b'R' => 1,
+            b'D' | b'E' => -1,
+            _ => 0,
+        })
+        .sum();
+    if n_charge < 1 {
+        return None;
+    }
+
+    let last = (h_end + MAX_C_REGION).min(MAX_CLEAVAGE_SITE).min(seq.len());
+    let mut best: Option<(usize, usize)> = None;
+    for c in h_end + 1..=last {
+        let (s1, s3) = (score_minus_one(seq[c - 1]), score_minus_three(seq[c - 3]));
+        if s1 > 0 && s3 > 0 && best.map_or(true, |(score, _)| s1 + s3 > score) {
+            best = Some((s1 + s3, c));
+        }
+    }
+    best.map(|(_, c)| c).map(|cleavage_site| SignalPeptide {
+        h_region: h_start..h_end,
+        cleavage_site,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_peptide() {
+        // human serum albumin precursor (start)
+        let seq = b"MKWVTFISLLFLFSSAYSRGVFRRDAHKSEVAHRFKDLGEENFKALVLIAFAQYLQQCPFEDHVK";
+        let sp = signal_peptide(seq).unwrap();
+        assert_eq!(sp.h_region, 2..16);
+        assert_eq!(sp.cleavage_site, 18);
+
+        // no hydrophobic core
+        assert_eq!(
+            signal_peptide(b"MKDEQRSTNKDEQRSTNKDEQRSTNKDEQRSTNKDEQRST"),
+            None
+        );
+        // no positive n-region
+        assert_eq!(signal_peptide(b"MDDLLLLLLLLLLLLAGADEQRSTNKDEQRST"), None);
+        // hydrophobic core without n-region
+        assert_eq!(signal_peptide(b"MKLLLLLLLLLLLLLAGA"), None);
+        assert_eq!(
+            signal_peptide(b"MKKDLLLLLLLLLLLAGAQPAQAQQ"),
+            Some(SignalPeptide {
+                h_region: 2..18,
+                cleavage_site: 23,
+            })
+        );
+    }
+
+    #[test]
+    fn test_tm_helices() {
+        let loop_ = b"DEKRSNQGPDEKRSNQGPDEKRS";
+        let tm = b"LLVIAFLGVLIAVLFWLIA";
+        let mut seq = loop_.to_vec();
+        seq.extend_from_slice(tm);
+        seq.extend_from_slice(loop_);
+        seq.extend_from_slice(tm);
+        seq.extend_from_slice(loop_);
+        let helices = tm_helices(&seq, 19, 1.6).unwrap();
+        assert_eq!(helices.len(), 2);
+        let first = &helices[0];
+        assert!(first.range.start <= 23 && first.range.end >= 42);
+        assert!(first.score > 2.5);
+        assert!(helices[1].range.start > first.range.end);
+        assert!(tm_helices(loop_, 19, 1.6).unwrap().is_empty());
+    }
+}
@@ -5,5 +5,22 @@
 
 //! Sequence analysis algorithms.
 
+pub mod ani;
+pub mod assembly;
+pub mod bisulfite;
+pub mod classify;
+pub mod codon;
+pub mod composition;
+pub mod denoise;
+pub mod dotplot;
 pub mod gc;
+pub mod membrane;
 pub mod orf;
+pub mod protein;
+pub mod repeats;
+pub mod rna;
+pub mod scaffold;
+pub mod screen;
+pub mod synteny;
+pub mod tm;
+pub mod vecscreen;
@@ -0,0 +1,377 @@
+//! Physicochemical properties of protein sequences: molecular weight, net charge and isoelectric
+//! point, hydropathy (GRAVY and Kyte-Doolittle profiles) and the instability index, as well as
+//! sliding window profiles of residue scales like secondary structure propensities or disorder.
+//! Residues are given in one letter code (case insensitive).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::protein;
+//!
+//! let seq = b"MKWVTFISLLFLFSSAYS";
+//! let mw = protein::molecular_weight(seq).unwrap();
+//! assert!((mw - 2140.57).abs() < 0.01);
+//! assert!(protein::gravy(seq).unwrap() > 1.0);
+//! let pi = protein::isoelectric_point(seq);
+//! assert!(pi > 8.0 && pi < 10.0);
+//!
+//! // per residue helix propensity, averaged over windows of 5 residues
+//! let helix = protein::profile(seq, protein::Scale::ChouFasmanHelix, 5).unwrap();
+//! assert_eq!(helix.len(), seq.len());
+//! ```
+
+use std::ops::Range;
+
+/// The 20 standard amino acids in the order used for the tables of this module.
+pub const AMINO_ACIDS: &[u8; 20] = b"ACDEFGHIKLMNPQRSTVWY";
+
+/// Index of the given amino acid in `AMINO_ACIDS`.
+pub fn aa_index(aa: u8) -> Option<usize> {
+    let aa = aa.to_ascii_uppercase();
+    AMINO_ACIDS.iter().position(|&a| a == aa)
+}
+
+/// Average residue masses (Da), in the order of `AMINO_ACIDS`.
+const RESIDUE_MASSES: [f64; 20] = [
+    71.0788, 103.1388, 115.0886, 129.1155, 147.1766, 57.0519, 137.1411, 113.1594, 128.1741,
+    113.1594, 131.1926, 114.1038, 97.1167, 128.1307, 156.1875, 87.0782, 101.1051, 99.1326,
+    186.2132, 163.1760,
+];
+
+const WATER_MASS: f64 = 18.01524;
+
+/// Hydropathy index of Kyte and Doolittle (1982), in the order of `AMINO_ACIDS`.
+const KYTE_DOOLITTLE: [f64; 20] = [
+    1.8, 2.5, -3.5, -3.5, 2.8, -0.4, -3.2, 4.5, -3.9, 3.8, 1.9, -3.5, -1.6, -3.5, -4.5, -0.8, -0.7,
+    4.2, -0.9, -1.3,
+];
+
+/// Helix propensities of Chou and Fasman (1978), in the order of `AMINO_ACIDS`.
+const CHOU_FASMAN_HELIX: [f64; 20] = [
+    1.42, 0.70, 1.01, 1.51, 1.13, 0.57, 1.00, 1.08, 1.14, 1.21, 1.45, 0.67, 0.57, 1.11, 0.98, 0.77,
+    0.83, 1.06, 1.08, 0.69,
+];
+
+/// Beta sheet propensities of Chou and Fasman (1978), in the order of `AMINO_ACIDS`.
+const CHOU_FASMAN_SHEET: [f64; 20] = [
+    0.83, 1.19, 0.54, 0.37, 1.38, 0.75, 0.87, 1.60, 0.74, 1.30, 1.05, 0.89, 0.55, 1.10, 0.93, 0.75,
+    1.19, 1.70, 1.37, 1.47,
+];
+
+/// Turn propensities of Chou and Fasman (1978), in the order of `AMINO_ACIDS`.
+const CHOU_FASMAN_TURN: [f64; 20] = [
+    0.66, 1.19, 1.46, 0.74, 0.60, 1.56, 0.95, 0.47, 1.01, 0.59, 0.60, 1.56, 1.52, 0.98, 0.95, 1.43,
+    0.96, 0.50, 0.96, 1.14,
+];
+
+/// Disorder propensities (TOP-IDP) of Campen et al. (2008), in the order of `AMINO_ACIDS`.
+#[allow(clippy::approx_constant)]
+const TOP_IDP: [f64; 20] = [
+    0.06, 0.02, 0.192, 0.736, -0.697, 0.166, 0.303, -0.486, 0.586, -0.326, -0.397, 0.007, 0.987,
+    0.318, 0.180, 0.341, 0.059, -0.121, -0.884, -0.510,
+];
+
+/// Dipeptide instability weight values of Guruprasad et al. (1990). Row is the first and
+/// column the second residue of a dipeptide, both in the order of `AMINO_ACIDS`.
+#[rustfmt::skip]
+const DIWV: [[f64; 20]; 20] = [
+    // A
+    [1.0, 44.94, -7.49, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 1.0, 1.0, 20.26, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0],
+    // C
+    [1.0, 1.0, 20.26, 1.0, 1.0, 1.0, 33.60, 1.0, 1.0, 20.26, 33.60, 1.0, 20.26, -6.54, 1.0, 1.0, 33.60, -6.54, 24.68, 1.0],
+    // D
+    [1.0, 1.0, 1.0, 1.0, -6.54, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 1.0, 1.0, -6.54, 20.26, -14.03, 1.0, 1.0, 1.0],
+    // E
+    [1.0, 44.94, 20.26, 33.60, 1.0, 1.0, -6.54, 20.26, 1.0, 1.0, 1.0, 1.0, 20.26, 20.26, 1.0, 20.26, 1.0, 1.0, -14.03, 1.0],
+    // F
+    [1.0, 1.0, 13.34, 1.0, 1.0, 1.0, 1.0, 1.0, -14.03, 1.0, 1.0, 1.0, 20.26, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 33.601],
+    // G
+    [-7.49, 1.0, 1.0, -6.54, 1.0, 13.34, 1.0, -7.49, -7.49, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 1.0, -7.49, 1.0, 13.34, -7.49],
+    // H
+    [1.0, 1.0, 1.0, 1.0, -9.37, -9.37, 1.0, 44.94, 24.68, 1.0, 1.0, 24.68, -1.88, 1.0, 1.0, 1.0, -6.54, 1.0, -1.88, 44.94],
+    // I
+    [1.0, 1.0, 1.0, 44.94, 1.0, 1.0, 13.34, 1.0, -7.49, 20.26, 1.0, 1.0, -1.88, 1.0, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0],
+    // K
+    [1.0, 1.0, 1.0, 1.0, 1.0, -7.49, 1.0, -7.49, 1.0, -7.49, 33.60, 1.0, -6.54, 24.64, 33.60, 1.0, 1.0, -7.49, 1.0, 1.0],
+    // L
+    [1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, 1.0, 20.26, 33.60, 20.26, 1.0, 1.0, 1.0, 24.68, 1.0],
+    // M
+    [13.34, 1.0, 1.0, 1.0, 1.0, 1.0, 58.28, 1.0, 1.0, 1.0, -1.88, 1.0, 44.94, -6.54, -6.54, 44.94, -1.88, 1.0, 1.0, 24.68],
+    // N
+    [1.0, -1.88, 1.0, 1.0, -14.03, -14.03, 1.0, 44.94, 24.68, 1.0, 1.0, 1.0, -1.88, -6.54, 1.0, 1.0, -7.49, 1.0, -9.37, 1.0],
+    // P
+    [20.26, -6.54, -6.54, 18.38, 20.26, 1.0, 1.0, 1.0, 1.0, 1.0, -6.54, 1.0, 20.26, 20.26, -6.54, 20.26, 1.0, 20.26, -1.88, 1.0],
+    // Q
+    [1.0, -6.54, 20.26, 20.26, -6.54, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 20.26, 20.26, 1.0, 44.94, 1.0, -6.54, 1.0, -6.54],
+    // R
+    [1.0, 1.0, 1.0, 1.0, 1.0, -7.49, 20.26, 1.0, 1.0, 1.0, 1.0, 13.34, 20.26, 20.26, 58.28, 44.94, 1.0, 1.0, 58.28, -6.54],
+    // S
+    [1.0, 33.60, 1.0, 20.26, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 44.94, 20.26, 20.26, 20.26, 1.0, 1.0, 1.0, 1.0],
+    // T
+    [1.0, 1.0, 1.0, 20.26, 13.34, -7.49, 1.0, 1.0, 1.0, 1.0, 1.0, -14.03, 1.0, -6.54, 1.0, 1.0, 1.0, 1.0, -14.03, 1.0],
+    // V
+    [1.0, 1.0, -14.03, 1.0, 1.0, -7.49, 1.0, 1.0, -1.88, 1.0, 1.0, 1.0, 20.26, 1.0, 1.0, 1.0, -7.49, 1.0, 1.0, -6.54],
+    // W
+    [-14.03, 1.0, 1.0, 1.0, 1.0, -9.37, 24.68, 1.0, 1.0, 13.34, 24.68, 13.34, 1.0, 1.0, 1.0, 1.0, -14.03, -7.49, 1.0, 1.0],
+    // Y
+    [24.68, 1.0, 24.68, -6.54, 1.0, -7.49, 13.34, 1.0, 1.0, 1.0, 44.94, 1.0, 13.34, 1.0, -15.91, 1.0, -7.49, 1.0, -9.37, 13.34],
+];
+
+/// pKa values (EMBOSS) of the ionizable groups.
+const PKA_N_TERM: f64 = 8.6;
+const PKA_C_TERM: f64 = 3.6;
+const PKA_POSITIVE: [(u8, f64); 3] = [(b'K', 10.8), (b'R', 12.5), (b'H', 6.5)];
+const PKA_NEGATIVE: [(u8, f64); 4] = [(b'D', 3.9), (b'E', 4.1), (b'C', 8.5), (b'Y', 10.1)];
+
+/// Map each residue with the given table, returning `None` for non-standard residues.
+fn residue_values<'a>(
+    seq: &'a [u8],
+    table: &'a [f64; 20],
+) -> impl Iterator<Item = Option<f64>> + 'a {
+    seq.iter().map(move |&aa| aa_index(aa).map(|i| table[i]))
+}
+
+/// Average molecular weight (Da) of the given protein.
+/// Returns `None` if the sequence contains non-standard residues.
+pub fn molecular_weight(seq: &[u8]) -> Option<f64> {
+    let residues: f64 = residue_values(seq, &RESIDUE_MASSES).sum::<Option<f64>>()?;
+    Some(if seq.is_empty() {
+        0.0
+    } else {
+        residues + WATER_MASS
+    })
+}
+
+/// Net charge of the given protein at the given pH, computed with the Henderson-Hasselbalch
+/// equation. Residues other than the ionizable ones (`KRHDECY`) are ignored.
+pub fn charge_at_ph(seq: &[u8], ph: f64) -> f64 {
+    if seq.is_empty() {
+        return 0.0;
+    }
+    let positive = |pka: f64| 1.0 / (1.0 + 10f64.powf(ph - pka));
+    let negative = |pka: f64| 1.0 / (1.0 + 10f64.powf(pka - ph));
+    let mut charge = positive(PKA_N_TERM) - negative(PKA_C_TERM);
+    for aa in seq.iter().map(|aa| aa.to_ascii_uppercase()) {
+        if let Some(&(_, pka)) = PKA_POSITIVE.iter().find(|&&(a, _)| a == aa) {
+            charge += positive(pka);
+        } else if let Some(&(_, pka)) = PKA_NEGATIVE.iter().find(|&&(a, _)| a == aa) {
+            charge -= negative(pka);
+        }
+    }
+    charge
+}
+
+/// Isoelectric point of the given protein, i.e. the pH at which its net charge is zero,
+/// found by bisection.
+pub fn isoelectric_point(seq: &[u8]) -> f64 {
+    let (mut low, mut high) = (0.0, 14.0);
+    while high - low > 1e-4 {
+        let mid = (low + high) / 2.0;
+        // the charge decreases with growing pH
+        if charge_at_ph(seq, mid) > 0.0 {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Kyte-Doolittle hydropathy index of the given amino acid.
+pub fn hydropathy(aa: u8) -> Option<f64> {
+    aa_index(aa).map(|i| KYTE_DOOLITTLE[i])
+}
+
+/// Grand average of hydropathy (GRAVY), i.e. the mean Kyte-Doolittle hydropathy of all
+/// residues. Returns `None` for empty sequences and sequences with non-standard residues.
+pub fn gravy(seq: &[u8]) -> Option<f64> {
+    if seq.is_empty() {
+        return None;
+    }
+    let sum: f64 = residue_values(seq, &KYTE_DOOLITTLE).sum::<Option<f64>>()?;
+    Some(sum / seq.len() as f64)
+}
+
+/// Kyte-Doolittle hydropathy profile, i.e. the mean hydropathy of each window of the given
+/// (odd) size, reported at the window's central residue. The profile has
+/// `seq.len() - window + 1` entries, the first one belonging to residue `window / 2`.
+/// Returns `None` if the sequence contains non-standard residues.
+pub fn hydropathy_profile(seq: &[u8], window: usize) -> Option<Vec<f64>> {
+    assert!(window > 0, "Expecting positive window size.");
+    let values: Vec<f64> = residue_values(seq, &KYTE_DOOLITTLE).collect::<Option<_>>()?;
+    Some(
+        values
+            .windows(window)
+            .map(|w| w.iter().sum::<f64>() / window as f64)
+            .collect(),
+    )
+}
+
+/// Per residue scales for sliding window profiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scale {
+    /// Hydropathy index of Kyte and Doolittle (1982).
+    KyteDoolittle,
+    /// Helix propensity of Chou and Fasman (1978).
+    ChouFasmanHelix,
+    /// Beta sheet propensity of Chou and Fasman (1978).
+    ChouFasmanSheet,
+    /// Turn propensity of Chou and Fasman (1978).
+    ChouFasmanTurn,
+    /// Disorder propensity (TOP-IDP) of Campen et al. (2008). Higher values indicate disorder.
+    TopIdp,
+}
+
+impl Scale {
+    fn table(self) -> &'static [f64; 20] {
+        match self {
+            Scale::KyteDoolittle => &KYTE_DOOLITTLE,
+            Scale::ChouFasmanHelix => &CHOU_FASMAN_HELIX,
+            Scale::ChouFasmanSheet => &CHOU_FASMAN_SHEET,
+            Scale::ChouFasmanTurn => &CHOU_FASMAN_TURN,
+            Scale::TopIdp => &TOP_IDP,
+        }
+    }
+
+    /// Value of the given amino acid on this scale.
+    pub fn value(self, aa: u8) -> Option<f64> {
+        aa_index(aa).map(|i| self.table()[i])
+    }
+}
+
+/// Per residue profile of the given scale, i.e. for each residue the mean scale value of the
+/// window of the given (odd) size centered at it. Towards the ends of the sequence, the windows
+/// are truncated. Returns `None` if the sequence contains non-standard residues.
+pub fn profile(seq: &[u8], scale: Scale, window: usize) -> Option<Vec<f64>> {
+    assert!(window > 0, "Expecting positive window size.");
+    let values: Vec<f64> = residue_values(seq, scale.table()).collect::<Option<_>>()?;
+    // prefix sums allow computing each window mean in constant time
+    let mut prefix = Vec::with_capacity(values.len() + 1);
+    prefix.push(0.0);
+    for v in &values {
+        let last = prefix[prefix.len() - 1];
+        prefix.push(last + v);
+    }
+    let half = window / 2;
+    Some(
+        (0..values.len())
+            .map(|i| {
+                let start = i.saturating_sub(half);
+                let end = (i + window - half).min(values.len());
+                (prefix[end] - prefix[start]) / (end - start) as f64
+            })
+            .collect(),
+    )
+}
+
+/// Maximal regions of at least `min_len` consecutive positions with a profile value above
+/// `threshold`, e.g. for disorder or helix predictions from a profile.
+pub fn regions_above(profile: &[f64], threshold: f64, min_len: usize) -> Vec<Range<usize>> {
+    let mut regions = Vec::new();
+    let mut start = None;
+    for (i, &v) in profile.iter().chain(&[f64::NEG_INFINITY]).enumerate() {
+        match (v > threshold, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= min_len {
+                    regions.push(s..i);
+                }
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    regions
+}
+
+/// Predict intrinsically disordered regions of at least `min_len` residues as those with a
+/// windowed TOP-IDP score above `threshold` (e.g. a window of 21 and a threshold of 0.2).
+/// Returns `None` if the sequence contains non-standard residues.
+pub fn disordered_regions(
+    seq: &[u8],
+    window: usize,
+    threshold: f64,
+    min_len: usize,
+) -> Option<Vec<Range<usize>>> {
+    profile(seq, Scale::TopIdp, window).map(|p| regions_above(&p, threshold, min_len))
+}
+
+/// Instability index of Guruprasad et al. (1990). Proteins with an index above 40 are
+/// predicted to be unstable in vitro. Returns `None` for empty sequences and sequences with
+/// non-standard residues.
+pub fn instability_index(seq: &[u8]) -> Option<f64> {
+    if seq.is_empty() {
+        return None;
+    }
+    let indices: Vec<usize> = seq.iter().map(|&aa| aa_index(aa)).collect::<Option<_>>()?;
+    let sum: f64 = indices.windows(2).map(|w| DIWV[w[0]][w[1]]).sum();
+    Some(10.0 / seq.len() as f64 * sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_molecular_weight() {
+        assert_relative_eq!(molecular_weight(b"G").unwrap(), 75.067, epsilon = 1e-3);
+        assert_relative_eq!(molecular_weight(b"a").unwrap(), 89.094, epsilon = 1e-3);
+        assert_eq!(molecular_weight(b""), Some(0.0));
+        assert_eq!(molecular_weight(b"AXA"), None);
+    }
+
+    #[test]
+    fn test_charge() {
+        assert!(isoelectric_point(b"KKKKKRRR") > 11.0);
+        assert!(isoelectric_point(b"DDDEEE") < 4.0);
+        let seq = b"ACDEFGHIKLMNPQRSTVWY";
+        let pi = isoelectric_point(seq);
+        assert!(charge_at_ph(seq, pi).abs() < 1e-3);
+        assert!(charge_at_ph(seq, pi - 1.0) > 0.0);
+    }
+
+    #[test]
+    fn test_hydropathy() {
+        assert_relative_eq!(gravy(AMINO_ACIDS).unwrap(), -0.49, epsilon = 1e-9);
+        assert_eq!(gravy(b""), None);
+        let profile = hydropathy_profile(b"IIIDDD", 3).unwrap();
+        assert_eq!(profile.len(), 4);
+        assert_relative_eq!(profile[0], 4.5);
+        assert_relative_eq!(profile[1], (9.0 - 3.5) / 3.0);
+        assert_eq!(hydropathy(b'r'), Some(-4.5));
+    }
+
+    #[test]
+    fn test_profile() {
+        let seq = b"EEEEEEEEEEPGNPGVVIVYVIV";
+        let helix = profile(seq, Scale::ChouFasmanHelix, 5).unwrap();
+        let sheet = profile(seq, Scale::ChouFasmanSheet, 5).unwrap();
+        assert_eq!(helix.len(), seq.len());
+        assert_relative_eq!(helix[0], 1.51);
+        assert!(helix[4] > sheet[4]);
+        assert!(helix[20] < sheet[20]);
+        // truncated window at the end: I, V, Y, V, I, V (last three)
+        assert_relative_eq!(sheet[22], (1.70 + 1.60 + 1.70) / 3.0);
+        assert_eq!(Scale::TopIdp.value(b'p'), Some(0.987));
+        assert_eq!(profile(b"AZ", Scale::KyteDoolittle, 3), None);
+    }
+
+    #[test]
+    fn test_regions() {
+        let profile = [0.0, 1.0, 1.0, 0.0, 1.0, 1.0, 1.0];
+        assert_eq!(regions_above(&profile, 0.5, 2), vec![1..3, 4..7]);
+        assert_eq!(regions_above(&profile, 0.5, 3), vec![4..7]);
+        let seq = b"MLLIVFAWLLIVFGAPESKPEQSKPEESPKQEPSKESPKEQPSKLLIVFAVLIVFLW";
+        let disordered = disordered_regions(seq, 11, 0.2, 10).unwrap();
+        assert_eq!(disordered.len(), 1);
+        assert!(disordered[0].start > 10 && disordered[0].end < 50);
+    }
+
+    #[test]
+    fn test_instability_index() {
+        // AC: 44.94, CD: 20.26
+        assert_relative_eq!(instability_index(b"ACD").unwrap(), 10.0 / 3.0 * 65.2);
+        assert_relative_eq!(instability_index(b"AAAA").unwrap(), 7.5);
+        assert_eq!(instability_index(b"AB"), None);
+    }
+}
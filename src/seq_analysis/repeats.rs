@@ -0,0 +1,279 @@
+//! Screening of reads or contigs for telomeric and satellite repeat content.
+//!
+//! Copies of each motif are searched on both strands (the motif and its reverse complement),
+//! allowing a number of mismatches per copy so that variant repeats (e.g. `TCAGGG` or `TGAGGG`
+//! in human telomeres) are counted as well. Copies are found greedily from left to right
+//! without overlaps. Copies of the same strand separated by less than one motif length (e.g.
+//! because of small indels) are joined into tracts. Telomeric tracts at the ends of contigs
+//! indicate complete chromosome arms in assemblies, and the overall repeat content of reads is
+//! a QC metric for telomere length estimation.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::repeats::RepeatScanner;
+//!
+//! let scanner = RepeatScanner::telomeric().motif("alpha", b"CATTC").min_tract_copies(2);
+//! let contig = b"CCCTAACCCTAACCCTAACCCTAAGATTACAGATTACAGATTACA";
+//! let reports = scanner.scan(contig);
+//!
+//! let telomere = &reports[0];
+//! assert_eq!(telomere.name, "telomere");
+//! assert_eq!((telomere.forward_copies, telomere.reverse_copies), (0, 4));
+//! assert_eq!(telomere.covered, 24);
+//! assert_eq!((telomere.tracts[0].start, telomere.tracts[0].end), (0, 24));
+//! assert_eq!(reports[1].covered, 0);
+//! ```
+
+use bio_types::strand::ReqStrand;
+
+use alphabets::dna;
+
+/// Telomeric repeat of vertebrates and many other animals.
+pub const TELOMERE_VERTEBRATE: &[u8] = b"TTAGGG";
+/// Telomeric repeat of most plants (Arabidopsis-type).
+pub const TELOMERE_PLANT: &[u8] = b"TTTAGGG";
+/// Telomeric repeat of most insects.
+pub const TELOMERE_INSECT: &[u8] = b"TTAGG";
+/// Telomeric repeat of nematodes.
+pub const TELOMERE_NEMATODE: &[u8] = b"TTAGGC";
+
+/// A tract of tandem copies of a motif.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepeatTract {
+    pub start: usize,
+    pub end: usize,
+    pub copies: usize,
+    /// `Forward` for copies of the motif, `Reverse` for copies of its reverse complement.
+    pub strand: ReqStrand,
+}
+
+impl RepeatTract {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Repeat content of a sequence for one motif.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotifReport {
+    pub name: String,
+    pub forward_copies: usize,
+    pub reverse_copies: usize,
+    /// Number of bases covered by copies of either strand.
+    pub covered: usize,
+    /// Fraction of the sequence covered by copies.
+    pub fraction: f64,
+    /// Tracts with at least the minimal number of copies, ordered by position.
+    pub tracts: Vec<RepeatTract>,
+}
+
+impl MotifReport {
+    /// Whether a tract starts at the beginning or ends at the end of a sequence of the given
+    /// length, allowing for the given number of non-repeat bases.
+    pub fn at_ends(&self, len: usize, tolerance: usize) -> (bool, bool) {
+        (
+            self.tracts.iter().any(|tract| tract.start <= tolerance),
+            self.tracts.iter().any(|tract| tract.end + tolerance >= len),
+        )
+    }
+}
+
+/// Scanner for the repeat content of sequences.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepeatScanner {
+    motifs: Vec<(String, Vec<u8>)>,
+    max_mismatches: usize,
+    min_tract_copies: usize,
+}
+
+impl Default for RepeatScanner {
+    fn default() -> Self {
+        RepeatScanner {
+            motifs: Vec::new(),
+            max_mismatches: 1,
+            min_tract_copies: 3,
+        }
+    }
+}
+
+impl RepeatScanner {
+    /// Create a scanner without motifs, allowing one mismatch per copy and reporting tracts of
+    /// at least three copies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a scanner for the vertebrate telomeric repeat, named `telomere`.
+    pub fn telomeric() -> Self {
+        Self::default().motif("telomere", TELOMERE_VERTEBRATE)
+    }
+
+    /// Add a motif with the given name.
+    pub fn motif(mut self, name: &str, motif: &[u8]) -> Self {
+        assert!(!motif.is_empty(), "Expecting non-empty motif.");
+        self.motifs
+            .push((name.to_owned(), motif.to_ascii_uppercase()));
+        self
+    }
+
+    /// Set the maximal number of mismatches per copy of a motif.
+    pub fn max_mismatches(mut self, max_mismatches: usize) -> Self {
+        self.max_mismatches = max_mismatches;
+        self
+    }
+
+    /// Set the minimal number of copies of reported tracts.
+    pub fn min_tract_copies(mut self, min_tract_copies: usize) -> Self {
+        self.min_tract_copies = min_tract_copies;
+        self
+    }
+
+    /// Start positions of the greedily found, non-overlapping copies of the motif.
+    fn copies(&self, seq: &[u8], motif: &[u8]) -> Vec<usize> {
+        let k = motif.len();
+        let mut copies = Vec::new();
+        let mut i = 0;
+        while i + k <= seq.len() {
+            let mismatches = seq[i..i + k]
+                .iter()
+                .zip(motif)
+                .filter(|&(a, b)| a.to_ascii_uppercase() != *b)
+                .count();
+            // require more matches than mismatches, for short motifs
+            if mismatches <= self.max_mismatches && 2 * mismatches < k {
+                copies.push(i);
+                i += k;
+            } else {
+                i += 1;
+            }
+        }
+        copies
+    }
+
+    /// Join copies into tracts.
+    fn tracts(&self, copies: &[usize], k: usize, strand: ReqStrand) -> Vec<RepeatTract> {
+        let mut tracts = Vec::new();
+        let mut current: Option<RepeatTract> = None;
+        for &start in copies {
+            match current {
+                Some(ref mut tract) if start < tract.end + k => {
+                    tract.end = start + k;
+                    tract.copies += 1;
+                }
+                _ => {
+                    tracts.extend(current.take());
+                    current = Some(RepeatTract {
+                        start,
+                        end: start + k,
+                        copies: 1,
+                        strand,
+                    });
+                }
+            }
+        }
+        tracts.extend(current);
+        tracts.retain(|tract| tract.copies >= self.min_tract_copies);
+        tracts
+    }
+
+    /// Repeat content of the given sequence, in the order the motifs were added.
+    pub fn scan(&self, seq: &[u8]) -> Vec<MotifReport> {
+        self.motifs
+            .iter()
+            .map(|(name, motif)| {
+                let k = motif.len();
+                let reverse_motif = dna::revcomp(motif);
+                let forward = self.copies(seq, motif);
+                // palindromic motifs are only counted once
+                let reverse = if reverse_motif == *motif {
+                    Vec::new()
+                } else {
+                    self.copies(seq, &reverse_motif)
+                };
+
+                let mut covered = vec![false; seq.len()];
+                for &start in forward.iter().chain(&reverse) {
+                    for c in &mut covered[start..start + k] {
+                        *c = true;
+                    }
+                }
+                let covered = covered.iter().filter(|&&c| c).count();
+
+                let mut tracts = self.tracts(&forward, k, ReqStrand::Forward);
+                tracts.extend(self.tracts(&reverse, k, ReqStrand::Reverse));
+                tracts.sort_by_key(|tract| (tract.start, tract.end));
+                MotifReport {
+                    name: name.clone(),
+                    forward_copies: forward.len(),
+                    reverse_copies: reverse.len(),
+                    covered,
+                    fraction: if seq.is_empty() {
+                        0.0
+                    } else {
+                        covered as f64 / seq.len() as f64
+                    },
+                    tracts,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants() {
+        // canonical and variant repeats, with a single base insertion
+        let seq = b"TTAGGGTCAGGGTTAGGGTTAGGGATTAGGGTGAGGGacgtacgtacgt";
+        let scanner = RepeatScanner::telomeric();
+        let report = &scanner.scan(seq)[0];
+        assert_eq!(report.forward_copies, 6);
+        assert_eq!(report.reverse_copies, 0);
+        assert_eq!(report.covered, 36);
+        assert_eq!(report.tracts.len(), 1);
+        assert_eq!(report.tracts[0].copies, 6);
+        assert_eq!((report.tracts[0].start, report.tracts[0].end), (0, 37));
+        assert_eq!(report.at_ends(seq.len(), 0), (true, false));
+        assert_eq!(report.at_ends(seq.len(), 12), (true, true));
+
+        let strict = RepeatScanner::telomeric().max_mismatches(0);
+        assert_eq!(strict.scan(seq)[0].forward_copies, 4);
+    }
+
+    #[test]
+    fn test_strands() {
+        let mut seq = b"CCCTAA".repeat(5);
+        seq.extend(b"ACGTACGTACGTACGT");
+        seq.extend(b"ttaggg".repeat(4));
+        let report = &RepeatScanner::telomeric().scan(&seq)[0];
+        assert_eq!((report.forward_copies, report.reverse_copies), (4, 5));
+        let strands: Vec<_> = report.tracts.iter().map(|t| t.strand).collect();
+        assert_eq!(strands, [ReqStrand::Reverse, ReqStrand::Forward]);
+        assert_relative_eq!(report.fraction, 54.0 / 70.0);
+        assert_eq!(report.at_ends(seq.len(), 0), (true, true));
+    }
+
+    #[test]
+    fn test_satellite() {
+        // palindromic motifs are counted once
+        let scanner = RepeatScanner::new()
+            .motif("palindrome", b"GAATTC")
+            .max_mismatches(0)
+            .min_tract_copies(2);
+        let report = &scanner.scan(b"GAATTCGAATTCAAAA")[0];
+        assert_eq!((report.forward_copies, report.reverse_copies), (2, 0));
+        assert_eq!(report.tracts.len(), 1);
+        // short motifs need more matches than mismatches
+        let scanner = RepeatScanner::new().motif("short", b"AT").max_mismatches(1);
+        assert_eq!(scanner.scan(b"GGGGGG")[0].covered, 0);
+        assert!(RepeatScanner::new().scan(b"ACGT").is_empty());
+        assert_eq!(RepeatScanner::telomeric().scan(b"")[0].fraction, 0.0);
+    }
+}
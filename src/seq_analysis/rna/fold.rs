@@ -0,0 +1,416 @@
+//! RNA secondary structure prediction by base pair maximization (Nussinov and Jacobson, 1980)
+//! and by free energy minimization with a simplified nearest neighbor energy model
+//! (Zuker and Stiegler, 1981).
+//!
+//! Canonical (Watson-Crick) and wobble (GU) base pairs are allowed, `T` is treated as `U`.
+//! The energy model uses the stacking energies of Turner 2004, loop initiation energies
+//! depending on loop type and length, a linear multiloop model and terminal AU/GU penalties.
+//! Sequence dependent mismatch and special loop energies are omitted, hence energies are
+//! approximate. Energies are given in kcal/mol. Pseudoknots are not considered.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::rna::fold::{mfe, nussinov};
+//!
+//! let (pairs, structure) = nussinov(b"GGGAAAUCC", 3);
+//! assert_eq!(pairs, 3);
+//! assert_eq!(structure, "(((...)))");
+//!
+//! let (energy, structure) = mfe(b"GGGGAAACCCC");
+//! assert_eq!(structure, "((((...))))");
+//! assert!((energy - -4.5).abs() < 1e-9);
+//! ```
+
+use std::cmp;
+
+/// Minimum number of unpaired bases in a hairpin loop used by `mfe`.
+pub const MIN_HAIRPIN: usize = 3;
+
+/// Maximum total size of interior loops and bulges used by `mfe`.
+pub const MAX_INTERIOR: usize = 30;
+
+/// Base pair type index (CG, GC, GU, UG, AU, UA) of two bases, if they can pair.
+fn pair_type(a: u8, b: u8) -> Option<usize> {
+    let normalize = |x: u8| match x.to_ascii_uppercase() {
+        b'T' => b'U',
+        x => x,
+    };
+    match (normalize(a), normalize(b)) {
+        (b'C', b'G') => Some(0),
+        (b'G', b'C') => Some(1),
+        (b'G', b'U') => Some(2),
+        (b'U', b'G') => Some(3),
+        (b'A', b'U') => Some(4),
+        (b'U', b'A') => Some(5),
+        _ => None,
+    }
+}
+
+/// Whether the given bases can form a canonical or wobble base pair.
+pub fn can_pair(a: u8, b: u8) -> bool {
+    pair_type(a, b).is_some()
+}
+
+/// Maximize the number of base pairs with hairpin loops of at least `min_loop` bases.
+/// Returns the number of pairs and the structure in dot-bracket notation.
+pub fn nussinov(seq: &[u8], min_loop: usize) -> (usize, String) {
+    let n = seq.len();
+    if n == 0 {
+        return (0, String::new());
+    }
+    let can_pair = |i: usize, j: usize| j > i + min_loop && pair_type(seq[i], seq[j]).is_some();
+    // dp[i][j]: maximum number of pairs in seq[i..=j]
+    let mut dp = vec![vec![0usize; n]; n];
+    for d in 1..n {
+        for i in 0..n - d {
+            let j = i + d;
+            let mut best = cmp::max(dp[i + 1][j], dp[i][j - 1]);
+            if can_pair(i, j) {
+                best = cmp::max(best, dp[i + 1][j - 1] + 1);
+            }
+            for k in i + 1..j {
+                best = cmp::max(best, dp[i][k] + dp[k + 1][j]);
+            }
+            dp[i][j] = best;
+        }
+    }
+
+    let mut structure = vec![b'.'; n];
+    let mut stack = vec![(0, n - 1)];
+    while let Some((i, j)) = stack.pop() {
+        if i >= j || dp[i][j] == 0 {
+            continue;
+        }
+        if dp[i][j] == dp[i + 1][j] {
+            stack.push((i + 1, j));
+        } else if dp[i][j] == dp[i][j - 1] {
+            stack.push((i, j - 1));
+        } else if can_pair(i, j) && dp[i][j] == dp[i + 1][j - 1] + 1 {
+            structure[i] = b'(';
+            structure[j] = b')';
+            stack.push((i + 1, j - 1));
+        } else {
+            let k = (i + 1..j)
+                .find(|&k| dp[i][j] == dp[i][k] + dp[k + 1][j])
+                .expect("bug: no traceback for bifurcation");
+            stack.push((i, k));
+            stack.push((k + 1, j));
+        }
+    }
+
+    (dp[0][n - 1], String::from_utf8(structure).unwrap())
+}
+
+// Energies in units of 0.01 kcal/mol.
+const INF: i32 = i32::MAX / 4;
+
+/// Stacking energies of Turner 2004, indexed by the type of the outer pair (i, j) and the
+/// type of the reversed inner pair (j - 1, i + 1).
+#[rustfmt::skip]
+const STACK: [[i32; 6]; 6] = [
+    [-240, -330, -210, -140, -210, -210],
+    [-330, -340, -250, -150, -220, -240],
+    [-210, -250,  130,  -50, -140, -130],
+    [-140, -150,  -50,   30,  -60, -100],
+    [-210, -220, -140,  -60, -110,  -90],
+    [-210, -240, -130, -100,  -90, -130],
+];
+
+/// Hairpin loop initiation by loop length (0 to 9).
+const HAIRPIN: [i32; 10] = [INF, INF, INF, 540, 560, 570, 540, 600, 550, 640];
+/// Bulge loop initiation by loop length (0 to 6).
+const BULGE: [i32; 7] = [INF, 380, 280, 320, 360, 400, 440];
+/// Interior loop initiation by loop length (0 to 6).
+const INTERIOR: [i32; 7] = [INF, INF, 50, 160, 110, 200, 200];
+/// Penalty per unit of loop asymmetry and its maximum.
+const NINIO: i32 = 60;
+const MAX_NINIO: i32 = 300;
+/// Terminal AU or GU pair penalty.
+const TERMINAL_AU: i32 = 50;
+/// Multiloop closing, unpaired base and branch penalties.
+const ML_CLOSING: i32 = 340;
+const ML_BASE: i32 = 0;
+const ML_BRANCH: i32 = 40;
+
+/// Extrapolate loop energies beyond the tabulated lengths (Jacobson-Stockmayer).
+fn loop_extrapolation(table: &[i32], len: usize) -> i32 {
+    let max = table.len() - 1;
+    if len <= max {
+        table[len]
+    } else {
+        table[max] + (107.856 * (len as f64 / max as f64).ln()).round() as i32
+    }
+}
+
+fn terminal(pair: usize) -> i32 {
+    if pair >= 2 {
+        TERMINAL_AU
+    } else {
+        0
+    }
+}
+
+struct Model<'a> {
+    seq: &'a [u8],
+}
+
+impl<'a> Model<'a> {
+    fn pair(&self, i: usize, j: usize) -> Option<usize> {
+        if j > i + MIN_HAIRPIN {
+            pair_type(self.seq[i], self.seq[j])
+        } else {
+            None
+        }
+    }
+
+    fn hairpin(&self, i: usize, j: usize, pair: usize) -> i32 {
+        loop_extrapolation(&HAIRPIN, j - i - 1) + terminal(pair)
+    }
+
+    /// Energy of the interior loop, bulge or stack closed by (i, j) and (p, q).
+    fn interior(&self, i: usize, j: usize, p: usize, q: usize, outer: usize, inner: usize) -> i32 {
+        let (l1, l2) = (p - i - 1, j - q - 1);
+        let reversed_inner = pair_type(self.seq[q], self.seq[p]).unwrap();
+        if l1 == 0 && l2 == 0 {
+            STACK[outer][reversed_inner]
+        } else if l1 == 0 || l2 == 0 {
+            let len = l1 + l2;
+            if len == 1 {
+                // single base bulges keep the stacking of the adjacent pairs
+                BULGE[1] + STACK[outer][reversed_inner]
+            } else {
+                loop_extrapolation(&BULGE, len) + terminal(outer) + terminal(inner)
+            }
+        } else {
+            let asymmetry = (l1 as i32 - l2 as i32).abs() * NINIO;
+            loop_extrapolation(&INTERIOR, l1 + l2)
+                + cmp::min(asymmetry, MAX_NINIO)
+                + terminal(outer)
+                + terminal(inner)
+        }
+    }
+}
+
+/// Tables of the minimum free energy dynamic programming.
+struct Tables {
+    /// v[i][j]: minimum energy of seq[i..=j] given that i and j pair.
+    v: Vec<Vec<i32>>,
+    /// wm[i][j]: minimum energy of seq[i..=j] as part of a multiloop with at least one branch.
+    wm: Vec<Vec<i32>>,
+    /// f[j]: minimum energy of the prefix of length j.
+    f: Vec<i32>,
+}
+
+#[allow(clippy::needless_range_loop)]
+fn fill(model: &Model) -> Tables {
+    let n = model.seq.len();
+    let mut v = vec![vec![INF; n]; n];
+    let mut wm = vec![vec![INF; n]; n];
+    for d in MIN_HAIRPIN + 1..n {
+        for i in 0..n - d {
+            let j = i + d;
+            if let Some(outer) = model.pair(i, j) {
+                let mut e = model.hairpin(i, j, outer);
+                for p in i + 1..cmp::min(i + MAX_INTERIOR + 2, j) {
+                    let l1 = p - i - 1;
+                    for q in (p + 1..j).rev() {
+                        if l1 + (j - q - 1) > MAX_INTERIOR {
+                            break;
+                        }
+                        if v[p][q] < INF {
+                            let inner = model.pair(p, q).unwrap();
+                            e = cmp::min(e, model.interior(i, j, p, q, outer, inner) + v[p][q]);
+                        }
+                    }
+                }
+                for u in i + 2..j - 1 {
+                    let branches = wm[i + 1][u] + wm[u + 1][j - 1];
+                    e = cmp::min(e, branches + ML_CLOSING + ML_BRANCH + terminal(outer));
+                }
+                v[i][j] = e;
+            }
+
+            let mut e = cmp::min(wm[i + 1][j], wm[i][j - 1]) + ML_BASE;
+            if v[i][j] < INF {
+                e = cmp::min(e, v[i][j] + ML_BRANCH + terminal(model.pair(i, j).unwrap()));
+            }
+            for k in i + 1..j {
+                e = cmp::min(e, wm[i][k] + wm[k + 1][j]);
+            }
+            wm[i][j] = cmp::min(e, INF);
+        }
+    }
+
+    let mut f = vec![0; n + 1];
+    for j in 1..=n {
+        let mut e = f[j - 1];
+        for k in 0..j {
+            if v[k][j - 1] < INF {
+                let pair = model.pair(k, j - 1).unwrap();
+                e = cmp::min(e, f[k] + v[k][j - 1] + terminal(pair));
+            }
+        }
+        f[j] = e;
+    }
+
+    Tables { v, wm, f }
+}
+
+enum Trace {
+    Paired(usize, usize),
+    Multi(usize, usize),
+}
+
+fn traceback(model: &Model, tables: &Tables) -> Vec<u8> {
+    let n = model.seq.len();
+    let (v, wm, f) = (&tables.v, &tables.wm, &tables.f);
+    let mut structure = vec![b'.'; n];
+    let mut stack = Vec::new();
+
+    let mut j = n;
+    while j > 0 {
+        if f[j] == f[j - 1] {
+            j -= 1;
+            continue;
+        }
+        let k = (0..j)
+            .find(|&k| {
+                v[k][j - 1] < INF
+                    && f[j] == f[k] + v[k][j - 1] + terminal(model.pair(k, j - 1).unwrap())
+            })
+            .expect("bug: no traceback for exterior loop");
+        stack.push(Trace::Paired(k, j - 1));
+        j = k;
+    }
+
+    while let Some(trace) = stack.pop() {
+        match trace {
+            Trace::Paired(i, j) => {
+                structure[i] = b'(';
+                structure[j] = b')';
+                let outer = model.pair(i, j).unwrap();
+                if v[i][j] == model.hairpin(i, j, outer) {
+                    continue;
+                }
+                let mut found = false;
+                'interior: for p in i + 1..cmp::min(i + MAX_INTERIOR + 2, j) {
+                    for q in (p + 1..j).rev() {
+                        if (p - i - 1) + (j - q - 1) > MAX_INTERIOR {
+                            break;
+                        }
+                        if v[p][q] < INF {
+                            let inner = model.pair(p, q).unwrap();
+                            if v[i][j] == model.interior(i, j, p, q, outer, inner) + v[p][q] {
+                                stack.push(Trace::Paired(p, q));
+                                found = true;
+                                break 'interior;
+                            }
+                        }
+                    }
+                }
+                if !found {
+                    let u = (i + 2..j - 1)
+                        .find(|&u| {
+                            v[i][j]
+                                == wm[i + 1][u]
+                                    + wm[u + 1][j - 1]
+                                    + ML_CLOSING
+                                    + ML_BRANCH
+                                    + terminal(outer)
+                        })
+                        .expect("bug: no traceback for paired bases");
+                    stack.push(Trace::Multi(i + 1, u));
+                    stack.push(Trace::Multi(u + 1, j - 1));
+                }
+            }
+            Trace::Multi(i, j) => {
+                let e = wm[i][j];
+                if v[i][j] < INF && e == v[i][j] + ML_BRANCH + terminal(model.pair(i, j).unwrap()) {
+                    stack.push(Trace::Paired(i, j));
+                } else if i < j && e == wm[i + 1][j] + ML_BASE {
+                    stack.push(Trace::Multi(i + 1, j));
+                } else if i < j && e == wm[i][j - 1] + ML_BASE {
+                    stack.push(Trace::Multi(i, j - 1));
+                } else {
+                    let k = (i + 1..j)
+                        .find(|&k| e == wm[i][k] + wm[k + 1][j])
+                        .expect("bug: no traceback for multiloop");
+                    stack.push(Trace::Multi(i, k));
+                    stack.push(Trace::Multi(k + 1, j));
+                }
+            }
+        }
+    }
+
+    structure
+}
+
+/// Predict the minimum free energy structure. Returns the energy (kcal/mol) and the structure
+/// in dot-bracket notation. Complexity: O(n^3) time and O(n^2) space.
+pub fn mfe(seq: &[u8]) -> (f64, String) {
+    if seq.is_empty() {
+        return (0.0, String::new());
+    }
+    let model = Model { seq };
+    let tables = fill(&model);
+    let structure = traceback(&model, &tables);
+    (
+        f64::from(tables.f[seq.len()]) / 100.0,
+        String::from_utf8(structure).unwrap(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that the brackets are balanced and only enclose valid pairs.
+    fn validate(seq: &[u8], structure: &str, min_loop: usize) -> usize {
+        let mut stack = Vec::new();
+        let mut pairs = 0;
+        for (j, c) in structure.bytes().enumerate() {
+            match c {
+                b'(' => stack.push(j),
+                b')' => {
+                    let i = stack.pop().unwrap();
+                    assert!(pair_type(seq[i], seq[j]).is_some());
+                    assert!(j - i > min_loop);
+                    pairs += 1;
+                }
+                _ => assert_eq!(c, b'.'),
+            }
+        }
+        assert!(stack.is_empty());
+        pairs
+    }
+
+    #[test]
+    fn test_nussinov() {
+        let seq = b"GGGAAAUCCAGCUAGCGAAAGCUAGGAUCC";
+        let (pairs, structure) = nussinov(seq, 3);
+        assert_eq!(structure.len(), seq.len());
+        assert_eq!(validate(seq, &structure, 3), pairs);
+        assert_eq!(nussinov(b"GCGC", 0).0, 2);
+        assert_eq!(nussinov(b"GCGC", 3).0, 0);
+        assert_eq!(nussinov(b"AAAA", 3), (0, "....".to_owned()));
+        assert_eq!(nussinov(b"", 3), (0, String::new()));
+    }
+
+    #[test]
+    fn test_mfe() {
+        assert_eq!(mfe(b"AAAAAAA"), (0.0, ".......".to_owned()));
+        // stacks of GC/GC (-3.3) and CG/GC (-2.4) (and AU/UA (-1.3))
+        let (energy, structure) = mfe(b"GCGCAAAAGCGC");
+        assert_eq!(structure, "((((....))))");
+        assert_relative_eq!(energy, -3.4 - 2.4 - 3.4 + 5.6, epsilon = 1e-9);
+
+        // a tRNA-like multi branched structure
+        let seq = b"GCGGAUUUAGCUCAGUUGGGAGAGCGCCAGACUGAAGAUCUGGAGGUCCUGUGUUCGAUCCACAGAAUUCGCACCA";
+        let (energy, structure) = mfe(seq);
+        assert!(energy < -15.0);
+        assert!(validate(seq, &structure, MIN_HAIRPIN) > 15);
+        let (_, dna_structure) = mfe(b"GGGGAAACCCC");
+        assert_eq!(mfe(b"ggggaaacccc").1, dna_structure);
+    }
+}
@@ -0,0 +1,4 @@
+//! RNA structure analysis.
+
+pub mod fold;
+pub mod structure;
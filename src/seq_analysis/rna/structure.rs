@@ -0,0 +1,312 @@
+//! RNA secondary structures in dot-bracket notation and as pair tables.
+//!
+//! Unpaired bases are written as `.` (or `,`, `_`, `-`, `:`, `~`). Pairs are written with
+//! matching brackets, where pseudoknotted pairs use additional bracket layers: `()`, `[]`, `{}`,
+//! `<>`, followed by the letters `Aa`, `Bb`, ... (upper case opening, lower case closing).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::rna::structure::PairTable;
+//!
+//! let s = PairTable::from_dot_bracket("((..[[..))..]]").unwrap();
+//! assert_eq!(s.partner(0), Some(9));
+//! assert_eq!(s.partner(4), Some(13));
+//! assert!(!s.is_pseudoknot_free());
+//! assert_eq!(s.to_dot_bracket(), "((..[[..))..]]");
+//!
+//! let t = PairTable::from_dot_bracket("((......))....").unwrap();
+//! assert_eq!(s.base_pair_distance(&t), 2);
+//! ```
+
+use seq_analysis::rna::fold::can_pair;
+
+/// Bracket layers used for writing pairs, from the outermost to innermost pseudoknot level.
+const BRACKETS: [(u8, u8); 4] = [(b'(', b')'), (b'[', b']'), (b'{', b'}'), (b'<', b'>')];
+
+quick_error! {
+    #[derive(Debug, PartialEq)]
+    pub enum StructureError {
+        InvalidSymbol(pos: usize, symbol: u8) {
+            description("invalid symbol in dot-bracket string")
+            display("invalid symbol '{}' at position {}", char::from(*symbol), pos)
+        }
+        UnmatchedClosing(pos: usize) {
+            description("closing bracket without opening bracket")
+            display("unmatched closing bracket at position {}", pos)
+        }
+        UnmatchedOpening(pos: usize) {
+            description("opening bracket without closing bracket")
+            display("unmatched opening bracket at position {}", pos)
+        }
+        LengthMismatch(structure_len: usize, seq_len: usize) {
+            description("structure and sequence lengths differ")
+            display("structure length {} differs from sequence length {}", structure_len, seq_len)
+        }
+        InvalidPair(i: usize, j: usize) {
+            description("paired bases cannot form a canonical or wobble pair")
+            display("bases at positions {} and {} cannot pair", i, j)
+        }
+    }
+}
+
+/// Layer of a bracket symbol and whether it opens a pair.
+fn bracket_layer(symbol: u8) -> Option<(usize, bool)> {
+    if let Some(layer) = BRACKETS.iter().position(|&(open, _)| open == symbol) {
+        Some((layer, true))
+    } else if let Some(layer) = BRACKETS.iter().position(|&(_, close)| close == symbol) {
+        Some((layer, false))
+    } else if symbol.is_ascii_uppercase() {
+        Some((BRACKETS.len() + (symbol - b'A') as usize, true))
+    } else if symbol.is_ascii_lowercase() {
+        Some((BRACKETS.len() + (symbol - b'a') as usize, false))
+    } else {
+        None
+    }
+}
+
+fn layer_brackets(layer: usize) -> (u8, u8) {
+    if layer < BRACKETS.len() {
+        BRACKETS[layer]
+    } else {
+        let offset = (layer - BRACKETS.len()) as u8;
+        assert!(offset < 26, "Too many pseudoknot layers.");
+        (b'A' + offset, b'a' + offset)
+    }
+}
+
+/// A secondary structure given by the pairing partner of each base.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PairTable {
+    partners: Vec<Option<usize>>,
+}
+
+impl PairTable {
+    /// Create an open chain of the given length, i.e. a structure without pairs.
+    pub fn new(len: usize) -> Self {
+        PairTable {
+            partners: vec![None; len],
+        }
+    }
+
+    /// Parse a structure in dot-bracket notation, possibly with pseudoknots.
+    pub fn from_dot_bracket(structure: &str) -> Result<Self, StructureError> {
+        let mut table = PairTable::new(structure.len());
+        let mut stacks: Vec<Vec<usize>> = Vec::new();
+        for (j, &symbol) in structure.as_bytes().iter().enumerate() {
+            match symbol {
+                b'.' | b',' | b'_' | b'-' | b':' | b'~' => continue,
+                _ => (),
+            }
+            let (layer, opening) =
+                bracket_layer(symbol).ok_or(StructureError::InvalidSymbol(j, symbol))?;
+            if stacks.len() <= layer {
+                stacks.resize(layer + 1, Vec::new());
+            }
+            if opening {
+                stacks[layer].push(j);
+            } else {
+                let i = stacks[layer]
+                    .pop()
+                    .ok_or(StructureError::UnmatchedClosing(j))?;
+                table.partners[i] = Some(j);
+                table.partners[j] = Some(i);
+            }
+        }
+        match stacks.iter().filter_map(|stack| stack.first()).min() {
+            Some(&i) => Err(StructureError::UnmatchedOpening(i)),
+            None => Ok(table),
+        }
+    }
+
+    /// Create a structure from a list of pairs. Returns `None` if a position is out of bounds,
+    /// paired to itself or involved in several pairs.
+    pub fn from_pairs<I: IntoIterator<Item = (usize, usize)>>(
+        len: usize,
+        pairs: I,
+    ) -> Option<Self> {
+        let mut table = PairTable::new(len);
+        for (i, j) in pairs {
+            if i == j || i >= len || j >= len {
+                return None;
+            }
+            if table.partners[i].is_some() || table.partners[j].is_some() {
+                return None;
+            }
+            table.partners[i] = Some(j);
+            table.partners[j] = Some(i);
+        }
+        Some(table)
+    }
+
+    /// Number of bases.
+    pub fn len(&self) -> usize {
+        self.partners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.partners.is_empty()
+    }
+
+    /// Pairing partner of the given base, if any.
+    pub fn partner(&self, i: usize) -> Option<usize> {
+        self.partners[i]
+    }
+
+    /// Iterate over all pairs `(i, j)` with `i < j`, ordered by `i`.
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.partners
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &partner)| partner.filter(|&j| j > i).map(|j| (i, j)))
+    }
+
+    /// Number of pairs.
+    pub fn num_pairs(&self) -> usize {
+        self.pairs().count()
+    }
+
+    /// Whether no two pairs cross each other.
+    pub fn is_pseudoknot_free(&self) -> bool {
+        let mut stack = Vec::new();
+        for (j, &partner) in self.partners.iter().enumerate() {
+            match partner {
+                Some(i) if i > j => stack.push(j),
+                Some(i) if stack.pop() != Some(i) => return false,
+                _ => (),
+            }
+        }
+        true
+    }
+
+    /// Check that the structure matches the given sequence in length and consists of canonical
+    /// and wobble pairs only.
+    pub fn validate(&self, seq: &[u8]) -> Result<(), StructureError> {
+        if seq.len() != self.len() {
+            return Err(StructureError::LengthMismatch(self.len(), seq.len()));
+        }
+        match self.pairs().find(|&(i, j)| !can_pair(seq[i], seq[j])) {
+            Some((i, j)) => Err(StructureError::InvalidPair(i, j)),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the structure in dot-bracket notation. Pairs are greedily assigned to the first
+    /// bracket layer in which they do not cross any other pair, so that pseudoknot-free
+    /// structures are written with parentheses only.
+    pub fn to_dot_bracket(&self) -> String {
+        let mut layers: Vec<Vec<(usize, usize)>> = Vec::new();
+        let mut structure = vec![b'.'; self.len()];
+        for (i, j) in self.pairs() {
+            let crosses =
+                |&(k, l): &(usize, usize)| (k < i && i < l && l < j) || (i < k && k < j && j < l);
+            let layer = match layers.iter().position(|layer| !layer.iter().any(crosses)) {
+                Some(layer) => layer,
+                None => {
+                    layers.push(Vec::new());
+                    layers.len() - 1
+                }
+            };
+            layers[layer].push((i, j));
+            let (open, close) = layer_brackets(layer);
+            structure[i] = open;
+            structure[j] = close;
+        }
+        String::from_utf8(structure).unwrap()
+    }
+
+    /// Base pair distance, i.e. the number of pairs contained in exactly one of both structures.
+    pub fn base_pair_distance(&self, other: &PairTable) -> usize {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "Structures must have the same length."
+        );
+        let only_self = self
+            .pairs()
+            .filter(|&(i, j)| other.partners[i] != Some(j))
+            .count();
+        let only_other = other
+            .pairs()
+            .filter(|&(i, j)| self.partners[i] != Some(j))
+            .count();
+        only_self + only_other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seq_analysis::rna::fold::mfe;
+
+    #[test]
+    fn test_parse() {
+        let s = PairTable::from_dot_bracket("((.))..(..)").unwrap();
+        assert_eq!(s.len(), 11);
+        assert_eq!(s.pairs().collect::<Vec<_>>(), [(0, 4), (1, 3), (7, 10)]);
+        assert_eq!(s.partner(2), None);
+        assert!(s.is_pseudoknot_free());
+        assert_eq!(s.to_dot_bracket(), "((.))..(..)");
+
+        assert_eq!(
+            PairTable::from_dot_bracket("((.)"),
+            Err(StructureError::UnmatchedOpening(0))
+        );
+        assert_eq!(
+            PairTable::from_dot_bracket("(.))"),
+            Err(StructureError::UnmatchedClosing(3))
+        );
+        assert_eq!(
+            PairTable::from_dot_bracket("(.[)]").ok(),
+            PairTable::from_pairs(5, vec![(0, 3), (2, 4)])
+        );
+        assert_eq!(
+            PairTable::from_dot_bracket("(.#)"),
+            Err(StructureError::InvalidSymbol(2, b'#'))
+        );
+        assert!(PairTable::from_dot_bracket("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pseudoknots() {
+        // written with a different layer assignment than the canonical one
+        let s = PairTable::from_dot_bracket("[[..((..]]..))").unwrap();
+        assert!(!s.is_pseudoknot_free());
+        assert_eq!(s.to_dot_bracket(), "((..[[..))..]]");
+        // three mutually crossing pairs and letter layers
+        let s = PairTable::from_pairs(6, vec![(0, 3), (1, 4), (2, 5)]).unwrap();
+        assert_eq!(s.to_dot_bracket(), "([{)]}");
+        let s = PairTable::from_dot_bracket("AB.ab").unwrap();
+        assert_eq!(s.to_dot_bracket(), "([.)]");
+        assert_eq!(PairTable::from_pairs(4, vec![(0, 3), (3, 1)]), None);
+        assert_eq!(PairTable::from_pairs(4, vec![(0, 4)]), None);
+    }
+
+    #[test]
+    fn test_validate_and_distance() {
+        let seq = b"GGGAAAUCC";
+        let s = PairTable::from_dot_bracket("(((...)))").unwrap();
+        assert_eq!(s.validate(seq), Ok(()));
+        assert_eq!(
+            s.validate(b"GGG"),
+            Err(StructureError::LengthMismatch(9, 3))
+        );
+        let t = PairTable::from_dot_bracket("((.....))").unwrap();
+        assert_eq!(t.validate(b"GGAAAACCC"), Ok(()));
+        assert_eq!(
+            s.validate(b"GGAAAACCC"),
+            Err(StructureError::InvalidPair(2, 6))
+        );
+
+        assert_eq!(s.base_pair_distance(&s), 0);
+        assert_eq!(s.base_pair_distance(&t), 1);
+        assert_eq!(s.base_pair_distance(&PairTable::new(9)), 3);
+
+        let seq = b"GCGGAUUUAGCUCAGUUGGGAGAGCGCCAGACUGAAGAUCUGGAGGUCCUGUGUUCGAUCCACAGAAUUCGCACCA";
+        let (_, structure) = mfe(seq);
+        let s = PairTable::from_dot_bracket(&structure).unwrap();
+        assert_eq!(s.validate(seq), Ok(()));
+        assert!(s.is_pseudoknot_free());
+        assert_eq!(s.to_dot_bracket(), structure);
+    }
+}
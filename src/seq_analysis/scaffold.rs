@@ -0,0 +1,212 @@
+//! Detection of gaps (runs of `N`) in scaffolds, and splitting of scaffolds into contigs.
+//!
+//! When splitting, the layout of each scaffold is recorded as a list of parts in the manner of
+//! AGP files: each part is either a contig or a gap, with its coordinates in the scaffold. The
+//! original scaffold can be restored from the layout and the contigs with `join`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::scaffold::{find_gaps, join, split, PartKind};
+//!
+//! let scaffold = b"ACGTNNNNNNGGCCnTTAANNNNNNNNNNAC";
+//! assert_eq!(find_gaps(scaffold, 5), [4..10, 19..29]);
+//!
+//! let (contigs, layout) = split("scaf1", scaffold, 5);
+//! let names: Vec<_> = contigs.iter().map(|(name, _)| name.as_str()).collect();
+//! assert_eq!(names, ["scaf1_1", "scaf1_2", "scaf1_3"]);
+//! assert_eq!(contigs[1].1, b"GGCCnTTAA");
+//! assert_eq!(layout[2].kind, PartKind::Contig("scaf1_2".to_owned()));
+//! assert_eq!((layout[2].start, layout[2].end), (10, 19));
+//! assert_eq!(layout[3].kind, PartKind::Gap);
+//!
+//! assert_eq!(join(&layout, &contigs).unwrap(), &scaffold[..]);
+//! ```
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+quick_error! {
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ScaffoldError {
+        MissingContig(name: String) {
+            description("missing contig")
+            display("contig {} of the layout is missing", name)
+        }
+        LengthMismatch(name: String, len: usize, expected: usize) {
+            description("contig length does not match layout")
+            display("contig {} has length {}, but its part has length {}", name, len, expected)
+        }
+    }
+}
+
+/// Ranges of all runs of `N` (in any case) of at least the given length.
+pub fn find_gaps(seq: &[u8], min_len: usize) -> Vec<Range<usize>> {
+    let mut gaps = Vec::new();
+    let mut start = None;
+    for (i, b) in seq.iter().chain(b"$").enumerate() {
+        match (b.eq_ignore_ascii_case(&b'N'), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= min_len.max(1) {
+                    gaps.push(s..i);
+                }
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    gaps
+}
+
+/// Kind of a part of a scaffold.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PartKind {
+    /// A contig with the given name, in forward orientation.
+    Contig(String),
+    Gap,
+}
+
+/// A part `start..end` (0-based, end exclusive) of a scaffold.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Part {
+    pub scaffold: String,
+    pub start: usize,
+    pub end: usize,
+    pub kind: PartKind,
+}
+
+impl Part {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Split a scaffold at all gaps of at least the given length. Contigs are named after the
+/// scaffold with their 1-based number appended (e.g. `scaf1_2`). Returns the named contigs and
+/// the layout of the scaffold, including gaps at its ends.
+pub fn split(name: &str, seq: &[u8], min_gap: usize) -> (Vec<(String, Vec<u8>)>, Vec<Part>) {
+    let mut contigs = Vec::new();
+    let mut layout = Vec::new();
+    let mut start = 0;
+    let mut add_contig = |start: usize, end: usize, layout: &mut Vec<Part>| {
+        if end > start {
+            let contig = format!("{}_{}", name, contigs.len() + 1);
+            layout.push(Part {
+                scaffold: name.to_owned(),
+                start,
+                end,
+                kind: PartKind::Contig(contig.clone()),
+            });
+            contigs.push((contig, seq[start..end].to_vec()));
+        }
+    };
+    for gap in find_gaps(seq, min_gap) {
+        add_contig(start, gap.start, &mut layout);
+        layout.push(Part {
+            scaffold: name.to_owned(),
+            start: gap.start,
+            end: gap.end,
+            kind: PartKind::Gap,
+        });
+        start = gap.end;
+    }
+    add_contig(start, seq.len(), &mut layout);
+    (contigs, layout)
+}
+
+/// Restore the scaffold described by the given layout from the contigs. Gaps are filled with
+/// `N`. Fails if a contig is missing, or does not match the length of its part.
+pub fn join(layout: &[Part], contigs: &[(String, Vec<u8>)]) -> Result<Vec<u8>, ScaffoldError> {
+    let contigs: HashMap<_, _> = contigs
+        .iter()
+        .map(|(name, seq)| (name.as_str(), seq))
+        .collect();
+    let mut seq = Vec::new();
+    for part in layout {
+        match part.kind {
+            PartKind::Gap => seq.resize(seq.len() + part.len(), b'N'),
+            PartKind::Contig(ref name) => {
+                let contig = contigs
+                    .get(name.as_str())
+                    .ok_or_else(|| ScaffoldError::MissingContig(name.clone()))?;
+                if contig.len() != part.len() {
+                    return Err(ScaffoldError::LengthMismatch(
+                        name.clone(),
+                        contig.len(),
+                        part.len(),
+                    ));
+                }
+                seq.extend_from_slice(contig);
+            }
+        }
+    }
+    Ok(seq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_gaps() {
+        assert_eq!(find_gaps(b"NNACnGTNNN", 1), [0..2, 4..5, 7..10]);
+        assert_eq!(find_gaps(b"NNACnGTNNN", 2), [0..2, 7..10]);
+        let gaps = find_gaps(b"NNN", 0);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0], 0..3);
+        assert!(find_gaps(b"", 1).is_empty());
+        assert!(find_gaps(b"ACGT", 1).is_empty());
+    }
+
+    #[test]
+    fn test_split() {
+        let scaffold = b"NNACGTNNNAC";
+        let (contigs, layout) = split("s", scaffold, 2);
+        assert_eq!(
+            contigs,
+            [
+                ("s_1".to_owned(), b"ACGT".to_vec()),
+                ("s_2".to_owned(), b"AC".to_vec())
+            ]
+        );
+        let parts: Vec<_> = layout
+            .iter()
+            .map(|p| (p.start, p.end, p.kind.clone()))
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                (0, 2, PartKind::Gap),
+                (2, 6, PartKind::Contig("s_1".to_owned())),
+                (6, 9, PartKind::Gap),
+                (9, 11, PartKind::Contig("s_2".to_owned())),
+            ]
+        );
+        assert_eq!(join(&layout, &contigs).unwrap(), scaffold);
+
+        let (contigs, layout) = split("s", b"ACGT", 1);
+        assert_eq!(contigs.len(), 1);
+        assert_eq!(layout.len(), 1);
+        let (contigs, layout) = split("s", b"", 1);
+        assert!(contigs.is_empty() && layout.is_empty());
+    }
+
+    #[test]
+    fn test_join_errors() {
+        let (mut contigs, layout) = split("s", b"ACNNGT", 1);
+        assert_eq!(
+            join(&layout, &contigs[..1]),
+            Err(ScaffoldError::MissingContig("s_2".to_owned()))
+        );
+        contigs[1].1.push(b'A');
+        assert_eq!(
+            join(&layout, &contigs),
+            Err(ScaffoldError::LengthMismatch("s_2".to_owned(), 3, 2))
+        );
+    }
+}
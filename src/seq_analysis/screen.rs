@@ -0,0 +1,471 @@
+//! Screening of reads for contamination against a small panel of references, e.g. PhiX, human
+//! or cloning vectors, similar to FastQ Screen or BBDuk.
+//!
+//! A `Screen` stores the canonical k-mers of each target, such that reads are screened on both
+//! strands. A read hits a target if enough of its k-mers occur in the target (by default a
+//! single one, as in BBDuk). Targets sharing sequence can be hit by the same read. `ScreenStats`
+//! collects the per-target hit rates, and `ScreenFilter` removes reads hitting any target from
+//! a record stream (see `io::pipeline`), or keeps only those.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fastq;
+//! use bio::io::pipeline::Pipeline;
+//! use bio::seq_analysis::screen::Screen;
+//!
+//! let mut screen = Screen::new(8);
+//! screen.add_target("phix", &[b"GAGTTTTATCGCTTCCATGACGCAGAAGTTAACACTTTCGGATATTTCTG"]);
+//! screen.add_target("vector", &[b"TTCTCATGTTTGACAGCTTATCATCGATAAGCTTTAATGCGGTAGTTTAT"]);
+//!
+//! let result = screen.screen(b"CGCTTCCATGACGCAGAAG");
+//! assert_eq!(result.targets, [0]);
+//!
+//! let reads = b"@r1\nCATGACGCAGAAGTTAAC\n+\nIIIIIIIIIIIIIIIIII\n@r2\nACACACACACACAC\n+\nIIIIIIIIIIIIII\n";
+//! let mut filter = screen.filter();
+//! let clean: Vec<_> = fastq::Reader::new(&reads[..])
+//!     .records()
+//!     .map(|record| record.unwrap())
+//!     .transform(&mut filter)
+//!     .collect();
+//! assert_eq!(clean.len(), 1);
+//! assert_eq!(clean[0].id(), "r2");
+//!
+//! let stats = filter.stats();
+//! assert_eq!(stats.reads, 2);
+//! assert_eq!(stats.hit_rate(0), 0.5);
+//! assert_eq!(stats.targets[1].reads, 0);
+//! ```
+
+use std::io;
+
+use alphabets::dna::canonical_kmers;
+use io::pipeline::RecordTransform;
+use io::{fasta, fastq};
+use utils::KmerHashMap;
+
+/// Maximum number of targets of a screen.
+pub const MAX_TARGETS: usize = 64;
+
+/// A panel of target references to screen reads against.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Screen {
+    k: usize,
+    min_hits: usize,
+    min_fraction: f64,
+    targets: Vec<String>,
+    /// Bit set of the targets containing each k-mer.
+    kmers: KmerHashMap<u64, u64>,
+}
+
+impl Screen {
+    /// Create an empty screen for k-mers of length `k` (at most 32).
+    pub fn new(k: usize) -> Self {
+        assert!((1..=32).contains(&k), "Expecting k between 1 and 32.");
+        Screen {
+            k,
+            min_hits: 1,
+            min_fraction: 0.0,
+            targets: Vec::new(),
+            kmers: KmerHashMap::default(),
+        }
+    }
+
+    /// Minimum number of k-mers of a read occurring in a target for a hit (default: 1).
+    pub fn min_hits(mut self, min_hits: usize) -> Self {
+        assert!(min_hits > 0, "Expecting positive minimum number of hits.");
+        self.min_hits = min_hits;
+        self
+    }
+
+    /// Minimum fraction of the k-mers of a read occurring in a target for a hit (default: 0).
+    pub fn min_fraction(mut self, min_fraction: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&min_fraction),
+            "Expecting fraction between 0 and 1."
+        );
+        self.min_fraction = min_fraction;
+        self
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The names of the targets, in the order they were added.
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Number of distinct canonical k-mers of all targets.
+    pub fn len(&self) -> usize {
+        self.kmers.len()
+    }
+
+    /// Check whether the screen contains no k-mers.
+    pub fn is_empty(&self) -> bool {
+        self.kmers.is_empty()
+    }
+
+    /// Add a target with the given sequences (e.g. the chromosomes of a genome), returning its
+    /// index.
+    pub fn add_target<I, T>(&mut self, name: &str, seqs: I) -> usize
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let target = self.push_target(name);
+        for seq in seqs {
+            self.add_kmers(target, seq.as_ref());
+        }
+        target
+    }
+
+    /// Add a target with the sequences of the given FASTA reader, returning its index.
+    pub fn add_fasta_target<R: io::Read>(
+        &mut self,
+        name: &str,
+        reader: fasta::Reader<R>,
+    ) -> io::Result<usize> {
+        let target = self.push_target(name);
+        for record in reader.records() {
+            self.add_kmers(target, record?.seq());
+        }
+        Ok(target)
+    }
+
+    fn push_target(&mut self, name: &str) -> usize {
+        assert!(
+            self.targets.len() < MAX_TARGETS,
+            "Expecting at most 64 targets."
+        );
+        self.targets.push(name.to_owned());
+        self.targets.len() - 1
+    }
+
+    fn add_kmers(&mut self, target: usize, seq: &[u8]) {
+        for kmer in canonical_kmers(seq, self.k) {
+            *self.kmers.entry(kmer).or_insert(0) |= 1 << target;
+        }
+    }
+
+    /// Screen a read against all targets.
+    pub fn screen(&self, seq: &[u8]) -> ScreenResult {
+        let mut kmers = 0;
+        let mut hits = vec![0; self.targets.len()];
+        for kmer in canonical_kmers(seq, self.k) {
+            kmers += 1;
+            if let Some(&set) = self.kmers.get(&kmer) {
+                for (target, count) in hits.iter_mut().enumerate() {
+                    if set & (1 << target) != 0 {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        let targets = hits
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| {
+                count >= self.min_hits && count as f64 >= self.min_fraction * kmers as f64
+            })
+            .map(|(target, _)| target)
+            .collect();
+        ScreenResult {
+            kmers,
+            hits,
+            targets,
+        }
+    }
+
+    /// Empty statistics for the targets of this screen.
+    pub fn stats(&self) -> ScreenStats {
+        ScreenStats {
+            reads: 0,
+            hit_reads: 0,
+            targets: self
+                .targets
+                .iter()
+                .map(|name| TargetStats {
+                    name: name.clone(),
+                    reads: 0,
+                    unique_reads: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// A record transform that drops reads hitting any target.
+    pub fn filter(&self) -> ScreenFilter<'_> {
+        ScreenFilter {
+            screen: self,
+            stats: self.stats(),
+            keep_hits: false,
+        }
+    }
+}
+
+/// Result of screening a read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreenResult {
+    /// Number of k-mers of the read consisting only of `ACGT`.
+    pub kmers: usize,
+    /// Number of k-mers of the read occurring in each target.
+    pub hits: Vec<usize>,
+    /// Indices of the targets hit by the read.
+    pub targets: Vec<usize>,
+}
+
+impl ScreenResult {
+    /// Check whether the read hits any target.
+    pub fn is_hit(&self) -> bool {
+        !self.targets.is_empty()
+    }
+}
+
+/// Number of reads hitting a target.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetStats {
+    /// Name of the target.
+    pub name: String,
+    /// Number of reads hitting the target.
+    pub reads: u64,
+    /// Number of reads hitting only this target.
+    pub unique_reads: u64,
+}
+
+/// Statistics of screened reads.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScreenStats {
+    /// Number of screened reads.
+    pub reads: u64,
+    /// Number of reads hitting any target.
+    pub hit_reads: u64,
+    /// Statistics per target.
+    pub targets: Vec<TargetStats>,
+}
+
+impl ScreenStats {
+    /// Count a screened read.
+    pub fn add(&mut self, result: &ScreenResult) {
+        self.reads += 1;
+        if result.is_hit() {
+            self.hit_reads += 1;
+        }
+        for &target in &result.targets {
+            self.targets[target].reads += 1;
+            if result.targets.len() == 1 {
+                self.targets[target].unique_reads += 1;
+            }
+        }
+    }
+
+    /// Add the counts of other statistics of the same screen, e.g. of another lane.
+    pub fn merge(&mut self, other: &ScreenStats) {
+        assert_eq!(
+            self.targets.len(),
+            other.targets.len(),
+            "Expecting statistics of the same screen."
+        );
+        self.reads += other.reads;
+        self.hit_reads += other.hit_reads;
+        for (stats, other) in self.targets.iter_mut().zip(&other.targets) {
+            stats.reads += other.reads;
+            stats.unique_reads += other.unique_reads;
+        }
+    }
+
+    /// Fraction of reads hitting the given target (0 if there are no reads).
+    pub fn hit_rate(&self, target: usize) -> f64 {
+        fraction(self.targets[target].reads, self.reads)
+    }
+
+    /// Fraction of reads hitting any target (0 if there are no reads).
+    pub fn contamination_rate(&self) -> f64 {
+        fraction(self.hit_reads, self.reads)
+    }
+}
+
+fn fraction(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// A record transform screening reads, which drops the reads hitting any target (or, with
+/// `keep_hits`, all other reads) and collects statistics of all screened reads.
+#[derive(Clone, Debug)]
+pub struct ScreenFilter<'a> {
+    screen: &'a Screen,
+    stats: ScreenStats,
+    keep_hits: bool,
+}
+
+impl<'a> ScreenFilter<'a> {
+    /// Keep the reads hitting a target instead of dropping them, e.g. to extract the
+    /// contaminants.
+    pub fn keep_hits(mut self) -> Self {
+        self.keep_hits = true;
+        self
+    }
+
+    /// Statistics of all reads screened so far.
+    pub fn stats(&self) -> &ScreenStats {
+        &self.stats
+    }
+
+    /// Return the statistics of all screened reads.
+    pub fn into_stats(self) -> ScreenStats {
+        self.stats
+    }
+
+    fn keep(&mut self, seq: &[u8]) -> bool {
+        let result = self.screen.screen(seq);
+        self.stats.add(&result);
+        result.is_hit() == self.keep_hits
+    }
+}
+
+impl<'a> RecordTransform<fasta::Record> for ScreenFilter<'a> {
+    type Output = fasta::Record;
+
+    fn apply(&mut self, record: fasta::Record) -> Option<fasta::Record> {
+        if self.keep(record.seq()) {
+            Some(record)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> RecordTransform<fastq::Record> for ScreenFilter<'a> {
+    type Output = fastq::Record;
+
+    fn apply(&mut self, record: fastq::Record) -> Option<fastq::Record> {
+        if self.keep(record.seq()) {
+            Some(record)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> RecordTransform<T> for &mut ScreenFilter<'a>
+where
+    ScreenFilter<'a>: RecordTransform<T>,
+{
+    type Output = <ScreenFilter<'a> as RecordTransform<T>>::Output;
+
+    fn apply(&mut self, record: T) -> Option<Self::Output> {
+        (**self).apply(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alphabets::dna;
+    use io::pipeline::Pipeline;
+    use utils::{SeedableRngExt, Xoshiro256};
+
+    fn random_seq(len: usize, rng: &mut Xoshiro256) -> Vec<u8> {
+        (0..len).map(|_| b"ACGT"[rng.below(4) as usize]).collect()
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut rng = Xoshiro256::seed_from_u64(42);
+        let chr1 = random_seq(500, &mut rng);
+        let chr2 = random_seq(500, &mut rng);
+        let vector = random_seq(300, &mut rng);
+        // the vector contains part of chr2
+        let mut construct = vector.clone();
+        construct.extend_from_slice(&chr2[100..200]);
+
+        let mut screen = Screen::new(21);
+        assert_eq!(screen.add_target("genome", [&chr1, &chr2]), 0);
+        assert_eq!(screen.add_target("vector", [&construct]), 1);
+        assert_eq!(screen.targets(), ["genome", "vector"]);
+
+        let result = screen.screen(&chr1[50..150]);
+        assert_eq!(result.kmers, 80);
+        assert_eq!(result.hits, [80, 0]);
+        assert_eq!(result.targets, [0]);
+        // reverse strand
+        assert_eq!(screen.screen(&dna::revcomp(&vector[..100])).targets, [1]);
+        assert_eq!(screen.screen(&chr2[120..180]).targets, [0, 1]);
+        let result = screen.screen(&random_seq(100, &mut rng));
+        assert!(!result.is_hit());
+
+        // a read with a few k-mers from chr1
+        let mut read = random_seq(80, &mut rng);
+        read.extend_from_slice(&chr1[..25]);
+        assert_eq!(screen.screen(&read).targets, [0]);
+        let strict = screen.clone().min_fraction(0.5);
+        assert!(!strict.screen(&read).is_hit());
+        let strict = screen.min_hits(10);
+        assert!(!strict.screen(&read).is_hit());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut rng = Xoshiro256::seed_from_u64(7);
+        let phix = random_seq(400, &mut rng);
+        let human = random_seq(400, &mut rng);
+        let mut screen = Screen::new(15);
+        screen.add_target("phix", [&phix]);
+        screen.add_target("human", [&human]);
+        screen.add_target("shared", [&phix[..100]]);
+
+        let mut stats = screen.stats();
+        for read in &[
+            &phix[..50],
+            &phix[200..250],
+            &human[..50],
+            &random_seq(50, &mut rng),
+        ] {
+            stats.add(&screen.screen(read));
+        }
+        assert_eq!(stats.reads, 4);
+        assert_eq!(stats.hit_reads, 3);
+        assert_eq!(stats.targets[0].reads, 2);
+        assert_eq!(stats.targets[0].unique_reads, 1);
+        assert_eq!(stats.targets[1].unique_reads, 1);
+        assert_eq!(stats.targets[2].reads, 1);
+        assert_eq!(stats.hit_rate(0), 0.5);
+        assert_eq!(stats.contamination_rate(), 0.75);
+
+        let mut total = stats.clone();
+        total.merge(&stats);
+        assert_eq!(total.reads, 8);
+        assert_eq!(total.targets[0].unique_reads, 2);
+    }
+
+    #[test]
+    fn test_filter() {
+        let fasta = b">chr1\nACGTTGCATGCAGGCTAGCTAGGATC\n>chr2\nTTTTGGGGCCCCAAAATTGGCCAA\n";
+        let mut screen = Screen::new(10);
+        screen
+            .add_fasta_target("genome", fasta::Reader::new(&fasta[..]))
+            .unwrap();
+        let reads = vec![
+            fasta::Record::with_attrs("a", None, b"GCATGCAGGCTAG"),
+            fasta::Record::with_attrs("b", None, b"CACACACACACACA"),
+            fasta::Record::with_attrs("c", None, b"GGCCCCAAAATT"),
+        ];
+
+        let mut filter = screen.filter();
+        let clean: Vec<_> = reads.clone().into_iter().transform(&mut filter).collect();
+        assert_eq!(clean.len(), 1);
+        assert_eq!(clean[0].id(), "b");
+        assert_eq!(filter.stats().hit_reads, 2);
+
+        let mut filter = screen.filter().keep_hits();
+        let hits: Vec<_> = reads.into_iter().transform(&mut filter).collect();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(filter.into_stats().reads, 3);
+    }
+}
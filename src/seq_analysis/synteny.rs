@@ -0,0 +1,336 @@
+//! Detection of syntenic blocks, i.e. regions of two genomes in which anchors (e.g. pairs of
+//! orthologous genes, or alignments from `alignment::wga`) occur in conserved order.
+//!
+//! Anchors are grouped by the pair of sequences and their relative orientation. Within each
+//! group, colinear chains are found by dynamic programming: on the forward strand, the query
+//! positions of the anchors of a block increase with their reference positions, on the reverse
+//! strand they decrease (i.e. the block is inverted). Adjacent anchors of a block may be at most a
+//! given distance apart in both genomes. Blocks are extracted greedily, the highest scoring first,
+//! as long as they contain enough anchors.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate bio;
+//! # extern crate bio_types;
+//! use bio::seq_analysis::synteny::{SyntenyAnchor, SyntenyDetector};
+//! use bio_types::strand::ReqStrand;
+//!
+//! // gene pairs of an inverted region
+//! let anchors: Vec<_> = (0..4)
+//!     .map(|i| SyntenyAnchor {
+//!         ref_id: "chr1".to_owned(),
+//!         ref_start: 1000 * i,
+//!         ref_end: 1000 * i + 500,
+//!         query_id: "chrA".to_owned(),
+//!         query_start: 9000 - 1000 * i,
+//!         query_end: 9500 - 1000 * i,
+//!         strand: ReqStrand::Reverse,
+//!         score: 500.0,
+//!     })
+//!     .collect();
+//!
+//! let blocks = SyntenyDetector::new().max_distance(1000).min_anchors(3).blocks(&anchors);
+//! assert_eq!(blocks.len(), 1);
+//! assert_eq!((blocks[0].ref_start, blocks[0].ref_end), (0, 3500));
+//! assert_eq!((blocks[0].query_start, blocks[0].query_end), (6000, 9500));
+//! assert_eq!(blocks[0].strand, ReqStrand::Reverse);
+//! assert_eq!(blocks[0].anchors.len(), 4);
+//! ```
+
+use std::collections::BTreeMap;
+
+use bio_types::strand::ReqStrand;
+
+use alignment::wga::{Anchor, DeltaAlignment};
+
+/// An anchor between a reference and a query region (0-based, end exclusive, forward strand
+/// coordinates). The strand gives the relative orientation of both regions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntenyAnchor {
+    pub ref_id: String,
+    pub ref_start: u64,
+    pub ref_end: u64,
+    pub query_id: String,
+    pub query_start: u64,
+    pub query_end: u64,
+    pub strand: ReqStrand,
+    /// Weight of the anchor in blocks, e.g. its length or alignment score.
+    pub score: f64,
+}
+
+impl SyntenyAnchor {
+    /// Anchor of a maximal unique match of the given sequences, scored by its length. `query_len`
+    /// is needed to convert reverse strand matches to forward strand coordinates.
+    pub fn from_mum(ref_id: &str, query_id: &str, query_len: usize, mum: &Anchor) -> Self {
+        let query_start = match mum.strand {
+            ReqStrand::Forward => mum.query_pos,
+            ReqStrand::Reverse => query_len - mum.query_pos - mum.len,
+        };
+        SyntenyAnchor {
+            ref_id: ref_id.to_owned(),
+            ref_start: mum.ref_pos as u64,
+            ref_end: (mum.ref_pos + mum.len) as u64,
+            query_id: query_id.to_owned(),
+            query_start: query_start as u64,
+            query_end: (query_start + mum.len) as u64,
+            strand: mum.strand,
+            score: mum.len as f64,
+        }
+    }
+
+    /// Anchor of an alignment of the given sequences, scored by its number of error-free
+    /// columns.
+    pub fn from_alignment(ref_id: &str, query_id: &str, alignment: &DeltaAlignment) -> Self {
+        let (query_start, query_end) = if alignment.query_start <= alignment.query_end {
+            (alignment.query_start, alignment.query_end)
+        } else {
+            (alignment.query_end, alignment.query_start)
+        };
+        let ref_len = alignment.ref_end + 1 - alignment.ref_start;
+        let query_len = query_end + 1 - query_start;
+        SyntenyAnchor {
+            ref_id: ref_id.to_owned(),
+            ref_start: alignment.ref_start as u64 - 1,
+            ref_end: alignment.ref_end as u64,
+            query_id: query_id.to_owned(),
+            query_start: query_start as u64 - 1,
+            query_end: query_end as u64,
+            strand: alignment.strand,
+            score: (ref_len.max(query_len) - alignment.errors.min(ref_len.max(query_len))) as f64,
+        }
+    }
+}
+
+/// A syntenic block, spanning the anchors it consists of.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntenyBlock {
+    pub ref_id: String,
+    pub ref_start: u64,
+    pub ref_end: u64,
+    pub query_id: String,
+    pub query_start: u64,
+    pub query_end: u64,
+    /// `Reverse` for inverted blocks.
+    pub strand: ReqStrand,
+    /// Total score of the anchors.
+    pub score: f64,
+    /// The anchors, ordered by reference position.
+    pub anchors: Vec<SyntenyAnchor>,
+}
+
+/// Detector of syntenic blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntenyDetector {
+    max_distance: u64,
+    min_anchors: usize,
+}
+
+impl Default for SyntenyDetector {
+    fn default() -> Self {
+        SyntenyDetector {
+            max_distance: 100_000,
+            min_anchors: 5,
+        }
+    }
+}
+
+impl SyntenyDetector {
+    /// Create a new detector, for blocks of at least 5 anchors with at most 100 kb between
+    /// adjacent anchors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximal distance between adjacent anchors of a block, in both genomes.
+    pub fn max_distance(mut self, max_distance: u64) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Set the minimal number of anchors of a block.
+    pub fn min_anchors(mut self, min_anchors: usize) -> Self {
+        assert!(
+            min_anchors > 0,
+            "Expecting positive minimal number of anchors."
+        );
+        self.min_anchors = min_anchors;
+        self
+    }
+
+    /// Syntenic blocks of the given anchors, ordered by reference sequence and position. Each
+    /// anchor is part of at most one block.
+    pub fn blocks(&self, anchors: &[SyntenyAnchor]) -> Vec<SyntenyBlock> {
+        let mut groups: BTreeMap<_, Vec<&SyntenyAnchor>> = BTreeMap::new();
+        for anchor in anchors {
+            groups
+                .entry((&anchor.ref_id, &anchor.query_id, anchor.strand))
+                .or_default()
+                .push(anchor);
+        }
+
+        let mut blocks = Vec::new();
+        for (_, mut group) in groups {
+            group.sort_by_key(|a| (a.ref_start, a.query_start, a.ref_end, a.query_end));
+            while let Some(chain) = self.best_chain(&group) {
+                let anchors: Vec<_> = chain.iter().map(|&i| group[i].clone()).collect();
+                blocks.push(Self::block(anchors));
+                // remove the chained anchors, in decreasing order of index
+                for &i in chain.iter().rev() {
+                    group.remove(i);
+                }
+            }
+        }
+        blocks.sort_by(|a, b| {
+            (&a.ref_id, a.ref_start, &a.query_id, a.query_start).cmp(&(
+                &b.ref_id,
+                b.ref_start,
+                &b.query_id,
+                b.query_start,
+            ))
+        });
+        blocks
+    }
+
+    /// Whether `next` can follow `prev` in a block.
+    fn is_successor(&self, prev: &SyntenyAnchor, next: &SyntenyAnchor) -> bool {
+        if next.ref_start < prev.ref_end {
+            return false;
+        }
+        let query_distance = match next.strand {
+            ReqStrand::Forward if next.query_start >= prev.query_end => {
+                next.query_start - prev.query_end
+            }
+            ReqStrand::Reverse if prev.query_start >= next.query_end => {
+                prev.query_start - next.query_end
+            }
+            _ => return false,
+        };
+        next.ref_start - prev.ref_end <= self.max_distance && query_distance <= self.max_distance
+    }
+
+    /// Indices of the anchors of the highest scoring chain, if it has enough anchors.
+    fn best_chain(&self, anchors: &[&SyntenyAnchor]) -> Option<Vec<usize>> {
+        let mut scores: Vec<f64> = Vec::with_capacity(anchors.len());
+        let mut preds: Vec<Option<usize>> = Vec::with_capacity(anchors.len());
+        for (i, anchor) in anchors.iter().enumerate() {
+            let mut best = (anchor.score, None);
+            for (j, prev) in anchors[..i].iter().enumerate() {
+                if scores[j] + anchor.score > best.0 && self.is_successor(prev, anchor) {
+                    best = (scores[j] + anchor.score, Some(j));
+                }
+            }
+            scores.push(best.0);
+            preds.push(best.1);
+        }
+
+        // the first of the highest scoring chain ends
+        let mut i = (0..scores.len())
+            .max_by(|&a, &b| scores[a].partial_cmp(&scores[b]).unwrap().then(b.cmp(&a)))?;
+        let mut chain = vec![i];
+        while let Some(j) = preds[i] {
+            chain.push(j);
+            i = j;
+        }
+        chain.reverse();
+        if chain.len() >= self.min_anchors {
+            Some(chain)
+        } else {
+            None
+        }
+    }
+
+    fn block(anchors: Vec<SyntenyAnchor>) -> SyntenyBlock {
+        let first = &anchors[0];
+        SyntenyBlock {
+            ref_id: first.ref_id.clone(),
+            ref_start: anchors.iter().map(|a| a.ref_start).min().unwrap(),
+            ref_end: anchors.iter().map(|a| a.ref_end).max().unwrap(),
+            query_id: first.query_id.clone(),
+            query_start: anchors.iter().map(|a| a.query_start).min().unwrap(),
+            query_end: anchors.iter().map(|a| a.query_end).max().unwrap(),
+            strand: first.strand,
+            score: anchors.iter().map(|a| a.score).sum(),
+            anchors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alignment::wga::GenomeAligner;
+    use alphabets::dna;
+
+    fn anchor(ref_start: u64, query_start: u64, strand: ReqStrand) -> SyntenyAnchor {
+        SyntenyAnchor {
+            ref_id: "r".to_owned(),
+            ref_start,
+            ref_end: ref_start + 10,
+            query_id: "q".to_owned(),
+            query_start,
+            query_end: query_start + 10,
+            strand,
+            score: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_blocks() {
+        let forward = ReqStrand::Forward;
+        let anchors = vec![
+            anchor(0, 100, forward),
+            anchor(20, 120, forward),
+            // out of order
+            anchor(30, 50, forward),
+            anchor(40, 140, forward),
+            // too distant
+            anchor(500, 160, forward),
+            // second block, on another query sequence
+            SyntenyAnchor {
+                query_id: "p".to_owned(),
+                ..anchor(600, 0, forward)
+            },
+            SyntenyAnchor {
+                query_id: "p".to_owned(),
+                ..anchor(620, 20, forward)
+            },
+            // wrong orientation
+            anchor(60, 160, ReqStrand::Reverse),
+        ];
+        let detector = SyntenyDetector::new().max_distance(50).min_anchors(2);
+        let blocks = detector.blocks(&anchors);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!((blocks[0].ref_start, blocks[0].ref_end), (0, 50));
+        assert_eq!((blocks[0].query_start, blocks[0].query_end), (100, 150));
+        assert_eq!(blocks[0].anchors.len(), 3);
+        assert_relative_eq!(blocks[0].score, 30.0);
+        assert_eq!(blocks[1].query_id, "p");
+
+        assert_eq!(detector.min_anchors(4).blocks(&anchors).len(), 0);
+        assert_eq!(detector.max_distance(1000).blocks(&anchors).len(), 2);
+        assert_eq!(
+            detector.max_distance(1000).blocks(&anchors)[0]
+                .anchors
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_from_wga() {
+        let reference = b"ATGAATCTCTGATTTACCCACTCTGCCAAACTCCAGCGCGGTCAGTTCCATCACCCTAAGTAACCGAATA";
+        let query = dna::revcomp(&reference[10..50]);
+        let aligner = GenomeAligner::new().min_match(10).min_cluster(20);
+        let alignments = aligner.align(reference, &query);
+        let anchor = SyntenyAnchor::from_alignment("r", "q", &alignments[0]);
+        assert_eq!((anchor.ref_start, anchor.ref_end), (10, 50));
+        assert_eq!((anchor.query_start, anchor.query_end), (0, 40));
+        assert_eq!(anchor.strand, ReqStrand::Reverse);
+        assert_relative_eq!(anchor.score, 40.0);
+
+        let mums = aligner.anchors(reference, &query);
+        let anchor = SyntenyAnchor::from_mum("r", "q", query.len(), &mums[0]);
+        assert_eq!((anchor.query_start, anchor.query_end), (0, 40));
+    }
+}
@@ -0,0 +1,338 @@
+//! Melting temperature (Tm) of DNA duplexes by the nearest neighbor model, using the unified
+//! parameters of SantaLucia (1998) for perfectly matching duplexes.
+//!
+//! The duplex stability at 1 M NaCl is corrected for the monovalent cation concentration by the
+//! entropy correction of SantaLucia (1998), where divalent cations (Mg2+) are converted to
+//! monovalent equivalents after subtracting the dNTPs that chelate them (von Ahsen et al., 2001).
+//! The melting temperature is computed for two strands of equal concentration, or for a self
+//! complementary oligo.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::tm::{tm, tm_gc_track, Conditions};
+//!
+//! let primer = b"AGCGGATAACAATTTCACACAGGA";
+//! let standard = tm(primer, &Conditions::default()).unwrap();
+//! assert!(standard > 50.0 && standard < 60.0);
+//!
+//! // PCR buffer with magnesium and dNTPs stabilizes the duplex
+//! let pcr = Conditions {
+//!     mg: 1.5,
+//!     dntp: 0.2,
+//!     ..Default::default()
+//! };
+//! assert!(tm(primer, &pcr).unwrap() > standard);
+//!
+//! // a melting profile along a longer sequence
+//! let seq = b"ATATATATATATATATATATGCGCGCGCGCGCGCGCGCGC";
+//! let track = tm_gc_track(seq, 20, 10, &Conditions::default());
+//! assert_eq!(track.len(), 3);
+//! assert!(track[0].gc < track[2].gc);
+//! assert!(track[0].tm.unwrap() < track[2].tm.unwrap());
+//! ```
+
+/// Gas constant in cal/(K mol).
+pub const R: f64 = 1.987;
+/// Zero degrees Celsius in Kelvin.
+const KELVIN: f64 = 273.15;
+
+/// Enthalpy (kcal/mol) and entropy (cal/(K mol)) of the nearest neighbor stacks, indexed by the
+/// dinucleotide of the upper strand encoded with A=0, C=1, G=2, T=3 as `4 * first + second`.
+#[rustfmt::skip]
+const STACKS: [(f64, f64); 16] = [
+    (-7.9, -22.2), (-8.4, -22.4), (-7.8, -21.0), (-7.2, -20.4), // AA AC AG AT
+    (-8.5, -22.7), (-8.0, -19.9), (-10.6, -27.2), (-7.8, -21.0), // CA CC CG CT
+    (-8.2, -22.2), (-9.8, -24.4), (-8.0, -19.9), (-8.4, -22.4), // GA GC GG GT
+    (-7.2, -21.3), (-8.2, -22.2), (-8.5, -22.7), (-7.9, -22.2), // TA TC TG TT
+];
+/// Initiation with a terminal GC pair (per duplex end).
+const INIT_GC: (f64, f64) = (0.1, -2.8);
+/// Initiation with a terminal AT pair (per duplex end).
+const INIT_AT: (f64, f64) = (2.3, 4.1);
+/// Symmetry correction for self complementary duplexes.
+const SYMMETRY: (f64, f64) = (0.0, -1.4);
+
+/// Reaction conditions for melting temperature calculations.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Conditions {
+    /// Concentration of each strand (nM).
+    pub oligo_conc: f64,
+    /// Concentration of monovalent cations (mM).
+    pub na: f64,
+    /// Concentration of divalent cations (mM).
+    pub mg: f64,
+    /// Concentration of dNTPs (mM).
+    pub dntp: f64,
+}
+
+impl Default for Conditions {
+    /// 50 nM of each strand in 50 mM NaCl.
+    fn default() -> Self {
+        Conditions {
+            oligo_conc: 50.0,
+            na: 50.0,
+            mg: 0.0,
+            dntp: 0.0,
+        }
+    }
+}
+
+impl Conditions {
+    /// Monovalent cation equivalent concentration (mM): `na + 120 * sqrt(mg - dntp)`.
+    pub fn na_equivalent(&self) -> f64 {
+        self.na + 120.0 * (self.mg - self.dntp).max(0.0).sqrt()
+    }
+}
+
+fn base_rank(base: u8) -> Option<usize> {
+    match base {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' | b'U' | b'u' => Some(3),
+        _ => None,
+    }
+}
+
+fn is_self_complementary(seq: &[u8]) -> bool {
+    let complementary = |a: u8, b: u8| match (base_rank(a), base_rank(b)) {
+        (Some(a), Some(b)) => a + b == 3,
+        _ => false,
+    };
+    seq.iter()
+        .zip(seq.iter().rev())
+        .take(seq.len() / 2 + 1)
+        .all(|(&a, &b)| complementary(a, b))
+}
+
+/// Enthalpy (kcal/mol) and entropy (cal/(K mol)) of the duplex of the given sequence and its
+/// reverse complement at 1 M NaCl. Returns `None` if the sequence is shorter than 2 bases or
+/// contains other symbols than `ACGTU` (case insensitive).
+pub fn thermodynamics(seq: &[u8]) -> Option<(f64, f64)> {
+    if seq.len() < 2 {
+        return None;
+    }
+    let ranks = seq
+        .iter()
+        .map(|&b| base_rank(b))
+        .collect::<Option<Vec<_>>>()?;
+    let (mut dh, mut ds) = (0.0, 0.0);
+    for w in ranks.windows(2) {
+        let (h, s) = STACKS[4 * w[0] + w[1]];
+        dh += h;
+        ds += s;
+    }
+    for &end in &[ranks[0], ranks[ranks.len() - 1]] {
+        // C and G have ranks 1 and 2
+        let (h, s) = if end == 1 || end == 2 {
+            INIT_GC
+        } else {
+            INIT_AT
+        };
+        dh += h;
+        ds += s;
+    }
+    if is_self_complementary(seq) {
+        dh += SYMMETRY.0;
+        ds += SYMMETRY.1;
+    }
+    Some((dh, ds))
+}
+
+/// Entropy (cal/(K mol)) of the duplex corrected for the cation concentrations.
+fn corrected_entropy(seq: &[u8], ds: f64, conditions: &Conditions) -> f64 {
+    let na = conditions.na_equivalent() / 1000.0;
+    assert!(na > 0.0, "Expecting positive cation concentration.");
+    ds + 0.368 * (seq.len() - 1) as f64 * na.ln()
+}
+
+/// Gibbs free energy (kcal/mol) of duplex formation at the given temperature (°C), corrected for
+/// the cation concentrations.
+pub fn delta_g(seq: &[u8], temperature: f64, conditions: &Conditions) -> Option<f64> {
+    let (dh, ds) = thermodynamics(seq)?;
+    let ds = corrected_entropy(seq, ds, conditions);
+    Some(dh - (temperature + KELVIN) * ds / 1000.0)
+}
+
+/// Melting temperature (°C) of the duplex of the given sequence and its reverse complement,
+/// i.e. the temperature at which half of the strands are in duplex state.
+/// Returns `None` if the sequence is shorter than 2 bases or ambiguous.
+pub fn tm(seq: &[u8], conditions: &Conditions) -> Option<f64> {
+    let (dh, ds) = thermodynamics(seq)?;
+    Some(melting_temperature(
+        dh,
+        corrected_entropy(seq, ds, conditions),
+        is_self_complementary(seq),
+        conditions,
+    ))
+}
+
+fn melting_temperature(dh: f64, ds: f64, self_complementary: bool, conditions: &Conditions) -> f64 {
+    let conc = conditions.oligo_conc * 1e-9;
+    assert!(conc > 0.0, "Expecting positive oligo concentration.");
+    // two different strands of equal concentration need a factor of 4 (2 for the total strand
+    // concentration, 1/2 for half of the strands in duplex)
+    let conc = if self_complementary { conc } else { conc / 4.0 };
+    1000.0 * dh / (ds + R * conc.ln()) - KELVIN
+}
+
+/// GC content and melting temperature of a sequence window.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Window {
+    /// Start position of the window.
+    pub start: usize,
+    /// Fraction of `GC` among all bases of the window.
+    pub gc: f64,
+    /// Melting temperature (°C), `None` if the window contains ambiguous bases.
+    pub tm: Option<f64>,
+}
+
+/// Compute GC content and melting temperature over sliding windows of the given size, shifted
+/// by `step`, e.g. to find regions of low duplex stability along a large sequence. Windows are
+/// evaluated from prefix sums of the nearest neighbor parameters, such that the run time is
+/// independent of the window size for all but (partially) self complementary windows.
+/// Trailing bases that do not fill a window are not reported.
+pub fn tm_gc_track(seq: &[u8], window: usize, step: usize, conditions: &Conditions) -> Vec<Window> {
+    assert!(window >= 2, "Expecting window size of at least 2.");
+    assert!(step > 0, "Expecting positive step.");
+    let ranks: Vec<Option<usize>> = seq.iter().map(|&b| base_rank(b)).collect();
+    // prefix sums of GC bases, ambiguous bases and stack parameters
+    let (mut gc, mut ambiguous) = (vec![0usize], vec![0usize]);
+    for rank in &ranks {
+        let is_gc = rank.is_some_and(|r| r == 1 || r == 2);
+        gc.push(gc.last().unwrap() + is_gc as usize);
+        ambiguous.push(ambiguous.last().unwrap() + rank.is_none() as usize);
+    }
+    let (mut dh, mut ds) = (vec![0.0], vec![0.0]);
+    for w in ranks.windows(2) {
+        let (h, s) = match (w[0], w[1]) {
+            (Some(a), Some(b)) => STACKS[4 * a + b],
+            _ => (0.0, 0.0),
+        };
+        dh.push(dh.last().unwrap() + h);
+        ds.push(ds.last().unwrap() + s);
+    }
+
+    let mut track = Vec::new();
+    let mut start = 0;
+    while start + window <= seq.len() {
+        let end = start + window;
+        let tm = if ambiguous[end] - ambiguous[start] > 0 {
+            None
+        } else {
+            let (mut h, mut s) = (dh[end - 1] - dh[start], ds[end - 1] - ds[start]);
+            for &terminal in &[ranks[start], ranks[end - 1]] {
+                let (ih, is) = if terminal == Some(1) || terminal == Some(2) {
+                    INIT_GC
+                } else {
+                    INIT_AT
+                };
+                h += ih;
+                s += is;
+            }
+            let self_complementary = is_self_complementary(&seq[start..end]);
+            if self_complementary {
+                h += SYMMETRY.0;
+                s += SYMMETRY.1;
+            }
+            let s = corrected_entropy(&seq[start..end], s, conditions);
+            Some(melting_temperature(h, s, self_complementary, conditions))
+        };
+        track.push(Window {
+            start,
+            gc: (gc[end] - gc[start]) as f64 / window as f64,
+            tm,
+        });
+        start += step;
+    }
+    track
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use seq_analysis::gc::gc_content;
+
+    #[test]
+    fn test_thermodynamics() {
+        // stacks CG, GT, TT, TG, GA with terminal CG and AT pairs
+        let (dh, ds) = thermodynamics(b"CGTTGA").unwrap();
+        assert_relative_eq!(
+            dh,
+            -10.6 - 8.4 - 7.9 - 8.5 - 8.2 + 0.1 + 2.3,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            ds,
+            -27.2 - 22.4 - 22.2 - 22.7 - 22.2 - 2.8 + 4.1,
+            epsilon = 1e-9
+        );
+        // symmetry correction and strand independence
+        let (_, ds) = thermodynamics(b"CGCGAATTCGCG").unwrap();
+        let (_, ds_lower) = thermodynamics(b"cgcgaattcgcg").unwrap();
+        assert_relative_eq!(ds, ds_lower);
+        let (dh, ds) = thermodynamics(b"ACGTTGCA").unwrap();
+        let (dh_rc, ds_rc) = thermodynamics(b"TGCAACGT").unwrap();
+        assert_relative_eq!(dh, dh_rc, epsilon = 1e-9);
+        assert_relative_eq!(ds, ds_rc, epsilon = 1e-9);
+        assert_eq!(thermodynamics(b"A"), None);
+        assert_eq!(thermodynamics(b"ACGN"), None);
+    }
+
+    #[test]
+    fn test_tm() {
+        let conditions = Conditions::default();
+        let primer = b"AGCGGATAACAATTTCACACAGGA";
+        let (dh, ds) = thermodynamics(primer).unwrap();
+        let ds = ds + 0.368 * 23.0 * 0.05f64.ln();
+        let expected = 1000.0 * dh / (ds + R * 12.5e-9f64.ln()) - 273.15;
+        assert_relative_eq!(tm(primer, &conditions).unwrap(), expected, epsilon = 1e-9);
+
+        // higher concentrations and salt increase the Tm
+        let high = Conditions {
+            oligo_conc: 500.0,
+            ..conditions
+        };
+        assert!(tm(primer, &high).unwrap() > expected);
+        let salt = Conditions {
+            na: 1000.0,
+            ..conditions
+        };
+        assert!(tm(primer, &salt).unwrap() > expected);
+        let mg = Conditions {
+            mg: 2.0,
+            dntp: 1.0,
+            ..conditions
+        };
+        assert_relative_eq!(mg.na_equivalent(), 170.0);
+        assert_relative_eq!(Conditions { dntp: 3.0, ..mg }.na_equivalent(), 50.0);
+
+        // the duplex is stable below and unstable above its Tm
+        let t = tm(primer, &conditions).unwrap();
+        assert!(delta_g(primer, t - 10.0, &conditions).unwrap() < 0.0);
+        assert!(delta_g(primer, 37.0, &conditions).unwrap() < -15.0);
+        assert!(
+            tm(b"GCGCGCGCGCGC", &conditions).unwrap() > tm(b"ATATATATATAT", &conditions).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tm_gc_track() {
+        let conditions = Conditions::default();
+        let seq = b"ATATATATATGCGCGCGCGCATTGCANNACGTAGCTAGCTAGGATC";
+        let track = tm_gc_track(seq, 10, 3, &conditions);
+        assert_eq!(track.len(), (seq.len() - 10) / 3 + 1);
+        for w in &track {
+            let window = &seq[w.start..w.start + 10];
+            assert_relative_eq!(w.gc, f64::from(gc_content(window)), epsilon = 1e-6);
+            match tm(window, &conditions) {
+                Some(expected) => assert_relative_eq!(w.tm.unwrap(), expected, epsilon = 1e-9),
+                None => assert_eq!(w.tm, None),
+            }
+        }
+        assert!(track[0].tm.unwrap() < track[3].tm.unwrap());
+        assert!(track[6].tm.is_none());
+        assert!(tm_gc_track(b"ACGT", 10, 1, &conditions).is_empty());
+    }
+}
@@ -0,0 +1,594 @@
+//! Screening of reads or contigs for vector and adapter contamination against a vector database
+//! such as UniVec, following NCBI VecScreen.
+//!
+//! Vectors sharing a k-mer with the query (on either strand) are aligned to it by local
+//! alignment with the VecScreen scores (match 1, mismatch -5, gap open and extension -3 each),
+//! repeatedly, such that several hits of the same vector are found. Hits are classified as
+//! strong, moderate or weak by their score, with lower thresholds for terminal hits, which
+//! start or end within 25 bases of a sequence end and are thus more likely real contamination.
+//! Segments shorter than 50 bases between a hit and a sequence end or between two hits are of
+//! suspect origin and flagged as well.
+//!
+//! `VecTrim` applies the screen in a record pipeline (see `io::pipeline`), trimming reads to
+//! their longest unflagged segment or masking the flagged segments with `N`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fasta;
+//! use bio::io::pipeline::Pipeline;
+//! use bio::seq_analysis::vecscreen::{Strength, VecScreen};
+//!
+//! let mut screen = VecScreen::new();
+//! screen.add_vector("Illumina adapter", b"AGATCGGAAGAGCACACGTCTGAACTCCAGTCAC");
+//!
+//! // a read through its insert into the adapter
+//! let read = b"GCATTCAGGTACCTTGAGCATTATTGACCGATAGGCTTACCAGGATTCAGCAGTTACCGAAGATCGGAAGAGCACACGTCTGAAC";
+//! let result = screen.screen(read);
+//! assert_eq!(result.hits.len(), 1);
+//! assert_eq!(result.hits[0].range, 60..read.len());
+//! assert_eq!(result.hits[0].strength, Strength::Strong);
+//!
+//! let records = vec![fasta::Record::with_attrs("read", None, read)];
+//! let trimmed: Vec<_> = records.into_iter().transform(screen.trim()).collect();
+//! assert_eq!(trimmed[0].seq(), &read[..60]);
+//! ```
+
+use std::cmp;
+use std::io;
+use std::ops::Range;
+
+use alignment::pairwise::{Aligner, MatchParams};
+use alphabets::dna::{self, canonical_kmers, Orientation};
+use io::pipeline::RecordTransform;
+use io::{fasta, fastq};
+use utils::KmerHashMap;
+
+/// Default k-mer length for finding the candidate vectors of a query.
+pub const DEFAULT_K: usize = 11;
+/// Maximum distance of a terminal hit to a sequence end.
+pub const TERMINAL_DISTANCE: usize = 25;
+/// Segments shorter than this, between a hit and a sequence end or between two hits, are of
+/// suspect origin.
+pub const SUSPECT_LEN: usize = 50;
+
+const MATCH_SCORE: i32 = 1;
+const MISMATCH_SCORE: i32 = -5;
+const GAP_OPEN: i32 = -3;
+const GAP_EXTEND: i32 = -3;
+
+/// Strength of a vector hit, by increasing alignment score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Strength {
+    Weak,
+    Moderate,
+    Strong,
+}
+
+impl Strength {
+    /// Classify a hit by its score as VecScreen, or return `None` if the score is too low for
+    /// a weak hit. Terminal hits are strong from score 24, moderate from 19 and weak from 16,
+    /// internal hits are strong from 30, moderate from 25 and weak from 23.
+    pub fn classify(score: i32, terminal: bool) -> Option<Strength> {
+        let (strong, moderate, weak) = if terminal { (24, 19, 16) } else { (30, 25, 23) };
+        if score >= strong {
+            Some(Strength::Strong)
+        } else if score >= moderate {
+            Some(Strength::Moderate)
+        } else if score >= weak {
+            Some(Strength::Weak)
+        } else {
+            None
+        }
+    }
+}
+
+/// A local alignment of a vector to a query.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorHit {
+    /// Index of the vector in the database.
+    pub vector: usize,
+    /// The aligned range of the query.
+    pub range: Range<usize>,
+    /// Orientation of the vector relative to the query.
+    pub orientation: Orientation,
+    /// Local alignment score.
+    pub score: i32,
+    /// Whether the hit starts or ends within `TERMINAL_DISTANCE` of a query end.
+    pub terminal: bool,
+    pub strength: Strength,
+}
+
+/// Vector hits of a query.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VecScreenResult {
+    /// Hits of all vectors, sorted by their start in the query.
+    pub hits: Vec<VectorHit>,
+    /// Segments of suspect origin.
+    pub suspect: Vec<Range<usize>>,
+}
+
+impl VecScreenResult {
+    /// Check whether the query has no hits.
+    pub fn is_clean(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    /// The sorted, disjoint segments covered by hits or of suspect origin.
+    pub fn flagged(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<_> = self
+            .hits
+            .iter()
+            .map(|hit| hit.range.clone())
+            .chain(self.suspect.iter().cloned())
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        union(ranges)
+    }
+}
+
+/// Join overlapping or adjacent ranges, sorted by their start.
+fn union(ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut joined: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match joined.last_mut() {
+            Some(last) if range.start <= last.end => last.end = cmp::max(last.end, range.end),
+            _ => joined.push(range),
+        }
+    }
+    joined
+}
+
+/// A database of vector sequences to screen queries against.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VecScreen {
+    k: usize,
+    min_strength: Strength,
+    names: Vec<String>,
+    seqs: Vec<Vec<u8>>,
+    /// The vectors containing each canonical k-mer.
+    kmers: KmerHashMap<u64, Vec<u32>>,
+}
+
+impl Default for VecScreen {
+    fn default() -> Self {
+        VecScreen {
+            k: DEFAULT_K,
+            min_strength: Strength::Weak,
+            names: Vec::new(),
+            seqs: Vec::new(),
+            kmers: KmerHashMap::default(),
+        }
+    }
+}
+
+impl VecScreen {
+    /// Create an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a database with the vectors of the given FASTA reader, e.g. of UniVec.
+    pub fn from_fasta<R: io::Read>(reader: fasta::Reader<R>) -> io::Result<Self> {
+        let mut screen = Self::new();
+        for record in reader.records() {
+            let record = record?;
+            screen.add_vector(record.id(), record.seq());
+        }
+        Ok(screen)
+    }
+
+    /// Length of the k-mers a vector has to share with a query for being aligned
+    /// (default: `DEFAULT_K`). Has to be set before adding vectors.
+    pub fn k(mut self, k: usize) -> Self {
+        assert!((1..=32).contains(&k), "Expecting k between 1 and 32.");
+        assert!(
+            self.seqs.is_empty(),
+            "Expecting k to be set before adding vectors."
+        );
+        self.k = k;
+        self
+    }
+
+    /// Ignore hits weaker than the given strength (default: `Strength::Weak`).
+    pub fn min_strength(mut self, min_strength: Strength) -> Self {
+        self.min_strength = min_strength;
+        self
+    }
+
+    /// Add a vector to the database, returning its index.
+    pub fn add_vector(&mut self, name: &str, seq: &[u8]) -> usize {
+        let vector = self.seqs.len();
+        for kmer in canonical_kmers(seq, self.k) {
+            let vectors = self.kmers.entry(kmer).or_default();
+            if vectors.last() != Some(&(vector as u32)) {
+                vectors.push(vector as u32);
+            }
+        }
+        self.names.push(name.to_owned());
+        self.seqs.push(seq.to_ascii_uppercase());
+        vector
+    }
+
+    /// Number of vectors.
+    pub fn len(&self) -> usize {
+        self.seqs.len()
+    }
+
+    /// Check whether the database contains no vectors.
+    pub fn is_empty(&self) -> bool {
+        self.seqs.is_empty()
+    }
+
+    /// The name of the given vector.
+    pub fn name(&self, vector: usize) -> &str {
+        &self.names[vector]
+    }
+
+    /// Screen a query against all vectors.
+    pub fn screen(&self, seq: &[u8]) -> VecScreenResult {
+        let mut candidates: Vec<u32> = canonical_kmers(seq, self.k)
+            .filter_map(|kmer| self.kmers.get(&kmer))
+            .flatten()
+            .cloned()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut hits = Vec::new();
+        let forward = seq.to_ascii_uppercase();
+        let reverse = dna::revcomp(&forward);
+        for &vector in &candidates {
+            for &orientation in &[Orientation::Forward, Orientation::Reverse] {
+                let query = match orientation {
+                    Orientation::Forward => &forward,
+                    Orientation::Reverse => &reverse,
+                };
+                self.align(vector as usize, query, orientation, &mut hits);
+            }
+        }
+        hits.sort_by_key(|hit| (hit.range.start, hit.range.end, hit.vector));
+
+        // short segments next to hits are suspect
+        let covered = union(hits.iter().map(|hit| hit.range.clone()).collect());
+        let mut suspect = Vec::new();
+        let mut last_end = 0;
+        // segments of 1 to SUSPECT_LEN - 1 bases after the last end
+        let short = |last_end: usize| last_end + 1..last_end + SUSPECT_LEN;
+        for range in &covered {
+            if short(last_end).contains(&range.start) {
+                suspect.push(last_end..range.start);
+            }
+            last_end = range.end;
+        }
+        if !covered.is_empty() && short(last_end).contains(&seq.len()) {
+            suspect.push(last_end..seq.len());
+        }
+
+        VecScreenResult { hits, suspect }
+    }
+
+    /// Find all hits of a vector in the query (in the given orientation) by repeated local
+    /// alignment, masking the hits found so far.
+    fn align(
+        &self,
+        vector: usize,
+        query: &[u8],
+        orientation: Orientation,
+        hits: &mut Vec<VectorHit>,
+    ) {
+        let n = query.len();
+        let target = &self.seqs[vector];
+        let mut query = query.to_vec();
+        let mut aligner = Aligner::with_capacity(
+            n,
+            target.len(),
+            GAP_OPEN,
+            GAP_EXTEND,
+            MatchParams::new(MATCH_SCORE, MISMATCH_SCORE),
+        );
+        loop {
+            let alignment = aligner.local(&query, target);
+            let (start, end) = (alignment.xstart, alignment.xend);
+            if end <= start {
+                break;
+            }
+            // hits are reported on the forward strand of the query
+            let range = match orientation {
+                Orientation::Forward => start..end,
+                Orientation::Reverse => n - end..n - start,
+            };
+            let terminal = range.start < TERMINAL_DISTANCE || range.end + TERMINAL_DISTANCE > n;
+            let strength = match Strength::classify(alignment.score, terminal) {
+                Some(strength) => strength,
+                None => break,
+            };
+            if strength >= self.min_strength {
+                hits.push(VectorHit {
+                    vector,
+                    range,
+                    orientation,
+                    score: alignment.score,
+                    terminal,
+                    strength,
+                });
+            }
+            for base in &mut query[start..end] {
+                *base = b'N';
+            }
+        }
+    }
+
+    /// A record transform removing vector contamination, by default trimming records to their
+    /// longest segment without hits and suspect segments.
+    pub fn trim(&self) -> VecTrim<'_> {
+        VecTrim {
+            screen: self,
+            action: VectorAction::default(),
+            min_len: 1,
+            stats: VecTrimStats::default(),
+        }
+    }
+}
+
+/// What to do with the flagged segments of a record.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VectorAction {
+    /// Keep the longest segment without flagged bases.
+    #[default]
+    Trim,
+    /// Replace flagged bases by `N`.
+    Mask,
+}
+
+/// Counts of the records processed by a `VecTrim`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VecTrimStats {
+    /// Number of processed records.
+    pub records: u64,
+    /// Number of records with vector hits.
+    pub contaminated: u64,
+    /// Number of trimmed or masked bases.
+    pub flagged_bases: u64,
+    /// Number of records dropped for being too short after trimming.
+    pub dropped: u64,
+}
+
+/// A record transform trimming or masking vector contamination.
+#[derive(Clone, Debug)]
+pub struct VecTrim<'a> {
+    screen: &'a VecScreen,
+    action: VectorAction,
+    min_len: usize,
+    stats: VecTrimStats,
+}
+
+impl<'a> VecTrim<'a> {
+    /// Set the action for flagged segments (default: `VectorAction::Trim`).
+    pub fn action(mut self, action: VectorAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    /// Drop records shorter than `min_len` after trimming (default: 1, i.e. empty records).
+    pub fn min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len;
+        self
+    }
+
+    /// Statistics of all records processed so far.
+    pub fn stats(&self) -> &VecTrimStats {
+        &self.stats
+    }
+
+    /// The range of the record to keep and the ranges to mask, or `None` to drop the record.
+    fn process(&mut self, seq: &[u8]) -> Option<(Range<usize>, Vec<Range<usize>>)> {
+        self.stats.records += 1;
+        let result = self.screen.screen(seq);
+        if result.is_clean() {
+            return Some((0..seq.len(), Vec::new()));
+        }
+        self.stats.contaminated += 1;
+        let flagged = result.flagged();
+        let (keep, masked) = match self.action {
+            VectorAction::Trim => {
+                let mut best = 0..0;
+                let mut last_end = 0;
+                for range in flagged.iter().chain(Some(&(seq.len()..seq.len()))) {
+                    if range.start - last_end > best.len() {
+                        best = last_end..range.start;
+                    }
+                    last_end = range.end;
+                }
+                (best, Vec::new())
+            }
+            VectorAction::Mask => (0..seq.len(), flagged),
+        };
+        let kept = keep.len() - masked.iter().map(|range| range.len()).sum::<usize>();
+        self.stats.flagged_bases += (seq.len() - kept) as u64;
+        if keep.len() < self.min_len {
+            self.stats.dropped += 1;
+            return None;
+        }
+        Some((keep, masked))
+    }
+}
+
+fn apply_mask(seq: &mut [u8], masked: &[Range<usize>]) {
+    for range in masked {
+        for base in &mut seq[range.clone()] {
+            *base = b'N';
+        }
+    }
+}
+
+impl<'a> RecordTransform<fasta::Record> for VecTrim<'a> {
+    type Output = fasta::Record;
+
+    fn apply(&mut self, record: fasta::Record) -> Option<fasta::Record> {
+        let (keep, masked) = self.process(record.seq())?;
+        if keep.len() == record.seq().len() && masked.is_empty() {
+            return Some(record);
+        }
+        let mut seq = record.seq()[keep].to_vec();
+        apply_mask(&mut seq, &masked);
+        Some(fasta::Record::with_attrs(record.id(), record.desc(), &seq))
+    }
+}
+
+impl<'a> RecordTransform<fastq::Record> for VecTrim<'a> {
+    type Output = fastq::Record;
+
+    fn apply(&mut self, record: fastq::Record) -> Option<fastq::Record> {
+        let (keep, masked) = self.process(record.seq())?;
+        if keep.len() == record.seq().len() && masked.is_empty() {
+            return Some(record);
+        }
+        let mut seq = record.seq()[keep.clone()].to_vec();
+        apply_mask(&mut seq, &masked);
+        Some(fastq::Record::with_attrs(
+            record.id(),
+            record.desc(),
+            &seq,
+            &record.qual()[keep],
+        ))
+    }
+}
+
+impl<'a, T> RecordTransform<T> for &mut VecTrim<'a>
+where
+    VecTrim<'a>: RecordTransform<T>,
+{
+    type Output = <VecTrim<'a> as RecordTransform<T>>::Output;
+
+    fn apply(&mut self, record: T) -> Option<Self::Output> {
+        (**self).apply(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use io::pipeline::Pipeline;
+    use utils::{SeedableRngExt, Xoshiro256};
+
+    fn random_seq(len: usize, rng: &mut Xoshiro256) -> Vec<u8> {
+        (0..len).map(|_| b"ACGT"[rng.below(4) as usize]).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(Strength::classify(24, true), Some(Strength::Strong));
+        assert_eq!(Strength::classify(24, false), Some(Strength::Weak));
+        assert_eq!(Strength::classify(16, true), Some(Strength::Weak));
+        assert_eq!(Strength::classify(22, false), None);
+        assert_eq!(Strength::classify(27, false), Some(Strength::Moderate));
+        assert!(Strength::Strong > Strength::Weak);
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut rng = Xoshiro256::seed_from_u64(42);
+        let vector = random_seq(400, &mut rng);
+        let other = random_seq(300, &mut rng);
+        let mut screen = VecScreen::new();
+        assert_eq!(screen.add_vector("pUC19", &vector), 0);
+        assert_eq!(screen.add_vector("other", &other), 1);
+        assert_eq!(screen.name(1), "other");
+
+        // internal hit on the reverse strand, 40 bases from the start
+        let mut query = random_seq(200, &mut rng);
+        let insert = dna::revcomp(&vector[100..160]);
+        query.splice(40..100, insert.iter().cloned());
+        let result = screen.screen(&query);
+        assert_eq!(result.hits.len(), 1);
+        let hit = &result.hits[0];
+        assert_eq!(hit.vector, 0);
+        assert_eq!(hit.range, 40..100);
+        assert_eq!(hit.orientation, Orientation::Reverse);
+        assert_eq!(hit.score, 60);
+        assert!(!hit.terminal);
+        assert_eq!(hit.strength, Strength::Strong);
+        // the 40 bases before the hit are suspect
+        assert_eq!(result.suspect.len(), 1);
+        assert_eq!(result.suspect[0], 0..40);
+        assert_eq!(result.flagged().len(), 1);
+        assert_eq!(result.flagged()[0], 0..100);
+
+        // a short terminal hit and an internal hit of another vector
+        let mut query = vector[380..].to_vec();
+        query.extend(random_seq(100, &mut rng));
+        query.extend_from_slice(&other[..40]);
+        query.extend(random_seq(100, &mut rng));
+        let result = screen.screen(&query);
+        assert_eq!(result.hits.len(), 2);
+        let (terminal, internal) = (&result.hits[0], &result.hits[1]);
+        assert_eq!((terminal.vector, terminal.range.clone()), (0, 0..20));
+        assert!(terminal.terminal);
+        // the alignment may extend by chance into the random flanks
+        assert_eq!(internal.vector, 1);
+        assert!(internal.range.start <= 120 && internal.range.end >= 160);
+        assert!(internal.range.len() <= 42 && !internal.terminal);
+        assert_eq!(result.hits[0].strength, Strength::Moderate);
+        assert!(result.suspect.is_empty());
+        let strict = screen.clone().min_strength(Strength::Strong);
+        assert_eq!(strict.screen(&query).hits.len(), 1);
+
+        assert!(screen.screen(&random_seq(300, &mut rng)).is_clean());
+    }
+
+    #[test]
+    fn test_repeated_hits() {
+        let mut rng = Xoshiro256::seed_from_u64(3);
+        let adapter = b"CTGTCTCTTATACACATCTCCGAGCCCACGAGAC";
+        let mut screen = VecScreen::new();
+        screen.add_vector("Nextera", adapter);
+        let mut query = adapter.to_vec();
+        query.extend(random_seq(150, &mut rng));
+        query.extend_from_slice(adapter);
+        let result = screen.screen(&query);
+        let ranges: Vec<_> = result.hits.iter().map(|hit| hit.range.clone()).collect();
+        assert_eq!(ranges, [0..34, 184..218]);
+        assert!(result.hits.iter().all(|hit| hit.terminal));
+    }
+
+    #[test]
+    fn test_trim() {
+        let mut rng = Xoshiro256::seed_from_u64(7);
+        let adapter = b"AGATCGGAAGAGCACACGTCTGAACTCCAGTCAC";
+        let mut screen = VecScreen::new();
+        screen.add_vector("TruSeq", adapter);
+        let insert = random_seq(100, &mut rng);
+        let mut seq = insert.clone();
+        seq.extend_from_slice(&adapter[..30]);
+        let qual = vec![b'I'; seq.len()];
+        let records = vec![
+            fastq::Record::with_attrs("contaminated", None, &seq, &qual),
+            fastq::Record::with_attrs("clean", None, &insert, &qual[..100]),
+            fastq::Record::with_attrs("adapter", None, adapter, &qual[..adapter.len()]),
+        ];
+
+        let mut trim = screen.trim();
+        let trimmed: Vec<_> = records.clone().into_iter().transform(&mut trim).collect();
+        assert_eq!(trimmed.len(), 2);
+        assert_eq!(trimmed[0].seq(), &insert[..]);
+        assert_eq!(trimmed[0].qual().len(), 100);
+        assert_eq!(trimmed[1].id(), "clean");
+        assert_eq!(
+            *trim.stats(),
+            VecTrimStats {
+                records: 3,
+                contaminated: 2,
+                flagged_bases: 30 + adapter.len() as u64,
+                dropped: 1,
+            }
+        );
+
+        let mut mask = screen.trim().action(VectorAction::Mask);
+        let masked: Vec<_> = records.into_iter().transform(&mut mask).collect();
+        assert_eq!(masked.len(), 3);
+        assert_eq!(&masked[0].seq()[..100], &insert[..]);
+        assert!(masked[0].seq()[100..].iter().all(|&b| b == b'N'));
+        assert_eq!(masked[2].seq(), &vec![b'N'; adapter.len()][..]);
+    }
+}
@@ -0,0 +1,268 @@
+//! Copy number segmentation of normalized read depth with a hidden Markov model.
+//!
+//! Each state of the model is a copy number `0..=max_copy_number`. Observations are depth
+//! ratios per window, i.e. the observed depth divided by the depth expected for the normal
+//! copy number (`ploidy`), e.g. after GC correction. In copy number `c`, ratios are normally
+//! distributed around `c / ploidy` with a common standard deviation. Changes between copy
+//! numbers occur with a fixed probability per window, towards any other copy number with equal
+//! probability. Missing windows (NaN) are uninformative.
+//!
+//! Copy numbers are called with the Viterbi algorithm, and each segment is annotated with the
+//! mean posterior probability of its copy number (from the forward-backward algorithm) as
+//! confidence.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::stats::cnv::CnvModel;
+//!
+//! let mut ratios = vec![1.0; 40];
+//! ratios.extend(vec![1.5; 20]);
+//! ratios.extend(vec![0.5; 10]);
+//! for (i, r) in ratios.iter_mut().enumerate() {
+//!     *r += [0.05, -0.1, 0.08, -0.03][i % 4];
+//! }
+//!
+//! let segments = CnvModel::new(2, 5, 0.1).segment(&ratios);
+//! let calls: Vec<_> = segments.iter().map(|s| (s.start, s.end, s.copy_number)).collect();
+//! assert_eq!(calls, [(0, 40, 2), (40, 60, 3), (60, 70, 1)]);
+//! assert!(segments.iter().all(|s| s.posterior > 0.99));
+//! ```
+
+use std::f64;
+
+use stats::hmm::{backward, forward, viterbi, Model, State, StateIter, StateTransitionIter};
+use stats::LogProb;
+
+/// HMM for copy number segmentation of depth ratios.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CnvModel {
+    ploidy: u32,
+    max_copy_number: u32,
+    sd: f64,
+    change_prob: f64,
+}
+
+/// A segment `start..end` of windows (end exclusive) with the called copy number.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CnvSegment {
+    pub start: usize,
+    pub end: usize,
+    pub copy_number: u32,
+    /// Mean depth ratio of the non-missing windows (NaN if all are missing).
+    pub mean_ratio: f64,
+    /// Mean posterior probability of the copy number over the windows of the segment.
+    pub posterior: f64,
+}
+
+impl CnvSegment {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl CnvModel {
+    /// Create a new model with the given normal copy number, maximal copy number and standard
+    /// deviation of depth ratios, and a probability of copy number changes of `1e-4`.
+    pub fn new(ploidy: u32, max_copy_number: u32, sd: f64) -> Self {
+        assert!(ploidy > 0, "Expecting positive ploidy.");
+        assert!(
+            max_copy_number >= ploidy,
+            "Expecting maximal copy number of at least the ploidy."
+        );
+        assert!(sd > 0.0, "Expecting positive standard deviation.");
+        CnvModel {
+            ploidy,
+            max_copy_number,
+            sd,
+            change_prob: 1e-4,
+        }
+    }
+
+    /// Set the probability of a copy number change between consecutive windows.
+    pub fn change_prob(mut self, change_prob: f64) -> Self {
+        assert!(
+            change_prob > 0.0 && change_prob < 1.0,
+            "Expecting change probability between 0 and 1."
+        );
+        self.change_prob = change_prob;
+        self
+    }
+
+    /// Expected depth ratio in the given copy number.
+    pub fn expected_ratio(&self, copy_number: u32) -> f64 {
+        f64::from(copy_number) / f64::from(self.ploidy)
+    }
+
+    /// Most likely copy number of each window.
+    pub fn copy_numbers(&self, ratios: &[f64]) -> Vec<u32> {
+        if ratios.is_empty() {
+            return Vec::new();
+        }
+        let (path, _) = viterbi(self, ratios);
+        path.into_iter().map(|state| *state as u32).collect()
+    }
+
+    /// Posterior probabilities of the copy numbers of each window, indexed by window and copy
+    /// number.
+    pub fn posteriors(&self, ratios: &[f64]) -> Vec<Vec<f64>> {
+        if ratios.is_empty() {
+            return Vec::new();
+        }
+        let n = ratios.len();
+        let (fwd, total) = forward(self, ratios);
+        // backward values are stored in reverse order and include the emission (and initial)
+        // probability of their window
+        let (bwd, _) = backward(self, ratios);
+        ratios
+            .iter()
+            .enumerate()
+            .map(|(i, ratio)| {
+                let mut posterior: Vec<_> = self
+                    .states()
+                    .map(|s| {
+                        let mut p = fwd[[i, *s]] + bwd[[n - 1 - i, *s]]
+                            - self.observation_prob(s, ratio)
+                            - total;
+                        if i == 0 {
+                            p -= self.initial_prob(s);
+                        }
+                        p.exp()
+                    })
+                    .collect();
+                // correct for the approximate exponentials used in the HMM algorithms
+                let sum: f64 = posterior.iter().sum();
+                for p in &mut posterior {
+                    *p /= sum;
+                }
+                posterior
+            })
+            .collect()
+    }
+
+    /// Segment the given depth ratios into runs of equal copy number.
+    pub fn segment(&self, ratios: &[f64]) -> Vec<CnvSegment> {
+        let copy_numbers = self.copy_numbers(ratios);
+        let posteriors = self.posteriors(ratios);
+        let mut segments = Vec::new();
+        let mut start = 0;
+        for end in 1..=ratios.len() {
+            if end < ratios.len() && copy_numbers[end] == copy_numbers[start] {
+                continue;
+            }
+            let copy_number = copy_numbers[start];
+            let observed: Vec<_> = ratios[start..end].iter().filter(|r| !r.is_nan()).collect();
+            let posterior = posteriors[start..end]
+                .iter()
+                .map(|p| p[copy_number as usize])
+                .sum::<f64>()
+                / (end - start) as f64;
+            segments.push(CnvSegment {
+                start,
+                end,
+                copy_number,
+                mean_ratio: observed.iter().copied().sum::<f64>() / observed.len() as f64,
+                posterior,
+            });
+            start = end;
+        }
+        segments
+    }
+}
+
+impl Model<f64> for CnvModel {
+    fn num_states(&self) -> usize {
+        self.max_copy_number as usize + 1
+    }
+
+    fn states(&self) -> StateIter {
+        StateIter::new(self.num_states())
+    }
+
+    fn transitions(&self) -> StateTransitionIter {
+        StateTransitionIter::new(self.num_states())
+    }
+
+    fn transition_prob(&self, from: State, to: State) -> LogProb {
+        if from == to {
+            LogProb((1.0 - self.change_prob).ln())
+        } else {
+            LogProb((self.change_prob / self.max_copy_number as f64).ln())
+        }
+    }
+
+    fn initial_prob(&self, _state: State) -> LogProb {
+        LogProb(-(self.num_states() as f64).ln())
+    }
+
+    fn observation_prob(&self, state: State, ratio: &f64) -> LogProb {
+        if ratio.is_nan() {
+            return LogProb::ln_one();
+        }
+        let z = (ratio - self.expected_ratio(*state as u32)) / self.sd;
+        LogProb(-0.5 * z * z - (self.sd * (2.0 * f64::consts::PI).sqrt()).ln())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_numbers() {
+        let model = CnvModel::new(2, 4, 0.15);
+        assert_eq!(model.expected_ratio(3), 1.5);
+        let mut ratios = vec![1.02, 0.95, 1.1, 0.9, 1.0, 0.48, 0.55, 0.5, 0.45];
+        ratios.extend(&[0.0, 0.05, 0.02, 0.0, 0.03]);
+        ratios.extend(vec![1.0; 10]);
+        let copy_numbers = model.copy_numbers(&ratios);
+        assert_eq!(
+            &copy_numbers[..14],
+            [2, 2, 2, 2, 2, 1, 1, 1, 1, 0, 0, 0, 0, 0]
+        );
+        assert!(copy_numbers[14..].iter().all(|&c| c == 2));
+        // a single outlier window is not called with a low change probability
+        let mut ratios = vec![1.0; 20];
+        ratios[10] = 1.5;
+        assert!(model
+            .change_prob(1e-8)
+            .copy_numbers(&ratios)
+            .iter()
+            .all(|&c| c == 2));
+        assert!(model.copy_numbers(&[]).is_empty());
+        assert!(model.segment(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_posteriors() {
+        let model = CnvModel::new(2, 3, 0.2);
+        let mut ratios = vec![1.0; 10];
+        ratios.push(1.2);
+        ratios.extend(vec![1.5; 10]);
+        ratios[15] = f64::NAN;
+        let posteriors = model.posteriors(&ratios);
+        for p in &posteriors {
+            assert_relative_eq!(p.iter().sum::<f64>(), 1.0, epsilon = 1e-9);
+        }
+        // the ambiguous window is the least certain
+        let best: Vec<_> = posteriors
+            .iter()
+            .map(|p| p.iter().cloned().fold(0.0, f64::max))
+            .collect();
+        let min = best.iter().cloned().fold(1.0, f64::min);
+        assert_eq!(min, best[10]);
+        assert!(best[0] > 0.99);
+
+        let segments = model.segment(&ratios);
+        assert_eq!(segments.len(), 2);
+        assert_eq!((segments[1].start, segments[1].end), (11, 21));
+        assert_eq!(segments[1].copy_number, 3);
+        assert_relative_eq!(segments[1].mean_ratio, 1.5);
+        assert!(segments[0].posterior < segments[1].posterior);
+        assert!(segments[1].posterior > 0.99 && segments[1].posterior <= 1.0);
+    }
+}
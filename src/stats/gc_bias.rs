@@ -0,0 +1,271 @@
+//! Correction of GC bias in read depth tracks, e.g. of windows along the genome before copy
+//! number segmentation.
+//!
+//! The expected depth is modeled as a smooth function of the GC content of a window, fitted
+//! either by the median depth per GC bin or by LOESS (local linear regression with tricube
+//! weights). Corrected tracks are the ratios of observed and expected depth, i.e. about 1 for
+//! windows of normal copy number. Windows with missing (NaN) depth or GC content, as well as
+//! windows without coverage (e.g. assembly gaps), are ignored for fitting.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::stats::gc_bias::GcBias;
+//!
+//! let gc: Vec<_> = (0..200).map(|i| 0.3 + 0.004 * ((i * 37) % 100) as f64).collect();
+//! // depth drops for GC rich windows, and is doubled in the last 50 windows
+//! let depth: Vec<_> = gc
+//!     .iter()
+//!     .enumerate()
+//!     .map(|(i, g)| (60.0 - 50.0 * g) * if i >= 150 { 2.0 } else { 1.0 })
+//!     .collect();
+//!
+//! let bias = GcBias::binned(&depth, &gc, 40);
+//! let ratios = bias.correct(&depth, &gc);
+//! assert!(ratios[..150].iter().all(|r| (r - 1.0).abs() < 0.05));
+//! assert!(ratios[150..].iter().all(|r| (r - 2.0).abs() < 0.1));
+//! ```
+
+use std::cmp::Ordering;
+use std::f64;
+
+/// Number of GC values at which the LOESS curve is evaluated.
+const LOESS_GRID: usize = 101;
+
+/// Median of the given values, which are sorted in place.
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
+
+/// Pairs of GC content and depth usable for fitting, sorted by GC content.
+fn fit_points(depth: &[f64], gc: &[f64]) -> Vec<(f64, f64)> {
+    assert_eq!(
+        depth.len(),
+        gc.len(),
+        "Expecting depth and GC content for the same windows."
+    );
+    let mut points: Vec<_> = gc
+        .iter()
+        .zip(depth)
+        .filter(|&(g, d)| g.is_finite() && d.is_finite() && *d > 0.0)
+        .map(|(&g, &d)| (g, d))
+        .collect();
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    points
+}
+
+/// Expected depth as a function of GC content.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GcBias {
+    /// Pairs of GC content and expected depth, sorted by GC content.
+    curve: Vec<(f64, f64)>,
+}
+
+impl GcBias {
+    /// Fit the median depth in each of the given number of equally sized GC content bins.
+    pub fn binned(depth: &[f64], gc: &[f64], bins: usize) -> Self {
+        assert!(bins > 0, "Expecting at least one bin.");
+        let points = fit_points(depth, gc);
+        let mut curve = Vec::new();
+        let mut i = 0;
+        for bin in 0..bins {
+            let upper = (bin + 1) as f64 / bins as f64;
+            let mut values = Vec::new();
+            let mut sum_gc = 0.0;
+            while i < points.len() && (points[i].0 < upper || bin + 1 == bins) {
+                sum_gc += points[i].0;
+                values.push(points[i].1);
+                i += 1;
+            }
+            if !values.is_empty() {
+                let center = sum_gc / values.len() as f64;
+                curve.push((center, median(&mut values)));
+            }
+        }
+        GcBias { curve }
+    }
+
+    /// Fit a LOESS curve, using the given fraction of windows nearest in GC content for each
+    /// local regression.
+    pub fn loess(depth: &[f64], gc: &[f64], span: f64) -> Self {
+        assert!(span > 0.0 && span <= 1.0, "Expecting span between 0 and 1.");
+        let points = fit_points(depth, gc);
+        if points.is_empty() {
+            return GcBias { curve: Vec::new() };
+        }
+        let q = ((span * points.len() as f64).ceil() as usize).clamp(1, points.len());
+        let (min, max) = (points[0].0, points[points.len() - 1].0);
+        let curve = (0..LOESS_GRID)
+            .map(|k| {
+                let x = min + (max - min) * k as f64 / (LOESS_GRID - 1) as f64;
+                (x, Self::local_fit(&points, x, q))
+            })
+            .collect();
+        GcBias { curve }
+    }
+
+    /// Local linear regression at `x` over the `q` points nearest to it.
+    fn local_fit(points: &[(f64, f64)], x: f64, q: usize) -> f64 {
+        // grow the window of nearest points around the insertion position of x
+        let mut hi = points.partition_point(|p| p.0 < x);
+        let mut lo = hi;
+        while hi - lo < q {
+            if lo == 0 || (hi < points.len() && points[hi].0 - x < x - points[lo - 1].0) {
+                hi += 1;
+            } else {
+                lo -= 1;
+            }
+        }
+        let neighbors = &points[lo..hi];
+        let radius = neighbors
+            .iter()
+            .map(|p| (p.0 - x).abs())
+            .fold(0.0, f64::max);
+        let (mut sw, mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for &(px, py) in neighbors {
+            let w = if radius > 0.0 {
+                (1.0 - ((px - x).abs() / (radius * 1.0001)).powi(3)).powi(3)
+            } else {
+                1.0
+            };
+            sw += w;
+            sx += w * px;
+            sy += w * py;
+            sxx += w * px * px;
+            sxy += w * px * py;
+        }
+        let (mx, my) = (sx / sw, sy / sw);
+        let var = sxx / sw - mx * mx;
+        if var <= 1e-12 {
+            return my;
+        }
+        let slope = (sxy / sw - mx * my) / var;
+        my + slope * (x - mx)
+    }
+
+    /// The fitted pairs of GC content and expected depth, sorted by GC content.
+    pub fn curve(&self) -> &[(f64, f64)] {
+        &self.curve
+    }
+
+    /// Expected depth for the given GC content, interpolated linearly between the points of the
+    /// curve and constant beyond them. NaN if the curve is empty or the GC content missing.
+    pub fn expected(&self, gc: f64) -> f64 {
+        if self.curve.is_empty() || gc.is_nan() {
+            return f64::NAN;
+        }
+        let i = self.curve.partition_point(|p| p.0 < gc);
+        if i == 0 {
+            return self.curve[0].1;
+        }
+        if i == self.curve.len() {
+            return self.curve[i - 1].1;
+        }
+        let ((x0, y0), (x1, y1)) = (self.curve[i - 1], self.curve[i]);
+        y0 + (y1 - y0) * (gc - x0) / (x1 - x0)
+    }
+
+    /// Ratios of observed and expected depth of the given windows. Windows with non-positive
+    /// expected depth are NaN.
+    pub fn correct(&self, depth: &[f64], gc: &[f64]) -> Vec<f64> {
+        assert_eq!(
+            depth.len(),
+            gc.len(),
+            "Expecting depth and GC content for the same windows."
+        );
+        depth
+            .iter()
+            .zip(gc)
+            .map(|(&d, &g)| {
+                let expected = self.expected(g);
+                if expected > 0.0 {
+                    d / expected
+                } else {
+                    f64::NAN
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn biased(gc: f64) -> f64 {
+        // unimodal bias with a maximum at 45% GC
+        40.0 - 300.0 * (gc - 0.45) * (gc - 0.45)
+    }
+
+    fn track() -> (Vec<f64>, Vec<f64>) {
+        let gc: Vec<_> = (0..1000)
+            .map(|i| 0.3 + 0.3 * ((i * 37) % 100) as f64 / 100.0)
+            .collect();
+        let depth = gc
+            .iter()
+            .enumerate()
+            .map(|(i, &g)| {
+                let noise = [0.5, -0.5, 1.0, -1.0, 0.0][i % 5];
+                if i % 100 == 0 {
+                    // gap
+                    0.0
+                } else if (500..600).contains(&i) {
+                    1.5 * biased(g) + noise
+                } else {
+                    biased(g) + noise
+                }
+            })
+            .collect();
+        (depth, gc)
+    }
+
+    fn check(bias: &GcBias, depth: &[f64], gc: &[f64], gain: f64) {
+        let ratios = bias.correct(depth, gc);
+        for (i, &r) in ratios.iter().enumerate() {
+            if i % 100 == 0 {
+                assert_eq!(r, 0.0);
+            } else if (500..600).contains(&i) {
+                assert!((r - gain).abs() < 0.1, "window {}: {}", i, r);
+            } else {
+                assert!((r - 1.0).abs() < 0.1, "window {}: {}", i, r);
+            }
+        }
+    }
+
+    #[test]
+    fn test_binned() {
+        let (depth, gc) = track();
+        let bias = GcBias::binned(&depth, &gc, 50);
+        assert!(bias.curve().windows(2).all(|w| w[0].0 < w[1].0));
+        check(&bias, &depth, &gc, 1.5);
+    }
+
+    #[test]
+    fn test_loess() {
+        let (depth, gc) = track();
+        let bias = GcBias::loess(&depth, &gc, 0.3);
+        assert_eq!(bias.curve().len(), LOESS_GRID);
+        // unlike medians, local regression is affected by the amplified windows
+        assert!((bias.expected(0.45) - 40.0 * 1.05).abs() < 1.0);
+        check(&bias, &depth, &gc, 1.5 / 1.05);
+    }
+
+    #[test]
+    fn test_expected() {
+        let bias = GcBias {
+            curve: vec![(0.4, 10.0), (0.6, 20.0)],
+        };
+        assert_eq!(bias.expected(0.3), 10.0);
+        assert_eq!(bias.expected(0.5), 15.0);
+        assert_eq!(bias.expected(0.7), 20.0);
+        assert!(bias.expected(f64::NAN).is_nan());
+        assert!(GcBias::loess(&[0.0], &[0.5], 0.5).expected(0.5).is_nan());
+        assert!(GcBias::binned(&[], &[], 10).correct(&[1.0], &[0.5])[0].is_nan());
+    }
+}
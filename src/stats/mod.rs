@@ -6,9 +6,13 @@
 //! Mathematical and statistical tools.
 
 pub mod bayesian;
+pub mod cnv;
 pub mod combinatorics;
+pub mod gc_bias;
 pub mod hmm;
 pub mod pairhmm;
+pub mod phred;
 pub mod probs;
+pub mod segmentation;
 
 pub use stats::probs::{LogProb, PHREDProb, Prob};
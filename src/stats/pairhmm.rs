@@ -141,6 +141,20 @@ impl PairHMM {
         Default::default()
     }
 
+    /// Create a new instance with the lattices preallocated for sequences of length up to
+    /// `max_x` and `max_y`. Since the lattices are reused, repeatedly calling `prob_related`
+    /// within these bounds (e.g. with one instance per thread) does not allocate.
+    pub fn with_capacity(max_x: usize, max_y: usize) -> Self {
+        let col = || Vec::with_capacity(max_y + 1);
+        PairHMM {
+            fm: [col(), col()],
+            fx: [col(), col()],
+            fy: [col(), col()],
+            min_edit_dist: [Vec::with_capacity(max_y + 1), Vec::with_capacity(max_y + 1)],
+            prob_cols: Vec::with_capacity(max_x * 3),
+        }
+    }
+
     /// Calculate the probability of sequence x being related to y via any alignment.
     ///
     /// # Arguments
@@ -546,4 +560,33 @@ CTGTCTTTGATTCCTGCCTCATCCTATTATTTATCGCACCTACGTTCAATATTACAGGCGAACATACTTACTAAAGTGT"
 
         assert_relative_eq!(*p, *p_banded, epsilon = 1e-7);
     }
+
+    #[test]
+    fn test_with_capacity() {
+        let x = b"AGCTCGATCTGATCGATCT";
+        let y = b"AGCTCGATCGATCGATC";
+
+        let mut pair_hmm = PairHMM::with_capacity(x.len(), x.len());
+        let ptrs = |hmm: &PairHMM| {
+            (
+                hmm.fm[0].as_ptr(),
+                hmm.fy[1].as_ptr(),
+                hmm.prob_cols.as_ptr(),
+            )
+        };
+        let before = ptrs(&pair_hmm);
+        for _ in 0..3 {
+            let p = pair_hmm.prob_related(&SemiglobalGapParams, &TestEmissionParams { x, y }, None);
+            let expected = PairHMM::new().prob_related(
+                &SemiglobalGapParams,
+                &TestEmissionParams { x, y },
+                None,
+            );
+            assert_relative_eq!(*p, *expected);
+            let p = pair_hmm.prob_related(&TestGapParams, &TestEmissionParams { x: y, y: x }, None);
+            assert_relative_eq!(p.exp(), PROB_ILLUMINA_INS.powi(2), epsilon = 1e-11);
+        }
+        // the lattices were reused without reallocation
+        assert_eq!(ptrs(&pair_hmm), before);
+    }
 }
@@ -0,0 +1,206 @@
+//! Utilities for PHRED scaled base qualities, e.g. of FASTQ records.
+//! Conversions between integer quality scores and error probabilities use a precomputed table.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::stats::phred::{expected_errors, qual_to_prob, prob_to_qual, QualityEncoding};
+//!
+//! assert_eq!(qual_to_prob(20), 0.01);
+//! assert_eq!(prob_to_qual(0.001), 30);
+//!
+//! // ASCII encoded qualities as found in FASTQ files (Sanger/Illumina 1.8+, offset 33)
+//! let qual = b"I+5";
+//! let ee = expected_errors(qual, QualityEncoding::Sanger);
+//! assert!((ee - (0.0001 + 0.1 + 0.01)).abs() < 1e-9);
+//! ```
+
+use stats::{LogProb, PHREDProb, Prob};
+
+/// Highest integer quality score supported by the lookup tables.
+pub const MAX_QUAL: u8 = 93;
+
+lazy_static! {
+    static ref ERROR_PROBS: Vec<f64> = (0..=u32::from(MAX_QUAL))
+        .map(|q| 10f64.powf(-(q as f64) / 10.0))
+        .collect();
+}
+
+/// Offset of ASCII encoded quality scores.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QualityEncoding {
+    /// Sanger and Illumina 1.8+ (offset 33).
+    #[default]
+    Sanger,
+    /// Illumina 1.3 to 1.7 (offset 64).
+    Illumina13,
+}
+
+impl QualityEncoding {
+    /// The ASCII offset of quality score 0.
+    pub fn offset(self) -> u8 {
+        match self {
+            QualityEncoding::Sanger => 33,
+            QualityEncoding::Illumina13 => 64,
+        }
+    }
+
+    /// Decode an ASCII character into an integer quality score.
+    /// Panics if the character is below the offset.
+    pub fn decode(self, c: u8) -> u8 {
+        c.checked_sub(self.offset())
+            .expect("Invalid quality character for the given encoding.")
+    }
+
+    /// Encode an integer quality score as ASCII character.
+    pub fn encode(self, q: u8) -> u8 {
+        q.min(MAX_QUAL) + self.offset()
+    }
+}
+
+/// Error probability of the given integer quality score.
+/// Scores above `MAX_QUAL` are treated like `MAX_QUAL`.
+#[inline]
+pub fn qual_to_prob(q: u8) -> f64 {
+    ERROR_PROBS[q.min(MAX_QUAL) as usize]
+}
+
+/// Nearest integer quality score of the given error probability, capped at `MAX_QUAL`.
+pub fn prob_to_qual(p: f64) -> u8 {
+    assert!(
+        (0.0..=1.0).contains(&p),
+        "Expecting probability between 0 and 1."
+    );
+    if p == 0.0 {
+        return MAX_QUAL;
+    }
+    let q = (-10.0 * p.log10()).round();
+    if q >= f64::from(MAX_QUAL) {
+        MAX_QUAL
+    } else {
+        q as u8
+    }
+}
+
+/// Error probability of an ASCII encoded quality character.
+#[inline]
+pub fn ascii_to_prob(c: u8, encoding: QualityEncoding) -> f64 {
+    qual_to_prob(encoding.decode(c))
+}
+
+/// Error probability of the given quality score as `LogProb`.
+pub fn qual_to_logprob(q: u8) -> LogProb {
+    LogProb::from(PHREDProb(f64::from(q)))
+}
+
+/// Expected number of errors of a read with the given ASCII encoded qualities, i.e. the
+/// sum of the error probabilities.
+pub fn expected_errors(qual: &[u8], encoding: QualityEncoding) -> f64 {
+    qual.iter().map(|&c| ascii_to_prob(c, encoding)).sum()
+}
+
+/// Length of the longest prefix of a read with the given ASCII encoded qualities whose expected
+/// number of errors does not exceed `max_ee` (as `-fastq_truncee` in USEARCH).
+pub fn max_ee_prefix_len(qual: &[u8], max_ee: f64, encoding: QualityEncoding) -> usize {
+    let mut ee = 0.0;
+    for (i, &c) in qual.iter().enumerate() {
+        ee += ascii_to_prob(c, encoding);
+        if ee > max_ee {
+            return i;
+        }
+    }
+    qual.len()
+}
+
+/// Mean error probability of a read with the given ASCII encoded qualities.
+/// Note that this is different from the error probability of the mean quality score.
+pub fn mean_error_prob(qual: &[u8], encoding: QualityEncoding) -> f64 {
+    if qual.is_empty() {
+        0.0
+    } else {
+        expected_errors(qual, encoding) / qual.len() as f64
+    }
+}
+
+/// Posterior error probability of a base that was observed twice independently with the same
+/// call, given the error probabilities of both observations.
+/// Errors are assumed to be uniformly distributed over the three other bases.
+pub fn combine_agreeing(p1: Prob, p2: Prob) -> Prob {
+    let (p1, p2) = (*p1, *p2);
+    let correct = (1.0 - p1) * (1.0 - p2);
+    // both wrong, but wrong in the same way
+    let wrong = p1 * p2 / 3.0;
+    Prob(wrong / (correct + wrong))
+}
+
+/// Posterior error probability of the call with error probability `p_winner`, given that an
+/// independent observation of the same base with error probability `p_loser` yielded a
+/// different call. Usually, the winner is the call with the lower error probability.
+/// Errors are assumed to be uniformly distributed over the three other bases.
+pub fn combine_disagreeing(p_winner: Prob, p_loser: Prob) -> Prob {
+    let (pw, pl) = (*p_winner, *p_loser);
+    let winner_correct = (1.0 - pw) * pl / 3.0;
+    let loser_correct = (1.0 - pl) * pw / 3.0;
+    // both wrong, with the true base being one of the remaining two
+    let both_wrong = 2.0 * (pw / 3.0) * (pl / 3.0);
+    Prob(1.0 - winner_correct / (winner_correct + loser_correct + both_wrong))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        for q in 0..=MAX_QUAL {
+            assert_eq!(prob_to_qual(qual_to_prob(q)), q);
+        }
+        assert_relative_eq!(qual_to_prob(10), 0.1);
+        assert_eq!(qual_to_prob(200), qual_to_prob(MAX_QUAL));
+        assert_eq!(prob_to_qual(0.0), MAX_QUAL);
+        assert_eq!(prob_to_qual(1.0), 0);
+        assert_relative_eq!(*qual_to_logprob(30), 0.001f64.ln(), epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(QualityEncoding::Sanger.decode(b'I'), 40);
+        assert_eq!(QualityEncoding::Illumina13.decode(b'h'), 40);
+        assert_eq!(QualityEncoding::Sanger.encode(40), b'I');
+        assert_eq!(QualityEncoding::Sanger.encode(120), b'~');
+        assert_relative_eq!(ascii_to_prob(b'5', QualityEncoding::Sanger), 0.01);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_encoding() {
+        QualityEncoding::Illumina13.decode(b'5');
+    }
+
+    #[test]
+    fn test_expected_errors() {
+        let qual = b"++++++++++";
+        assert_relative_eq!(expected_errors(qual, QualityEncoding::Sanger), 1.0);
+        assert_relative_eq!(mean_error_prob(qual, QualityEncoding::Sanger), 0.1);
+        assert_eq!(mean_error_prob(b"", QualityEncoding::Sanger), 0.0);
+    }
+
+    #[test]
+    fn test_max_ee_prefix_len() {
+        let qual = b"IIII+++II";
+        assert_eq!(max_ee_prefix_len(qual, 0.25, QualityEncoding::Sanger), 6);
+        assert_eq!(max_ee_prefix_len(qual, 0.0, QualityEncoding::Sanger), 0);
+        assert_eq!(max_ee_prefix_len(qual, 1.0, QualityEncoding::Sanger), 9);
+    }
+
+    #[test]
+    fn test_combine() {
+        let p = combine_agreeing(Prob(0.01), Prob(0.01));
+        assert!(*p < 1e-4);
+        let p = combine_disagreeing(Prob(0.001), Prob(0.1));
+        assert!(*p > 0.001 && *p < 0.1);
+        // equally good disagreeing calls leave a coin flip
+        let p = combine_disagreeing(Prob(0.01), Prob(0.01));
+        assert!(*p > 0.49 && *p < 0.51);
+    }
+}
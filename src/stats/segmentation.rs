@@ -0,0 +1,348 @@
+//! Changepoint detection in numeric tracks, e.g. read depth per genomic window for copy number
+//! calling, with the PELT algorithm (Killick et al. 2012).
+//!
+//! PELT finds the segmentation minimizing the sum of segment costs plus a penalty per segment,
+//! exactly, while pruning candidate changepoints that can never be optimal. Costs are twice the
+//! negative log-likelihood of a segment under one of the following models:
+//!
+//! * `Cost::NormalMean`: normal distribution with a per-segment mean and a common variance,
+//!   which is estimated robustly from the differences of consecutive values,
+//! * `Cost::NormalMeanVar`: normal distribution with a per-segment mean and variance,
+//! * `Cost::Poisson`: Poisson distribution with a per-segment rate, e.g. for raw read counts.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::stats::segmentation::{Cost, Pelt, Penalty};
+//!
+//! let mut depth = vec![30.0; 50];
+//! depth.extend(vec![45.0; 20]);
+//! depth.extend(vec![30.0; 30]);
+//! for (i, d) in depth.iter_mut().enumerate() {
+//!     *d += [-2.0, 1.0, 0.5, -1.0, 1.5][i % 5];
+//! }
+//!
+//! let segments = Pelt::new(Cost::NormalMean, Penalty::Bic).segment(&depth);
+//! assert_eq!(segments.len(), 3);
+//! assert_eq!((segments[1].start, segments[1].end), (50, 70));
+//! assert!((segments[1].mean - 45.0).abs() < 0.5);
+//! ```
+
+use std::f64;
+
+/// Scale factor turning the median absolute deviation into a consistent estimate of the
+/// standard deviation of a normal distribution.
+const MAD_SCALE: f64 = 1.4826;
+
+/// Smallest variance used in cost functions, avoiding infinite likelihoods of constant segments.
+const MIN_VARIANCE: f64 = 1e-8;
+
+/// Segment cost models.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cost {
+    NormalMean,
+    NormalMeanVar,
+    Poisson,
+}
+
+impl Cost {
+    /// Number of parameters estimated per segment.
+    fn params(self) -> usize {
+        match self {
+            Cost::NormalMeanVar => 2,
+            Cost::NormalMean | Cost::Poisson => 1,
+        }
+    }
+}
+
+/// Penalty added for each segment.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Penalty {
+    /// Bayesian information criterion, i.e. `(p + 1) ln(n)` for `p` parameters per segment and
+    /// `n` values.
+    Bic,
+    /// The given penalty.
+    Manual(f64),
+}
+
+/// A segment `start..end` of a track (indices of values, end exclusive) and its mean.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start: usize,
+    pub end: usize,
+    pub mean: f64,
+}
+
+impl Segment {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Median of the given values, which are sorted in place.
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).expect("Expecting values without NaN."));
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.0
+    }
+}
+
+/// Robust estimate of the noise variance of a piecewise constant track, from the median
+/// absolute deviation of the differences of consecutive values.
+pub fn noise_variance(data: &[f64]) -> f64 {
+    if data.len() < 3 {
+        return 1.0;
+    }
+    let mut diffs: Vec<_> = data.windows(2).map(|w| w[1] - w[0]).collect();
+    let center = median(&mut diffs);
+    let mut deviations: Vec<_> = diffs.iter().map(|d| (d - center).abs()).collect();
+    let sd = median(&mut deviations) * MAD_SCALE / f64::consts::SQRT_2;
+    (sd * sd).max(MIN_VARIANCE)
+}
+
+/// Prefix sums for constant time segment costs.
+struct CostFunction {
+    cost: Cost,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+    variance: f64,
+}
+
+impl CostFunction {
+    fn new(cost: Cost, data: &[f64]) -> Self {
+        let mut sum = Vec::with_capacity(data.len() + 1);
+        let mut sum_sq = Vec::with_capacity(data.len() + 1);
+        let (mut s, mut s2) = (0.0, 0.0);
+        sum.push(s);
+        sum_sq.push(s2);
+        for &x in data {
+            if cost == Cost::Poisson {
+                assert!(x >= 0.0, "Expecting non-negative values for Poisson cost.");
+            }
+            s += x;
+            s2 += x * x;
+            sum.push(s);
+            sum_sq.push(s2);
+        }
+        let variance = if cost == Cost::NormalMean {
+            noise_variance(data)
+        } else {
+            1.0
+        };
+        CostFunction {
+            cost,
+            sum,
+            sum_sq,
+            variance,
+        }
+    }
+
+    /// Cost of the segment `start..end`, up to a constant per value.
+    fn cost(&self, start: usize, end: usize) -> f64 {
+        let n = (end - start) as f64;
+        let s = self.sum[end] - self.sum[start];
+        let s2 = self.sum_sq[end] - self.sum_sq[start];
+        let rss = (s2 - s * s / n).max(0.0);
+        match self.cost {
+            Cost::NormalMean => rss / self.variance,
+            Cost::NormalMeanVar => n * (rss / n).max(MIN_VARIANCE).ln(),
+            Cost::Poisson if s > 0.0 => -2.0 * s * ((s / n).ln() - 1.0),
+            Cost::Poisson => 0.0,
+        }
+    }
+}
+
+/// Pruned exact linear time (PELT) changepoint detection.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pelt {
+    cost: Cost,
+    penalty: Penalty,
+    min_size: usize,
+}
+
+impl Pelt {
+    /// Create a new instance with the given cost model and penalty, and a minimal segment size
+    /// of 2 values (1 for the Poisson and normal mean models).
+    pub fn new(cost: Cost, penalty: Penalty) -> Self {
+        Pelt {
+            cost,
+            penalty,
+            min_size: cost.params(),
+        }
+    }
+
+    /// Set the minimal number of values per segment.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        assert!(min_size > 0, "Expecting positive minimal segment size.");
+        self.min_size = min_size;
+        self
+    }
+
+    /// Positions of the changepoints, i.e. the start indices of all segments but the first.
+    pub fn changepoints(&self, data: &[f64]) -> Vec<usize> {
+        let n = data.len();
+        if n < 2 * self.min_size {
+            return Vec::new();
+        }
+        let cost = CostFunction::new(self.cost, data);
+        let penalty = match self.penalty {
+            Penalty::Bic => (self.cost.params() + 1) as f64 * (n as f64).ln(),
+            Penalty::Manual(penalty) => penalty,
+        };
+
+        // optimal[t]: minimal penalized cost of data[..t], last[t]: start of its last segment
+        let mut optimal = vec![f64::INFINITY; n + 1];
+        let mut last = vec![0; n + 1];
+        optimal[0] = -penalty;
+        let mut candidates = vec![0];
+        for t in self.min_size..=n {
+            let mut costs = Vec::with_capacity(candidates.len());
+            for &s in &candidates {
+                let c = if t - s >= self.min_size {
+                    optimal[s] + cost.cost(s, t)
+                } else {
+                    f64::INFINITY
+                };
+                costs.push(c);
+                if c + penalty < optimal[t] {
+                    optimal[t] = c + penalty;
+                    last[t] = s;
+                }
+            }
+            // prune candidates that cannot be the last changepoint of any later optimum
+            let mut costs = costs.into_iter();
+            candidates.retain(|&s| {
+                let c = costs.next().unwrap();
+                t - s < self.min_size || c <= optimal[t]
+            });
+            if t < n {
+                candidates.push(t + 1 - self.min_size);
+            }
+        }
+
+        let mut changepoints = Vec::new();
+        let mut t = last[n];
+        while t > 0 {
+            changepoints.push(t);
+            t = last[t];
+        }
+        changepoints.reverse();
+        changepoints
+    }
+
+    /// Segment the given track.
+    pub fn segment(&self, data: &[f64]) -> Vec<Segment> {
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let mut bounds = vec![0];
+        bounds.extend(self.changepoints(data));
+        bounds.push(data.len());
+        bounds
+            .windows(2)
+            .map(|w| Segment {
+                start: w[0],
+                end: w[1],
+                mean: data[w[0]..w[1]].iter().sum::<f64>() / (w[1] - w[0]) as f64,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::Xoshiro256;
+
+    /// Approximately normal noise with standard deviation 1.
+    fn noise(rng: &mut Xoshiro256) -> f64 {
+        (0..12).map(|_| rng.next_f64()).sum::<f64>() - 6.0
+    }
+
+    #[test]
+    fn test_noise_variance() {
+        let mut rng = Xoshiro256::new(1);
+        let mut data: Vec<_> = (0..2000).map(|_| 2.0 * noise(&mut rng)).collect();
+        for x in &mut data[1000..] {
+            *x += 100.0;
+        }
+        assert!((noise_variance(&data) - 4.0).abs() < 0.5);
+        assert_eq!(noise_variance(&[1.0; 10]), MIN_VARIANCE);
+    }
+
+    #[test]
+    fn test_normal_mean() {
+        let mut rng = Xoshiro256::new(2);
+        let means = [(0, 2.0), (300, 3.0), (350, 1.0), (600, 2.0)];
+        let data: Vec<_> = (0..1000)
+            .map(|i| {
+                let mean = means
+                    .iter()
+                    .rev()
+                    .find(|&&(start, _)| i >= start)
+                    .unwrap()
+                    .1;
+                mean + 0.3 * noise(&mut rng)
+            })
+            .collect();
+        let segments = Pelt::new(Cost::NormalMean, Penalty::Bic).segment(&data);
+        assert_eq!(segments.len(), 4);
+        for (segment, &(start, mean)) in segments.iter().zip(&means) {
+            assert!((segment.start as i64 - start as i64).abs() <= 2);
+            assert!((segment.mean - mean).abs() < 0.1);
+        }
+        assert_eq!(segments.last().unwrap().end, 1000);
+    }
+
+    #[test]
+    fn test_normal_mean_var() {
+        let mut rng = Xoshiro256::new(3);
+        let data: Vec<_> = (0..400)
+            .map(|i| if i < 200 { 0.1 } else { 2.0 } * noise(&mut rng))
+            .collect();
+        let pelt = Pelt::new(Cost::NormalMeanVar, Penalty::Bic);
+        let changepoints = pelt.changepoints(&data);
+        assert_eq!(changepoints.len(), 1);
+        assert!((changepoints[0] as i64 - 200).abs() <= 5);
+    }
+
+    #[test]
+    fn test_poisson() {
+        let data: Vec<_> = (0..100)
+            .map(|i| match (i / 20, i % 4) {
+                (2, 0) => 20.0,
+                (2, _) => 15.0,
+                (_, 0) => 6.0,
+                (_, 1) => 4.0,
+                _ => 5.0,
+            })
+            .collect();
+        let pelt = Pelt::new(Cost::Poisson, Penalty::Bic);
+        assert_eq!(pelt.changepoints(&data), [40, 60]);
+        assert!(pelt.changepoints(&[0.0; 10]).is_empty());
+    }
+
+    #[test]
+    fn test_min_size_and_penalty() {
+        let data = [0.0, 0.0, 0.0, 5.0, 5.0, 0.0, 0.0, 0.0, 0.0];
+        let pelt = Pelt::new(Cost::NormalMean, Penalty::Manual(1.0));
+        assert_eq!(pelt.changepoints(&data), [3, 5]);
+        assert_eq!(pelt.min_size(2).changepoints(&data), [3, 5]);
+        let segments = pelt.min_size(3).segment(&data);
+        assert_eq!(segments.len(), 3);
+        assert!(segments.iter().all(|s| s.len() >= 3));
+        assert!(pelt.min_size(5).changepoints(&data).is_empty());
+        assert!(Pelt::new(Cost::NormalMean, Penalty::Manual(1e12))
+            .changepoints(&data)
+            .is_empty());
+        assert!(pelt.segment(&[]).is_empty());
+        assert_eq!(pelt.segment(&[1.0]).len(), 1);
+    }
+}
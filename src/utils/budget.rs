@@ -0,0 +1,69 @@
+//! Memory budgets for constructors of large data structures. A constructor with several
+//! strategies uses the fastest one whose estimated peak memory fits into the budget, e.g.
+//! `IndexBuilder` indexes the sequences of a text in groups and merges the indexes, instead of
+//! building the suffix array of the whole text. Estimates are approximate, so the budget should
+//! leave some room below the actual memory limit.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::utils::MemoryBudget;
+//!
+//! let budget = MemoryBudget::Bytes(1 << 30);
+//! assert!(budget.fits(1 << 20));
+//! assert_eq!(budget.remaining(1 << 29), MemoryBudget::Bytes(1 << 29));
+//! assert!(MemoryBudget::Unlimited.fits(usize::MAX));
+//! ```
+
+/// An upper bound on the memory (in bytes) used by a constructor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemoryBudget {
+    #[default]
+    Unlimited,
+    Bytes(usize),
+}
+
+impl MemoryBudget {
+    /// Check whether the given number of bytes fits into the budget.
+    pub fn fits(&self, bytes: usize) -> bool {
+        match *self {
+            MemoryBudget::Unlimited => true,
+            MemoryBudget::Bytes(limit) => bytes <= limit,
+        }
+    }
+
+    /// The budget in bytes, or `None` if unlimited.
+    pub fn limit(&self) -> Option<usize> {
+        match *self {
+            MemoryBudget::Unlimited => None,
+            MemoryBudget::Bytes(limit) => Some(limit),
+        }
+    }
+
+    /// The budget left after using the given number of bytes.
+    pub fn remaining(&self, used: usize) -> MemoryBudget {
+        match *self {
+            MemoryBudget::Unlimited => MemoryBudget::Unlimited,
+            MemoryBudget::Bytes(limit) => MemoryBudget::Bytes(limit.saturating_sub(used)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_budget() {
+        let budget = MemoryBudget::Bytes(100);
+        assert!(budget.fits(100));
+        assert!(!budget.fits(101));
+        assert_eq!(budget.limit(), Some(100));
+        assert_eq!(budget.remaining(150), MemoryBudget::Bytes(0));
+        assert_eq!(MemoryBudget::default().limit(), None);
+        assert_eq!(
+            MemoryBudget::default().remaining(10),
+            MemoryBudget::Unlimited
+        );
+    }
+}
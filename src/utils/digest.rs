@@ -0,0 +1,437 @@
+//! Minimal streaming implementations of the MD5, SHA-1 and SHA-512 message digests, as needed
+//! for sequence checksums. These are not meant for cryptographic purposes.
+
+/// Feed `data` into fixed size blocks, calling `compress` for every complete block.
+fn update_blocks<F: FnMut(&[u8])>(
+    buffer: &mut Vec<u8>,
+    block_size: usize,
+    data: &[u8],
+    mut compress: F,
+) {
+    let mut data = data;
+    if !buffer.is_empty() {
+        let n = (block_size - buffer.len()).min(data.len());
+        buffer.extend_from_slice(&data[..n]);
+        data = &data[n..];
+        if buffer.len() == block_size {
+            compress(buffer);
+            buffer.clear();
+        }
+    }
+    let mut blocks = data.chunks_exact(block_size);
+    for block in &mut blocks {
+        compress(block);
+    }
+    buffer.extend_from_slice(blocks.remainder());
+}
+
+/// Padding of a message of the given length (bytes): a one bit, zeros and the message length in
+/// bits, encoded as `len_bytes` bytes, filling the last block.
+fn padding(len: u128, block_size: usize, len_bytes: usize, little_endian: bool) -> Vec<u8> {
+    let rem = (len % block_size as u128) as usize;
+    let zeros = (2 * block_size - rem - 1 - len_bytes) % block_size;
+    let mut pad = vec![0x80];
+    pad.resize(1 + zeros, 0);
+    let bits = (len * 8).to_be_bytes();
+    let bits = &bits[bits.len() - len_bytes..];
+    if little_endian {
+        pad.extend(bits.iter().rev());
+    } else {
+        pad.extend_from_slice(bits);
+    }
+    pad
+}
+
+#[rustfmt::skip]
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee,
+    0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+    0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa,
+    0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+    0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05,
+    0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039,
+    0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+    0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+#[rustfmt::skip]
+const MD5_S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// The MD5 message digest (RFC 1321).
+#[derive(Clone, Debug)]
+pub struct Md5 {
+    state: [u32; 4],
+    buffer: Vec<u8>,
+    len: u128,
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Md5 {
+            state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476],
+            buffer: Vec::with_capacity(64),
+            len: 0,
+        }
+    }
+}
+
+impl Md5 {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Digest of the given data.
+    pub fn digest(data: &[u8]) -> [u8; 16] {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        md5.finalize()
+    }
+
+    fn compress(state: &mut [u32; 4], block: &[u8]) {
+        let mut m = [0u32; 16];
+        for (w, chunk) in m.iter_mut().zip(block.chunks_exact(4)) {
+            *w = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = *state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(MD5_S[i]));
+        }
+        for (s, v) in state.iter_mut().zip(&[a, b, c, d]) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.len += data.len() as u128;
+        let state = &mut self.state;
+        update_blocks(&mut self.buffer, 64, data, |block| {
+            Md5::compress(state, block)
+        });
+    }
+
+    pub fn finalize(mut self) -> [u8; 16] {
+        let pad = padding(self.len, 64, 8, true);
+        self.update(&pad);
+        let mut digest = [0; 16];
+        for (chunk, s) in digest.chunks_exact_mut(4).zip(&self.state) {
+            chunk.copy_from_slice(&s.to_le_bytes());
+        }
+        digest
+    }
+}
+
+/// The SHA-1 message digest (FIPS 180-4).
+#[derive(Clone, Debug)]
+pub struct Sha1 {
+    state: [u32; 5],
+    buffer: Vec<u8>,
+    len: u128,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Sha1 {
+            state: [
+                0x6745_2301,
+                0xefcd_ab89,
+                0x98ba_dcfe,
+                0x1032_5476,
+                0xc3d2_e1f0,
+            ],
+            buffer: Vec::with_capacity(64),
+            len: 0,
+        }
+    }
+}
+
+impl Sha1 {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Digest of the given data.
+    pub fn digest(data: &[u8]) -> [u8; 20] {
+        let mut sha1 = Sha1::new();
+        sha1.update(data);
+        sha1.finalize()
+    }
+
+    fn compress(state: &mut [u32; 5], block: &[u8]) {
+        let mut w = [0u32; 80];
+        for (w, chunk) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *w = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = *state;
+        for (i, &w) in w.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a82_7999),
+                1 => (b ^ c ^ d, 0x6ed9_eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+        for (s, v) in state.iter_mut().zip(&[a, b, c, d, e]) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.len += data.len() as u128;
+        let state = &mut self.state;
+        update_blocks(&mut self.buffer, 64, data, |block| {
+            Sha1::compress(state, block)
+        });
+    }
+
+    pub fn finalize(mut self) -> [u8; 20] {
+        let pad = padding(self.len, 64, 8, false);
+        self.update(&pad);
+        let mut digest = [0; 20];
+        for (chunk, s) in digest.chunks_exact_mut(4).zip(&self.state) {
+            chunk.copy_from_slice(&s.to_be_bytes());
+        }
+        digest
+    }
+}
+
+#[rustfmt::skip]
+const SHA512_K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019,
+    0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe,
+    0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210,
+    0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725,
+    0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001,
+    0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910,
+    0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53,
+    0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60,
+    0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6,
+    0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+#[rustfmt::skip]
+const SHA512_H: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+/// The SHA-512 message digest (FIPS 180-4).
+#[derive(Clone, Debug)]
+pub struct Sha512 {
+    state: [u64; 8],
+    buffer: Vec<u8>,
+    len: u128,
+}
+
+impl Default for Sha512 {
+    fn default() -> Self {
+        Sha512 {
+            state: SHA512_H,
+            buffer: Vec::with_capacity(128),
+            len: 0,
+        }
+    }
+}
+
+impl Sha512 {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Digest of the given data.
+    pub fn digest(data: &[u8]) -> [u8; 64] {
+        let mut sha512 = Sha512::new();
+        sha512.update(data);
+        sha512.finalize()
+    }
+
+    fn compress(state: &mut [u64; 8], block: &[u8]) {
+        let mut w = [0u64; 80];
+        for (w, chunk) in w.iter_mut().zip(block.chunks_exact(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            *w = u64::from_be_bytes(bytes);
+        }
+        for i in 16..80 {
+            let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+            let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+        for (&k, &w) in SHA512_K.iter().zip(w.iter()) {
+            let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(k)
+                .wrapping_add(w);
+            let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in state.iter_mut().zip(&[a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.len += data.len() as u128;
+        let state = &mut self.state;
+        update_blocks(&mut self.buffer, 128, data, |block| {
+            Sha512::compress(state, block)
+        });
+    }
+
+    pub fn finalize(mut self) -> [u8; 64] {
+        let pad = padding(self.len, 128, 16, false);
+        self.update(&pad);
+        let mut digest = [0; 64];
+        for (chunk, s) in digest.chunks_exact_mut(8).zip(&self.state) {
+            chunk.copy_from_slice(&s.to_be_bytes());
+        }
+        digest
+    }
+}
+
+/// Lower case hexadecimal representation of the given bytes.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md5() {
+        assert_eq!(
+            to_hex(&Md5::digest(b"")),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert_eq!(
+            to_hex(&Md5::digest(b"abc")),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            to_hex(&Md5::digest(b"The quick brown fox jumps over the lazy dog")),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+    }
+
+    #[test]
+    fn test_sha1() {
+        assert_eq!(
+            to_hex(&Sha1::digest(b"")),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert_eq!(
+            to_hex(&Sha1::digest(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn test_sha512() {
+        assert_eq!(
+            to_hex(&Sha512::digest(b"abc")),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 % 251) as u8).collect();
+        for &chunk_size in &[1, 55, 64, 100, 128, 999] {
+            let (mut md5, mut sha1, mut sha512) = (Md5::new(), Sha1::new(), Sha512::new());
+            for chunk in data.chunks(chunk_size) {
+                md5.update(chunk);
+                sha1.update(chunk);
+                sha512.update(chunk);
+            }
+            assert_eq!(md5.finalize(), Md5::digest(&data));
+            assert_eq!(sha1.finalize(), Sha1::digest(&data));
+            assert_eq!(sha512.finalize()[..], Sha512::digest(&data)[..]);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Fast, non-cryptographic hashing tuned for short keys like k-mers, packed k-mer integers or
+//! diagonal indices. The standard SipHash hasher protects against hash flooding, which is
+//! rarely a concern for genomic data but costs a lot of time in hot counting loops.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::utils::{KmerHashMap, KmerHashSet};
+//!
+//! let text = b"ACGTACGTAC";
+//! let mut counts: KmerHashMap<&[u8], usize> = KmerHashMap::default();
+//! for kmer in text.windows(4) {
+//!     *counts.entry(kmer).or_insert(0) += 1;
+//! }
+//! assert_eq!(counts[&b"ACGT"[..]], 2);
+//!
+//! let distinct: KmerHashSet<&[u8]> = text.windows(4).collect();
+//! assert_eq!(distinct.len(), 4);
+//! ```
+//!
+//! Modules hashing packed k-mers (e.g. MinHash sketches) are generic over the `KmerHasher`
+//! trait, such that hash functions can be swapped consistently. Besides `ShortKeyHasher`, the
+//! MurmurHash3 finalizer, the invertible hash of minimap2 and ntHash are provided, and closures
+//! can be used for custom hashes.
+//!
+//! ```
+//! use bio::utils::{InvertibleKmerHasher, KmerHasher};
+//!
+//! let kmer = 0b00_01_10_11; // ACGT
+//! let hash = InvertibleKmerHasher.hash_kmer(kmer, 4);
+//! assert!(hash < 1 << 8);
+//! assert_eq!(InvertibleKmerHasher.invert(hash, 4), kmer);
+//!
+//! let custom = |kmer: u64, _k: usize| kmer.reverse_bits();
+//! assert_eq!(custom.hash_kmer(1, 4), 1 << 63);
+//! ```
+
+use core::hash::{BuildHasherDefault, Hasher};
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// Finalization step of MurmurHash3, mixing all input bits into the low bits used by hash tables.
+#[inline]
+fn fmix64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    h
+}
+
+/// A hasher for short keys. Bytes are consumed in words of eight with a multiply-rotate step
+/// (as in FxHash), and the state is finalized with the MurmurHash3 mixer, such that low bits
+/// are well distributed even for keys that differ in few positions.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShortKeyHasher {
+    state: u64,
+}
+
+impl ShortKeyHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.state = (self.state.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for ShortKeyHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0u8; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            // include the length to distinguish trailing zero bytes
+            self.add(u64::from_le_bytes(word) ^ ((rest.len() as u64) << 59));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        fmix64(self.state)
+    }
+}
+
+/// Builder for `ShortKeyHasher`, to be used as hash state of maps and sets.
+pub type BuildShortKeyHasher = BuildHasherDefault<ShortKeyHasher>;
+
+/// A `HashMap` using `ShortKeyHasher`, for k-mers and other short keys.
+#[cfg(feature = "std")]
+pub type KmerHashMap<K, V> = HashMap<K, V, BuildShortKeyHasher>;
+
+/// A `HashSet` using `ShortKeyHasher`, for k-mers and other short keys.
+#[cfg(feature = "std")]
+pub type KmerHashSet<K> = HashSet<K, BuildShortKeyHasher>;
+
+/// A hash function of k-mers packed with two bits per base (`A` = 0, `C` = 1, `G` = 2, `T` = 3,
+/// first base in the highest bits), as produced by `alphabets::dna::canonical_kmers`.
+pub trait KmerHasher {
+    /// Hash of the given packed k-mer of length `k`.
+    fn hash_kmer(&self, kmer: u64, k: usize) -> u64;
+}
+
+impl<F: Fn(u64, usize) -> u64> KmerHasher for F {
+    fn hash_kmer(&self, kmer: u64, k: usize) -> u64 {
+        self(kmer, k)
+    }
+}
+
+impl KmerHasher for ShortKeyHasher {
+    /// Hash of the k-mer with a copy of this hasher.
+    fn hash_kmer(&self, kmer: u64, _k: usize) -> u64 {
+        let mut hasher = *self;
+        hasher.write_u64(kmer);
+        hasher.finish()
+    }
+}
+
+/// The finalization step of MurmurHash3, a bijection on 64 bit values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MurmurKmerHasher;
+
+impl KmerHasher for MurmurKmerHasher {
+    fn hash_kmer(&self, kmer: u64, _k: usize) -> u64 {
+        fmix64(kmer)
+    }
+}
+
+/// The invertible integer hash of minimap2 (Li, 2018), a bijection on the `2k` bit values, such
+/// that k-mers can be restored from their hashes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InvertibleKmerHasher;
+
+fn kmer_mask(k: usize) -> u64 {
+    assert!(k > 0 && k <= 32, "Expecting k between 1 and 32.");
+    if k == 32 {
+        !0
+    } else {
+        (1 << (2 * k)) - 1
+    }
+}
+
+impl InvertibleKmerHasher {
+    /// The k-mer with the given hash.
+    pub fn invert(&self, hash: u64, k: usize) -> u64 {
+        let mask = kmer_mask(k);
+        let mut key = hash;
+        let tmp = key.wrapping_sub(key << 31);
+        key = key.wrapping_sub(tmp << 31) & mask;
+        let tmp = key ^ key >> 28;
+        key ^= tmp >> 28;
+        // multiplicative inverse of 21
+        key = key.wrapping_mul(0xcf3c_f3cf_3cf3_cf3d) & mask;
+        let mut tmp = key ^ key >> 14;
+        tmp = key ^ tmp >> 14;
+        tmp = key ^ tmp >> 14;
+        key ^= tmp >> 14;
+        // multiplicative inverse of 265
+        key = key.wrapping_mul(0xd38f_f08b_1c03_dd39) & mask;
+        let tmp = key ^ key >> 24;
+        key ^= tmp >> 24;
+        let mut tmp = !key;
+        for _ in 0..3 {
+            tmp = !key.wrapping_sub(tmp << 21);
+        }
+        !key.wrapping_sub(tmp << 21) & mask
+    }
+}
+
+impl KmerHasher for InvertibleKmerHasher {
+    fn hash_kmer(&self, kmer: u64, k: usize) -> u64 {
+        let mask = kmer_mask(k);
+        let mut key = (!kmer).wrapping_add(kmer << 21) & mask;
+        key ^= key >> 24;
+        key = key.wrapping_mul(265) & mask;
+        key ^= key >> 14;
+        key = key.wrapping_mul(21) & mask;
+        key ^= key >> 28;
+        key.wrapping_add(key << 31) & mask
+    }
+}
+
+/// The forward strand hash of ntHash (Mohamadi et al., 2016), which XORs rotated random seeds
+/// of the bases.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NtHasher;
+
+/// Seeds of ntHash for the bases `A`, `C`, `G` and `T`.
+const NT_SEEDS: [u64; 4] = [
+    0x3c8b_fbb3_95c6_0474,
+    0x3193_c185_62a0_2b4c,
+    0x2032_3ed0_8257_2324,
+    0x2955_49f5_4be2_4456,
+];
+
+impl KmerHasher for NtHasher {
+    fn hash_kmer(&self, kmer: u64, k: usize) -> u64 {
+        (0..k).fold(0, |hash, i| {
+            let base = (kmer >> (2 * (k - 1 - i))) & 3;
+            hash.rotate_left(1) ^ NT_SEEDS[base as usize]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::{BuildHasher, Hash};
+
+    fn hash<T: Hash + ?Sized>(t: &T) -> u64 {
+        BuildShortKeyHasher::default().hash_one(t)
+    }
+
+    #[test]
+    fn test_distinct_kmers() {
+        let text = b"ACGTTGCAACGGTACCATGGATCCAGGTTTAAACCCGGGTTTAA";
+        let mut hashes = KmerHashSet::default();
+        let mut kmers = KmerHashSet::default();
+        for kmer in text.windows(9) {
+            kmers.insert(kmer);
+            hashes.insert(hash(&kmer));
+        }
+        assert_eq!(hashes.len(), kmers.len());
+    }
+
+    #[test]
+    fn test_trailing_zeros() {
+        assert_ne!(hash(&[1u8, 0][..]), hash(&[1u8][..]));
+        assert_eq!(hash(&42u64), hash(&42u64));
+    }
+
+    #[test]
+    fn test_invertible_kmer_hasher() {
+        let mut rng = ::utils::Xoshiro256::new(7);
+        for &k in &[1, 5, 15, 31, 32] {
+            let mask = kmer_mask(k);
+            for _ in 0..100 {
+                let kmer = rng.next_u64() & mask;
+                let hash = InvertibleKmerHasher.hash_kmer(kmer, k);
+                assert!(hash <= mask);
+                assert_eq!(InvertibleKmerHasher.invert(hash, k), kmer);
+            }
+        }
+        // a bijection on the 2-mers
+        let hashes: KmerHashSet<u64> = (0..16)
+            .map(|kmer| InvertibleKmerHasher.hash_kmer(kmer, 2))
+            .collect();
+        assert_eq!(hashes.len(), 16);
+    }
+
+    #[test]
+    fn test_nthasher() {
+        // h(ACG) = rol^2(seed(A)) ^ rol(seed(C)) ^ seed(G)
+        let expected = NT_SEEDS[0].rotate_left(2) ^ NT_SEEDS[1].rotate_left(1) ^ NT_SEEDS[2];
+        assert_eq!(NtHasher.hash_kmer(0b00_01_10, 3), expected);
+        assert_ne!(NtHasher.hash_kmer(0, 3), NtHasher.hash_kmer(0, 4));
+    }
+
+    #[test]
+    fn test_kmer_hashers() {
+        assert_eq!(ShortKeyHasher::default().hash_kmer(42, 4), hash(&42u64));
+        assert_eq!(MurmurKmerHasher.hash_kmer(42, 4), fmix64(42));
+        assert_eq!((|kmer: u64, k: usize| kmer + k as u64).hash_kmer(42, 4), 46);
+    }
+
+    #[test]
+    fn test_low_bits_spread() {
+        // consecutive packed k-mers should not collide in the low bits of a small table
+        let buckets: KmerHashSet<u64> = (0..64u64).map(|i| hash(&i) & 0xff).collect();
+        assert!(buckets.len() > 40);
+    }
+}
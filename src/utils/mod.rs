@@ -5,15 +5,41 @@
 
 //! Common utilities.
 
+#[cfg(feature = "std")]
 mod fastexp;
+#[cfg(feature = "std")]
 pub use self::fastexp::FastExp;
 
 mod text;
-pub use self::text::{trim_newline, Text, TextSlice};
+pub use self::text::{trim_newline, IndexableText, Text, TextSlice, TextSymbols};
 
+#[cfg(feature = "std")]
 mod interval;
+#[cfg(feature = "std")]
 pub use self::interval::{Interval, IntervalError};
 
+#[cfg(feature = "std")]
+mod digest;
+#[cfg(feature = "std")]
+pub use self::digest::{to_hex, Md5, Sha1, Sha512};
+
+mod budget;
+pub use self::budget::MemoryBudget;
+
+mod sized_int;
+pub use self::sized_int::SizedInt;
+
+mod random;
+pub use self::random::{SeedableRngExt, Xoshiro256};
+
+mod hash;
+pub use self::hash::{
+    BuildShortKeyHasher, InvertibleKmerHasher, KmerHasher, MurmurKmerHasher, NtHasher,
+    ShortKeyHasher,
+};
+#[cfg(feature = "std")]
+pub use self::hash::{KmerHashMap, KmerHashSet};
+
 /// In place implementation of scan over a slice.
 pub fn scan<T: Copy, F: Fn(T, T) -> T>(a: &mut [T], op: F) {
     let mut s = a[0];
@@ -0,0 +1,209 @@
+//! A small, seedable pseudo random number generator (xoshiro256**, Blackman and Vigna 2018),
+//! for reproducible sampling without external dependencies. It is fast and statistically sound,
+//! but not cryptographically secure.
+//!
+//! Stochastic functions of this crate (e.g. `genome::sample`, `genome::enrichment` and
+//! `io::sample`) take an explicit generator implementing `SeedableRngExt`, or a seed, and never
+//! use global or time-based randomness. Their results are reproducible: equal seeds yield equal
+//! results on all platforms, as long as the calls (and the order of draws) are the same.
+//! Changing the sequence generated for a seed is considered a breaking change.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::utils::{SeedableRngExt, Xoshiro256};
+//!
+//! // independent, reproducible streams, e.g. one per chromosome or thread
+//! let mut rngs: Vec<_> = (0..3).map(|chrom| Xoshiro256::from_stream(42, chrom)).collect();
+//! let mut items = [1, 2, 3, 4, 5];
+//! rngs[1].shuffle(&mut items);
+//!
+//! let mut again = [1, 2, 3, 4, 5];
+//! Xoshiro256::from_stream(42, 1).shuffle(&mut again);
+//! assert_eq!(items, again);
+//! ```
+
+/// A seedable generator of uniform random numbers, with helpers for reproducible sampling.
+/// Only `seed_from_u64` and `next_u64` need to be implemented, e.g. for wrapping the
+/// generators of other crates.
+pub trait SeedableRngExt: Sized {
+    /// Create a generator from the given seed. Equal seeds have to yield equal sequences.
+    fn seed_from_u64(seed: u64) -> Self;
+
+    /// Uniform sample from all `u64` values.
+    fn next_u64(&mut self) -> u64;
+
+    /// Create a generator for the given stream of a seed. The sequence of each stream only
+    /// depends on the seed and the stream, regardless of how many numbers other streams draw.
+    fn from_stream(seed: u64, stream: u64) -> Self {
+        // mix the stream into the seed, such that nearby streams yield unrelated seeds
+        let mut z = seed ^ stream.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self::seed_from_u64(z ^ (z >> 31))
+    }
+
+    /// Create a new generator seeded by this one.
+    fn fork(&mut self) -> Self {
+        Self::seed_from_u64(self.next_u64())
+    }
+
+    /// Uniform sample from `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform sample from `0..n` (Lemire's method, without bias). Panics if `n` is zero.
+    fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Expecting positive upper bound.");
+        let threshold = n.wrapping_neg() % n;
+        loop {
+            let m = u128::from(self.next_u64()) * u128::from(n);
+            if (m as u64) >= threshold {
+                return (m >> 64) as u64;
+            }
+        }
+    }
+
+    /// Shuffle the given items uniformly (Fisher-Yates).
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// A uniformly chosen item, `None` if there are none.
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len() as u64) as usize])
+        }
+    }
+}
+
+/// The xoshiro256** generator, seeded via SplitMix64.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Xoshiro256 {
+    state: [u64; 4],
+}
+
+impl Xoshiro256 {
+    /// Create a generator from the given seed. Equal seeds yield equal sequences.
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut state = [0; 4];
+        for s in &mut state {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *s = z ^ (z >> 31);
+        }
+        Xoshiro256 { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform sample from `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        SeedableRngExt::next_f64(self)
+    }
+
+    /// Uniform sample from `0..n` (Lemire's method, without bias). Panics if `n` is zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        SeedableRngExt::below(self, n)
+    }
+}
+
+impl SeedableRngExt for Xoshiro256 {
+    fn seed_from_u64(seed: u64) -> Self {
+        Xoshiro256::new(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        Xoshiro256::next_u64(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = Xoshiro256::new(42);
+        let mut b = Xoshiro256::new(42);
+        let mut c = Xoshiro256::new(43);
+        let xs: Vec<_> = (0..10).map(|_| a.next_u64()).collect();
+        assert_eq!(xs, (0..10).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(xs, (0..10).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_uniform() {
+        let mut rng = Xoshiro256::new(7);
+        let mut counts = [0; 6];
+        for _ in 0..60_000 {
+            counts[rng.below(6) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 9_500 && c < 10_500));
+        let mean = (0..10_000).map(|_| rng.next_f64()).sum::<f64>() / 10_000.0;
+        assert!((mean - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_stable_sequence() {
+        // the sequence of a seed must not change between versions
+        let mut rng = Xoshiro256::new(0);
+        assert_eq!(rng.next_u64(), 0x99ec_5f36_cb75_f2b4);
+    }
+
+    #[test]
+    fn test_streams() {
+        let first = |mut rng: Xoshiro256| (0..4).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        assert_eq!(
+            first(Xoshiro256::from_stream(1, 0)),
+            first(Xoshiro256::from_stream(1, 0))
+        );
+        assert_ne!(
+            first(Xoshiro256::from_stream(1, 0)),
+            first(Xoshiro256::from_stream(1, 1))
+        );
+        assert_ne!(
+            first(Xoshiro256::from_stream(1, 0)),
+            first(Xoshiro256::from_stream(2, 0))
+        );
+        let mut rng = Xoshiro256::new(5);
+        let mut forked = rng.fork();
+        assert_ne!(forked.next_u64(), rng.next_u64());
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut rng = Xoshiro256::new(11);
+        let mut counts = [[0; 3]; 3];
+        for _ in 0..30_000 {
+            let mut items = [0, 1, 2];
+            rng.shuffle(&mut items);
+            for (i, &item) in items.iter().enumerate() {
+                counts[i][item] += 1;
+            }
+        }
+        assert!(counts.iter().flatten().all(|&c| c > 9_500 && c < 10_500));
+        assert_eq!(rng.choose::<u8>(&[]), None);
+        assert_eq!(rng.choose(&[7]), Some(&7));
+    }
+}
@@ -0,0 +1,76 @@
+//! Unsigned integer types for storing text positions. On 64 bit machines, `usize` positions
+//! take twice the memory needed for texts shorter than 4 GB, hence suffix arrays can be stored
+//! with `u32` entries instead.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::utils::SizedInt;
+//!
+//! assert!(u32::fits(1 << 31));
+//! assert!(!u32::fits(1 << 40));
+//! assert_eq!(u32::from_usize(42).to_usize(), 42);
+//! ```
+
+use core::fmt::Debug;
+use core::hash::Hash;
+
+/// An unsigned integer type for text positions.
+pub trait SizedInt: Copy + Ord + Hash + Debug + Default + Send + Sync + 'static {
+    /// The largest representable value.
+    const MAX: usize;
+
+    /// Convert from `usize`. The value must be at most `MAX`, this is only checked in debug
+    /// builds.
+    fn from_usize(value: usize) -> Self;
+
+    /// Convert to `usize`.
+    fn to_usize(self) -> usize;
+
+    /// Check whether the given value is representable.
+    fn fits(value: usize) -> bool {
+        value <= Self::MAX
+    }
+}
+
+macro_rules! impl_sized_int {
+    ($($t:ty),*) => {
+        $(
+            impl SizedInt for $t {
+                const MAX: usize = if (<$t>::MAX as u128) < (usize::MAX as u128) {
+                    <$t>::MAX as usize
+                } else {
+                    usize::MAX
+                };
+
+                #[inline]
+                fn from_usize(value: usize) -> Self {
+                    debug_assert!(Self::fits(value), "Expecting value within the type.");
+                    value as $t
+                }
+
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_sized_int!(u32, u64, usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sized_int() {
+        assert_eq!(<u32 as SizedInt>::MAX, u32::MAX as usize);
+        assert_eq!(<u64 as SizedInt>::MAX, usize::MAX);
+        assert!(usize::fits(usize::MAX));
+        assert!(u32::fits(u32::MAX as usize));
+        assert!(!u32::fits(u32::MAX as usize + 1));
+        assert_eq!(u64::from_usize(7).to_usize(), 7);
+    }
+}
@@ -1,8 +1,96 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
 /// Type alias for an owned text, i.e. ``Vec<u8>``.
 pub type Text = Vec<u8>;
 /// Type alias for a text slice, i.e. ``&[u8]``.
 pub type TextSlice<'a> = &'a [u8];
 
+/// A text that indexes (suffix arrays, BWTs and FM-Indexes) can be built from without copying
+/// it into a `Vec<u8>`, e.g. byte slices, packed sequences (`PackedDnaSeq`) or memory mapped
+/// files (as slices, e.g. `&mmap[..]`).
+pub trait IndexableText {
+    /// Number of symbols.
+    fn len(&self) -> usize;
+
+    /// The symbol at position `i`.
+    fn symbol(&self, i: usize) -> u8;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the symbols in the given range.
+    fn symbols(&self, range: Range<usize>) -> TextSymbols<'_, Self> {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "Expecting range within the text."
+        );
+        TextSymbols { text: self, range }
+    }
+}
+
+/// Iterator over the symbols of a range of an `IndexableText`.
+pub struct TextSymbols<'a, T: 'a + ?Sized> {
+    text: &'a T,
+    range: Range<usize>,
+}
+
+impl<'a, T: IndexableText + ?Sized> Iterator for TextSymbols<'a, T> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        self.range.next().map(|i| self.text.symbol(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, T: IndexableText + ?Sized> ExactSizeIterator for TextSymbols<'a, T> {}
+
+impl IndexableText for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+
+    fn symbol(&self, i: usize) -> u8 {
+        self[i]
+    }
+}
+
+impl<const N: usize> IndexableText for [u8; N] {
+    fn len(&self) -> usize {
+        N
+    }
+
+    fn symbol(&self, i: usize) -> u8 {
+        self[i]
+    }
+}
+
+impl IndexableText for Vec<u8> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn symbol(&self, i: usize) -> u8 {
+        self[i]
+    }
+}
+
+impl<T: IndexableText + ?Sized> IndexableText for &T {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn symbol(&self, i: usize) -> u8 {
+        (**self).symbol(i)
+    }
+}
+
 /// Remove a trailing newline from the given string in place.
 pub fn trim_newline(s: &mut String) {
     if s.ends_with('\n') {
@@ -0,0 +1,212 @@
+//! A small API for embedding the crate's matchers and aligners into WebAssembly modules, e.g.
+//! browser-based sequence tools. Enabled with the `wasm` feature.
+//!
+//! The types only use 32 bit positions (`u32`), which fit into JavaScript numbers, and owned
+//! data, so that they can be wrapped by `wasm-bindgen` or serialized to JSON. Nothing here reads
+//! files, maps memory or spawns threads. The rest of the crate remains usable from WebAssembly
+//! as long as files are replaced by in-memory readers (all readers accept any `io::Read`).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::wasm::{Matcher, Scores};
+//!
+//! let matcher = Matcher::new(b"ACGTACGTTACG").unwrap();
+//! assert_eq!(matcher.find(b"ACG"), [0, 4, 9]);
+//! assert_eq!(matcher.count(b"GTT"), 1);
+//!
+//! let alignment = Scores::default().global(b"ACGTACGT", b"ACGACGT");
+//! assert_eq!((alignment.score, alignment.xend, alignment.yend), (1, 8, 7));
+//! assert_eq!(alignment.cigar, "3=1I4=");
+//! ```
+
+use std::convert::TryFrom;
+
+use alignment::pairwise::{Aligner, MatchParams};
+use alignment::{Alignment, AlignmentOperation};
+use data_structures::fmindex::{FMIndexable, IndexBuilder, SampledFMIndex};
+use error::{Error, Result};
+
+/// Convert a position into a `u32`.
+fn to_u32(pos: usize) -> u32 {
+    u32::try_from(pos).expect("Expecting positions below 2^32.")
+}
+
+/// Extended CIGAR string of the given operations, ignoring clips.
+fn cigar(operations: &[AlignmentOperation]) -> String {
+    let mut cigar = String::new();
+    let mut run: Option<(char, usize)> = None;
+    for op in operations {
+        let symbol = match *op {
+            AlignmentOperation::Match => '=',
+            AlignmentOperation::Subst => 'X',
+            AlignmentOperation::Ins => 'I',
+            AlignmentOperation::Del => 'D',
+            _ => continue,
+        };
+        run = match run {
+            Some((last, k)) if last == symbol => Some((last, k + 1)),
+            Some((last, k)) => {
+                cigar.push_str(&format!("{}{}", k, last));
+                Some((symbol, 1))
+            }
+            None => Some((symbol, 1)),
+        };
+    }
+    if let Some((last, k)) = run {
+        cigar.push_str(&format!("{}{}", k, last));
+    }
+    cigar
+}
+
+/// Exact matcher of patterns in a text, based on an FM-Index.
+#[derive(Serialize, Deserialize)]
+pub struct Matcher {
+    index: SampledFMIndex<u32>,
+}
+
+impl Matcher {
+    /// Index the given text (without sentinel `$`). Returns an error if the text contains `$`
+    /// or is not shorter than 2^32 symbols.
+    pub fn new(text: &[u8]) -> Result<Self> {
+        if let Some(pos) = text.iter().position(|&c| c == b'$') {
+            return Err(Error::InvalidSymbol { symbol: b'$', pos });
+        }
+        if u32::try_from(text.len()).is_err() {
+            return Err(Error::TooLong {
+                len: text.len(),
+                max: u32::MAX as usize,
+            });
+        }
+        let mut text = text.to_vec();
+        text.push(b'$');
+        let index = IndexBuilder::new().build_sized(&text)?;
+        Ok(Matcher { index })
+    }
+
+    /// Sorted start positions of the occurrences of the given pattern.
+    pub fn find(&self, pattern: &[u8]) -> Vec<u32> {
+        let mut positions: Vec<_> = self.index.find(pattern).into_iter().map(to_u32).collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    /// Number of occurrences of the given pattern.
+    pub fn count(&self, pattern: &[u8]) -> u32 {
+        to_u32(self.index.count(pattern.iter()))
+    }
+}
+
+/// A pairwise alignment with 32 bit positions (0-based, end exclusive) and a CIGAR string.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlignmentResult {
+    pub score: i32,
+    pub xstart: u32,
+    pub xend: u32,
+    pub ystart: u32,
+    pub yend: u32,
+    /// Extended CIGAR (`=`, `X`, `I` and `D`) of the aligned parts, without clipping.
+    pub cigar: String,
+}
+
+impl<'a> From<&'a Alignment> for AlignmentResult {
+    fn from(alignment: &'a Alignment) -> Self {
+        AlignmentResult {
+            score: alignment.score,
+            xstart: to_u32(alignment.xstart),
+            xend: to_u32(alignment.xend),
+            ystart: to_u32(alignment.ystart),
+            yend: to_u32(alignment.yend),
+            cigar: cigar(&alignment.operations),
+        }
+    }
+}
+
+/// Scores for aligning two sequences with affine gap penalties. A gap of length `k` scores
+/// `gap_open + k * gap_extend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scores {
+    pub match_score: i32,
+    pub mismatch_score: i32,
+    pub gap_open: i32,
+    pub gap_extend: i32,
+}
+
+impl Default for Scores {
+    fn default() -> Self {
+        Scores {
+            match_score: 1,
+            mismatch_score: -1,
+            gap_open: -5,
+            gap_extend: -1,
+        }
+    }
+}
+
+impl Scores {
+    /// Align the whole of `x` to the whole of `y`.
+    pub fn global(&self, x: &[u8], y: &[u8]) -> AlignmentResult {
+        AlignmentResult::from(&self.aligner(x, y).global(x, y))
+    }
+
+    /// Align the whole of `x` to a part of `y`.
+    pub fn semiglobal(&self, x: &[u8], y: &[u8]) -> AlignmentResult {
+        AlignmentResult::from(&self.aligner(x, y).semiglobal(x, y))
+    }
+
+    /// Align the best matching parts of `x` and `y`.
+    pub fn local(&self, x: &[u8], y: &[u8]) -> AlignmentResult {
+        AlignmentResult::from(&self.aligner(x, y).local(x, y))
+    }
+
+    fn aligner(&self, x: &[u8], y: &[u8]) -> Aligner<MatchParams> {
+        Aligner::with_capacity(
+            x.len(),
+            y.len(),
+            self.gap_open,
+            self.gap_extend,
+            MatchParams::new(self.match_score, self.mismatch_score),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matcher() {
+        let matcher = Matcher::new(b"GATTACAGATTACA").unwrap();
+        assert_eq!(matcher.find(b"ATTA"), [1, 8]);
+        assert_eq!(matcher.find(b"A"), [1, 4, 6, 8, 11, 13]);
+        assert!(matcher.find(b"CC").is_empty());
+        assert_eq!(matcher.count(b"TTAC"), 2);
+    }
+
+    #[test]
+    fn test_matcher_sentinel() {
+        match Matcher::new(b"GATTA$CA") {
+            Err(Error::InvalidSymbol {
+                symbol: b'$',
+                pos: 5,
+            }) => (),
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(_) => panic!("Expecting sentinel in text to be rejected."),
+        }
+    }
+
+    #[test]
+    fn test_alignment() {
+        let scores = Scores::default();
+        let alignment = scores.semiglobal(b"TTAC", b"GGGTTACGGG");
+        assert_eq!((alignment.ystart, alignment.yend), (3, 7));
+        assert_eq!(alignment.cigar, "4=");
+        let alignment = scores.local(b"CCCCGATTACACCCC", b"GATTACA");
+        assert_eq!(
+            (alignment.xstart, alignment.xend, alignment.score),
+            (4, 11, 7)
+        );
+        assert_eq!(alignment.cigar, "7=");
+        assert_eq!(scores.global(b"ACGTA", b"AGGTCA").cigar, "1=1X2=1D1=");
+    }
+}